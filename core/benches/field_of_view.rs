@@ -0,0 +1,99 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rhombus_core::hex::{
+    coordinates::{axial::AxialVector, ring::polar_index_to_vector},
+    field_of_view::{visible_positions, FieldOfView},
+    storage::hash::RectHashStorage,
+};
+use std::collections::HashSet;
+
+// About 100k hexes.
+const MAP_RADIUS: usize = 182;
+const FOV_RADIUS: usize = 60;
+
+fn map() -> RectHashStorage<bool> {
+    let mut map = RectHashStorage::new();
+    for position in AxialVector::default().spiral_iter(MAP_RADIUS) {
+        // Sparse pillars, most of the field of view stays open.
+        let wall = (position.q() * 7 + position.r() * 13).rem_euclid(29) == 0;
+        map.insert(position, wall);
+    }
+    map
+}
+
+// What the viewer did before HexSet.
+fn visible_positions_hash_set(
+    map: &RectHashStorage<bool>,
+    center: AxialVector,
+) -> HashSet<AxialVector> {
+    let mut visible_positions = HashSet::new();
+    visible_positions.insert(center);
+    let mut fov = FieldOfView::default();
+    fov.start(center);
+    let is_obstacle = |pos| map.get(pos).copied().unwrap_or(false);
+    loop {
+        let prev_len = visible_positions.len();
+        for pos in fov.iter() {
+            let key = center + pos;
+            if map.contains_position(key) {
+                visible_positions.insert(key);
+            }
+        }
+        if visible_positions.len() == prev_len || fov.radius() >= FOV_RADIUS {
+            break;
+        }
+        fov.next_radius(&is_obstacle);
+    }
+    visible_positions
+}
+
+fn field_of_view_benchmark(c: &mut Criterion) {
+    let map = map();
+    let center = AxialVector::new(5, -3);
+    let is_obstacle = |pos| map.get(pos).copied().unwrap_or(false);
+    assert_eq!(
+        visible_positions(center, Some(FOV_RADIUS), &is_obstacle, |pos| map
+            .contains_position(pos))
+        .len(),
+        visible_positions_hash_set(&map, center).len()
+    );
+    c.bench_function("visible_positions_hash_set", |b| {
+        b.iter(|| visible_positions_hash_set(&map, black_box(center)).len())
+    });
+    c.bench_function("visible_positions_hex_set", |b| {
+        b.iter(|| {
+            visible_positions(black_box(center), Some(FOV_RADIUS), &is_obstacle, |pos| {
+                map.contains_position(pos)
+            })
+            .len()
+        })
+    });
+}
+
+// The rings swept by an open field of view: computing each hex of a ring from its polar index
+// against expanding the field of view, which looks them up in its ring cache.
+fn rings_benchmark(c: &mut Criterion) {
+    c.bench_function("polar_index_to_vector_rings", |b| {
+        b.iter(|| {
+            (1..=FOV_RADIUS)
+                .flat_map(|radius| {
+                    (0..6 * radius).map(move |polar_index| {
+                        polar_index_to_vector::<AxialVector>(black_box(polar_index), radius)
+                    })
+                })
+                .fold(AxialVector::default(), |sum, vector| sum + vector)
+        })
+    });
+    c.bench_function("field_of_view_open_rings", |b| {
+        b.iter(|| {
+            let mut fov = FieldOfView::default();
+            fov.start(black_box(AxialVector::default()));
+            while fov.radius() < FOV_RADIUS {
+                fov.next_radius(&|_: AxialVector| false);
+            }
+            fov.iter().count()
+        })
+    });
+}
+
+criterion_group!(benches, field_of_view_benchmark, rings_benchmark);
+criterion_main!(benches);
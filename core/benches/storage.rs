@@ -0,0 +1,131 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector,
+    storage::hash::{HexBuildHasher, RectHashStorage},
+};
+use std::{collections::hash_map::RandomState, hash::BuildHasher};
+
+const RADIUS: isize = 64;
+
+fn positions() -> Vec<AxialVector> {
+    let mut positions = Vec::new();
+    for q in -RADIUS..=RADIUS {
+        for r in -RADIUS..=RADIUS {
+            positions.push(AxialVector::new(q, r));
+        }
+    }
+    positions
+}
+
+fn filled_storage<S: BuildHasher>(
+    hasher: S,
+    positions: &[AxialVector],
+) -> RectHashStorage<usize, S> {
+    let mut storage = RectHashStorage::with_hasher(hasher);
+    for (i, position) in positions.iter().enumerate() {
+        storage.insert(*position, i);
+    }
+    storage
+}
+
+fn bench_get<S: BuildHasher>(c: &mut Criterion, name: &str, hasher: S) {
+    let positions = positions();
+    let storage = filled_storage(hasher, &positions);
+    c.bench_with_input(
+        BenchmarkId::new("rect_hash_storage_get", name),
+        &positions,
+        |b, positions| {
+            b.iter(|| {
+                let mut sum = 0;
+                for position in positions {
+                    sum += storage.get(black_box(*position)).copied().unwrap_or(0);
+                }
+                sum
+            })
+        },
+    );
+}
+
+fn bench_insert<S: BuildHasher + Clone>(c: &mut Criterion, name: &str, hasher: S) {
+    let positions = positions();
+    c.bench_with_input(
+        BenchmarkId::new("rect_hash_storage_insert", name),
+        &positions,
+        |b, positions| b.iter(|| filled_storage(hasher.clone(), black_box(positions)).len()),
+    );
+}
+
+// Half of the hexes, so that the adjacents are not always there.
+fn bench_hex_with_adjacents(c: &mut Criterion) {
+    let positions = positions();
+    let mut storage = filled_storage(
+        HexBuildHasher::default(),
+        &positions.iter().copied().step_by(2).collect::<Vec<_>>(),
+    );
+    c.bench_with_input(
+        BenchmarkId::new("rect_hash_storage_hex_with_adjacents", "default"),
+        &positions,
+        |b, positions| {
+            b.iter(|| {
+                let mut sum = 0;
+                for position in positions {
+                    let hex_with_adjacents = storage.hex_with_adjacents(black_box(*position));
+                    sum += hex_with_adjacents.hex().copied().unwrap_or(0);
+                    for direction in 0..6 {
+                        sum += hex_with_adjacents.adjacent(direction).copied().unwrap_or(0);
+                    }
+                }
+                sum
+            })
+        },
+    );
+    c.bench_with_input(
+        BenchmarkId::new("rect_hash_storage_hex_with_adjacents_mut", "default"),
+        &positions,
+        |b, positions| {
+            b.iter(|| {
+                for position in positions {
+                    let mut hex_with_adjacents =
+                        storage.hex_with_adjacents_mut(black_box(*position));
+                    for direction in 0..6 {
+                        if let Some(adjacent) = hex_with_adjacents.adjacent(direction) {
+                            *adjacent = adjacent.wrapping_add(direction);
+                        }
+                    }
+                }
+            })
+        },
+    );
+}
+
+// The rows walked from left to right, as get does in bench_get.
+fn bench_cursor(c: &mut Criterion) {
+    let positions = positions();
+    let storage = filled_storage(HexBuildHasher::default(), &positions);
+    c.bench_function("rect_hash_storage_cursor", |b| {
+        b.iter(|| {
+            let mut sum = 0;
+            let mut cursor = storage.cursor(AxialVector::new(-RADIUS, -RADIUS));
+            for r in -RADIUS..=RADIUS {
+                cursor.jump(black_box(AxialVector::new(-RADIUS, r)));
+                for _ in -RADIUS..=RADIUS {
+                    sum += cursor.get().copied().unwrap_or(0);
+                    cursor.move_to(0);
+                }
+            }
+            sum
+        })
+    });
+}
+
+fn storage_benchmark(c: &mut Criterion) {
+    bench_get(c, "default", HexBuildHasher::default());
+    bench_get(c, "sip", RandomState::new());
+    bench_insert(c, "default", HexBuildHasher::default());
+    bench_insert(c, "sip", RandomState::new());
+    bench_hex_with_adjacents(c);
+    bench_cursor(c);
+}
+
+criterion_group!(benches, storage_benchmark);
+criterion_main!(benches);
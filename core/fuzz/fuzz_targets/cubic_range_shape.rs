@@ -0,0 +1,78 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use rhombus_core::hex::shape::cubic_range::{CubicRangeShape, Range};
+
+#[derive(Arbitrary, Debug)]
+enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    StretchStart(Axis, u8),
+    StretchEnd(Axis, u8),
+    ShrinkStart(Axis, u8),
+    ShrinkEnd(Axis, u8),
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    ranges: [(i8, i8); 3],
+    ops: Vec<Op>,
+}
+
+fn range((start, end): (i8, i8)) -> Range {
+    Range::from((start as isize, end as isize))
+}
+
+fn check_shape(shape: &CubicRangeShape) {
+    for range in &[shape.range_x(), shape.range_y(), shape.range_z()] {
+        assert!(range.start() <= range.end(), "{:?}", shape);
+    }
+    assert!(shape.is_valid(), "{:?}", shape);
+    for vertex in &shape.vertices() {
+        assert!(shape.contains_position(*vertex), "{:?}", shape);
+    }
+    let perimeter = shape.perimeter();
+    let (length, _) = perimeter.size_hint();
+    let mut count = 0;
+    for position in perimeter {
+        assert!(shape.contains_position(position), "{:?}", shape);
+        count += 1;
+    }
+    assert_eq!(count, length.max(1), "{:?}", shape);
+}
+
+fuzz_target!(|input: Input| {
+    let [x, y, z] = input.ranges;
+    let (range_x, range_y, range_z) = (range(x), range(y), range(z));
+    if !CubicRangeShape::are_ranges_valid(&range_x, &range_y, &range_z) {
+        return;
+    }
+    let mut shape = CubicRangeShape::new(range_x, range_y, range_z);
+    check_shape(&shape);
+    for op in input.ops {
+        let before = shape.clone();
+        let changed = match op {
+            Op::StretchStart(Axis::X, amount) => shape.stretch_x_start(amount as usize),
+            Op::StretchStart(Axis::Y, amount) => shape.stretch_y_start(amount as usize),
+            Op::StretchStart(Axis::Z, amount) => shape.stretch_z_start(amount as usize),
+            Op::StretchEnd(Axis::X, amount) => shape.stretch_x_end(amount as usize),
+            Op::StretchEnd(Axis::Y, amount) => shape.stretch_y_end(amount as usize),
+            Op::StretchEnd(Axis::Z, amount) => shape.stretch_z_end(amount as usize),
+            Op::ShrinkStart(Axis::X, amount) => shape.shrink_x_start(amount as usize),
+            Op::ShrinkStart(Axis::Y, amount) => shape.shrink_y_start(amount as usize),
+            Op::ShrinkStart(Axis::Z, amount) => shape.shrink_z_start(amount as usize),
+            Op::ShrinkEnd(Axis::X, amount) => shape.shrink_x_end(amount as usize),
+            Op::ShrinkEnd(Axis::Y, amount) => shape.shrink_y_end(amount as usize),
+            Op::ShrinkEnd(Axis::Z, amount) => shape.shrink_z_end(amount as usize),
+        };
+        if !changed {
+            assert_eq!(shape, before);
+        }
+        check_shape(&shape);
+    }
+});
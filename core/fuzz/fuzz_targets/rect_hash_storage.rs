@@ -0,0 +1,98 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use rhombus_core::hex::{
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    storage::hash::RectHashStorage,
+};
+use std::collections::BTreeMap;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(i8, i8, u16),
+    Remove(i8, i8),
+    GetMut(i8, i8, u16),
+    Entry(i8, i8, u16),
+    Adjacents(i8, i8),
+    Clear,
+}
+
+fn position(q: i8, r: i8) -> AxialVector {
+    AxialVector::new(q as isize, r as isize)
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut storage = RectHashStorage::<Box<u16>>::new();
+    let mut model = BTreeMap::<AxialVector, u16>::new();
+    for op in ops {
+        match op {
+            Op::Insert(q, r, value) => {
+                let position = position(q, r);
+                assert_eq!(
+                    storage.insert(position, Box::new(value)).map(|hex| *hex),
+                    model.insert(position, value)
+                );
+            }
+            Op::Remove(q, r) => {
+                let position = position(q, r);
+                assert_eq!(
+                    storage.remove(position).map(|hex| *hex),
+                    model.remove(&position)
+                );
+            }
+            Op::GetMut(q, r, value) => {
+                let position = position(q, r);
+                match (storage.get_mut(position), model.get_mut(&position)) {
+                    (Some(hex), Some(expected)) => {
+                        assert_eq!(**hex, *expected);
+                        **hex = value;
+                        *expected = value;
+                    }
+                    (None, None) => {}
+                    (hex, expected) => panic!("{:?} != {:?}", hex, expected),
+                }
+            }
+            Op::Entry(q, r, value) => {
+                let position = position(q, r);
+                let hex = storage
+                    .entry(position)
+                    .and_modify(|hex| **hex = hex.wrapping_add(value))
+                    .or_insert_with(|| Box::new(value));
+                let expected = model
+                    .entry(position)
+                    .and_modify(|expected| *expected = expected.wrapping_add(value))
+                    .or_insert(value);
+                assert_eq!(**hex, *expected);
+            }
+            Op::Adjacents(q, r) => {
+                let position = position(q, r);
+                let hex_with_adjacents = storage.hex_with_adjacents(position);
+                assert_eq!(
+                    (*hex_with_adjacents.hex()).map(|hex| **hex),
+                    model.get(&position).copied()
+                );
+                for direction in 0..6 {
+                    assert_eq!(
+                        hex_with_adjacents.adjacent(direction).map(|hex| **hex),
+                        model.get(&position.neighbor(direction)).copied()
+                    );
+                }
+            }
+            Op::Clear => {
+                storage.clear();
+                model.clear();
+            }
+        }
+        assert_eq!(storage.len(), model.len());
+        assert_eq!(storage.is_empty(), model.is_empty());
+        let mut hexes = storage
+            .iter()
+            .map(|(position, hex)| (position, **hex))
+            .collect::<Vec<_>>();
+        hexes.sort();
+        assert_eq!(
+            hexes,
+            model.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>()
+        );
+    }
+});
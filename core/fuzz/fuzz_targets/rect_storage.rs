@@ -0,0 +1,82 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use rhombus_core::hex::storage::rect::{RectStorage, RECT_X_LEN, RECT_Y_LEN};
+use std::collections::BTreeMap;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(u8, u8, u16),
+    Remove(u8, u8),
+    GetMut(u8, u8, u16),
+    Entry(u8, u8, u16),
+    Clear,
+}
+
+fn position(x: u8, y: u8) -> (usize, usize) {
+    (x as usize % RECT_X_LEN, y as usize % RECT_Y_LEN)
+}
+
+// Hexes are boxed so that double drops and leaks in the MaybeUninit handling are caught by
+// sanitizers and Miri.
+fuzz_target!(|ops: Vec<Op>| {
+    let mut storage = RectStorage::<Box<u16>>::new();
+    let mut model = BTreeMap::<(usize, usize), u16>::new();
+    for op in ops {
+        match op {
+            Op::Insert(x, y, value) => {
+                let (x, y) = position(x, y);
+                assert_eq!(
+                    storage.insert(x, y, Box::new(value)).map(|hex| *hex),
+                    model.insert((x, y), value)
+                );
+            }
+            Op::Remove(x, y) => {
+                let (x, y) = position(x, y);
+                assert_eq!(storage.remove(x, y).map(|hex| *hex), model.remove(&(x, y)));
+            }
+            Op::GetMut(x, y, value) => {
+                let (x, y) = position(x, y);
+                match (storage.get_mut(x, y), model.get_mut(&(x, y))) {
+                    (Some(hex), Some(expected)) => {
+                        assert_eq!(**hex, *expected);
+                        **hex = value;
+                        *expected = value;
+                    }
+                    (None, None) => {}
+                    (hex, expected) => panic!("{:?} != {:?}", hex, expected),
+                }
+            }
+            Op::Entry(x, y, value) => {
+                let (x, y) = position(x, y);
+                let hex = storage
+                    .entry(x, y)
+                    .and_modify(|hex| **hex = hex.wrapping_add(value))
+                    .or_insert_with(|| Box::new(value));
+                let expected = model
+                    .entry((x, y))
+                    .and_modify(|expected| *expected = expected.wrapping_add(value))
+                    .or_insert(value);
+                assert_eq!(**hex, *expected);
+            }
+            Op::Clear => {
+                storage.clear();
+                model.clear();
+            }
+        }
+        assert_eq!(storage.len(), model.len());
+        let mut hexes = storage
+            .iter()
+            .map(|(x, y, hex)| ((x, y), **hex))
+            .collect::<Vec<_>>();
+        hexes.sort();
+        assert_eq!(
+            hexes,
+            model.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>()
+        );
+        for (&(x, y), value) in &model {
+            assert!(storage.contains_position(x, y));
+            assert_eq!(storage.get(x, y).map(|hex| **hex), Some(*value));
+        }
+    }
+});
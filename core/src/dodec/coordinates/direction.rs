@@ -0,0 +1,108 @@
+pub const NUM_DIRECTIONS: usize = 12;
+
+// Directions d and d + 6 are opposite, together they form an axis.
+pub const NUM_AXES: usize = NUM_DIRECTIONS / 2;
+
+// The hex planes through a position, plane i being made of the directions for which the coordinate
+// i (x, y, z then t) is zero. Plane 3 is the hex layer.
+pub const NUM_PLANES: usize = 4;
+
+pub const LAYER_PLANE: usize = 3;
+
+// Each plane in rotation order, one step being a turn of 60 degrees.
+const PLANE_DIRECTIONS: [[usize; 6]; NUM_PLANES] = [
+    [2, 4, 5, 8, 10, 11],
+    [1, 3, 5, 7, 9, 11],
+    [0, 3, 4, 6, 9, 10],
+    [0, 1, 2, 6, 7, 8],
+];
+
+pub fn opposite(direction: usize) -> usize {
+    (direction + NUM_AXES) % NUM_DIRECTIONS
+}
+
+pub fn axis(direction: usize) -> usize {
+    direction % NUM_AXES
+}
+
+// The positive direction first.
+pub fn axis_directions(axis: usize) -> [usize; 2] {
+    [axis, axis + NUM_AXES]
+}
+
+pub fn plane_directions(plane: usize) -> [usize; 6] {
+    PLANE_DIRECTIONS[plane]
+}
+
+// Every direction belongs to two planes, the ones of its two zero coordinates.
+pub fn direction_planes(direction: usize) -> [usize; 2] {
+    let mut planes = (0..NUM_PLANES).filter(|plane| PLANE_DIRECTIONS[*plane].contains(&direction));
+    let first = planes.next().expect("first plane");
+    let second = planes.next().expect("second plane");
+    [first, second]
+}
+
+// None if the direction is not in the plane.
+pub fn rotate(direction: usize, plane: usize, steps: isize) -> Option<usize> {
+    let directions = &PLANE_DIRECTIONS[plane];
+    directions
+        .iter()
+        .position(|d| *d == direction)
+        .map(|index| directions[(index as isize + steps).rem_euclid(6) as usize])
+}
+
+#[cfg(test)]
+use crate::dodec::coordinates::quadric::QuadricVector;
+
+#[test]
+fn test_opposite() {
+    for direction in 0..NUM_DIRECTIONS {
+        assert_eq!(
+            QuadricVector::direction(direction) + QuadricVector::direction(opposite(direction)),
+            QuadricVector::default()
+        );
+        assert_eq!(opposite(opposite(direction)), direction);
+    }
+}
+
+#[test]
+fn test_axis_directions() {
+    for axis in 0..NUM_AXES {
+        let [forward, backward] = axis_directions(axis);
+        assert_eq!(opposite(forward), backward);
+        assert_eq!(self::axis(forward), axis);
+        assert_eq!(self::axis(backward), axis);
+    }
+}
+
+#[test]
+fn test_plane_directions() {
+    let coordinate = |v: QuadricVector, i| [v.x(), v.y(), v.z(), v.t()][i];
+    for plane in 0..NUM_PLANES {
+        let directions = plane_directions(plane);
+        for (i, direction) in directions.iter().enumerate() {
+            let vector = QuadricVector::direction(*direction);
+            assert_eq!(coordinate(vector, plane), 0);
+            assert!(direction_planes(*direction).contains(&plane));
+            // Neighbors in the rotation order are 60 degrees apart, their difference is a direction
+            let next = QuadricVector::direction(directions[(i + 1) % 6]);
+            assert_eq!(next.distance(vector), 1);
+            assert_eq!(directions[(i + 3) % 6], opposite(*direction));
+        }
+    }
+}
+
+#[test]
+fn test_rotate() {
+    assert_eq!(rotate(0, LAYER_PLANE, 1), Some(1));
+    assert_eq!(rotate(0, LAYER_PLANE, -1), Some(8));
+    assert_eq!(rotate(0, LAYER_PLANE, 3), Some(6));
+    assert_eq!(rotate(3, 1, 2), Some(7));
+    assert_eq!(rotate(3, LAYER_PLANE, 1), None);
+    for direction in 0..NUM_DIRECTIONS {
+        for plane in direction_planes(direction).iter() {
+            assert_eq!(rotate(direction, *plane, 6), Some(direction));
+            assert_eq!(rotate(direction, *plane, 3), Some(opposite(direction)));
+        }
+    }
+}
@@ -1 +1,2 @@
+pub mod direction;
 pub mod quadric;
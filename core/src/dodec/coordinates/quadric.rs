@@ -1,7 +1,13 @@
-use crate::vector::Vector4ISize;
+use crate::{
+    dodec::coordinates::direction::NUM_DIRECTIONS,
+    hex::coordinates::{axial::AxialVector, cubic::CubicVector},
+    vector::Vector4ISize,
+};
 use derive_more::Add;
 use std::ops::Mul;
 
+// Ordered by x, then y, then z (t follows from them). The layer is t, so sorted positions are not
+// grouped by layer.
 #[derive(
     Default,
     Clone,
@@ -63,9 +69,28 @@ impl QuadricVector {
         *self + Self::direction(direction)
     }
 
+    // The direction to follow from self to reach the other position, if they are on the same axis.
+    pub fn direction_between(self, other: Self) -> Option<usize> {
+        let distance = self.distance(other);
+        if distance == 0 {
+            return None;
+        }
+        let vector = other - self;
+        (0..NUM_DIRECTIONS).find(|direction| Self::direction(*direction) * distance == vector)
+    }
+
     pub fn sphere_iter(&self, radius: usize) -> SphereIter {
         SphereIter::new(radius, *self)
     }
+
+    pub fn ball_iter(&self, radius: usize) -> BallIter {
+        BallIter::new(0, radius, *self)
+    }
+
+    // Empty if the min radius is greater than the max radius.
+    pub fn sphere_between(&self, min_radius: usize, max_radius: usize) -> BallIter {
+        BallIter::new(min_radius, max_radius, *self)
+    }
 }
 
 impl Mul<isize> for QuadricVector {
@@ -84,7 +109,59 @@ impl Mul<QuadricVector> for isize {
     }
 }
 
-const NUM_DIRECTIONS: usize = 12;
+// The hexes of a layer are the positions with the same t, they form a hex plane. Layer t is
+// stacked on the hex plane by moving t times in direction 9 (x - 1, t + 1), so that every position
+// is exactly one pair of a hex and a layer.
+impl QuadricVector {
+    pub fn from_hex_and_layer(hex: CubicVector, layer: isize) -> Self {
+        Self::new(hex.x() - layer, hex.y(), hex.z(), layer)
+    }
+
+    pub fn layer(&self) -> isize {
+        self.t()
+    }
+
+    // Projection onto the hex plane of layer 0 along the stacking direction.
+    pub fn hex(&self) -> CubicVector {
+        CubicVector::new(self.x() + self.t(), self.y(), self.z())
+    }
+
+    // The rings of the hex plane of the layer of self, in the order of the hex rings.
+    pub fn ring_iter(&self, radius: usize) -> impl Iterator<Item = Self> {
+        let layer = self.layer();
+        self.hex()
+            .ring_iter(radius)
+            .map(move |hex| Self::from_hex_and_layer(hex, layer))
+    }
+
+    pub fn spiral_iter(&self, radius: usize) -> impl Iterator<Item = Self> {
+        let layer = self.layer();
+        self.hex()
+            .spiral_iter(radius)
+            .map(move |hex| Self::from_hex_and_layer(hex, layer))
+    }
+
+    pub fn big_ring_iter(&self, cell_radius: usize, radius: usize) -> impl Iterator<Item = Self> {
+        let layer = self.layer();
+        self.hex()
+            .big_ring_iter(cell_radius, radius)
+            .map(move |hex| Self::from_hex_and_layer(hex, layer))
+    }
+}
+
+// Embedding in layer 0, where the hex directions 0 to 5 are the directions 0, 1, 2, 6, 7 and 8 and
+// the distances are kept.
+impl From<CubicVector> for QuadricVector {
+    fn from(cubic: CubicVector) -> Self {
+        Self::from_hex_and_layer(cubic, 0)
+    }
+}
+
+impl From<AxialVector> for QuadricVector {
+    fn from(axial: AxialVector) -> Self {
+        CubicVector::from(axial).into()
+    }
+}
 
 // Don't use constructor and lazy_static so that the compiler can actually optimize the use
 // of directions.
@@ -207,7 +284,10 @@ impl Iterator for SphereRingIter {
         let direction = self.direction;
         if direction < 6 {
             let next = self.next;
-            self.next = next.neighbor(SPHERE_RING_ITER_DIRECTIONS[direction]);
+            // The ring of size 0 stays in place so that the next ring starts from its position
+            if edge_lengths[direction & 1] > 0 {
+                self.next = next.neighbor(SPHERE_RING_ITER_DIRECTIONS[direction]);
+            }
             let ei = self.edge_index;
             if ei < edge_lengths[direction & 1] {
                 self.edge_index = ei + 1;
@@ -297,21 +377,95 @@ impl Iterator for SphereIter {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let radius = self.radius;
-        if radius > 0 {
-            // Triangles minus shared vertices
-            let mut exact = 4 * (1 + radius) * (2 + radius) - 12;
-            if radius > 1 {
-                // Squares interior
-                exact += 6 * (radius - 1) * (radius - 1);
-            }
-            (exact, Some(exact))
+        let exact = sphere_len(self.radius);
+        (exact, Some(exact))
+    }
+}
+
+fn sphere_len(radius: usize) -> usize {
+    if radius > 0 {
+        // Triangles minus shared vertices
+        let mut exact = 4 * (1 + radius) * (2 + radius) - 12;
+        if radius > 1 {
+            // Squares interior
+            exact += 6 * (radius - 1) * (radius - 1);
+        }
+        exact
+    } else {
+        1
+    }
+}
+
+// Sum of the spheres up to the radius, a centered cuboctahedral number.
+fn ball_len(radius: usize) -> usize {
+    (2 * radius + 1) * (5 * radius * radius + 5 * radius + 3) / 3
+}
+
+// The spheres from the min radius to the max radius, a ball when the min radius is 0.
+pub struct BallIter {
+    center: QuadricVector,
+    radius: usize,
+    max_radius: usize,
+    sphere: SphereIter,
+    remaining: usize,
+}
+
+impl BallIter {
+    fn new(min_radius: usize, max_radius: usize, center: QuadricVector) -> Self {
+        let remaining = if min_radius > max_radius {
+            0
+        } else if min_radius > 0 {
+            ball_len(max_radius) - ball_len(min_radius - 1)
         } else {
-            (1, Some(1))
+            ball_len(max_radius)
+        };
+        Self {
+            center,
+            radius: min_radius,
+            max_radius,
+            sphere: SphereIter::new(min_radius, center),
+            remaining,
+        }
+    }
+
+    // The radius of the sphere the next position belongs to.
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    pub fn peek(&mut self) -> Option<&QuadricVector> {
+        if self.remaining > 0 {
+            self.sphere.peek()
+        } else {
+            None
         }
     }
 }
 
+impl Iterator for BallIter {
+    type Item = QuadricVector;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let res = self.sphere.next();
+        self.remaining -= 1;
+        // Start the next sphere right away so that peek always sees the next position.
+        if self.sphere.peek().is_none() && self.radius < self.max_radius {
+            self.radius += 1;
+            self.sphere = SphereIter::new(self.radius, self.center);
+        }
+        res
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for BallIter {}
+
 #[test]
 fn test_new_quadric_vector() {
     assert_eq!(
@@ -685,3 +839,170 @@ fn test_sphere_iter4() {
         ],
     );
 }
+
+#[test]
+fn test_quadric_vector_hex_and_layer() {
+    let position = QuadricVector::new(1, 2, -7, 4);
+    assert_eq!(position.layer(), 4);
+    assert_eq!(position.hex(), CubicVector::new(5, 2, -7));
+    assert_eq!(
+        QuadricVector::from_hex_and_layer(position.hex(), position.layer()),
+        position
+    );
+    // Going up a layer in the stacking direction stays above the same hex.
+    let above = position.neighbor(9);
+    assert_eq!(above.layer(), 5);
+    assert_eq!(above.hex(), position.hex());
+}
+
+#[test]
+fn test_quadric_vector_from_cubic_vector() {
+    use crate::hex::coordinates::direction::HexagonalDirection;
+
+    for (hex_direction, direction) in [0, 1, 2, 6, 7, 8].iter().enumerate() {
+        assert_eq!(
+            QuadricVector::from(CubicVector::direction(hex_direction)),
+            QuadricVector::direction(*direction)
+        );
+    }
+    let (a, b) = (CubicVector::new(3, -5, 2), CubicVector::new(-1, 4, -3));
+    assert_eq!(
+        QuadricVector::from(a).distance(QuadricVector::from(b)),
+        a.distance(b)
+    );
+    assert_eq!(QuadricVector::from(a).hex(), a);
+    assert_eq!(
+        QuadricVector::from(AxialVector::from(a)),
+        QuadricVector::from(a)
+    );
+}
+
+#[test]
+fn test_ball_len() {
+    for radius in 0..10 {
+        assert_eq!(
+            ball_len(radius),
+            (0..=radius).map(sphere_len).sum::<usize>()
+        );
+    }
+}
+
+#[cfg(test)]
+fn do_test_sphere_between(min_radius: usize, max_radius: usize) {
+    let center = QuadricVector::new(1, 2, -7, 4);
+    let mut iter = center.sphere_between(min_radius, max_radius);
+    let expected = (min_radius..=max_radius)
+        .flat_map(|radius| center.sphere_iter(radius))
+        .collect::<Vec<_>>();
+    for (i, position) in expected.iter().enumerate() {
+        assert_eq!(iter.len(), expected.len() - i);
+        assert_eq!(iter.radius() as isize, position.distance(center));
+        assert_eq!(iter.peek(), Some(position));
+        assert_eq!(iter.next(), Some(*position));
+    }
+    assert_eq!(iter.len(), 0);
+    assert_eq!(iter.peek(), None);
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn test_sphere_between() {
+    do_test_sphere_between(0, 0);
+    do_test_sphere_between(0, 3);
+    do_test_sphere_between(2, 2);
+    do_test_sphere_between(1, 4);
+    do_test_sphere_between(3, 2);
+}
+
+#[test]
+fn test_ball_iter() {
+    let center = QuadricVector::default();
+    let ball = center.ball_iter(7).collect::<Vec<_>>();
+    assert_eq!(ball.len(), ball_len(7));
+    assert!(ball.iter().all(|position| position.distance(center) <= 7));
+    let distinct = ball.iter().collect::<std::collections::HashSet<_>>();
+    assert_eq!(distinct.len(), ball.len());
+}
+
+#[test]
+fn test_direction_between() {
+    let center = QuadricVector::new(1, 2, -7, 4);
+    for direction in 0..NUM_DIRECTIONS {
+        for distance in 1..4 {
+            let other = center + QuadricVector::direction(direction) * distance;
+            assert_eq!(center.direction_between(other), Some(direction));
+        }
+    }
+    assert_eq!(center.direction_between(center), None);
+    let off_axis = center + QuadricVector::direction(0) + QuadricVector::direction(3);
+    assert_eq!(center.direction_between(off_axis), None);
+}
+
+#[test]
+fn test_quadric_layer_rings() {
+    let center = QuadricVector::from_hex_and_layer(CubicVector::new(2, -3, 1), -2);
+    for radius in 0..4 {
+        let ring = center.ring_iter(radius).collect::<Vec<_>>();
+        let hexes = center.hex().ring_iter(radius).collect::<Vec<_>>();
+        assert_eq!(
+            ring.iter().map(QuadricVector::hex).collect::<Vec<_>>(),
+            hexes
+        );
+        for position in &ring {
+            assert_eq!(position.layer(), center.layer());
+            assert_eq!(position.distance(center), radius as isize);
+        }
+        assert_eq!(
+            center.spiral_iter(radius).collect::<Vec<_>>(),
+            (0..=radius)
+                .flat_map(|r| center.ring_iter(r))
+                .collect::<Vec<_>>()
+        );
+        let big_ring = center.big_ring_iter(2, radius).collect::<Vec<_>>();
+        let hexes = center.hex().big_ring_iter(2, radius).collect::<Vec<_>>();
+        assert_eq!(
+            big_ring.iter().map(QuadricVector::hex).collect::<Vec<_>>(),
+            hexes
+        );
+        assert!(big_ring
+            .iter()
+            .all(|position| position.layer() == center.layer()));
+    }
+}
+
+#[test]
+fn test_quadric_vector_order() {
+    let mut positions = vec![
+        QuadricVector::new(1, 0, -1, 0),
+        QuadricVector::new(0, 1, 0, -1),
+        QuadricVector::new(0, 0, 1, -1),
+        QuadricVector::new(0, 0, 0, 0),
+        QuadricVector::new(-1, 0, 0, 1),
+        QuadricVector::new(0, 0, -1, 1),
+    ];
+    let expected = vec![
+        QuadricVector::new(-1, 0, 0, 1),
+        QuadricVector::new(0, 0, -1, 1),
+        QuadricVector::new(0, 0, 0, 0),
+        QuadricVector::new(0, 0, 1, -1),
+        QuadricVector::new(0, 1, 0, -1),
+        QuadricVector::new(1, 0, -1, 0),
+    ];
+    positions.sort();
+    assert_eq!(positions, expected);
+    // Not grouped by layer.
+    assert_eq!(
+        positions
+            .iter()
+            .map(QuadricVector::layer)
+            .collect::<Vec<_>>(),
+        vec![1, 1, 0, -1, -1, 0]
+    );
+
+    let set = expected
+        .iter()
+        .rev()
+        .copied()
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(set.into_iter().collect::<Vec<_>>(), expected);
+}
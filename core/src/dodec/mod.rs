@@ -1 +1,2 @@
 pub mod coordinates;
+pub mod storage;
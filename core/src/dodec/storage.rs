@@ -0,0 +1,107 @@
+use crate::{dodec::coordinates::quadric::QuadricVector, hex::storage::hash::HexBuildHasher};
+use std::{collections::HashMap, hash::BuildHasher};
+
+// Dodecahedral worlds are sparse and have no natural chunking plane, a plain hash map does.
+pub struct DodecHashStorage<D, S = HexBuildHasher> {
+    dodecs: HashMap<QuadricVector, D, S>,
+}
+
+impl<D> DodecHashStorage<D> {
+    pub fn new() -> Self {
+        Self::with_hasher(HexBuildHasher::default())
+    }
+}
+
+impl<D, S: BuildHasher> DodecHashStorage<D, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            dodecs: HashMap::with_hasher(hasher),
+        }
+    }
+
+    pub fn get(&self, position: QuadricVector) -> Option<&D> {
+        self.dodecs.get(&position)
+    }
+
+    pub fn get_mut(&mut self, position: QuadricVector) -> Option<&mut D> {
+        self.dodecs.get_mut(&position)
+    }
+
+    pub fn contains_position(&self, position: QuadricVector) -> bool {
+        self.dodecs.contains_key(&position)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (QuadricVector, &D)> {
+        self.dodecs
+            .iter()
+            .map(|(position, dodec)| (*position, dodec))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (QuadricVector, &mut D)> {
+        self.dodecs
+            .iter_mut()
+            .map(|(position, dodec)| (*position, dodec))
+    }
+
+    pub fn positions<'a>(&'a self) -> impl 'a + Iterator<Item = QuadricVector> {
+        self.dodecs.keys().copied()
+    }
+
+    pub fn dodecs(&self) -> impl Iterator<Item = &D> {
+        self.dodecs.values()
+    }
+
+    pub fn dodecs_mut(&mut self) -> impl Iterator<Item = &mut D> {
+        self.dodecs.values_mut()
+    }
+
+    pub fn insert(&mut self, position: QuadricVector, dodec: D) -> Option<D> {
+        self.dodecs.insert(position, dodec)
+    }
+
+    pub fn remove(&mut self, position: QuadricVector) -> Option<D> {
+        self.dodecs.remove(&position)
+    }
+
+    pub fn clear(&mut self) {
+        self.dodecs.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.dodecs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dodecs.is_empty()
+    }
+}
+
+impl<D, S: BuildHasher + Default> Default for DodecHashStorage<D, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+#[test]
+fn test_dodec_hash_storage() {
+    let mut storage = DodecHashStorage::new();
+    assert!(storage.is_empty());
+    for (index, dodec) in QuadricVector::new(1, -2, 3, -2).sphere_iter(2).enumerate() {
+        assert_eq!(storage.insert(dodec, index), None);
+    }
+    let len = storage.len();
+    assert!(len > 0);
+    assert_eq!(storage.positions().count(), len);
+    let first = QuadricVector::new(1, -2, 3, -2)
+        .sphere_iter(2)
+        .next()
+        .unwrap();
+    assert_eq!(storage.get(first), Some(&0));
+    assert_eq!(storage.insert(first, 42), Some(0));
+    *storage.get_mut(first).unwrap() += 1;
+    assert_eq!(storage.remove(first), Some(43));
+    assert!(!storage.contains_position(first));
+    assert_eq!(storage.len(), len - 1);
+    storage.clear();
+    assert!(storage.is_empty());
+}
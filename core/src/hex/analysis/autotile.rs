@@ -0,0 +1,172 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+
+const ALL_NEIGHBORS: u8 = 0b11_1111;
+
+// How many tiles autotile_index tells apart.
+pub const AUTOTILE_COUNT: usize = 14;
+
+// Bit d is set when the neighbor in the direction d has the property.
+pub fn neighbor_mask<P>(position: AxialVector, mut has_property: P) -> u8
+where
+    P: FnMut(AxialVector) -> bool,
+{
+    (0..NUM_DIRECTIONS)
+        .filter(|direction| has_property(position.neighbor(*direction)))
+        .fold(0, |mask, direction| mask | 1 << direction)
+}
+
+// The mask turned by the given number of directions, bit d going to d + turns.
+pub fn rotate_mask(mask: u8, turns: usize) -> u8 {
+    let turns = turns % NUM_DIRECTIONS;
+    ((mask << turns) | (mask >> ((NUM_DIRECTIONS - turns) % NUM_DIRECTIONS))) & ALL_NEIGHBORS
+}
+
+// The 64 masks are 14 tiles turned by 0 to 5 directions. Returns the index of the tile, ordered by
+// their smallest rotation, and by how many directions it must turn to match the mask. A renderer
+// then only needs 14 meshes for the corners and junctions of the walls.
+pub fn autotile_index(mask: u8) -> (usize, usize) {
+    let (tile, turns) = canonical(mask & ALL_NEIGHBORS);
+    let index = (0..tile).filter(|mask| canonical(*mask).0 == *mask).count();
+    (index, turns)
+}
+
+// The mask of the tile at the index, not turned.
+pub fn autotile_mask(index: usize) -> u8 {
+    (0..=ALL_NEIGHBORS)
+        .filter(|mask| canonical(*mask).0 == *mask)
+        .nth(index)
+        .expect("autotile index")
+}
+
+fn canonical(mask: u8) -> (u8, usize) {
+    (0..NUM_DIRECTIONS)
+        .map(|turns| (rotate_mask(mask, NUM_DIRECTIONS - turns), turns))
+        .min()
+        .expect("rotation")
+}
+
+// The neighbor masks of a set of hexes, kept up to date as hexes come and go: each change only
+// updates the hex and its neighbors.
+#[derive(Default)]
+pub struct Autotiles {
+    masks: RectHashStorage<u8>,
+}
+
+impl Autotiles {
+    pub fn new(set: &HexSet) -> Self {
+        let mut autotiles = Self::default();
+        for position in set.iter() {
+            autotiles.masks.insert(
+                position,
+                neighbor_mask(position, |neighbor| set.contains(neighbor)),
+            );
+        }
+        autotiles
+    }
+
+    pub fn mask(&self, position: AxialVector) -> Option<u8> {
+        self.masks.get(position).copied()
+    }
+
+    pub fn masks(&self) -> &RectHashStorage<u8> {
+        &self.masks
+    }
+
+    // Whether the hex was not in the set yet.
+    pub fn insert(&mut self, position: AxialVector) -> bool {
+        if self.masks.contains_position(position) {
+            return false;
+        }
+        let masks = &mut self.masks;
+        let mask = neighbor_mask(position, |neighbor| masks.contains_position(neighbor));
+        masks.insert(position, mask);
+        for direction in 0..NUM_DIRECTIONS {
+            if let Some(neighbor_mask) = masks.get_mut(position.neighbor(direction)) {
+                *neighbor_mask |= 1 << ((direction + 3) % NUM_DIRECTIONS);
+            }
+        }
+        true
+    }
+
+    // Whether the hex was in the set.
+    pub fn remove(&mut self, position: AxialVector) -> bool {
+        if self.masks.remove(position).is_none() {
+            return false;
+        }
+        for direction in 0..NUM_DIRECTIONS {
+            if let Some(neighbor_mask) = self.masks.get_mut(position.neighbor(direction)) {
+                *neighbor_mask &= !(1 << ((direction + 3) % NUM_DIRECTIONS));
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn test_autotile_index() {
+    let mut tiles = (0..64)
+        .map(|mask| autotile_index(mask).0)
+        .collect::<Vec<_>>();
+    tiles.sort();
+    tiles.dedup();
+    assert_eq!(tiles, (0..AUTOTILE_COUNT).collect::<Vec<_>>());
+    assert_eq!(autotile_index(0), (0, 0));
+    assert_eq!(autotile_index(ALL_NEIGHBORS), (13, 0));
+    // A wall end, then a straight wall, in every direction.
+    for direction in 0..NUM_DIRECTIONS {
+        let end = 1 << direction;
+        assert_eq!(autotile_index(end), (1, direction));
+        let straight = end | 1 << ((direction + 3) % NUM_DIRECTIONS);
+        assert_eq!(autotile_index(straight).0, autotile_index(0b1001).0);
+    }
+    for mask in 0..64 {
+        let (index, turns) = autotile_index(mask);
+        let (tile, _) = canonical(mask);
+        assert_eq!(rotate_mask(tile, turns), mask);
+        assert_eq!(autotile_index(rotate_mask(mask, 2)).0, index);
+        assert_eq!(autotile_mask(index), tile);
+    }
+}
+
+#[test]
+fn test_autotiles() {
+    let center = AxialVector::new(5, -2);
+    let mut set = center.ring_iter(2).collect::<HexSet>();
+    let mut autotiles = Autotiles::new(&set);
+    // Every hex of a ring has its two ring neighbors.
+    assert!(autotiles
+        .masks()
+        .iter()
+        .all(|(_, mask)| mask.count_ones() == 2));
+
+    let changes = [
+        (center, true),
+        (center + AxialVector::direction(1), true),
+        (center + AxialVector::direction(1) * 2, false),
+        (center, false),
+        (center + AxialVector::direction(4) * 2, false),
+    ];
+    for (position, inserted) in changes.iter() {
+        if *inserted {
+            assert!(autotiles.insert(*position));
+            assert!(!autotiles.insert(*position));
+            set.insert(*position);
+        } else {
+            assert!(autotiles.remove(*position));
+            assert!(!autotiles.remove(*position));
+            set.remove(*position);
+        }
+        let expected = Autotiles::new(&set);
+        assert_eq!(autotiles.masks().len(), expected.masks().len());
+        for position in set.iter() {
+            assert_eq!(autotiles.mask(position), expected.mask(position));
+        }
+    }
+    assert_eq!(autotiles.mask(center), None);
+}
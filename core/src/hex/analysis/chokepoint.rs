@@ -0,0 +1,303 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    storage::hash::RectHashStorage,
+};
+use std::{cmp::Reverse, hash::BuildHasher};
+
+// An open hex worth guarding: closing it cuts its area in two, or the open area is narrow across
+// it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Chokepoint {
+    pub position: AxialVector,
+    // The open hexes cut off from the largest remaining part of the area when this one is closed,
+    // zero if the area stays in one piece.
+    pub separated: usize,
+    // The fewest open hexes in a row through this one along one of the three axes, None if wider
+    // than the maximum width asked for.
+    pub width: Option<usize>,
+}
+
+// Two neighbors linked by no other way: the area falls apart if one cannot step from one to the
+// other.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Bridge {
+    pub from: AxialVector,
+    pub to: AxialVector,
+    // The open hexes on the smaller side.
+    pub separated: usize,
+}
+
+// The cut hexes of the open areas of the storage and the hexes of the passages at most
+// `max_width` hexes wide, the hexes missing from the storage being closed. The ones cutting off
+// the most hexes come first, then the narrowest, ties ordered by position.
+pub fn find_chokepoints<H, S, P>(
+    storage: &RectHashStorage<H, S>,
+    max_width: usize,
+    is_open: P,
+) -> Vec<Chokepoint>
+where
+    S: BuildHasher,
+    P: Fn(&H) -> bool,
+{
+    let open = |position| matches!(storage.get(position), Some(hex) if is_open(hex));
+    let graph = OpenGraph::search(storage, &is_open);
+    let mut chokepoints = graph
+        .positions
+        .iter()
+        .zip(graph.separated.iter())
+        .filter_map(|(position, separated)| {
+            let width = (0..NUM_DIRECTIONS / 2)
+                .map(|direction| {
+                    let run = |direction| {
+                        (1..=max_width)
+                            .take_while(|step| {
+                                open(*position + AxialVector::direction(direction) * *step as isize)
+                            })
+                            .count()
+                    };
+                    1 + run(direction) + run(direction + NUM_DIRECTIONS / 2)
+                })
+                .min()
+                .filter(|width| *width <= max_width);
+            if *separated > 0 || width.is_some() {
+                Some(Chokepoint {
+                    position: *position,
+                    separated: *separated,
+                    width,
+                })
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    chokepoints.sort_by_key(|chokepoint| {
+        (
+            Reverse(chokepoint.separated),
+            chokepoint.width.unwrap_or(usize::MAX),
+            chokepoint.position,
+        )
+    });
+    chokepoints
+}
+
+// The bridges between the open hexes of the storage, the ones with the most hexes on their smaller
+// side first, ties ordered by their ends. Each bridge goes from the side of the search start, the
+// smallest position of the area, to the other one.
+pub fn find_bridges<H, S, P>(storage: &RectHashStorage<H, S>, is_open: P) -> Vec<Bridge>
+where
+    S: BuildHasher,
+    P: Fn(&H) -> bool,
+{
+    let mut bridges = OpenGraph::search(storage, &is_open).bridges;
+    bridges.sort_by_key(|bridge| (Reverse(bridge.separated), bridge.from, bridge.to));
+    bridges
+}
+
+// The depth-first search of Tarjan over the open hexes, without recursion.
+struct OpenGraph {
+    positions: Vec<AxialVector>,
+    separated: Vec<usize>,
+    bridges: Vec<Bridge>,
+}
+
+impl OpenGraph {
+    fn search<H, S, P>(storage: &RectHashStorage<H, S>, is_open: &P) -> Self
+    where
+        S: BuildHasher,
+        P: Fn(&H) -> bool,
+    {
+        let mut positions = storage
+            .iter()
+            .filter(|(_, hex)| is_open(hex))
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        positions.sort();
+        let mut indices = RectHashStorage::new();
+        for (index, position) in positions.iter().enumerate() {
+            indices.insert(*position, index);
+        }
+        let len = positions.len();
+        // The order of discovery (0 for not yet), the lowest one reachable through a back edge
+        // and the size of the subtree of each hex.
+        let mut discovery = vec![0; len];
+        let mut low = vec![0; len];
+        let mut size = vec![1; len];
+        // The sizes of the parts each hex would cut off, when it has more than one.
+        let mut parts = vec![Vec::new(); len];
+        let mut separated = vec![0; len];
+        let mut bridges = Vec::new();
+        let mut counter = 0;
+        for root in 0..len {
+            if discovery[root] != 0 {
+                continue;
+            }
+            let mut area = Vec::new();
+            let mut area_bridges = Vec::new();
+            counter += 1;
+            discovery[root] = counter;
+            low[root] = counter;
+            let mut stack = vec![(root, None, 0)];
+            while let Some((node, parent, direction)) = stack.pop() {
+                if direction < NUM_DIRECTIONS {
+                    stack.push((node, parent, direction + 1));
+                    let next = match indices.get(positions[node].neighbor(direction)) {
+                        Some(next) => *next,
+                        None => continue,
+                    };
+                    if discovery[next] == 0 {
+                        counter += 1;
+                        discovery[next] = counter;
+                        low[next] = counter;
+                        stack.push((next, Some(node), 0));
+                    } else if Some(next) != parent {
+                        low[node] = low[node].min(discovery[next]);
+                    }
+                } else {
+                    area.push(node);
+                    if let Some(parent) = parent {
+                        size[parent] += size[node];
+                        low[parent] = low[parent].min(low[node]);
+                        if low[node] >= discovery[parent] {
+                            parts[parent].push(size[node]);
+                        }
+                        if low[node] > discovery[parent] {
+                            area_bridges.push((parent, node));
+                        }
+                    }
+                }
+            }
+            let area_len = area.len();
+            for node in area {
+                let node_parts = &mut parts[node];
+                // The rest of the area stays with the parent.
+                if node != root {
+                    node_parts.push(area_len - 1 - node_parts.iter().sum::<usize>());
+                }
+                if node_parts.len() > 1 {
+                    let largest = node_parts.iter().max().copied().unwrap_or(0);
+                    separated[node] = area_len - 1 - largest;
+                }
+            }
+            bridges.extend(area_bridges.into_iter().map(|(from, to)| Bridge {
+                from: positions[from],
+                to: positions[to],
+                separated: size[to].min(area_len - size[to]),
+            }));
+        }
+        Self {
+            positions,
+            separated,
+            bridges,
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_rooms() -> RectHashStorage<bool> {
+    // Two rooms linked by a corridor, a dead end off the west one and another room apart.
+    let mut storage = RectHashStorage::new();
+    for center in [
+        AxialVector::new(-6, 0),
+        AxialVector::new(6, 0),
+        AxialVector::new(20, 0),
+    ]
+    .iter()
+    {
+        for position in center.spiral_iter(2) {
+            storage.insert(position, true);
+        }
+    }
+    for q in -3..=3 {
+        storage.insert(AxialVector::new(q, 0), true);
+    }
+    storage.insert(AxialVector::new(-6, 3), true);
+    // Closed hexes are no way through.
+    storage.insert(AxialVector::new(0, 1), false);
+    storage.insert(AxialVector::new(0, -1), false);
+    storage
+}
+
+#[test]
+fn test_find_chokepoints() {
+    let storage = test_rooms();
+    let chokepoints = find_chokepoints(&storage, 2, |open| *open);
+    assert_eq!(
+        chokepoints[0],
+        Chokepoint {
+            position: AxialVector::new(-1, 0),
+            separated: 22,
+            width: Some(1),
+        }
+    );
+    let cut = chokepoints
+        .iter()
+        .filter(|chokepoint| chokepoint.separated > 0)
+        .map(|chokepoint| (chokepoint.position, chokepoint.separated))
+        .collect::<Vec<_>>();
+    // The west side has the dead end too.
+    assert_eq!(
+        cut,
+        vec![
+            (AxialVector::new(-1, 0), 22),
+            (AxialVector::new(0, 0), 22),
+            (AxialVector::new(-2, 0), 21),
+            (AxialVector::new(1, 0), 21),
+            (AxialVector::new(-3, 0), 20),
+            (AxialVector::new(2, 0), 20),
+            // Narrower.
+            (AxialVector::new(3, 0), 19),
+            (AxialVector::new(-4, 0), 19),
+            (AxialVector::new(4, 0), 18),
+            (AxialVector::new(-6, 2), 1),
+        ]
+    );
+    // The dead end cuts nothing off but it is narrow.
+    assert_eq!(
+        chokepoints[cut.len()],
+        Chokepoint {
+            position: AxialVector::new(-6, 3),
+            separated: 0,
+            width: Some(1),
+        }
+    );
+    assert_eq!(chokepoints.len(), cut.len() + 1);
+    // Wider passages only count when asked for.
+    assert!(find_chokepoints(&storage, 3, |open| *open)
+        .iter()
+        .any(|chokepoint| chokepoint.position == AxialVector::new(20, 2)));
+    assert!(find_chokepoints(&RectHashStorage::<bool>::new(), 2, |open| *open).is_empty());
+}
+
+#[test]
+fn test_find_bridges() {
+    let storage = test_rooms();
+    let bridges = find_bridges(&storage, |open| *open);
+    assert_eq!(bridges.len(), 9);
+    assert_eq!(
+        bridges[0],
+        Bridge {
+            from: AxialVector::new(-1, 0),
+            to: AxialVector::new(0, 0),
+            separated: 23,
+        }
+    );
+    assert_eq!(bridges[1].separated, 22);
+    assert_eq!(
+        bridges[8],
+        Bridge {
+            from: AxialVector::new(-6, 2),
+            to: AxialVector::new(-6, 3),
+            separated: 1,
+        }
+    );
+    // Without the dead end and with a second corridor, no way is the only one.
+    let mut storage = test_rooms();
+    storage.insert(AxialVector::new(-6, 3), false);
+    for q in -4..=6 {
+        storage.insert(AxialVector::new(q, -2), true);
+    }
+    assert!(find_bridges(&storage, |open| *open).is_empty());
+}
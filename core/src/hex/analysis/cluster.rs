@@ -0,0 +1,99 @@
+use crate::hex::{
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use std::{cmp::Reverse, hash::BuildHasher};
+
+// The hexes two hexes away between two neighbors, like the diagonals of a square grid.
+const DIAGONALS: [(isize, isize); 6] = [(2, -1), (1, -2), (-1, -1), (-2, 1), (-1, 2), (1, 1)];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum ClusterAdjacency {
+    // Sharing a side.
+    Neighbors,
+    // Also across the corner where they meet with two neighbors, e.g. for the resource patches
+    // which look as one.
+    Diagonals,
+}
+
+// The connected groups of at least `min_size` hexes matching the predicate, e.g. the matches of a
+// match-3 game. The largest come first, ties are ordered by their smallest position.
+pub fn clusters_matching<H, S, P>(
+    storage: &RectHashStorage<H, S>,
+    predicate: P,
+    min_size: usize,
+    adjacency: ClusterAdjacency,
+) -> Vec<HexSet>
+where
+    S: BuildHasher,
+    P: Fn(&H) -> bool,
+{
+    let matching: HexSet = HexSet::from_storage(storage, predicate);
+    let mut visited = HexSet::new();
+    let mut clusters = Vec::new();
+    for start in matching.iter() {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut cluster = HexSet::new();
+        cluster.insert(start);
+        let mut pending = vec![start];
+        while let Some(position) = pending.pop() {
+            let neighbors = (0..6).map(|direction| position.neighbor(direction));
+            let diagonals = DIAGONALS
+                .iter()
+                .filter(|_| adjacency == ClusterAdjacency::Diagonals)
+                .map(|(q, r)| position + AxialVector::new(*q, *r));
+            for next in neighbors.chain(diagonals) {
+                if matching.contains(next) && visited.insert(next) {
+                    cluster.insert(next);
+                    pending.push(next);
+                }
+            }
+        }
+        if cluster.len() >= min_size {
+            let first = cluster.iter().min().expect("cluster position");
+            clusters.push((first, cluster));
+        }
+    }
+    clusters.sort_by_key(|(first, cluster)| (Reverse(cluster.len()), *first));
+    clusters.into_iter().map(|(_, cluster)| cluster).collect()
+}
+
+#[test]
+fn test_clusters_matching() {
+    use crate::hex::ascii::parse_ascii;
+
+    let storage = parse_ascii(
+        &[
+            "o o . . x . . .",
+            " o . . x x . o .",
+            ". . . . . . . o",
+            " . x . . x . . .",
+            ". . . . x x . .",
+        ]
+        .join("\n"),
+        |c| if c == ' ' { None } else { Some(c) },
+    );
+    let clusters = |c, min_size, adjacency| {
+        clusters_matching(&storage, |hex| *hex == c, min_size, adjacency)
+            .iter()
+            .map(HexSet::len)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(clusters('x', 1, ClusterAdjacency::Neighbors), vec![3, 3, 1]);
+    assert_eq!(clusters('x', 2, ClusterAdjacency::Neighbors), vec![3, 3]);
+    assert_eq!(clusters('o', 1, ClusterAdjacency::Neighbors), vec![3, 2]);
+    assert_eq!(
+        clusters('o', 4, ClusterAdjacency::Neighbors),
+        Vec::<usize>::new()
+    );
+    assert_eq!(clusters('x', 1, ClusterAdjacency::Diagonals), vec![6, 1]);
+    assert_eq!(clusters('o', 1, ClusterAdjacency::Diagonals), vec![3, 2]);
+
+    // The ties come out in the same order every time.
+    let first = clusters_matching(&storage, |hex| *hex == 'x', 3, ClusterAdjacency::Neighbors);
+    assert!(first[0].contains(AxialVector::new(2, 4)));
+    assert!(first[1].contains(AxialVector::new(4, 0)));
+}
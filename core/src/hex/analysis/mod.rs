@@ -0,0 +1,5 @@
+pub mod autotile;
+pub mod chokepoint;
+pub mod cluster;
+pub mod sector;
+pub mod zone_of_control;
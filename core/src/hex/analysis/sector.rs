@@ -0,0 +1,127 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    geometry::VertexVector,
+    storage::set::HexSet,
+};
+
+// Splits the plane around a unit facing one of the 6 directions into 60° sectors centered on the
+// directions, sector 0 being in front of it and sector 3 behind it, or into twice as many 30° half
+// sectors, the halves of sector k being 2k and 2k + 1. A hex exactly on the line between two
+// sectors (or halves) belongs to the one with the higher index, modulo their count. The unit hex
+// belongs to none. Everything is computed with integers, the lines need no rounding.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Observer {
+    pub position: AxialVector,
+    pub facing: usize,
+}
+
+impl Observer {
+    pub fn new(position: AxialVector, facing: usize) -> Self {
+        Self { position, facing }
+    }
+
+    pub fn sector(&self, position: AxialVector) -> Option<usize> {
+        self.half_sector(position)
+            .map(|half_sector| half_sector / 2)
+    }
+
+    pub fn half_sector(&self, position: AxialVector) -> Option<usize> {
+        if position == self.position {
+            return None;
+        }
+        let vector = VertexVector::from(position - self.position);
+        // The lines of the half sectors, starting with the one between the absolute sectors 5
+        // and 0.
+        let lines = (0..2 * NUM_DIRECTIONS)
+            .map(|line| {
+                let direction = line / 2;
+                let previous = AxialVector::direction((direction + 5) % NUM_DIRECTIONS);
+                if line % 2 == 0 {
+                    VertexVector::from(previous + AxialVector::direction(direction))
+                } else {
+                    VertexVector::from(AxialVector::direction(direction))
+                }
+            })
+            .collect::<Vec<_>>();
+        let absolute = (0..lines.len())
+            .find(|half_sector| {
+                let stop = &lines[(*half_sector + 1) % lines.len()];
+                vector.is_in_cone(&lines[*half_sector], stop)
+            })
+            .expect("half sector");
+        Some((absolute + 2 * (NUM_DIRECTIONS - self.facing % NUM_DIRECTIONS)) % lines.len())
+    }
+
+    // The positions of the set in each of the 6 sectors.
+    pub fn sectors(&self, positions: &HexSet) -> Vec<HexSet> {
+        self.classify(positions, NUM_DIRECTIONS, |position| self.sector(position))
+    }
+
+    // The positions of the set in each of the 12 half sectors.
+    pub fn half_sectors(&self, positions: &HexSet) -> Vec<HexSet> {
+        self.classify(positions, 2 * NUM_DIRECTIONS, |position| {
+            self.half_sector(position)
+        })
+    }
+
+    // The sight radius towards each hex given the one of each sector, to limit a field of view
+    // with (e.g. shorter behind the observer).
+    pub fn sight(&self, distances: [usize; NUM_DIRECTIONS]) -> impl '_ + Fn(AxialVector) -> usize {
+        move |position| distances[self.sector(position).unwrap_or(0)]
+    }
+
+    fn classify<F>(&self, positions: &HexSet, count: usize, index: F) -> Vec<HexSet>
+    where
+        F: Fn(AxialVector) -> Option<usize>,
+    {
+        let mut sets = (0..count).map(|_| HexSet::new()).collect::<Vec<_>>();
+        for position in positions.iter() {
+            if let Some(index) = index(position) {
+                sets[index].insert(position);
+            }
+        }
+        sets
+    }
+}
+
+#[test]
+fn test_observer_sectors() {
+    let center = AxialVector::new(4, -1);
+    for facing in 0..NUM_DIRECTIONS {
+        let observer = Observer::new(center, facing);
+        assert_eq!(observer.sector(center), None);
+        for direction in 0..NUM_DIRECTIONS {
+            let relative = (direction + NUM_DIRECTIONS - facing) % NUM_DIRECTIONS;
+            let position = center + AxialVector::direction(direction) * 3;
+            assert_eq!(observer.sector(position), Some(relative));
+            // On the line between two halves.
+            assert_eq!(observer.half_sector(position), Some(2 * relative + 1));
+        }
+        let ahead = center + AxialVector::direction(facing) * 2;
+        assert_eq!(observer.sector(ahead), Some(0));
+        let behind = center + AxialVector::direction((facing + 3) % NUM_DIRECTIONS) * 2;
+        assert_eq!(observer.sector(behind), Some(3));
+    }
+
+    // The sectors split the set evenly since they turn into one another.
+    let area = center.spiral_iter(5).collect::<HexSet>();
+    let observer = Observer::new(center, 2);
+    let sectors = observer.sectors(&area);
+    assert_eq!(sectors.len(), 6);
+    assert!(sectors
+        .iter()
+        .all(|sector| sector.len() == (area.len() - 1) / 6));
+    let halves = observer.half_sectors(&area);
+    assert_eq!(halves.len(), 12);
+    for (index, half) in halves.iter().enumerate() {
+        assert!(half.is_subset(&sectors[index / 2]));
+        assert_eq!(half.len(), halves[index % 2].len());
+    }
+    assert_eq!(
+        halves.iter().map(|half| half.len()).sum::<usize>(),
+        area.len() - 1
+    );
+}
@@ -0,0 +1,156 @@
+use crate::hex::{
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+
+// All the bits of Threat::directions.
+pub const ALL_DIRECTIONS: u8 = 0b11_1111;
+
+// A unit threatens the hexes up to `range` away from it, but not its own hex. With some of the
+// `directions` bits missing, it only threatens the sectors of the others: the hexes closer to the
+// line of such a direction than to the line of any other one, the hexes halfway between two lines
+// belonging to both sectors.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Threat {
+    pub position: AxialVector,
+    pub range: usize,
+    pub directions: u8,
+}
+
+impl Threat {
+    pub fn new(position: AxialVector, range: usize) -> Self {
+        Self {
+            position,
+            range,
+            directions: ALL_DIRECTIONS,
+        }
+    }
+
+    pub fn facing(position: AxialVector, range: usize, directions: u8) -> Self {
+        Self {
+            position,
+            range,
+            directions,
+        }
+    }
+
+    pub fn threatens(&self, position: AxialVector) -> bool {
+        let distance = position.distance(self.position);
+        if distance == 0 || distance as usize > self.range {
+            return false;
+        }
+        if self.directions == ALL_DIRECTIONS {
+            return true;
+        }
+        let offsets = (0..6)
+            .map(|direction| {
+                let line = self.position + AxialVector::direction(direction) * distance;
+                position.distance(line)
+            })
+            .collect::<Vec<_>>();
+        let closest = offsets.iter().min().copied().unwrap_or(0);
+        (0..6).any(|direction| {
+            self.directions & (1 << direction) != 0 && offsets[direction] == closest
+        })
+    }
+}
+
+// How many units threaten each hex, the hexes threatened by none being absent.
+#[derive(Default)]
+pub struct ZoneOfControl {
+    counts: RectHashStorage<usize>,
+}
+
+impl ZoneOfControl {
+    pub fn new<I>(threats: I) -> Self
+    where
+        I: IntoIterator<Item = Threat>,
+    {
+        let mut zone = Self::default();
+        for threat in threats {
+            zone.add(&threat);
+        }
+        zone
+    }
+
+    pub fn add(&mut self, threat: &Threat) {
+        for position in threat.position.spiral_iter(threat.range) {
+            if threat.threatens(position) {
+                *self.counts.entry(position).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub fn count(&self, position: AxialVector) -> usize {
+        self.counts.get(position).copied().unwrap_or(0)
+    }
+
+    pub fn is_threatened(&self, position: AxialVector) -> bool {
+        self.counts.contains_position(position)
+    }
+
+    pub fn counts(&self) -> &RectHashStorage<usize> {
+        &self.counts
+    }
+
+    pub fn threatened(&self) -> HexSet {
+        HexSet::from_storage(&self.counts, |_| true)
+    }
+}
+
+#[test]
+fn test_threat_all_around() {
+    let center = AxialVector::new(2, -5);
+    let threat = Threat::new(center, 2);
+    let threatened = center
+        .spiral_iter(3)
+        .filter(|position| threat.threatens(*position))
+        .collect::<HexSet>();
+    let mut expected = center.spiral_iter(2).collect::<HexSet>();
+    expected.remove(center);
+    assert_eq!(threatened, expected);
+}
+
+#[test]
+fn test_threat_facing() {
+    let center = AxialVector::default();
+    let threat = Threat::facing(center, 4, 1 << 0);
+    for distance in 1..=4 {
+        let ring = center
+            .ring_iter(distance)
+            .filter(|position| threat.threatens(*position))
+            .count();
+        // The hexes of the ring up to halfway to the neighboring lines on both sides.
+        assert_eq!(ring, 1 + 2 * (distance / 2));
+        assert!(threat.threatens(AxialVector::direction(0) * distance as isize));
+        assert!(!threat.threatens(AxialVector::direction(3) * distance as isize));
+    }
+
+    // Opposite sectors share no hex, the sectors all around cover everything.
+    let front = Threat::facing(center, 4, 1 << 1);
+    let back = Threat::facing(center, 4, 1 << 4);
+    let sides = Threat::facing(center, 4, ALL_DIRECTIONS & !(1 << 1 | 1 << 4));
+    for position in center.spiral_iter(4) {
+        assert!(!(front.threatens(position) && back.threatens(position)));
+        assert_eq!(
+            front.threatens(position) || back.threatens(position) || sides.threatens(position),
+            position != center
+        );
+    }
+}
+
+#[test]
+fn test_zone_of_control() {
+    let a = AxialVector::new(0, 0);
+    let b = AxialVector::new(3, 0);
+    let zone = ZoneOfControl::new(vec![Threat::new(a, 2), Threat::new(b, 1)]);
+    assert_eq!(zone.count(a), 0);
+    assert_eq!(zone.count(AxialVector::new(1, 0)), 1);
+    assert_eq!(zone.count(AxialVector::new(2, 0)), 2);
+    assert_eq!(zone.count(b), 0);
+    assert!(!zone.is_threatened(AxialVector::new(5, 0)));
+    let threatened = zone.threatened();
+    assert_eq!(threatened.len(), 18 + 6 - 1);
+    assert!(threatened.iter().all(|position| zone.count(position) > 0));
+    assert_eq!(zone.counts().len(), threatened.len());
+}
@@ -0,0 +1,89 @@
+use crate::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+
+// Each hex takes two columns and each row is shifted by one column so that the output looks
+// like a hexagonal grid: the column of a hex is 2 * q + r (minus the smallest such value).
+
+pub fn render_ascii<H, F>(storage: &RectHashStorage<H>, to_char: F) -> String
+where
+    F: Fn(AxialVector, &H) -> char,
+{
+    let mut min_r = isize::MAX;
+    let mut max_r = isize::MIN;
+    let mut min_column = isize::MAX;
+    for position in storage.positions() {
+        min_r = min_r.min(position.r());
+        max_r = max_r.max(position.r());
+        min_column = min_column.min(2 * position.q() + position.r());
+    }
+    if storage.is_empty() {
+        return String::new();
+    }
+    // Keep the parity of columns consistent with rows so that the output can be parsed back.
+    if (min_column - min_r) % 2 != 0 {
+        min_column -= 1;
+    }
+    let mut lines = vec![Vec::<char>::new(); (max_r - min_r + 1) as usize];
+    for (position, hex) in storage.iter() {
+        let line = &mut lines[(position.r() - min_r) as usize];
+        let column = (2 * position.q() + position.r() - min_column) as usize;
+        if line.len() <= column {
+            line.resize(column + 1, ' ');
+        }
+        line[column] = to_char(position, hex);
+    }
+    let mut ascii = String::new();
+    for line in lines {
+        ascii.extend(line);
+        ascii.push('\n');
+    }
+    ascii
+}
+
+pub fn parse_ascii<H, F>(ascii: &str, from_char: F) -> RectHashStorage<H>
+where
+    F: Fn(char) -> Option<H>,
+{
+    let mut storage = RectHashStorage::new();
+    for (r, line) in ascii.lines().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            let shifted = column as isize - r as isize;
+            if shifted % 2 != 0 {
+                continue;
+            }
+            if let Some(hex) = from_char(c) {
+                storage.insert(AxialVector::new(shifted / 2, r as isize), hex);
+            }
+        }
+    }
+    storage
+}
+
+#[test]
+fn test_render_ascii() {
+    let mut storage = RectHashStorage::new();
+    for pos in AxialVector::default().ring_iter(1) {
+        storage.insert(pos, '#');
+    }
+    storage.insert(AxialVector::default(), '.');
+    assert_eq!(
+        render_ascii(&storage, |_, hex| *hex),
+        "  # #\n # . #\n  # #\n"
+    );
+}
+
+#[test]
+fn test_render_empty_ascii() {
+    assert_eq!(render_ascii(&RectHashStorage::<()>::new(), |_, _| '#'), "");
+}
+
+#[test]
+fn test_parse_ascii_round_trip() {
+    let ascii = "  # #\n # . #\n  # #\n";
+    let storage = parse_ascii(ascii, |c| if c == ' ' { None } else { Some(c) });
+    assert_eq!(storage.len(), 7);
+    assert_eq!(storage.get(AxialVector::new(1, 1)), Some(&'.'));
+    for pos in AxialVector::new(1, 1).ring_iter(1) {
+        assert_eq!(storage.get(pos), Some(&'#'));
+    }
+    assert_eq!(render_ascii(&storage, |_, hex| *hex), ascii);
+}
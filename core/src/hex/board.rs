@@ -0,0 +1,212 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+
+// Board of the classic hex games (Hex, Havannah, Yinsh-like boards): a fixed set of cells, each one
+// empty or holding a piece. A rhombus board may also wrap around like a torus, leaving it by a side
+// enters it again by the opposite one.
+pub struct Board<P> {
+    cells: RectHashStorage<Option<P>>,
+    // Width and height of the wrapped rhombus.
+    wrap: Option<(isize, isize)>,
+}
+
+impl<P> Board<P> {
+    pub fn new<I: IntoIterator<Item = AxialVector>>(positions: I) -> Self {
+        let mut cells = RectHashStorage::new();
+        for position in positions {
+            cells.insert(position, None);
+        }
+        Self { cells, wrap: None }
+    }
+
+    // The columns of q and the rows of r from 0, e.g. the 11 by 11 board of Hex.
+    pub fn rhombus(width: usize, height: usize) -> Self {
+        Self::new(
+            (0..height as isize)
+                .flat_map(|r| (0..width as isize).map(move |q| AxialVector::new(q, r))),
+        )
+    }
+
+    pub fn wrapped_rhombus(width: usize, height: usize) -> Self {
+        let mut board = Self::rhombus(width, height);
+        board.wrap = Some((width as isize, height as isize));
+        board
+    }
+
+    // Centered on the origin, e.g. the board of Havannah.
+    pub fn hexagon(radius: usize) -> Self {
+        Self::new(AxialVector::default().spiral_iter(radius))
+    }
+
+    pub fn contains(&self, position: AxialVector) -> bool {
+        self.cells.contains_position(position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn positions(&self) -> impl '_ + Iterator<Item = AxialVector> {
+        self.cells.positions()
+    }
+
+    pub fn get(&self, position: AxialVector) -> Option<&P> {
+        self.cells.get(position).and_then(Option::as_ref)
+    }
+
+    // Returns false if the cell is off the board or already taken.
+    pub fn place(&mut self, position: AxialVector, piece: P) -> bool {
+        match self.cells.get_mut(position) {
+            Some(cell) if cell.is_none() => {
+                *cell = Some(piece);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn take(&mut self, position: AxialVector) -> Option<P> {
+        self.cells.get_mut(position).and_then(Option::take)
+    }
+
+    // The neighbor cells on the board, across the sides when it wraps.
+    pub fn neighbors(&self, position: AxialVector) -> impl '_ + Iterator<Item = AxialVector> {
+        (0..NUM_DIRECTIONS).filter_map(move |direction| {
+            let mut neighbor = position.neighbor(direction);
+            if let Some((width, height)) = self.wrap {
+                neighbor = AxialVector::new(
+                    neighbor.q().rem_euclid(width),
+                    neighbor.r().rem_euclid(height),
+                );
+            }
+            if self.contains(neighbor) {
+                Some(neighbor)
+            } else {
+                None
+            }
+        })
+    }
+
+    // The cells whose neighbor in the direction is off the board, none when the board wraps. The
+    // opposite edges of the rhombus are those of the directions 0 and 3 (q) and 2 and 5 (r).
+    pub fn edge(&self, direction: usize) -> HexSet {
+        if self.wrap.is_some() {
+            return HexSet::new();
+        }
+        self.positions()
+            .filter(|position| !self.contains(position.neighbor(direction)))
+            .collect()
+    }
+}
+
+impl<P: PartialEq> Board<P> {
+    // The cells connected to the position through neighbors holding the same piece, empty if the
+    // cell is empty.
+    pub fn group(&self, position: AxialVector) -> HexSet {
+        let mut group = HexSet::new();
+        let piece = match self.get(position) {
+            Some(piece) => piece,
+            None => return group,
+        };
+        group.insert(position);
+        let mut pending = vec![position];
+        while let Some(position) = pending.pop() {
+            for neighbor in self.neighbors(position) {
+                if self.get(neighbor) == Some(piece) && group.insert(neighbor) {
+                    pending.push(neighbor);
+                }
+            }
+        }
+        group
+    }
+
+    // Whether a group of the piece links both sets of cells, e.g. the opposite edges of the Hex
+    // board.
+    pub fn connects(&self, piece: &P, from: &HexSet, to: &HexSet) -> bool {
+        let mut seen = HexSet::new();
+        for start in from.iter() {
+            if self.get(start) != Some(piece) || seen.contains(start) {
+                continue;
+            }
+            let group = self.group(start);
+            if group.iter().any(|position| to.contains(position)) {
+                return true;
+            }
+            seen.union_with(&group);
+        }
+        false
+    }
+}
+
+#[test]
+fn test_hex_board() {
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    enum Stone {
+        Red,
+        Blue,
+    }
+
+    let mut board = Board::rhombus(5, 5);
+    assert_eq!(board.len(), 25);
+    assert!(!board.place(AxialVector::new(5, 0), Stone::Red));
+    assert!(board.place(AxialVector::new(2, 2), Stone::Red));
+    assert!(!board.place(AxialVector::new(2, 2), Stone::Blue));
+    assert_eq!(board.get(AxialVector::new(2, 2)), Some(&Stone::Red));
+    assert_eq!(board.neighbors(AxialVector::new(2, 2)).count(), 6);
+    assert_eq!(board.neighbors(AxialVector::new(0, 0)).count(), 2);
+    assert_eq!(board.neighbors(AxialVector::new(4, 0)).count(), 3);
+
+    // Red goes from the top to the bottom, Blue from the left to the right.
+    let (top, bottom) = (board.edge(2), board.edge(5));
+    let (left, right) = (board.edge(3), board.edge(0));
+    assert_eq!(
+        (top.len(), bottom.len(), left.len(), right.len()),
+        (5, 5, 5, 5)
+    );
+    for position in &[(2, 0), (2, 1), (1, 4)] {
+        board.place(AxialVector::new(position.0, position.1), Stone::Red);
+    }
+    assert!(!board.connects(&Stone::Red, &top, &bottom));
+    for q in 0..5 {
+        board.place(AxialVector::new(q, 3), Stone::Blue);
+    }
+    assert_eq!(board.group(AxialVector::new(4, 3)).len(), 5);
+    assert!(board.connects(&Stone::Blue, &left, &right));
+    assert_eq!(board.take(AxialVector::new(1, 3)), Some(Stone::Blue));
+    board.place(AxialVector::new(1, 3), Stone::Red);
+    assert!(board.connects(&Stone::Red, &top, &bottom));
+    assert!(!board.connects(&Stone::Blue, &left, &right));
+    assert!(board.group(AxialVector::new(0, 0)).is_empty());
+}
+
+#[test]
+fn test_hexagon_and_wrapped_boards() {
+    let board = Board::<()>::hexagon(2);
+    assert_eq!(board.len(), 19);
+    for direction in 0..NUM_DIRECTIONS {
+        assert_eq!(board.edge(direction).len(), 5);
+    }
+
+    // Across the sides of a wrapped board.
+    let mut board = Board::wrapped_rhombus(4, 3);
+    assert!(board.edge(0).is_empty());
+    assert!(board
+        .positions()
+        .all(|position| board.neighbors(position).count() == 6));
+    assert!(board
+        .neighbors(AxialVector::new(3, 0))
+        .any(|neighbor| neighbor == AxialVector::new(0, 2)));
+    board.place(AxialVector::new(3, 0), 'x');
+    board.place(AxialVector::new(0, 2), 'x');
+    board.place(AxialVector::new(2, 2), 'x');
+    assert_eq!(board.group(AxialVector::new(3, 0)).len(), 2);
+}
@@ -0,0 +1,137 @@
+use crate::hex::{
+    coordinates::axial::AxialVector,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use std::hash::BuildHasher;
+
+// The hexes at most (width - 1) / 2 away from the line between start and end, both included. A
+// corridor grows by 2 hexes from one width to the next, even widths are rounded down.
+pub fn line_corridor(start: AxialVector, end: AxialVector, width: usize) -> HexSet {
+    let radius = width.saturating_sub(1) / 2;
+    start
+        .line_iter(end)
+        .flat_map(|position| position.spiral_iter(radius))
+        .collect()
+}
+
+// Opens a straight corridor from start to end, the cell closure gives the open hex for each
+// position. Returns the carved positions.
+pub fn carve_line<H, S, C>(
+    storage: &mut RectHashStorage<H, S>,
+    start: AxialVector,
+    end: AxialVector,
+    width: usize,
+    mut cell: C,
+) -> HexSet
+where
+    S: BuildHasher,
+    C: FnMut(AxialVector) -> H,
+{
+    let carved = line_corridor(start, end, width);
+    for position in carved.iter() {
+        storage.insert(position, cell(position));
+    }
+    carved
+}
+
+// Same, but the open hexes are left as they are and the corridor stops at the first open hex of the
+// line once out of the open area around the start, if any: it links the start to whatever open area
+// lies on the way to the end.
+pub fn carve_line_to_open<H, S, P, C>(
+    storage: &mut RectHashStorage<H, S>,
+    start: AxialVector,
+    end: AxialVector,
+    width: usize,
+    is_open: P,
+    mut cell: C,
+) -> HexSet
+where
+    S: BuildHasher,
+    P: Fn(&H) -> bool,
+    C: FnMut(AxialVector) -> H,
+{
+    let radius = width.saturating_sub(1) / 2;
+    let mut carved = HexSet::new();
+    let mut left = false;
+    for position in start.line_iter(end) {
+        // The open hexes, but not the ones of the corridor itself.
+        if !carved.contains(position) && matches!(storage.get(position), Some(hex) if is_open(hex))
+        {
+            if left {
+                break;
+            }
+            continue;
+        }
+        left = true;
+        for hex in position.spiral_iter(radius) {
+            if !matches!(storage.get(hex), Some(hex) if is_open(hex)) && carved.insert(hex) {
+                storage.insert(hex, cell(hex));
+            }
+        }
+    }
+    carved
+}
+
+#[test]
+fn test_carve_line() {
+    let (start, end) = (AxialVector::new(-3, 1), AxialVector::new(6, -5));
+    let mut storage = RectHashStorage::new();
+    let carved = carve_line(&mut storage, start, end, 1, |_| true);
+    assert_eq!(carved, start.line_iter(end).collect::<HexSet>());
+    assert_eq!(storage.len(), start.distance(end) as usize + 1);
+
+    // Every hex of a wider corridor is next to the line, all the neighbors of the line are in.
+    let carved = carve_line(&mut storage, start, end, 4, |_| true);
+    assert_eq!(carved, line_corridor(start, end, 3));
+    for position in carved.iter() {
+        assert!(start.line_iter(end).any(|hex| hex.distance(position) <= 1));
+    }
+    assert!(start
+        .line_iter(end)
+        .all(|hex| hex.ring_iter(1).all(|neighbor| carved.contains(neighbor))));
+    assert_eq!(storage.len(), carved.len());
+}
+
+#[test]
+fn test_carve_line_to_open() {
+    // A wall with an open room in the middle of the way.
+    let room = AxialVector::new(5, 0);
+    let mut storage = RectHashStorage::new();
+    for position in AxialVector::default().spiral_iter(10) {
+        storage.insert(position, position.distance(room) <= 2);
+    }
+    let start = AxialVector::new(-4, 0);
+    let is_open = |hex: &bool| *hex;
+    let carved = carve_line_to_open(
+        &mut storage,
+        start,
+        AxialVector::new(10, 0),
+        1,
+        is_open,
+        |_| true,
+    );
+    assert_eq!(
+        carved,
+        (-4..=2).map(|q| AxialVector::new(q, 0)).collect::<HexSet>()
+    );
+    assert!(carved
+        .iter()
+        .all(|position| storage.get(position) == Some(&true)));
+    assert_eq!(storage.get(AxialVector::new(9, 0)), Some(&false));
+
+    // The hexes of the room are not carved again, the start may be open.
+    let carved = carve_line_to_open(&mut storage, room, start, 3, is_open, |_| true);
+    assert!(carved.is_empty());
+    let carved = carve_line_to_open(
+        &mut storage,
+        room,
+        AxialVector::new(5, 8),
+        3,
+        is_open,
+        |_| true,
+    );
+    assert!(carved.iter().all(|position| position.distance(room) > 2));
+    assert!((3..=8).all(|r| AxialVector::new(5, r)
+        .spiral_iter(1)
+        .all(|position| position.distance(room) <= 2 || carved.contains(position))));
+}
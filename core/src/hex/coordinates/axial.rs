@@ -2,13 +2,16 @@ use crate::{
     hex::coordinates::{
         cubic::CubicVector,
         direction::{HexagonalDirection, NUM_DIRECTIONS},
-        ring::{BigRingIter, RingIter},
-        HexagonalVector,
+        line::LineIter,
+        ring::{BigRingIter, RingIter, SpiralIter},
+        HexCoord, HexagonalVector,
     },
     vector::Vector2ISize,
 };
 use std::ops::{Mul, MulAssign};
 
+// Ordered by q then r, so that sorted positions and BTreeMap keys come out in a deterministic
+// order. Hashing is consistent with equality.
 #[derive(
     Default,
     Clone,
@@ -43,13 +46,33 @@ impl AxialVector {
         CubicVector::from(self).distance(CubicVector::from(other))
     }
 
+    pub fn rotate(self, steps: usize) -> Self {
+        CubicVector::from(self).rotate(steps).into()
+    }
+
+    pub fn reflect(self) -> Self {
+        CubicVector::from(self).reflect().into()
+    }
+
+    pub fn ring_index(self, position: Self) -> (usize, usize) {
+        CubicVector::from(self).ring_index(position.into())
+    }
+
     pub fn ring_iter(&self, radius: usize) -> RingIter<Self> {
         RingIter::new(radius, *self)
     }
 
+    pub fn spiral_iter(&self, radius: usize) -> SpiralIter<Self> {
+        SpiralIter::new(radius, *self)
+    }
+
     pub fn big_ring_iter(&self, cell_radius: usize, radius: usize) -> BigRingIter<Self> {
         BigRingIter::new(cell_radius, radius, *self)
     }
+
+    pub fn line_iter(&self, end: Self) -> LineIter {
+        LineIter::new(*self, end)
+    }
 }
 
 impl Mul<isize> for AxialVector {
@@ -76,6 +99,16 @@ impl Mul<AxialVector> for isize {
 
 impl HexagonalVector for AxialVector {}
 
+impl HexCoord for AxialVector {
+    fn q(&self) -> isize {
+        self.0.x
+    }
+
+    fn r(&self) -> isize {
+        self.0.y
+    }
+}
+
 // Don't use constructor and lazy_static so that the compiler can actually optimize the use
 // of directions.
 const DIRECTIONS: [AxialVector; NUM_DIRECTIONS] = [
@@ -224,3 +257,89 @@ fn test_axial_ring_iter2() {
         ],
     );
 }
+
+#[test]
+fn test_axial_spiral_iter() {
+    let center = AxialVector::new(3, -1);
+    for radius in 0..5 {
+        let spiral = center.spiral_iter(radius).collect::<Vec<_>>();
+        let rings = (0..=radius)
+            .flat_map(|r| center.ring_iter(r))
+            .collect::<Vec<_>>();
+        assert_eq!(spiral, rings);
+        assert_eq!(spiral.len(), 1 + 3 * radius * (radius + 1));
+        let distinct = spiral.iter().collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(distinct.len(), spiral.len());
+    }
+}
+
+#[test]
+fn test_axial_ring_index() {
+    use crate::hex::coordinates::ring::polar_index_to_vector;
+    let center = AxialVector::new(-2, 5);
+    assert_eq!(center.ring_index(center), (0, 0));
+    assert_eq!(
+        polar_index_to_vector::<AxialVector>(0, 0),
+        AxialVector::default()
+    );
+    for radius in 1..6 {
+        let mut ring = (0..6 * radius)
+            .map(|polar_index| {
+                let position = center + polar_index_to_vector(polar_index, radius);
+                assert_eq!(center.ring_index(position), (radius, polar_index));
+                position
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            center + polar_index_to_vector(6 * radius, radius),
+            center + AxialVector::direction(0) * radius as isize
+        );
+        let mut expected = center.ring_iter(radius).collect::<Vec<_>>();
+        ring.sort();
+        expected.sort();
+        assert_eq!(ring, expected);
+    }
+}
+
+#[test]
+fn test_axial_rotate_and_reflect() {
+    for dir in 0..NUM_DIRECTIONS {
+        assert_eq!(
+            AxialVector::direction(dir).rotate(2),
+            AxialVector::direction((dir + 2) % NUM_DIRECTIONS)
+        );
+        assert_eq!(
+            AxialVector::direction(dir).reflect(),
+            AxialVector::direction((NUM_DIRECTIONS + 1 - dir) % NUM_DIRECTIONS)
+        );
+    }
+    let v = AxialVector::new(4, -1);
+    assert_eq!(v.rotate(3), AxialVector::new(-4, 1));
+    assert_eq!(v.reflect(), AxialVector::new(4, -3));
+}
+
+#[test]
+fn test_axial_vector_order() {
+    let mut positions = AxialVector::default().spiral_iter(1).collect::<Vec<_>>();
+    positions.sort();
+    assert_eq!(
+        positions,
+        vec![
+            AxialVector::new(-1, 0),
+            AxialVector::new(-1, 1),
+            AxialVector::new(0, -1),
+            AxialVector::new(0, 0),
+            AxialVector::new(0, 1),
+            AxialVector::new(1, -1),
+            AxialVector::new(1, 0),
+        ]
+    );
+
+    let map = positions
+        .iter()
+        .rev()
+        .map(|position| (*position, position.q() * 10 + position.r()))
+        .collect::<std::collections::BTreeMap<_, _>>();
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), positions);
+    assert_eq!(map.get(&AxialVector::new(1, -1)), Some(&9));
+}
@@ -2,14 +2,16 @@ use crate::{
     hex::coordinates::{
         axial::AxialVector,
         direction::{HexagonalDirection, NUM_DIRECTIONS},
-        ring::{BigRingIter, RingIter},
-        HexagonalVector,
+        ring::{BigRingIter, RingIter, SpiralIter},
+        HexCoord, HexagonalVector,
     },
     vector::Vector3ISize,
 };
 use derive_more::Add;
 use std::ops::{Mul, MulAssign};
 
+// Ordered by x, then y (z follows from them). Since y decreases when the axial r increases, it is
+// not the order of the axial vectors.
 #[derive(
     Default,
     Clone,
@@ -52,10 +54,59 @@ impl CubicVector {
         (vector.x().abs() + vector.y().abs() + vector.z().abs()) / 2
     }
 
+    // Rotation around the origin by steps * 60 degrees, direction d becomes direction d + steps.
+    pub fn rotate(self, steps: usize) -> Self {
+        let Vector3ISize { x, y, z } = self.0;
+        let (x, y, z) = match steps % NUM_DIRECTIONS {
+            0 => (x, y, z),
+            1 => (-y, -z, -x),
+            2 => (z, x, y),
+            3 => (-x, -y, -z),
+            4 => (y, z, x),
+            5 => (-z, -x, -y),
+            _ => unreachable!(),
+        };
+        Self(Vector3ISize { x, y, z })
+    }
+
+    // Reflection swapping y and z, direction d becomes direction 1 - d.
+    pub fn reflect(self) -> Self {
+        let Vector3ISize { x, y, z } = self.0;
+        Self(Vector3ISize { x, y: z, z: y })
+    }
+
+    // The radius of the ring around self the position lies on, and its polar index in that ring,
+    // the inverse of polar_index_to_vector.
+    pub fn ring_index(self, position: Self) -> (usize, usize) {
+        let vector = position - self;
+        let radius = self.distance(position) as usize;
+        if radius == 0 {
+            return (0, 0);
+        }
+        let polar_index = (0..NUM_DIRECTIONS)
+            .find_map(|side| {
+                let offset = vector - Self::direction(side) * radius as isize;
+                let side_offset = offset.distance(Self::default()) as usize;
+                if side_offset < radius
+                    && offset == Self::direction((side + 2) % NUM_DIRECTIONS) * side_offset as isize
+                {
+                    Some(side * radius + side_offset)
+                } else {
+                    None
+                }
+            })
+            .expect("polar index");
+        (radius, polar_index)
+    }
+
     pub fn ring_iter(&self, radius: usize) -> RingIter<Self> {
         RingIter::new(radius, *self)
     }
 
+    pub fn spiral_iter(&self, radius: usize) -> SpiralIter<Self> {
+        SpiralIter::new(radius, *self)
+    }
+
     pub fn big_ring_iter(&self, cell_radius: usize, radius: usize) -> BigRingIter<Self> {
         BigRingIter::new(cell_radius, radius, *self)
     }
@@ -85,6 +136,28 @@ impl Mul<CubicVector> for isize {
 
 impl HexagonalVector for CubicVector {}
 
+impl HexCoord for CubicVector {
+    fn q(&self) -> isize {
+        self.0.x
+    }
+
+    fn r(&self) -> isize {
+        self.0.z
+    }
+
+    fn x(&self) -> isize {
+        self.0.x
+    }
+
+    fn y(&self) -> isize {
+        self.0.y
+    }
+
+    fn z(&self) -> isize {
+        self.0.z
+    }
+}
+
 impl From<AxialVector> for CubicVector {
     fn from(axial: AxialVector) -> Self {
         let x = axial.q();
@@ -299,3 +372,93 @@ fn test_cubic_ring_iter2() {
         ],
     );
 }
+
+#[test]
+fn test_cubic_rotate_and_reflect() {
+    let v = CubicVector::new(3, -1, -2);
+    for dir in 0..NUM_DIRECTIONS {
+        for steps in 0..=NUM_DIRECTIONS {
+            assert_eq!(
+                CubicVector::direction(dir).rotate(steps),
+                CubicVector::direction((dir + steps) % NUM_DIRECTIONS)
+            );
+        }
+        assert_eq!(
+            CubicVector::direction(dir).reflect(),
+            CubicVector::direction((NUM_DIRECTIONS + 1 - dir) % NUM_DIRECTIONS)
+        );
+    }
+    assert_eq!(v.rotate(2).rotate(4), v);
+    assert_eq!(v.reflect().reflect(), v);
+    assert_eq!(v.rotate(1).distance(CubicVector::default()), 3);
+}
+
+#[test]
+fn test_cubic_vector_order() {
+    let mut axial = AxialVector::default().spiral_iter(2).collect::<Vec<_>>();
+    let mut cubic = axial
+        .iter()
+        .map(|position| CubicVector::from(*position))
+        .collect::<Vec<_>>();
+    axial.sort_by_key(|position| (position.q(), -position.r()));
+    cubic.sort();
+    assert_eq!(
+        cubic.into_iter().map(AxialVector::from).collect::<Vec<_>>(),
+        axial
+    );
+}
+
+#[test]
+fn test_cubic_rings_follow_axial() {
+    use crate::hex::coordinates::ring::polar_index_to_vector;
+    let axial = AxialVector::new(-2, 5);
+    let center = CubicVector::from(axial);
+    let to_cubic = |positions: Vec<AxialVector>| {
+        positions
+            .into_iter()
+            .map(CubicVector::from)
+            .collect::<Vec<_>>()
+    };
+    for radius in 0..5 {
+        assert_eq!(
+            center.ring_iter(radius).collect::<Vec<_>>(),
+            to_cubic(axial.ring_iter(radius).collect())
+        );
+        assert_eq!(
+            center.spiral_iter(radius).collect::<Vec<_>>(),
+            to_cubic(axial.spiral_iter(radius).collect())
+        );
+        for cell_radius in 0..3 {
+            assert_eq!(
+                center
+                    .big_ring_iter(cell_radius, radius)
+                    .collect::<Vec<_>>(),
+                to_cubic(axial.big_ring_iter(cell_radius, radius).collect())
+            );
+        }
+        for polar_index in 0..(6 * radius).max(1) {
+            let position = center + polar_index_to_vector(polar_index, radius);
+            assert_eq!(center.ring_index(position), (radius, polar_index));
+            assert_eq!(
+                center.ring_index(position),
+                axial.ring_index(position.into())
+            );
+        }
+    }
+}
+
+#[test]
+fn test_hex_coord_of_both_vectors() {
+    for axial in AxialVector::new(2, -1).spiral_iter(3) {
+        let cubic = CubicVector::from(axial);
+        assert_eq!(
+            (HexCoord::q(&axial), HexCoord::r(&axial)),
+            (axial.q(), axial.r())
+        );
+        assert_eq!((cubic.q(), cubic.r()), (axial.q(), axial.r()));
+        assert_eq!(
+            (axial.x(), axial.y(), axial.z()),
+            (cubic.x(), cubic.y(), cubic.z())
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::hex::coordinates::axial::AxialVector;
+use std::cmp::Ordering;
+
+// Hexes crossed by the segment between the centers of two hexes, both included, each one being a
+// neighbor of the previous one. The segment is nudged by an infinitesimal amount so that it never
+// runs exactly along an edge, ties always break the same way. Integers only: the hexes are rounded
+// from exact fractions.
+pub struct LineIter {
+    start: AxialVector,
+    end: AxialVector,
+    len: usize,
+    index: usize,
+}
+
+impl LineIter {
+    pub fn new(start: AxialVector, end: AxialVector) -> Self {
+        Self {
+            start,
+            end,
+            len: start.distance(end) as usize + 1,
+            index: 0,
+        }
+    }
+}
+
+// A cubic coordinate as the fraction `numerator / denominator` plus `nudge` times an infinitesimal.
+#[derive(Clone, Copy)]
+struct Fraction {
+    numerator: isize,
+    denominator: isize,
+    nudge: isize,
+}
+
+impl Fraction {
+    // The nearest integer, the nudge breaking the ties.
+    fn round(self) -> isize {
+        let double = 2 * self.denominator;
+        if self.nudge > 0 {
+            (2 * self.numerator + self.denominator).div_euclid(double)
+        } else {
+            -(self.denominator - 2 * self.numerator).div_euclid(double)
+        }
+    }
+
+    // How far from an integer, in units of 1 / denominator then of the infinitesimal.
+    fn distance(self, integer: isize) -> (isize, isize) {
+        let difference = integer * self.denominator - self.numerator;
+        match difference.cmp(&0) {
+            Ordering::Greater => (difference, -self.nudge),
+            Ordering::Less => (-difference, self.nudge),
+            Ordering::Equal => (0, self.nudge.abs()),
+        }
+    }
+}
+
+fn round(q: Fraction, r: Fraction) -> AxialVector {
+    let s = Fraction {
+        numerator: -q.numerator - r.numerator,
+        denominator: q.denominator,
+        nudge: -q.nudge - r.nudge,
+    };
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = (q.distance(rq), r.distance(rr), s.distance(rs));
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    AxialVector::new(rq, rr)
+}
+
+impl Iterator for LineIter {
+    type Item = AxialVector;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+        if index == 0 {
+            return Some(self.start);
+        } else if index == self.len - 1 {
+            return Some(self.end);
+        }
+        let denominator = (self.len - 1) as isize;
+        let index = index as isize;
+        let lerp = |start: isize, end: isize| Fraction {
+            numerator: start * denominator + (end - start) * index,
+            denominator,
+            nudge: 1,
+        };
+        Some(round(
+            lerp(self.start.q(), self.end.q()),
+            lerp(self.start.r(), self.end.r()),
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.index.min(self.len);
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for LineIter {}
+
+#[test]
+fn test_line_iter() {
+    let start = AxialVector::new(2, -1);
+    assert_eq!(start.line_iter(start).collect::<Vec<_>>(), vec![start]);
+    for end in AxialVector::new(-1, 3).spiral_iter(7) {
+        let line = start.line_iter(end).collect::<Vec<_>>();
+        assert_eq!(line.len(), start.distance(end) as usize + 1);
+        assert_eq!(line.first(), Some(&start));
+        assert_eq!(line.last(), Some(&end));
+        for pair in line.windows(2) {
+            assert_eq!(pair[0].distance(pair[1]), 1);
+        }
+    }
+    // Straight along a direction.
+    assert_eq!(
+        AxialVector::new(0, 0)
+            .line_iter(AxialVector::new(3, 0))
+            .collect::<Vec<_>>(),
+        (0..=3).map(|q| AxialVector::new(q, 0)).collect::<Vec<_>>()
+    );
+    // Along an edge, the nudge picks the same side whatever the length.
+    for length in 1..5 {
+        let end = AxialVector::new(length, length);
+        let line = AxialVector::new(0, 0).line_iter(end).collect::<Vec<_>>();
+        assert_eq!(line[1], AxialVector::new(1, 0));
+        assert_eq!(line[2 * length as usize - 1], end - AxialVector::new(0, 1));
+    }
+    // Exact far away from the origin.
+    let far = AxialVector::new(1 << 40, -(1 << 41));
+    assert_eq!(
+        far.line_iter(far + AxialVector::new(3, 0))
+            .collect::<Vec<_>>(),
+        (0..=3)
+            .map(|q| far + AxialVector::new(q, 0))
+            .collect::<Vec<_>>()
+    );
+}
@@ -3,9 +3,32 @@ use std::ops::{Add, AddAssign, Mul, MulAssign};
 pub mod axial;
 pub mod cubic;
 pub mod direction;
+pub mod line;
+pub mod offset;
 pub mod ring;
+pub mod transform;
 
 pub trait HexagonalVector:
     Sized + Clone + Copy + Add<Output = Self> + AddAssign + Mul<isize, Output = Self> + MulAssign<isize>
 {
 }
+
+// The axial and cubic coordinates of a position, whichever vector holds it, so that range checks
+// read them without converting the vector (q is x and r is z).
+pub trait HexCoord: Copy {
+    fn q(&self) -> isize;
+
+    fn r(&self) -> isize;
+
+    fn x(&self) -> isize {
+        self.q()
+    }
+
+    fn y(&self) -> isize {
+        -self.q() - self.r()
+    }
+
+    fn z(&self) -> isize {
+        self.r()
+    }
+}
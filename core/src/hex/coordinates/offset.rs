@@ -0,0 +1,69 @@
+use crate::hex::coordinates::axial::AxialVector;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HexOrientation {
+    PointyTop,
+    FlatTop,
+}
+
+// Column and row of a hex in a square grid, the way square grid libraries and 2D arrays (images,
+// heightmaps, Tiled maps) index them. With pointy top hexes the odd rows are shifted by half a hex
+// towards the next column, with flat top hexes the odd columns towards the next row. The origins
+// are the same.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct OffsetVector {
+    pub col: isize,
+    pub row: isize,
+}
+
+impl OffsetVector {
+    pub fn new(col: isize, row: isize) -> Self {
+        Self { col, row }
+    }
+
+    pub fn from_axial(position: AxialVector, orientation: HexOrientation) -> Self {
+        match orientation {
+            HexOrientation::PointyTop => {
+                Self::new(position.q() + position.r().div_euclid(2), position.r())
+            }
+            HexOrientation::FlatTop => {
+                Self::new(position.q(), position.r() + position.q().div_euclid(2))
+            }
+        }
+    }
+
+    pub fn to_axial(self, orientation: HexOrientation) -> AxialVector {
+        match orientation {
+            HexOrientation::PointyTop => {
+                AxialVector::new(self.col - self.row.div_euclid(2), self.row)
+            }
+            HexOrientation::FlatTop => {
+                AxialVector::new(self.col, self.row - self.col.div_euclid(2))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_offset_vector() {
+    for orientation in &[HexOrientation::PointyTop, HexOrientation::FlatTop] {
+        for position in AxialVector::new(3, -7).spiral_iter(6) {
+            let offset = OffsetVector::from_axial(position, *orientation);
+            assert_eq!(offset.to_axial(*orientation), position);
+        }
+        assert_eq!(
+            OffsetVector::from_axial(AxialVector::default(), *orientation),
+            OffsetVector::default()
+        );
+    }
+
+    // The grid neighbors of a shifted row (or column) are the next hexes of the rows around it.
+    let pointy = |col, row| OffsetVector::new(col, row).to_axial(HexOrientation::PointyTop);
+    assert_eq!(pointy(2, 1).distance(pointy(2, 0)), 1);
+    assert_eq!(pointy(2, 1).distance(pointy(3, 0)), 1);
+    assert_eq!(pointy(2, 1).distance(pointy(1, 0)), 2);
+    assert_eq!(pointy(2, -1).distance(pointy(3, 0)), 1);
+    let flat = |col, row| OffsetVector::new(col, row).to_axial(HexOrientation::FlatTop);
+    assert_eq!(flat(1, 2).distance(flat(0, 3)), 1);
+    assert_eq!(flat(1, 2).distance(flat(0, 1)), 2);
+}
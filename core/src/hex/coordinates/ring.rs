@@ -3,6 +3,18 @@ use crate::hex::coordinates::{
     HexagonalVector,
 };
 
+// The vector from the center to the hex at the given polar index of the ring of the given radius:
+// index 0 is radius steps away in direction 0, the ring then goes on towards direction 2 and
+// turns at every corner, back to the start after 6 * radius hexes. The field of view sweeps the
+// rings in this order.
+pub fn polar_index_to_vector<V: HexagonalDirection>(polar_index: usize, radius: usize) -> V {
+    // The center itself for a radius of 0.
+    let side = polar_index.checked_div(radius).unwrap_or(0) % NUM_DIRECTIONS;
+    let side_offset = polar_index.checked_rem(radius).unwrap_or(0);
+    V::direction(side) * radius as isize
+        + V::direction((side + 2) % NUM_DIRECTIONS) * side_offset as isize
+}
+
 pub struct RingIter<V: HexagonalVector + HexagonalDirection> {
     edge_length: usize,
     direction: usize,
@@ -65,6 +77,46 @@ impl<V: HexagonalDirection> Iterator for RingIter<V> {
     }
 }
 
+// The center first, then the rings up to the given radius, each of them in ring_iter order.
+pub struct SpiralIter<V: HexagonalVector + HexagonalDirection> {
+    center: V,
+    radius: usize,
+    max_radius: usize,
+    ring: RingIter<V>,
+}
+
+impl<V: HexagonalVector + HexagonalDirection> SpiralIter<V> {
+    pub fn new(radius: usize, center: V) -> Self {
+        Self {
+            center,
+            radius: 0,
+            max_radius: radius,
+            ring: RingIter::new(0, center),
+        }
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+}
+
+impl<V: HexagonalVector + HexagonalDirection> Iterator for SpiralIter<V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(next) = self.ring.next() {
+                return Some(next);
+            }
+            if self.radius >= self.max_radius {
+                return None;
+            }
+            self.radius += 1;
+            self.ring = RingIter::new(self.radius, self.center);
+        }
+    }
+}
+
 pub struct BigRingIter<V: HexagonalDirection> {
     edge_length: usize,
     direction: usize,
@@ -0,0 +1,121 @@
+use crate::hex::coordinates::{axial::AxialVector, direction::NUM_DIRECTIONS};
+
+// One of the 12 symmetries of the hex grid: an optional reflection (direction d becomes direction
+// 1 - d) followed by a rotation by steps * 60 degrees (direction d becomes direction d + steps).
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HexTransform {
+    steps: usize,
+    reflect: bool,
+}
+
+impl HexTransform {
+    pub fn new(steps: usize, reflect: bool) -> Self {
+        Self {
+            steps: steps % NUM_DIRECTIONS,
+            reflect,
+        }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn reflect(&self) -> bool {
+        self.reflect
+    }
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        [false, true]
+            .iter()
+            .flat_map(|reflect| (0..NUM_DIRECTIONS).map(move |steps| Self::new(steps, *reflect)))
+    }
+
+    // A reflection followed by a rotation is its own inverse.
+    pub fn inverse(self) -> Self {
+        if self.reflect {
+            self
+        } else {
+            Self::new(NUM_DIRECTIONS - self.steps, false)
+        }
+    }
+
+    // Around the origin.
+    pub fn apply(self, vector: AxialVector) -> AxialVector {
+        let vector = if self.reflect {
+            vector.reflect()
+        } else {
+            vector
+        };
+        vector.rotate(self.steps)
+    }
+
+    pub fn apply_around(self, position: AxialVector, center: AxialVector) -> AxialVector {
+        center + self.apply(position - center)
+    }
+
+    pub fn apply_direction(self, direction: usize) -> usize {
+        let direction = direction % NUM_DIRECTIONS;
+        let direction = if self.reflect {
+            (NUM_DIRECTIONS + 1 - direction) % NUM_DIRECTIONS
+        } else {
+            direction
+        };
+        (direction + self.steps) % NUM_DIRECTIONS
+    }
+
+    // Uniform over the 12 transforms, a u32 is sampled so that a seeded generator picks the same
+    // one on every platform.
+    #[cfg(feature = "rand")]
+    pub fn random<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        let index = rng.gen_range(0, 2 * NUM_DIRECTIONS as u32) as usize;
+        Self::new(index % NUM_DIRECTIONS, index >= NUM_DIRECTIONS)
+    }
+}
+
+#[test]
+fn test_hex_transform() {
+    use crate::hex::coordinates::direction::HexagonalDirection;
+
+    assert_eq!(HexTransform::all().count(), 12);
+    let position = AxialVector::new(3, -1);
+    let center = AxialVector::new(-2, 5);
+    for transform in HexTransform::all() {
+        for direction in 0..NUM_DIRECTIONS {
+            assert_eq!(
+                transform.apply(AxialVector::direction(direction)),
+                AxialVector::direction(transform.apply_direction(direction))
+            );
+        }
+        let moved = transform.apply_around(position, center);
+        assert_eq!(moved.distance(center), position.distance(center));
+        assert_eq!(transform.inverse().apply_around(moved, center), position);
+    }
+    assert_eq!(
+        HexTransform::new(1, false).apply(AxialVector::new(1, 0)),
+        AxialVector::new(1, -1)
+    );
+    assert_eq!(
+        HexTransform::new(0, true).apply(AxialVector::new(1, 0)),
+        AxialVector::new(1, -1)
+    );
+    assert_eq!(
+        HexTransform::new(2, true).apply_around(center, center),
+        center
+    );
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn test_random_hex_transform() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut seen = Vec::new();
+    for _ in 0..200 {
+        let transform = HexTransform::random(&mut rng);
+        if !seen.contains(&transform) {
+            seen.push(transform);
+        }
+    }
+    assert_eq!(seen.len(), 12);
+}
@@ -1,22 +1,29 @@
 use crate::{
-    hex::coordinates::{
-        axial::AxialVector, cubic::CubicVector, direction::HexagonalDirection, HexagonalVector,
+    hex::{
+        coordinates::{
+            axial::AxialVector, direction::HexagonalDirection, ring::polar_index_to_vector,
+            HexagonalVector,
+        },
+        geometry::{Turn, VertexVector, HEX_PLANE_VERTICES},
+        storage::set::HexSet,
     },
     vector::Vector2ISize,
 };
-use std::{cmp::Ordering, fmt::Debug};
+use std::fmt::Debug;
 
 #[derive(Default, Debug)]
 pub struct FieldOfView<V: HexagonalVector> {
     center: V,
     radius: usize,
     arcs: Vec<Arc>,
+    rings: RingCache<V>,
 }
 
 impl<V: HexagonalVector + HexagonalDirection + Into<VertexVector>> FieldOfView<V> {
     pub fn start(&mut self, center: V) {
         self.center = center;
         self.radius = 1;
+        self.rings.extend_to(1);
         self.arcs.clear();
         self.arcs.push(Arc {
             start: ArcEnd {
@@ -45,14 +52,17 @@ impl<V: HexagonalVector + HexagonalDirection + Into<VertexVector>> FieldOfView<V
         F: Fn(V) -> bool,
     {
         let radius = self.radius;
+        let _span = crate::timed_span!("next_radius", radius, arcs = self.arcs.len());
+        self.rings.extend_to(radius + 1);
+        let (ring, next_ring) = (self.rings.ring(radius), self.rings.ring(radius + 1));
         let mut expanded_arcs = Vec::new();
         for arc in &mut self.arcs {
             expanded_arcs.extend(
                 arc.clone()
-                    .split(self.center, radius, is_obstacle)
+                    .split(self.center, ring, is_obstacle)
                     .into_iter()
                     .map(|mut arc| {
-                        arc.expand::<V>(radius);
+                        arc.expand(radius, next_ring);
                         arc
                     }),
             );
@@ -61,11 +71,171 @@ impl<V: HexagonalVector + HexagonalDirection + Into<VertexVector>> FieldOfView<V
         self.radius = radius + 1;
     }
 
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
     pub fn iter(&self) -> ArcsIter<'_, V> {
-        ArcsIter::new(self.radius, self.arcs.iter())
+        ArcsIter::new(self.radius, self.rings.ring(self.radius), self.arcs.iter())
+    }
+
+    // Starts a field of view and expands it as the iteration goes, see RadiusIter.
+    pub fn expand<F>(center: V, max_radius: usize, is_obstacle: &F) -> RadiusIter<'_, V, F>
+    where
+        V: Default,
+        F: Fn(V) -> bool,
+    {
+        let mut fov = Self::default();
+        fov.start(center);
+        let ring = if max_radius > 0 {
+            fov.iter().map(|vector| center + vector).collect()
+        } else {
+            Vec::new()
+        };
+        RadiusIter {
+            fov,
+            is_obstacle,
+            max_radius,
+            ring: ring.into_iter(),
+        }
+    }
+}
+
+// The visible positions around the center with their radius, ring by ring up to the maximum
+// radius, the center excluded. Each position comes once, the obstacles being visible.
+pub struct RadiusIter<'a, V: HexagonalVector, F> {
+    fov: FieldOfView<V>,
+    is_obstacle: &'a F,
+    max_radius: usize,
+    ring: std::vec::IntoIter<V>,
+}
+
+impl<'a, V, F> Iterator for RadiusIter<'a, V, F>
+where
+    V: HexagonalVector + HexagonalDirection + Into<VertexVector>,
+    F: Fn(V) -> bool,
+{
+    type Item = (usize, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(position) = self.ring.next() {
+                return Some((self.fov.radius, position));
+            }
+            if self.fov.radius >= self.max_radius || self.fov.arcs.is_empty() {
+                return None;
+            }
+            self.fov.next_radius(self.is_obstacle);
+            let center = self.fov.center;
+            self.ring = self
+                .fov
+                .iter()
+                .map(|vector| center + vector)
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+// The vectors of the rings swept so far by polar index, see polar_index_to_vector, so that the arcs
+// do not compute them again each time they look at a hex. They do not depend on the center and are
+// kept when the field of view starts again.
+#[derive(Debug)]
+struct RingCache<V> {
+    rings: Vec<Vec<V>>,
+}
+
+impl<V> Default for RingCache<V> {
+    fn default() -> Self {
+        Self { rings: Vec::new() }
     }
 }
 
+impl<V: HexagonalDirection> RingCache<V> {
+    fn extend_to(&mut self, radius: usize) {
+        for ring_radius in self.rings.len()..=radius {
+            self.rings.push(
+                (0..(6 * ring_radius).max(1))
+                    .map(|polar_index| polar_index_to_vector(polar_index, ring_radius))
+                    .collect(),
+            );
+        }
+    }
+}
+
+impl<V> RingCache<V> {
+    // Empty if not extended to the radius yet.
+    fn ring(&self, radius: usize) -> &[V] {
+        self.rings.get(radius).map_or(&[], Vec::as_slice)
+    }
+}
+
+// The arcs go past the end of the ring, back to its start, like the polar indices.
+fn ring_vector<V: Copy>(ring: &[V], polar_index: usize) -> V {
+    ring[polar_index % ring.len()]
+}
+
+// Expands a field of view from the center until it stops discovering existing hexes (or reaches
+// the maximum radius) and returns the visible existing positions, the center included.
+pub fn visible_positions<O, E>(
+    center: AxialVector,
+    max_radius: Option<usize>,
+    is_obstacle: &O,
+    exists: E,
+) -> HexSet
+where
+    O: Fn(AxialVector) -> bool,
+    E: Fn(AxialVector) -> bool,
+{
+    visible_positions_in_sight(center, max_radius, |_| usize::MAX, is_obstacle, exists)
+}
+
+// Same as visible_positions, the hexes farther from the center than what `sight` returns for them
+// being out of sight (see Observer::sight for a sight depending on the facing). They still block
+// the view when they are obstacles.
+pub fn visible_positions_in_sight<S, O, E>(
+    center: AxialVector,
+    max_radius: Option<usize>,
+    sight: S,
+    is_obstacle: &O,
+    exists: E,
+) -> HexSet
+where
+    S: Fn(AxialVector) -> usize,
+    O: Fn(AxialVector) -> bool,
+    E: Fn(AxialVector) -> bool,
+{
+    let _span = crate::timed_span!("visible_positions", max_radius);
+    let mut visible_positions = HexSet::new();
+    visible_positions.insert(center);
+    let (mut ring_radius, mut ring_exists) = (0, false);
+    for (radius, position) in
+        FieldOfView::expand(center, max_radius.unwrap_or(usize::MAX), is_obstacle)
+    {
+        if radius != ring_radius {
+            if ring_radius > 0 && !ring_exists {
+                // Nothing new in the previous ring.
+                break;
+            }
+            ring_radius = radius;
+            ring_exists = false;
+        }
+        if radius <= sight(position) && exists(position) {
+            visible_positions.insert(position);
+            ring_exists = true;
+        }
+    }
+    visible_positions
+}
+
+// The hexes on the hex line strictly between both ends, e.g. to look for what stands in between.
+pub fn hexes_between(from: AxialVector, to: AxialVector) -> impl Iterator<Item = AxialVector> {
+    let distance = from.distance(to);
+    from.line_iter(to)
+        .skip(1)
+        .take((distance - 1).max(0) as usize)
+}
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 struct Arc {
     start: ArcEnd,
@@ -101,7 +271,7 @@ impl Arc {
     fn split<V: HexagonalDirection + Into<VertexVector>, F>(
         mut self,
         center: V,
-        radius: usize,
+        ring: &[V],
         is_obstacle: &F,
     ) -> Vec<Arc>
     where
@@ -111,7 +281,7 @@ impl Arc {
         loop {
             // Contract start
             while self.start.polar_index <= self.stop.polar_index {
-                let vector = ArcEnd::polar_index_to_vector(self.start.polar_index, radius);
+                let vector = ring_vector(ring, self.start.polar_index);
                 if is_obstacle(center + vector) {
                     self.start.contract_start(vector);
                     self.start.polar_index += 1;
@@ -122,7 +292,7 @@ impl Arc {
             // Find stop obstacle
             let mut polar_index = self.start.polar_index;
             while polar_index <= self.stop.polar_index {
-                let vector = ArcEnd::polar_index_to_vector(polar_index, radius);
+                let vector = ring_vector(ring, polar_index);
                 if is_obstacle(center + vector) {
                     let mut arc = self.clone();
                     // Contract stop
@@ -152,9 +322,10 @@ impl Arc {
         split
     }
 
-    fn expand<V: HexagonalDirection + Into<VertexVector>>(&mut self, radius: usize) {
-        self.start.expand_start::<V>(radius);
-        self.stop.expand_stop::<V>(radius);
+    // From the ring of the radius to the next one.
+    fn expand<V: HexagonalVector + Into<VertexVector>>(&mut self, radius: usize, next_ring: &[V]) {
+        self.start.expand_start(radius, next_ring);
+        self.stop.expand_stop(radius, next_ring);
     }
 }
 
@@ -165,14 +336,8 @@ struct ArcEnd {
 }
 
 impl ArcEnd {
-    fn polar_index_to_vector<V: HexagonalDirection>(polar_index: usize, radius: usize) -> V {
-        let side = (polar_index / radius) % 6;
-        let side_offset = polar_index % radius;
-        V::direction(side) * radius as isize + V::direction((side + 2) % 6) * side_offset as isize
-    }
-
-    fn is_right_of_arc<V: HexagonalDirection + Into<VertexVector>>(&self, radius: usize) -> bool {
-        let vector = ArcEnd::polar_index_to_vector::<V>(self.polar_index, radius);
+    fn is_right_of_arc<V: HexagonalVector + Into<VertexVector>>(&self, ring: &[V]) -> bool {
+        let vector = ring_vector(ring, self.polar_index);
         for local_vertex in HEX_PLANE_VERTICES.iter() {
             let vertex = vector.into() + *local_vertex;
             if self.vector.turns(&vertex) == Turn::Right {
@@ -182,8 +347,8 @@ impl ArcEnd {
         false
     }
 
-    fn is_left_of_arc<V: HexagonalDirection + Into<VertexVector>>(&self, radius: usize) -> bool {
-        let vector = ArcEnd::polar_index_to_vector::<V>(self.polar_index, radius);
+    fn is_left_of_arc<V: HexagonalVector + Into<VertexVector>>(&self, ring: &[V]) -> bool {
+        let vector = ring_vector(ring, self.polar_index);
         for local_vertex in HEX_PLANE_VERTICES.iter() {
             let vertex = vector.into() + *local_vertex;
             if self.vector.turns(&vertex) == Turn::Left {
@@ -211,18 +376,22 @@ impl ArcEnd {
         }
     }
 
-    fn expand_start<V: HexagonalDirection + Into<VertexVector>>(&mut self, radius: usize) {
+    fn expand_start<V: HexagonalVector + Into<VertexVector>>(
+        &mut self,
+        radius: usize,
+        next_ring: &[V],
+    ) {
         let side = self.polar_index / radius;
         let side_offset = self.polar_index % radius;
         let new_radius = radius + 1;
         self.polar_index = side * new_radius + side_offset + 1;
         loop {
-            if self.is_right_of_arc::<V>(new_radius) {
+            if self.is_right_of_arc(next_ring) {
                 // Found one hex crossing the arc.
                 // Check whether the next one is in the same case before breaking the loop.
                 if self.polar_index > 0 {
                     self.polar_index -= 1;
-                    if !self.is_left_of_arc::<V>(new_radius) {
+                    if !self.is_left_of_arc(next_ring) {
                         self.polar_index += 1;
                     }
                 }
@@ -233,18 +402,22 @@ impl ArcEnd {
         }
     }
 
-    fn expand_stop<V: HexagonalDirection + Into<VertexVector>>(&mut self, radius: usize) {
+    fn expand_stop<V: HexagonalVector + Into<VertexVector>>(
+        &mut self,
+        radius: usize,
+        next_ring: &[V],
+    ) {
         let side = self.polar_index / radius;
         let side_offset = self.polar_index % radius;
         let new_radius = radius + 1;
         self.polar_index = side * new_radius + side_offset;
         loop {
-            if self.is_left_of_arc::<V>(new_radius) {
+            if self.is_left_of_arc(next_ring) {
                 // Found one hex crossing the arc.
                 // Check whether the next one is in the same case before breaking the loop.
                 if self.polar_index < new_radius * 6 {
                     self.polar_index += 1;
-                    if !self.is_right_of_arc::<V>(new_radius) {
+                    if !self.is_right_of_arc(next_ring) {
                         self.polar_index -= 1;
                     }
                 }
@@ -256,82 +429,34 @@ impl ArcEnd {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
-enum Turn {
-    Left,
-    Straight,
-    Right,
-}
-
-#[derive(PartialEq, Eq, Clone, Copy, Add, AddAssign, Sub, SubAssign, Debug)]
-pub struct VertexVector(Vector2ISize);
-
-impl VertexVector {
-    fn turns(&self, other: &VertexVector) -> Turn {
-        let cross = self.0.x * other.0.y - self.0.y * other.0.x;
-        match cross.cmp(&0) {
-            Ordering::Greater => Turn::Left,
-            Ordering::Less => Turn::Right,
-            Ordering::Equal => Turn::Straight,
-        }
-    }
-}
-
-const HEX_PLANE_VERTICES: [VertexVector; 6] = [
-    VertexVector(Vector2ISize { x: 1, y: -1 }),
-    VertexVector(Vector2ISize { x: 1, y: 1 }),
-    VertexVector(Vector2ISize { x: 0, y: 2 }),
-    VertexVector(Vector2ISize { x: -1, y: 1 }),
-    VertexVector(Vector2ISize { x: -1, y: -1 }),
-    VertexVector(Vector2ISize { x: 0, y: -2 }),
-];
-
-impl From<AxialVector> for VertexVector {
-    fn from(axial: AxialVector) -> Self {
-        VertexVector(Vector2ISize {
-            x: 2 * axial.q() + axial.r(),
-            y: -3 * axial.r(),
-        })
-    }
-}
-
-impl From<CubicVector> for VertexVector {
-    fn from(cubic: CubicVector) -> Self {
-        VertexVector(Vector2ISize {
-            x: 2 * cubic.x() + cubic.z(),
-            y: -3 * cubic.z(),
-        })
-    }
-}
-
 pub struct ArcsIter<'a, V> {
     radius: usize,
+    ring: &'a [V],
     arcs: std::slice::Iter<'a, Arc>,
     current: Option<(&'a Arc, usize, usize)>,
-    _v: std::marker::PhantomData<V>,
 }
 
 impl<'a, V> ArcsIter<'a, V> {
-    fn new(radius: usize, mut arcs: std::slice::Iter<'a, Arc>) -> Self {
+    fn new(radius: usize, ring: &'a [V], mut arcs: std::slice::Iter<'a, Arc>) -> Self {
         let current = arcs
             .next()
             .map(|arc| (arc, arc.start.polar_index, arc.start.polar_index));
         Self {
             radius,
+            ring,
             arcs,
             current,
-            _v: Default::default(),
         }
     }
 }
 
-impl<'a, V: HexagonalDirection> Iterator for ArcsIter<'a, V> {
+impl<'a, V: HexagonalVector> Iterator for ArcsIter<'a, V> {
     type Item = V;
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some((arc, polar_index, first_polar_index)) = &mut self.current {
             let first_polar_index = *first_polar_index;
-            let res = Some(ArcEnd::polar_index_to_vector(*polar_index, self.radius));
+            let res = Some(ring_vector(self.ring, *polar_index));
             let next_polar_index = *polar_index + 1;
             if next_polar_index <= arc.stop.polar_index
                 && next_polar_index % (self.radius * 6) != first_polar_index
@@ -954,3 +1079,129 @@ fn test_field_of_view_line_dir_1_0() {
         );
     }
 }
+
+#[test]
+fn test_visible_positions() {
+    use std::collections::HashSet;
+
+    let center = AxialVector::new(2, -1);
+    let walls = center.ring_iter(3).collect::<HashSet<_>>();
+    let exists = |pos: AxialVector| pos.distance(center) <= 5;
+    let visible = visible_positions(center, None, &|pos| walls.contains(&pos), exists);
+    // The walls are seen, nothing behind them is.
+    let mut expected = center.spiral_iter(3).collect::<Vec<_>>();
+    expected.sort();
+    let mut positions = visible.iter().collect::<Vec<_>>();
+    positions.sort();
+    assert_eq!(positions, expected);
+
+    let visible = visible_positions(center, Some(2), &|_| false, exists);
+    assert_eq!(visible, center.spiral_iter(2).collect::<HexSet>());
+}
+
+#[test]
+fn test_field_of_view_expand() {
+    let center = AxialVector::new(-3, 4);
+    let walls = [
+        center + AxialVector::direction(0),
+        center + AxialVector::direction(0) * 3,
+    ];
+    let is_obstacle = |position| walls.contains(&position);
+    let positions = FieldOfView::expand(center, 4, &is_obstacle).collect::<Vec<_>>();
+    assert!(positions.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    assert!(positions
+        .iter()
+        .all(|(radius, position)| position.distance(center) as usize == *radius));
+    let set = positions
+        .iter()
+        .map(|(_, position)| *position)
+        .collect::<HexSet>();
+    assert_eq!(set.len(), positions.len());
+    // The first wall is seen, the second one is hidden behind it, the other lines are clear.
+    assert!(set.contains(walls[0]));
+    assert!(!set.contains(walls[1]));
+    for direction in 1..6 {
+        assert!(set.contains(center + AxialVector::direction(direction) * 4));
+    }
+    assert!(!set.contains(center));
+    assert_eq!(positions.last().map(|(radius, _)| *radius), Some(4));
+
+    assert_eq!(FieldOfView::expand(center, 0, &is_obstacle).count(), 0);
+    // Walled in.
+    let is_obstacle = |_| true;
+    assert_eq!(FieldOfView::expand(center, 10, &is_obstacle).count(), 6);
+}
+
+#[test]
+fn test_visible_positions_in_sight() {
+    use crate::hex::analysis::sector::Observer;
+
+    let observer = Observer::new(AxialVector::new(1, 1), 4);
+    let exists = |pos: AxialVector| pos.distance(observer.position) <= 6;
+    let visible = visible_positions_in_sight(
+        observer.position,
+        None,
+        observer.sight([5, 5, 2, 1, 2, 5]),
+        &|_| false,
+        exists,
+    );
+    for position in observer.position.spiral_iter(6) {
+        let distance = position.distance(observer.position) as usize;
+        let expected = match observer.sector(position) {
+            None => true,
+            Some(0) | Some(1) | Some(5) => distance <= 5,
+            Some(3) => distance <= 1,
+            Some(_) => distance <= 2,
+        };
+        assert_eq!(visible.contains(position), expected);
+    }
+
+    // The maximum radius still applies.
+    let visible = visible_positions_in_sight(
+        observer.position,
+        Some(3),
+        observer.sight([5; 6]),
+        &|_| false,
+        exists,
+    );
+    assert_eq!(
+        visible,
+        observer.position.spiral_iter(3).collect::<HexSet>()
+    );
+}
+
+#[test]
+fn test_hexes_between() {
+    let from = AxialVector::new(0, 0);
+    assert_eq!(
+        hexes_between(from, AxialVector::new(3, 0)).collect::<Vec<_>>(),
+        vec![AxialVector::new(1, 0), AxialVector::new(2, 0)]
+    );
+    assert_eq!(hexes_between(from, AxialVector::new(0, 1)).count(), 0);
+    assert_eq!(hexes_between(from, from).count(), 0);
+}
+
+#[test]
+fn test_field_of_view_ring_cache() {
+    let mut fov = FieldOfView::default();
+    fov.start(AxialVector::new(3, -1));
+    for _ in 0..4 {
+        fov.next_radius(&|_| false);
+    }
+    assert_eq!(fov.iter().count(), 30);
+    assert_eq!(fov.rings.rings.len(), 6);
+    for (radius, ring) in fov.rings.rings.iter().enumerate() {
+        assert_eq!(ring.len(), (6 * radius).max(1));
+        for (polar_index, vector) in ring.iter().enumerate() {
+            assert_eq!(*vector, polar_index_to_vector(polar_index, radius));
+        }
+    }
+    // The rings do not depend on the center.
+    fov.start(AxialVector::new(-2, 5));
+    assert_eq!(fov.rings.rings.len(), 6);
+    let mut ring = fov.iter().collect::<Vec<_>>();
+    ring.sort();
+    let mut expected = AxialVector::default().ring_iter(1).collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(ring, expected);
+}
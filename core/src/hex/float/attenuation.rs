@@ -0,0 +1,115 @@
+use crate::hex::{coordinates::axial::AxialVector, field_of_view::hexes_between};
+
+// How loud a sound is at the listener, as a factor of its volume at the source: 1 on the source
+// hex, fading with the hex distance down to 0 at the range and beyond, and muffled once more by
+// every obstacle between both ends. Positional sound on hex maps only needs to multiply the
+// volume with it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Attenuation {
+    // In hexes, nothing is heard that far.
+    pub range: usize,
+    // How the sound fades with the distance: 1 for a linear fade, more for a faster fade close to
+    // the source.
+    pub rolloff: f32,
+    // The factor applied for each obstacle on the line between the source and the listener, 0 for
+    // obstacles blocking the sound.
+    pub occlusion: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self {
+            range: 12,
+            rolloff: 1.0,
+            occlusion: 0.5,
+        }
+    }
+}
+
+impl Attenuation {
+    pub fn at_distance(&self, distance: usize) -> f32 {
+        if distance >= self.range {
+            return 0.0;
+        }
+        (1.0 - distance as f32 / self.range as f32).powf(self.rolloff)
+    }
+
+    pub fn with_obstacles(&self, distance: usize, obstacles: usize) -> f32 {
+        let occlusion = self.occlusion.clamp(0.0, 1.0);
+        self.at_distance(distance) * occlusion.powi(obstacles.min(i32::MAX as usize) as i32)
+    }
+
+    // The obstacles are counted on the hex line between both ends, these excluded.
+    pub fn between<O>(&self, source: AxialVector, listener: AxialVector, is_obstacle: O) -> f32
+    where
+        O: Fn(AxialVector) -> bool,
+    {
+        let distance = source.distance(listener) as usize;
+        if distance >= self.range {
+            return 0.0;
+        }
+        self.with_obstacles(distance, obstacles_between(source, listener, is_obstacle))
+    }
+}
+
+// The number of obstacles on the hex line from one end to the other, both ends excluded.
+pub fn obstacles_between<O>(from: AxialVector, to: AxialVector, is_obstacle: O) -> usize
+where
+    O: Fn(AxialVector) -> bool,
+{
+    hexes_between(from, to)
+        .filter(|position| is_obstacle(*position))
+        .count()
+}
+
+#[test]
+fn test_attenuation_at_distance() {
+    let linear = Attenuation {
+        range: 4,
+        rolloff: 1.0,
+        occlusion: 0.5,
+    };
+    assert_eq!(
+        (0..6)
+            .map(|distance| linear.at_distance(distance))
+            .collect::<Vec<_>>(),
+        vec![1.0, 0.75, 0.5, 0.25, 0.0, 0.0]
+    );
+    let quadratic = Attenuation {
+        rolloff: 2.0,
+        ..linear
+    };
+    assert_eq!(quadratic.at_distance(2), 0.25);
+    assert_eq!(linear.with_obstacles(2, 2), 0.125);
+    let blocking = Attenuation {
+        occlusion: 0.0,
+        ..linear
+    };
+    assert_eq!(blocking.with_obstacles(1, 0), 0.75);
+    assert_eq!(blocking.with_obstacles(1, 1), 0.0);
+    let silent = Attenuation { range: 0, ..linear };
+    assert_eq!(silent.at_distance(0), 0.0);
+}
+
+#[test]
+fn test_attenuation_between() {
+    let attenuation = Attenuation {
+        range: 10,
+        rolloff: 1.0,
+        occlusion: 0.5,
+    };
+    let source = AxialVector::new(0, 0);
+    let listener = AxialVector::new(5, 0);
+    let walls = [AxialVector::new(2, 0), AxialVector::new(3, 0)];
+    let is_wall = |position| walls.contains(&position);
+    assert_eq!(obstacles_between(source, listener, is_wall), 2);
+    assert_eq!(attenuation.between(source, listener, is_wall), 0.125);
+    assert_eq!(attenuation.between(source, listener, |_| false), 0.5);
+    // The ends do not muffle the sound.
+    assert_eq!(obstacles_between(walls[0], walls[1], is_wall), 0);
+    assert_eq!(attenuation.between(source, source, |_| true), 1.0);
+    assert_eq!(
+        attenuation.between(source, AxialVector::new(0, 10), |_| false),
+        0.0
+    );
+}
@@ -0,0 +1,231 @@
+use crate::hex::{
+    coordinates::direction::{HexagonalDirection, NUM_DIRECTIONS},
+    float::layout::HexLayout,
+    geometry::{VertexVector, HEX_PLANE_VERTICES},
+    storage::set::HexSet,
+};
+use std::{collections::HashMap, hash::BuildHasher};
+
+// The boundaries of the areas made of the hexes, in the layout coordinates, e.g. to build the
+// colliders of a physics engine or a navigation mesh. Each boundary is a closed ring, its last
+// point is not repeated. The outer boundaries have a positive signed area (shoelace formula on the
+// layout coordinates) and the holes a negative one, so that both can be told apart. The rings are
+// sorted, each one starting at its smallest corner in the hex plane.
+pub fn contours<S: BuildHasher>(hexes: &HexSet<S>, layout: &HexLayout) -> Vec<Vec<[f32; 2]>> {
+    // Each boundary corner starts a single boundary edge: with three hexes around a corner, the
+    // boundary goes through it once or not at all.
+    let mut edges = HashMap::new();
+    for position in hexes.iter() {
+        let center = VertexVector::from(position);
+        for direction in 0..NUM_DIRECTIONS {
+            if !hexes.contains(position.neighbor(direction)) {
+                let from = center + HEX_PLANE_VERTICES[(direction + 1) % NUM_DIRECTIONS];
+                let to = center + HEX_PLANE_VERTICES[direction];
+                edges.insert((from.x(), from.y()), (to.x(), to.y()));
+            }
+        }
+    }
+    let mut starts = edges.keys().copied().collect::<Vec<_>>();
+    starts.sort_unstable();
+    let mut rings = Vec::new();
+    for start in starts {
+        let mut next = match edges.remove(&start) {
+            Some(next) => next,
+            None => continue,
+        };
+        let mut ring = vec![start];
+        while next != start {
+            ring.push(next);
+            next = edges.remove(&next).expect("closed boundary");
+        }
+        rings.push(
+            ring.into_iter()
+                .map(|(x, y)| layout.vertex_to_point(VertexVector::new(x, y)))
+                .collect(),
+        );
+    }
+    rings
+}
+
+// The contours simplified so that no dropped corner is farther than `tolerance` from the new
+// boundary. The rings flattened to less than 3 points are dropped.
+pub fn simplified_contours<S: BuildHasher>(
+    hexes: &HexSet<S>,
+    layout: &HexLayout,
+    tolerance: f32,
+) -> Vec<Vec<[f32; 2]>> {
+    contours(hexes, layout)
+        .iter()
+        .map(|ring| simplify_ring(ring, tolerance))
+        .filter(|ring| ring.len() >= 3)
+        .collect()
+}
+
+// Douglas-Peucker on a closed ring, cut in two at its first point and the point farthest from it.
+pub fn simplify_ring(ring: &[[f32; 2]], tolerance: f32) -> Vec<[f32; 2]> {
+    if ring.len() < 3 {
+        return ring.to_vec();
+    }
+    let distance = |a: [f32; 2], b: [f32; 2]| (b[0] - a[0]).hypot(b[1] - a[1]);
+    let farthest = (1..ring.len())
+        .max_by(|a, b| {
+            distance(ring[0], ring[*a])
+                .partial_cmp(&distance(ring[0], ring[*b]))
+                .expect("comparable distances")
+        })
+        .expect("ring point");
+    let mut closed = ring.to_vec();
+    closed.push(ring[0]);
+    let mut simplified = simplify_polyline(&closed[..=farthest], tolerance);
+    simplified.pop();
+    simplified.extend(simplify_polyline(&closed[farthest..], tolerance));
+    simplified.pop();
+    simplified
+}
+
+// Douglas-Peucker, both ends kept.
+pub fn simplify_polyline(points: &[[f32; 2]], tolerance: f32) -> Vec<[f32; 2]> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut kept = vec![false; points.len()];
+    kept[0] = true;
+    kept[points.len() - 1] = true;
+    let mut pending = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = pending.pop() {
+        let (index, max) = (first + 1..last)
+            .map(|index| {
+                (
+                    index,
+                    segment_distance(points[index], points[first], points[last]),
+                )
+            })
+            .fold((first, 0.0), |max, candidate| {
+                if candidate.1 > max.1 {
+                    candidate
+                } else {
+                    max
+                }
+            });
+        if max > tolerance {
+            kept[index] = true;
+            pending.push((first, index));
+            pending.push((index, last));
+        }
+    }
+    points
+        .iter()
+        .zip(kept)
+        .filter(|(_, kept)| *kept)
+        .map(|(point, _)| *point)
+        .collect()
+}
+
+pub(crate) fn segment_distance(point: [f32; 2], start: [f32; 2], end: [f32; 2]) -> f32 {
+    let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+    let length2 = dx * dx + dy * dy;
+    let t = if length2 == 0.0 {
+        0.0
+    } else {
+        (((point[0] - start[0]) * dx + (point[1] - start[1]) * dy) / length2).clamp(0.0, 1.0)
+    };
+    (point[0] - start[0] - t * dx).hypot(point[1] - start[1] - t * dy)
+}
+
+// Shoelace formula, positive for the outer boundaries of contours.
+pub fn signed_area(ring: &[[f32; 2]]) -> f32 {
+    let mut area = 0.0;
+    for (index, [x0, y0]) in ring.iter().enumerate() {
+        let [x1, y1] = ring[(index + 1) % ring.len()];
+        area += x0 * y1 - x1 * y0;
+    }
+    area / 2.0
+}
+
+#[cfg(test)]
+fn hex_area(layout: &HexLayout) -> f32 {
+    3.0 * f32::sqrt(3.0) / 2.0 * layout.size() * layout.size()
+}
+
+#[test]
+fn test_contours_of_a_hex() {
+    use crate::hex::coordinates::axial::AxialVector;
+
+    let layout = HexLayout::pointy_top(10.0);
+    let position = AxialVector::new(2, -1);
+    let mut hexes = HexSet::new();
+    hexes.insert(position);
+    let rings = contours(&hexes, &layout);
+    assert_eq!(rings.len(), 1);
+    assert_eq!(rings[0].len(), 6);
+    assert!((signed_area(&rings[0]) - hex_area(&layout)).abs() < 0.01);
+    let [x, y] = layout.to_point(position);
+    for corner in 0..6 {
+        let [dx, dy] = layout.corner_offset(corner);
+        assert!(rings[0]
+            .iter()
+            .any(|[cx, cy]| (cx - x - dx).abs() < 1e-3 && (cy - y - dy).abs() < 1e-3));
+    }
+}
+
+#[test]
+fn test_contours_with_holes() {
+    use crate::hex::coordinates::{axial::AxialVector, offset::HexOrientation};
+
+    for orientation in &[HexOrientation::PointyTop, HexOrientation::FlatTop] {
+        let layout = HexLayout::new(*orientation, 2.0, [5.0, -3.0]);
+        // A ring of hexes around a hole, and a lone hex away.
+        let mut hexes = AxialVector::default().ring_iter(1).collect::<HexSet>();
+        hexes.insert(AxialVector::new(5, 0));
+        let rings = contours(&hexes, &layout);
+        assert_eq!(rings.len(), 3);
+        let mut areas = rings
+            .iter()
+            .map(|ring| signed_area(ring))
+            .collect::<Vec<_>>();
+        areas.sort_by(|a, b| a.partial_cmp(b).expect("area"));
+        let hex = hex_area(&layout);
+        assert!((areas[0] + hex).abs() < 0.01);
+        assert!((areas[1] - hex).abs() < 0.01);
+        assert!((areas[2] - 7.0 * hex).abs() < 0.01);
+        assert_eq!(contours(&hexes, &layout), rings);
+    }
+    assert!(contours(&HexSet::new(), &HexLayout::default()).is_empty());
+}
+
+#[test]
+fn test_simplified_contours() {
+    use crate::hex::coordinates::axial::AxialVector;
+
+    let layout = HexLayout::pointy_top(1.0);
+    let hexes = AxialVector::default().spiral_iter(4).collect::<HexSet>();
+    let rings = contours(&hexes, &layout);
+    assert_eq!(rings.len(), 1);
+    assert_eq!(rings[0].len(), 6 * (2 * 4 + 1));
+    // The zigzags of the sides are gone, not the corners of the big hexagon.
+    let simplified = simplified_contours(&hexes, &layout, 0.6);
+    assert_eq!(simplified.len(), 1);
+    assert!(simplified[0].len() >= 6 && simplified[0].len() < rings[0].len() / 2);
+    let area = signed_area(&rings[0]);
+    assert!((signed_area(&simplified[0]) - area).abs() < area * 0.1);
+    // Nothing is dropped without a tolerance.
+    assert_eq!(simplified_contours(&hexes, &layout, 0.0), rings);
+    // A single hex flattened away.
+    let mut lone = HexSet::new();
+    lone.insert(AxialVector::default());
+    assert!(simplified_contours(&lone, &layout, 10.0).is_empty());
+}
+
+#[test]
+fn test_simplify_polyline() {
+    let points = [[0.0, 0.0], [1.0, 0.1], [2.0, -0.1], [3.0, 5.0], [4.0, 6.0]];
+    assert_eq!(
+        simplify_polyline(&points, 0.5),
+        vec![[0.0, 0.0], [2.0, -0.1], [3.0, 5.0], [4.0, 6.0]]
+    );
+    assert_eq!(
+        simplify_polyline(&points, 10.0),
+        vec![[0.0, 0.0], [4.0, 6.0]]
+    );
+    assert_eq!(simplify_polyline(&points, 0.0), points.to_vec());
+}
@@ -0,0 +1,316 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        offset::{HexOrientation, OffsetVector},
+    },
+    float::layout::HexLayout,
+    storage::hash::RectHashStorage,
+};
+use std::{
+    hash::BuildHasher,
+    io::{Result, Write},
+};
+
+// Draws the hexes for which `fill` gives a color as SVG polygons, in the layout coordinates (the
+// y axis points down like r does).
+pub fn write_svg<W, H, S, F>(
+    writer: &mut W,
+    storage: &RectHashStorage<H, S>,
+    layout: &HexLayout,
+    fill: F,
+) -> Result<()>
+where
+    W: Write,
+    S: BuildHasher,
+    F: Fn(&H) -> Option<&'static str>,
+{
+    let mut hexes = storage
+        .iter()
+        .filter_map(|(position, hex)| fill(hex).map(|color| (position, color)))
+        .collect::<Vec<_>>();
+    hexes.sort_by_key(|(position, _)| *position);
+    let corners = (0..6)
+        .map(|corner| layout.corner_offset(corner))
+        .collect::<Vec<_>>();
+    let (mut min, mut max) = ([0.0f32; 2], [0.0f32; 2]);
+    for (index, (position, _)) in hexes.iter().enumerate() {
+        let [x, y] = layout.to_point(*position);
+        if index == 0 {
+            min = [x, y];
+            max = [x, y];
+        }
+        min = [min[0].min(x), min[1].min(y)];
+        max = [max[0].max(x), max[1].max(y)];
+    }
+    let margin = layout.size();
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        svg_number(min[0] - margin),
+        svg_number(min[1] - margin),
+        svg_number(max[0] - min[0] + 2.0 * margin),
+        svg_number(max[1] - min[1] + 2.0 * margin)
+    )?;
+    for (position, color) in hexes {
+        let [x, y] = layout.to_point(position);
+        let points = corners
+            .iter()
+            .map(|[dx, dy]| format!("{},{}", svg_number(x + dx), svg_number(y + dy)))
+            .collect::<Vec<_>>();
+        writeln!(
+            writer,
+            r#"  <polygon points="{}" fill="{}"/>"#,
+            points.join(" "),
+            color
+        )?;
+    }
+    writeln!(writer, "</svg>")
+}
+
+// Draws contours (see contour::contours) as a single SVG path filled with `fill`, the holes left
+// empty.
+pub fn write_contours_svg<W: Write>(
+    writer: &mut W,
+    contours: &[Vec<[f32; 2]>],
+    margin: f32,
+    fill: &str,
+) -> Result<()> {
+    let mut points = contours.iter().flatten();
+    let (mut min, mut max) = match points.next() {
+        Some(point) => (*point, *point),
+        None => ([0.0f32; 2], [0.0f32; 2]),
+    };
+    for [x, y] in points {
+        min = [min[0].min(*x), min[1].min(*y)];
+        max = [max[0].max(*x), max[1].max(*y)];
+    }
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        svg_number(min[0] - margin),
+        svg_number(min[1] - margin),
+        svg_number(max[0] - min[0] + 2.0 * margin),
+        svg_number(max[1] - min[1] + 2.0 * margin)
+    )?;
+    let path = contours
+        .iter()
+        .map(|ring| {
+            let points = ring
+                .iter()
+                .map(|[x, y]| format!("{},{}", svg_number(*x), svg_number(*y)))
+                .collect::<Vec<_>>();
+            format!("M{}Z", points.join(" L"))
+        })
+        .collect::<Vec<_>>();
+    writeln!(
+        writer,
+        r#"  <path d="{}" fill="{}" fill-rule="evenodd"/>"#,
+        path.join(" "),
+        fill
+    )?;
+    writeln!(writer, "</svg>")
+}
+
+// One decimal, without negative zeros.
+fn svg_number(value: f32) -> String {
+    format!("{:.1}", (value * 10.0).round() / 10.0 + 0.0)
+}
+
+pub struct TmxLayer<'a, H> {
+    pub name: &'a str,
+    // The tile of a hex, 0 leaves it empty.
+    pub tile: &'a dyn Fn(&H) -> u32,
+}
+
+// Tiled (https://www.mapeditor.org/) staggered hexagonal map with one tile layer per entry of
+// `layers`, covering the bounding box of the hexes. `hex_side` is the size of the hexes in pixels.
+pub fn write_tmx<W, H, S>(
+    writer: &mut W,
+    storage: &RectHashStorage<H, S>,
+    orientation: HexOrientation,
+    hex_side: u32,
+    layers: &[TmxLayer<H>],
+) -> Result<()>
+where
+    W: Write,
+    S: BuildHasher,
+{
+    // The stagger axis runs along the rows (pointy top) or the columns (flat top).
+    let to_offset = |position: AxialVector| {
+        let offset = OffsetVector::from_axial(position, orientation);
+        (offset.col, offset.row)
+    };
+    let mut bounds: Option<((isize, isize), (isize, isize))> = None;
+    for position in storage.positions() {
+        let (x, y) = to_offset(position);
+        bounds = Some(match bounds {
+            Some(((x0, y0), (x1, y1))) => ((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y))),
+            None => ((x, y), (x, y)),
+        });
+    }
+    let ((x0, y0), (x1, y1)) = bounds.unwrap_or(((0, 0), (-1, -1)));
+    let (width, height) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+    let side = f64::from(hex_side);
+    let (tile_width, tile_height, stagger_axis, stagger_start) = match orientation {
+        HexOrientation::PointyTop => ((side * f64::sqrt(3.0)).round(), 2.0 * side, "y", y0),
+        HexOrientation::FlatTop => (2.0 * side, (side * f64::sqrt(3.0)).round(), "x", x0),
+    };
+    // The odd rows (or columns) of the hex coordinates are the shifted ones.
+    let stagger_index = if stagger_start.rem_euclid(2) == 0 {
+        "odd"
+    } else {
+        "even"
+    };
+    let mut grids = vec![vec![0u32; width * height]; layers.len()];
+    for (position, hex) in storage.iter() {
+        let (x, y) = to_offset(position);
+        let offset = (x - x0) as usize + (y - y0) as usize * width;
+        for (grid, layer) in grids.iter_mut().zip(layers) {
+            grid[offset] = (layer.tile)(hex);
+        }
+    }
+    let tile_count = grids.iter().flatten().copied().max().unwrap_or(0);
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<map version="1.2" orientation="hexagonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" hexsidelength="{}" staggeraxis="{}" staggerindex="{}" infinite="0" nextlayerid="{}" nextobjectid="1">"#,
+        width,
+        height,
+        tile_width,
+        tile_height,
+        hex_side,
+        stagger_axis,
+        stagger_index,
+        layers.len() + 1
+    )?;
+    writeln!(
+        writer,
+        r#" <tileset firstgid="1" name="rhombus" tilewidth="{}" tileheight="{}" tilecount="{}" columns="0"/>"#,
+        tile_width, tile_height, tile_count
+    )?;
+    for (index, (layer, grid)) in layers.iter().zip(&grids).enumerate() {
+        writeln!(
+            writer,
+            r#" <layer id="{}" name="{}" width="{}" height="{}">"#,
+            index + 1,
+            layer.name,
+            width,
+            height
+        )?;
+        writeln!(writer, r#"  <data encoding="csv">"#)?;
+        for (row_index, row) in grid.chunks(width.max(1)).enumerate() {
+            let row = row.iter().map(u32::to_string).collect::<Vec<_>>();
+            let separator = if row_index + 1 < height { "," } else { "" };
+            writeln!(writer, "{}{}", row.join(","), separator)?;
+        }
+        writeln!(writer, "  </data>")?;
+        writeln!(writer, " </layer>")?;
+    }
+    writeln!(writer, "</map>")
+}
+
+#[cfg(test)]
+fn export_storage() -> RectHashStorage<u32> {
+    let mut storage = RectHashStorage::new();
+    storage.insert(AxialVector::new(0, 0), 1);
+    storage.insert(AxialVector::new(1, 0), 2);
+    storage.insert(AxialVector::new(0, 1), 0);
+    storage
+}
+
+#[test]
+fn test_write_svg() {
+    let mut svg = Vec::new();
+    write_svg(
+        &mut svg,
+        &export_storage(),
+        &HexLayout::pointy_top(10.0),
+        |hex| match hex {
+            0 => None,
+            1 => Some("gray"),
+            _ => Some("black"),
+        },
+    )
+    .expect("write svg");
+    let svg = String::from_utf8(svg).expect("utf8");
+    let lines = svg.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-10.0 -10.0 37.3 20.0">"#
+    );
+    assert_eq!(
+        lines[1],
+        r#"  <polygon points="8.7,-5.0 8.7,5.0 0.0,10.0 -8.7,5.0 -8.7,-5.0 0.0,-10.0" fill="gray"/>"#
+    );
+    assert!(lines[2].ends_with(r#"fill="black"/>"#));
+    assert_eq!(lines[3], "</svg>");
+    assert_eq!(lines.len(), 4);
+}
+
+#[test]
+fn test_write_contours_svg() {
+    use crate::hex::{float::contour::contours, storage::set::HexSet};
+
+    let mut hexes = HexSet::new();
+    hexes.insert(AxialVector::new(0, 0));
+    hexes.insert(AxialVector::new(3, 0));
+    let mut svg = Vec::new();
+    write_contours_svg(
+        &mut svg,
+        &contours(&hexes, &HexLayout::pointy_top(10.0)),
+        5.0,
+        "gray",
+    )
+    .expect("write svg");
+    let svg = String::from_utf8(svg).expect("utf8");
+    let lines = svg.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-13.7 -15.0 79.3 30.0">"#
+    );
+    assert_eq!(
+        lines[1],
+        concat!(
+            r#"  <path d="M-8.7,5.0 L-8.7,-5.0 L0.0,-10.0 L8.7,-5.0 L8.7,5.0 L0.0,10.0Z "#,
+            r#"M43.3,5.0 L43.3,-5.0 L52.0,-10.0 L60.6,-5.0 L60.6,5.0 L52.0,10.0Z" "#,
+            r#"fill="gray" fill-rule="evenodd"/>"#
+        )
+    );
+    assert_eq!(lines[2], "</svg>");
+}
+
+#[test]
+fn test_write_tmx() {
+    let mut tmx = Vec::new();
+    let tile = |hex: &u32| *hex;
+    let wall = |hex: &u32| (*hex == 2) as u32;
+    write_tmx(
+        &mut tmx,
+        &export_storage(),
+        HexOrientation::PointyTop,
+        16,
+        &[
+            TmxLayer {
+                name: "tiles",
+                tile: &tile,
+            },
+            TmxLayer {
+                name: "walls",
+                tile: &wall,
+            },
+        ],
+    )
+    .expect("write tmx");
+    let tmx = String::from_utf8(tmx).expect("utf8");
+    assert!(tmx.contains(
+        r#"width="2" height="2" tilewidth="28" tileheight="32" hexsidelength="16" staggeraxis="y" staggerindex="odd""#
+    ));
+    assert!(tmx.contains(r#"tilecount="2""#));
+    assert!(tmx.contains(
+        "<layer id=\"1\" name=\"tiles\" width=\"2\" height=\"2\">\n  <data encoding=\"csv\">\n1,2,\n0,0\n"
+    ));
+    assert!(tmx.contains(
+        "<layer id=\"2\" name=\"walls\" width=\"2\" height=\"2\">\n  <data encoding=\"csv\">\n0,1,\n0,0\n"
+    ));
+    assert!(tmx.ends_with("</map>\n"));
+}
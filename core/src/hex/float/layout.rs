@@ -0,0 +1,236 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        cubic::CubicVector,
+        offset::{HexOrientation, OffsetVector},
+    },
+    geometry::VertexVector,
+    storage::binary::{invalid_data, BinaryHex},
+};
+use std::io::{Read, Result, Write};
+
+// Maps hexagonal positions to the 2D plane: `size` is the distance between the center of a hex
+// and any of its vertices.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HexLayout {
+    orientation: HexOrientation,
+    size: f32,
+    origin: [f32; 2],
+}
+
+impl HexLayout {
+    pub fn new(orientation: HexOrientation, size: f32, origin: [f32; 2]) -> Self {
+        Self {
+            orientation,
+            size,
+            origin,
+        }
+    }
+
+    pub fn pointy_top(size: f32) -> Self {
+        Self::new(HexOrientation::PointyTop, size, [0.0, 0.0])
+    }
+
+    pub fn flat_top(size: f32) -> Self {
+        Self::new(HexOrientation::FlatTop, size, [0.0, 0.0])
+    }
+
+    pub fn orientation(&self) -> HexOrientation {
+        self.orientation
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    pub fn origin(&self) -> [f32; 2] {
+        self.origin
+    }
+
+    pub fn to_point(&self, position: AxialVector) -> [f32; 2] {
+        self.to_fractional_point(position.q() as f32, position.r() as f32)
+    }
+
+    // Same as to_point for fractional axial coordinates, e.g. to place something between hexes.
+    pub fn to_fractional_point(&self, q: f32, r: f32) -> [f32; 2] {
+        let sqrt3 = f32::sqrt(3.0);
+        let (x, y) = match self.orientation {
+            HexOrientation::PointyTop => (sqrt3 * q + sqrt3 / 2.0 * r, 1.5 * r),
+            HexOrientation::FlatTop => (1.5 * q, sqrt3 / 2.0 * q + sqrt3 * r),
+        };
+        [
+            self.origin[0] + x * self.size,
+            self.origin[1] + y * self.size,
+        ]
+    }
+
+    // The point of the hex plane, e.g. a hex corner, see VertexVector.
+    pub fn vertex_to_point(&self, vertex: VertexVector) -> [f32; 2] {
+        let r = -vertex.y() as f32 / 3.0;
+        self.to_fractional_point((vertex.x() as f32 - r) / 2.0, r)
+    }
+
+    // Offset of a corner from the center of any hex. Corners are sorted by increasing angle,
+    // corners 0 and 1 bound the edge facing direction 0.
+    pub fn corner_offset(&self, corner: usize) -> [f32; 2] {
+        let start_angle = match self.orientation {
+            HexOrientation::PointyTop => -30.0_f32,
+            HexOrientation::FlatTop => 0.0_f32,
+        };
+        let angle = (start_angle + 60.0 * (corner % 6) as f32).to_radians();
+        [self.size * angle.cos(), self.size * angle.sin()]
+    }
+
+    pub fn from_point(&self, point: [f32; 2]) -> AxialVector {
+        let x = (point[0] - self.origin[0]) / self.size;
+        let y = (point[1] - self.origin[1]) / self.size;
+        let sqrt3 = f32::sqrt(3.0);
+        let (q, r) = match self.orientation {
+            HexOrientation::PointyTop => (sqrt3 / 3.0 * x - y / 3.0, 2.0 / 3.0 * y),
+            HexOrientation::FlatTop => (2.0 / 3.0 * x, -x / 3.0 + sqrt3 / 3.0 * y),
+        };
+        Self::round(q, r)
+    }
+
+    fn round(q: f32, r: f32) -> AxialVector {
+        let s = -q - r;
+        let mut rq = q.round();
+        let mut rr = r.round();
+        let rs = s.round();
+        let dq = (rq - q).abs();
+        let dr = (rr - r).abs();
+        let ds = (rs - s).abs();
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        AxialVector::from(CubicVector::new(
+            rq as isize,
+            -(rq as isize) - (rr as isize),
+            rr as isize,
+        ))
+    }
+
+    pub fn to_offset(&self, position: AxialVector) -> OffsetVector {
+        OffsetVector::from_axial(position, self.orientation())
+    }
+
+    pub fn from_offset(&self, offset: OffsetVector) -> AxialVector {
+        offset.to_axial(self.orientation())
+    }
+}
+
+impl Default for HexLayout {
+    fn default() -> Self {
+        Self::pointy_top(1.0)
+    }
+}
+
+// As in the map files: the orientation, then the size and the origin.
+impl BinaryHex for HexLayout {
+    fn write_hex<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let orientation: u8 = match self.orientation {
+            HexOrientation::PointyTop => 0,
+            HexOrientation::FlatTop => 1,
+        };
+        orientation.write_hex(writer)?;
+        for value in &[self.size, self.origin[0], self.origin[1]] {
+            value.to_bits().write_hex(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_hex<R: Read>(reader: &mut R) -> Result<Self> {
+        let orientation = match u8::read_hex(reader)? {
+            0 => HexOrientation::PointyTop,
+            1 => HexOrientation::FlatTop,
+            value => return Err(invalid_data(format!("Invalid orientation {}", value))),
+        };
+        let mut values = [0.0; 3];
+        for value in values.iter_mut() {
+            *value = f32::from_bits(u32::read_hex(reader)?);
+        }
+        Ok(Self::new(orientation, values[0], [values[1], values[2]]))
+    }
+}
+
+#[cfg(test)]
+fn assert_point_eq(actual: [f32; 2], expected: [f32; 2]) {
+    assert!(
+        (actual[0] - expected[0]).abs() < 1e-4 && (actual[1] - expected[1]).abs() < 1e-4,
+        "{:?} != {:?}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn test_layout_to_point() {
+    let sqrt3 = f32::sqrt(3.0);
+    let pointy = HexLayout::pointy_top(2.0);
+    assert_point_eq(pointy.to_point(AxialVector::new(0, 0)), [0.0, 0.0]);
+    assert_point_eq(pointy.to_point(AxialVector::new(1, 0)), [2.0 * sqrt3, 0.0]);
+    assert_point_eq(pointy.to_point(AxialVector::new(0, 1)), [sqrt3, 3.0]);
+
+    let flat = HexLayout::new(HexOrientation::FlatTop, 1.0, [10.0, -10.0]);
+    assert_point_eq(flat.to_point(AxialVector::new(0, 0)), [10.0, -10.0]);
+    assert_point_eq(
+        flat.to_point(AxialVector::new(1, 0)),
+        [11.5, sqrt3 / 2.0 - 10.0],
+    );
+    assert_point_eq(flat.to_point(AxialVector::new(0, 1)), [10.0, sqrt3 - 10.0]);
+}
+
+#[test]
+fn test_layout_corner_offset() {
+    let sqrt3 = f32::sqrt(3.0);
+    let pointy = HexLayout::pointy_top(2.0);
+    assert_point_eq(pointy.corner_offset(0), [sqrt3, -1.0]);
+    assert_point_eq(pointy.corner_offset(1), [sqrt3, 1.0]);
+    assert_point_eq(pointy.corner_offset(4), [-sqrt3, -1.0]);
+    assert_point_eq(pointy.corner_offset(6), pointy.corner_offset(0));
+
+    let flat = HexLayout::flat_top(1.0);
+    assert_point_eq(flat.corner_offset(0), [1.0, 0.0]);
+    assert_point_eq(flat.corner_offset(1), [0.5, sqrt3 / 2.0]);
+
+    // A corner is at the same distance from the centers of three hexes.
+    for layout in [pointy, flat].iter() {
+        let center = layout.to_fractional_point(1.0 / 3.0, 1.0 / 3.0);
+        assert_point_eq(layout.corner_offset(1), center);
+    }
+}
+
+#[test]
+fn test_layout_from_point_round_trip() {
+    for layout in [
+        HexLayout::pointy_top(1.0),
+        HexLayout::flat_top(0.5),
+        HexLayout::new(HexOrientation::PointyTop, 3.0, [-7.0, 12.5]),
+    ]
+    .iter()
+    {
+        for radius in 0..10 {
+            for position in AxialVector::new(3, -5).ring_iter(radius) {
+                let point = layout.to_point(position);
+                assert_eq!(layout.from_point(point), position);
+                // Any point strictly inside the hex maps back to it.
+                let nudged = [
+                    point[0] + 0.4 * layout.size(),
+                    point[1] - 0.3 * layout.size(),
+                ];
+                assert_eq!(layout.from_point(nudged), position);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_layout_offset() {
+    // Where the hexes are drawn, the shifted rows are to the right.
+    let layout = HexLayout::pointy_top(1.0);
+    let [x0, _] = layout.to_point(layout.from_offset(OffsetVector::new(0, 0)));
+    let [x1, _] = layout.to_point(layout.from_offset(OffsetVector::new(0, 1)));
+    assert!(x1 > x0);
+}
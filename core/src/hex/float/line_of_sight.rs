@@ -0,0 +1,48 @@
+use crate::hex::{coordinates::axial::AxialVector, field_of_view::hexes_between};
+
+// Line of sight in a 2.5D world, the heights being in whatever unit `is_solid` takes. The hexes
+// between both ends are only checked at the height the sight line has above their center.
+pub fn is_in_line_of_sight<S>(
+    from: AxialVector,
+    from_height: f32,
+    to: AxialVector,
+    to_height: f32,
+    is_solid: S,
+) -> bool
+where
+    S: Fn(AxialVector, f32) -> bool,
+{
+    let distance = from.distance(to);
+    (1..).zip(hexes_between(from, to)).all(|(index, position)| {
+        let height = from_height + (to_height - from_height) * index as f32 / distance as f32;
+        !is_solid(position, height)
+    })
+}
+
+#[test]
+fn test_is_in_line_of_sight() {
+    let from = AxialVector::new(0, 0);
+    let to = AxialVector::new(6, 0);
+    // A 2 high wall in the middle.
+    let is_solid = |position: AxialVector, height: f32| position.q() == 3 && height < 2.0;
+    assert!(!is_in_line_of_sight(from, 1.0, to, 1.0, is_solid));
+    assert!(is_in_line_of_sight(from, 3.0, to, 1.5, is_solid));
+    assert!(!is_in_line_of_sight(from, 3.0, to, 0.5, is_solid));
+    // The ends are never checked.
+    let is_solid = |_: AxialVector, _: f32| true;
+    assert!(is_in_line_of_sight(from, 0.0, from, 0.0, is_solid));
+    assert!(is_in_line_of_sight(
+        from,
+        0.0,
+        AxialVector::new(0, 1),
+        0.0,
+        is_solid
+    ));
+    assert!(!is_in_line_of_sight(
+        from,
+        0.0,
+        AxialVector::new(-1, 2),
+        0.0,
+        is_solid
+    ));
+}
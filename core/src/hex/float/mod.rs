@@ -0,0 +1,13 @@
+// The only part of the hex module computing with floating point numbers, whose results may differ
+// from a platform to another: what maps the hexes to the plane or to sounds, and what works in
+// the plane from there. Lockstep games keep it out of their simulation. Elsewhere, the f32 and
+// f64 types are denied by the clippy configuration of the crate.
+#![allow(clippy::disallowed_types)]
+
+pub mod attenuation;
+pub mod contour;
+pub mod export;
+pub mod layout;
+pub mod line_of_sight;
+pub mod navmesh;
+pub mod path_sampler;
@@ -0,0 +1,460 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    float::{contour::segment_distance, layout::HexLayout},
+    geometry::{VertexVector, HEX_PLANE_VERTICES},
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use std::{cmp::Reverse, collections::BinaryHeap, hash::BuildHasher};
+
+// Navigation mesh of the open hexes, for agents moving freely in the layout plane rather than hex
+// by hex. The convex polygons are the hexes themselves: a union of hexes is never convex, so
+// larger polygons would cut through hexes and lose the link with the map. Paths go through a
+// corridor of polygons and are pulled tight along it, turning only at the corners of obstacles.
+pub struct NavMesh {
+    layout: HexLayout,
+    polygons: Vec<NavPolygon>,
+    indices: RectHashStorage<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NavPolygon {
+    position: AxialVector,
+    // Corners in the layout coordinates, the edge facing a direction between the corners of the
+    // same index and the next one.
+    vertices: Vec<[f32; 2]>,
+    // Indices of the polygons sharing an edge.
+    neighbors: Vec<usize>,
+}
+
+impl NavPolygon {
+    pub fn position(&self) -> AxialVector {
+        self.position
+    }
+
+    pub fn vertices(&self) -> &[[f32; 2]] {
+        &self.vertices
+    }
+
+    pub fn neighbors(&self) -> &[usize] {
+        &self.neighbors
+    }
+}
+
+impl NavMesh {
+    pub fn new<S: BuildHasher>(open: &HexSet<S>, layout: HexLayout) -> Self {
+        let mut positions = open.iter().collect::<Vec<_>>();
+        positions.sort_unstable();
+        let mut indices = RectHashStorage::new();
+        for (index, position) in positions.iter().enumerate() {
+            indices.insert(*position, index);
+        }
+        let polygons = positions
+            .iter()
+            .map(|position| {
+                let center = VertexVector::from(*position);
+                NavPolygon {
+                    position: *position,
+                    vertices: HEX_PLANE_VERTICES
+                        .iter()
+                        .map(|corner| layout.vertex_to_point(center + *corner))
+                        .collect(),
+                    neighbors: (0..NUM_DIRECTIONS)
+                        .filter_map(|direction| indices.get(position.neighbor(direction)).copied())
+                        .collect(),
+                }
+            })
+            .collect();
+        Self {
+            layout,
+            polygons,
+            indices,
+        }
+    }
+
+    pub fn layout(&self) -> &HexLayout {
+        &self.layout
+    }
+
+    // Sorted by position.
+    pub fn polygons(&self) -> &[NavPolygon] {
+        &self.polygons
+    }
+
+    pub fn polygon_index(&self, position: AxialVector) -> Option<usize> {
+        self.indices.get(position).copied()
+    }
+
+    // The polygon containing the point.
+    pub fn locate(&self, point: [f32; 2]) -> Option<usize> {
+        self.polygon_index(self.layout.from_point(point))
+    }
+
+    // A* over the polygons from the one of the start point to the one of the goal point, both
+    // included. Each polygon entered costs a step and twice its distance to the straight line
+    // between the points: the corridor follows that line when it is open, even with more steps,
+    // so that the path pulled along it is straight too.
+    pub fn corridor(&self, start: [f32; 2], goal: [f32; 2]) -> Option<Vec<usize>> {
+        let (start_index, goal_index) = (self.locate(start)?, self.locate(goal)?);
+        let step = f32::sqrt(3.0) * self.layout.size();
+        // In thousandths of steps, to be ordered.
+        let thousandths = |length: f32| (length / step * 1000.0) as usize;
+        let goal_center = self.layout.to_point(self.polygons[goal_index].position);
+        let estimate = |index: usize| {
+            let [x, y] = self.layout.to_point(self.polygons[index].position);
+            thousandths((x - goal_center[0]).hypot(y - goal_center[1]))
+        };
+        let cost = |index: usize| {
+            let center = self.layout.to_point(self.polygons[index].position);
+            1000 + 2 * thousandths(segment_distance(center, start, goal))
+        };
+        // The cheapest cost to reach a polygon and the polygon it is reached from.
+        let mut best = vec![None; self.polygons.len()];
+        let mut pending = BinaryHeap::new();
+        best[start_index] = Some((0, start_index));
+        pending.push(Reverse((estimate(start_index), 0, start_index)));
+        while let Some(Reverse((_, spent, index))) = pending.pop() {
+            if best[index].map(|(best_spent, _)| best_spent) != Some(spent) {
+                continue;
+            }
+            if index == goal_index {
+                let mut corridor = vec![goal_index];
+                let mut current = goal_index;
+                while current != start_index {
+                    current = best[current].expect("reached polygon").1;
+                    corridor.push(current);
+                }
+                corridor.reverse();
+                return Some(corridor);
+            }
+            for neighbor in &self.polygons[index].neighbors {
+                let total = spent + cost(*neighbor);
+                if matches!(best[*neighbor], Some((previous, _)) if previous <= total) {
+                    continue;
+                }
+                best[*neighbor] = Some((total, index));
+                pending.push(Reverse((total + estimate(*neighbor), total, *neighbor)));
+            }
+        }
+        None
+    }
+
+    // The shortest path from the start to the goal through the corridor, both ends included, None
+    // if either end is out of the mesh or the goal is not reachable.
+    pub fn find_path(&self, start: [f32; 2], goal: [f32; 2]) -> Option<Vec<[f32; 2]>> {
+        let corridor = self.corridor(start, goal)?;
+        let mut portals = vec![(start, start)];
+        for pair in corridor.windows(2) {
+            portals.push(self.portal(pair[0], pair[1]));
+        }
+        portals.push((goal, goal));
+        // The corridor is not always the one of the straight line, the corners of polygons that
+        // are not obstacles are cut.
+        let pulled = string_pull(&portals);
+        let mut path = vec![start];
+        let mut index = 0;
+        while index + 1 < pulled.len() {
+            index = (index + 2..pulled.len())
+                .rev()
+                .find(|next| self.is_visible(pulled[index], pulled[*next]))
+                .unwrap_or(index + 1);
+            path.push(pulled[index]);
+        }
+        Some(path)
+    }
+
+    // Whether the segment stays in the mesh, walking from polygon to polygon. Touching the border
+    // is fine, but running along it or through a corner between two obstacles may be refused.
+    pub fn is_visible(&self, from: [f32; 2], to: [f32; 2]) -> bool {
+        let direction = [to[0] - from[0], to[1] - from[1]];
+        let length = direction[0].hypot(direction[1]);
+        if length == 0.0 {
+            return self.locate(from).is_some();
+        }
+        // Off the corner or the edge the segment may start from.
+        let nudge = self.layout.size() * 1e-3 / length;
+        let mut current = match self.locate([
+            from[0] + nudge * direction[0],
+            from[1] + nudge * direction[1],
+        ]) {
+            Some(current) => current,
+            None => return false,
+        };
+        for _ in 0..self.polygons.len() {
+            let polygon = &self.polygons[current];
+            let center = self.layout.to_point(polygon.position);
+            // The segment leaves the convex polygon through the first edge it crosses outwards.
+            let mut exit = (f32::INFINITY, 0);
+            for edge in 0..NUM_DIRECTIONS {
+                let a = polygon.vertices[edge];
+                let b = polygon.vertices[(edge + 1) % NUM_DIRECTIONS];
+                let middle = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+                let normal = [middle[0] - center[0], middle[1] - center[1]];
+                let outwards = direction[0] * normal[0] + direction[1] * normal[1];
+                if outwards <= 0.0 {
+                    continue;
+                }
+                let t = ((middle[0] - from[0]) * normal[0] + (middle[1] - from[1]) * normal[1])
+                    / outwards;
+                if t < exit.0 {
+                    exit = (t, edge);
+                }
+            }
+            if exit.0 >= 1.0 {
+                return true;
+            }
+            current = match self.polygon_index(polygon.position.neighbor(exit.1)) {
+                Some(next) => next,
+                None => return false,
+            };
+        }
+        false
+    }
+
+    // The edge shared by two neighbor polygons as (left, right) when going from one to the other.
+    fn portal(&self, from: usize, to: usize) -> ([f32; 2], [f32; 2]) {
+        let (from, to) = (&self.polygons[from], &self.polygons[to]);
+        let direction = (0..NUM_DIRECTIONS)
+            .find(|direction| from.position.neighbor(*direction) == to.position)
+            .expect("neighbor polygons");
+        let a = from.vertices[direction];
+        let b = from.vertices[(direction + 1) % NUM_DIRECTIONS];
+        let center = self.layout.to_point(from.position);
+        if cross(center, self.layout.to_point(to.position), a) > 0.0 {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+}
+
+// Positive when c is on the left of the line from a to b, with the left of the portals.
+fn cross(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+// The funnel algorithm: the funnel from the apex narrows portal after portal, when one side
+// crosses the other the corner it crosses becomes the new apex.
+fn string_pull(portals: &[([f32; 2], [f32; 2])]) -> Vec<[f32; 2]> {
+    let mut path = vec![portals[0].0];
+    let (mut apex, mut left, mut right) = (portals[0].0, portals[0].0, portals[0].1);
+    let (mut left_index, mut right_index) = (0, 0);
+    let mut index = 1;
+    while index < portals.len() {
+        let (portal_left, portal_right) = portals[index];
+        if cross(apex, right, portal_right) >= 0.0 {
+            if apex == right || cross(apex, left, portal_right) < 0.0 {
+                right = portal_right;
+                right_index = index;
+            } else {
+                path.push(left);
+                apex = left;
+                right = apex;
+                right_index = left_index;
+                index = left_index + 1;
+                continue;
+            }
+        }
+        if cross(apex, left, portal_left) <= 0.0 {
+            if apex == left || cross(apex, right, portal_left) > 0.0 {
+                left = portal_left;
+                left_index = index;
+            } else {
+                path.push(right);
+                apex = right;
+                left = apex;
+                left_index = right_index;
+                index = right_index + 1;
+                continue;
+            }
+        }
+        index += 1;
+    }
+    let goal = portals[portals.len() - 1].0;
+    if path.last() != Some(&goal) {
+        path.push(goal);
+    }
+    path
+}
+
+#[cfg(test)]
+fn path_length(path: &[[f32; 2]]) -> f32 {
+    path.windows(2)
+        .map(|pair| (pair[1][0] - pair[0][0]).hypot(pair[1][1] - pair[0][1]))
+        .sum()
+}
+
+#[test]
+fn test_navmesh_polygons() {
+    let layout = HexLayout::pointy_top(2.0);
+    let open = AxialVector::default().spiral_iter(2).collect::<HexSet>();
+    let navmesh = NavMesh::new(&open, layout);
+    assert_eq!(navmesh.polygons().len(), 19);
+    assert!(navmesh
+        .polygons()
+        .windows(2)
+        .all(|pair| pair[0].position() < pair[1].position()));
+    let center = navmesh.polygon_index(AxialVector::default()).unwrap();
+    let polygon = &navmesh.polygons()[center];
+    assert_eq!(polygon.neighbors().len(), 6);
+    assert_eq!(polygon.vertices().len(), 6);
+    for neighbor in polygon.neighbors() {
+        let neighbor = &navmesh.polygons()[*neighbor];
+        assert_eq!(neighbor.position().distance(AxialVector::default()), 1);
+        // Neighbors share an edge.
+        let shared = polygon
+            .vertices()
+            .iter()
+            .filter(|[x, y]| {
+                neighbor
+                    .vertices()
+                    .iter()
+                    .any(|[nx, ny]| (x - nx).abs() < 1e-3 && (y - ny).abs() < 1e-3)
+            })
+            .count();
+        assert_eq!(shared, 2);
+    }
+    let corner = navmesh
+        .polygons()
+        .iter()
+        .find(|polygon| polygon.position() == AxialVector::new(2, 0))
+        .unwrap();
+    assert_eq!(corner.neighbors().len(), 3);
+    assert_eq!(
+        navmesh.locate(layout.to_point(AxialVector::new(1, -1))),
+        navmesh.polygon_index(AxialVector::new(1, -1))
+    );
+    assert_eq!(
+        navmesh.locate(layout.to_point(AxialVector::new(3, 0))),
+        None
+    );
+}
+
+#[test]
+fn test_navmesh_straight_path() {
+    let layout = HexLayout::pointy_top(1.0);
+    let open = AxialVector::default().spiral_iter(6).collect::<HexSet>();
+    let navmesh = NavMesh::new(&open, layout);
+    let start = layout.to_point(AxialVector::new(-4, 1));
+    let goal = [layout.to_point(AxialVector::new(3, 2))[0] + 0.3, 3.0];
+    assert_eq!(navmesh.find_path(start, goal), Some(vec![start, goal]));
+    assert_eq!(navmesh.find_path(start, start), Some(vec![start]));
+    // Between any points of a convex area.
+    let inner = AxialVector::default().spiral_iter(4).collect::<Vec<_>>();
+    for (index, from) in inner.iter().enumerate() {
+        let to = inner[(index * 7 + 3) % inner.len()];
+        let start = layout.to_fractional_point(from.q() as f32 + 0.2, from.r() as f32 - 0.1);
+        let goal = layout.to_fractional_point(to.q() as f32 - 0.3, to.r() as f32 + 0.25);
+        assert_eq!(navmesh.find_path(start, goal), Some(vec![start, goal]));
+    }
+}
+
+#[test]
+fn test_navmesh_path_around_walls() {
+    use crate::hex::coordinates::offset::HexOrientation;
+
+    for orientation in &[HexOrientation::PointyTop, HexOrientation::FlatTop] {
+        let layout = HexLayout::new(*orientation, 1.5, [3.0, -2.0]);
+        // A wall across the map with a gap at one end.
+        let walls = (-6..=3).map(|r| AxialVector::new(0, r)).collect::<HexSet>();
+        let open = AxialVector::default()
+            .spiral_iter(6)
+            .filter(|position| !walls.contains(*position))
+            .collect::<HexSet>();
+        let navmesh = NavMesh::new(&open, layout);
+        let start = layout.to_point(AxialVector::new(-3, 0));
+        let goal = layout.to_point(AxialVector::new(3, 0));
+        let path = navmesh.find_path(start, goal).unwrap();
+        assert_eq!(path[0], start);
+        assert_eq!(path[path.len() - 1], goal);
+        assert!(path.len() >= 3);
+
+        // Turns only at the corners of the obstacles, the walls or the border of the map.
+        let obstacles = AxialVector::default()
+            .spiral_iter(7)
+            .filter(|position| !open.contains(*position))
+            .collect::<Vec<_>>();
+        for point in &path[1..path.len() - 1] {
+            assert!(obstacles.iter().any(|obstacle| {
+                let [x, y] = layout.to_point(*obstacle);
+                (0..6).any(|corner| {
+                    let [dx, dy] = layout.corner_offset(corner);
+                    (point[0] - x - dx).abs() < 1e-3 && (point[1] - y - dy).abs() < 1e-3
+                })
+            }));
+        }
+
+        // Never through an obstacle, at most along its edges.
+        for pair in path.windows(2) {
+            for step in 0..=100 {
+                let t = step as f32 / 100.0;
+                let point = [
+                    pair[0][0] + t * (pair[1][0] - pair[0][0]),
+                    pair[0][1] + t * (pair[1][1] - pair[0][1]),
+                ];
+                let position = layout.from_point(point);
+                if !open.contains(position) {
+                    let [x, y] = layout.to_point(position);
+                    let inner_radius = f32::sqrt(3.0) / 2.0 * layout.size();
+                    assert!((point[0] - x).hypot(point[1] - y) > inner_radius - 1e-3);
+                }
+            }
+        }
+
+        // Shorter than going from hex center to hex center.
+        let corridor = navmesh.corridor(start, goal).unwrap();
+        let centers = corridor
+            .iter()
+            .map(|index| layout.to_point(navmesh.polygons()[*index].position()))
+            .collect::<Vec<_>>();
+        assert!(path_length(&path) < path_length(&centers));
+    }
+}
+
+#[test]
+fn test_navmesh_no_path() {
+    let layout = HexLayout::default();
+    // The goal enclosed in a ring of walls.
+    let open = AxialVector::default()
+        .spiral_iter(5)
+        .filter(|position| position.distance(AxialVector::new(2, 0)) != 1)
+        .collect::<HexSet>();
+    let navmesh = NavMesh::new(&open, layout);
+    let start = layout.to_point(AxialVector::new(-3, 0));
+    assert_eq!(
+        navmesh.find_path(start, layout.to_point(AxialVector::new(2, 0))),
+        None
+    );
+    assert_eq!(
+        navmesh.find_path(start, layout.to_point(AxialVector::new(3, 0))),
+        None
+    );
+    assert_eq!(
+        navmesh.find_path(start, layout.to_point(AxialVector::new(9, 0))),
+        None
+    );
+}
+
+#[test]
+fn test_navmesh_visibility() {
+    let layout = HexLayout::flat_top(1.0);
+    let walls = (-1..=1).map(|q| AxialVector::new(q, 0)).collect::<HexSet>();
+    let open = AxialVector::default()
+        .spiral_iter(4)
+        .filter(|position| !walls.contains(*position))
+        .collect::<HexSet>();
+    let navmesh = NavMesh::new(&open, layout);
+    let above = layout.to_point(AxialVector::new(0, -2));
+    let below = layout.to_point(AxialVector::new(0, 2));
+    assert!(!navmesh.is_visible(above, below));
+    assert!(navmesh.is_visible(above, layout.to_point(AxialVector::new(3, -3))));
+    assert!(navmesh.is_visible(above, above));
+    assert!(!navmesh.is_visible(above, layout.to_point(AxialVector::new(0, 0))));
+    assert!(!navmesh.is_visible(above, layout.to_point(AxialVector::new(0, 6))));
+    // Around the wall.
+    let path = navmesh.find_path(above, below).unwrap();
+    // Along the edges of the west end of the wall.
+    assert_eq!(path.len(), 5);
+}
@@ -0,0 +1,145 @@
+use crate::hex::{coordinates::axial::AxialVector, float::layout::HexLayout};
+
+// Moves along a path in the layout coordinates at constant speed: `t` is the distance travelled
+// from the start, whatever the lengths of the segments. Smoothed hex paths (see
+// reachable::smooth_path) and navigation mesh paths give few long segments, any path works.
+#[derive(Clone, Debug)]
+pub struct PathSampler {
+    points: Vec<[f32; 2]>,
+    // Distance from the start to each point.
+    distances: Vec<f32>,
+}
+
+impl PathSampler {
+    // None without any point. Repeated points are dropped.
+    pub fn new(points: &[[f32; 2]]) -> Option<Self> {
+        let mut sampler = Self {
+            points: vec![*points.first()?],
+            distances: vec![0.0],
+        };
+        for point in &points[1..] {
+            let last = sampler.points[sampler.points.len() - 1];
+            let length = (point[0] - last[0]).hypot(point[1] - last[1]);
+            if length > 0.0 {
+                sampler.points.push(*point);
+                sampler
+                    .distances
+                    .push(sampler.distances[sampler.distances.len() - 1] + length);
+            }
+        }
+        Some(sampler)
+    }
+
+    // From hex center to hex center.
+    pub fn from_hexes(path: &[AxialVector], layout: &HexLayout) -> Option<Self> {
+        Self::new(
+            &path
+                .iter()
+                .map(|position| layout.to_point(*position))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn length(&self) -> f32 {
+        self.distances[self.distances.len() - 1]
+    }
+
+    // The position at the distance t from the start, t being clamped to the path.
+    pub fn position_at(&self, t: f32) -> [f32; 2] {
+        match self.segment_at(t) {
+            Some(segment) => {
+                let [from, to] = [self.points[segment], self.points[segment + 1]];
+                let ratio = (t.clamp(0.0, self.length()) - self.distances[segment])
+                    / (self.distances[segment + 1] - self.distances[segment]);
+                [
+                    from[0] + ratio * (to[0] - from[0]),
+                    from[1] + ratio * (to[1] - from[1]),
+                ]
+            }
+            None => self.points[0],
+        }
+    }
+
+    // The unit direction of the segment at the distance t from the start, the one leaving a
+    // waypoint when exactly on it. Zero for a path of a single point.
+    pub fn direction_at(&self, t: f32) -> [f32; 2] {
+        match self.segment_at(t) {
+            Some(segment) => {
+                let [from, to] = [self.points[segment], self.points[segment + 1]];
+                let length = self.distances[segment + 1] - self.distances[segment];
+                [(to[0] - from[0]) / length, (to[1] - from[1]) / length]
+            }
+            None => [0.0, 0.0],
+        }
+    }
+
+    // The index of the first point of the segment at the distance t, the last segment at the end.
+    fn segment_at(&self, t: f32) -> Option<usize> {
+        let segments = self.points.len().checked_sub(1).filter(|len| *len > 0)?;
+        Some(
+            self.distances
+                .partition_point(|distance| *distance <= t)
+                .clamp(1, segments)
+                - 1,
+        )
+    }
+}
+
+#[cfg(test)]
+fn assert_point_eq(actual: [f32; 2], expected: [f32; 2]) {
+    assert!(
+        (actual[0] - expected[0]).abs() < 1e-4 && (actual[1] - expected[1]).abs() < 1e-4,
+        "{:?} != {:?}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn test_path_sampler() {
+    let sampler =
+        PathSampler::new(&[[0.0, 0.0], [3.0, 4.0], [3.0, 4.0], [3.0, 10.0]]).expect("sampler");
+    assert_eq!(sampler.length(), 11.0);
+    assert_point_eq(sampler.position_at(0.0), [0.0, 0.0]);
+    assert_point_eq(sampler.position_at(2.5), [1.5, 2.0]);
+    assert_point_eq(sampler.position_at(5.0), [3.0, 4.0]);
+    assert_point_eq(sampler.position_at(8.0), [3.0, 7.0]);
+    assert_point_eq(sampler.position_at(11.0), [3.0, 10.0]);
+    // Clamped at both ends.
+    assert_point_eq(sampler.position_at(-1.0), [0.0, 0.0]);
+    assert_point_eq(sampler.position_at(20.0), [3.0, 10.0]);
+
+    assert_point_eq(sampler.direction_at(-1.0), [0.6, 0.8]);
+    assert_point_eq(sampler.direction_at(4.9), [0.6, 0.8]);
+    assert_point_eq(sampler.direction_at(5.0), [0.0, 1.0]);
+    assert_point_eq(sampler.direction_at(20.0), [0.0, 1.0]);
+
+    let still = PathSampler::new(&[[1.0, 2.0], [1.0, 2.0]]).expect("sampler");
+    assert_eq!(still.length(), 0.0);
+    assert_eq!(still.position_at(1.0), [1.0, 2.0]);
+    assert_eq!(still.direction_at(1.0), [0.0, 0.0]);
+    assert!(PathSampler::new(&[]).is_none());
+}
+
+#[test]
+fn test_path_sampler_from_hexes() {
+    let layout = HexLayout::pointy_top(2.0);
+    let path = [
+        AxialVector::new(0, 0),
+        AxialVector::new(2, 0),
+        AxialVector::new(2, 2),
+    ];
+    let sampler = PathSampler::from_hexes(&path, &layout).expect("sampler");
+    // Two hexes away is twice the distance between the centers of neighbors.
+    let step = f32::sqrt(3.0) * layout.size();
+    assert!((sampler.length() - 4.0 * step).abs() < 1e-4);
+    assert_point_eq(
+        sampler.position_at(step),
+        layout.to_point(AxialVector::new(1, 0)),
+    );
+    assert_point_eq(
+        sampler.position_at(3.0 * step),
+        layout.to_point(AxialVector::new(2, 1)),
+    );
+    assert_point_eq(sampler.direction_at(0.0), [1.0, 0.0]);
+}
@@ -0,0 +1,39 @@
+use crate::hex::{coordinates::axial::AxialVector, storage::set::HexSet};
+
+// The open hexes connected to the start, or None if there are more than `limit` of them, which
+// usually means that the area is not enclosed.
+pub fn flood_fill<F>(start: AxialVector, limit: usize, is_open: F) -> Option<HexSet>
+where
+    F: Fn(AxialVector) -> bool,
+{
+    let mut filled = HexSet::new();
+    if !is_open(start) {
+        return Some(filled);
+    }
+    filled.insert(start);
+    let mut pending = vec![start];
+    while let Some(position) = pending.pop() {
+        for neighbor in position.ring_iter(1) {
+            if is_open(neighbor) && filled.insert(neighbor) {
+                if filled.len() > limit {
+                    return None;
+                }
+                pending.push(neighbor);
+            }
+        }
+    }
+    Some(filled)
+}
+
+#[test]
+fn test_flood_fill() {
+    let center = AxialVector::new(4, -2);
+    let is_open = |position: AxialVector| position.distance(center) != 5;
+    let filled = flood_fill(center, 1000, is_open).expect("enclosed");
+    assert_eq!(filled, center.spiral_iter(4).collect::<HexSet>());
+    assert!(flood_fill(AxialVector::new(15, -6), 1000, is_open).is_none());
+    assert_eq!(flood_fill(center, 60, is_open), None);
+    assert!(flood_fill(center + AxialVector::new(5, 0), 1000, is_open)
+        .expect("wall")
+        .is_empty());
+}
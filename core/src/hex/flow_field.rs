@@ -0,0 +1,132 @@
+use crate::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    reachable::MovementCost,
+    storage::hash::RectHashStorage,
+};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+// The cheapest way to the closest goal from every hex, computed once for any number of agents
+// heading to the same goals: each hex points to its next step, the goals to nothing. The field is
+// grown backwards from the goals, the costs being the ones of the steps the agents take towards
+// them, and stops at the budget. The steps out of the hexes the agents never stand on, like walls,
+// are to be refused by the cost too, or these hexes get in the field.
+pub struct FlowField {
+    // The cost to the closest goal and the direction of the next step.
+    hexes: RectHashStorage<(usize, Option<usize>)>,
+}
+
+impl FlowField {
+    pub fn new<G, C>(goals: G, budget: usize, cost: C) -> Self
+    where
+        G: IntoIterator<Item = AxialVector>,
+        C: MovementCost,
+    {
+        let _span = crate::timed_span!("flow_field", budget);
+        let mut hexes = RectHashStorage::new();
+        let mut pending = BinaryHeap::new();
+        for goal in goals {
+            hexes.insert(goal, (0, None));
+            pending.push(Reverse((0, goal.q(), goal.r())));
+        }
+        while let Some(Reverse((spent, q, r))) = pending.pop() {
+            let position = AxialVector::new(q, r);
+            if hexes.get(position).map(|(best, _)| *best) != Some(spent) {
+                continue;
+            }
+            for direction in 0..NUM_DIRECTIONS {
+                let neighbor = position.neighbor(direction);
+                let total = match cost.cost(neighbor, position) {
+                    Some(step) if spent + step <= budget => spent + step,
+                    _ => continue,
+                };
+                if !matches!(hexes.get(neighbor), Some((previous, _)) if *previous <= total) {
+                    // Back the way the field came.
+                    let next = (direction + NUM_DIRECTIONS / 2) % NUM_DIRECTIONS;
+                    hexes.insert(neighbor, (total, Some(next)));
+                    pending.push(Reverse((total, neighbor.q(), neighbor.r())));
+                }
+            }
+        }
+        Self { hexes }
+    }
+
+    // The number of hexes from which a goal is reached, the goals included.
+    pub fn len(&self) -> usize {
+        self.hexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hexes.is_empty()
+    }
+
+    // None out of the field.
+    pub fn cost(&self, position: AxialVector) -> Option<usize> {
+        self.hexes.get(position).map(|(cost, _)| *cost)
+    }
+
+    // None on a goal and out of the field.
+    pub fn direction(&self, position: AxialVector) -> Option<usize> {
+        self.hexes
+            .get(position)
+            .and_then(|(_, direction)| *direction)
+    }
+
+    pub fn next(&self, position: AxialVector) -> Option<AxialVector> {
+        self.direction(position)
+            .map(|direction| position.neighbor(direction))
+    }
+
+    // Down the field to a goal, both ends included, None out of the field.
+    pub fn path(&self, start: AxialVector) -> Option<Vec<AxialVector>> {
+        self.cost(start)?;
+        let mut path = vec![start];
+        let mut current = start;
+        while let Some(next) = self.next(current) {
+            path.push(next);
+            current = next;
+        }
+        Some(path)
+    }
+}
+
+#[test]
+fn test_flow_field() {
+    let goals = [AxialVector::new(-4, 0), AxialVector::new(4, 0)];
+    let walls = (-3..=3).map(|r| AxialVector::new(2, r)).collect::<Vec<_>>();
+    let blocked =
+        |hex: AxialVector| walls.contains(&hex) || hex.distance(AxialVector::default()) > 6;
+    let cost = |from: AxialVector, to: AxialVector| {
+        if blocked(from) || blocked(to) {
+            None
+        } else {
+            Some(1)
+        }
+    };
+    let field = FlowField::new(goals.iter().copied(), 100, cost);
+    assert_eq!(field.len(), 127 - walls.len());
+    for goal in &goals {
+        assert_eq!(field.cost(*goal), Some(0));
+        assert_eq!(field.direction(*goal), None);
+    }
+    // Every step goes down the field to the closest goal.
+    for (position, (spent, _)) in field.hexes.iter() {
+        let path = field.path(position).expect("path");
+        assert_eq!(path.len(), spent + 1);
+        assert!(goals.contains(&path[path.len() - 1]));
+        assert!(path.windows(2).all(|pair| pair[0].distance(pair[1]) == 1
+            && field.cost(pair[1]).unwrap() + 1 == field.cost(pair[0]).unwrap()));
+        assert!(path.iter().all(|hex| !walls.contains(hex)));
+    }
+    // Each side of the wall has its own closest goal.
+    assert_eq!(field.cost(AxialVector::new(3, 0)), Some(1));
+    assert_eq!(field.cost(AxialVector::new(1, 0)), Some(5));
+    assert_eq!(field.cost(walls[0]), None);
+    assert_eq!(field.path(walls[0]), None);
+
+    let short = FlowField::new(goals.iter().copied(), 1, cost);
+    assert_eq!(short.len(), 2 + 6 + 6);
+    assert!(FlowField::new(Vec::new(), 10, cost).is_empty());
+}
@@ -0,0 +1,142 @@
+use crate::{
+    hex::coordinates::{axial::AxialVector, cubic::CubicVector},
+    vector::Vector2ISize,
+};
+use std::cmp::Ordering;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Turn {
+    Left,
+    Straight,
+    Right,
+}
+
+// A point of the hex plane scaled so that both the hex centers and the hex corners have integral
+// coordinates: x = 2q + r, y = -3r (the y axis going the other way than r), HEX_PLANE_VERTICES
+// being the corners around the origin. Turns, dot products and cones are then computed exactly,
+// without any rounding, as long as the products fit in an isize. The field of view relies on them,
+// so tests based on them are consistent with what it sees.
+#[derive(PartialEq, Eq, Clone, Copy, Add, AddAssign, Sub, SubAssign, Debug)]
+pub struct VertexVector(pub(crate) Vector2ISize);
+
+impl VertexVector {
+    pub fn new(x: isize, y: isize) -> Self {
+        Self(Vector2ISize { x, y })
+    }
+
+    pub fn x(&self) -> isize {
+        self.0.x
+    }
+
+    pub fn y(&self) -> isize {
+        self.0.y
+    }
+
+    // The sign of the cross product, whether other turns left or right of self as seen from the
+    // origin.
+    pub fn turns(&self, other: &VertexVector) -> Turn {
+        let cross = self.0.x * other.0.y - self.0.y * other.0.x;
+        match cross.cmp(&0) {
+            Ordering::Greater => Turn::Left,
+            Ordering::Less => Turn::Right,
+            Ordering::Equal => Turn::Straight,
+        }
+    }
+
+    pub fn dot(&self, other: &VertexVector) -> isize {
+        self.0.x * other.0.x + self.0.y * other.0.y
+    }
+
+    // Whether self lies in the cone going from start to stop, start included but not stop, the
+    // cone being the narrower one (start and stop must not be opposite).
+    pub fn is_in_cone(&self, start: &VertexVector, stop: &VertexVector) -> bool {
+        let side = start.turns(stop);
+        let turn = start.turns(self);
+        (turn == side || (turn == Turn::Straight && start.dot(self) > 0))
+            && self.turns(stop) == side
+    }
+}
+
+pub const HEX_PLANE_VERTICES: [VertexVector; 6] = [
+    VertexVector(Vector2ISize { x: 1, y: -1 }),
+    VertexVector(Vector2ISize { x: 1, y: 1 }),
+    VertexVector(Vector2ISize { x: 0, y: 2 }),
+    VertexVector(Vector2ISize { x: -1, y: 1 }),
+    VertexVector(Vector2ISize { x: -1, y: -1 }),
+    VertexVector(Vector2ISize { x: 0, y: -2 }),
+];
+
+impl From<AxialVector> for VertexVector {
+    fn from(axial: AxialVector) -> Self {
+        VertexVector(Vector2ISize {
+            x: 2 * axial.q() + axial.r(),
+            y: -3 * axial.r(),
+        })
+    }
+}
+
+impl From<CubicVector> for VertexVector {
+    fn from(cubic: CubicVector) -> Self {
+        VertexVector(Vector2ISize {
+            x: 2 * cubic.x() + cubic.z(),
+            y: -3 * cubic.z(),
+        })
+    }
+}
+
+// Whether the polygon turns the same way at every vertex, straight angles and repeated vertices
+// being allowed.
+pub fn is_convex(polygon: &[VertexVector]) -> bool {
+    let mut side = Turn::Straight;
+    for (index, vertex) in polygon.iter().enumerate() {
+        let next = polygon[(index + 1) % polygon.len()];
+        let after = polygon[(index + 2) % polygon.len()];
+        match (next - *vertex).turns(&(after - next)) {
+            Turn::Straight => {}
+            turn if side == Turn::Straight => side = turn,
+            turn if turn != side => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+#[test]
+fn test_vertex_vector_turns() {
+    let east = VertexVector::from(AxialVector::new(1, 0));
+    assert_eq!((east.x(), east.y()), (2, 0));
+    let north = VertexVector::new(0, 2);
+    assert_eq!(east.turns(&north), Turn::Left);
+    assert_eq!(north.turns(&east), Turn::Right);
+    assert_eq!(east.turns(&VertexVector::new(-4, 0)), Turn::Straight);
+    assert_eq!(east.dot(&VertexVector::new(-4, 0)), -8);
+    assert_eq!(
+        VertexVector::from(CubicVector::from(AxialVector::new(-3, 2))),
+        VertexVector::from(AxialVector::new(-3, 2))
+    );
+
+    assert!(east.is_in_cone(&east, &north));
+    assert!(!north.is_in_cone(&east, &north));
+    assert!(VertexVector::new(1, 1).is_in_cone(&east, &north));
+    assert!(VertexVector::new(1, 1).is_in_cone(&north, &east));
+    assert!(!VertexVector::new(-2, 0).is_in_cone(&east, &north));
+    assert!(!VertexVector::new(1, -1).is_in_cone(&east, &north));
+}
+
+#[test]
+fn test_is_convex() {
+    assert!(is_convex(&HEX_PLANE_VERTICES));
+    let mut reversed = HEX_PLANE_VERTICES;
+    reversed.reverse();
+    assert!(is_convex(&reversed));
+    let mut dented = HEX_PLANE_VERTICES.to_vec();
+    dented[2] = VertexVector::new(0, 0);
+    assert!(!is_convex(&dented));
+    // A straight angle in the middle of a side.
+    assert!(is_convex(&[
+        VertexVector::new(0, 0),
+        VertexVector::new(2, 0),
+        VertexVector::new(4, 0),
+        VertexVector::new(2, 2),
+    ]));
+}
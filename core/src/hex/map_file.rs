@@ -0,0 +1,730 @@
+// The `.rhmap` file format, meant to be read and written by other tools and by later versions of
+// the crates: it does not depend on how the storages lay out their hexes in memory. Everything is
+// little endian, a string is its u32 length followed by as many bytes of UTF-8, a shape its six
+// i64 bounds (start and end of x, then y, then z) and a position its q and r as i64.
+//
+// - The magic `RHMP` and the u16 version, which readers only accept up to their own.
+// - The layout: the orientation as u8 (0 pointy top, 1 flat top), then the size and the origin
+//   x and y as f32.
+// - A u8 flag, then the shape of the map if it is 1.
+// - The u32 number of layers, then each layer: its name, the u32 size of the value of each hex
+//   (0xffff_ffff if they vary), the u64 length of its data, the data, then the CRC-32 (IEEE, as in
+//   zlib) of the data. A reader skips the layers it does not know, and the values it does not know
+//   when they have a size. The data is the u64 number of chunks, then each chunk: its x and y as
+//   i64, the u64 mask of its hexes then the value of each hex in the order of the mask bits. A
+//   chunk covers the hexes from (8x, 8y) to (8x + 7, 8y + 7), bit dq + 8 dr being set for the hex
+//   (8x + dq, 8y + dr). The chunks are sorted by y, then x.
+// - A u8 flag, then the dungeon metadata if it is 1: the generator, the u64 seed, the u32 number of
+//   rooms then their shapes and the u32 number of doors then their positions.
+//
+// Version 1 had neither the size of the values nor the checksum of the layers.
+use crate::hex::{
+    coordinates::axial::AxialVector,
+    float::layout::HexLayout,
+    shape::cubic_range::{CubicRangeShape, Range},
+    storage::{
+        binary::{invalid_data, BinaryHex},
+        hash::RectHashStorage,
+        set::HexSet,
+    },
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    hash::BuildHasher,
+    io::{Error, ErrorKind, Read, Result, Write},
+};
+
+pub const MAP_FILE_EXTENSION: &str = "rhmap";
+pub const MAP_FILE_MAGIC: [u8; 4] = *b"RHMP";
+pub const MAP_FILE_VERSION: u16 = 2;
+
+const CHUNK_LEN: isize = 8;
+// Larger coordinates are taken for a corrupted file, the hexes of the chunks still fit in isize.
+const MAX_COORDINATE: i64 = (isize::MAX / (4 * CHUNK_LEN)) as i64;
+const MAX_STRING_LEN: u32 = 1 << 16;
+const VARYING_VALUE_SIZE: u32 = u32::MAX;
+
+// What a generator knows of the dungeon beyond its hexes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DungeonMetadata {
+    pub generator: String,
+    pub seed: u64,
+    pub rooms: Vec<CubicRangeShape>,
+    pub doors: Vec<AxialVector>,
+}
+
+#[derive(Clone, Debug)]
+struct Layer {
+    name: String,
+    // None if the values vary in size.
+    value_size: Option<u32>,
+    // Encoded as in the file, decoded on demand.
+    data: Vec<u8>,
+    // The checksum read did not match.
+    corrupted: bool,
+}
+
+impl Layer {
+    fn check(&self) -> Result<()> {
+        if self.corrupted {
+            Err(invalid_data(format!("Corrupted layer {}", self.name)))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// A map as saved in a `.rhmap` file: named layers of hexes (terrain, walls, items...), each with
+// its own type of values, along with how to draw them and where they come from.
+#[derive(Clone, Debug)]
+pub struct MapFile {
+    pub layout: HexLayout,
+    pub shape: Option<CubicRangeShape>,
+    pub dungeon: Option<DungeonMetadata>,
+    version: u16,
+    layers: Vec<Layer>,
+}
+
+impl MapFile {
+    pub fn new(layout: HexLayout) -> Self {
+        Self {
+            layout,
+            shape: None,
+            dungeon: None,
+            version: MAP_FILE_VERSION,
+            layers: Vec::new(),
+        }
+    }
+
+    // The version of the file the map was read from, it is always written in the current one.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    // In the order they were first set.
+    pub fn layer_names(&self) -> impl Iterator<Item = &str> {
+        self.layers.iter().map(|layer| layer.name.as_str())
+    }
+
+    pub fn has_layer(&self, name: &str) -> bool {
+        self.find_layer(name).is_some()
+    }
+
+    // The size of the value of each hex of the layer, None if there is no such layer or if the
+    // values vary in size.
+    pub fn value_size(&self, name: &str) -> Option<u32> {
+        self.find_layer(name).and_then(|layer| layer.value_size)
+    }
+
+    // The layers whose checksum does not match, they cannot be read.
+    pub fn verify(&self) -> Vec<&str> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.corrupted)
+            .map(|layer| layer.name.as_str())
+            .collect()
+    }
+
+    // Replaces the layer of the same name.
+    pub fn set_layer<H: BinaryHex, S: BuildHasher>(
+        &mut self,
+        name: &str,
+        hexes: &RectHashStorage<H, S>,
+    ) -> Result<()> {
+        let (data, value_size) = encode_layer(hexes)?;
+        let layer = Layer {
+            name: name.to_owned(),
+            value_size,
+            data,
+            corrupted: false,
+        };
+        match self.layers.iter_mut().find(|layer| layer.name == name) {
+            Some(replaced) => *replaced = layer,
+            None => self.layers.push(layer),
+        }
+        Ok(())
+    }
+
+    // None if there is no such layer, an error if its values are not of that type.
+    pub fn layer<H: BinaryHex, S: BuildHasher + Default>(
+        &self,
+        name: &str,
+    ) -> Result<Option<RectHashStorage<H, S>>> {
+        match self.find_layer(name) {
+            Some(layer) => {
+                layer.check()?;
+                let mut hexes = RectHashStorage::with_hasher(S::default());
+                decode_chunks(&layer.data, |position, data| {
+                    hexes.insert(position, H::read_hex(data)?);
+                    Ok(())
+                })?;
+                Ok(Some(hexes))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // The hexes of a layer whatever the type of its values, as long as they have a size.
+    pub fn layer_positions(&self, name: &str) -> Result<Option<HexSet>> {
+        match self.find_layer(name) {
+            Some(layer) => {
+                layer.check()?;
+                let value_size = layer.value_size.ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("The values of layer {} vary in size", name),
+                    )
+                })? as usize;
+                let mut positions = HexSet::new();
+                decode_chunks(&layer.data, |position, data| {
+                    if data.len() < value_size {
+                        return Err(Error::new(
+                            ErrorKind::UnexpectedEof,
+                            format!("Truncated value in layer {}", name),
+                        ));
+                    }
+                    *data = &data[value_size..];
+                    positions.insert(position);
+                    Ok(())
+                })?;
+                Ok(Some(positions))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn remove_layer(&mut self, name: &str) -> bool {
+        let len = self.layers.len();
+        self.layers.retain(|layer| layer.name != name);
+        self.layers.len() != len
+    }
+
+    fn find_layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&MAP_FILE_MAGIC)?;
+        MAP_FILE_VERSION.write_hex(writer)?;
+        self.layout.write_hex(writer)?;
+        match &self.shape {
+            Some(shape) => {
+                true.write_hex(writer)?;
+                write_shape(writer, shape)?;
+            }
+            None => false.write_hex(writer)?,
+        }
+        write_count(writer, self.layers.len(), "layers")?;
+        for layer in &self.layers {
+            write_string(writer, &layer.name)?;
+            layer
+                .value_size
+                .unwrap_or(VARYING_VALUE_SIZE)
+                .write_hex(writer)?;
+            (layer.data.len() as u64).write_hex(writer)?;
+            writer.write_all(&layer.data)?;
+            crc32(&layer.data).write_hex(writer)?;
+        }
+        match &self.dungeon {
+            Some(dungeon) => {
+                true.write_hex(writer)?;
+                write_string(writer, &dungeon.generator)?;
+                dungeon.seed.write_hex(writer)?;
+                write_count(writer, dungeon.rooms.len(), "rooms")?;
+                for room in &dungeon.rooms {
+                    write_shape(writer, room)?;
+                }
+                write_count(writer, dungeon.doors.len(), "doors")?;
+                for door in &dungeon.doors {
+                    write_position(writer, *door)?;
+                }
+            }
+            None => false.write_hex(writer)?,
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAP_FILE_MAGIC {
+            return Err(invalid_data("Not a map file".to_owned()));
+        }
+        let version = u16::read_hex(reader)?;
+        if version == 0 || version > MAP_FILE_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported map file version {} (up to {})",
+                version, MAP_FILE_VERSION
+            )));
+        }
+        let mut map = Self::new(HexLayout::read_hex(reader)?);
+        map.version = version;
+        if bool::read_hex(reader)? {
+            map.shape = Some(read_shape(reader)?);
+        }
+        for _ in 0..u32::read_hex(reader)? {
+            let name = read_string(reader)?;
+            let value_size = if version >= 2 {
+                Some(u32::read_hex(reader)?).filter(|size| *size != VARYING_VALUE_SIZE)
+            } else {
+                None
+            };
+            let len = u64::read_hex(reader)?;
+            let mut data = Vec::new();
+            reader.by_ref().take(len).read_to_end(&mut data)?;
+            if data.len() as u64 != len {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!("Truncated layer {}", name),
+                ));
+            }
+            let corrupted = version >= 2 && u32::read_hex(reader)? != crc32(&data);
+            if map.has_layer(&name) {
+                return Err(invalid_data(format!("Duplicate layer {}", name)));
+            }
+            map.layers.push(Layer {
+                name,
+                value_size,
+                data,
+                corrupted,
+            });
+        }
+        if bool::read_hex(reader)? {
+            let generator = read_string(reader)?;
+            let seed = u64::read_hex(reader)?;
+            let mut rooms = Vec::new();
+            for _ in 0..u32::read_hex(reader)? {
+                rooms.push(read_shape(reader)?);
+            }
+            let mut doors = Vec::new();
+            for _ in 0..u32::read_hex(reader)? {
+                doors.push(read_position(reader)?);
+            }
+            map.dungeon = Some(DungeonMetadata {
+                generator,
+                seed,
+                rooms,
+                doors,
+            });
+        }
+        Ok(map)
+    }
+}
+
+// Along with the size of the values if they all have the same.
+fn encode_layer<H: BinaryHex, S: BuildHasher>(
+    hexes: &RectHashStorage<H, S>,
+) -> Result<(Vec<u8>, Option<u32>)> {
+    let mut chunks = BTreeMap::<(isize, isize), Vec<(usize, &H)>>::new();
+    for (position, hex) in hexes.iter() {
+        let chunk = (
+            position.r().div_euclid(CHUNK_LEN),
+            position.q().div_euclid(CHUNK_LEN),
+        );
+        let bit = (position.q().rem_euclid(CHUNK_LEN)
+            + position.r().rem_euclid(CHUNK_LEN) * CHUNK_LEN) as usize;
+        chunks.entry(chunk).or_default().push((bit, hex));
+    }
+    let mut data = Vec::new();
+    let mut value_size = Some(None);
+    (chunks.len() as u64).write_hex(&mut data)?;
+    for ((y, x), mut chunk_hexes) in chunks {
+        chunk_hexes.sort_unstable_by_key(|(bit, _)| *bit);
+        write_coordinate(&mut data, x)?;
+        write_coordinate(&mut data, y)?;
+        let mask = chunk_hexes
+            .iter()
+            .fold(0u64, |mask, (bit, _)| mask | 1 << bit);
+        mask.write_hex(&mut data)?;
+        for (_, hex) in chunk_hexes {
+            let start = data.len();
+            hex.write_hex(&mut data)?;
+            let size = (data.len() - start) as u32;
+            value_size = match value_size {
+                Some(None) => Some(Some(size)),
+                Some(Some(previous)) if previous == size => value_size,
+                _ => None,
+            };
+        }
+    }
+    Ok((data, value_size.map(|size| size.unwrap_or(0))))
+}
+
+// Calls read_value for each hex, which reads its value from the data.
+fn decode_chunks<F>(mut data: &[u8], mut read_value: F) -> Result<()>
+where
+    F: FnMut(AxialVector, &mut &[u8]) -> Result<()>,
+{
+    let mut chunks = Vec::new();
+    for _ in 0..u64::read_hex(&mut data)? {
+        let x = read_coordinate(&mut data)?;
+        let y = read_coordinate(&mut data)?;
+        let mut mask = u64::read_hex(&mut data)?;
+        chunks.push((x, y));
+        while mask != 0 {
+            let bit = mask.trailing_zeros() as isize;
+            mask &= mask - 1;
+            let position = AxialVector::new(
+                x * CHUNK_LEN + bit % CHUNK_LEN,
+                y * CHUNK_LEN + bit / CHUNK_LEN,
+            );
+            read_value(position, &mut data)?;
+        }
+    }
+    if !data.is_empty() {
+        return Err(invalid_data(format!(
+            "{} bytes left after the hexes of the layer",
+            data.len()
+        )));
+    }
+    chunks.sort_unstable();
+    if chunks.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(invalid_data("Duplicate chunk".to_owned()));
+    }
+    Ok(())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & 0u32.wrapping_sub(crc & 1));
+        }
+    }
+    !crc
+}
+
+// The writer refuses what the reader would, so that every written file can be read back.
+fn write_string<W: Write>(writer: &mut W, string: &str) -> Result<()> {
+    if string.len() > MAX_STRING_LEN as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("String of {} bytes", string.len()),
+        ));
+    }
+    (string.len() as u32).write_hex(writer)?;
+    writer.write_all(string.as_bytes())
+}
+
+fn write_count<W: Write>(writer: &mut W, count: usize, what: &str) -> Result<()> {
+    u32::try_from(count)
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("{} {}", count, what)))?
+        .write_hex(writer)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let len = u32::read_hex(reader)?;
+    if len > MAX_STRING_LEN {
+        return Err(invalid_data(format!("String of {} bytes", len)));
+    }
+    let mut bytes = vec![0; len as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|err| invalid_data(err.to_string()))
+}
+
+fn write_coordinate<W: Write>(writer: &mut W, value: isize) -> Result<()> {
+    let value = value as i64;
+    if !matches!(value.checked_abs(), Some(abs) if abs <= MAX_COORDINATE) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Coordinate {} out of bounds", value),
+        ));
+    }
+    value.write_hex(writer)
+}
+
+fn read_coordinate<R: Read>(reader: &mut R) -> Result<isize> {
+    let value = i64::read_hex(reader)?;
+    // i64::MIN has no absolute value.
+    match value.checked_abs() {
+        Some(abs) if abs <= MAX_COORDINATE => Ok(value as isize),
+        _ => Err(invalid_data(format!("Coordinate {} out of bounds", value))),
+    }
+}
+
+fn write_position<W: Write>(writer: &mut W, position: AxialVector) -> Result<()> {
+    write_coordinate(writer, position.q())?;
+    write_coordinate(writer, position.r())
+}
+
+fn read_position<R: Read>(reader: &mut R) -> Result<AxialVector> {
+    let q = read_coordinate(reader)?;
+    let r = read_coordinate(reader)?;
+    Ok(AxialVector::new(q, r))
+}
+
+fn write_shape<W: Write>(writer: &mut W, shape: &CubicRangeShape) -> Result<()> {
+    for range in &[shape.range_x(), shape.range_y(), shape.range_z()] {
+        write_coordinate(writer, range.start())?;
+        write_coordinate(writer, range.end())?;
+    }
+    Ok(())
+}
+
+fn read_shape<R: Read>(reader: &mut R) -> Result<CubicRangeShape> {
+    let mut ranges = [Range::from((0, 0)); 3];
+    for range in ranges.iter_mut() {
+        let start = read_coordinate(reader)?;
+        let end = read_coordinate(reader)?;
+        *range = Range::from((start, end));
+    }
+    if !CubicRangeShape::are_ranges_valid(&ranges[0], &ranges[1], &ranges[2]) {
+        return Err(invalid_data("Invalid shape".to_owned()));
+    }
+    Ok(CubicRangeShape::new(ranges[0], ranges[1], ranges[2]))
+}
+
+#[cfg(test)]
+use crate::hex::storage::hash::HexBuildHasher;
+
+#[test]
+fn test_map_file_round_trip() {
+    let mut walls = RectHashStorage::<(), _>::new();
+    let mut heights = RectHashStorage::<u16, _>::new();
+    for (i, position) in AxialVector::new(-5, 3).spiral_iter(12).enumerate() {
+        heights.insert(position, i as u16);
+        if i % 3 == 0 {
+            walls.insert(position, ());
+        }
+    }
+    let mut map = MapFile::new(HexLayout::flat_top(2.5));
+    map.shape = Some(CubicRangeShape::hexagon(AxialVector::new(-5, 3), 12));
+    map.set_layer("heights", &RectHashStorage::<u16, _>::new())
+        .unwrap();
+    map.set_layer("walls", &walls).unwrap();
+    // Replaced in place.
+    map.set_layer("heights", &heights).unwrap();
+    map.dungeon = Some(DungeonMetadata {
+        generator: "rooms and mazes".to_owned(),
+        seed: 42,
+        rooms: vec![CubicRangeShape::default()],
+        doors: vec![AxialVector::new(2, -1), AxialVector::new(-7, 0)],
+    });
+
+    let mut buffer = Vec::new();
+    map.write_to(&mut buffer).unwrap();
+    let read = MapFile::read_from(&mut buffer.as_slice()).unwrap();
+    assert_eq!(read.layout, map.layout);
+    assert_eq!(read.shape, map.shape);
+    assert_eq!(read.dungeon, map.dungeon);
+    assert_eq!(
+        read.layer_names().collect::<Vec<_>>(),
+        vec!["heights", "walls"]
+    );
+    let read_heights: RectHashStorage<u16> = read.layer("heights").unwrap().unwrap();
+    crate::assert_storage_eq!(read_heights, heights);
+    let read_walls: RectHashStorage<()> = read.layer("walls").unwrap().unwrap();
+    crate::assert_storage_eq!(read_walls, walls);
+    let items: Option<RectHashStorage<()>> = read.layer("items").unwrap();
+    assert!(items.is_none());
+    // The values of the walls are not u16.
+    assert!(read.layer::<u16, HexBuildHasher>("walls").is_err());
+    assert_eq!(read.value_size("heights"), Some(2));
+    assert_eq!(read.value_size("walls"), Some(0));
+    assert_eq!(
+        read.layer_positions("heights").unwrap().unwrap(),
+        AxialVector::new(-5, 3).spiral_iter(12).collect::<HexSet>()
+    );
+
+    let mut map = read;
+    assert!(map.remove_layer("heights"));
+    assert!(!map.remove_layer("heights"));
+    map.shape = None;
+    map.dungeon = None;
+    let mut buffer = Vec::new();
+    map.write_to(&mut buffer).unwrap();
+    let read = MapFile::read_from(&mut buffer.as_slice()).unwrap();
+    assert_eq!(read.shape, None);
+    assert_eq!(read.dungeon, None);
+    assert_eq!(read.layer_names().collect::<Vec<_>>(), vec!["walls"]);
+}
+
+#[test]
+fn test_map_file_bytes() {
+    // Changing any of these bytes breaks the files already saved: a new version is needed.
+    let mut hexes = RectHashStorage::<u8, _>::new();
+    hexes.insert(AxialVector::new(9, -1), 7);
+    hexes.insert(AxialVector::new(0, 0), 3);
+    let mut map = MapFile::new(HexLayout::pointy_top(1.0));
+    map.set_layer("a", &hexes).unwrap();
+    let mut buffer = Vec::new();
+    map.write_to(&mut buffer).unwrap();
+
+    let header = |version: u8| {
+        let mut header = b"RHMP".to_vec();
+        header.extend_from_slice(&[version, 0]);
+        header.push(0);
+        header.extend_from_slice(&[0, 0, 0x80, 0x3f]);
+        header.extend_from_slice(&[0; 8]);
+        header.push(0);
+        header.extend_from_slice(&[1, 0, 0, 0]);
+        header.extend_from_slice(&[1, 0, 0, 0, b'a']);
+        header
+    };
+    let mut layer = Vec::new();
+    layer.extend_from_slice(&2u64.to_le_bytes());
+    // The chunk of (9, -1) comes first, on the row above.
+    layer.extend_from_slice(&1i64.to_le_bytes());
+    layer.extend_from_slice(&(-1i64).to_le_bytes());
+    layer.extend_from_slice(&(1u64 << (1 + 7 * 8)).to_le_bytes());
+    layer.push(7);
+    layer.extend_from_slice(&[0; 16]);
+    layer.extend_from_slice(&1u64.to_le_bytes());
+    layer.push(3);
+    let mut expected = header(2);
+    expected.extend_from_slice(&1u32.to_le_bytes());
+    expected.extend_from_slice(&(layer.len() as u64).to_le_bytes());
+    expected.extend_from_slice(&layer);
+    expected.extend_from_slice(&crc32(&layer).to_le_bytes());
+    expected.push(0);
+    assert_eq!(buffer, expected);
+
+    // The version 1 files are still read, then written in the current version.
+    let mut version_1 = header(1);
+    version_1.extend_from_slice(&(layer.len() as u64).to_le_bytes());
+    version_1.extend_from_slice(&layer);
+    version_1.push(0);
+    let map = MapFile::read_from(&mut version_1.as_slice()).unwrap();
+    assert_eq!(map.version(), 1);
+    let read: RectHashStorage<u8> = map.layer("a").unwrap().unwrap();
+    crate::assert_storage_eq!(read, hexes);
+    // Without the size of the values.
+    assert_eq!(map.value_size("a"), None);
+    assert!(map.layer_positions("a").is_err());
+    let mut buffer = Vec::new();
+    map.write_to(&mut buffer).unwrap();
+    let map = MapFile::read_from(&mut buffer.as_slice()).unwrap();
+    assert_eq!(map.version(), MAP_FILE_VERSION);
+    assert!(map.verify().is_empty());
+    let read: RectHashStorage<u8> = map.layer("a").unwrap().unwrap();
+    crate::assert_storage_eq!(read, hexes);
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn test_map_file_should_report_corrupted_layers() {
+    let center = AxialVector::new(3, 4);
+    let mut hexes = RectHashStorage::<u8, _>::new();
+    hexes.insert(center, 7);
+    let mut others = RectHashStorage::<(), _>::new();
+    for position in center.ring_iter(2) {
+        others.insert(position, ());
+    }
+    let mut map = MapFile::new(HexLayout::default());
+    map.set_layer("a", &hexes).unwrap();
+    map.set_layer("b", &others).unwrap();
+    let mut buffer = Vec::new();
+    map.write_to(&mut buffer).unwrap();
+    // The value of the single hex of the layer a, after the header, the layer header, the number
+    // of chunks and the chunk header.
+    let value = 24 + 5 + 4 + 8 + 8 + 24;
+    assert_eq!(buffer[value], 7);
+    buffer[value] = 8;
+
+    let map = MapFile::read_from(&mut buffer.as_slice()).unwrap();
+    assert_eq!(map.verify(), vec!["a"]);
+    assert_eq!(
+        map.layer::<u8, HexBuildHasher>("a")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::InvalidData)
+    );
+    assert!(map.layer_positions("a").is_err());
+    assert_eq!(
+        map.layer_positions("b").unwrap().unwrap(),
+        center.ring_iter(2).collect::<HexSet>()
+    );
+}
+
+#[test]
+fn test_map_file_should_reject_invalid_files() {
+    let mut buffer = Vec::new();
+    MapFile::new(HexLayout::pointy_top(1.0))
+        .write_to(&mut buffer)
+        .unwrap();
+    assert!(MapFile::read_from(&mut buffer.as_slice()).is_ok());
+
+    let mut newer = buffer.clone();
+    newer[4] = MAP_FILE_VERSION as u8 + 1;
+    let mut zero = buffer.clone();
+    zero[4] = 0;
+    let mut magic = buffer.clone();
+    magic[0] = b'X';
+    let mut orientation = buffer.clone();
+    orientation[6] = 2;
+    let mut shape = buffer[..19].to_vec();
+    shape.push(1);
+    for value in &[3i64, 3, 0, 0, 0, 0] {
+        shape.extend_from_slice(&value.to_le_bytes());
+    }
+    shape.extend_from_slice(&buffer[20..]);
+    let coordinate = |value: i64| {
+        let mut coordinate = buffer[..19].to_vec();
+        coordinate.push(1);
+        for value in &[value, 3, 0, 0, 0, 0] {
+            coordinate.extend_from_slice(&value.to_le_bytes());
+        }
+        coordinate.extend_from_slice(&buffer[20..]);
+        coordinate
+    };
+    for (name, bytes) in &[
+        ("newer", newer),
+        ("zero", zero),
+        ("magic", magic),
+        ("orientation", orientation),
+        ("shape", shape),
+        ("coordinate max", coordinate(i64::MAX)),
+        ("coordinate min", coordinate(i64::MIN)),
+    ] {
+        assert_eq!(
+            MapFile::read_from(&mut bytes.as_slice())
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidData,
+            "{}",
+            name
+        );
+    }
+    assert_eq!(
+        MapFile::read_from(&mut &buffer[..buffer.len() - 1])
+            .unwrap_err()
+            .kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn test_map_file_should_not_write_what_it_cannot_read() {
+    let write = |map: &MapFile| map.write_to(&mut Vec::new());
+    let mut map = MapFile::new(HexLayout::pointy_top(1.0));
+    let hexes = RectHashStorage::<u8, _>::new();
+    map.set_layer(&"a".repeat(MAX_STRING_LEN as usize), &hexes)
+        .unwrap();
+    assert!(write(&map).is_ok());
+    map.set_layer(&"a".repeat(MAX_STRING_LEN as usize + 1), &hexes)
+        .unwrap();
+    assert_eq!(write(&map).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    let mut map = MapFile::new(HexLayout::pointy_top(1.0));
+    map.dungeon = Some(DungeonMetadata {
+        generator: "rooms and mazes".to_owned(),
+        seed: 42,
+        rooms: Vec::new(),
+        doors: vec![AxialVector::new(isize::MAX, 0)],
+    });
+    assert_eq!(write(&map).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    let mut far = RectHashStorage::<u8, _>::new();
+    far.insert(AxialVector::new(0, isize::MIN), 1);
+    assert_eq!(
+        map.set_layer("far", &far).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+}
@@ -1,4 +1,18 @@
+// Everything here but the float module only computes with integers, so that the same inputs give
+// bit-identical results on every platform, e.g. for lockstep multiplayer games.
+pub mod analysis;
+pub mod ascii;
+pub mod board;
+pub mod carve;
 pub mod coordinates;
 pub mod field_of_view;
+pub mod float;
+pub mod flood_fill;
+pub mod flow_field;
+pub mod geometry;
 pub mod largest_area;
+pub mod map_file;
+pub mod reachable;
+pub mod shape;
+pub mod spawn;
 pub mod storage;
@@ -0,0 +1,212 @@
+use crate::hex::{
+    coordinates::axial::AxialVector, field_of_view::hexes_between, storage::hash::RectHashStorage,
+};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+// The movement points spent to step from a hex to its neighbor, None if the step is not allowed.
+pub trait MovementCost {
+    fn cost(&self, from: AxialVector, to: AxialVector) -> Option<usize>;
+}
+
+impl<F> MovementCost for F
+where
+    F: Fn(AxialVector, AxialVector) -> Option<usize>,
+{
+    fn cost(&self, from: AxialVector, to: AxialVector) -> Option<usize> {
+        self(from, to)
+    }
+}
+
+// Every hex reachable from the start without spending more than the budget, with the cheapest cost
+// to reach it, in increasing cost order. The start comes first with a cost of zero.
+pub fn reachable<C>(start: AxialVector, budget: usize, cost: C) -> Vec<(AxialVector, usize)>
+where
+    C: MovementCost,
+{
+    let mut best = RectHashStorage::new();
+    let mut reached = Vec::new();
+    let mut pending = BinaryHeap::new();
+    best.insert(start, 0);
+    pending.push(Reverse((0, start.q(), start.r())));
+    while let Some(Reverse((spent, q, r))) = pending.pop() {
+        let position = AxialVector::new(q, r);
+        if best.get(position) != Some(&spent) {
+            // Reached for less in the meantime.
+            continue;
+        }
+        reached.push((position, spent));
+        for neighbor in position.ring_iter(1) {
+            let total = match cost.cost(position, neighbor) {
+                Some(step) if spent + step <= budget => spent + step,
+                _ => continue,
+            };
+            if !matches!(best.get(neighbor), Some(previous) if *previous <= total) {
+                best.insert(neighbor, total);
+                pending.push(Reverse((total, neighbor.q(), neighbor.r())));
+            }
+        }
+    }
+    reached
+}
+
+// The cheapest way from the start to the goal without spending more than the budget, both ends
+// included, with its cost. Among the paths of the same cost, the one found first is returned.
+pub fn cheapest_path<C>(
+    start: AxialVector,
+    goal: AxialVector,
+    budget: usize,
+    cost: C,
+) -> Option<(Vec<AxialVector>, usize)>
+where
+    C: MovementCost,
+{
+    // The cheapest cost to reach a hex and the hex it is reached from.
+    let mut best = RectHashStorage::new();
+    let mut pending = BinaryHeap::new();
+    best.insert(start, (0, start));
+    pending.push(Reverse((0, start.q(), start.r())));
+    while let Some(Reverse((spent, q, r))) = pending.pop() {
+        let position = AxialVector::new(q, r);
+        if best.get(position).map(|(best_spent, _)| *best_spent) != Some(spent) {
+            continue;
+        }
+        if position == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while current != start {
+                current = best.get(current).expect("reached hex").1;
+                path.push(current);
+            }
+            path.reverse();
+            return Some((path, spent));
+        }
+        for neighbor in position.ring_iter(1) {
+            let total = match cost.cost(position, neighbor) {
+                Some(step) if spent + step <= budget => spent + step,
+                _ => continue,
+            };
+            if !matches!(best.get(neighbor), Some((previous, _)) if *previous <= total) {
+                best.insert(neighbor, (total, position));
+                pending.push(Reverse((total, neighbor.q(), neighbor.r())));
+            }
+        }
+    }
+    None
+}
+
+// The fewest waypoints of a path such that each one is in line of sight of the next one, both
+// ends kept, for agents walking straight from hex center to hex center. Each waypoint is the
+// farthest hex of the path still in sight of the previous one.
+pub fn smooth_path<O>(path: &[AxialVector], is_obstacle: O) -> Vec<AxialVector>
+where
+    O: Fn(AxialVector) -> bool,
+{
+    let mut waypoints = Vec::new();
+    let mut index = 0;
+    while let Some(position) = path.get(index) {
+        waypoints.push(*position);
+        index = (index + 2..path.len())
+            .rev()
+            .find(|next| hexes_between(*position, path[*next]).all(|hex| !is_obstacle(hex)))
+            .unwrap_or(index + 1);
+    }
+    waypoints
+}
+
+#[test]
+fn test_reachable_uniform() {
+    let start = AxialVector::new(3, -1);
+    let hexes = reachable(start, 3, |_, _| Some(1));
+    assert_eq!(hexes.len(), 37);
+    assert_eq!(hexes[0], (start, 0));
+    for (position, spent) in &hexes {
+        assert_eq!(position.distance(start) as usize, *spent);
+    }
+    assert!(hexes.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+}
+
+#[test]
+fn test_reachable_costs() {
+    let start = AxialVector::default();
+    // A wall on the east ring 2 and swamps on the ring 1.
+    let cost = |_: AxialVector, to: AxialVector| match to.distance(start) {
+        1 => Some(3),
+        2 if to.q() > 0 => None,
+        _ => Some(1),
+    };
+    let hexes = reachable(start, 4, cost);
+    let cost_of = |position| {
+        hexes
+            .iter()
+            .find(|(hex, _)| *hex == position)
+            .map(|(_, spent)| *spent)
+    };
+    assert_eq!(cost_of(AxialVector::new(1, 0)), Some(3));
+    assert_eq!(cost_of(AxialVector::new(2, 0)), None);
+    assert_eq!(cost_of(AxialVector::new(-2, 0)), Some(4));
+    assert_eq!(cost_of(AxialVector::new(-3, 0)), None);
+    assert_eq!(hexes.len(), 1 + 6 + 7);
+
+    // Nothing can be entered.
+    assert_eq!(reachable(start, 10, |_, _| None), vec![(start, 0)]);
+}
+
+#[test]
+fn test_cheapest_path() {
+    let start = AxialVector::default();
+    let goal = AxialVector::new(3, 0);
+    let (path, spent) = cheapest_path(start, goal, 10, |_, _| Some(1)).expect("path");
+    assert_eq!(spent, 3);
+    assert_eq!(path.len(), 4);
+    assert_eq!((path[0], path[3]), (start, goal));
+    assert!(path.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+
+    // A swamp on the straight line is walked around.
+    let swamp = AxialVector::new(1, 0);
+    let cost = |_: AxialVector, to: AxialVector| Some(if to == swamp { 5 } else { 1 });
+    let (path, spent) = cheapest_path(start, goal, 10, cost).expect("path");
+    assert_eq!(spent, 4);
+    assert!(!path.contains(&swamp));
+
+    assert_eq!(cheapest_path(start, goal, 2, |_, _| Some(1)), None);
+    assert_eq!(cheapest_path(start, goal, 10, |_, _| None), None);
+    assert_eq!(
+        cheapest_path(start, start, 0, |_, _| None),
+        Some((vec![start], 0))
+    );
+}
+
+#[test]
+fn test_smooth_path() {
+    let start = AxialVector::new(-3, 0);
+    let goal = AxialVector::new(3, 0);
+    // Zigzags in the open are straightened.
+    let (path, _) = cheapest_path(start, goal, 20, |_, _| Some(1)).expect("path");
+    assert_eq!(smooth_path(&path, |_| false), vec![start, goal]);
+
+    // Around a wall, the waypoints are the corners of the way.
+    let walls = (-3..=1).map(|r| AxialVector::new(0, r)).collect::<Vec<_>>();
+    let cost = |_: AxialVector, to: AxialVector| {
+        if walls.contains(&to) || to.distance(AxialVector::default()) > 5 {
+            None
+        } else {
+            Some(1)
+        }
+    };
+    let (path, _) = cheapest_path(start, goal, 20, cost).expect("path");
+    let waypoints = smooth_path(&path, |position| walls.contains(&position));
+    assert_eq!(
+        (waypoints[0], waypoints[waypoints.len() - 1]),
+        (start, goal)
+    );
+    assert!(waypoints.len() >= 3 && waypoints.len() < path.len());
+    assert!(waypoints.iter().all(|waypoint| path.contains(waypoint)));
+    assert!(waypoints.windows(2).all(|pair| {
+        pair[0]
+            .line_iter(pair[1])
+            .all(|position| !walls.contains(&position))
+    }));
+
+    assert_eq!(smooth_path(&[start], |_| false), vec![start]);
+    assert!(smooth_path(&[], |_| false).is_empty());
+}
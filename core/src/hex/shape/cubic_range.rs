@@ -0,0 +1,959 @@
+use crate::hex::coordinates::{
+    axial::AxialVector,
+    cubic::CubicVector,
+    direction::{HexagonalDirection, NUM_DIRECTIONS},
+    transform::HexTransform,
+    HexCoord,
+};
+use std::ops::RangeInclusive;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Range {
+    start: isize,
+    end: isize,
+}
+
+impl Range {
+    pub fn start(&self) -> isize {
+        self.start
+    }
+
+    pub fn end(&self) -> isize {
+        self.end
+    }
+
+    pub fn start_mut(&mut self) -> &mut isize {
+        &mut self.start
+    }
+
+    pub fn end_mut(&mut self) -> &mut isize {
+        &mut self.end
+    }
+
+    pub fn contains(&self, value: isize) -> bool {
+        self.start <= value && value <= self.end
+    }
+}
+
+impl From<(isize, isize)> for Range {
+    fn from(tuple: (isize, isize)) -> Self {
+        Range {
+            start: tuple.0,
+            end: tuple.1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResizeAnchor {
+    OppositeEdge,
+    Center,
+    Vertex(usize),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CubicRangeShape {
+    range_x: Range,
+    range_y: Range,
+    range_z: Range,
+}
+
+impl CubicRangeShape {
+    pub fn new<R: Into<Range>>(range_x: R, range_y: R, range_z: R) -> Self {
+        let range_x = range_x.into();
+        let range_y = range_y.into();
+        let range_z = range_z.into();
+        if !Self::are_ranges_valid(&range_x, &range_y, &range_z) {
+            panic!(
+                "Invalid CubicRangeShape [{}, {}], [{}, {}], [{}, {}]",
+                range_x.start(),
+                range_x.end(),
+                range_y.start(),
+                range_y.end(),
+                range_z.start(),
+                range_z.end()
+            );
+        }
+        Self {
+            range_x,
+            range_y,
+            range_z,
+        }
+    }
+
+    // The hexes at most `radius` away from the center.
+    pub fn hexagon(center: AxialVector, radius: usize) -> Self {
+        let center = CubicVector::from(center);
+        let radius = radius as isize;
+        Self::new(
+            (center.x() - radius, center.x() + radius),
+            (center.y() - radius, center.y() + radius),
+            (center.z() - radius, center.z() + radius),
+        )
+    }
+
+    // The smallest shape holding all the positions, None without any.
+    pub fn bounding<I: IntoIterator<Item = AxialVector>>(positions: I) -> Option<Self> {
+        let mut positions = positions.into_iter().map(CubicVector::from);
+        let first = positions.next()?;
+        let bound = |value| Range {
+            start: value,
+            end: value,
+        };
+        let (mut range_x, mut range_y, mut range_z) =
+            (bound(first.x()), bound(first.y()), bound(first.z()));
+        for cubic in positions {
+            for (range, value) in [
+                (&mut range_x, cubic.x()),
+                (&mut range_y, cubic.y()),
+                (&mut range_z, cubic.z()),
+            ]
+            .iter_mut()
+            {
+                range.start = range.start.min(*value);
+                range.end = range.end.max(*value);
+            }
+        }
+        Some(Self::new(range_x, range_y, range_z))
+    }
+
+    pub fn range_x(&self) -> &Range {
+        &self.range_x
+    }
+
+    pub fn range_y(&self) -> &Range {
+        &self.range_y
+    }
+
+    pub fn range_z(&self) -> &Range {
+        &self.range_z
+    }
+
+    fn edges_length(&self) -> [usize; 6] {
+        let signed = Self::signed_edges_lengths(&self.range_x, &self.range_y, &self.range_z);
+        [
+            signed[0] as usize,
+            signed[1] as usize,
+            signed[2] as usize,
+            signed[3] as usize,
+            signed[4] as usize,
+            signed[5] as usize,
+        ]
+    }
+
+    pub fn is_valid(&self) -> bool {
+        Self::are_ranges_valid(&self.range_x, &self.range_y, &self.range_z)
+    }
+
+    pub fn are_ranges_valid(range_x: &Range, range_y: &Range, range_z: &Range) -> bool {
+        let edges_lengths = Self::signed_edges_lengths(range_x, range_y, range_z);
+        for edge_length in &edges_lengths {
+            if *edge_length < 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn signed_edges_lengths(range_x: &Range, range_y: &Range, range_z: &Range) -> [isize; 6] {
+        [
+            -range_x.start() - range_y.start() - range_z.end(),
+            range_x.end() + range_y.start() + range_z.end(),
+            -range_x.end() - range_y.start() - range_z.start(),
+            range_x.end() + range_y.end() + range_z.start(),
+            -range_x.start() - range_y.end() - range_z.start(),
+            range_x.start() + range_y.end() + range_z.end(),
+        ]
+    }
+
+    pub fn vertices(&self) -> [AxialVector; 6] {
+        [
+            CubicVector::new(
+                self.range_x.start(),
+                -self.range_x.start() - self.range_z.end(),
+                self.range_z.end(),
+            )
+            .into(),
+            CubicVector::new(
+                -self.range_y.start() - self.range_z.end(),
+                self.range_y.start(),
+                self.range_z.end(),
+            )
+            .into(),
+            CubicVector::new(
+                self.range_x.end(),
+                self.range_y.start(),
+                -self.range_x.end() - self.range_y.start(),
+            )
+            .into(),
+            CubicVector::new(
+                self.range_x.end(),
+                -self.range_x.end() - self.range_z.start(),
+                self.range_z.start(),
+            )
+            .into(),
+            CubicVector::new(
+                -self.range_y.end() - self.range_z.start(),
+                self.range_y.end(),
+                self.range_z.start(),
+            )
+            .into(),
+            CubicVector::new(
+                self.range_x.start(),
+                self.range_y.end(),
+                -self.range_x.start() - self.range_y.end(),
+            )
+            .into(),
+        ]
+    }
+
+    pub fn perimeter(&self) -> PerimeterIter {
+        PerimeterIter::new(
+            self.edges_length(),
+            CubicVector::new(
+                self.range_x.start(),
+                -self.range_x.start() - self.range_z.end(),
+                self.range_z.end(),
+            )
+            .into(),
+        )
+    }
+
+    // Positions with x, y and z above the range starts form a triangle, the corners beyond the
+    // range ends are cut off (inclusion-exclusion, the corners may overlap).
+    pub fn len(&self) -> usize {
+        let triangle = |side: isize| {
+            if side < 0 {
+                0
+            } else {
+                ((side + 1) * (side + 2) / 2) as usize
+            }
+        };
+        let side = -(self.range_x.start() + self.range_y.start() + self.range_z.start());
+        let cuts = [
+            self.range_x.end() - self.range_x.start() + 1,
+            self.range_y.end() - self.range_y.start() + 1,
+            self.range_z.end() - self.range_z.start() + 1,
+        ];
+        triangle(side)
+            + triangle(side - cuts[0] - cuts[1])
+            + triangle(side - cuts[1] - cuts[2])
+            + triangle(side - cuts[0] - cuts[2])
+            - triangle(side - cuts[0])
+            - triangle(side - cuts[1])
+            - triangle(side - cuts[2])
+            - triangle(side - cuts[0] - cuts[1] - cuts[2])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Row by row (r, then q), without allocating anything.
+    pub fn iter_positions(&self) -> PositionsIter {
+        PositionsIter::new(self.clone())
+    }
+
+    // The q range of each row, in the order of iter_positions.
+    pub fn scanlines(&self) -> impl Iterator<Item = (isize, RangeInclusive<isize>)> {
+        let shape = self.clone();
+        (self.range_z.start()..=self.range_z.end()).filter_map(move |r| {
+            let (start, end) = PositionsIter::row(&shape, r);
+            if start <= end {
+                Some((r, start..=end))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn contains<C: HexCoord>(&self, position: C) -> bool {
+        self.range_x.contains(position.x())
+            && self.range_y.contains(position.y())
+            && self.range_z.contains(position.z())
+    }
+
+    // Strictly inside, the range ends excluded.
+    pub fn contains_inside<C: HexCoord>(&self, position: C) -> bool {
+        let inside = |range: &Range, value| range.start() < value && value < range.end();
+        inside(&self.range_x, position.x())
+            && inside(&self.range_y, position.y())
+            && inside(&self.range_z, position.z())
+    }
+
+    pub fn contains_position(&self, position: AxialVector) -> bool {
+        self.contains(position)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        if self.contains_position(other.vertices()[0]) {
+            return true;
+        }
+        for pos in self.perimeter() {
+            if other.contains_position(pos) {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn center(&self) -> AxialVector {
+        AxialVector::new(
+            (self.range_x.start() + self.range_x.end()
+                - (self.range_y.start()
+                    + self.range_y.end()
+                    + self.range_z.start()
+                    + self.range_z.end())
+                    / 2)
+                / 3,
+            (self.range_z.start() + self.range_z.end()
+                - (self.range_x.start()
+                    + self.range_x.end()
+                    + self.range_y.start()
+                    + self.range_y.end())
+                    / 2)
+                / 3,
+        )
+    }
+
+    pub fn translate(&mut self, offset: AxialVector) {
+        let offset = CubicVector::from(offset);
+        *self.range_x.start_mut() += offset.x();
+        *self.range_x.end_mut() += offset.x();
+        *self.range_y.start_mut() += offset.y();
+        *self.range_y.end_mut() += offset.y();
+        *self.range_z.start_mut() += offset.z();
+        *self.range_z.end_mut() += offset.z();
+    }
+
+    // The ranges are swapped and negated like the coordinates, the image of a cubic range shape
+    // being another one.
+    pub fn transform(&mut self, transform: HexTransform, center: AxialVector) {
+        self.translate(AxialVector::default() - center);
+        let (x, y, z) = if transform.reflect() {
+            (self.range_x, self.range_z, self.range_y)
+        } else {
+            (self.range_x, self.range_y, self.range_z)
+        };
+        let neg = |range: Range| Range::from((-range.end, -range.start));
+        let (x, y, z) = match transform.steps() {
+            0 => (x, y, z),
+            1 => (neg(y), neg(z), neg(x)),
+            2 => (z, x, y),
+            3 => (neg(x), neg(y), neg(z)),
+            4 => (y, z, x),
+            5 => (neg(z), neg(x), neg(y)),
+            _ => unreachable!(),
+        };
+        self.range_x = x;
+        self.range_y = y;
+        self.range_z = z;
+        self.translate(center);
+    }
+
+    // The stretch and shrink operations keep the opposite edge in place, the shape is then
+    // translated back so that the anchor does not move. An anchor on a vertex that does not exist
+    // is rejected before the shape is touched.
+    pub fn resize(
+        &mut self,
+        operation: fn(&mut Self, usize) -> bool,
+        amount: usize,
+        anchor: ResizeAnchor,
+    ) -> bool {
+        if let ResizeAnchor::Vertex(vertex) = anchor {
+            if vertex >= 6 {
+                return false;
+            }
+        }
+        let center = self.center();
+        let vertices = self.vertices();
+        if !operation(self, amount) {
+            return false;
+        }
+        match anchor {
+            ResizeAnchor::OppositeEdge => {}
+            ResizeAnchor::Center => {
+                let offset = center - self.center();
+                self.translate(offset);
+            }
+            ResizeAnchor::Vertex(vertex) => {
+                let offset = vertices[vertex] - self.vertices()[vertex];
+                self.translate(offset);
+            }
+        }
+        true
+    }
+
+    pub fn stretch_x_start(&mut self, amount: usize) -> bool {
+        Self::stretch_axis_start(
+            &mut self.range_x,
+            &mut self.range_y,
+            &mut self.range_z,
+            amount,
+        )
+    }
+
+    pub fn stretch_y_start(&mut self, amount: usize) -> bool {
+        Self::stretch_axis_start(
+            &mut self.range_y,
+            &mut self.range_z,
+            &mut self.range_x,
+            amount,
+        )
+    }
+
+    pub fn stretch_z_start(&mut self, amount: usize) -> bool {
+        Self::stretch_axis_start(
+            &mut self.range_z,
+            &mut self.range_x,
+            &mut self.range_y,
+            amount,
+        )
+    }
+
+    fn stretch_axis_start(a: &mut Range, b: &mut Range, c: &mut Range, amount: usize) -> bool {
+        *a.start_mut() -= amount as isize;
+        if a.start() + b.end() + c.end() < 0 {
+            *b.end_mut() += amount as isize;
+            *c.end_mut() += amount as isize;
+        }
+        true
+    }
+
+    pub fn stretch_x_end(&mut self, amount: usize) -> bool {
+        Self::stretch_axis_end(
+            &mut self.range_x,
+            &mut self.range_y,
+            &mut self.range_z,
+            amount,
+        )
+    }
+
+    pub fn stretch_y_end(&mut self, amount: usize) -> bool {
+        Self::stretch_axis_end(
+            &mut self.range_y,
+            &mut self.range_z,
+            &mut self.range_x,
+            amount,
+        )
+    }
+
+    pub fn stretch_z_end(&mut self, amount: usize) -> bool {
+        Self::stretch_axis_end(
+            &mut self.range_z,
+            &mut self.range_x,
+            &mut self.range_y,
+            amount,
+        )
+    }
+
+    fn stretch_axis_end(a: &mut Range, b: &mut Range, c: &mut Range, amount: usize) -> bool {
+        *a.end_mut() += amount as isize;
+        if -a.end() - b.start() - c.start() < 0 {
+            *b.start_mut() -= amount as isize;
+            *c.start_mut() -= amount as isize;
+        }
+        true
+    }
+
+    pub fn shrink_x_start(&mut self, amount: usize) -> bool {
+        Self::shrink_axis_start(
+            &mut self.range_x,
+            &mut self.range_y,
+            &mut self.range_z,
+            amount,
+        )
+    }
+
+    pub fn shrink_y_start(&mut self, amount: usize) -> bool {
+        Self::shrink_axis_start(
+            &mut self.range_y,
+            &mut self.range_z,
+            &mut self.range_x,
+            amount,
+        )
+    }
+
+    pub fn shrink_z_start(&mut self, amount: usize) -> bool {
+        Self::shrink_axis_start(
+            &mut self.range_z,
+            &mut self.range_x,
+            &mut self.range_y,
+            amount,
+        )
+    }
+
+    fn shrink_axis_start(a: &mut Range, b: &mut Range, c: &mut Range, amount: usize) -> bool {
+        if a.start() + amount as isize <= a.end() {
+            *a.start_mut() += amount as isize;
+            Self::clamp_ranges(a, b, c);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn shrink_x_end(&mut self, amount: usize) -> bool {
+        Self::shrink_axis_end(
+            &mut self.range_x,
+            &mut self.range_y,
+            &mut self.range_z,
+            amount,
+        )
+    }
+
+    pub fn shrink_y_end(&mut self, amount: usize) -> bool {
+        Self::shrink_axis_end(
+            &mut self.range_y,
+            &mut self.range_z,
+            &mut self.range_x,
+            amount,
+        )
+    }
+
+    pub fn shrink_z_end(&mut self, amount: usize) -> bool {
+        Self::shrink_axis_end(
+            &mut self.range_z,
+            &mut self.range_x,
+            &mut self.range_y,
+            amount,
+        )
+    }
+
+    fn shrink_axis_end(a: &mut Range, b: &mut Range, c: &mut Range, amount: usize) -> bool {
+        if a.start() + amount as isize <= a.end() {
+            *a.end_mut() -= amount as isize;
+            Self::clamp_ranges(a, b, c);
+            true
+        } else {
+            false
+        }
+    }
+
+    // Tightens the ranges to the bounds of the positions they contain. Shrinking one range of a
+    // valid shape always leaves a non empty set of positions, therefore the result is valid.
+    fn clamp_ranges(a: &mut Range, b: &mut Range, c: &mut Range) {
+        loop {
+            let clamped = (
+                Range::from((
+                    a.start().max(-b.end() - c.end()),
+                    a.end().min(-b.start() - c.start()),
+                )),
+                Range::from((
+                    b.start().max(-c.end() - a.end()),
+                    b.end().min(-c.start() - a.start()),
+                )),
+                Range::from((
+                    c.start().max(-a.end() - b.end()),
+                    c.end().min(-a.start() - b.start()),
+                )),
+            );
+            if clamped == (*a, *b, *c) {
+                break;
+            }
+            *a = clamped.0;
+            *b = clamped.1;
+            *c = clamped.2;
+        }
+    }
+}
+
+impl Default for CubicRangeShape {
+    fn default() -> Self {
+        CubicRangeShape::new((-1, 1), (-1, 1), (-1, 1))
+    }
+}
+
+pub struct PositionsIter {
+    shape: CubicRangeShape,
+    q: isize,
+    q_end: isize,
+    r: isize,
+    remaining: usize,
+}
+
+impl PositionsIter {
+    fn new(shape: CubicRangeShape) -> Self {
+        let r = shape.range_z.start();
+        let (q, q_end) = Self::row(&shape, r);
+        let remaining = shape.len();
+        Self {
+            shape,
+            q,
+            q_end,
+            r,
+            remaining,
+        }
+    }
+
+    // q bounds of a row, z is r and y is -q - r.
+    fn row(shape: &CubicRangeShape, r: isize) -> (isize, isize) {
+        (
+            shape.range_x.start().max(-shape.range_y.end() - r),
+            shape.range_x.end().min(-shape.range_y.start() - r),
+        )
+    }
+}
+
+impl Iterator for PositionsIter {
+    type Item = AxialVector;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nth(0)
+    }
+
+    // Whole rows are skipped at once.
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        if n >= self.remaining {
+            self.remaining = 0;
+            return None;
+        }
+        self.remaining -= n + 1;
+        loop {
+            let row_len = (self.q_end - self.q + 1).max(0) as usize;
+            if n < row_len {
+                let position = AxialVector::new(self.q + n as isize, self.r);
+                self.q += n as isize + 1;
+                if self.q > self.q_end && self.r < self.shape.range_z.end() {
+                    self.r += 1;
+                    let (q, q_end) = Self::row(&self.shape, self.r);
+                    self.q = q;
+                    self.q_end = q_end;
+                }
+                return Some(position);
+            }
+            n -= row_len;
+            self.r += 1;
+            let (q, q_end) = Self::row(&self.shape, self.r);
+            self.q = q;
+            self.q_end = q_end;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for PositionsIter {}
+
+pub struct PerimeterIter {
+    edges_lengths: [usize; 6],
+    direction: usize,
+    next: AxialVector,
+    edge_index: usize,
+}
+
+impl PerimeterIter {
+    pub fn new(edges_lengths: [usize; 6], initial: AxialVector) -> Self {
+        let mut direction = 0;
+        // Drain all but last edge so that:
+        //     - the state is ready for next iteration
+        //     - the ring of size 0 case is handled correctly (it returns
+        //       the first value, and no more then)
+        while direction < 5 && edges_lengths[direction] == 0 {
+            direction += 1;
+        }
+        Self {
+            edges_lengths,
+            direction,
+            next: initial,
+            edge_index: 1,
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<&AxialVector> {
+        if self.direction < NUM_DIRECTIONS {
+            Some(&self.next)
+        } else {
+            None
+        }
+    }
+}
+
+impl Iterator for PerimeterIter {
+    type Item = AxialVector;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let edges_lengths = self.edges_lengths;
+        let direction = self.direction;
+        if direction < NUM_DIRECTIONS {
+            let next = self.next;
+            self.next = next.neighbor(direction);
+            let ei = self.edge_index;
+            if ei < edges_lengths[direction] {
+                self.edge_index = ei + 1;
+            } else {
+                self.edge_index = 1;
+                self.direction = direction + 1;
+                while self.direction < NUM_DIRECTIONS && edges_lengths[self.direction] == 0 {
+                    self.direction += 1;
+                }
+            }
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let length = self.edges_lengths.iter().sum();
+        (length, Some(length))
+    }
+}
+
+#[cfg(test)]
+fn apply_shape_operation(shape: &mut CubicRangeShape, operation: usize, amount: usize) -> bool {
+    match operation {
+        0 => shape.stretch_x_start(amount),
+        1 => shape.stretch_x_end(amount),
+        2 => shape.stretch_y_start(amount),
+        3 => shape.stretch_y_end(amount),
+        4 => shape.stretch_z_start(amount),
+        5 => shape.stretch_z_end(amount),
+        6 => shape.shrink_x_start(amount),
+        7 => shape.shrink_x_end(amount),
+        8 => shape.shrink_y_start(amount),
+        9 => shape.shrink_y_end(amount),
+        10 => shape.shrink_z_start(amount),
+        11 => shape.shrink_z_end(amount),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_shrink_should_keep_shape_valid() {
+    let mut shape = CubicRangeShape::new((-2, 2), (-4, 0), (0, 4));
+    assert!(shape.shrink_y_end(2));
+    assert!(shape.is_valid());
+    assert_eq!(shape, CubicRangeShape::new((-2, 2), (-4, -2), (0, 4)));
+
+    let mut shape = CubicRangeShape::new((-3, 3), (-3, 3), (-3, 3));
+    assert!(shape.shrink_x_start(5));
+    assert!(shape.is_valid());
+    assert_eq!(shape, CubicRangeShape::new((2, 3), (-3, 1), (-3, 1)));
+}
+
+#[test]
+fn test_shrink_should_not_mutate_when_failing() {
+    let mut shape = CubicRangeShape::new((0, 2), (-2, 0), (-1, 1));
+    let before = shape.clone();
+    assert!(!shape.shrink_x_end(3));
+    assert_eq!(shape, before);
+    assert!(!shape.shrink_z_start(3));
+    assert_eq!(shape, before);
+}
+
+#[test]
+fn test_shape_operation_sequences_should_keep_shape_valid() {
+    for initial in &[
+        CubicRangeShape::default(),
+        CubicRangeShape::new((0, 0), (0, 0), (0, 0)),
+        CubicRangeShape::new((-2, 2), (-4, 0), (0, 4)),
+        CubicRangeShape::new((-3, 5), (-4, 2), (-3, 4)),
+    ] {
+        for sequence in 0..36 * 36 * 36 {
+            let mut shape = initial.clone();
+            let mut steps = sequence;
+            for _ in 0..3 {
+                let before = shape.clone();
+                let changed = apply_shape_operation(&mut shape, steps % 12, 1 + steps / 12 % 3);
+                steps /= 36;
+                assert!(shape.is_valid(), "{:?} from {:?}", shape, before);
+                if !changed {
+                    assert_eq!(shape, before);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_shape_len() {
+    for initial in &[
+        CubicRangeShape::default(),
+        CubicRangeShape::new((-2, 2), (-4, 0), (0, 4)),
+        CubicRangeShape::new((-3, 5), (-4, 2), (-3, 4)),
+        CubicRangeShape::new((-30, 30), (-30, 30), (-30, 30)),
+    ] {
+        for sequence in 0..36 * 36 {
+            let mut shape = initial.clone();
+            apply_shape_operation(&mut shape, sequence % 12, 1 + sequence / 12 % 3);
+            apply_shape_operation(&mut shape, sequence / 36 % 12, 1 + sequence / 432);
+            let mut count = 0;
+            for x in shape.range_x().start()..=shape.range_x().end() {
+                for z in shape.range_z().start()..=shape.range_z().end() {
+                    if shape.range_y().contains(-x - z) {
+                        count += 1;
+                    }
+                }
+            }
+            assert_eq!(shape.len(), count, "{:?}", shape);
+            assert!(!shape.is_empty());
+        }
+    }
+    assert_eq!(
+        CubicRangeShape::new((-3, 3), (-3, 3), (-3, 3)).len(),
+        1 + 3 * 3 * 4
+    );
+}
+
+#[test]
+fn test_shape_iter_positions() {
+    for shape in &[
+        CubicRangeShape::default(),
+        CubicRangeShape::new((0, 0), (0, 0), (0, 0)),
+        CubicRangeShape::new((-2, 2), (-4, 0), (0, 4)),
+        CubicRangeShape::new((-3, 5), (-4, 2), (-3, 4)),
+        CubicRangeShape::new((2, 3), (-3, 1), (-3, 1)),
+    ] {
+        let positions = shape.iter_positions().collect::<Vec<_>>();
+        assert_eq!(positions.len(), shape.len());
+        assert!(positions.iter().all(|pos| shape.contains_position(*pos)));
+        let mut sorted = positions.clone();
+        sorted.sort_by_key(|pos| (pos.r(), pos.q()));
+        sorted.dedup();
+        assert_eq!(sorted, positions);
+        assert_eq!(
+            shape
+                .scanlines()
+                .flat_map(|(r, row)| row.map(move |q| AxialVector::new(q, r)))
+                .collect::<Vec<_>>(),
+            positions
+        );
+        for n in 0..=positions.len() {
+            let mut iter = shape.iter_positions();
+            assert_eq!(iter.nth(n), positions.get(n).copied());
+            assert_eq!(iter.len(), positions.len().saturating_sub(n + 1));
+            assert_eq!(
+                iter.collect::<Vec<_>>(),
+                positions[(n + 1).min(positions.len())..]
+            );
+        }
+    }
+}
+
+#[test]
+fn test_hexagon_shape() {
+    let center = AxialVector::new(3, -7);
+    for radius in 0..5 {
+        let shape = CubicRangeShape::hexagon(center, radius);
+        assert_eq!(shape.center(), center);
+        let mut positions = shape.iter_positions().collect::<Vec<_>>();
+        let mut expected = center.spiral_iter(radius).collect::<Vec<_>>();
+        positions.sort();
+        expected.sort();
+        assert_eq!(positions, expected);
+    }
+}
+
+#[test]
+fn test_shape_contains_either_coordinates() {
+    let center = AxialVector::new(3, -7);
+    let shape = CubicRangeShape::hexagon(center, 2);
+    for position in center.spiral_iter(4) {
+        let distance = position.distance(center);
+        assert_eq!(shape.contains(position), distance <= 2);
+        assert_eq!(shape.contains(CubicVector::from(position)), distance <= 2);
+        assert_eq!(shape.contains_inside(position), distance <= 1);
+        assert_eq!(
+            shape.contains_inside(CubicVector::from(position)),
+            distance <= 1
+        );
+    }
+}
+
+#[test]
+fn test_bounding_shape() {
+    assert_eq!(CubicRangeShape::bounding(Vec::new()), None);
+    let center = AxialVector::new(3, -7);
+    assert_eq!(
+        CubicRangeShape::bounding(center.ring_iter(4)),
+        Some(CubicRangeShape::hexagon(center, 4))
+    );
+    assert_eq!(
+        CubicRangeShape::bounding(std::iter::once(center)),
+        Some(CubicRangeShape::hexagon(center, 0))
+    );
+    let shape = CubicRangeShape::new((-2, 5), (-6, 1), (-3, 4));
+    assert_eq!(
+        CubicRangeShape::bounding(shape.iter_positions()),
+        Some(shape.clone())
+    );
+    // Every position of a line, as long as its ends.
+    let (start, end) = (AxialVector::new(-3, 2), AxialVector::new(4, -6));
+    let line = CubicRangeShape::bounding(start.line_iter(end)).expect("line");
+    assert_eq!(
+        line,
+        CubicRangeShape::bounding(vec![start, end]).expect("ends")
+    );
+    assert!(start
+        .line_iter(end)
+        .all(|position| line.contains_position(position)));
+}
+
+#[test]
+fn test_translate_shape() {
+    let mut shape = CubicRangeShape::new((-2, 2), (-4, 0), (0, 4));
+    shape.translate(AxialVector::new(1, -2));
+    assert_eq!(shape, CubicRangeShape::new((-1, 3), (-3, 1), (-2, 2)));
+    assert_eq!(shape.center(), AxialVector::new(1, 0));
+}
+
+#[test]
+fn test_transform_shape() {
+    use std::collections::BTreeSet;
+
+    let shape = CubicRangeShape::new((-3, 1), (-2, 4), (-5, 2));
+    let center = AxialVector::new(4, -1);
+    for transform in HexTransform::all() {
+        let mut transformed = shape.clone();
+        transformed.transform(transform, center);
+        assert_eq!(
+            transformed.iter_positions().collect::<BTreeSet<_>>(),
+            shape
+                .iter_positions()
+                .map(|position| transform.apply_around(position, center))
+                .collect::<BTreeSet<_>>()
+        );
+    }
+}
+
+#[test]
+fn test_resize_shape_with_anchor() {
+    let initial = CubicRangeShape::new((-2, 2), (-2, 2), (-2, 2));
+
+    let mut shape = initial.clone();
+    assert!(shape.resize(
+        CubicRangeShape::stretch_x_end,
+        2,
+        ResizeAnchor::OppositeEdge
+    ));
+    assert_eq!(shape.range_x().start(), -2);
+    assert_eq!(shape.range_x().end(), 4);
+
+    let mut shape = initial.clone();
+    assert!(shape.resize(CubicRangeShape::stretch_x_end, 2, ResizeAnchor::Center));
+    assert_eq!(shape.center(), initial.center());
+    assert!(shape.resize(CubicRangeShape::shrink_y_start, 1, ResizeAnchor::Center));
+    assert_eq!(shape.center(), initial.center());
+
+    for vertex in 0..6 {
+        let mut shape = initial.clone();
+        assert!(shape.resize(
+            CubicRangeShape::shrink_z_end,
+            1,
+            ResizeAnchor::Vertex(vertex)
+        ));
+        assert_eq!(shape.vertices()[vertex], initial.vertices()[vertex]);
+        assert!(shape.is_valid());
+    }
+
+    let mut shape = initial.clone();
+    assert!(!shape.resize(CubicRangeShape::shrink_x_start, 5, ResizeAnchor::Center));
+    assert_eq!(shape, initial);
+
+    let mut shape = initial.clone();
+    assert!(!shape.resize(CubicRangeShape::stretch_x_end, 2, ResizeAnchor::Vertex(6)));
+    assert_eq!(shape, initial);
+}
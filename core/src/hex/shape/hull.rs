@@ -0,0 +1,155 @@
+use crate::hex::{
+    coordinates::{axial::AxialVector, cubic::CubicVector},
+    geometry::{Turn, VertexVector},
+    shape::cubic_range::CubicRangeShape,
+};
+
+// The smallest shape containing all the positions, none if there is no position. It is always
+// valid since every range bound is reached by one of the positions.
+pub fn bounding_cubic_range<I>(positions: I) -> Option<CubicRangeShape>
+where
+    I: IntoIterator<Item = AxialVector>,
+{
+    positions
+        .into_iter()
+        .map(CubicVector::from)
+        .fold(None, |bounds: Option<[(isize, isize); 3]>, cubic| {
+            let coordinates = [cubic.x(), cubic.y(), cubic.z()];
+            let mut bounds = bounds.unwrap_or_else(|| {
+                [
+                    (coordinates[0], coordinates[0]),
+                    (coordinates[1], coordinates[1]),
+                    (coordinates[2], coordinates[2]),
+                ]
+            });
+            for (range, coordinate) in bounds.iter_mut().zip(coordinates.iter()) {
+                range.0 = range.0.min(*coordinate);
+                range.1 = range.1.max(*coordinate);
+            }
+            Some(bounds)
+        })
+        .map(|[x, y, z]| CubicRangeShape::new(x, y, z))
+}
+
+// The vertices of the convex hull of the hex centers, turning left at every vertex (see
+// geometry::Turn): the positions on its sides but not at its corners are left out, a single
+// position or two ends are all there is to flat sets.
+pub fn convex_hull<I>(positions: I) -> Vec<AxialVector>
+where
+    I: IntoIterator<Item = AxialVector>,
+{
+    let mut points = positions
+        .into_iter()
+        .map(|position| (VertexVector::from(position), position))
+        .collect::<Vec<_>>();
+    points.sort_by_key(|(vertex, _)| (vertex.x(), vertex.y()));
+    points.dedup_by_key(|(vertex, _)| *vertex);
+    if points.len() < 3 {
+        return points.into_iter().map(|(_, position)| position).collect();
+    }
+    // Andrew's monotone chain, the lower side then the upper side.
+    let mut hull: Vec<(VertexVector, AxialVector)> = Vec::with_capacity(points.len() + 1);
+    for pass in 0..2 {
+        let start = hull.len();
+        let mut add = |point: &(VertexVector, AxialVector)| {
+            while hull.len() >= start + 2 {
+                let (a, b) = (hull[hull.len() - 2].0, hull[hull.len() - 1].0);
+                if (b - a).turns(&(point.0 - b)) == Turn::Left {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(*point);
+        };
+        if pass == 0 {
+            points.iter().for_each(&mut add);
+        } else {
+            points.iter().rev().for_each(&mut add);
+        }
+        // The last point of a side is the first one of the other side.
+        hull.pop();
+    }
+    hull.into_iter().map(|(_, position)| position).collect()
+}
+
+#[test]
+fn test_bounding_cubic_range() {
+    assert_eq!(bounding_cubic_range(Vec::new()), None);
+    let center = AxialVector::new(2, -7);
+    let mut disc = CubicRangeShape::new((-3, 3), (-3, 3), (-3, 3));
+    disc.translate(center);
+    assert_eq!(bounding_cubic_range(center.spiral_iter(3)), Some(disc));
+
+    let positions = [
+        AxialVector::new(0, 0),
+        AxialVector::new(5, -1),
+        AxialVector::new(-2, 4),
+        AxialVector::new(1, 3),
+    ];
+    let shape = bounding_cubic_range(positions.iter().copied()).expect("shape");
+    assert!(shape.is_valid());
+    assert!(positions
+        .iter()
+        .all(|position| shape.contains_position(*position)));
+    // Every bound is reached, shrinking any of them leaves a position out.
+    for shrink in &[
+        CubicRangeShape::shrink_x_start,
+        CubicRangeShape::shrink_x_end,
+        CubicRangeShape::shrink_y_start,
+        CubicRangeShape::shrink_y_end,
+        CubicRangeShape::shrink_z_start,
+        CubicRangeShape::shrink_z_end,
+    ] {
+        let mut smaller = shape.clone();
+        if shrink(&mut smaller, 1) {
+            assert!(!positions
+                .iter()
+                .all(|position| smaller.contains_position(*position)));
+        }
+    }
+}
+
+#[test]
+fn test_convex_hull() {
+    use crate::hex::{coordinates::direction::HexagonalDirection, geometry::is_convex};
+
+    assert_eq!(convex_hull(Vec::new()), vec![]);
+    let center = AxialVector::new(-1, 4);
+    assert_eq!(convex_hull(vec![center, center]), vec![center]);
+    let line = (0..5)
+        .map(|step| center + AxialVector::direction(1) * step)
+        .collect::<Vec<_>>();
+    let mut ends = convex_hull(line.iter().copied());
+    ends.sort();
+    let mut expected = vec![line[0], line[line.len() - 1]];
+    expected.sort();
+    assert_eq!(ends, expected);
+
+    // The corners of a disc, not the hexes in between.
+    let mut corners = convex_hull(center.spiral_iter(3));
+    corners.sort();
+    let mut expected = (0..6)
+        .map(|direction| center + AxialVector::direction(direction) * 3)
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(corners, expected);
+
+    let positions = center
+        .spiral_iter(4)
+        .filter(|position| (position.q() * 7 + position.r() * 3).rem_euclid(5) == 0)
+        .collect::<Vec<_>>();
+    let hull = convex_hull(positions.iter().copied());
+    let vertices = hull
+        .iter()
+        .map(|position| VertexVector::from(*position))
+        .collect::<Vec<_>>();
+    assert!(is_convex(&vertices));
+    for (index, a) in vertices.iter().enumerate() {
+        let b = vertices[(index + 1) % vertices.len()];
+        for position in &positions {
+            let turn = (b - *a).turns(&(VertexVector::from(*position) - *a));
+            assert_ne!(turn, Turn::Right);
+        }
+    }
+    assert!(hull.iter().all(|position| positions.contains(position)));
+}
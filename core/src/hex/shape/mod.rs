@@ -0,0 +1,43 @@
+pub mod cubic_range;
+pub mod hull;
+
+use crate::hex::{
+    coordinates::axial::AxialVector, shape::cubic_range::Range, storage::set::HexSet,
+};
+use std::hash::BuildHasher;
+
+pub trait Shape {
+    fn contains_position(&self, position: AxialVector) -> bool;
+
+    // Inclusive q and r ranges of the positions, none if there is no position. Storages use them
+    // to skip whole chunks.
+    fn bounds(&self) -> Option<(Range, Range)>;
+}
+
+impl Shape for cubic_range::CubicRangeShape {
+    fn contains_position(&self, position: AxialVector) -> bool {
+        cubic_range::CubicRangeShape::contains_position(self, position)
+    }
+
+    fn bounds(&self) -> Option<(Range, Range)> {
+        Some((*self.range_x(), *self.range_z()))
+    }
+}
+
+impl<S: BuildHasher> Shape for HexSet<S> {
+    fn contains_position(&self, position: AxialVector) -> bool {
+        self.contains(position)
+    }
+
+    fn bounds(&self) -> Option<(Range, Range)> {
+        self.iter().fold(None, |bounds, pos| {
+            Some(match bounds {
+                None => ((pos.q(), pos.q()).into(), (pos.r(), pos.r()).into()),
+                Some((q, r)) => (
+                    (q.start().min(pos.q()), q.end().max(pos.q())).into(),
+                    (r.start().min(pos.r()), r.end().max(pos.r())).into(),
+                ),
+            })
+        })
+    }
+}
@@ -0,0 +1,267 @@
+use crate::hex::{
+    coordinates::axial::AxialVector,
+    field_of_view::hexes_between,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use std::{fmt, hash::BuildHasher};
+
+// What the spawn points must respect. The default only asks for distinct open hexes, the with_*
+// methods add the constraints.
+#[derive(Clone, Default, Debug)]
+#[non_exhaustive]
+pub struct SpawnConstraints {
+    // Between any two spawn points.
+    pub min_distance: usize,
+    // From any spawn point to the closest wall, 1 being next to it.
+    pub min_wall_distance: usize,
+    // The hexes the spawn points are taken from, the whole storage if None.
+    pub region: Option<HexSet>,
+    // No spawn point sees another one: a wall stands on the hex line between them.
+    pub hidden_from_each_other: bool,
+}
+
+impl SpawnConstraints {
+    pub fn with_min_distance(mut self, min_distance: usize) -> Self {
+        self.min_distance = min_distance;
+        self
+    }
+
+    pub fn with_min_wall_distance(mut self, min_wall_distance: usize) -> Self {
+        self.min_wall_distance = min_wall_distance;
+        self
+    }
+
+    pub fn with_region(mut self, region: HexSet) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    pub fn with_hidden_from_each_other(mut self, hidden_from_each_other: bool) -> Self {
+        self.hidden_from_each_other = hidden_from_each_other;
+        self
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum SpawnError {
+    // Not a single open hex is in the region and far enough from the walls.
+    NoCandidate {
+        open: usize,
+        in_region: usize,
+    },
+    // The points found far enough apart, or out of sight of each other, fewer than asked.
+    TooFew {
+        found: Vec<AxialVector>,
+        asked: usize,
+    },
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpawnError::NoCandidate { open: 0, .. } => write!(f, "no open hex"),
+            SpawnError::NoCandidate { in_region: 0, .. } => {
+                write!(f, "no open hex in the region")
+            }
+            SpawnError::NoCandidate { in_region, .. } => write!(
+                f,
+                "none of the {} open hexes of the region is far enough from the walls",
+                in_region
+            ),
+            SpawnError::TooFew { found, asked } => write!(
+                f,
+                "only {} of the {} spawn points are far enough apart and out of sight of each \
+                 other",
+                found.len(),
+                asked
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpawnError {}
+
+// `n` spawn points among the open hexes of the storage, the hexes missing from it or not open
+// being walls. Each point is the candidate farthest from the points already taken which respects
+// the constraints, the first one being the smallest candidate, so that the points spread over the
+// map and the same map always gives the same points. The search is greedy: it may miss a solution
+// of a tightly constrained map.
+pub fn select_spawn_points<H, S, P>(
+    storage: &RectHashStorage<H, S>,
+    n: usize,
+    constraints: &SpawnConstraints,
+    is_open: P,
+) -> Result<Vec<AxialVector>, SpawnError>
+where
+    S: BuildHasher,
+    P: Fn(&H) -> bool,
+{
+    let open = |position| matches!(storage.get(position), Some(hex) if is_open(hex));
+    let mut open_count = 0;
+    let mut in_region = 0;
+    let mut candidates = Vec::new();
+    for (position, hex) in storage.iter() {
+        if !is_open(hex) {
+            continue;
+        }
+        open_count += 1;
+        if !matches!(&constraints.region, Some(region) if !region.contains(position)) {
+            in_region += 1;
+            if position
+                .spiral_iter(constraints.min_wall_distance.saturating_sub(1))
+                .all(open)
+            {
+                candidates.push(position);
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return if n == 0 {
+            Ok(Vec::new())
+        } else {
+            Err(SpawnError::NoCandidate {
+                open: open_count,
+                in_region,
+            })
+        };
+    }
+    candidates.sort();
+
+    let sees = |from: AxialVector, to: AxialVector| hexes_between(from, to).all(open);
+    // The distance from each candidate to the closest point, None once ruled out.
+    let mut distances = vec![Some(usize::MAX); candidates.len()];
+    let mut points = Vec::new();
+    while points.len() < n {
+        let farthest = distances
+            .iter()
+            .enumerate()
+            .filter_map(|(index, distance)| distance.map(|distance| (index, distance)))
+            .max_by_key(|(index, distance)| (*distance, std::cmp::Reverse(*index)));
+        let point = match farthest {
+            Some((index, _)) => candidates[index],
+            None => {
+                return Err(SpawnError::TooFew {
+                    found: points,
+                    asked: n,
+                })
+            }
+        };
+        points.push(point);
+        for (candidate, distance) in candidates.iter().zip(distances.iter_mut()) {
+            if let Some(previous) = *distance {
+                let to_point = candidate.distance(point) as usize;
+                *distance = if to_point == 0
+                    || to_point < constraints.min_distance
+                    || (constraints.hidden_from_each_other && sees(*candidate, point))
+                {
+                    None
+                } else {
+                    Some(previous.min(to_point))
+                };
+            }
+        }
+    }
+    Ok(points)
+}
+
+#[test]
+fn test_select_spawn_points() {
+    use crate::hex::ascii::parse_ascii;
+
+    let storage = parse_ascii(
+        &[
+            "# # # # # # # # # # #",
+            " # . . . . # . . . . #",
+            "# . . . . # . . . . #",
+            " # . . . . . . . . . #",
+            "# . . . . # . . . . #",
+            " # # # # # # # # # # #",
+        ]
+        .join("\n"),
+        |c| match c {
+            '.' => Some(true),
+            '#' => Some(false),
+            _ => None,
+        },
+    );
+    let open = |hex: &bool| *hex;
+    let select =
+        |n, constraints: &SpawnConstraints| select_spawn_points(&storage, n, constraints, open);
+
+    let points = select(3, &SpawnConstraints::default()).expect("spawn points");
+    assert_eq!(points.len(), 3);
+    assert!(points
+        .iter()
+        .all(|point| storage.get(*point) == Some(&true)));
+    // As far apart as possible.
+    assert_eq!(points[0].distance(points[1]), 10);
+    assert_eq!(select(3, &SpawnConstraints::default()), Ok(points));
+    assert_eq!(select(0, &SpawnConstraints::default()), Ok(Vec::new()));
+    // Every open hex once.
+    let open_count = storage.hexes().filter(|hex| **hex).count();
+    assert_eq!(
+        select(open_count, &SpawnConstraints::default())
+            .expect("all open hexes")
+            .into_iter()
+            .collect::<HexSet>()
+            .len(),
+        open_count
+    );
+    assert!(matches!(
+        select(open_count + 1, &SpawnConstraints::default()),
+        Err(SpawnError::TooFew { found, asked }) if found.len() == open_count
+            && asked == open_count + 1
+    ));
+
+    let apart = SpawnConstraints::default().with_min_distance(5);
+    let points = select(2, &apart).expect("apart");
+    assert!(points[0].distance(points[1]) >= 5);
+    let error = select(4, &apart).unwrap_err();
+    assert!(matches!(&error, SpawnError::TooFew { found, asked: 4 } if found.len() < 4));
+
+    let inner = SpawnConstraints::default().with_min_wall_distance(2);
+    for point in select(4, &inner).expect("inner") {
+        assert!(point
+            .ring_iter(1)
+            .all(|hex| storage.get(hex) == Some(&true)));
+    }
+    let error = select(1, &SpawnConstraints::default().with_min_wall_distance(3)).unwrap_err();
+    assert_eq!(
+        error,
+        SpawnError::NoCandidate {
+            open: open_count,
+            in_region: open_count
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "none of the {} open hexes of the region is far enough from the walls",
+            open_count
+        )
+    );
+
+    // Within the east room only.
+    let east = storage
+        .iter()
+        .filter(|(position, hex)| **hex && position.q() >= 6 - position.r() / 2)
+        .map(|(position, _)| position)
+        .collect::<HexSet>();
+    let in_east = SpawnConstraints::default().with_region(east.clone());
+    let points = select(3, &in_east).expect("east");
+    assert!(points.iter().all(|point| east.contains(*point)));
+    assert_eq!(
+        select(1, &SpawnConstraints::default().with_region(HexSet::new())),
+        Err(SpawnError::NoCandidate {
+            open: open_count,
+            in_region: 0
+        })
+    );
+
+    // One in each room, the doorway row aside.
+    let hidden = SpawnConstraints::default().with_hidden_from_each_other(true);
+    let points = select(2, &hidden).expect("hidden");
+    assert!(hexes_between(points[0], points[1]).any(|hex| storage.get(hex) == Some(&false)));
+    assert!(select(10, &hidden).is_err());
+}
@@ -0,0 +1,190 @@
+use crate::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+
+// Stable reference to a hex of a HexArena, it stays valid until that hex is removed and is never
+// reused for another hex afterwards.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct HexHandle {
+    index: u32,
+    generation: u32,
+}
+
+struct Slot<H> {
+    generation: u32,
+    hex: Option<(AxialVector, H)>,
+}
+
+// Hexes live in a vector of slots and the storage only maps the positions to their handles, so a
+// handle resolves to its position and hex without hashing. Freed slots are reused with the next
+// generation.
+pub struct HexArena<H> {
+    slots: Vec<Slot<H>>,
+    free_slots: Vec<u32>,
+    handles: RectHashStorage<HexHandle>,
+}
+
+impl<H> HexArena<H> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            handles: RectHashStorage::new(),
+        }
+    }
+
+    // Replaces the hex already at the position, if any, without changing its handle.
+    pub fn insert(&mut self, position: AxialVector, hex: H) -> HexHandle {
+        if let Some(handle) = self.handles.get(position).copied() {
+            self.slots[handle.index as usize].hex = Some((position, hex));
+            return handle;
+        }
+        let handle = if let Some(index) = self.free_slots.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.hex = Some((position, hex));
+            HexHandle {
+                index,
+                generation: slot.generation,
+            }
+        } else {
+            let index = self.slots.len() as u32;
+            self.slots.push(Slot {
+                generation: 0,
+                hex: Some((position, hex)),
+            });
+            HexHandle {
+                index,
+                generation: 0,
+            }
+        };
+        self.handles.insert(position, handle);
+        handle
+    }
+
+    pub fn handle(&self, position: AxialVector) -> Option<HexHandle> {
+        self.handles.get(position).copied()
+    }
+
+    pub fn contains(&self, handle: HexHandle) -> bool {
+        self.slot(handle).is_some()
+    }
+
+    pub fn get(&self, handle: HexHandle) -> Option<(AxialVector, &H)> {
+        self.slot(handle)
+            .and_then(|slot| slot.hex.as_ref())
+            .map(|(position, hex)| (*position, hex))
+    }
+
+    pub fn get_mut(&mut self, handle: HexHandle) -> Option<(AxialVector, &mut H)> {
+        self.slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.hex.as_mut())
+            .map(|(position, hex)| (*position, hex))
+    }
+
+    pub fn get_at(&self, position: AxialVector) -> Option<&H> {
+        self.handle(position)
+            .and_then(|handle| self.get(handle))
+            .map(|(_, hex)| hex)
+    }
+
+    pub fn remove(&mut self, handle: HexHandle) -> Option<(AxialVector, H)> {
+        let slot = self
+            .slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation && slot.hex.is_some())?;
+        let (position, hex) = slot.hex.take().expect("hex");
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(handle.index);
+        self.handles.remove(position);
+        Some((position, hex))
+    }
+
+    pub fn remove_at(&mut self, position: AxialVector) -> Option<H> {
+        self.handle(position)
+            .and_then(|handle| self.remove(handle))
+            .map(|(_, hex)| hex)
+    }
+
+    pub fn clear(&mut self) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.hex.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free_slots.push(index as u32);
+            }
+        }
+        self.handles.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (HexHandle, AxialVector, &H)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.hex.as_ref().map(|(position, hex)| {
+                let handle = HexHandle {
+                    index: index as u32,
+                    generation: slot.generation,
+                };
+                (handle, *position, hex)
+            })
+        })
+    }
+
+    fn slot(&self, handle: HexHandle) -> Option<&Slot<H>> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation && slot.hex.is_some())
+    }
+}
+
+impl<H> Default for HexArena<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_hex_arena_handles() {
+    let mut arena = HexArena::new();
+    let a = arena.insert(AxialVector::new(1, 2), "a");
+    let b = arena.insert(AxialVector::new(-3, 0), "b");
+    assert_ne!(a, b);
+    assert_eq!(arena.len(), 2);
+    assert_eq!(arena.get(a), Some((AxialVector::new(1, 2), &"a")));
+    assert_eq!(arena.handle(AxialVector::new(-3, 0)), Some(b));
+    assert_eq!(arena.get_at(AxialVector::new(-3, 0)), Some(&"b"));
+
+    // Replacing a hex keeps its handle.
+    assert_eq!(arena.insert(AxialVector::new(1, 2), "c"), a);
+    assert_eq!(arena.get(a), Some((AxialVector::new(1, 2), &"c")));
+    if let Some((_, hex)) = arena.get_mut(b) {
+        *hex = "d";
+    }
+    assert_eq!(arena.get_at(AxialVector::new(-3, 0)), Some(&"d"));
+
+    // Removed handles are never resolved again, even when the slot is reused.
+    assert_eq!(arena.remove(a), Some((AxialVector::new(1, 2), "c")));
+    assert!(!arena.contains(a));
+    assert_eq!(arena.remove(a), None);
+    let e = arena.insert(AxialVector::new(1, 2), "e");
+    assert_ne!(e, a);
+    assert_eq!(arena.get(a), None);
+    assert_eq!(arena.get(e), Some((AxialVector::new(1, 2), &"e")));
+    assert_eq!(arena.get(b), Some((AxialVector::new(-3, 0), &"d")));
+
+    let mut hexes = arena.iter().map(|(_, _, hex)| *hex).collect::<Vec<_>>();
+    hexes.sort();
+    assert_eq!(hexes, vec!["d", "e"]);
+    assert_eq!(arena.remove_at(AxialVector::new(-3, 0)), Some("d"));
+    assert!(!arena.contains(b));
+
+    arena.clear();
+    assert!(arena.is_empty());
+    assert!(!arena.contains(e));
+    assert_eq!(arena.iter().count(), 0);
+}
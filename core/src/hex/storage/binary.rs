@@ -0,0 +1,119 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+pub const BINARY_MAGIC: [u8; 4] = *b"RHSB";
+pub const BINARY_VERSION: u8 = 1;
+
+pub trait BinaryHex: Sized {
+    fn write_hex<W: Write>(&self, writer: &mut W) -> Result<()>;
+
+    fn read_hex<R: Read>(reader: &mut R) -> Result<Self>;
+}
+
+macro_rules! impl_binary_hex_for_int {
+    ($($int:ty),+) => {
+        $(
+            impl BinaryHex for $int {
+                fn write_hex<W: Write>(&self, writer: &mut W) -> Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn read_hex<R: Read>(reader: &mut R) -> Result<Self> {
+                    let mut bytes = [0; std::mem::size_of::<$int>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$int>::from_le_bytes(bytes))
+                }
+            }
+        )+
+    };
+}
+
+impl_binary_hex_for_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl BinaryHex for bool {
+    fn write_hex<W: Write>(&self, writer: &mut W) -> Result<()> {
+        (*self as u8).write_hex(writer)
+    }
+
+    fn read_hex<R: Read>(reader: &mut R) -> Result<Self> {
+        match u8::read_hex(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(invalid_data(format!("Invalid bool value {}", value))),
+        }
+    }
+}
+
+impl BinaryHex for () {
+    fn write_hex<W: Write>(&self, _writer: &mut W) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_hex<R: Read>(_reader: &mut R) -> Result<Self> {
+        Ok(())
+    }
+}
+
+impl<T1: BinaryHex, T2: BinaryHex> BinaryHex for (T1, T2) {
+    fn write_hex<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.0.write_hex(writer)?;
+        self.1.write_hex(writer)
+    }
+
+    fn read_hex<R: Read>(reader: &mut R) -> Result<Self> {
+        let t1 = T1::read_hex(reader)?;
+        let t2 = T2::read_hex(reader)?;
+        Ok((t1, t2))
+    }
+}
+
+pub(crate) fn write_header<W: Write>(writer: &mut W) -> Result<()> {
+    writer.write_all(&BINARY_MAGIC)?;
+    BINARY_VERSION.write_hex(writer)
+}
+
+pub(crate) fn read_header<R: Read>(reader: &mut R) -> Result<()> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != BINARY_MAGIC {
+        return Err(invalid_data("Invalid magic number".to_owned()));
+    }
+    let version = u8::read_hex(reader)?;
+    if version != BINARY_VERSION {
+        return Err(invalid_data(format!("Unsupported version {}", version)));
+    }
+    Ok(())
+}
+
+pub(crate) fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[test]
+fn test_binary_hex_int_round_trip() {
+    let mut buffer = Vec::new();
+    0x1234u16.write_hex(&mut buffer).unwrap();
+    (-42i64).write_hex(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), 10);
+    let mut reader = buffer.as_slice();
+    assert_eq!(u16::read_hex(&mut reader).unwrap(), 0x1234);
+    assert_eq!(i64::read_hex(&mut reader).unwrap(), -42);
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_binary_hex_should_reject_invalid_bool() {
+    let mut reader: &[u8] = &[2];
+    assert_eq!(
+        bool::read_hex(&mut reader).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn test_binary_header_should_reject_bad_version() {
+    let mut reader: &[u8] = &[b'R', b'H', b'S', b'B', BINARY_VERSION + 1];
+    assert_eq!(
+        read_header(&mut reader).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
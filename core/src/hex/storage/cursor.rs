@@ -0,0 +1,205 @@
+use crate::{
+    hex::{
+        coordinates::{axial::AxialVector, direction::HexagonalDirection},
+        storage::{
+            hash::RectHashStorage,
+            rect::{RectStorage, RECT_X_LEN, RECT_Y_LEN},
+        },
+    },
+    vector::Vector2ISize,
+};
+use std::hash::BuildHasher;
+
+// The origin of the rect of a position and where the position is in it.
+fn locate(position: AxialVector) -> (Vector2ISize, usize, usize) {
+    (
+        Vector2ISize {
+            x: position.q().div_euclid(RECT_X_LEN as isize),
+            y: position.r().div_euclid(RECT_Y_LEN as isize),
+        },
+        position.q().rem_euclid(RECT_X_LEN as isize) as usize,
+        position.r().rem_euclid(RECT_Y_LEN as isize) as usize,
+    )
+}
+
+// The coordinates after the step when it stays in the rect.
+fn step_in_rect(x: usize, y: usize, step: AxialVector) -> Option<(usize, usize)> {
+    let x = (x as isize + step.q()) as usize;
+    let y = (y as isize + step.r()) as usize;
+    if x < RECT_X_LEN && y < RECT_Y_LEN {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+// Walks a storage from hex to hex, e.g. along a path, a ring or a scanline, keeping the rect of the
+// current hex: only moving to another rect looks it up, which happens once every few steps at
+// most.
+pub struct Cursor<'a, H, S> {
+    storage: &'a RectHashStorage<H, S>,
+    position: AxialVector,
+    origin: Vector2ISize,
+    x: usize,
+    y: usize,
+    rect: Option<&'a RectStorage<H>>,
+}
+
+impl<'a, H, S: BuildHasher> Cursor<'a, H, S> {
+    pub fn new(storage: &'a RectHashStorage<H, S>, position: AxialVector) -> Self {
+        let (origin, x, y) = locate(position);
+        Self {
+            storage,
+            position,
+            origin,
+            x,
+            y,
+            rect: storage.rect(origin),
+        }
+    }
+
+    pub fn position(&self) -> AxialVector {
+        self.position
+    }
+
+    pub fn move_to(&mut self, direction: usize) {
+        let step = AxialVector::direction(direction);
+        match step_in_rect(self.x, self.y, step) {
+            Some((x, y)) => {
+                self.position += step;
+                self.x = x;
+                self.y = y;
+            }
+            None => self.jump(self.position + step),
+        }
+    }
+
+    // Anywhere, the rect being only looked up when it changes.
+    pub fn jump(&mut self, position: AxialVector) {
+        let (origin, x, y) = locate(position);
+        if origin != self.origin {
+            self.origin = origin;
+            self.rect = self.storage.rect(origin);
+        }
+        self.position = position;
+        self.x = x;
+        self.y = y;
+    }
+
+    pub fn get(&self) -> Option<&'a H> {
+        self.rect.and_then(|rect| rect.get(self.x, self.y))
+    }
+}
+
+// Same as Cursor, the current hex being mutable. The hexes are never added nor removed so that
+// the rect kept stays where it is.
+pub struct CursorMut<'a, H, S> {
+    storage: &'a mut RectHashStorage<H, S>,
+    position: AxialVector,
+    origin: Vector2ISize,
+    x: usize,
+    y: usize,
+    rect: Option<*mut RectStorage<H>>,
+}
+
+impl<'a, H, S: BuildHasher> CursorMut<'a, H, S> {
+    pub fn new(storage: &'a mut RectHashStorage<H, S>, position: AxialVector) -> Self {
+        let (origin, x, y) = locate(position);
+        let rect = storage
+            .rect_mut(origin)
+            .map(|rect| rect as *mut RectStorage<H>);
+        Self {
+            storage,
+            position,
+            origin,
+            x,
+            y,
+            rect,
+        }
+    }
+
+    pub fn position(&self) -> AxialVector {
+        self.position
+    }
+
+    pub fn move_to(&mut self, direction: usize) {
+        let step = AxialVector::direction(direction);
+        match step_in_rect(self.x, self.y, step) {
+            Some((x, y)) => {
+                self.position += step;
+                self.x = x;
+                self.y = y;
+            }
+            None => self.jump(self.position + step),
+        }
+    }
+
+    pub fn jump(&mut self, position: AxialVector) {
+        let (origin, x, y) = locate(position);
+        if origin != self.origin {
+            self.origin = origin;
+            self.rect = self
+                .storage
+                .rect_mut(origin)
+                .map(|rect| rect as *mut RectStorage<H>);
+        }
+        self.position = position;
+        self.x = x;
+        self.y = y;
+    }
+
+    pub fn get(&self) -> Option<&H> {
+        // The storage is borrowed by the cursor, the rect cannot move nor go away.
+        self.rect
+            .and_then(|rect| unsafe { &*rect }.get(self.x, self.y))
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut H> {
+        self.rect
+            .and_then(|rect| unsafe { &mut *rect }.get_mut(self.x, self.y))
+    }
+}
+
+#[test]
+fn test_cursor_walk() {
+    let mut storage = RectHashStorage::new();
+    let center = AxialVector::new(-3, 9);
+    for position in center.spiral_iter(12) {
+        if position.q().rem_euclid(5) != 0 {
+            storage.insert(position, position);
+        }
+    }
+
+    // Along the rings, across many rects and the missing hexes.
+    let mut cursor = storage.cursor(center);
+    assert_eq!(cursor.get(), Some(&center));
+    for radius in 1..=13 {
+        cursor.jump(center + AxialVector::direction(4) * radius as isize);
+        for direction in 0..6 {
+            for _ in 0..radius {
+                assert_eq!(cursor.get(), storage.get(cursor.position()));
+                cursor.move_to(direction);
+            }
+        }
+        assert_eq!(
+            cursor.position(),
+            center + AxialVector::direction(4) * radius as isize
+        );
+    }
+
+    let mut cursor = storage.cursor_mut(center);
+    for _ in 0..20 {
+        if let Some(hex) = cursor.get_mut() {
+            *hex = AxialVector::default();
+        }
+        cursor.move_to(0);
+    }
+    assert_eq!(cursor.position(), center + AxialVector::new(20, 0));
+    assert_eq!(cursor.get(), None);
+    let cleared = storage
+        .iter()
+        .filter(|(_, hex)| **hex == AxialVector::default())
+        .count();
+    // The hexes of the row up to the edge of the spiral, but the ones at q = 0 and q = 5.
+    assert_eq!(cleared, 13 - 2);
+}
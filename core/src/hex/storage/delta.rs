@@ -0,0 +1,202 @@
+use crate::hex::{
+    coordinates::axial::AxialVector,
+    storage::{
+        binary::{invalid_data, read_header, write_header, BinaryHex},
+        hash::RectHashStorage,
+    },
+};
+use std::{
+    collections::BTreeMap,
+    io::{Read, Result, Write},
+};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum HexChange<H> {
+    Set(H),
+    Remove,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StorageDelta<H> {
+    changes: BTreeMap<AxialVector, HexChange<H>>,
+}
+
+impl<H> StorageDelta<H> {
+    pub fn new() -> Self {
+        Self {
+            changes: BTreeMap::new(),
+        }
+    }
+
+    pub fn record_set(&mut self, position: AxialVector, hex: H) {
+        self.changes.insert(position, HexChange::Set(hex));
+    }
+
+    pub fn record_remove(&mut self, position: AxialVector) {
+        self.changes.insert(position, HexChange::Remove);
+    }
+
+    pub fn get(&self, position: AxialVector) -> Option<&HexChange<H>> {
+        self.changes.get(&position)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AxialVector, &HexChange<H>)> {
+        self.changes
+            .iter()
+            .map(|(position, change)| (*position, change))
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.changes.extend(other.changes);
+    }
+
+    pub fn apply_into(self, storage: &mut RectHashStorage<H>) {
+        for (position, change) in self.changes {
+            match change {
+                HexChange::Set(hex) => {
+                    storage.insert(position, hex);
+                }
+                HexChange::Remove => {
+                    storage.remove(position);
+                }
+            }
+        }
+    }
+}
+
+impl<H: Clone + PartialEq> StorageDelta<H> {
+    pub fn between(old: &RectHashStorage<H>, new: &RectHashStorage<H>) -> Self {
+        let mut delta = Self::new();
+        for (position, hex) in new.iter() {
+            if old.get(position) != Some(hex) {
+                delta.record_set(position, hex.clone());
+            }
+        }
+        for position in old.positions() {
+            if !new.contains_position(position) {
+                delta.record_remove(position);
+            }
+        }
+        delta
+    }
+}
+
+impl<H: Clone> StorageDelta<H> {
+    pub fn apply(&self, storage: &mut RectHashStorage<H>) {
+        self.clone().apply_into(storage);
+    }
+}
+
+impl<H> Default for StorageDelta<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const CHANGE_SET: u8 = 0;
+const CHANGE_REMOVE: u8 = 1;
+
+impl<H: BinaryHex> StorageDelta<H> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_header(writer)?;
+        (self.changes.len() as u64).write_hex(writer)?;
+        for (position, change) in &self.changes {
+            (position.q() as i64).write_hex(writer)?;
+            (position.r() as i64).write_hex(writer)?;
+            match change {
+                HexChange::Set(hex) => {
+                    CHANGE_SET.write_hex(writer)?;
+                    hex.write_hex(writer)?;
+                }
+                HexChange::Remove => {
+                    CHANGE_REMOVE.write_hex(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        read_header(reader)?;
+        let mut delta = Self::new();
+        let changes_len = u64::read_hex(reader)?;
+        for _ in 0..changes_len {
+            let q = i64::read_hex(reader)? as isize;
+            let r = i64::read_hex(reader)? as isize;
+            let position = AxialVector::new(q, r);
+            match u8::read_hex(reader)? {
+                CHANGE_SET => delta.record_set(position, H::read_hex(reader)?),
+                CHANGE_REMOVE => delta.record_remove(position),
+                tag => return Err(invalid_data(format!("Invalid change tag {}", tag))),
+            }
+        }
+        Ok(delta)
+    }
+}
+
+#[test]
+fn test_storage_delta_between_storages() {
+    let mut old = RectHashStorage::new();
+    old.insert(AxialVector::new(0, 0), 1u32);
+    old.insert(AxialVector::new(12, -42), 2);
+    old.insert(AxialVector::new(-5, 24), 3);
+
+    let mut new = RectHashStorage::new();
+    new.insert(AxialVector::new(0, 0), 1u32);
+    new.insert(AxialVector::new(12, -42), 20);
+    new.insert(AxialVector::new(100, 1), 4);
+
+    let delta = StorageDelta::between(&old, &new);
+    assert_eq!(
+        delta.iter().collect::<Vec<_>>(),
+        vec![
+            (AxialVector::new(-5, 24), &HexChange::Remove),
+            (AxialVector::new(12, -42), &HexChange::Set(20)),
+            (AxialVector::new(100, 1), &HexChange::Set(4)),
+        ]
+    );
+
+    delta.apply(&mut old);
+    assert!(StorageDelta::between(&old, &new).is_empty());
+    assert_eq!(old.len(), 3);
+}
+
+#[test]
+fn test_storage_delta_accumulation() {
+    let mut delta = StorageDelta::new();
+    delta.record_set(AxialVector::new(1, 1), 1u8);
+    delta.record_remove(AxialVector::new(1, 1));
+    delta.record_set(AxialVector::new(2, 2), 2);
+    assert_eq!(delta.len(), 2);
+    assert_eq!(delta.get(AxialVector::new(1, 1)), Some(&HexChange::Remove));
+
+    let mut storage = RectHashStorage::new();
+    storage.insert(AxialVector::new(1, 1), 0u8);
+    delta.apply_into(&mut storage);
+    assert_eq!(storage.get(AxialVector::new(1, 1)), None);
+    assert_eq!(storage.get(AxialVector::new(2, 2)), Some(&2));
+}
+
+#[test]
+fn test_storage_delta_should_write_and_read_binary() {
+    let mut delta = StorageDelta::new();
+    delta.record_set(AxialVector::new(12, -42), (7u16, true));
+    delta.record_remove(AxialVector::new(-5, 24));
+
+    let mut buffer = Vec::new();
+    delta.write_to(&mut buffer).unwrap();
+    let read = StorageDelta::<(u16, bool)>::read_from(&mut buffer.as_slice()).unwrap();
+    assert_eq!(read, delta);
+}
@@ -0,0 +1,144 @@
+use crate::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+use std::fmt::{Debug, Display, Formatter, Result};
+
+const MAX_REPORTED_POSITIONS: usize = 20;
+
+pub struct StorageDiff<'a, H> {
+    pub only_in_left: Vec<AxialVector>,
+    pub only_in_right: Vec<AxialVector>,
+    pub different: Vec<(AxialVector, &'a H, &'a H)>,
+}
+
+impl<'a, H> StorageDiff<'a, H> {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_left.is_empty() && self.only_in_right.is_empty() && self.different.is_empty()
+    }
+}
+
+pub fn diff_storages<'a, H: PartialEq>(
+    left: &'a RectHashStorage<H>,
+    right: &'a RectHashStorage<H>,
+) -> StorageDiff<'a, H> {
+    let mut only_in_left = Vec::new();
+    let mut different = Vec::new();
+    for (position, left_hex) in left.iter() {
+        match right.get(position) {
+            Some(right_hex) => {
+                if left_hex != right_hex {
+                    different.push((position, left_hex, right_hex));
+                }
+            }
+            None => only_in_left.push(position),
+        }
+    }
+    let mut only_in_right = right
+        .positions()
+        .filter(|position| !left.contains_position(*position))
+        .collect::<Vec<_>>();
+    only_in_left.sort();
+    only_in_right.sort();
+    different.sort_by_key(|(position, _, _)| *position);
+    StorageDiff {
+        only_in_left,
+        only_in_right,
+        different,
+    }
+}
+
+fn fmt_positions(f: &mut Formatter<'_>, title: &str, positions: &[AxialVector]) -> Result {
+    if positions.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{} ({}):", title, positions.len())?;
+    for position in positions.iter().take(MAX_REPORTED_POSITIONS) {
+        writeln!(f, "    ({}, {})", position.q(), position.r())?;
+    }
+    if positions.len() > MAX_REPORTED_POSITIONS {
+        writeln!(f, "    ...")?;
+    }
+    Ok(())
+}
+
+impl<'a, H: Debug> Display for StorageDiff<'a, H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.is_empty() {
+            return writeln!(f, "storages are equal");
+        }
+        fmt_positions(f, "only in left", &self.only_in_left)?;
+        fmt_positions(f, "only in right", &self.only_in_right)?;
+        if !self.different.is_empty() {
+            writeln!(f, "different ({}):", self.different.len())?;
+            for (position, left, right) in self.different.iter().take(MAX_REPORTED_POSITIONS) {
+                writeln!(
+                    f,
+                    "    ({}, {}): {:?} != {:?}",
+                    position.q(),
+                    position.r(),
+                    left,
+                    right
+                )?;
+            }
+            if self.different.len() > MAX_REPORTED_POSITIONS {
+                writeln!(f, "    ...")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[macro_export]
+macro_rules! assert_storage_eq {
+    ($left:expr, $right:expr) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let diff = $crate::hex::storage::diff::diff_storages(left, right);
+                if !diff.is_empty() {
+                    panic!("assertion failed: storages differ\n{}", diff);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+fn test_storages() -> (RectHashStorage<usize>, RectHashStorage<usize>) {
+    let mut left = RectHashStorage::new();
+    let mut right = RectHashStorage::new();
+    for (x, y, value) in [(12, -42, 42), (-5, 24, 7), (0, 0, 1)].iter() {
+        left.insert(AxialVector::new(*x, *y), *value);
+        right.insert(AxialVector::new(*x, *y), *value);
+    }
+    (left, right)
+}
+
+#[test]
+fn test_diff_equal_storages() {
+    let (left, right) = test_storages();
+    let diff = diff_storages(&left, &right);
+    assert!(diff.is_empty());
+    assert_storage_eq!(left, right);
+}
+
+#[test]
+fn test_diff_storages() {
+    let (mut left, mut right) = test_storages();
+    left.insert(AxialVector::new(3, 3), 3);
+    right.insert(AxialVector::new(-3, -3), 3);
+    right.insert(AxialVector::new(0, 0), 2);
+    let diff = diff_storages(&left, &right);
+    assert_eq!(diff.only_in_left, vec![AxialVector::new(3, 3)]);
+    assert_eq!(diff.only_in_right, vec![AxialVector::new(-3, -3)]);
+    assert_eq!(diff.different, vec![(AxialVector::new(0, 0), &1, &2)]);
+    assert_eq!(
+        diff.to_string(),
+        "only in left (1):\n    (3, 3)\nonly in right (1):\n    (-3, -3)\ndifferent (1):\n    (0, 0): 1 != 2\n"
+    );
+}
+
+#[test]
+#[should_panic(expected = "(0, 0): 1 != 2")]
+fn test_assert_storage_eq_should_panic() {
+    let (left, mut right) = test_storages();
+    right.insert(AxialVector::new(0, 0), 2);
+    assert_storage_eq!(left, right);
+}
@@ -1,27 +1,197 @@
 use crate::{
     hex::{
-        coordinates::{axial::AxialVector, direction::HexagonalDirection},
+        coordinates::{axial::AxialVector, direction::HexagonalDirection, transform::HexTransform},
+        shape::{cubic_range::Range, Shape},
         storage::{
             adjacent::{HexWithAdjacents, HexWithAdjacentsMut},
+            binary::{invalid_data, read_header, write_header, BinaryHex},
+            cursor::{Cursor, CursorMut},
             rect::{
-                RectEntry, RectOccupiedEntry, RectStorage, RectVacantEntry, RECT_X_LEN, RECT_Y_LEN,
+                count_neighbors, RectEntry, RectOccupiedEntry, RectStorage, RectVacantEntry,
+                RECT_X_LEN, RECT_Y_LEN,
             },
+            set::HexSet,
         },
     },
     vector::Vector2ISize,
 };
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::{self, Display, Formatter},
+    hash::{BuildHasher, Hash, Hasher},
+    io::{Read, Result, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+#[cfg(feature = "fxhash")]
+pub type HexBuildHasher = std::hash::BuildHasherDefault<rustc_hash::FxHasher>;
+
+#[cfg(all(feature = "ahash", not(feature = "fxhash")))]
+pub type HexBuildHasher = ahash::RandomState;
+
+#[cfg(not(any(feature = "fxhash", feature = "ahash")))]
+pub type HexBuildHasher = std::collections::hash_map::RandomState;
+
+pub(crate) fn rect_position(rect_origin: Vector2ISize, x: usize, y: usize) -> AxialVector {
+    AxialVector::new(
+        rect_origin.x * RECT_X_LEN as isize + x as isize,
+        rect_origin.y * RECT_Y_LEN as isize + y as isize,
+    )
+}
+
+// The rects holding a hex and its adjacents, looked up once: its own rect and, only when the hex
+// is on their edges, the rects across the q edge, the r edge and the corner of its rect (the
+// directions (1, -1) and (-1, 1) are the only ones crossing a corner). At most 4 rects, most hexes
+// needing only theirs.
+struct AdjacentRects<R> {
+    x: isize,
+    y: isize,
+    rects: [Option<R>; 4],
+}
+
+impl<R: Copy> AdjacentRects<R> {
+    #[inline]
+    fn new<L>(position: AxialVector, mut lookup: L) -> Self
+    where
+        L: FnMut(Vector2ISize) -> Option<R>,
+    {
+        let origin = Vector2ISize {
+            x: position.q().div_euclid(RECT_X_LEN as isize),
+            y: position.r().div_euclid(RECT_Y_LEN as isize),
+        };
+        let x = position.q().rem_euclid(RECT_X_LEN as isize);
+        let y = position.r().rem_euclid(RECT_Y_LEN as isize);
+        let edge = |coordinate: isize, len: usize| match coordinate {
+            0 => -1,
+            c if c == len as isize - 1 => 1,
+            _ => 0,
+        };
+        let (edge_x, edge_y) = (edge(x, RECT_X_LEN), edge(y, RECT_Y_LEN));
+        let mut rects = [lookup(origin), None, None, None];
+        if edge_x != 0 {
+            rects[1] = lookup(Vector2ISize {
+                x: origin.x + edge_x,
+                y: origin.y,
+            });
+        }
+        if edge_y != 0 {
+            rects[2] = lookup(Vector2ISize {
+                x: origin.x,
+                y: origin.y + edge_y,
+            });
+        }
+        if edge_x != 0 && edge_y == -edge_x {
+            rects[3] = lookup(Vector2ISize {
+                x: origin.x + edge_x,
+                y: origin.y + edge_y,
+            });
+        }
+        Self { x, y, rects }
+    }
+
+    // The rect of the hex at the offset, a direction or zero, and where it is in it.
+    #[inline]
+    fn locate(&self, offset: AxialVector) -> (Option<R>, usize, usize) {
+        let x = self.x + offset.q();
+        let y = self.y + offset.r();
+        let across_x = x as usize >= RECT_X_LEN;
+        let across_y = y as usize >= RECT_Y_LEN;
+        (
+            self.rects[across_x as usize | (across_y as usize) << 1],
+            x.rem_euclid(RECT_X_LEN as isize) as usize,
+            y.rem_euclid(RECT_Y_LEN as isize) as usize,
+        )
+    }
+}
+
+// FNV-1a, whose digests depend neither on the platform nor on the Rust version: integers are
+// hashed as their little endian bytes, sizes as 64 bits.
+#[derive(Clone, Copy, Debug)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
 
-pub struct RectHashStorage<H> {
-    rects: HashMap<Vector2ISize, RectStorage<H>>,
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+// What becomes of the rects left empty by remove(). Keeping them saves reallocating them when
+// hexes come back, pruning them gives the memory back to storages which shrink over time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum EmptyRectPolicy {
+    Keep,
+    Prune,
+}
+
+pub struct RectHashStorage<H, S = HexBuildHasher> {
+    rects: HashMap<Vector2ISize, RectStorage<H>, S>,
     len: usize,
+    empty_rect_policy: EmptyRectPolicy,
 }
 
 impl<H> RectHashStorage<H> {
     pub fn new() -> Self {
+        Self::with_hasher(HexBuildHasher::default())
+    }
+}
+
+impl<H, S: BuildHasher> RectHashStorage<H, S> {
+    pub fn with_hasher(hasher: S) -> Self {
         Self {
-            rects: HashMap::new(),
+            rects: HashMap::with_hasher(hasher),
             len: 0,
+            empty_rect_policy: EmptyRectPolicy::Keep,
+        }
+    }
+
+    pub fn empty_rect_policy(&self) -> EmptyRectPolicy {
+        self.empty_rect_policy
+    }
+
+    // Switching to Prune also prunes the rects which are already empty.
+    pub fn set_empty_rect_policy(&mut self, policy: EmptyRectPolicy) {
+        self.empty_rect_policy = policy;
+        if policy == EmptyRectPolicy::Prune {
+            self.prune_empty_rects();
         }
     }
 
@@ -103,6 +273,58 @@ impl<H> RectHashStorage<H> {
         self.rects.values().flat_map(|rect| rect.hexes())
     }
 
+    // The chunks and their origins, in rects: the hex (x, y) of the rect at the origin (i, j) is
+    // at (i * RECT_X_LEN + x, j * RECT_Y_LEN + y).
+    pub fn rects(&self) -> impl Iterator<Item = (Vector2ISize, &RectStorage<H>)> {
+        self.rects
+            .iter()
+            .map(|(rect_origin, rect)| (*rect_origin, rect))
+    }
+
+    pub(crate) fn rects_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (Vector2ISize, &mut RectStorage<H>)> {
+        self.rects
+            .iter_mut()
+            .map(|(rect_origin, rect)| (*rect_origin, rect))
+    }
+
+    pub fn rect(&self, rect_origin: Vector2ISize) -> Option<&RectStorage<H>> {
+        self.rects.get(&rect_origin)
+    }
+
+    pub(crate) fn rect_mut(&mut self, rect_origin: Vector2ISize) -> Option<&mut RectStorage<H>> {
+        self.rects.get_mut(&rect_origin)
+    }
+
+    pub fn cursor(&self, position: AxialVector) -> Cursor<'_, H, S> {
+        Cursor::new(self, position)
+    }
+
+    pub fn cursor_mut(&mut self, position: AxialVector) -> CursorMut<'_, H, S> {
+        CursorMut::new(self, position)
+    }
+
+    // The hexes inside the shape only, without scanning the chunks outside of its bounds.
+    pub fn view<'a, T: Shape>(&'a self, shape: &'a T) -> RectHashStorageView<'a, H, S, T> {
+        RectHashStorageView {
+            bounds: shape.bounds(),
+            storage: self,
+            shape,
+        }
+    }
+
+    pub fn view_mut<'a, T: Shape>(
+        &'a mut self,
+        shape: &'a T,
+    ) -> RectHashStorageViewMut<'a, H, S, T> {
+        RectHashStorageViewMut {
+            bounds: shape.bounds(),
+            storage: self,
+            shape,
+        }
+    }
+
     pub fn hexes_mut(&mut self) -> impl Iterator<Item = &mut H> {
         self.rects.values_mut().flat_map(|rect| rect.hexes_mut())
     }
@@ -142,39 +364,19 @@ impl<H> RectHashStorage<H> {
     }
 
     pub fn hex_with_adjacents(&self, position: AxialVector) -> HexWithAdjacents<Option<&H>, H> {
-        let mut rects_len = 0;
-        let mut rects: [(Vector2ISize, Option<&RectStorage<H>>); 4] = Default::default();
-        let mut get = |pos: AxialVector| -> Option<&H> {
-            let x = pos.q().div_euclid(RECT_X_LEN as isize);
-            let y = pos.r().div_euclid(RECT_Y_LEN as isize);
-            let rect_pos = Vector2ISize { x, y };
-            let mut index = 0;
-            while index < rects_len && rects[index].0 != rect_pos {
-                index += 1;
-            }
-            if index >= rects_len {
-                if index < rects.len() {
-                    rects[index] = (rect_pos, self.rects.get(&rect_pos));
-                    rects_len += 1;
-                } else {
-                    unreachable!();
-                }
-            }
-            rects[index].1.and_then(|rect| {
-                rect.get(
-                    pos.q().rem_euclid(RECT_X_LEN as isize) as usize,
-                    pos.r().rem_euclid(RECT_Y_LEN as isize) as usize,
-                )
-            })
+        let rects = AdjacentRects::new(position, |origin| self.rects.get(&origin));
+        let get = |offset: AxialVector| {
+            let (rect, x, y) = rects.locate(offset);
+            rect.and_then(|rect| rect.get(x, y))
         };
         HexWithAdjacents::new(
-            get(position),
-            get(position + AxialVector::direction(0)),
-            get(position + AxialVector::direction(1)),
-            get(position + AxialVector::direction(2)),
-            get(position + AxialVector::direction(3)),
-            get(position + AxialVector::direction(4)),
-            get(position + AxialVector::direction(5)),
+            get(AxialVector::default()),
+            get(AxialVector::direction(0)),
+            get(AxialVector::direction(1)),
+            get(AxialVector::direction(2)),
+            get(AxialVector::direction(3)),
+            get(AxialVector::direction(4)),
+            get(AxialVector::direction(5)),
         )
     }
 
@@ -182,44 +384,26 @@ impl<H> RectHashStorage<H> {
         &mut self,
         position: AxialVector,
     ) -> HexWithAdjacentsMut<Option<&mut H>, H> {
-        let mut rects_len = 0;
-        let mut rects: [(Vector2ISize, Option<&mut RectStorage<H>>); 4] = Default::default();
-        let mut get = |pos: AxialVector| -> Option<&mut H> {
-            let x = pos.q().div_euclid(RECT_X_LEN as isize);
-            let y = pos.r().div_euclid(RECT_Y_LEN as isize);
-            let rect_pos = Vector2ISize { x, y };
-            let mut index = 0;
-            while index < rects_len && rects[index].0 != rect_pos {
-                index += 1;
-            }
-            if index >= rects_len {
-                if index < rects.len() {
-                    rects[index] = (
-                        rect_pos,
-                        unsafe { &mut *(self as *mut Self) }
-                            .rects
-                            .get_mut(&rect_pos),
-                    );
-                    rects_len += 1;
-                } else {
-                    unreachable!();
-                }
-            }
-            rects[index].1.as_mut().and_then(|rect| {
-                unsafe { &mut *(*rect as *mut RectStorage<H>) }.get_mut(
-                    pos.q().rem_euclid(RECT_X_LEN as isize) as usize,
-                    pos.r().rem_euclid(RECT_Y_LEN as isize) as usize,
-                )
-            })
+        let rects = AdjacentRects::new(position, |origin| {
+            self.rects
+                .get_mut(&origin)
+                .map(|rect| rect as *mut RectStorage<H>)
+        });
+        // The rects are distinct and so are the seven positions, the hexes are borrowed once
+        // each, and for as long as self.
+        let get = |offset: AxialVector| {
+            let (rect, x, y) = rects.locate(offset);
+            rect.and_then(|rect| unsafe { RectStorage::get_ptr_mut(rect, x, y) })
+                .map(|hex| unsafe { &mut *hex })
         };
         HexWithAdjacentsMut::new(
-            get(position),
-            get(position + AxialVector::direction(0)),
-            get(position + AxialVector::direction(1)),
-            get(position + AxialVector::direction(2)),
-            get(position + AxialVector::direction(3)),
-            get(position + AxialVector::direction(4)),
-            get(position + AxialVector::direction(5)),
+            get(AxialVector::default()),
+            get(AxialVector::direction(0)),
+            get(AxialVector::direction(1)),
+            get(AxialVector::direction(2)),
+            get(AxialVector::direction(3)),
+            get(AxialVector::direction(4)),
+            get(AxialVector::direction(5)),
         )
     }
 
@@ -244,19 +428,22 @@ impl<H> RectHashStorage<H> {
     pub fn remove(&mut self, position: AxialVector) -> Option<H> {
         let x = position.q().div_euclid(RECT_X_LEN as isize);
         let y = position.r().div_euclid(RECT_Y_LEN as isize);
-        let mut hex = None;
-        self.rects.entry(Vector2ISize { x, y }).and_modify(|rect| {
-            hex = rect.remove(
-                position.q().rem_euclid(RECT_X_LEN as isize) as usize,
-                position.r().rem_euclid(RECT_Y_LEN as isize) as usize,
-            );
-        });
+        let rect_origin = Vector2ISize { x, y };
+        let rect = self.rects.get_mut(&rect_origin)?;
+        let hex = rect.remove(
+            position.q().rem_euclid(RECT_X_LEN as isize) as usize,
+            position.r().rem_euclid(RECT_Y_LEN as isize) as usize,
+        );
         if hex.is_some() {
             self.len -= 1;
+            if rect.is_empty() && self.empty_rect_policy == EmptyRectPolicy::Prune {
+                self.rects.remove(&rect_origin);
+            }
         }
         hex
     }
 
+    // The rects are kept for the hexes to come, whatever the policy.
     pub fn clear(&mut self) {
         for rect in &mut self.rects.values_mut() {
             rect.clear();
@@ -264,6 +451,134 @@ impl<H> RectHashStorage<H> {
         self.len = 0;
     }
 
+    // Drops the rects too and gives back the memory of the hash map, for storages regenerated
+    // from scratch which should not hold on to their peak size.
+    pub fn clear_and_shrink(&mut self) {
+        self.rects.clear();
+        self.rects.shrink_to_fit();
+        self.len = 0;
+    }
+
+    pub fn prune_empty_rects(&mut self) {
+        self.rects.retain(|_, rect| !rect.is_empty());
+    }
+
+    // Moves every hex by the offset. The rects move as a whole when the offset is a multiple of
+    // their size, otherwise each of them is split into the (up to) 4 rects it then overlaps, which
+    // are only looked up once.
+    pub fn translate(&mut self, offset: AxialVector) {
+        let rect_offset = Vector2ISize {
+            x: offset.q().div_euclid(RECT_X_LEN as isize),
+            y: offset.r().div_euclid(RECT_Y_LEN as isize),
+        };
+        let dx = offset.q().rem_euclid(RECT_X_LEN as isize) as usize;
+        let dy = offset.r().rem_euclid(RECT_Y_LEN as isize) as usize;
+        let rects = self.rects.drain().collect::<Vec<_>>();
+        for (rect_origin, mut rect) in rects {
+            let rect_origin = Vector2ISize {
+                x: rect_origin.x + rect_offset.x,
+                y: rect_origin.y + rect_offset.y,
+            };
+            if dx == 0 && dy == 0 {
+                self.rects.insert(rect_origin, rect);
+                continue;
+            }
+            let mut parts = (0..4).map(|_| RectStorage::new()).collect::<Vec<_>>();
+            for (x, y) in rect.positions().collect::<Vec<_>>() {
+                let hex = rect.remove(x, y).expect("hex");
+                let (x, y) = (x + dx, y + dy);
+                let part = x / RECT_X_LEN + 2 * (y / RECT_Y_LEN);
+                parts[part].insert(x % RECT_X_LEN, y % RECT_Y_LEN, hex);
+            }
+            for (part, mut part_rect) in parts.into_iter().enumerate() {
+                if part_rect.is_empty() {
+                    continue;
+                }
+                let part_origin = Vector2ISize {
+                    x: rect_origin.x + (part % 2) as isize,
+                    y: rect_origin.y + (part / 2) as isize,
+                };
+                match self.rects.entry(part_origin) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(part_rect);
+                    }
+                    Entry::Occupied(mut entry) => {
+                        for (x, y) in part_rect.positions().collect::<Vec<_>>() {
+                            let hex = part_rect.remove(x, y).expect("hex");
+                            entry.get_mut().insert(x, y, hex);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn translated(mut self, offset: AxialVector) -> Self {
+        self.translate(offset);
+        self
+    }
+
+    // Maps every hex through the transform around the center, e.g. to turn a generated map so that
+    // the same seed does not always give the same layout.
+    pub fn transform(&mut self, transform: HexTransform, center: AxialVector) {
+        let rects = self.rects.drain().collect::<Vec<_>>();
+        self.len = 0;
+        for (rect_origin, mut rect) in rects {
+            for (x, y) in rect.positions().collect::<Vec<_>>() {
+                let hex = rect.remove(x, y).expect("hex");
+                let position = rect_position(rect_origin, x, y);
+                self.insert(transform.apply_around(position, center), hex);
+            }
+        }
+    }
+
+    pub fn transformed(mut self, transform: HexTransform, center: AxialVector) -> Self {
+        self.transform(transform, center);
+        self
+    }
+
+    // Removes the hexes out of the shape, the rects out of its bounds at once. The rects left empty
+    // are kept or pruned according to the policy.
+    pub fn crop<T: Shape>(&mut self, shape: &T) {
+        let bounds = shape.bounds();
+        let mut len = 0;
+        for (rect_origin, rect) in self.rects.iter_mut() {
+            let q_start = rect_origin.x * RECT_X_LEN as isize;
+            let r_start = rect_origin.y * RECT_Y_LEN as isize;
+            let q_end = q_start + RECT_X_LEN as isize - 1;
+            let r_end = r_start + RECT_Y_LEN as isize - 1;
+            let in_bounds = match bounds {
+                Some((q, r)) => {
+                    q.start() <= q_end
+                        && q_start <= q.end()
+                        && r.start() <= r_end
+                        && r_start <= r.end()
+                }
+                None => false,
+            };
+            if !in_bounds {
+                rect.clear();
+                continue;
+            }
+            for (x, y) in rect.positions().collect::<Vec<_>>() {
+                let position = AxialVector::new(q_start + x as isize, r_start + y as isize);
+                if !shape.contains_position(position) {
+                    rect.remove(x, y);
+                }
+            }
+            len += rect.len();
+        }
+        self.len = len;
+        if self.empty_rect_policy == EmptyRectPolicy::Prune {
+            self.prune_empty_rects();
+        }
+    }
+
+    pub fn cropped<T: Shape>(mut self, shape: &T) -> Self {
+        self.crop(shape);
+        self
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -272,6 +587,66 @@ impl<H> RectHashStorage<H> {
         self.len == 0
     }
 
+    // The positions next to the stored hexes but not stored themselves, computed rect by rect.
+    pub fn frontier(&self) -> HexSet {
+        HexSet::from_storage(self, |_| true).frontier()
+    }
+
+    // The bit mask of the hexes matching the predicate in every rect, possibly empty.
+    pub(crate) fn rect_masks<'a, P>(
+        &'a self,
+        predicate: P,
+    ) -> impl 'a + Iterator<Item = (Vector2ISize, u64)>
+    where
+        P: 'a + Fn(&H) -> bool,
+    {
+        self.rects.iter().map(move |(rect_origin, rect)| {
+            let mask = rect
+                .iter()
+                .filter(|(_, _, hex)| predicate(hex))
+                .fold(0u64, |mask, (x, y, _)| mask | 1 << (x + y * RECT_X_LEN));
+            (*rect_origin, mask)
+        })
+    }
+
+    // Digest of the positions and the hexes which does not depend on the insertion order nor on the
+    // storage hasher, see StableHasher.
+    pub fn content_hash(&self) -> u64
+    where
+        H: Hash,
+    {
+        self.content_hash_with(|hex, hasher| hex.hash(hasher))
+    }
+
+    // Same with the bytes of each hex written by the closure, for hexes without a Hash
+    // implementation or with parts not worth hashing.
+    pub fn content_hash_with<F>(&self, mut hash_hex: F) -> u64
+    where
+        F: FnMut(&H, &mut StableHasher),
+    {
+        let mut hexes = self.iter().collect::<Vec<_>>();
+        hexes.sort_by_key(|(position, _)| *position);
+        let mut hasher = StableHasher::default();
+        for (position, hex) in hexes {
+            hasher.write_isize(position.q());
+            hasher.write_isize(position.r());
+            hash_hex(hex, &mut hasher);
+        }
+        hasher.finish()
+    }
+
+    pub fn stats(&self) -> RectHashStorageStats {
+        let mut histogram = vec![0; RECT_X_LEN * RECT_Y_LEN + 1];
+        for rect in self.rects.values() {
+            histogram[rect.len()] += 1;
+        }
+        RectHashStorageStats {
+            rects: self.rects.len(),
+            len: self.len,
+            histogram,
+        }
+    }
+
     pub fn entry(&mut self, position: AxialVector) -> RectHashEntry<H> {
         let x = position.q().div_euclid(RECT_X_LEN as isize);
         let y = position.r().div_euclid(RECT_Y_LEN as isize);
@@ -304,9 +679,359 @@ impl<H> RectHashStorage<H> {
     }
 }
 
-impl<H> Default for RectHashStorage<H> {
+impl<H: BinaryHex, S: BuildHasher + Default> RectHashStorage<H, S> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_header(writer)?;
+        (self.rects.len() as u64).write_hex(writer)?;
+        let mut rect_origins = self.rects.keys().collect::<Vec<_>>();
+        rect_origins.sort();
+        for rect_origin in rect_origins {
+            let rect = &self.rects[rect_origin];
+            (rect_origin.x as i64).write_hex(writer)?;
+            (rect_origin.y as i64).write_hex(writer)?;
+            rect.write_to(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        read_header(reader)?;
+        let mut storage = Self::with_hasher(S::default());
+        let rects_len = u64::read_hex(reader)?;
+        for _ in 0..rects_len {
+            let x = i64::read_hex(reader)? as isize;
+            let y = i64::read_hex(reader)? as isize;
+            let rect = RectStorage::read_from(reader)?;
+            storage.len += rect.len();
+            if storage.rects.insert(Vector2ISize { x, y }, rect).is_some() {
+                return Err(invalid_data(format!("Duplicate rect ({}, {})", x, y)));
+            }
+        }
+        Ok(storage)
+    }
+}
+
+impl<H: Sync, S: BuildHasher + Sync> RectHashStorage<H, S> {
+    // Updates every hex with a value computed from the storage as it was before the update (e.g.
+    // the previous generation of an automaton), so a hex can read its neighbors in the other
+    // rects without any lock. The rects are computed by `threads` workers, each one takes the next
+    // pending rect as soon as it is done with the previous one, then the values are applied.
+    pub fn par_update<T, C, A>(&mut self, threads: usize, compute: C, apply: A)
+    where
+        T: Send,
+        C: Fn(AxialVector, &H, &Self) -> T + Sync,
+        A: FnMut(&mut H, T),
+    {
+        let compute_rect = |rect_origin: Vector2ISize, rect: &RectStorage<H>, storage: &Self| {
+            rect.iter()
+                .map(|(x, y, hex)| {
+                    let position = AxialVector::new(
+                        rect_origin.x * RECT_X_LEN as isize + x as isize,
+                        rect_origin.y * RECT_Y_LEN as isize + y as isize,
+                    );
+                    compute(position, hex, storage)
+                })
+                .collect()
+        };
+        self.par_update_rects(threads, compute_rect, apply);
+    }
+
+    // Same as par_update when a hex only needs its number of radius 1 neighbors matching the
+    // predicate: they are counted with bit operations on the rect masks instead of looked up one
+    // by one.
+    pub fn par_update_neighbor_counts<P, A>(&mut self, threads: usize, is_counted: P, apply: A)
+    where
+        P: Fn(&H) -> bool,
+        A: FnMut(&mut H, u8),
+    {
+        let counted = HexSet::<HexBuildHasher>::from_storage(self, is_counted);
+        let compute_rect = |rect_origin: Vector2ISize, rect: &RectStorage<H>, _: &Self| {
+            let mut neighborhood = [[0; 3]; 3];
+            for (ry, row) in neighborhood.iter_mut().enumerate() {
+                for (rx, mask) in row.iter_mut().enumerate() {
+                    let origin = Vector2ISize {
+                        x: rect_origin.x + rx as isize - 1,
+                        y: rect_origin.y + ry as isize - 1,
+                    };
+                    *mask = counted.rect_mask(origin);
+                }
+            }
+            let planes = count_neighbors(&neighborhood);
+            rect.positions()
+                .map(|(x, y)| {
+                    let offset = x + y * RECT_X_LEN;
+                    (0..3).fold(0, |count, k| count | ((planes[k] >> offset) & 1) << k) as u8
+                })
+                .collect()
+        };
+        self.par_update_rects(threads, compute_rect, apply);
+    }
+
+    fn par_update_rects<T, C, A>(&mut self, threads: usize, compute_rect: C, mut apply: A)
+    where
+        T: Send,
+        C: Fn(Vector2ISize, &RectStorage<H>, &Self) -> Vec<T> + Sync,
+        A: FnMut(&mut H, T),
+    {
+        let rect_origins = self.rects.keys().copied().collect::<Vec<_>>();
+        let compute_index = |index: usize| {
+            let rect_origin = rect_origins[index];
+            compute_rect(rect_origin, &self.rects[&rect_origin], &*self)
+        };
+        let values = if threads <= 1 || rect_origins.len() <= 1 {
+            (0..rect_origins.len()).map(compute_index).collect()
+        } else {
+            let next_index = AtomicUsize::new(0);
+            let mut values = Vec::with_capacity(rect_origins.len());
+            values.resize_with(rect_origins.len(), Vec::new);
+            thread::scope(|scope| {
+                let workers = (0..threads.min(rect_origins.len()))
+                    .map(|_| {
+                        scope.spawn(|| {
+                            let mut computed = Vec::new();
+                            loop {
+                                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                                if index >= rect_origins.len() {
+                                    break computed;
+                                }
+                                computed.push((index, compute_index(index)));
+                            }
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                for worker in workers {
+                    for (index, rect_values) in worker.join().expect("worker panicked") {
+                        values[index] = rect_values;
+                    }
+                }
+            });
+            values
+        };
+        for (rect_origin, rect_values) in rect_origins.iter().zip(values) {
+            let rect = self.rects.get_mut(rect_origin).expect("rect");
+            for ((_, _, hex), value) in rect.iter_mut().zip(rect_values) {
+                apply(hex, value);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<H, S: BuildHasher> RectHashStorage<H, S> {
+    // Uniform over the hexes matching the predicate: a rect is drawn with a weight equal to its
+    // number of matching hexes, then one of those hexes. Rects are weighted in a sorted order and
+    // a u32 is sampled so that a seeded generator picks the same hex on every platform.
+    pub fn random_open_position<R, P>(&self, rng: &mut R, is_open: P) -> Option<AxialVector>
+    where
+        R: rand::Rng + ?Sized,
+        P: Fn(AxialVector, &H) -> bool,
+    {
+        let open_positions = |rect_origin: &Vector2ISize| {
+            let is_open = &is_open;
+            let rect = &self.rects[rect_origin];
+            let rect_origin = *rect_origin;
+            rect.iter().filter_map(move |(x, y, hex)| {
+                let position = AxialVector::new(
+                    rect_origin.x * RECT_X_LEN as isize + x as isize,
+                    rect_origin.y * RECT_Y_LEN as isize + y as isize,
+                );
+                if is_open(position, hex) {
+                    Some(position)
+                } else {
+                    None
+                }
+            })
+        };
+        let mut rect_origins = self.rects.keys().collect::<Vec<_>>();
+        rect_origins.sort();
+        let counts = rect_origins
+            .into_iter()
+            .map(|rect_origin| (rect_origin, open_positions(rect_origin).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+        let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+        if total == 0 {
+            return None;
+        }
+        let mut index = rng.gen_range(0, total as u32) as usize;
+        for (rect_origin, count) in counts {
+            if index < count {
+                return open_positions(rect_origin).nth(index);
+            }
+            index -= count;
+        }
+        unreachable!()
+    }
+}
+
+fn is_rect_in_bounds(rect_origin: &Vector2ISize, bounds: &Option<(Range, Range)>) -> bool {
+    let q_start = rect_origin.x * RECT_X_LEN as isize;
+    let r_start = rect_origin.y * RECT_Y_LEN as isize;
+    match bounds {
+        Some((q, r)) => {
+            q_start <= q.end()
+                && q.start() < q_start + RECT_X_LEN as isize
+                && r_start <= r.end()
+                && r.start() < r_start + RECT_Y_LEN as isize
+        }
+        None => false,
+    }
+}
+
+pub struct RectHashStorageView<'a, H, S, T> {
+    storage: &'a RectHashStorage<H, S>,
+    shape: &'a T,
+    bounds: Option<(Range, Range)>,
+}
+
+impl<'a, H, S: BuildHasher, T: Shape> RectHashStorageView<'a, H, S, T> {
+    pub fn get(&self, position: AxialVector) -> Option<&'a H> {
+        if self.shape.contains_position(position) {
+            self.storage.get(position)
+        } else {
+            None
+        }
+    }
+
+    pub fn contains_position(&self, position: AxialVector) -> bool {
+        self.shape.contains_position(position) && self.storage.contains_position(position)
+    }
+
+    pub fn iter(&self) -> impl 'a + Iterator<Item = (AxialVector, &'a H)> {
+        let shape = self.shape;
+        let bounds = self.bounds;
+        self.storage
+            .rects
+            .iter()
+            .filter(move |(rect_origin, _)| is_rect_in_bounds(rect_origin, &bounds))
+            .flat_map(|(rect_origin, rect)| {
+                rect.iter().map(move |(x, y, hex)| {
+                    (
+                        AxialVector::new(
+                            rect_origin.x * RECT_X_LEN as isize + x as isize,
+                            rect_origin.y * RECT_Y_LEN as isize + y as isize,
+                        ),
+                        hex,
+                    )
+                })
+            })
+            .filter(move |(position, _)| shape.contains_position(*position))
+    }
+
+    pub fn positions(&self) -> impl 'a + Iterator<Item = AxialVector> {
+        self.iter().map(|(position, _)| position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+pub struct RectHashStorageViewMut<'a, H, S, T> {
+    storage: &'a mut RectHashStorage<H, S>,
+    shape: &'a T,
+    bounds: Option<(Range, Range)>,
+}
+
+impl<'a, H, S: BuildHasher, T: Shape> RectHashStorageViewMut<'a, H, S, T> {
+    pub fn get(&self, position: AxialVector) -> Option<&H> {
+        if self.shape.contains_position(position) {
+            self.storage.get(position)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, position: AxialVector) -> Option<&mut H> {
+        if self.shape.contains_position(position) {
+            self.storage.get_mut(position)
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AxialVector, &H)> {
+        self.storage.view(self.shape).iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (AxialVector, &mut H)> {
+        let shape = self.shape;
+        let bounds = self.bounds;
+        self.storage
+            .rects
+            .iter_mut()
+            .filter(move |(rect_origin, _)| is_rect_in_bounds(rect_origin, &bounds))
+            .flat_map(|(rect_origin, rect)| {
+                rect.iter_mut().map(move |(x, y, hex)| {
+                    (
+                        AxialVector::new(
+                            rect_origin.x * RECT_X_LEN as isize + x as isize,
+                            rect_origin.y * RECT_Y_LEN as isize + y as isize,
+                        ),
+                        hex,
+                    )
+                })
+            })
+            .filter(move |(position, _)| shape.contains_position(*position))
+    }
+}
+
+impl<H, S: BuildHasher + Default> Default for RectHashStorage<H, S> {
     fn default() -> Self {
-        Self::new()
+        Self::with_hasher(S::default())
+    }
+}
+
+// Rects are kept once allocated, empty rects count as rects with an occupancy of zero.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RectHashStorageStats {
+    pub rects: usize,
+    pub len: usize,
+    // Number of rects for each occupancy, from 0 to RECT_X_LEN * RECT_Y_LEN hexes.
+    pub histogram: Vec<usize>,
+}
+
+impl RectHashStorageStats {
+    pub fn min_occupancy(&self) -> usize {
+        self.histogram
+            .iter()
+            .position(|count| *count > 0)
+            .unwrap_or(0)
+    }
+
+    pub fn max_occupancy(&self) -> usize {
+        self.histogram
+            .iter()
+            .rposition(|count| *count > 0)
+            .unwrap_or(0)
+    }
+
+    // A statistic for the reports, it does not feed any algorithm.
+    #[allow(clippy::disallowed_types)]
+    pub fn avg_occupancy(&self) -> f64 {
+        if self.rects > 0 {
+            self.len as f64 / self.rects as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Display for RectHashStorageStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hexes in {} rects, occupancy {}/{:.1}/{}",
+            self.len,
+            self.rects,
+            self.min_occupancy(),
+            self.avg_occupancy(),
+            self.max_occupancy()
+        )
     }
 }
 
@@ -392,6 +1117,20 @@ fn test_rect_hash_storage_should_give_access_to_hex() {
     assert!(!storage.is_empty());
 }
 
+#[test]
+fn test_rect_hash_storage_should_accept_any_hasher() {
+    let mut storage =
+        RectHashStorage::with_hasher(std::collections::hash_map::RandomState::default());
+    for position in AxialVector::new(3, -7).ring_iter(20) {
+        storage.insert(position, position.q() + position.r());
+    }
+    assert_eq!(storage.len(), 120);
+    for position in AxialVector::new(3, -7).ring_iter(20) {
+        assert_eq!(storage.get(position), Some(&(position.q() + position.r())));
+    }
+    assert_eq!(storage.get(AxialVector::new(3, -7)), None);
+}
+
 #[test]
 fn test_rect_hash_storage_should_give_mutable_access_to_hex() {
     #[derive(PartialEq, Eq, Debug)]
@@ -654,6 +1393,43 @@ fn test_rect_hash_storage_should_iterate_over_mutable_hexes() {
     assert!(!storage.is_empty());
 }
 
+#[test]
+fn test_rect_hash_storage_hex_with_adjacents_across_rects() {
+    // Every third hex missing, over several rects, each hex counting the times it is reached.
+    let mut storage = RectHashStorage::new();
+    let center = AxialVector::new(3, -5);
+    for position in center.spiral_iter(20) {
+        if (position.q() + 2 * position.r()).rem_euclid(3) != 0 {
+            storage.insert(position, (position, 0));
+        }
+    }
+    for position in center.spiral_iter(21) {
+        let hex_with_adjacents = storage.hex_with_adjacents(position);
+        assert_eq!(*hex_with_adjacents.hex(), storage.get(position));
+        for direction in 0..6 {
+            let adjacent = position + AxialVector::direction(direction);
+            assert_eq!(
+                hex_with_adjacents.adjacent(direction),
+                storage.get(adjacent)
+            );
+        }
+    }
+    for position in center.spiral_iter(21) {
+        let mut hex_with_adjacents = storage.hex_with_adjacents_mut(position);
+        if let Some(hex) = hex_with_adjacents.hex() {
+            assert_eq!(hex.0, position);
+        }
+        for direction in 0..6 {
+            if let Some(adjacent) = hex_with_adjacents.adjacent(direction) {
+                assert_eq!(adjacent.0, position + AxialVector::direction(direction));
+                adjacent.1 += 1;
+            }
+        }
+    }
+    // Reached from the six hexes around, there or not.
+    assert!(storage.hexes().all(|(_, count)| *count == 6));
+}
+
 #[test]
 fn test_rect_hash_storage_should_iterate_over_mutable_hexes_with_adjacents() {
     #[derive(PartialEq, Eq, Debug)]
@@ -820,3 +1596,463 @@ fn test_rect_hash_storage_should_have_entry_api() {
     assert_eq!(storage.len(), 2);
     assert!(!storage.is_empty());
 }
+
+#[test]
+fn test_rect_hash_storage_should_write_and_read_binary() {
+    let mut storage = RectHashStorage::new();
+    for (x, y, value) in [(12, -42, 93u32), (-5, 24, 7), (13, -42, 42), (0, 0, 1)].iter() {
+        storage.insert(AxialVector::new(*x, *y), (*value, *x < 0));
+    }
+
+    let mut buffer = Vec::new();
+    storage.write_to(&mut buffer).unwrap();
+    let read = RectHashStorage::<(u32, bool)>::read_from(&mut buffer.as_slice()).unwrap();
+
+    assert_eq!(read.len(), 4);
+    assert_eq!(
+        read.iter()
+            .map(|(position, hex)| (position, *hex))
+            .collect::<std::collections::HashSet<_>>(),
+        storage
+            .iter()
+            .map(|(position, hex)| (position, *hex))
+            .collect::<std::collections::HashSet<_>>()
+    );
+
+    let mut buffer_again = Vec::new();
+    read.write_to(&mut buffer_again).unwrap();
+    assert_eq!(buffer, buffer_again);
+}
+
+#[test]
+fn test_rect_hash_storage_binary_should_be_compact() {
+    let mut storage = RectHashStorage::new();
+    for q in 0..RECT_X_LEN as isize {
+        for r in 0..RECT_Y_LEN as isize {
+            storage.insert(AxialVector::new(q, r), 1u8);
+        }
+    }
+
+    let mut buffer = Vec::new();
+    storage.write_to(&mut buffer).unwrap();
+    // Header + rect count + one rect (origin, occupancy, payloads)
+    assert_eq!(buffer.len(), 5 + 8 + 8 + 8 + 8 + RECT_X_LEN * RECT_Y_LEN);
+}
+
+#[test]
+fn test_rect_hash_storage_should_reject_truncated_binary() {
+    let mut storage = RectHashStorage::new();
+    storage.insert(AxialVector::new(12, -42), 93u32);
+
+    let mut buffer = Vec::new();
+    storage.write_to(&mut buffer).unwrap();
+    buffer.pop();
+    assert!(RectHashStorage::<u32>::read_from(&mut buffer.as_slice()).is_err());
+}
+
+#[test]
+fn test_rect_hash_storage_stats() {
+    let mut storage = RectHashStorage::new();
+    let stats = storage.stats();
+    assert_eq!((stats.rects, stats.len), (0, 0));
+    assert_eq!(stats.histogram.len(), RECT_X_LEN * RECT_Y_LEN + 1);
+    assert_eq!(stats.to_string(), "0 hexes in 0 rects, occupancy 0/0.0/0");
+
+    for q in 0..RECT_X_LEN as isize {
+        for r in 0..RECT_Y_LEN as isize {
+            storage.insert(AxialVector::new(q, r), ());
+        }
+    }
+    storage.insert(AxialVector::new(-1, 0), ());
+    storage.insert(AxialVector::new(-1, -1), ());
+    storage.insert(AxialVector::new(-2, -2), ());
+    let stats = storage.stats();
+    assert_eq!((stats.rects, stats.len), (3, 67));
+    assert_eq!(stats.histogram[1], 1);
+    assert_eq!(stats.histogram[2], 1);
+    assert_eq!(stats.histogram[RECT_X_LEN * RECT_Y_LEN], 1);
+    assert_eq!(stats.histogram.iter().sum::<usize>(), stats.rects);
+    assert_eq!(
+        stats.to_string(),
+        "67 hexes in 3 rects, occupancy 1/22.3/64"
+    );
+
+    storage.remove(AxialVector::new(-1, 0));
+    let stats = storage.stats();
+    assert_eq!((stats.rects, stats.len), (3, 66));
+    assert_eq!((stats.min_occupancy(), stats.max_occupancy()), (0, 64));
+}
+
+#[test]
+fn test_rect_hash_storage_transform() {
+    use crate::assert_storage_eq;
+
+    let center = AxialVector::new(7, -3);
+    let positions = AxialVector::new(2, 4).spiral_iter(5).collect::<Vec<_>>();
+    let storage = || {
+        let mut storage = RectHashStorage::new();
+        for (i, position) in positions.iter().enumerate() {
+            storage.insert(*position, i);
+        }
+        storage
+    };
+    for transform in HexTransform::all() {
+        let mut expected = RectHashStorage::new();
+        for (i, position) in positions.iter().enumerate() {
+            expected.insert(transform.apply_around(*position, center), i);
+        }
+        let transformed = storage().transformed(transform, center);
+        assert_storage_eq!(transformed, expected);
+        assert_eq!(transformed.len(), positions.len());
+
+        let mut back = transformed.transformed(transform.inverse(), center);
+        back.prune_empty_rects();
+        assert_storage_eq!(back, storage());
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn test_rect_hash_storage_random_open_position() {
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashMap;
+
+    let center = AxialVector::new(-5, 3);
+    let mut storage = RectHashStorage::new();
+    for position in center.spiral_iter(12) {
+        storage.insert(position, position.distance(center) % 3 == 0);
+    }
+    let open_len = storage.iter().filter(|(_, open)| **open).count();
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut draws = HashMap::new();
+    for _ in 0..open_len * 100 {
+        let position = storage
+            .random_open_position(&mut rng, |_, open| *open)
+            .expect("open position");
+        assert_eq!(storage.get(position), Some(&true));
+        *draws.entry(position).or_insert(0) += 1;
+    }
+    // Every open hex is drawn, about 100 times each.
+    assert_eq!(draws.len(), open_len);
+    assert!(draws.values().all(|count| *count > 50 && *count < 150));
+
+    let draw = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..10)
+            .map(|_| storage.random_open_position(&mut rng, |_, open| *open))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(draw(7), draw(7));
+
+    assert_eq!(storage.random_open_position(&mut rng, |_, _| false), None);
+    assert_eq!(
+        storage.random_open_position(&mut rng, |position, _| position == center),
+        Some(center)
+    );
+}
+
+#[test]
+fn test_rect_hash_storage_views() {
+    use crate::hex::shape::cubic_range::CubicRangeShape;
+    let mut storage = RectHashStorage::new();
+    for pos in AxialVector::new(3, -2).spiral_iter(25) {
+        storage.insert(pos, pos.q() * 100 + pos.r());
+    }
+    let room = CubicRangeShape::new((-5, 9), (-7, 2), (-8, 4));
+    let fov = AxialVector::new(20, -10)
+        .spiral_iter(12)
+        .collect::<HexSet>();
+    let mut empty = HexSet::new();
+    empty.insert(AxialVector::new(200, 0));
+
+    let check = |storage: &RectHashStorage<isize>,
+                 shape: &dyn Fn(AxialVector) -> bool,
+                 view: Vec<(AxialVector, isize)>| {
+        let mut view = view;
+        view.sort();
+        let mut expected = storage
+            .iter()
+            .filter(|(pos, _)| shape(*pos))
+            .map(|(pos, hex)| (pos, *hex))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(view, expected);
+    };
+    let view = storage.view(&room);
+    check(
+        &storage,
+        &|pos| room.contains_position(pos),
+        view.iter().map(|(pos, hex)| (pos, *hex)).collect(),
+    );
+    assert_eq!(
+        view.len(),
+        room.iter_positions()
+            .filter(|pos| storage.contains_position(*pos))
+            .count()
+    );
+    assert_eq!(view.get(AxialVector::new(0, 0)), Some(&0));
+    assert_eq!(view.get(AxialVector::new(20, -10)), None);
+    assert!(view.contains_position(AxialVector::new(1, -1)));
+    assert!(!view.contains_position(AxialVector::new(20, -10)));
+    let view = storage.view(&fov);
+    check(
+        &storage,
+        &|pos| fov.contains(pos),
+        view.positions()
+            .map(|pos| (pos, *storage.get(pos).unwrap()))
+            .collect(),
+    );
+    assert!(storage.view(&empty).is_empty());
+
+    let mut view = storage.view_mut(&fov);
+    for (_, hex) in view.iter_mut() {
+        *hex = -1;
+    }
+    assert_eq!(view.get_mut(AxialVector::new(0, 0)), None);
+    *view.get_mut(AxialVector::new(20, -10)).unwrap() = -2;
+    assert_eq!(
+        view.iter().count(),
+        fov.iter()
+            .filter(|pos| storage.contains_position(*pos))
+            .count()
+    );
+    for (pos, hex) in storage.iter() {
+        if pos == AxialVector::new(20, -10) {
+            assert_eq!(*hex, -2);
+        } else if fov.contains(pos) {
+            assert_eq!(*hex, -1);
+        } else {
+            assert_eq!(*hex, pos.q() * 100 + pos.r());
+        }
+    }
+}
+
+#[test]
+fn test_rect_hash_storage_par_update() {
+    let generate = |threads| {
+        let mut storage = RectHashStorage::new();
+        for position in AxialVector::new(3, -5).spiral_iter(40) {
+            storage.insert(
+                position,
+                (position.q() * 7 + position.r() * 3).rem_euclid(5),
+            );
+        }
+        // Every hex becomes the sum of its neighbors, which must all be read before the update.
+        storage.par_update(
+            threads,
+            |position, _, storage| {
+                position
+                    .ring_iter(1)
+                    .filter_map(|neighbor| storage.get(neighbor))
+                    .sum::<isize>()
+            },
+            |hex, sum| *hex = sum,
+        );
+        storage
+    };
+    let expected = generate(1);
+    assert_eq!(expected.len(), 1 + 3 * 40 * 41);
+    assert_eq!(
+        expected.get(AxialVector::new(3, -5)).copied(),
+        Some(
+            AxialVector::new(3, -5)
+                .ring_iter(1)
+                .map(|position| (position.q() * 7 + position.r() * 3).rem_euclid(5))
+                .sum()
+        )
+    );
+    for threads in &[2, 4, 7] {
+        let storage = generate(*threads);
+        assert_eq!(storage.len(), expected.len());
+        for (position, hex) in expected.iter() {
+            assert_eq!(storage.get(position), Some(hex));
+        }
+    }
+}
+
+#[test]
+fn test_rect_hash_storage_par_update_neighbor_counts() {
+    let mut storage = RectHashStorage::new();
+    for position in AxialVector::new(-9, 4).spiral_iter(30) {
+        storage.insert(
+            position,
+            ((position.q() * 5 + position.r() * 11) % 3 == 0, 0),
+        );
+    }
+    let mut expected = RectHashStorage::new();
+    for (position, hex) in storage.iter() {
+        expected.insert(position, *hex);
+    }
+    expected.par_update(
+        1,
+        |position, _, storage| {
+            position
+                .ring_iter(1)
+                .filter(|neighbor| matches!(storage.get(*neighbor), Some((true, _))))
+                .count() as u8
+        },
+        |hex, count| hex.1 = count,
+    );
+    for threads in &[1, 3] {
+        storage.par_update_neighbor_counts(*threads, |hex| hex.0, |hex, count| hex.1 = count);
+        assert_eq!(storage.len(), expected.len());
+        for (position, hex) in expected.iter() {
+            assert_eq!(storage.get(position), Some(hex));
+        }
+    }
+}
+
+#[test]
+fn test_rect_hash_storage_should_prune_empty_rects() {
+    let mut storage = RectHashStorage::new();
+    for position in AxialVector::new(0, 0).spiral_iter(10) {
+        storage.insert(position, ());
+    }
+    let rects = storage.stats().rects;
+    for position in AxialVector::new(0, 0).ring_iter(10) {
+        storage.remove(position);
+    }
+    assert_eq!(storage.stats().rects, rects);
+
+    storage.set_empty_rect_policy(EmptyRectPolicy::Prune);
+    assert_eq!(storage.empty_rect_policy(), EmptyRectPolicy::Prune);
+    let pruned = storage.stats();
+    assert!(pruned.rects < rects);
+    assert!(pruned.min_occupancy() > 0);
+    for position in AxialVector::new(0, 0).spiral_iter(10) {
+        storage.remove(position);
+        let stats = storage.stats();
+        assert!(stats.min_occupancy() > 0 || stats.rects == 0);
+    }
+    assert!(storage.is_empty());
+    assert_eq!(storage.stats().rects, 0);
+    assert_eq!(storage.remove(AxialVector::new(0, 0)), None);
+}
+
+#[test]
+fn test_rect_hash_storage_clear_and_shrink() {
+    let mut storage = RectHashStorage::new();
+    for position in AxialVector::new(0, 0).spiral_iter(10) {
+        storage.insert(position, position.q());
+    }
+    storage.clear();
+    assert!(storage.is_empty());
+    assert!(storage.stats().rects > 0);
+    for position in AxialVector::new(0, 0).spiral_iter(10) {
+        storage.insert(position, position.q());
+    }
+    storage.clear_and_shrink();
+    assert!(storage.is_empty());
+    assert_eq!(storage.stats().rects, 0);
+    storage.insert(AxialVector::new(3, 4), 3);
+    assert_eq!(storage.get(AxialVector::new(3, 4)), Some(&3));
+    assert_eq!(storage.len(), 1);
+}
+
+#[test]
+fn test_rect_hash_storage_content_hash() {
+    let positions = AxialVector::new(-3, 5).spiral_iter(4).collect::<Vec<_>>();
+    let mut storage = RectHashStorage::new();
+    for (i, position) in positions.iter().enumerate() {
+        storage.insert(*position, i as u32);
+    }
+    let mut reversed =
+        RectHashStorage::with_hasher(std::collections::hash_map::RandomState::default());
+    for (i, position) in positions.iter().enumerate().rev() {
+        reversed.insert(*position, i as u32);
+    }
+    assert_eq!(storage.content_hash(), reversed.content_hash());
+    // Pinned, and independent of the storage hasher as shown by `reversed`.
+    assert_eq!(storage.content_hash(), 469_865_387_920_151_409);
+    assert_eq!(
+        storage.content_hash_with(|hex, hasher| hasher.write(&hex.to_le_bytes())),
+        storage.content_hash()
+    );
+
+    reversed.insert(positions[7], 0);
+    assert_ne!(storage.content_hash(), reversed.content_hash());
+    reversed.insert(positions[7], 7);
+    reversed.remove(positions[0]);
+    assert_ne!(storage.content_hash(), reversed.content_hash());
+    assert_eq!(
+        RectHashStorage::<u32>::new().content_hash(),
+        StableHasher::default().finish()
+    );
+}
+
+#[test]
+fn test_rect_hash_storage_translate() {
+    use crate::assert_storage_eq;
+
+    let positions = AxialVector::new(3, -9).spiral_iter(6).collect::<Vec<_>>();
+    let mut storage = RectHashStorage::new();
+    for (i, position) in positions.iter().enumerate() {
+        storage.insert(*position, i);
+    }
+    for offset in &[
+        AxialVector::new(16, -8),
+        AxialVector::new(5, 3),
+        AxialVector::new(-13, -1),
+    ] {
+        let mut expected = RectHashStorage::new();
+        for (i, position) in positions.iter().enumerate() {
+            expected.insert(*position + *offset, i);
+        }
+        let mut translated = RectHashStorage::new();
+        for (i, position) in positions.iter().enumerate() {
+            translated.insert(*position, i);
+        }
+        let translated = translated.translated(*offset);
+        assert_storage_eq!(translated, expected);
+        assert_eq!(translated.len(), positions.len());
+
+        let mut back = translated.translated(AxialVector::default() - *offset);
+        back.prune_empty_rects();
+        assert_storage_eq!(back, storage);
+    }
+}
+
+#[test]
+fn test_rect_hash_storage_crop() {
+    use crate::{assert_storage_eq, hex::shape::cubic_range::CubicRangeShape};
+
+    let mut storage = RectHashStorage::new();
+    for (i, position) in AxialVector::default().spiral_iter(20).enumerate() {
+        storage.insert(position, i);
+    }
+    let mut shape = CubicRangeShape::new((-3, 4), (-5, 2), (-2, 6));
+    shape.translate(AxialVector::new(7, -3));
+    let mut expected = RectHashStorage::new();
+    for (position, hex) in storage.iter() {
+        if shape.contains_position(position) {
+            expected.insert(position, *hex);
+        }
+    }
+
+    storage.set_empty_rect_policy(EmptyRectPolicy::Prune);
+    let cropped = storage.cropped(&shape);
+    assert_storage_eq!(cropped, expected);
+    assert_eq!(cropped.len(), expected.len());
+    assert_eq!(cropped.stats().rects, expected.stats().rects);
+
+    let set = cropped.positions().take(5).collect::<HexSet>();
+    let cropped = cropped.cropped(&set);
+    assert_eq!(cropped.len(), 5);
+    assert!(cropped.positions().all(|position| set.contains(position)));
+    assert!(cropped.cropped(&HexSet::new()).is_empty());
+}
+
+#[test]
+fn test_rect_hash_storage_frontier() {
+    let mut storage = RectHashStorage::new();
+    for position in AxialVector::new(-7, 3).ring_iter(5) {
+        storage.insert(position, ());
+    }
+    let frontier = storage.frontier();
+    assert!(frontier
+        .iter()
+        .all(|position| !storage.contains_position(position)));
+    assert_eq!(frontier.len(), 24 + 36);
+    assert!(RectHashStorage::<()>::new().frontier().is_empty());
+}
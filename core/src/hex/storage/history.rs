@@ -0,0 +1,150 @@
+use crate::hex::storage::{delta::StorageDelta, hash::RectHashStorage};
+
+pub struct StorageHistory<H> {
+    present: RectHashStorage<H>,
+    steps: Vec<(StorageDelta<H>, StorageDelta<H>)>,
+    cursor: usize,
+}
+
+impl<H: Clone + PartialEq> StorageHistory<H> {
+    pub fn new() -> Self {
+        Self {
+            present: RectHashStorage::new(),
+            steps: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    pub fn reset(&mut self, initial: RectHashStorage<H>) {
+        self.present = initial;
+        self.steps.clear();
+        self.cursor = 0;
+    }
+
+    // Steps are only recorded at the end of the history, past states cannot be rewritten.
+    pub fn record(&mut self, state: RectHashStorage<H>) -> bool {
+        if !self.is_at_present() {
+            return false;
+        }
+        let forward = StorageDelta::between(&self.present, &state);
+        if forward.is_empty() {
+            return false;
+        }
+        let backward = StorageDelta::between(&state, &self.present);
+        self.steps.push((forward, backward));
+        self.cursor = self.steps.len();
+        self.present = state;
+        true
+    }
+
+    // Forgets the steps after the cursor so that new ones can be recorded from there, the state
+    // at the cursor becomes the present.
+    pub fn truncate(&mut self) {
+        while self.steps.len() > self.cursor {
+            let (_, backward) = self.steps.pop().expect("step");
+            backward.apply_into(&mut self.present);
+        }
+    }
+
+    pub fn is_at_present(&self) -> bool {
+        self.cursor == self.steps.len()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn step_backward(&mut self) -> Option<&StorageDelta<H>> {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            Some(&self.steps[self.cursor].1)
+        } else {
+            None
+        }
+    }
+
+    pub fn step_forward(&mut self) -> Option<&StorageDelta<H>> {
+        if self.cursor < self.steps.len() {
+            self.cursor += 1;
+            Some(&self.steps[self.cursor - 1].0)
+        } else {
+            None
+        }
+    }
+}
+
+impl<H: Clone + PartialEq> Default for StorageHistory<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::hex::coordinates::axial::AxialVector;
+
+#[cfg(test)]
+fn history_state(step: isize) -> RectHashStorage<u8> {
+    let mut storage = RectHashStorage::new();
+    storage.insert(AxialVector::new(0, 0), step as u8);
+    for s in 1..=step {
+        storage.insert(AxialVector::new(s, 0), s as u8);
+    }
+    storage
+}
+
+#[test]
+fn test_storage_history_should_scrub_through_steps() {
+    let mut history = StorageHistory::new();
+    history.reset(history_state(0));
+    for step in 1..=3 {
+        assert!(history.record(history_state(step)));
+    }
+    assert!(!history.record(history_state(3)));
+    assert_eq!(history.len(), 3);
+
+    let mut scrubbed = history_state(3);
+    for cursor in (0..3).rev() {
+        history.step_backward().unwrap().apply(&mut scrubbed);
+        assert_eq!(history.cursor(), cursor);
+        crate::assert_storage_eq!(scrubbed, history_state(cursor as isize));
+    }
+    assert!(history.step_backward().is_none());
+
+    history.step_forward().unwrap().apply(&mut scrubbed);
+    crate::assert_storage_eq!(scrubbed, history_state(1));
+    assert!(!history.record(history_state(4)));
+
+    while let Some(delta) = history.step_forward() {
+        delta.apply(&mut scrubbed);
+    }
+    assert!(history.is_at_present());
+    crate::assert_storage_eq!(scrubbed, history_state(3));
+}
+
+#[test]
+fn test_storage_history_should_truncate() {
+    let mut history = StorageHistory::new();
+    history.reset(history_state(0));
+    for step in 1..=3 {
+        assert!(history.record(history_state(step)));
+    }
+    history.step_backward();
+    history.step_backward();
+    history.truncate();
+    assert_eq!(history.len(), 1);
+    assert!(history.is_at_present());
+    // The present is now the state at step 1, which does not record as a change.
+    assert!(!history.record(history_state(1)));
+    assert!(history.record(history_state(5)));
+    let mut scrubbed = history_state(5);
+    history.step_backward().unwrap().apply(&mut scrubbed);
+    crate::assert_storage_eq!(scrubbed, history_state(1));
+}
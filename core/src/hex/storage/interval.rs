@@ -0,0 +1,205 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Bound, RangeInclusive},
+};
+
+// Disjoint height ranges (both ends included) of a single hex, e.g. the floor to ceiling blocks of
+// 2.5D terrain stored in a RectHashStorage<IntervalMap<_>>. Since ranges never overlap, sorting them
+// by start is enough to find the ones around a height.
+#[derive(Clone, Debug)]
+pub struct IntervalMap<V> {
+    intervals: BTreeMap<isize, (isize, V)>,
+}
+
+impl<V> IntervalMap<V> {
+    pub fn new() -> Self {
+        Self {
+            intervals: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.intervals.clear();
+    }
+
+    // Gives the value back if the range is empty or overlaps another one.
+    pub fn insert(&mut self, range: RangeInclusive<isize>, value: V) -> Result<(), V> {
+        if range.is_empty() || self.overlaps(range.clone()) {
+            return Err(value);
+        }
+        self.intervals.insert(*range.start(), (*range.end(), value));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, start: isize) -> Option<(RangeInclusive<isize>, V)> {
+        self.intervals
+            .remove(&start)
+            .map(|(end, value)| (start..=end, value))
+    }
+
+    // The range starting exactly at the height.
+    pub fn get(&self, start: isize) -> Option<(RangeInclusive<isize>, &V)> {
+        self.intervals
+            .get(&start)
+            .map(|(end, value)| (start..=*end, value))
+    }
+
+    pub fn get_mut(&mut self, start: isize) -> Option<(RangeInclusive<isize>, &mut V)> {
+        self.intervals
+            .get_mut(&start)
+            .map(|(end, value)| (start..=*end, value))
+    }
+
+    // The range containing the height.
+    pub fn containing(&self, height: isize) -> Option<(RangeInclusive<isize>, &V)> {
+        self.floor(height)
+            .filter(|(range, _)| *range.end() >= height)
+    }
+
+    // The highest range starting at or below the height, whether it contains it or not.
+    pub fn floor(&self, height: isize) -> Option<(RangeInclusive<isize>, &V)> {
+        self.intervals
+            .range(..=height)
+            .next_back()
+            .map(|(start, (end, value))| (*start..=*end, value))
+    }
+
+    // The lowest range starting above the height.
+    pub fn ceiling(&self, height: isize) -> Option<(RangeInclusive<isize>, &V)> {
+        self.intervals
+            .range((Bound::Excluded(height), Bound::Unbounded))
+            .next()
+            .map(|(start, (end, value))| (*start..=*end, value))
+    }
+
+    // The ranges starting within the given one.
+    pub fn starting_in<'a>(
+        &'a self,
+        range: RangeInclusive<isize>,
+    ) -> impl 'a + DoubleEndedIterator<Item = (RangeInclusive<isize>, &'a V)> {
+        let (start, end) = range.into_inner();
+        // BTreeMap::range does not accept empty ranges.
+        self.intervals
+            .range(start..=end.max(start))
+            .filter(move |(other_start, _)| **other_start <= end)
+            .map(|(start, (end, value))| (*start..=*end, value))
+    }
+
+    // Only the range starting below the given one may reach into it, all the others start within.
+    pub fn overlapping<'a>(
+        &'a self,
+        range: RangeInclusive<isize>,
+    ) -> impl 'a + Iterator<Item = (RangeInclusive<isize>, &'a V)> {
+        let below = if range.is_empty() {
+            None
+        } else {
+            self.floor(*range.start() - 1)
+                .filter(|(other, _)| other.end() >= range.start())
+        };
+        below.into_iter().chain(self.starting_in(range))
+    }
+
+    pub fn overlaps(&self, range: RangeInclusive<isize>) -> bool {
+        self.overlapping(range).next().is_some()
+    }
+
+    pub fn iter<'a>(
+        &'a self,
+    ) -> impl 'a + DoubleEndedIterator<Item = (RangeInclusive<isize>, &'a V)> {
+        self.intervals
+            .iter()
+            .map(|(start, (end, value))| (*start..=*end, value))
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.intervals.values().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> {
+        self.intervals.values_mut().map(|(_, value)| value)
+    }
+}
+
+impl<V> Default for IntervalMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_interval_map_should_reject_overlaps() {
+    let mut map = IntervalMap::new();
+    assert_eq!(map.insert(0..=5, "a"), Ok(()));
+    assert_eq!(map.insert(10..=15, "b"), Ok(()));
+    assert_eq!(map.insert(5..=7, "c"), Err("c"));
+    assert_eq!(map.insert(-3..=0, "c"), Err("c"));
+    assert_eq!(map.insert(7..=20, "c"), Err("c"));
+    assert_eq!(map.insert(2..=3, "c"), Err("c"));
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = 8..=7;
+    assert_eq!(map.insert(empty, "c"), Err("c"));
+    assert_eq!(map.insert(6..=9, "c"), Ok(()));
+    assert_eq!(map.len(), 3);
+    assert_eq!(
+        map.values().copied().collect::<Vec<_>>(),
+        vec!["a", "c", "b"]
+    );
+    assert_eq!(map.remove(6), Some((6..=9, "c")));
+    assert_eq!(map.remove(6), None);
+    assert_eq!(map.insert(7..=8, "d"), Ok(()));
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_interval_map_queries() {
+    let mut map = IntervalMap::new();
+    for (range, value) in [(0..=5, 'a'), (10..=15, 'b'), (16..=16, 'c'), (30..=40, 'd')] {
+        map.insert(range, value).expect("disjoint");
+    }
+    assert_eq!(map.get(10), Some((10..=15, &'b')));
+    assert_eq!(map.get(11), None);
+    assert_eq!(map.containing(12), Some((10..=15, &'b')));
+    assert_eq!(map.containing(16), Some((16..=16, &'c')));
+    assert_eq!(map.containing(20), None);
+    assert_eq!(map.containing(-1), None);
+
+    assert_eq!(map.floor(20), Some((16..=16, &'c')));
+    assert_eq!(map.floor(10), Some((10..=15, &'b')));
+    assert_eq!(map.floor(-1), None);
+    assert_eq!(map.ceiling(10), Some((16..=16, &'c')));
+    assert_eq!(map.ceiling(-100), Some((0..=5, &'a')));
+    assert_eq!(map.ceiling(30), None);
+
+    let values = |iter: &mut dyn Iterator<Item = (RangeInclusive<isize>, &char)>| {
+        iter.map(|(_, value)| *value).collect::<String>()
+    };
+    assert_eq!(values(&mut map.overlapping(3..=12)), "ab");
+    assert_eq!(values(&mut map.overlapping(15..=29)), "bc");
+    assert_eq!(values(&mut map.overlapping(6..=9)), "");
+    assert_eq!(values(&mut map.overlapping(35..=35)), "d");
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = 4..=3;
+    assert_eq!(values(&mut map.overlapping(empty)), "");
+    assert_eq!(values(&mut map.starting_in(5..=16)), "bc");
+    assert_eq!(values(&mut map.iter().rev()), "dcba");
+    assert!(!map.overlaps(41..=50) && map.overlaps(40..=50));
+
+    if let Some((_, value)) = map.get_mut(30) {
+        *value = 'e';
+    }
+    assert_eq!(map.containing(31), Some((30..=40, &'e')));
+
+    for value in map.values_mut() {
+        *value = value.to_ascii_uppercase();
+    }
+    assert_eq!(map.values().collect::<String>(), "ABCE");
+}
@@ -0,0 +1,214 @@
+use crate::{
+    hex::{
+        coordinates::axial::AxialVector,
+        storage::{
+            hash::{rect_position, RectHashStorage},
+            rect::{mask_offsets, RectStorage, RECT_X_LEN},
+        },
+    },
+    vector::Vector2ISize,
+};
+use std::hash::BuildHasher;
+
+// Storages joined rect by rect: a storage reference, or a pair of them, nested pairs joining more
+// storages. `rect` looks up the rects of all of them at the same origin once, `mask` tells which
+// hexes they all have there.
+pub trait RectJoin {
+    type Rect: Copy;
+    type Item;
+
+    fn rect(&self, rect_origin: Vector2ISize) -> Option<Self::Rect>;
+
+    fn mask(rect: Self::Rect) -> u64;
+
+    fn item(rect: Self::Rect, x: usize, y: usize) -> Self::Item;
+}
+
+impl<'a, H, S: BuildHasher> RectJoin for &'a RectHashStorage<H, S> {
+    type Rect = &'a RectStorage<H>;
+    type Item = &'a H;
+
+    fn rect(&self, rect_origin: Vector2ISize) -> Option<Self::Rect> {
+        RectHashStorage::rect(self, rect_origin)
+    }
+
+    fn mask(rect: Self::Rect) -> u64 {
+        rect.mask()
+    }
+
+    fn item(rect: Self::Rect, x: usize, y: usize) -> Self::Item {
+        rect.get(x, y).expect("joined hex")
+    }
+}
+
+impl<A: RectJoin, B: RectJoin> RectJoin for (A, B) {
+    type Rect = (A::Rect, B::Rect);
+    type Item = (A::Item, B::Item);
+
+    fn rect(&self, rect_origin: Vector2ISize) -> Option<Self::Rect> {
+        Some((self.0.rect(rect_origin)?, self.1.rect(rect_origin)?))
+    }
+
+    fn mask(rect: Self::Rect) -> u64 {
+        A::mask(rect.0) & B::mask(rect.1)
+    }
+
+    fn item(rect: Self::Rect, x: usize, y: usize) -> Self::Item {
+        (A::item(rect.0, x, y), B::item(rect.1, x, y))
+    }
+}
+
+// The hexes present in the first storage and in all the others, with their values. Each rect is
+// looked up once per storage and only the hexes present everywhere are visited.
+pub fn join<'a, H, S, R>(
+    first: &'a RectHashStorage<H, S>,
+    others: R,
+) -> impl 'a + Iterator<Item = (AxialVector, &'a H, R::Item)>
+where
+    S: BuildHasher,
+    R: 'a + RectJoin,
+{
+    first
+        .rects()
+        .filter_map(move |(rect_origin, rect)| {
+            others
+                .rect(rect_origin)
+                .map(|others_rect| (rect_origin, rect, others_rect))
+        })
+        .flat_map(|(rect_origin, rect, others_rect)| {
+            mask_offsets(rect.mask() & R::mask(others_rect)).map(move |offset| {
+                let (x, y) = (offset % RECT_X_LEN, offset / RECT_X_LEN);
+                (
+                    rect_position(rect_origin, x, y),
+                    rect.get(x, y).expect("joined hex"),
+                    R::item(others_rect, x, y),
+                )
+            })
+        })
+}
+
+// All the hexes of the first storage with their values, and the values of the others when they all
+// have the hex.
+pub fn left_join<'a, H, S, R>(
+    first: &'a RectHashStorage<H, S>,
+    others: R,
+) -> impl 'a + Iterator<Item = (AxialVector, &'a H, Option<R::Item>)>
+where
+    S: BuildHasher,
+    R: 'a + RectJoin,
+{
+    first.rects().flat_map(move |(rect_origin, rect)| {
+        let others_rect = others.rect(rect_origin);
+        let others_mask = others_rect.map_or(0, R::mask);
+        mask_offsets(rect.mask()).map(move |offset| {
+            let (x, y) = (offset % RECT_X_LEN, offset / RECT_X_LEN);
+            (
+                rect_position(rect_origin, x, y),
+                rect.get(x, y).expect("joined hex"),
+                others_rect
+                    .filter(|_| others_mask & 1 << offset != 0)
+                    .map(|others_rect| R::item(others_rect, x, y)),
+            )
+        })
+    })
+}
+
+// join_storages!(a, b, c, ...) joins any number of storages, the values of the storages after the
+// first two come in nested pairs: (position, &A, (&B, (&C, &D))).
+#[macro_export]
+macro_rules! join_storages {
+    ($first:expr, $($others:expr),+ $(,)?) => {
+        $crate::hex::storage::join::join(&$first, $crate::join_others!($($others),+))
+    };
+}
+
+#[macro_export]
+macro_rules! left_join_storages {
+    ($first:expr, $($others:expr),+ $(,)?) => {
+        $crate::hex::storage::join::left_join(&$first, $crate::join_others!($($others),+))
+    };
+}
+
+#[macro_export]
+macro_rules! join_others {
+    ($last:expr) => {
+        &$last
+    };
+    ($head:expr, $($tail:expr),+) => {
+        (&$head, $crate::join_others!($($tail),+))
+    };
+}
+
+#[cfg(test)]
+fn test_layers() -> (
+    RectHashStorage<usize>,
+    RectHashStorage<bool>,
+    RectHashStorage<char>,
+) {
+    let mut terrain = RectHashStorage::new();
+    let mut visible = RectHashStorage::new();
+    let mut light = RectHashStorage::new();
+    for (index, position) in AxialVector::new(3, -2).spiral_iter(12).enumerate() {
+        terrain.insert(position, index);
+        if index % 3 == 0 {
+            visible.insert(position, index % 2 == 0);
+        }
+        if index % 5 == 0 {
+            light.insert(position, 'L');
+        }
+    }
+    // Out of the terrain.
+    visible.insert(AxialVector::new(100, 100), true);
+    light.insert(AxialVector::new(100, 100), 'L');
+    (terrain, visible, light)
+}
+
+#[cfg(test)]
+fn test_count(terrain: &RectHashStorage<usize>, modulo: usize) -> usize {
+    terrain
+        .iter()
+        .filter(|(_, index)| *index % modulo == 0)
+        .count()
+}
+
+#[test]
+fn test_join() {
+    let (terrain, visible, light) = test_layers();
+    let mut joined = join(&terrain, &visible).collect::<Vec<_>>();
+    joined.sort_by_key(|(_, index, _)| **index);
+    assert_eq!(joined.len(), test_count(&terrain, 3));
+    for (position, index, visible) in joined {
+        assert_eq!(terrain.get(position), Some(index));
+        assert_eq!(*index % 3, 0);
+        assert_eq!(*visible, *index % 2 == 0);
+    }
+
+    let three = join_storages!(terrain, visible, light).collect::<Vec<_>>();
+    assert_eq!(three.len(), test_count(&terrain, 15));
+    for (position, index, (visible, light)) in three {
+        assert_eq!(*index % 15, 0);
+        assert_eq!(visible, &(*index % 2 == 0));
+        assert_eq!(light, &'L');
+        assert_eq!(terrain.get(position), Some(index));
+    }
+}
+
+#[test]
+fn test_left_join() {
+    let (terrain, visible, light) = test_layers();
+    let joined = left_join(&terrain, &light).collect::<Vec<_>>();
+    assert_eq!(joined.len(), terrain.len());
+    for (_, index, light) in joined {
+        assert_eq!(light.is_some(), *index % 5 == 0);
+    }
+
+    let three = left_join_storages!(terrain, visible, light).collect::<Vec<_>>();
+    assert_eq!(three.len(), terrain.len());
+    assert_eq!(
+        three
+            .iter()
+            .filter(|(_, _, others)| others.is_some())
+            .count(),
+        test_count(&terrain, 15)
+    );
+}
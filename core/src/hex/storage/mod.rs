@@ -1,3 +1,16 @@
 pub mod adjacent;
+pub mod arena;
+pub mod binary;
+pub mod cursor;
+pub mod delta;
+pub mod diff;
 pub mod hash;
+pub mod history;
+pub mod interval;
+pub mod join;
+pub mod offset;
+#[cfg(feature = "persistence")]
+pub mod persistent;
 pub mod rect;
+pub mod set;
+pub mod side;
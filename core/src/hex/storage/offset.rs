@@ -0,0 +1,135 @@
+use crate::hex::{
+    coordinates::offset::{HexOrientation, OffsetVector},
+    storage::hash::RectHashStorage,
+};
+use std::hash::BuildHasher;
+
+// A storage seen as a square grid, indexed by column and row, see OffsetVector for the staggering.
+pub struct OffsetGrid<'a, H, S> {
+    storage: &'a RectHashStorage<H, S>,
+    orientation: HexOrientation,
+}
+
+impl<'a, H, S: BuildHasher> OffsetGrid<'a, H, S> {
+    pub fn get(&self, col: isize, row: isize) -> Option<&'a H> {
+        self.storage
+            .get(OffsetVector::new(col, row).to_axial(self.orientation))
+    }
+
+    pub fn iter(&self) -> impl 'a + Iterator<Item = (OffsetVector, &'a H)> {
+        let orientation = self.orientation;
+        self.storage
+            .iter()
+            .map(move |(position, hex)| (OffsetVector::from_axial(position, orientation), hex))
+    }
+
+    // The first and the last columns and rows of the hexes, both included.
+    pub fn bounds(&self) -> Option<(OffsetVector, OffsetVector)> {
+        self.iter().fold(None, |bounds, (offset, _)| {
+            Some(match bounds {
+                Some((min, max)) => (
+                    OffsetVector::new(min.col.min(offset.col), min.row.min(offset.row)),
+                    OffsetVector::new(max.col.max(offset.col), max.row.max(offset.row)),
+                ),
+                None => (offset, offset),
+            })
+        })
+    }
+
+    // The rows of the bounding box from its first one, and its first column and row. Each row
+    // goes from the first column, the missing hexes are None.
+    pub fn rows(&self) -> (OffsetVector, Vec<Vec<Option<&'a H>>>) {
+        let (min, max) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return (OffsetVector::default(), Vec::new()),
+        };
+        let width = (max.col - min.col + 1) as usize;
+        let mut rows = vec![vec![None; width]; (max.row - min.row + 1) as usize];
+        for (offset, hex) in self.iter() {
+            rows[(offset.row - min.row) as usize][(offset.col - min.col) as usize] = Some(hex);
+        }
+        (min, rows)
+    }
+}
+
+impl<H, S: BuildHasher> RectHashStorage<H, S> {
+    pub fn offset_grid(&self, orientation: HexOrientation) -> OffsetGrid<'_, H, S> {
+        OffsetGrid {
+            storage: self,
+            orientation,
+        }
+    }
+
+    // Back from a 2D array, the first cell of the first row going at `origin`. None leaves the
+    // hex out of the storage.
+    pub fn from_offset_rows<R, C>(
+        rows: R,
+        origin: OffsetVector,
+        orientation: HexOrientation,
+    ) -> Self
+    where
+        S: Default,
+        R: IntoIterator<Item = C>,
+        C: IntoIterator<Item = Option<H>>,
+    {
+        let mut storage = Self::with_hasher(S::default());
+        for (row, cells) in rows.into_iter().enumerate() {
+            for (col, hex) in cells.into_iter().enumerate() {
+                if let Some(hex) = hex {
+                    let offset =
+                        OffsetVector::new(origin.col + col as isize, origin.row + row as isize);
+                    storage.insert(offset.to_axial(orientation), hex);
+                }
+            }
+        }
+        storage
+    }
+}
+
+#[test]
+fn test_offset_grid() {
+    use crate::hex::coordinates::axial::AxialVector;
+    let mut storage = RectHashStorage::new();
+    for position in AxialVector::new(-5, 2).spiral_iter(3) {
+        storage.insert(position, position.q() * 100 + position.r());
+    }
+    for orientation in &[HexOrientation::PointyTop, HexOrientation::FlatTop] {
+        let grid = storage.offset_grid(*orientation);
+        for (position, hex) in storage.iter() {
+            let offset = OffsetVector::from_axial(position, *orientation);
+            assert_eq!(grid.get(offset.col, offset.row), Some(hex));
+        }
+        let (origin, rows) = grid.rows();
+        assert_eq!(rows.len(), 7);
+        assert_eq!(
+            rows.iter().flatten().filter(|hex| hex.is_some()).count(),
+            storage.len()
+        );
+        let back = RectHashStorage::<isize>::from_offset_rows(
+            rows.iter()
+                .map(|row| row.iter().map(|hex| hex.copied()).collect::<Vec<_>>()),
+            origin,
+            *orientation,
+        );
+        assert_eq!(back.len(), storage.len());
+        assert!(storage
+            .iter()
+            .all(|(position, hex)| back.get(position) == Some(hex)));
+    }
+
+    // A 3 by 2 image whose second row is shifted.
+    let image = [[1, 2, 3], [4, 5, 6]];
+    let storage = RectHashStorage::<i32>::from_offset_rows(
+        image.iter().map(|row| row.iter().map(|pixel| Some(*pixel))),
+        OffsetVector::default(),
+        HexOrientation::PointyTop,
+    );
+    assert_eq!(storage.get(AxialVector::new(2, 0)), Some(&3));
+    assert_eq!(storage.get(AxialVector::new(0, 1)), Some(&4));
+    assert_eq!(storage.get(AxialVector::new(2, 1)), Some(&6));
+    assert!(RectHashStorage::<i32>::new()
+        .offset_grid(HexOrientation::FlatTop)
+        .rows()
+        .1
+        .is_empty());
+}
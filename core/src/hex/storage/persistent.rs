@@ -0,0 +1,490 @@
+use crate::{
+    hex::{
+        coordinates::axial::AxialVector,
+        storage::{
+            binary::{invalid_data, read_header, write_header, BinaryHex},
+            hash::HexBuildHasher,
+            rect::{RectStorage, RECT_X_LEN, RECT_Y_LEN},
+        },
+    },
+    vector::Vector2ISize,
+};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression, Crc};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{BufReader, BufWriter, ErrorKind, Read, Result, Write},
+    path::{Path, PathBuf},
+};
+
+const CHUNK_EXTENSION: &str = "chunk";
+const TMP_EXTENSION: &str = "tmp";
+
+fn checksum(payload: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(payload);
+    crc.sum()
+}
+
+struct Chunk<H> {
+    rect: RectStorage<H>,
+    dirty: bool,
+    last_used: u64,
+}
+
+// At most `max_loaded_chunks` chunks stay in memory, the least recently used one is written back
+// (if dirty) and unloaded to make room for another. The dirty chunks are flushed on drop, but the
+// errors can only be handled by calling flush() or unload() first.
+pub struct PersistentHexMap<H: BinaryHex> {
+    path: PathBuf,
+    on_disk: HashSet<Vector2ISize, HexBuildHasher>,
+    loaded: HashMap<Vector2ISize, Chunk<H>, HexBuildHasher>,
+    max_loaded_chunks: usize,
+    clock: u64,
+    written_chunks: usize,
+}
+
+impl<H: BinaryHex> PersistentHexMap<H> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        fs::create_dir_all(&path)?;
+        let mut on_disk = HashSet::default();
+        for entry in fs::read_dir(&path)? {
+            let entry_path = entry?.path();
+            if entry_path.extension().and_then(|ext| ext.to_str()) != Some(CHUNK_EXTENSION) {
+                continue;
+            }
+            let rect_origin = entry_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(Self::parse_chunk_name)
+                .ok_or_else(|| {
+                    invalid_data(format!("Invalid chunk file {}", entry_path.display()))
+                })?;
+            on_disk.insert(rect_origin);
+        }
+        Ok(Self {
+            path,
+            on_disk,
+            loaded: HashMap::default(),
+            max_loaded_chunks: usize::MAX,
+            clock: 0,
+            written_chunks: 0,
+        })
+    }
+
+    pub fn with_max_loaded_chunks(mut self, max_loaded_chunks: usize) -> Self {
+        self.max_loaded_chunks = max_loaded_chunks.max(1);
+        self
+    }
+
+    pub fn get(&mut self, position: AxialVector) -> Result<Option<&H>> {
+        let (rect_origin, x, y) = Self::split_position(position);
+        Ok(self
+            .load_chunk(rect_origin)?
+            .and_then(|chunk| chunk.rect.get(x, y)))
+    }
+
+    pub fn get_mut(&mut self, position: AxialVector) -> Result<Option<&mut H>> {
+        let (rect_origin, x, y) = Self::split_position(position);
+        Ok(self.load_chunk(rect_origin)?.and_then(|chunk| {
+            let hex = chunk.rect.get_mut(x, y);
+            if hex.is_some() {
+                chunk.dirty = true;
+            }
+            hex
+        }))
+    }
+
+    pub fn contains_position(&mut self, position: AxialVector) -> Result<bool> {
+        let (rect_origin, x, y) = Self::split_position(position);
+        Ok(match self.load_chunk(rect_origin)? {
+            Some(chunk) => chunk.rect.contains_position(x, y),
+            None => false,
+        })
+    }
+
+    pub fn insert(&mut self, position: AxialVector, hex: H) -> Result<Option<H>> {
+        let (rect_origin, x, y) = Self::split_position(position);
+        let chunk = match self.load_chunk(rect_origin)? {
+            Some(chunk) => chunk,
+            None => self.insert_chunk(rect_origin, RectStorage::new())?,
+        };
+        chunk.dirty = true;
+        Ok(chunk.rect.insert(x, y, hex))
+    }
+
+    pub fn remove(&mut self, position: AxialVector) -> Result<Option<H>> {
+        let (rect_origin, x, y) = Self::split_position(position);
+        Ok(self.load_chunk(rect_origin)?.and_then(|chunk| {
+            let hex = chunk.rect.remove(x, y);
+            if hex.is_some() {
+                chunk.dirty = true;
+            }
+            hex
+        }))
+    }
+
+    pub fn loaded_chunks(&self) -> usize {
+        self.loaded.len()
+    }
+
+    // The number of chunk files written since the map was opened, by flushes and evictions.
+    pub fn written_chunks(&self) -> usize {
+        self.written_chunks
+    }
+
+    // Reads every chunk on disk and returns the origins of the corrupted ones, sorted. The loaded
+    // chunks are not compared to their files, flush them first to check what they will be read as.
+    pub fn verify(&self) -> Result<Vec<Vector2ISize>> {
+        let mut rect_origins = self.on_disk.iter().copied().collect::<Vec<_>>();
+        rect_origins.sort();
+        let mut corrupted = Vec::new();
+        for rect_origin in rect_origins {
+            match self.read_chunk(rect_origin) {
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::InvalidData => corrupted.push(rect_origin),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(corrupted)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        let mut rect_origins = self
+            .loaded
+            .iter()
+            .filter_map(|(rect_origin, chunk)| {
+                if chunk.dirty {
+                    Some(*rect_origin)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        rect_origins.sort();
+        for rect_origin in rect_origins {
+            self.write_back(rect_origin)?;
+        }
+        Ok(())
+    }
+
+    pub fn unload(&mut self) -> Result<()> {
+        self.flush()?;
+        self.loaded.clear();
+        Ok(())
+    }
+
+    fn load_chunk(&mut self, rect_origin: Vector2ISize) -> Result<Option<&mut Chunk<H>>> {
+        if !self.loaded.contains_key(&rect_origin) {
+            if !self.on_disk.contains(&rect_origin) {
+                return Ok(None);
+            }
+            let rect = self.read_chunk(rect_origin)?;
+            let chunk = self.insert_chunk(rect_origin, rect)?;
+            chunk.dirty = false;
+            return Ok(Some(chunk));
+        }
+        self.clock += 1;
+        let chunk = self.loaded.get_mut(&rect_origin).expect("loaded chunk");
+        chunk.last_used = self.clock;
+        Ok(Some(chunk))
+    }
+
+    fn insert_chunk(
+        &mut self,
+        rect_origin: Vector2ISize,
+        rect: RectStorage<H>,
+    ) -> Result<&mut Chunk<H>> {
+        while self.loaded.len() >= self.max_loaded_chunks {
+            self.evict_chunk()?;
+        }
+        self.clock += 1;
+        Ok(self.loaded.entry(rect_origin).or_insert(Chunk {
+            rect,
+            dirty: true,
+            last_used: self.clock,
+        }))
+    }
+
+    // The least recently used chunk stays loaded if it cannot be written back.
+    fn evict_chunk(&mut self) -> Result<()> {
+        let rect_origin = *self
+            .loaded
+            .iter()
+            .min_by_key(|(_, chunk)| chunk.last_used)
+            .expect("loaded chunk")
+            .0;
+        if self.loaded[&rect_origin].dirty {
+            self.write_back(rect_origin)?;
+        }
+        self.loaded.remove(&rect_origin);
+        Ok(())
+    }
+
+    // An empty chunk is deleted instead of being written.
+    // The chunk is written to a temporary file of the same directory, then renamed over the chunk
+    // file: an interrupted write leaves the previous chunk intact, and open() skips the leftovers.
+    fn write_back(&mut self, rect_origin: Vector2ISize) -> Result<()> {
+        let chunk_path = self.chunk_path(rect_origin);
+        let chunk = self.loaded.get_mut(&rect_origin).expect("loaded chunk");
+        if !chunk.rect.is_empty() {
+            let mut payload = Vec::new();
+            chunk.rect.write_to(&mut payload)?;
+            let tmp_path = chunk_path.with_extension(TMP_EXTENSION);
+            let write_tmp = || -> Result<()> {
+                let mut writer = BufWriter::new(File::create(&tmp_path)?);
+                write_header(&mut writer)?;
+                checksum(&payload).write_hex(&mut writer)?;
+                let mut encoder = DeflateEncoder::new(writer, Compression::default());
+                encoder.write_all(&payload)?;
+                let mut writer = encoder.finish()?;
+                writer.flush()?;
+                writer.get_ref().sync_all()
+            };
+            if let Err(err) = write_tmp().and_then(|()| fs::rename(&tmp_path, &chunk_path)) {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(err);
+            }
+            self.on_disk.insert(rect_origin);
+            self.written_chunks += 1;
+        } else if self.on_disk.remove(&rect_origin) {
+            fs::remove_file(&chunk_path)?;
+        }
+        chunk.dirty = false;
+        Ok(())
+    }
+
+    // The header is followed by the checksum of the uncompressed hexes, a chunk file that does not
+    // match it (or cannot even be decompressed) is reported as invalid data instead of being read
+    // as garbage.
+    fn read_chunk(&self, rect_origin: Vector2ISize) -> Result<RectStorage<H>> {
+        let chunk_path = self.chunk_path(rect_origin);
+        let mut reader = BufReader::new(File::open(&chunk_path)?);
+        let corrupted = || invalid_data(format!("Corrupted chunk file {}", chunk_path.display()));
+        let mut read_payload = || -> Result<(u32, Vec<u8>)> {
+            read_header(&mut reader)?;
+            let expected = u32::read_hex(&mut reader)?;
+            let mut payload = Vec::new();
+            DeflateDecoder::new(&mut reader).read_to_end(&mut payload)?;
+            Ok((expected, payload))
+        };
+        let (expected, payload) = match read_payload() {
+            Ok(read) => read,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::UnexpectedEof | ErrorKind::InvalidInput
+                ) =>
+            {
+                return Err(corrupted());
+            }
+            Err(err) => return Err(err),
+        };
+        if checksum(&payload) != expected {
+            return Err(corrupted());
+        }
+        RectStorage::read_from(&mut payload.as_slice())
+    }
+
+    fn chunk_path(&self, rect_origin: Vector2ISize) -> PathBuf {
+        self.path.join(format!(
+            "{}_{}.{}",
+            rect_origin.x, rect_origin.y, CHUNK_EXTENSION
+        ))
+    }
+
+    fn parse_chunk_name(name: &str) -> Option<Vector2ISize> {
+        let mut split = name.splitn(2, '_');
+        let x = split.next()?.parse().ok()?;
+        let y = split.next()?.parse().ok()?;
+        Some(Vector2ISize { x, y })
+    }
+
+    fn split_position(position: AxialVector) -> (Vector2ISize, usize, usize) {
+        (
+            Vector2ISize {
+                x: position.q().div_euclid(RECT_X_LEN as isize),
+                y: position.r().div_euclid(RECT_Y_LEN as isize),
+            },
+            position.q().rem_euclid(RECT_X_LEN as isize) as usize,
+            position.r().rem_euclid(RECT_Y_LEN as isize) as usize,
+        )
+    }
+}
+
+impl<H: BinaryHex> Drop for PersistentHexMap<H> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+fn test_map_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rhombus-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+#[test]
+fn test_persistent_hex_map_should_persist_hexes() {
+    let path = test_map_path("persist");
+    {
+        let mut map = PersistentHexMap::open(&path).unwrap();
+        map.insert(AxialVector::new(12, -42), 93u32).unwrap();
+        map.insert(AxialVector::new(-5, 24), 7).unwrap();
+        map.insert(AxialVector::new(12, -42), 42).unwrap();
+        map.flush().unwrap();
+    }
+    {
+        let mut map = PersistentHexMap::<u32>::open(&path).unwrap();
+        assert_eq!(map.loaded_chunks(), 0);
+        assert_eq!(map.get(AxialVector::new(12, -42)).unwrap(), Some(&42));
+        assert_eq!(map.loaded_chunks(), 1);
+        assert_eq!(map.get(AxialVector::new(-5, 24)).unwrap(), Some(&7));
+        assert_eq!(map.get(AxialVector::new(0, 0)).unwrap(), None);
+        assert_eq!(map.loaded_chunks(), 2);
+    }
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_persistent_hex_map_should_write_back_dirty_chunks_only() {
+    let path = test_map_path("dirty");
+    let mut map = PersistentHexMap::open(&path).unwrap();
+    map.insert(AxialVector::new(0, 0), 1u8).unwrap();
+    map.insert(AxialVector::new(100, 100), 2).unwrap();
+    map.unload().unwrap();
+    assert_eq!(map.loaded_chunks(), 0);
+    assert_eq!(map.written_chunks(), 2);
+
+    *map.get_mut(AxialVector::new(0, 0)).unwrap().unwrap() = 3;
+    assert_eq!(map.get(AxialVector::new(100, 100)).unwrap(), Some(&2));
+    map.unload().unwrap();
+    assert_eq!(map.written_chunks(), 3);
+    assert_eq!(map.get(AxialVector::new(0, 0)).unwrap(), Some(&3));
+    map.unload().unwrap();
+    assert_eq!(map.written_chunks(), 3);
+
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_persistent_hex_map_should_delete_empty_chunks() {
+    let path = test_map_path("empty");
+    let mut map = PersistentHexMap::open(&path).unwrap();
+    map.insert(AxialVector::new(-1, -1), 1u8).unwrap();
+    map.flush().unwrap();
+    assert!(path.join("-1_-1.chunk").exists());
+
+    assert_eq!(map.remove(AxialVector::new(-1, -1)).unwrap(), Some(1));
+    map.flush().unwrap();
+    assert!(!path.join("-1_-1.chunk").exists());
+    assert!(!map.contains_position(AxialVector::new(-1, -1)).unwrap());
+
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_persistent_hex_map_should_report_corrupted_chunks() {
+    let path = test_map_path("corrupted");
+    let mut map = PersistentHexMap::open(&path).unwrap();
+    map.insert(AxialVector::new(0, 0), 1u32).unwrap();
+    map.insert(AxialVector::new(8, 0), 2).unwrap();
+    map.insert(AxialVector::new(16, 0), 3).unwrap();
+    map.unload().unwrap();
+    assert_eq!(map.verify().unwrap(), vec![]);
+
+    // One bit flipped in the checksum, then a truncated file.
+    let flipped = path.join("1_0.chunk");
+    let mut bytes = fs::read(&flipped).unwrap();
+    bytes[5] ^= 1;
+    fs::write(&flipped, bytes).unwrap();
+    let truncated = path.join("2_0.chunk");
+    let bytes = fs::read(&truncated).unwrap();
+    fs::write(&truncated, &bytes[..bytes.len() / 2]).unwrap();
+
+    assert_eq!(
+        map.verify().unwrap(),
+        vec![Vector2ISize { x: 1, y: 0 }, Vector2ISize { x: 2, y: 0 }]
+    );
+    assert_eq!(
+        map.get(AxialVector::new(8, 0)).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    assert_eq!(
+        map.get(AxialVector::new(16, 0)).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    assert_eq!(map.get(AxialVector::new(0, 0)).unwrap(), Some(&1));
+
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_persistent_hex_map_should_evict_least_recently_used_chunks() {
+    let path = test_map_path("evict");
+    let mut map = PersistentHexMap::open(&path)
+        .unwrap()
+        .with_max_loaded_chunks(2);
+    map.insert(AxialVector::new(0, 0), 1u8).unwrap();
+    map.insert(AxialVector::new(8, 0), 2).unwrap();
+    assert_eq!(map.get(AxialVector::new(0, 0)).unwrap(), Some(&1));
+    map.insert(AxialVector::new(16, 0), 3).unwrap();
+    assert_eq!(map.loaded_chunks(), 2);
+    assert_eq!(map.written_chunks(), 1);
+    assert!(path.join("1_0.chunk").exists());
+    assert!(!path.join("0_0.chunk").exists());
+
+    // Reading the evicted chunk back writes the least recently used one.
+    assert_eq!(map.get(AxialVector::new(8, 0)).unwrap(), Some(&2));
+    assert_eq!(map.written_chunks(), 2);
+    assert!(path.join("0_0.chunk").exists());
+    assert!(!path.join("2_0.chunk").exists());
+
+    // Clean chunks are unloaded without being written.
+    map.flush().unwrap();
+    assert_eq!(map.written_chunks(), 3);
+    assert_eq!(map.get(AxialVector::new(16, 0)).unwrap(), Some(&3));
+    assert_eq!(map.get(AxialVector::new(0, 0)).unwrap(), Some(&1));
+    assert_eq!(map.written_chunks(), 3);
+    assert_eq!(map.loaded_chunks(), 2);
+
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_persistent_hex_map_should_flush_on_drop() {
+    let path = test_map_path("drop");
+    {
+        let mut map = PersistentHexMap::open(&path).unwrap();
+        map.insert(AxialVector::new(3, -9), 5u16).unwrap();
+    }
+    let mut map = PersistentHexMap::<u16>::open(&path).unwrap();
+    assert_eq!(map.get(AxialVector::new(3, -9)).unwrap(), Some(&5));
+
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_persistent_hex_map_should_replace_chunks_through_temporary_files() {
+    let path = test_map_path("replace");
+    let mut map = PersistentHexMap::open(&path).unwrap();
+    map.insert(AxialVector::new(0, 0), 1u8).unwrap();
+    map.flush().unwrap();
+    map.insert(AxialVector::new(0, 0), 2).unwrap();
+    map.flush().unwrap();
+    let names = fs::read_dir(&path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["0_0.chunk"]);
+
+    // A leftover of an interrupted write is not taken for a chunk.
+    fs::write(path.join("1_0.tmp"), b"partial").unwrap();
+    let mut map = PersistentHexMap::<u8>::open(&path).unwrap();
+    assert_eq!(map.get(AxialVector::new(0, 0)).unwrap(), Some(&2));
+    assert_eq!(map.get(AxialVector::new(8, 0)).unwrap(), None);
+
+    fs::remove_dir_all(&path).unwrap();
+}
@@ -1,4 +1,8 @@
-use std::mem::MaybeUninit;
+use crate::hex::storage::binary::BinaryHex;
+use std::{
+    io::{Read, Result, Write},
+    mem::MaybeUninit,
+};
 
 pub const RECT_X_LEN: usize = 8;
 pub const RECT_Y_LEN: usize = 8;
@@ -38,6 +42,18 @@ impl<H> RectStorage<H> {
         }
     }
 
+    // Like get_mut without borrowing the whole rect, so that several hexes of a rect can be
+    // borrowed at once. The rect must outlive the hex and the positions must be distinct.
+    pub(crate) unsafe fn get_ptr_mut(rect: *mut Self, x: usize, y: usize) -> Option<*mut H> {
+        Self::check_bounds(x, y);
+        let offset = x + y * RECT_X_LEN;
+        if (*rect).option_bits & (1 << offset as u64) != 0 {
+            Some((*rect).hexes[offset].as_mut_ptr())
+        } else {
+            None
+        }
+    }
+
     pub fn contains_position(&self, x: usize, y: usize) -> bool {
         Self::check_bounds(x, y);
         let offset = x + y * RECT_X_LEN;
@@ -48,6 +64,15 @@ impl<H> RectStorage<H> {
         self.option_bits.count_ones() as usize
     }
 
+    // Bit x + y * RECT_X_LEN is set for every hex.
+    pub fn mask(&self) -> u64 {
+        self.option_bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.option_bits == 0
+    }
+
     pub fn iter(&self) -> Iter<H> {
         Iter {
             storage: self,
@@ -142,6 +167,31 @@ impl<H> RectStorage<H> {
     }
 }
 
+impl<H: BinaryHex> RectStorage<H> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.option_bits.write_hex(writer)?;
+        for hex in self.hexes() {
+            hex.write_hex(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut storage = Self::new();
+        let option_bits = u64::read_hex(reader)?;
+        for offset in 0..RECT_X_LEN * RECT_Y_LEN {
+            if option_bits & 1 << offset as u64 != 0 {
+                storage.insert(
+                    offset % RECT_X_LEN,
+                    offset / RECT_X_LEN,
+                    H::read_hex(reader)?,
+                );
+            }
+        }
+        Ok(storage)
+    }
+}
+
 impl<C> Drop for RectStorage<C> {
     fn drop(&mut self) {
         for offset in 0..(self.hexes.len()) {
@@ -152,6 +202,68 @@ impl<C> Drop for RectStorage<C> {
     }
 }
 
+// The offsets of the bits set in the mask, in increasing order.
+pub(crate) fn mask_offsets(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let offset = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(offset)
+    })
+}
+
+// Counts the radius 1 neighbors of the 64 hexes of a rect at once. `masks` holds the bit masks of
+// the rect (at [1][1]) and of the rects around it, indexed by [y][x]. The counts are bit sliced:
+// bit `x + y * RECT_X_LEN` of the plane k is the bit k of the count of the hex (x, y).
+pub fn count_neighbors(masks: &[[u64; 3]; 3]) -> [u64; 3] {
+    let mut planes = [0u64; 3];
+    for (dx, dy) in &[(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)] {
+        let neighbors = shift_masks(masks, *dx, *dy);
+        // Bit sliced increment, the count never exceeds 6.
+        let carry = planes[0] & neighbors;
+        planes[0] ^= neighbors;
+        planes[2] |= planes[1] & carry;
+        planes[1] ^= carry;
+    }
+    planes
+}
+
+// Bit (x, y) of the result is the bit (x + dx, y + dy) of the masks, possibly in a rect around.
+fn shift_masks(masks: &[[u64; 3]; 3], dx: isize, dy: isize) -> u64 {
+    let (x_len, y_len) = (RECT_X_LEN as isize, RECT_Y_LEN as isize);
+    let mut result = 0;
+    for (ry, row) in masks.iter().enumerate() {
+        for (rx, mask) in row.iter().enumerate() {
+            let (rx, ry) = (rx as isize - 1, ry as isize - 1);
+            // The hexes of the rect whose neighbor falls in that rect.
+            let (x0, x1) = (
+                (rx * x_len - dx).max(0),
+                (rx * x_len - dx + x_len).min(x_len),
+            );
+            let (y0, y1) = (
+                (ry * y_len - dy).max(0),
+                (ry * y_len - dy + y_len).min(y_len),
+            );
+            if *mask == 0 || x0 >= x1 || y0 >= y1 {
+                continue;
+            }
+            let shift = dx - rx * x_len + (dy - ry * y_len) * x_len;
+            let shifted = if shift >= 0 {
+                mask >> shift
+            } else {
+                mask << -shift
+            };
+            let row_window = ((1u64 << (x1 - x0)) - 1) << x0;
+            for y in y0..y1 {
+                result |= shifted & row_window << (y * x_len);
+            }
+        }
+    }
+    result
+}
+
 pub struct Iter<'a, H> {
     storage: &'a RectStorage<H>,
     next_offset: usize,
@@ -690,3 +802,35 @@ fn test_rect_storage_should_have_entry_api() {
         panic!();
     }
 }
+
+#[test]
+fn test_count_neighbors() {
+    use crate::hex::coordinates::axial::AxialVector;
+    // Pseudo random hexes over the 3x3 rects.
+    let is_set = |q: isize, r: isize| (q * 17 + r * 31 + q * r).rem_euclid(7) < 3;
+    let mut masks = [[0u64; 3]; 3];
+    for (ry, row) in masks.iter_mut().enumerate() {
+        for (rx, mask) in row.iter_mut().enumerate() {
+            for offset in 0..RECT_X_LEN * RECT_Y_LEN {
+                let q = (rx * RECT_X_LEN + offset % RECT_X_LEN) as isize;
+                let r = (ry * RECT_Y_LEN + offset / RECT_X_LEN) as isize;
+                if is_set(q, r) {
+                    *mask |= 1 << offset;
+                }
+            }
+        }
+    }
+    let planes = count_neighbors(&masks);
+    for offset in 0..RECT_X_LEN * RECT_Y_LEN {
+        let position = AxialVector::new(
+            (RECT_X_LEN + offset % RECT_X_LEN) as isize,
+            (RECT_Y_LEN + offset / RECT_X_LEN) as isize,
+        );
+        let expected = position
+            .ring_iter(1)
+            .filter(|neighbor| is_set(neighbor.q(), neighbor.r()))
+            .count();
+        let count = (0..3).fold(0, |count, k| count | ((planes[k] >> offset) & 1) << k);
+        assert_eq!(count as usize, expected);
+    }
+}
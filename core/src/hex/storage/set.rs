@@ -0,0 +1,605 @@
+use crate::{
+    hex::{
+        coordinates::{axial::AxialVector, direction::HexagonalDirection, transform::HexTransform},
+        storage::{
+            hash::{HexBuildHasher, RectHashStorage},
+            rect::{RECT_X_LEN, RECT_Y_LEN},
+        },
+    },
+    vector::Vector2ISize,
+};
+use std::{
+    collections::HashMap,
+    hash::BuildHasher,
+    iter::FromIterator,
+    ops::{BitAnd, BitOr, BitXor, Sub},
+};
+
+// Same rect layout as RectHashStorage, but each rect is reduced to the bit mask of its hexes. Rects
+// never hold an empty mask.
+#[derive(Clone, Debug)]
+pub struct HexSet<S = HexBuildHasher> {
+    rects: HashMap<Vector2ISize, u64, S>,
+    len: usize,
+}
+
+// Boolean attributes of the hexes (wall, visible, explored...) do not need a whole storage, a set
+// of the hexes having it has the same chunks and combines with the bitwise operations.
+pub type BitLayer<S = HexBuildHasher> = HexSet<S>;
+
+impl HexSet {
+    pub fn new() -> Self {
+        Self::with_hasher(HexBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> HexSet<S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            rects: HashMap::with_hasher(hasher),
+            len: 0,
+        }
+    }
+
+    // The hexes of the storage matching the predicate, rect by rect.
+    pub fn from_storage<H, T, P>(storage: &RectHashStorage<H, T>, predicate: P) -> Self
+    where
+        S: Default,
+        T: BuildHasher,
+        P: Fn(&H) -> bool,
+    {
+        let mut set = Self::with_hasher(S::default());
+        for (rect_origin, mask) in storage.rect_masks(predicate) {
+            if mask != 0 {
+                set.rects.insert(rect_origin, mask);
+                set.len += mask.count_ones() as usize;
+            }
+        }
+        set
+    }
+
+    pub(crate) fn rect_mask(&self, rect_origin: Vector2ISize) -> u64 {
+        self.rects.get(&rect_origin).copied().unwrap_or(0)
+    }
+
+    fn split(position: AxialVector) -> (Vector2ISize, u64) {
+        let x = position.q().div_euclid(RECT_X_LEN as isize);
+        let y = position.r().div_euclid(RECT_Y_LEN as isize);
+        let offset = position.q().rem_euclid(RECT_X_LEN as isize) as usize
+            + position.r().rem_euclid(RECT_Y_LEN as isize) as usize * RECT_X_LEN;
+        (Vector2ISize { x, y }, 1 << offset as u64)
+    }
+
+    pub fn contains(&self, position: AxialVector) -> bool {
+        let (rect_origin, bit) = Self::split(position);
+        self.rects.get(&rect_origin).copied().unwrap_or(0) & bit != 0
+    }
+
+    // Returns true if the position was not in the set.
+    pub fn insert(&mut self, position: AxialVector) -> bool {
+        let (rect_origin, bit) = Self::split(position);
+        let mask = self.rects.entry(rect_origin).or_insert(0);
+        if *mask & bit == 0 {
+            *mask |= bit;
+            self.len += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Returns true if the position was in the set.
+    pub fn remove(&mut self, position: AxialVector) -> bool {
+        let (rect_origin, bit) = Self::split(position);
+        if let Some(mask) = self.rects.get_mut(&rect_origin) {
+            if *mask & bit != 0 {
+                *mask &= !bit;
+                if *mask == 0 {
+                    self.rects.remove(&rect_origin);
+                }
+                self.len -= 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn clear(&mut self) {
+        self.rects.clear();
+        self.len = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter<'a>(&'a self) -> impl 'a + Iterator<Item = AxialVector> {
+        self.rects.iter().flat_map(|(rect_origin, mask)| {
+            let mut mask = *mask;
+            std::iter::from_fn(move || {
+                if mask == 0 {
+                    return None;
+                }
+                let offset = mask.trailing_zeros() as usize;
+                mask &= mask - 1;
+                Some(AxialVector::new(
+                    rect_origin.x * RECT_X_LEN as isize + (offset % RECT_X_LEN) as isize,
+                    rect_origin.y * RECT_Y_LEN as isize + (offset / RECT_X_LEN) as isize,
+                ))
+            })
+        })
+    }
+
+    // Row by row (r, then q), the order in which the rects are laid out in memory, e.g. to fill a
+    // storage. The rects are sorted first, iter() is cheaper when the order does not matter.
+    pub fn scanline_iter(&self) -> impl Iterator<Item = AxialVector> {
+        let mut rects = self
+            .rects
+            .iter()
+            .map(|(rect_origin, mask)| (*rect_origin, *mask))
+            .collect::<Vec<_>>();
+        rects.sort_unstable_by_key(|(rect_origin, _)| (rect_origin.y, rect_origin.x));
+        ScanlineIter {
+            rects,
+            rect_row: 0..0,
+            index: 0,
+            y: RECT_Y_LEN - 1,
+            row_mask: 0,
+        }
+    }
+
+    pub fn union_with<T: BuildHasher>(&mut self, other: &HexSet<T>) {
+        for (rect_origin, other_mask) in &other.rects {
+            let mask = self.rects.entry(*rect_origin).or_insert(0);
+            self.len += (other_mask & !*mask).count_ones() as usize;
+            *mask |= other_mask;
+        }
+    }
+
+    pub fn intersect_with<T: BuildHasher>(&mut self, other: &HexSet<T>) {
+        let mut len = 0;
+        self.rects.retain(|rect_origin, mask| {
+            *mask &= other.rects.get(rect_origin).copied().unwrap_or(0);
+            len += mask.count_ones() as usize;
+            *mask != 0
+        });
+        self.len = len;
+    }
+
+    pub fn difference_with<T: BuildHasher>(&mut self, other: &HexSet<T>) {
+        let mut len = 0;
+        self.rects.retain(|rect_origin, mask| {
+            *mask &= !other.rects.get(rect_origin).copied().unwrap_or(0);
+            len += mask.count_ones() as usize;
+            *mask != 0
+        });
+        self.len = len;
+    }
+
+    pub fn symmetric_difference_with<T: BuildHasher>(&mut self, other: &HexSet<T>) {
+        for (rect_origin, other_mask) in &other.rects {
+            *self.rects.entry(*rect_origin).or_insert(0) ^= other_mask;
+        }
+        let mut len = 0;
+        self.rects.retain(|_, mask| {
+            len += mask.count_ones() as usize;
+            *mask != 0
+        });
+        self.len = len;
+    }
+
+    pub fn is_subset<T: BuildHasher>(&self, other: &HexSet<T>) -> bool {
+        self.len <= other.len
+            && self.rects.iter().all(|(rect_origin, mask)| {
+                mask & !other.rects.get(rect_origin).copied().unwrap_or(0) == 0
+            })
+    }
+
+    pub fn transformed(&self, transform: HexTransform, center: AxialVector) -> HexSet {
+        self.iter()
+            .map(|position| transform.apply_around(position, center))
+            .collect()
+    }
+}
+
+// Morphological operations, a step of radius 1 at a time: the hex neighborhood of radius k is the
+// neighborhood of radius 1 grown k times. Each step shifts the whole rects in the six directions.
+impl<S: BuildHasher + Clone> HexSet<S> {
+    // The hexes at most `radius` away from the set.
+    pub fn dilate(&self, radius: usize) -> Self {
+        let mut set = self.clone();
+        for _ in 0..radius {
+            let step = set.clone();
+            for direction in 0..6 {
+                set.union_with(&step.shifted(direction));
+            }
+        }
+        set
+    }
+
+    // The hexes of the set which have the whole neighborhood of radius `radius` in it.
+    pub fn erode(&self, radius: usize) -> Self {
+        let mut set = self.clone();
+        for _ in 0..radius {
+            let step = set.clone();
+            for direction in 0..6 {
+                set.intersect_with(&step.shifted(direction));
+            }
+        }
+        set
+    }
+
+    // Removes the parts narrower than the neighborhood, e.g. thin corridors and peninsulas.
+    pub fn opening(&self, radius: usize) -> Self {
+        self.erode(radius).dilate(radius)
+    }
+
+    // Fills the gaps narrower than the neighborhood, e.g. holes and notches.
+    pub fn closing(&self, radius: usize) -> Self {
+        self.dilate(radius).erode(radius)
+    }
+
+    // The hexes next to the set but not in it, e.g. where a region grows or what is left to explore.
+    pub fn frontier(&self) -> Self {
+        let mut frontier = self.dilate(1);
+        frontier.difference_with(self);
+        frontier
+    }
+
+    fn shifted(&self, direction: usize) -> Self {
+        let step = AxialVector::direction(direction);
+        let mut set = Self::with_hasher(self.rects.hasher().clone());
+        for (rect_origin, mask) in &self.rects {
+            for ((dx, dy), part) in shift_mask(*mask, step.q(), step.r()).iter() {
+                if *part != 0 {
+                    *set.rects
+                        .entry(Vector2ISize {
+                            x: rect_origin.x + dx,
+                            y: rect_origin.y + dy,
+                        })
+                        .or_insert(0) |= part;
+                }
+            }
+        }
+        set.len = set
+            .rects
+            .values()
+            .map(|mask| mask.count_ones() as usize)
+            .sum();
+        set
+    }
+}
+
+// The rects sorted by row, then each row of hexes goes across the rects of the same rect row.
+struct ScanlineIter {
+    rects: Vec<(Vector2ISize, u64)>,
+    rect_row: std::ops::Range<usize>,
+    index: usize,
+    // The row of hexes in the rects.
+    y: usize,
+    // What is left of the row in the current rect.
+    row_mask: u64,
+}
+
+impl Iterator for ScanlineIter {
+    type Item = AxialVector;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.row_mask != 0 {
+                let x = self.row_mask.trailing_zeros() as usize;
+                self.row_mask &= self.row_mask - 1;
+                let rect_origin = self.rects[self.index].0;
+                return Some(AxialVector::new(
+                    rect_origin.x * RECT_X_LEN as isize + x as isize,
+                    rect_origin.y * RECT_Y_LEN as isize + self.y as isize,
+                ));
+            }
+            if self.index + 1 < self.rect_row.end {
+                self.index += 1;
+            } else if self.y + 1 < RECT_Y_LEN {
+                self.y += 1;
+                self.index = self.rect_row.start;
+            } else {
+                let start = self.rect_row.end;
+                if start == self.rects.len() {
+                    return None;
+                }
+                let rect_y = self.rects[start].0.y;
+                let end = self.rects[start..]
+                    .iter()
+                    .position(|(rect_origin, _)| rect_origin.y != rect_y)
+                    .map_or(self.rects.len(), |len| start + len);
+                self.rect_row = start..end;
+                self.index = start;
+                self.y = 0;
+            }
+            self.row_mask =
+                self.rects[self.index].1 >> (self.y * RECT_X_LEN) & ((1 << RECT_X_LEN) - 1);
+        }
+    }
+}
+
+fn column_mask(column: usize) -> u64 {
+    (0..RECT_Y_LEN).fold(0, |mask, row| mask | 1 << (row * RECT_X_LEN + column))
+}
+
+// The mask of a rect moved by dq and dr (-1, 0 or 1), split between the rect and its neighbors,
+// each part along with the offset of its rect.
+fn shift_mask(mask: u64, dq: isize, dr: isize) -> [((isize, isize), u64); 4] {
+    let (inner, carry) = shift_columns(mask, dq);
+    let (inner_inner, inner_carry) = shift_rows(inner, dr);
+    let (carry_inner, carry_carry) = shift_rows(carry, dr);
+    [
+        ((0, 0), inner_inner),
+        ((0, dr), inner_carry),
+        ((dq, 0), carry_inner),
+        ((dq, dr), carry_carry),
+    ]
+}
+
+fn shift_columns(mask: u64, dq: isize) -> (u64, u64) {
+    let (first, last) = (column_mask(0), column_mask(RECT_X_LEN - 1));
+    match dq {
+        1 => ((mask & !last) << 1, (mask & last) >> (RECT_X_LEN - 1)),
+        -1 => ((mask & !first) >> 1, (mask & first) << (RECT_X_LEN - 1)),
+        _ => (mask, 0),
+    }
+}
+
+fn shift_rows(mask: u64, dr: isize) -> (u64, u64) {
+    let last_row = RECT_X_LEN * (RECT_Y_LEN - 1);
+    match dr {
+        1 => (mask << RECT_X_LEN, mask >> last_row),
+        -1 => (mask >> RECT_X_LEN, mask << last_row),
+        _ => (mask, 0),
+    }
+}
+
+impl<S: BuildHasher + Default> Default for HexSet<S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<S: BuildHasher + Clone, T: BuildHasher> BitOr<&HexSet<T>> for &HexSet<S> {
+    type Output = HexSet<S>;
+
+    fn bitor(self, other: &HexSet<T>) -> HexSet<S> {
+        let mut set = self.clone();
+        set.union_with(other);
+        set
+    }
+}
+
+impl<S: BuildHasher + Clone, T: BuildHasher> BitAnd<&HexSet<T>> for &HexSet<S> {
+    type Output = HexSet<S>;
+
+    fn bitand(self, other: &HexSet<T>) -> HexSet<S> {
+        let mut set = self.clone();
+        set.intersect_with(other);
+        set
+    }
+}
+
+impl<S: BuildHasher + Clone, T: BuildHasher> BitXor<&HexSet<T>> for &HexSet<S> {
+    type Output = HexSet<S>;
+
+    fn bitxor(self, other: &HexSet<T>) -> HexSet<S> {
+        let mut set = self.clone();
+        set.symmetric_difference_with(other);
+        set
+    }
+}
+
+impl<S: BuildHasher + Clone, T: BuildHasher> Sub<&HexSet<T>> for &HexSet<S> {
+    type Output = HexSet<S>;
+
+    fn sub(self, other: &HexSet<T>) -> HexSet<S> {
+        let mut set = self.clone();
+        set.difference_with(other);
+        set
+    }
+}
+
+impl<S: BuildHasher, T: BuildHasher> PartialEq<HexSet<T>> for HexSet<S> {
+    fn eq(&self, other: &HexSet<T>) -> bool {
+        self.len == other.len && self.is_subset(other)
+    }
+}
+
+impl<S: BuildHasher> Eq for HexSet<S> {}
+
+impl<S: BuildHasher> Extend<AxialVector> for HexSet<S> {
+    fn extend<I: IntoIterator<Item = AxialVector>>(&mut self, iter: I) {
+        for position in iter {
+            self.insert(position);
+        }
+    }
+}
+
+impl<S: BuildHasher + Default> FromIterator<AxialVector> for HexSet<S> {
+    fn from_iter<I: IntoIterator<Item = AxialVector>>(iter: I) -> Self {
+        let mut set = Self::default();
+        set.extend(iter);
+        set
+    }
+}
+
+#[test]
+fn test_hex_set_should_insert_and_remove() {
+    let mut set = HexSet::new();
+    assert!(set.is_empty());
+    assert!(set.insert(AxialVector::new(12, -42)));
+    assert!(!set.insert(AxialVector::new(12, -42)));
+    assert!(set.insert(AxialVector::new(-1, -1)));
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(AxialVector::new(12, -42)));
+    assert!(!set.contains(AxialVector::new(0, 0)));
+    assert!(set.remove(AxialVector::new(12, -42)));
+    assert!(!set.remove(AxialVector::new(12, -42)));
+    assert_eq!(
+        set.iter().collect::<Vec<_>>(),
+        vec![AxialVector::new(-1, -1)]
+    );
+    set.clear();
+    assert!(set.is_empty());
+    assert_eq!(set.iter().count(), 0);
+}
+
+#[test]
+fn test_hex_set_should_iterate_over_positions() {
+    let center = AxialVector::new(3, -7);
+    let set = center.spiral_iter(20).collect::<HexSet>();
+    assert_eq!(set.len(), 1 + 3 * 20 * 21);
+    let mut positions = set.iter().collect::<Vec<_>>();
+    positions.sort();
+    let mut expected = center.spiral_iter(20).collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(positions, expected);
+}
+
+#[test]
+fn test_hex_set_scanline_iter() {
+    assert_eq!(HexSet::new().scanline_iter().count(), 0);
+    let center = AxialVector::new(3, -7);
+    let mut set = center.spiral_iter(20).collect::<HexSet>();
+    // Holes and lone hexes, across the rects.
+    for position in center.ring_iter(5).chain(center.ring_iter(11)) {
+        set.remove(position);
+    }
+    set.insert(AxialVector::new(100, -7));
+    set.insert(AxialVector::new(-100, 40));
+    let positions = set.scanline_iter().collect::<Vec<_>>();
+    let mut expected = set.iter().collect::<Vec<_>>();
+    expected.sort_by_key(|pos| (pos.r(), pos.q()));
+    assert_eq!(positions, expected);
+}
+
+#[test]
+fn test_hex_set_operations() {
+    let a = AxialVector::new(0, 0).spiral_iter(10).collect::<HexSet>();
+    let b = AxialVector::new(7, -2).spiral_iter(10).collect::<HexSet>();
+    let all = AxialVector::new(3, -1).spiral_iter(30).collect::<Vec<_>>();
+
+    let mut union = a.clone();
+    union.union_with(&b);
+    let mut intersection = a.clone();
+    intersection.intersect_with(&b);
+    let mut difference = a.clone();
+    difference.difference_with(&b);
+    let mut symmetric_difference = a.clone();
+    symmetric_difference.symmetric_difference_with(&b);
+    for position in all {
+        let (in_a, in_b) = (a.contains(position), b.contains(position));
+        assert_eq!(union.contains(position), in_a || in_b);
+        assert_eq!(intersection.contains(position), in_a && in_b);
+        assert_eq!(difference.contains(position), in_a && !in_b);
+        assert_eq!(symmetric_difference.contains(position), in_a != in_b);
+    }
+    assert_eq!(union.len(), union.iter().count());
+    assert_eq!(intersection.len(), intersection.iter().count());
+    assert_eq!(difference.len(), difference.iter().count());
+    assert_eq!(
+        symmetric_difference.len(),
+        symmetric_difference.iter().count()
+    );
+    assert_eq!(&a | &b, union);
+    assert_eq!(&a & &b, intersection);
+    assert_eq!(&a - &b, difference);
+    assert_eq!(&a ^ &b, symmetric_difference);
+    assert_eq!(&(&a | &b) - &(&a & &b), symmetric_difference);
+    assert_eq!(intersection.len() + difference.len(), a.len());
+
+    assert!(intersection.is_subset(&a) && intersection.is_subset(&b));
+    assert!(a.is_subset(&union) && !union.is_subset(&a));
+    let mut other = difference.clone();
+    other.union_with(&intersection);
+    assert_eq!(other, a);
+
+    let mut empty = a.clone();
+    empty.difference_with(&a);
+    assert!(empty.is_empty());
+    assert_eq!(empty, HexSet::new());
+}
+
+#[test]
+fn test_bit_layer_from_storage() {
+    let mut storage = RectHashStorage::new();
+    for position in AxialVector::new(-4, 9).spiral_iter(25) {
+        storage.insert(position, (position.q() + 2 * position.r()).rem_euclid(3));
+    }
+    let walls: BitLayer = BitLayer::from_storage(&storage, |hex| *hex == 0);
+    let open: BitLayer = BitLayer::from_storage(&storage, |hex| *hex != 0);
+    for (position, hex) in storage.iter() {
+        assert_eq!(walls.contains(position), *hex == 0);
+    }
+    assert_eq!(walls.len(), walls.iter().count());
+    assert!((&walls & &open).is_empty());
+    assert_eq!((&walls | &open).len(), storage.len());
+    let none: BitLayer = BitLayer::from_storage(&storage, |_| false);
+    assert!(none.is_empty());
+}
+
+#[test]
+fn test_hex_set_morphology() {
+    // Two overlapping blobs across several rects, with a hole and a thin corridor.
+    let mut set = AxialVector::new(-3, 2)
+        .spiral_iter(6)
+        .chain(AxialVector::new(9, -4).spiral_iter(4))
+        .chain((0..12).map(|q| AxialVector::new(q - 20, 5)))
+        .collect::<HexSet>();
+    set.remove(AxialVector::new(-3, 2));
+    let area = AxialVector::new(-3, 0).spiral_iter(30).collect::<Vec<_>>();
+    for radius in 0..3 {
+        let dilated = set.dilate(radius);
+        let eroded = set.erode(radius);
+        for position in &area {
+            let neighborhood = || position.spiral_iter(radius);
+            assert_eq!(
+                dilated.contains(*position),
+                neighborhood().any(|hex| set.contains(hex))
+            );
+            assert_eq!(
+                eroded.contains(*position),
+                neighborhood().all(|hex| set.contains(hex))
+            );
+        }
+        assert_eq!(dilated.len(), dilated.iter().count());
+        assert_eq!(eroded.len(), eroded.iter().count());
+    }
+    assert_eq!(set.dilate(0), set);
+
+    let opened = set.opening(1);
+    let closed = set.closing(1);
+    assert!(opened.is_subset(&set) && set.is_subset(&closed));
+    assert_eq!(opened.opening(1), opened);
+    assert_eq!(closed.closing(1), closed);
+    // The hole is filled, the corridor is gone.
+    assert!(closed.contains(AxialVector::new(-3, 2)));
+    assert!(!opened.contains(AxialVector::new(-14, 5)));
+}
+
+#[test]
+fn test_hex_set_frontier() {
+    let mut set = AxialVector::new(5, -2)
+        .spiral_iter(3)
+        .chain(AxialVector::new(-12, 7).spiral_iter(1))
+        .collect::<HexSet>();
+    set.remove(AxialVector::new(5, -2));
+    let frontier = set.frontier();
+    for position in AxialVector::new(0, 0).spiral_iter(30) {
+        assert_eq!(
+            frontier.contains(position),
+            !set.contains(position)
+                && (0..6).any(|direction| set.contains(position.neighbor(direction)))
+        );
+    }
+    // The hole inside, the ring around the big blob and the one around the small one.
+    assert_eq!(frontier.len(), 1 + 24 + 12);
+    assert!(HexSet::new().frontier().is_empty());
+}
@@ -0,0 +1,176 @@
+use crate::hex::{
+    coordinates::axial::AxialVector,
+    storage::{
+        hash::{rect_position as position, HexBuildHasher, RectHashStorage},
+        join::join,
+        rect::{mask_offsets, RECT_X_LEN},
+    },
+};
+use std::hash::BuildHasher;
+
+// Auxiliary data (decals, spawn markers, scripting flags...) attached to some of the hexes of a
+// primary storage without changing its hex type. The table is sparse but uses the same rects as
+// the primary storage, joining both looks up each rect once and only visits the hexes present in
+// both rects.
+pub struct SideTable<T, S = HexBuildHasher> {
+    table: RectHashStorage<T, S>,
+}
+
+impl<T> SideTable<T> {
+    pub fn new() -> Self {
+        Self {
+            table: RectHashStorage::new(),
+        }
+    }
+}
+
+impl<T> Default for SideTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, S: BuildHasher> SideTable<T, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            table: RectHashStorage::with_hasher(hasher),
+        }
+    }
+
+    pub fn get(&self, position: AxialVector) -> Option<&T> {
+        self.table.get(position)
+    }
+
+    pub fn get_mut(&mut self, position: AxialVector) -> Option<&mut T> {
+        self.table.get_mut(position)
+    }
+
+    pub fn contains_position(&self, position: AxialVector) -> bool {
+        self.table.contains_position(position)
+    }
+
+    pub fn insert(&mut self, position: AxialVector, value: T) -> Option<T> {
+        self.table.insert(position, value)
+    }
+
+    pub fn remove(&mut self, position: AxialVector) -> Option<T> {
+        self.table.remove(position)
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AxialVector, &T)> {
+        self.table.iter()
+    }
+
+    // The hexes of the primary storage which have a value in the table, with that value.
+    pub fn join<'a, H, P: BuildHasher>(
+        &'a self,
+        primary: &'a RectHashStorage<H, P>,
+    ) -> impl 'a + Iterator<Item = (AxialVector, &'a H, &'a T)> {
+        // The table is the sparse one, its rects lead.
+        join(&self.table, primary).map(|(position, value, hex)| (position, hex, value))
+    }
+
+    // Same as join with the values mutable.
+    pub fn for_each_joined_mut<H, P, F>(&mut self, primary: &RectHashStorage<H, P>, mut f: F)
+    where
+        P: BuildHasher,
+        F: FnMut(AxialVector, &H, &mut T),
+    {
+        for (rect_origin, rect) in self.table.rects_mut() {
+            if let Some(primary_rect) = primary.rect(rect_origin) {
+                for offset in mask_offsets(primary_rect.mask() & rect.mask()) {
+                    let (x, y) = (offset % RECT_X_LEN, offset / RECT_X_LEN);
+                    f(
+                        position(rect_origin, x, y),
+                        primary_rect.get(x, y).expect("primary hex"),
+                        rect.get_mut(x, y).expect("side value"),
+                    );
+                }
+            }
+        }
+    }
+
+    // Drops the values of the hexes the primary storage no longer has.
+    pub fn retain_primary<H, P: BuildHasher>(&mut self, primary: &RectHashStorage<H, P>) {
+        let orphans = self
+            .table
+            .rects()
+            .flat_map(|(rect_origin, rect)| {
+                let primary_mask = primary.rect(rect_origin).map_or(0, |rect| rect.mask());
+                mask_offsets(rect.mask() & !primary_mask).map(move |offset| {
+                    position(rect_origin, offset % RECT_X_LEN, offset / RECT_X_LEN)
+                })
+            })
+            .collect::<Vec<_>>();
+        for orphan in orphans {
+            self.table.remove(orphan);
+        }
+    }
+}
+
+#[test]
+fn test_side_table_join() {
+    let mut primary = RectHashStorage::new();
+    for (i, position) in AxialVector::new(5, -3).spiral_iter(6).enumerate() {
+        primary.insert(position, i);
+    }
+    let mut side = SideTable::new();
+    let markers = [
+        AxialVector::new(5, -3),
+        AxialVector::new(8, -3),
+        AxialVector::new(2, 1),
+        // Out of the primary storage.
+        AxialVector::new(40, 40),
+    ];
+    for (i, marker) in markers.iter().enumerate() {
+        side.insert(*marker, format!("marker {}", i));
+    }
+    assert_eq!(side.len(), 4);
+
+    let mut joined = side
+        .join(&primary)
+        .map(|(position, hex, value)| (position, *hex, value.clone()))
+        .collect::<Vec<_>>();
+    joined.sort();
+    let mut expected = markers[..3]
+        .iter()
+        .enumerate()
+        .map(|(i, marker)| {
+            (
+                *marker,
+                *primary.get(*marker).expect("hex"),
+                format!("marker {}", i),
+            )
+        })
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(joined, expected);
+
+    side.for_each_joined_mut(&primary, |_, hex, value| {
+        value.push_str(&format!(" on {}", hex));
+    });
+    assert_eq!(
+        side.get(markers[0]).map(String::as_str),
+        Some("marker 0 on 0")
+    );
+    assert_eq!(side.get(markers[3]).map(String::as_str), Some("marker 3"));
+
+    primary.remove(markers[1]);
+    side.retain_primary(&primary);
+    assert_eq!(side.len(), 2);
+    assert!(side.contains_position(markers[2]));
+    assert!(!side.contains_position(markers[1]));
+    assert!(!side.contains_position(markers[3]));
+}
@@ -0,0 +1,24 @@
+use crate::hex::{coordinates::axial::AxialVector, float::layout::HexLayout};
+use ::glam::Vec2;
+
+// glam only has single precision vectors, hence the conversions going through a layout.
+impl HexLayout {
+    pub fn to_glam_vec(&self, position: AxialVector) -> Vec2 {
+        let point = self.to_point(position);
+        Vec2::new(point[0], point[1])
+    }
+
+    pub fn from_glam_vec(&self, vec: Vec2) -> AxialVector {
+        self.from_point([vec.x, vec.y])
+    }
+}
+
+#[test]
+fn test_glam_conversions() {
+    let layout = HexLayout::flat_top(2.0);
+    let axial = AxialVector::new(12, -42);
+    assert!(layout
+        .to_glam_vec(axial)
+        .abs_diff_eq(Vec2::new(36.0, -72.0 * f32::sqrt(3.0)), 1e-4));
+    assert_eq!(layout.from_glam_vec(layout.to_glam_vec(axial)), axial);
+}
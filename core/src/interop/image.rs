@@ -0,0 +1,129 @@
+use crate::hex::{
+    float::layout::HexLayout,
+    shape::cubic_range::CubicRangeShape,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+
+// 8-bit grayscale pixels, row by row from the top left corner, e.g. a heightmap or a cave sketched
+// in an image editor. The pixel coordinates are the layout ones: the hex at the layout origin is
+// over the top left corner and r goes down like the rows do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        if pixels.len() != width * height {
+            panic!(
+                "Invalid GrayImage size {} for {}x{} pixels",
+                pixels.len(),
+                width,
+                height
+            );
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // The pixel containing the point, if any.
+    pub fn pixel(&self, x: f32, y: f32) -> Option<u8> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if x < self.width && y < self.height {
+            Some(self.pixels[x + y * self.width])
+        } else {
+            None
+        }
+    }
+
+    // The pixel under the center of each hex of the shape, converted by `to_hex`. The hexes out of
+    // the image are left out.
+    pub fn sample<H, F>(
+        &self,
+        shape: &CubicRangeShape,
+        layout: &HexLayout,
+        to_hex: F,
+    ) -> RectHashStorage<H>
+    where
+        F: Fn(u8) -> H,
+    {
+        let mut storage = RectHashStorage::new();
+        for position in shape.iter_positions() {
+            let [x, y] = layout.to_point(position);
+            if let Some(pixel) = self.pixel(x, y) {
+                storage.insert(position, to_hex(pixel));
+            }
+        }
+        storage
+    }
+
+    // The hexes whose pixel is darker than the threshold, walls being drawn in black.
+    pub fn mask(&self, shape: &CubicRangeShape, layout: &HexLayout, threshold: u8) -> HexSet {
+        self.sample(shape, layout, |pixel| pixel < threshold)
+            .iter()
+            .filter(|(_, dark)| **dark)
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
+
+#[test]
+fn test_gray_image_sampling() {
+    use crate::hex::coordinates::axial::AxialVector;
+
+    // A 40x30 gradient from left to right with a black square in the middle.
+    let (width, height) = (40, 30);
+    let mut pixels = vec![0; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let inside = (15..25).contains(&x) && (10..20).contains(&y);
+            pixels[x + y * width] = if inside { 0 } else { 50 + 5 * x as u8 };
+        }
+    }
+    let image = GrayImage::new(width, height, pixels);
+    assert_eq!(image.pixel(39.5, 29.5), Some(245));
+    assert_eq!(image.pixel(40.0, 0.0), None);
+    assert_eq!(image.pixel(-0.5, 0.0), None);
+
+    let layout = HexLayout::new(
+        crate::hex::coordinates::offset::HexOrientation::PointyTop,
+        2.0,
+        [20.0, 15.0],
+    );
+    let shape = CubicRangeShape::new((-10, 10), (-10, 10), (-10, 10));
+    let heights = image.sample(&shape, &layout, f32::from);
+    assert!(!heights.is_empty() && heights.len() < shape.len());
+    for (position, height) in heights.iter() {
+        let [x, y] = layout.to_point(position);
+        assert!(x >= 0.0 && y >= 0.0 && x < 40.0 && y < 30.0);
+        assert_eq!(Some(*height as u8), image.pixel(x, y));
+    }
+    assert_eq!(heights.get(AxialVector::default()), Some(&0.0));
+
+    let walls = image.mask(&shape, &layout, 50);
+    assert!(walls.contains(AxialVector::default()));
+    assert!(walls.iter().all(|position| {
+        let [x, y] = layout.to_point(position);
+        (15.0..25.0).contains(&x) && (10.0..20.0).contains(&y)
+    }));
+    assert_eq!(
+        walls.len(),
+        heights.iter().filter(|(_, height)| **height == 0.0).count()
+    );
+}
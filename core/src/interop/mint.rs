@@ -0,0 +1,62 @@
+use crate::hex::{
+    coordinates::{axial::AxialVector, cubic::CubicVector},
+    float::layout::HexLayout,
+};
+use ::mint::{Point2, Vector3};
+
+impl From<AxialVector> for Point2<isize> {
+    fn from(axial: AxialVector) -> Self {
+        Self {
+            x: axial.q(),
+            y: axial.r(),
+        }
+    }
+}
+
+impl From<Point2<isize>> for AxialVector {
+    fn from(point: Point2<isize>) -> Self {
+        Self::new(point.x, point.y)
+    }
+}
+
+impl From<CubicVector> for Vector3<isize> {
+    fn from(cubic: CubicVector) -> Self {
+        Self {
+            x: cubic.x(),
+            y: cubic.y(),
+            z: cubic.z(),
+        }
+    }
+}
+
+impl From<Vector3<isize>> for CubicVector {
+    fn from(vector: Vector3<isize>) -> Self {
+        Self::new(vector.x, vector.y, vector.z)
+    }
+}
+
+impl HexLayout {
+    pub fn to_mint_point(&self, position: AxialVector) -> Point2<f32> {
+        self.to_point(position).into()
+    }
+
+    pub fn from_mint_point(&self, point: Point2<f32>) -> AxialVector {
+        self.from_point(point.into())
+    }
+}
+
+#[test]
+fn test_mint_conversions() {
+    let axial = AxialVector::new(12, -42);
+    let point = Point2::from(axial);
+    assert_eq!((point.x, point.y), (12, -42));
+    assert_eq!(AxialVector::from(point), axial);
+
+    let cubic = CubicVector::from(axial);
+    let vector = Vector3::from(cubic);
+    assert_eq!((vector.x, vector.y, vector.z), (12, 30, -42));
+    assert_eq!(CubicVector::from(vector), cubic);
+
+    let layout = HexLayout::default();
+    assert_eq!(layout.from_mint_point(layout.to_mint_point(axial)), axial);
+}
@@ -0,0 +1,11 @@
+#![allow(clippy::disallowed_types)]
+
+#[cfg(feature = "glam")]
+pub mod glam;
+pub mod image;
+#[cfg(feature = "mint")]
+pub mod mint;
+#[cfg(feature = "nalgebra")]
+pub mod nalgebra;
+#[cfg(feature = "png")]
+pub mod png;
@@ -0,0 +1,59 @@
+use crate::hex::{
+    coordinates::{axial::AxialVector, cubic::CubicVector},
+    float::layout::HexLayout,
+};
+use ::nalgebra::{Point2, Vector3};
+
+impl From<AxialVector> for Point2<isize> {
+    fn from(axial: AxialVector) -> Self {
+        Self::new(axial.q(), axial.r())
+    }
+}
+
+impl From<Point2<isize>> for AxialVector {
+    fn from(point: Point2<isize>) -> Self {
+        Self::new(point.x, point.y)
+    }
+}
+
+impl From<CubicVector> for Vector3<isize> {
+    fn from(cubic: CubicVector) -> Self {
+        Self::new(cubic.x(), cubic.y(), cubic.z())
+    }
+}
+
+impl From<Vector3<isize>> for CubicVector {
+    fn from(vector: Vector3<isize>) -> Self {
+        Self::new(vector.x, vector.y, vector.z)
+    }
+}
+
+impl HexLayout {
+    pub fn to_nalgebra_point(&self, position: AxialVector) -> Point2<f32> {
+        let point = self.to_point(position);
+        Point2::new(point[0], point[1])
+    }
+
+    pub fn from_nalgebra_point(&self, point: Point2<f32>) -> AxialVector {
+        self.from_point([point.x, point.y])
+    }
+}
+
+#[test]
+fn test_nalgebra_conversions() {
+    let axial = AxialVector::new(12, -42);
+    let point = Point2::from(axial);
+    assert_eq!(point, Point2::new(12, -42));
+    assert_eq!(AxialVector::from(point), axial);
+
+    let cubic = CubicVector::from(axial);
+    let vector = Vector3::from(cubic);
+    assert_eq!(vector, Vector3::new(12, 30, -42));
+    assert_eq!(CubicVector::from(vector), cubic);
+
+    let layout = HexLayout::default();
+    assert_eq!(
+        layout.from_nalgebra_point(layout.to_nalgebra_point(axial)),
+        axial
+    );
+}
@@ -0,0 +1,73 @@
+use crate::interop::image::GrayImage;
+use ::png::{ColorType, Decoder, Transformations};
+use std::io::{Error, ErrorKind, Read, Result};
+
+// Any PNG image as grayscale: the palettes and the low bit depths are expanded, the colors are
+// converted to their luminance and the alpha channel is ignored.
+pub fn read_png<R: Read>(reader: R) -> Result<GrayImage> {
+    let mut decoder = Decoder::new(reader);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info()?;
+    let mut buffer = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buffer)?;
+    let samples = match info.color_type {
+        ColorType::Grayscale => 1,
+        ColorType::GrayscaleAlpha => 2,
+        ColorType::RGB => 3,
+        ColorType::RGBA => 4,
+        ColorType::Indexed => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Indexed colors were not expanded",
+            ))
+        }
+    };
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut pixels = Vec::with_capacity(width * height);
+    for line in buffer.chunks(info.line_size).take(height) {
+        for pixel in line.chunks(samples).take(width) {
+            pixels.push(if samples < 3 {
+                pixel[0]
+            } else {
+                let (r, g, b) = (
+                    u32::from(pixel[0]),
+                    u32::from(pixel[1]),
+                    u32::from(pixel[2]),
+                );
+                ((r * 299 + g * 587 + b * 114) / 1000) as u8
+            });
+        }
+    }
+    Ok(GrayImage::new(width, height, pixels))
+}
+
+#[test]
+fn test_read_png() {
+    use ::png::{BitDepth, Encoder};
+
+    let write = |color_type: ColorType, data: &[u8]| {
+        let mut bytes = Vec::new();
+        let mut encoder = Encoder::new(&mut bytes, 3, 2);
+        encoder.set_color(color_type);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header().expect("header");
+        writer.write_image_data(data).expect("data");
+        drop(writer);
+        bytes
+    };
+    let expected = GrayImage::new(3, 2, vec![0, 128, 255, 10, 20, 30]);
+
+    let gray = write(ColorType::Grayscale, &[0, 128, 255, 10, 20, 30]);
+    assert_eq!(read_png(gray.as_slice()).expect("gray"), expected);
+
+    let rgba = write(
+        ColorType::RGBA,
+        &[
+            0, 0, 0, 255, 128, 128, 128, 0, 255, 255, 255, 255, 10, 10, 10, 255, 20, 20, 20, 255,
+            30, 30, 30, 255,
+        ],
+    );
+    assert_eq!(read_png(rgba.as_slice()).expect("rgba"), expected);
+
+    assert!(read_png(&b"not a png"[..]).is_err());
+}
@@ -1,3 +1,6 @@
+// Floating point types are only allowed where it is explicitly stated, see hex::float.
+#![deny(clippy::disallowed_types)]
+
 #[macro_use]
 extern crate derive_more;
 #[cfg(test)]
@@ -9,3 +12,11 @@ pub mod vector;
 pub mod hex;
 
 pub mod dodec;
+
+pub mod interop;
+
+pub mod prelude;
+
+pub mod trace;
+
+pub mod turn;
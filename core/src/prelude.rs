@@ -0,0 +1,22 @@
+// The types and traits most games start with: `use rhombus_core::prelude::*`. Nothing leaves it
+// without a new minor version. The enums which grow with the features are non-exhaustive and the
+// configurations are built from their default with their with_* methods, so that additions do not
+// break the games.
+pub use crate::{
+    dodec::coordinates::quadric::QuadricVector,
+    hex::{
+        coordinates::{
+            axial::AxialVector,
+            cubic::CubicVector,
+            direction::{HexagonalDirection, NUM_DIRECTIONS},
+            HexCoord, HexagonalVector,
+        },
+        field_of_view::FieldOfView,
+        shape::{cubic_range::CubicRangeShape, Shape},
+        spawn::{SpawnConstraints, SpawnError},
+        storage::{
+            hash::{EmptyRectPolicy, RectHashStorage},
+            set::HexSet,
+        },
+    },
+};
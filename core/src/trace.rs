@@ -0,0 +1,60 @@
+#[cfg(feature = "tracing")]
+pub use tracing;
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+// Keeps a span entered until dropped, then reports how long it was entered.
+#[cfg(feature = "tracing")]
+pub struct TimedSpan {
+    span: tracing::span::EnteredSpan,
+    start: Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl TimedSpan {
+    pub fn enter(span: tracing::Span) -> Self {
+        Self {
+            span: span.entered(),
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for TimedSpan {
+    fn drop(&mut self) {
+        let elapsed_us = self.start.elapsed().as_micros() as u64;
+        self.span.record("elapsed_us", elapsed_us);
+        tracing::trace!(
+            elapsed_us,
+            "{} done",
+            self.span.metadata().map_or("?", |m| m.name())
+        );
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub struct TimedSpan;
+
+// `let _span = timed_span!("name", field = value);` times the rest of the enclosing block. It
+// compiles to nothing without the `tracing` feature, fields are not evaluated then.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! timed_span {
+    ($name:expr $(, $($fields:tt)*)?) => {
+        $crate::trace::TimedSpan::enter($crate::trace::tracing::trace_span!(
+            $name,
+            elapsed_us = $crate::trace::tracing::field::Empty
+            $(, $($fields)*)?
+        ))
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! timed_span {
+    ($($args:tt)*) => {
+        $crate::trace::TimedSpan
+    };
+}
@@ -0,0 +1,221 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    hash::Hash,
+};
+
+// The energy a turn costs. An actor with a speed of TURN_ENERGY gains that much energy every tick
+// and acts once per tick, twice as fast it acts twice per tick and half as fast every other tick.
+pub const TURN_ENERGY: u32 = 100;
+
+struct ActorState {
+    speed: u32,
+    // As of the `since` tick, it may be negative after costly actions.
+    energy: i64,
+    since: u64,
+    // The one of its live entry in the queue, the others being stale.
+    sequence: u64,
+}
+
+impl ActorState {
+    fn accrue(&mut self, tick: u64) {
+        self.energy += (tick - self.since) as i64 * i64::from(self.speed);
+        self.since = tick;
+    }
+
+    // None when it never acts.
+    fn ready_tick(&self) -> Option<u64> {
+        let missing = i64::from(TURN_ENERGY) - self.energy;
+        if missing <= 0 {
+            Some(self.since)
+        } else if self.speed == 0 {
+            None
+        } else {
+            let speed = i64::from(self.speed);
+            Some(self.since + ((missing + speed - 1) / speed) as u64)
+        }
+    }
+}
+
+// Who plays next among actors of different speeds, the actors being whatever identifies them
+// (entities, indices...). Every actor gains its speed in energy each tick and plays as soon as it
+// has enough for a turn; the actors ready at the same tick play in the order they got ready.
+pub struct TurnScheduler<A> {
+    actors: HashMap<A, ActorState>,
+    // The tick at which an actor is ready, then the order in which it got ready.
+    queue: BinaryHeap<Reverse<(u64, u64)>>,
+    queued: HashMap<u64, A>,
+    tick: u64,
+    next_sequence: u64,
+}
+
+impl<A: Clone + Eq + Hash> TurnScheduler<A> {
+    pub fn new() -> Self {
+        Self {
+            actors: HashMap::new(),
+            queue: BinaryHeap::new(),
+            queued: HashMap::new(),
+            tick: 0,
+            next_sequence: 0,
+        }
+    }
+
+    // The tick of the last turn.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn len(&self) -> usize {
+        self.actors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actors.is_empty()
+    }
+
+    pub fn contains(&self, actor: &A) -> bool {
+        self.actors.contains_key(actor)
+    }
+
+    pub fn speed(&self, actor: &A) -> Option<u32> {
+        self.actors.get(actor).map(|state| state.speed)
+    }
+
+    // Starts without energy, an actor already there starts again.
+    pub fn add(&mut self, actor: A, speed: u32) {
+        self.actors.insert(
+            actor.clone(),
+            ActorState {
+                speed,
+                energy: 0,
+                since: self.tick,
+                sequence: 0,
+            },
+        );
+        self.schedule(actor);
+    }
+
+    pub fn remove(&mut self, actor: &A) -> bool {
+        // Its entry in the queue is dropped once popped.
+        self.actors.remove(actor).is_some()
+    }
+
+    // The energy gained so far is kept. A speed of zero stops the actor until it changes again.
+    pub fn set_speed(&mut self, actor: &A, speed: u32) -> bool {
+        let tick = self.tick;
+        match self.actors.get_mut(actor) {
+            Some(state) => {
+                state.accrue(tick);
+                state.speed = speed;
+                self.schedule(actor.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Energy spent on top of the turn, e.g. for an action slower than a step, which delays the
+    // next turn of the actor.
+    pub fn spend(&mut self, actor: &A, energy: u32) -> bool {
+        let tick = self.tick;
+        match self.actors.get_mut(actor) {
+            Some(state) => {
+                state.accrue(tick);
+                state.energy -= i64::from(energy);
+                self.schedule(actor.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The next actor to play, its turn being paid for, None when no actor ever plays again.
+    pub fn next_turn(&mut self) -> Option<A> {
+        while let Some(Reverse((tick, sequence))) = self.queue.pop() {
+            let actor = match self.queued.remove(&sequence) {
+                Some(actor) => actor,
+                None => continue,
+            };
+            let state = match self.actors.get_mut(&actor) {
+                Some(state) if state.sequence == sequence => state,
+                _ => continue,
+            };
+            self.tick = tick;
+            state.accrue(tick);
+            state.energy -= i64::from(TURN_ENERGY);
+            self.schedule(actor.clone());
+            return Some(actor);
+        }
+        None
+    }
+
+    // Queues the actor again, its previous entry going stale.
+    fn schedule(&mut self, actor: A) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let state = self.actors.get_mut(&actor).expect("scheduled actor");
+        state.sequence = sequence;
+        if let Some(tick) = state.ready_tick() {
+            self.queue.push(Reverse((tick, sequence)));
+            self.queued.insert(sequence, actor);
+        }
+    }
+}
+
+impl<A: Clone + Eq + Hash> Default for TurnScheduler<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_turn_scheduler_speeds() {
+    let mut scheduler = TurnScheduler::new();
+    assert_eq!(scheduler.next_turn(), None);
+    scheduler.add('n', TURN_ENERGY);
+    scheduler.add('f', 2 * TURN_ENERGY);
+    scheduler.add('s', TURN_ENERGY / 2);
+    scheduler.add('z', 0);
+    assert_eq!(scheduler.len(), 4);
+    let turns = (0..14)
+        .map(|_| scheduler.next_turn().expect("actor"))
+        .collect::<String>();
+    // Twice as many turns for the fast one, half as many for the slow one, the ties in the order
+    // the actors got ready.
+    assert_eq!(turns, "nffsnffnffsnff");
+    assert_eq!(scheduler.tick(), 4);
+
+    // The speed changes from now on.
+    scheduler.set_speed(&'z', TURN_ENERGY);
+    scheduler.remove(&'f');
+    scheduler.set_speed(&'s', 0);
+    let turns = (0..4)
+        .map(|_| scheduler.next_turn().expect("actor"))
+        .collect::<String>();
+    assert_eq!(turns, "nznz");
+    assert_eq!(scheduler.tick(), 6);
+    assert!(!scheduler.contains(&'f'));
+    assert_eq!(scheduler.speed(&'s'), Some(0));
+    assert!(!scheduler.set_speed(&'f', 1));
+}
+
+#[test]
+fn test_turn_scheduler_spend() {
+    let mut scheduler = TurnScheduler::new();
+    scheduler.add(1, TURN_ENERGY);
+    scheduler.add(2, TURN_ENERGY);
+    assert_eq!(scheduler.next_turn(), Some(1));
+    // A slow action, worth two more turns.
+    assert!(scheduler.spend(&1, 2 * TURN_ENERGY));
+    let turns = (0..5)
+        .map(|_| scheduler.next_turn().expect("actor"))
+        .collect::<Vec<_>>();
+    assert_eq!(turns, vec![2, 2, 2, 1, 2]);
+    assert_eq!(scheduler.tick(), 4);
+    assert!(!scheduler.spend(&3, 1));
+
+    // Nobody left to play.
+    scheduler.set_speed(&1, 0);
+    scheduler.remove(&2);
+    assert_eq!(scheduler.next_turn(), None);
+}
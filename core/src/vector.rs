@@ -1,13 +1,39 @@
-use std::ops::{Mul, MulAssign};
+use std::{
+    hash::{Hash, Hasher},
+    ops::{Mul, MulAssign},
+};
+
+// Vectors are mostly used as hash keys of small maps on hot paths (storage chunks), so their
+// components are mixed into a single word which is cheaper to feed to the hasher than one word
+// per component.
+const HASH_MULTIPLIER: u64 = 0x517c_c1b7_2722_0a95;
+
+pub trait HashComponent: Copy {
+    fn to_hash_word(self) -> u64;
+}
+
+impl HashComponent for isize {
+    fn to_hash_word(self) -> u64 {
+        self as u64
+    }
+}
 
 macro_rules! define_vector {
     ($name:ident, $($fields:ident),+) => {
 
-        #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Add, AddAssign, Sub, SubAssign, Debug)]
+        #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Add, AddAssign, Sub, SubAssign, Debug)]
         pub struct $name<T> {
             $(pub $fields: T,)+
         }
 
+        impl<T: HashComponent> Hash for $name<T> {
+            fn hash<S: Hasher>(&self, state: &mut S) {
+                let mut word = 0u64;
+                $(word = (word.rotate_left(5) ^ self.$fields.to_hash_word()).wrapping_mul(HASH_MULTIPLIER);)+
+                state.write_u64(word);
+            }
+        }
+
         impl<T> Mul<T> for $name<T>
         where
             T: Mul<T, Output = T> + Copy,
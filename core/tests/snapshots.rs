@@ -0,0 +1,105 @@
+use rhombus_core::hex::{
+    ascii::{parse_ascii, render_ascii},
+    coordinates::axial::AxialVector,
+    field_of_view::FieldOfView,
+    storage::hash::RectHashStorage,
+};
+use std::{collections::HashSet, fs, path::PathBuf};
+
+// Snapshots are stored under tests/snapshots. Run the tests with UPDATE_SNAPSHOTS=1 to
+// (re)generate them after an intended change of output, a missing snapshot fails otherwise.
+// The generators depend on the core, their snapshots are in mapgen/tests.
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).expect("write snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "snapshot {} cannot be read ({}), run with UPDATE_SNAPSHOTS=1 to create it",
+            name, err
+        )
+    });
+    if expected != actual {
+        panic!(
+            "snapshot {} does not match\n--- expected\n{}--- actual\n{}",
+            name, expected, actual
+        );
+    }
+}
+
+const CAVE: &str = r"
+      # # # # # # # # # #
+     # . . . . . . . . . #
+    # . . # # . . . # . . #
+   # . . . # . . . . . . . #
+  # . # . . . . # # . . . . #
+ # . . . . . . # . . . # . . #
+# . . . # . . . . . . . . . . #
+ # . . . . # . . . . # . . . #
+  # . . . . . . . # . . . . #
+   # . # # . . . . . . # . #
+    # . . . . . # . . . . #
+     # . . . . . . . . . #
+      # # # # # # # # # #
+";
+
+fn parse_cave() -> RectHashStorage<bool> {
+    parse_ascii(CAVE.trim_start_matches('\n'), |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
+}
+
+#[test]
+fn snapshot_cave_round_trip() {
+    let cave = parse_cave();
+    assert_snapshot(
+        "cave",
+        &render_ascii(&cave, |_, wall| if *wall { '#' } else { '.' }),
+    );
+}
+
+// '@' is the observer, '.' and '#' are visible open hexes and walls, '-' and '+' are hidden ones.
+#[test]
+fn snapshot_field_of_view() {
+    let cave = parse_cave();
+    let center = AxialVector::new(5, 6);
+    assert_eq!(cave.get(center), Some(&false));
+
+    let mut visible_positions = HashSet::new();
+    visible_positions.insert(center);
+    let mut fov = FieldOfView::default();
+    fov.start(center);
+    loop {
+        let prev_len = visible_positions.len();
+        for pos in fov.iter() {
+            let key = center + pos;
+            if cave.contains_position(key) {
+                visible_positions.insert(key);
+            }
+        }
+        if visible_positions.len() == prev_len {
+            break;
+        }
+        fov.next_radius(&|pos| cave.get(pos).copied().unwrap_or(false));
+    }
+
+    assert_snapshot(
+        "field_of_view",
+        &render_ascii(&cave, |pos, wall| {
+            match (pos == center, visible_positions.contains(&pos), *wall) {
+                (true, _, _) => '@',
+                (false, true, false) => '.',
+                (false, true, true) => '#',
+                (false, false, false) => '-',
+                (false, false, true) => '+',
+            }
+        }),
+    );
+}
@@ -0,0 +1,21 @@
+// The C header is generated in OUT_DIR, the committed copy for the users who only build the
+// library is updated when RHOMBUS_UPDATE_HEADER is set (a test checks that it is up to date). The
+// API is all in src/lib.rs, parsing it alone does not need to go through cargo metadata.
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("manifest dir");
+    let out_dir = std::env::var("OUT_DIR").expect("out dir");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=RHOMBUS_UPDATE_HEADER");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("cbindgen configuration");
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/lib.rs", crate_dir))
+        .generate()
+        .expect("C bindings");
+    bindings.write_to_file(format!("{}/rhombus.h", out_dir));
+    if std::env::var_os("RHOMBUS_UPDATE_HEADER").is_some() {
+        bindings.write_to_file(format!("{}/include/rhombus.h", crate_dir));
+    }
+}
@@ -0,0 +1,129 @@
+#ifndef RHOMBUS_H
+#define RHOMBUS_H
+
+/* Generated by cbindgen from ffi/src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Region of the wall cells of a dungeon.
+#define RHOMBUS_WALL -1
+
+// The largest radius of the field of view and of the dungeons, about 3 million hexes: the arrays
+// of the larger ones are empty instead of taking all the memory.
+#define RHOMBUS_MAX_RADIUS 1024
+
+typedef enum RhombusOrientation {
+  RHOMBUS_ORIENTATION_POINTY_TOP,
+  RHOMBUS_ORIENTATION_FLAT_TOP,
+} RhombusOrientation;
+
+// Cubic coordinates, x + y + z = 0.
+typedef struct RhombusCubic {
+  int32_t x;
+  int32_t y;
+  int32_t z;
+} RhombusCubic;
+
+// Axial coordinates.
+typedef struct RhombusHex {
+  int32_t q;
+  int32_t r;
+} RhombusHex;
+
+// Column and row in a square grid: odd rows shifted right with pointy top hexes, odd columns
+// shifted down with flat top hexes.
+typedef struct RhombusOffset {
+  int32_t col;
+  int32_t row;
+} RhombusOffset;
+
+typedef struct RhombusPoint {
+  float x;
+  float y;
+} RhombusPoint;
+
+// Hexes sorted by q then r, to free with rhombus_hex_array_free. Empty with a null pointer when
+// the call failed.
+typedef struct RhombusHexArray {
+  struct RhombusHex *hexes;
+  uintptr_t len;
+} RhombusHexArray;
+
+// A dungeon cell, open cells belong to a region (room or corridor), walls to RHOMBUS_WALL.
+typedef struct RhombusCell {
+  struct RhombusHex hex;
+  int32_t region;
+} RhombusCell;
+
+// Cells sorted by q then r, to free with rhombus_cell_array_free. Empty with a null pointer when
+// the call failed.
+typedef struct RhombusCellArray {
+  struct RhombusCell *cells;
+  uintptr_t len;
+} RhombusCellArray;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct RhombusCubic rhombus_axial_to_cubic(struct RhombusHex hex);
+
+// The cubic coordinates must add up to 0.
+struct RhombusHex rhombus_cubic_to_axial(struct RhombusCubic cubic);
+
+struct RhombusOffset rhombus_axial_to_offset(struct RhombusHex hex,
+                                             enum RhombusOrientation orientation);
+
+struct RhombusHex rhombus_offset_to_axial(struct RhombusOffset offset,
+                                          enum RhombusOrientation orientation);
+
+// The center of the hex, the hex at the origin being centered on (0, 0) and size being the
+// distance from the center of a hex to its corners.
+struct RhombusPoint rhombus_hex_to_point(struct RhombusHex hex,
+                                         enum RhombusOrientation orientation,
+                                         float size);
+
+// The hex containing the point, the inverse of rhombus_hex_to_point.
+struct RhombusHex rhombus_point_to_hex(struct RhombusPoint point,
+                                       enum RhombusOrientation orientation,
+                                       float size);
+
+int32_t rhombus_distance(struct RhombusHex a, struct RhombusHex b);
+
+// The hexes seen from the origin at most radius hexes away, the origin included. The walls block
+// the view but are seen themselves. The array is empty if the radius exceeds RHOMBUS_MAX_RADIUS or
+// if a visible hex does not fit in 32 bits.
+//
+// # Safety
+//
+// walls points to walls_len hexes, it may be null when walls_len is 0.
+struct RhombusHexArray rhombus_field_of_view(struct RhombusHex origin,
+                                             uint32_t radius,
+                                             const struct RhombusHex *walls,
+                                             uintptr_t walls_len);
+
+// A rooms and mazes dungeon in the hexagon of the radius around the origin, made of room_attempts
+// rooms at most. Only the walls next to open cells are kept. The same seed gives the same dungeon
+// on every platform. The array is empty if the radius exceeds RHOMBUS_MAX_RADIUS.
+struct RhombusCellArray rhombus_generate_dungeon(uint32_t radius,
+                                                 uint32_t room_attempts,
+                                                 uint64_t seed);
+
+// # Safety
+//
+// The array comes from the library and is freed only once.
+void rhombus_hex_array_free(struct RhombusHexArray array);
+
+// # Safety
+//
+// The array comes from the library and is freed only once.
+void rhombus_cell_array_free(struct RhombusCellArray array);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* RHOMBUS_H */
@@ -0,0 +1,428 @@
+// C API of the core and the generators, see include/rhombus.h. Positions go through the API as
+// plain q/r pairs and the arrays returned by the library are freed by the library.
+
+use rand::{rngs::StdRng, SeedableRng};
+use rhombus_core::hex::{
+    coordinates::{
+        axial::AxialVector,
+        cubic::CubicVector,
+        offset::{HexOrientation, OffsetVector},
+    },
+    field_of_view::visible_positions,
+    float::layout::HexLayout,
+    shape::cubic_range::CubicRangeShape,
+    storage::set::HexSet,
+};
+use rhombus_mapgen::rooms_and_mazes::{HexState, RoomsAndMazesWorld};
+use std::{
+    convert::TryFrom,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+/// Region of the wall cells of a dungeon.
+pub const RHOMBUS_WALL: i32 = -1;
+
+/// The largest radius of the field of view and of the dungeons, about 3 million hexes: the arrays
+/// of the larger ones are empty instead of taking all the memory.
+pub const RHOMBUS_MAX_RADIUS: u32 = 1024;
+
+/// Axial coordinates.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct RhombusHex {
+    pub q: i32,
+    pub r: i32,
+}
+
+/// Cubic coordinates, x + y + z = 0.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RhombusCubic {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Column and row in a square grid: odd rows shifted right with pointy top hexes, odd columns
+/// shifted down with flat top hexes.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RhombusOffset {
+    pub col: i32,
+    pub row: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RhombusPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RhombusOrientation {
+    PointyTop,
+    FlatTop,
+}
+
+/// A dungeon cell, open cells belong to a region (room or corridor), walls to RHOMBUS_WALL.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RhombusCell {
+    pub hex: RhombusHex,
+    pub region: i32,
+}
+
+/// Hexes sorted by q then r, to free with rhombus_hex_array_free. Empty with a null pointer when
+/// the call failed.
+#[repr(C)]
+pub struct RhombusHexArray {
+    pub hexes: *mut RhombusHex,
+    pub len: usize,
+}
+
+/// Cells sorted by q then r, to free with rhombus_cell_array_free. Empty with a null pointer when
+/// the call failed.
+#[repr(C)]
+pub struct RhombusCellArray {
+    pub cells: *mut RhombusCell,
+    pub len: usize,
+}
+
+impl RhombusHexArray {
+    fn failed() -> Self {
+        Self {
+            hexes: ptr::null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl RhombusCellArray {
+    fn failed() -> Self {
+        Self {
+            cells: ptr::null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl From<RhombusHex> for AxialVector {
+    fn from(hex: RhombusHex) -> Self {
+        AxialVector::new(hex.q as isize, hex.r as isize)
+    }
+}
+
+impl From<AxialVector> for RhombusHex {
+    fn from(position: AxialVector) -> Self {
+        Self {
+            q: position.q() as i32,
+            r: position.r() as i32,
+        }
+    }
+}
+
+impl From<RhombusOrientation> for HexOrientation {
+    fn from(orientation: RhombusOrientation) -> Self {
+        match orientation {
+            RhombusOrientation::PointyTop => HexOrientation::PointyTop,
+            RhombusOrientation::FlatTop => HexOrientation::FlatTop,
+        }
+    }
+}
+
+// The caller gets the ownership of the elements.
+fn into_raw_parts<T>(elements: Vec<T>) -> (*mut T, usize) {
+    let elements = elements.into_boxed_slice();
+    let len = elements.len();
+    (Box::into_raw(elements) as *mut T, len)
+}
+
+unsafe fn free_raw_parts<T>(elements: *mut T, len: usize) {
+    if !elements.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(elements, len)));
+    }
+}
+
+// A panic must not unwind into the caller, whose frames Rust knows nothing about.
+fn catch_panic<T>(f: impl FnOnce() -> T, failed: T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(failed)
+}
+
+// Unlike the conversion of the scalar functions, the hexes of the arrays are not truncated.
+fn checked_hex(position: AxialVector) -> RhombusHex {
+    let coordinate = |value| i32::try_from(value).expect("coordinate out of the i32 range");
+    RhombusHex {
+        q: coordinate(position.q()),
+        r: coordinate(position.r()),
+    }
+}
+
+fn hex_array(set: &HexSet) -> RhombusHexArray {
+    let mut hexes = set.iter().map(checked_hex).collect::<Vec<_>>();
+    hexes.sort();
+    let (hexes, len) = into_raw_parts(hexes);
+    RhombusHexArray { hexes, len }
+}
+
+#[no_mangle]
+pub extern "C" fn rhombus_axial_to_cubic(hex: RhombusHex) -> RhombusCubic {
+    let cubic = CubicVector::from(AxialVector::from(hex));
+    RhombusCubic {
+        x: cubic.x() as i32,
+        y: cubic.y() as i32,
+        z: cubic.z() as i32,
+    }
+}
+
+/// The cubic coordinates must add up to 0.
+#[no_mangle]
+pub extern "C" fn rhombus_cubic_to_axial(cubic: RhombusCubic) -> RhombusHex {
+    AxialVector::from(CubicVector::new(
+        cubic.x as isize,
+        cubic.y as isize,
+        cubic.z as isize,
+    ))
+    .into()
+}
+
+#[no_mangle]
+pub extern "C" fn rhombus_axial_to_offset(
+    hex: RhombusHex,
+    orientation: RhombusOrientation,
+) -> RhombusOffset {
+    let offset = OffsetVector::from_axial(hex.into(), orientation.into());
+    RhombusOffset {
+        col: offset.col as i32,
+        row: offset.row as i32,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rhombus_offset_to_axial(
+    offset: RhombusOffset,
+    orientation: RhombusOrientation,
+) -> RhombusHex {
+    OffsetVector::new(offset.col as isize, offset.row as isize)
+        .to_axial(orientation.into())
+        .into()
+}
+
+/// The center of the hex, the hex at the origin being centered on (0, 0) and size being the
+/// distance from the center of a hex to its corners.
+#[no_mangle]
+pub extern "C" fn rhombus_hex_to_point(
+    hex: RhombusHex,
+    orientation: RhombusOrientation,
+    size: f32,
+) -> RhombusPoint {
+    let [x, y] = HexLayout::new(orientation.into(), size, [0.0, 0.0]).to_point(hex.into());
+    RhombusPoint { x, y }
+}
+
+/// The hex containing the point, the inverse of rhombus_hex_to_point.
+#[no_mangle]
+pub extern "C" fn rhombus_point_to_hex(
+    point: RhombusPoint,
+    orientation: RhombusOrientation,
+    size: f32,
+) -> RhombusHex {
+    HexLayout::new(orientation.into(), size, [0.0, 0.0])
+        .from_point([point.x, point.y])
+        .into()
+}
+
+#[no_mangle]
+pub extern "C" fn rhombus_distance(a: RhombusHex, b: RhombusHex) -> i32 {
+    AxialVector::from(a).distance(b.into()) as i32
+}
+
+/// The hexes seen from the origin at most radius hexes away, the origin included. The walls block
+/// the view but are seen themselves. The array is empty if the radius exceeds RHOMBUS_MAX_RADIUS or
+/// if a visible hex does not fit in 32 bits.
+///
+/// # Safety
+///
+/// walls points to walls_len hexes, it may be null when walls_len is 0.
+#[no_mangle]
+pub unsafe extern "C" fn rhombus_field_of_view(
+    origin: RhombusHex,
+    radius: u32,
+    walls: *const RhombusHex,
+    walls_len: usize,
+) -> RhombusHexArray {
+    if radius > RHOMBUS_MAX_RADIUS {
+        return RhombusHexArray::failed();
+    }
+    let walls = if walls_len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(walls, walls_len)
+    };
+    catch_panic(
+        || {
+            let radius = radius as isize;
+            let walls = walls
+                .iter()
+                .map(|wall| AxialVector::from(*wall))
+                .collect::<HexSet>();
+            let origin = AxialVector::from(origin);
+            let visible = visible_positions(
+                origin,
+                Some(radius as usize),
+                &|position| walls.contains(position),
+                |position| origin.distance(position) <= radius,
+            );
+            hex_array(&visible)
+        },
+        RhombusHexArray::failed(),
+    )
+}
+
+/// A rooms and mazes dungeon in the hexagon of the radius around the origin, made of room_attempts
+/// rooms at most. Only the walls next to open cells are kept. The same seed gives the same dungeon
+/// on every platform. The array is empty if the radius exceeds RHOMBUS_MAX_RADIUS.
+#[no_mangle]
+pub extern "C" fn rhombus_generate_dungeon(
+    radius: u32,
+    room_attempts: u32,
+    seed: u64,
+) -> RhombusCellArray {
+    if radius > RHOMBUS_MAX_RADIUS {
+        return RhombusCellArray::failed();
+    }
+    catch_panic(
+        || {
+            let radius = radius as isize;
+            let mut world = RoomsAndMazesWorld::new();
+            world.set_shape(CubicRangeShape::new(
+                (-radius, radius),
+                (-radius, radius),
+                (-radius, radius),
+            ));
+            world.reset(|_| ());
+            world.generate(room_attempts as usize, &mut StdRng::seed_from_u64(seed));
+            let mut cells = world
+                .hexes()
+                .iter()
+                .map(|(position, (hex_data, _))| RhombusCell {
+                    hex: checked_hex(position),
+                    region: match hex_data.state() {
+                        HexState::Open(region) => {
+                            i32::try_from(region).expect("region out of the i32 range")
+                        }
+                        _ => RHOMBUS_WALL,
+                    },
+                })
+                .collect::<Vec<_>>();
+            cells.sort_by_key(|cell| cell.hex);
+            let (cells, len) = into_raw_parts(cells);
+            RhombusCellArray { cells, len }
+        },
+        RhombusCellArray::failed(),
+    )
+}
+
+/// # Safety
+///
+/// The array comes from the library and is freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn rhombus_hex_array_free(array: RhombusHexArray) {
+    free_raw_parts(array.hexes, array.len);
+}
+
+/// # Safety
+///
+/// The array comes from the library and is freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn rhombus_cell_array_free(array: RhombusCellArray) {
+    free_raw_parts(array.cells, array.len);
+}
+
+#[test]
+fn test_coordinates() {
+    let hex = RhombusHex { q: 3, r: -5 };
+    let cubic = rhombus_axial_to_cubic(hex);
+    assert_eq!(cubic, RhombusCubic { x: 3, y: 2, z: -5 });
+    assert_eq!(rhombus_cubic_to_axial(cubic), hex);
+    for orientation in &[RhombusOrientation::PointyTop, RhombusOrientation::FlatTop] {
+        let offset = rhombus_axial_to_offset(hex, *orientation);
+        assert_eq!(rhombus_offset_to_axial(offset, *orientation), hex);
+        let point = rhombus_hex_to_point(hex, *orientation, 2.0);
+        assert_eq!(rhombus_point_to_hex(point, *orientation, 2.0), hex);
+    }
+    assert_eq!(
+        rhombus_axial_to_offset(hex, RhombusOrientation::PointyTop),
+        RhombusOffset { col: 0, row: -5 }
+    );
+    assert_eq!(rhombus_distance(hex, RhombusHex { q: 0, r: 0 }), 5);
+}
+
+#[test]
+fn test_field_of_view() {
+    let origin = RhombusHex { q: 0, r: 0 };
+    let visible = |walls: &[RhombusHex]| unsafe {
+        let array = rhombus_field_of_view(origin, 3, walls.as_ptr(), walls.len());
+        let hexes = slice::from_raw_parts(array.hexes, array.len).to_vec();
+        rhombus_hex_array_free(array);
+        hexes
+    };
+    let open = visible(&[]);
+    assert_eq!(open.len(), 37);
+    assert!(open.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // A wall east of the origin hides what is behind it.
+    let walls = [RhombusHex { q: 1, r: 0 }];
+    let behind_wall = visible(&walls);
+    assert!(behind_wall.contains(&walls[0]));
+    assert!(!behind_wall.contains(&RhombusHex { q: 3, r: 0 }));
+    assert!(behind_wall.len() < open.len());
+
+    let array = unsafe { rhombus_field_of_view(origin, 0, ptr::null(), 0) };
+    assert_eq!(array.len, 1);
+    unsafe { rhombus_hex_array_free(array) };
+
+    // The hexes east of the edge do not fit in 32 bits.
+    let edge = RhombusHex { q: i32::MAX, r: 0 };
+    let array = unsafe { rhombus_field_of_view(edge, 1, ptr::null(), 0) };
+    assert!(array.hexes.is_null());
+    assert_eq!(array.len, 0);
+    unsafe { rhombus_hex_array_free(array) };
+
+    let array = unsafe { rhombus_field_of_view(origin, RHOMBUS_MAX_RADIUS + 1, ptr::null(), 0) };
+    assert!(array.hexes.is_null());
+    assert_eq!(array.len, 0);
+}
+
+#[test]
+fn test_generate_dungeon() {
+    let dungeon = |seed| unsafe {
+        let array = rhombus_generate_dungeon(15, 20, seed);
+        let cells = slice::from_raw_parts(array.cells, array.len).to_vec();
+        rhombus_cell_array_free(array);
+        cells
+    };
+    let cells = dungeon(42);
+    assert_eq!(dungeon(42), cells);
+    assert_ne!(dungeon(43), cells);
+    assert!(cells.iter().any(|cell| cell.region == RHOMBUS_WALL));
+    assert!(cells.iter().any(|cell| cell.region >= 0));
+    assert!(cells
+        .iter()
+        .all(|cell| rhombus_distance(cell.hex, RhombusHex { q: 0, r: 0 }) <= 15));
+
+    let array = rhombus_generate_dungeon(u32::MAX, 20, 42);
+    assert!(array.cells.is_null());
+    assert_eq!(array.len, 0);
+}
+
+#[test]
+fn test_committed_header_is_up_to_date() {
+    assert!(
+        include_str!(concat!(env!("OUT_DIR"), "/rhombus.h"))
+            == include_str!("../include/rhombus.h"),
+        "the API changed, run RHOMBUS_UPDATE_HEADER=1 cargo build -p rhombus_ffi"
+    );
+}
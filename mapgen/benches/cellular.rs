@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+use rhombus_core::hex::shape::cubic_range::CubicRangeShape;
+use rhombus_mapgen::cellular::{Border, CellularWorld};
+use std::{num::NonZeroUsize, thread};
+
+// About 1M hexes.
+const MAP_RADIUS: isize = 577;
+
+fn world() -> CellularWorld<()> {
+    let mut world = CellularWorld::new();
+    world.reset(
+        CubicRangeShape::new(
+            (-MAP_RADIUS, MAP_RADIUS),
+            (-MAP_RADIUS, MAP_RADIUS),
+            (-MAP_RADIUS, MAP_RADIUS),
+        ),
+        100,
+        0.55,
+        Border::default(),
+        &mut StdRng::seed_from_u64(42),
+        |_| (),
+    );
+    world.expand(|_| ());
+    world
+}
+
+fn cellular_benchmark(c: &mut Criterion) {
+    let mut world = world();
+    let available = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut group = c.benchmark_group("phase2_step1");
+    group.sample_size(10);
+    let mut threads = 1;
+    loop {
+        world.set_threads(threads);
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, _| {
+            b.iter(|| world.phase2_step1())
+        });
+        if threads >= available {
+            break;
+        }
+        threads = (threads * 2).min(available);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, cellular_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,332 @@
+// A terminal roguelike slice built on the core and the generators only, without Amethyst: a rooms
+// and mazes dungeon, the field of view of the player computed again at each turn and an ASCII
+// rendering laid out like render_ascii does. The dungeon is turned or mirrored at random every
+// time, the same seed is not always the same layout. Monsters of different speeds share the turns
+// with the player through the turn scheduler: bats flutter twice as fast as the player, goblins
+// chase it when they see it and trolls too, slowly and hitting hard.
+//
+// cargo run -p rhombus_mapgen --example roguelike [seed]
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent},
+    queue,
+    style::{Color, Print, SetForegroundColor},
+    terminal,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use rhombus_core::{
+    hex::{
+        coordinates::transform::HexTransform, field_of_view::visible_positions,
+        reachable::cheapest_path,
+    },
+    turn::{TurnScheduler, TURN_ENERGY},
+};
+use rhombus_mapgen::{prelude::*, rooms_and_mazes::HexState};
+use std::io::{stdout, Write};
+
+const DUNGEON_RADIUS: isize = 20;
+const ROOM_ATTEMPTS: usize = 40;
+const SIGHT_RADIUS: usize = 8;
+const MONSTERS: usize = 12;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Actor {
+    Player,
+    Monster(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MonsterKind {
+    Bat,
+    Goblin,
+    Troll,
+}
+
+impl MonsterKind {
+    fn speed(self) -> u32 {
+        match self {
+            MonsterKind::Bat => 2 * TURN_ENERGY,
+            MonsterKind::Goblin => TURN_ENERGY,
+            MonsterKind::Troll => TURN_ENERGY / 2,
+        }
+    }
+
+    fn glyph(self) -> (char, Color) {
+        match self {
+            MonsterKind::Bat => ('b', Color::Magenta),
+            MonsterKind::Goblin => ('g', Color::Green),
+            MonsterKind::Troll => ('T', Color::Red),
+        }
+    }
+}
+
+struct Monster {
+    kind: MonsterKind,
+    position: AxialVector,
+}
+
+struct Game {
+    seed: u64,
+    transform: HexTransform,
+    world: RoomsAndMazesWorld<()>,
+    player: AxialVector,
+    visible: HexSet,
+    explored: HexSet,
+    turn: usize,
+    monsters: Vec<Monster>,
+    scheduler: TurnScheduler<Actor>,
+    rng: StdRng,
+    hits: usize,
+}
+
+impl Game {
+    fn new(seed: u64, transform: HexTransform) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut world = RoomsAndMazesWorld::new();
+        world.set_shape(CubicRangeShape::new(
+            (-DUNGEON_RADIUS, DUNGEON_RADIUS),
+            (-DUNGEON_RADIUS, DUNGEON_RADIUS),
+            (-DUNGEON_RADIUS, DUNGEON_RADIUS),
+        ));
+        world.reset(|_| ());
+        world.generate(ROOM_ATTEMPTS, &mut rng);
+        world.transform(transform);
+
+        let player = world.find_open_hex().expect("open hex");
+        let mut game = Self {
+            seed,
+            transform,
+            world,
+            player,
+            visible: HexSet::new(),
+            explored: HexSet::new(),
+            turn: 0,
+            monsters: Vec::new(),
+            scheduler: TurnScheduler::new(),
+            rng,
+            hits: 0,
+        };
+        game.scheduler.add(Actor::Player, TURN_ENERGY);
+        game.spawn_monsters();
+        game.look();
+        game.play_monsters();
+        game
+    }
+
+    // Out of sight of the player at first.
+    fn spawn_monsters(&mut self) {
+        let kinds = [MonsterKind::Bat, MonsterKind::Goblin, MonsterKind::Troll];
+        for attempt in 0..MONSTERS * 10 {
+            if self.monsters.len() == MONSTERS {
+                break;
+            }
+            let position = match self.world.random_open_hex(&mut self.rng) {
+                Some(position) => position,
+                None => break,
+            };
+            if position.distance(self.player) as usize <= SIGHT_RADIUS || !self.is_free(position) {
+                continue;
+            }
+            let kind = kinds[attempt % kinds.len()];
+            self.scheduler
+                .add(Actor::Monster(self.monsters.len()), kind.speed());
+            self.monsters.push(Monster { kind, position });
+        }
+    }
+
+    fn is_open(&self, position: AxialVector) -> bool {
+        matches!(self.world.hexes().get(position), Some((hex, _)) if matches!(hex.state(), HexState::Open(..)))
+    }
+
+    fn is_free(&self, position: AxialVector) -> bool {
+        self.is_open(position) && position != self.player && self.monster_at(position).is_none()
+    }
+
+    fn monster_at(&self, position: AxialVector) -> Option<&Monster> {
+        self.monsters
+            .iter()
+            .find(|monster| monster.position == position)
+    }
+
+    fn look(&mut self) {
+        let hexes = self.world.hexes();
+        self.visible = visible_positions(
+            self.player,
+            Some(SIGHT_RADIUS),
+            &|position| !self.is_open(position),
+            |position| hexes.contains_position(position),
+        );
+        self.explored.union_with(&self.visible);
+    }
+
+    // Walls and monsters block the way, a turn passes when the player moves.
+    fn step(&mut self, direction: AxialVector) {
+        let next = self.player + direction;
+        if self.is_free(next) {
+            self.player = next;
+            self.end_turn();
+        }
+    }
+
+    fn end_turn(&mut self) {
+        self.turn += 1;
+        self.look();
+        self.play_monsters();
+    }
+
+    // The monsters play until it is the turn of the player again.
+    fn play_monsters(&mut self) {
+        while let Some(Actor::Monster(index)) = self.scheduler.next_turn() {
+            self.play_monster(index);
+        }
+    }
+
+    // Next to the player a monster hits it, otherwise the ones in sight chase it and the others
+    // wander. Bats never chase anything and only hit half of the time.
+    fn play_monster(&mut self, index: usize) {
+        let Monster { kind, position } = self.monsters[index];
+        if position.distance(self.player) == 1
+            && (kind != MonsterKind::Bat || self.rng.gen_bool(0.5))
+        {
+            self.hits += 1;
+            if kind == MonsterKind::Troll {
+                // A heavy blow, the troll needs another turn to recover.
+                self.scheduler.spend(&Actor::Monster(index), TURN_ENERGY);
+            }
+            return;
+        }
+        let chase = if kind != MonsterKind::Bat && self.visible.contains(position) {
+            cheapest_path(position, self.player, 2 * SIGHT_RADIUS, |_, to| {
+                if to == self.player || self.is_free(to) {
+                    Some(1)
+                } else {
+                    None
+                }
+            })
+            .map(|(path, _)| path[1])
+        } else {
+            None
+        };
+        let next = chase.or_else(|| {
+            let free = position
+                .ring_iter(1)
+                .filter(|neighbor| self.is_free(*neighbor))
+                .collect::<Vec<_>>();
+            free.choose(&mut self.rng).copied()
+        });
+        if let Some(next) = next.filter(|next| self.is_free(*next)) {
+            self.monsters[index].position = next;
+        }
+    }
+
+    // The player at the center of the screen, each hex taking two columns with the rows shifted
+    // by one column, and a status line.
+    fn render<W: Write>(&self, out: &mut W, (width, height): (u16, u16)) -> crossterm::Result<()> {
+        let rows = height.saturating_sub(1);
+        let center_column = 2 * self.player.q() + self.player.r();
+        let mut current_color = None;
+        for y in 0..rows {
+            queue!(out, cursor::MoveTo(0, y))?;
+            let r = self.player.r() + y as isize - (rows / 2) as isize;
+            let mut line = String::new();
+            for x in 0..width {
+                let column = center_column + x as isize - (width / 2) as isize;
+                let (glyph, color) = if (column - r).rem_euclid(2) != 0 {
+                    (' ', Color::Reset)
+                } else {
+                    self.glyph(AxialVector::new((column - r) / 2, r))
+                };
+                if current_color != Some(color) {
+                    queue!(out, Print(&line), SetForegroundColor(color))?;
+                    line.clear();
+                    current_color = Some(color);
+                }
+                line.push(glyph);
+            }
+            queue!(out, Print(&line))?;
+        }
+        let status =
+            format!(
+            "seed {} turned {}{} - turn {} tick {} - {} hits taken - {} hexes explored - move with w e a d z x, s waits, q quits",
+            self.seed,
+            self.transform.steps(),
+            if self.transform.reflect() { " mirrored" } else { "" },
+            self.turn,
+            self.scheduler.tick(),
+            self.hits,
+            self.explored.len()
+        );
+        queue!(
+            out,
+            cursor::MoveTo(0, rows),
+            SetForegroundColor(Color::Reset),
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            Print(status.chars().take(width as usize).collect::<String>())
+        )
+    }
+
+    // The explored hexes out of sight are dimmed.
+    fn glyph(&self, position: AxialVector) -> (char, Color) {
+        if position == self.player {
+            return ('@', Color::Yellow);
+        }
+        if self.visible.contains(position) {
+            if let Some(monster) = self.monster_at(position) {
+                return monster.kind.glyph();
+            }
+        }
+        if !self.explored.contains(position) || !self.world.hexes().contains_position(position) {
+            return (' ', Color::Reset);
+        }
+        let glyph = if self.is_open(position) { '.' } else { '#' };
+        let color = if self.visible.contains(position) {
+            Color::White
+        } else {
+            Color::DarkGrey
+        };
+        (glyph, color)
+    }
+}
+
+fn run<W: Write>(game: &mut Game, out: &mut W) -> crossterm::Result<()> {
+    loop {
+        game.render(out, terminal::size()?)?;
+        out.flush()?;
+        if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+            // The rows being shifted, the six directions are around s on the keyboard.
+            let (q, r) = match code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('s') => {
+                    game.end_turn();
+                    continue;
+                }
+                KeyCode::Char('d') => (1, 0),
+                KeyCode::Char('e') => (1, -1),
+                KeyCode::Char('w') => (0, -1),
+                KeyCode::Char('a') => (-1, 0),
+                KeyCode::Char('z') => (-1, 1),
+                KeyCode::Char('x') => (0, 1),
+                _ => continue,
+            };
+            game.step(AxialVector::new(q, r));
+        }
+    }
+}
+
+fn main() -> crossterm::Result<()> {
+    let seed = std::env::args()
+        .nth(1)
+        .map(|seed| seed.parse().expect("seed"))
+        .unwrap_or(42);
+    let mut game = Game::new(seed, HexTransform::random(&mut thread_rng()));
+    let mut out = stdout();
+    terminal::enable_raw_mode()?;
+    queue!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    // The terminal is restored whatever happens.
+    let result = run(&mut game, &mut out);
+    queue!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    out.flush()?;
+    terminal::disable_raw_mode()?;
+    result
+}
@@ -0,0 +1,69 @@
+// Examines saved maps without the viewer, e.g. in CI or on a server: the report of each map file
+// and an ASCII preview of its layers. The exit code is 1 if a file cannot be read or one of its
+// layers is corrupted.
+//
+// cargo run -p rhombus_mapgen --bin rhombus-mapgen -- inspect [--no-preview] <file.rhmap>...
+
+use rhombus_core::hex::map_file::MapFile;
+use rhombus_mapgen::inspect::inspect;
+use std::{env, fs::File, io::BufReader, process};
+
+const USAGE: &str = "usage: rhombus-mapgen inspect [--no-preview] <file.rhmap>...";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) != Some("inspect") {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let mut preview = true;
+    let mut paths = Vec::new();
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "--no-preview" => preview = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option {}\n{}", arg, USAGE);
+                process::exit(2);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let mut failed = false;
+    for (i, path) in paths.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}:", path);
+        let map = File::open(path).and_then(|file| MapFile::read_from(&mut BufReader::new(file)));
+        match map {
+            Ok(map) => {
+                let inspection = inspect(&map, preview);
+                print!("{}", inspection.report);
+                if !inspection.is_valid() {
+                    eprintln!(
+                        "{}: invalid layers {}",
+                        path,
+                        inspection.invalid_layers.join(", ")
+                    );
+                    failed = true;
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
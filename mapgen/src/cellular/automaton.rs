@@ -0,0 +1,289 @@
+use super::HexState;
+use rhombus_core::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    storage::hash::RectHashStorage,
+};
+use smallvec::{smallvec, SmallVec};
+use std::marker::PhantomData;
+
+// The states of a multi-state automaton, e.g. water, sand and rock for an erosion model or trees,
+// fire and ash for a fire spreading. Each state has its own index below COUNT, so that the
+// neighbors are counted state by state.
+pub trait CellState: Copy + Eq + Send + Sync {
+    const COUNT: usize;
+
+    fn index(self) -> usize;
+}
+
+impl CellState for HexState {
+    const COUNT: usize = 3;
+
+    fn index(self) -> usize {
+        match self {
+            HexState::Open => 0,
+            HexState::Wall => 1,
+            HexState::HardWall => 2,
+        }
+    }
+}
+
+// The number of neighbors of a hex in each state, weighted by the kernel of the automaton. The
+// hexes missing from the automaton are not counted at all.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NeighborCounts<S> {
+    counts: SmallVec<[u16; 8]>,
+    state: PhantomData<S>,
+}
+
+impl<S: CellState> NeighborCounts<S> {
+    fn new() -> Self {
+        Self {
+            counts: smallvec![0; S::COUNT],
+            state: PhantomData,
+        }
+    }
+
+    pub fn get(&self, state: S) -> u16 {
+        self.counts[state.index()]
+    }
+
+    pub fn total(&self) -> u16 {
+        self.counts.iter().sum()
+    }
+}
+
+// Runs a transition function over the states of the hexes and the counts of their neighbors in
+// each state, every hex of a generation depending on the previous generation only. The wall/open
+// automaton of CellularWorld on hexes is the special case of HexState. The stepping knows nothing
+// about rendering: the hexes changed by the last generation are kept for whoever shows them, so
+// that a simulation running for a whole game only updates what changes.
+pub struct Automaton<S> {
+    hexes: RectHashStorage<S>,
+    // The weight of each ring, the first one for the direct neighbors, see RulePhase::weights.
+    kernel: Vec<u8>,
+    threads: usize,
+    generation: usize,
+    changed: Vec<AxialVector>,
+}
+
+impl<S: CellState> Automaton<S> {
+    // Counts the direct neighbors only.
+    pub fn new(hexes: RectHashStorage<S>) -> Self {
+        Self {
+            hexes,
+            kernel: vec![1],
+            threads: 1,
+            generation: 0,
+            changed: Vec::new(),
+        }
+    }
+
+    pub fn kernel(&self) -> &[u8] {
+        &self.kernel
+    }
+
+    pub fn set_kernel(&mut self, kernel: Vec<u8>) {
+        assert!(!kernel.is_empty(), "At least one ring is needed");
+        self.kernel = kernel;
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    // Number of workers counting the neighbors of the hexes, the result does not depend on it.
+    pub fn set_threads(&mut self, threads: usize) {
+        assert!(threads > 0, "At least one thread is needed");
+        self.threads = threads;
+    }
+
+    pub fn hexes(&self) -> &RectHashStorage<S> {
+        &self.hexes
+    }
+
+    pub fn hexes_mut(&mut self) -> &mut RectHashStorage<S> {
+        &mut self.hexes
+    }
+
+    pub fn into_hexes(self) -> RectHashStorage<S> {
+        self.hexes
+    }
+
+    // The number of generations stepped so far.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    // The hexes whose state changed in the last generation, sorted.
+    pub fn changed(&self) -> &[AxialVector] {
+        &self.changed
+    }
+
+    // One generation, true if the automaton is frozen (nothing changed). The transition is also
+    // given the generation being computed, e.g. to draw random events which do not depend on the
+    // number of threads.
+    pub fn step<F>(&mut self, transition: F) -> bool
+    where
+        F: Fn(AxialVector, usize, S, &NeighborCounts<S>) -> S + Sync,
+    {
+        let _span = rhombus_core::timed_span!(
+            "automaton_step",
+            hexes = self.hexes.len(),
+            radius = self.kernel.len(),
+            threads = self.threads
+        );
+        let kernel = &self.kernel;
+        let generation = self.generation + 1;
+        let changed = &mut self.changed;
+        changed.clear();
+        self.hexes.par_update(
+            self.threads,
+            |pos, state, hexes| {
+                let mut counts = NeighborCounts::new();
+                // The rings walked hex by hex, in ring_iter order.
+                let mut cursor = hexes.cursor(pos);
+                for (r, weight) in (1..).zip(kernel) {
+                    if *weight == 0 {
+                        continue;
+                    }
+                    cursor.jump(pos + AxialVector::direction(4) * r as isize);
+                    for direction in 0..NUM_DIRECTIONS {
+                        for _ in 0..r {
+                            if let Some(neighbor_state) = cursor.get() {
+                                counts.counts[neighbor_state.index()] += u16::from(*weight);
+                            }
+                            cursor.move_to(direction);
+                        }
+                    }
+                }
+                (pos, transition(pos, generation, *state, &counts))
+            },
+            |state, (pos, next)| {
+                if *state != next {
+                    *state = next;
+                    changed.push(pos);
+                }
+            },
+        );
+        changed.sort_unstable();
+        self.generation = generation;
+        self.changed.is_empty()
+    }
+
+    // Steps until the automaton freezes or after the given number of generations, returns the
+    // number of generations run.
+    pub fn run<F>(&mut self, max_generations: usize, transition: F) -> usize
+    where
+        F: Fn(AxialVector, usize, S, &NeighborCounts<S>) -> S + Sync,
+    {
+        for generation in 0..max_generations {
+            if self.step(&transition) {
+                return generation + 1;
+            }
+        }
+        max_generations
+    }
+}
+
+#[test]
+fn test_automaton_should_match_cellular_world() {
+    use super::{test_world, CellularWorld};
+
+    let birth = 3..=6;
+    let survive = 3..=6;
+    let mut world: CellularWorld<()> = test_world();
+    world.expand(|_| ());
+    let mut automaton = Automaton::new(world.states());
+    for _ in 0..3 {
+        world.phase2_step1();
+        let world_frozen = world.phase2_step2(
+            |count| birth.contains(&count),
+            |count| survive.contains(&count),
+        );
+        let frozen = automaton.step(|_, _, state, counts| {
+            let walls = (counts.get(HexState::Wall) + counts.get(HexState::HardWall)) as u8;
+            match state {
+                HexState::Open if birth.contains(&walls) => HexState::Wall,
+                HexState::Wall if !survive.contains(&walls) => HexState::Open,
+                state => state,
+            }
+        });
+        assert_eq!(frozen, world_frozen);
+        rhombus_core::assert_storage_eq!(automaton.hexes(), world.states());
+    }
+}
+
+#[test]
+fn test_automaton_should_spread_fire() {
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    enum Forest {
+        Tree,
+        Fire,
+        Ash,
+    }
+
+    impl CellState for Forest {
+        const COUNT: usize = 3;
+
+        fn index(self) -> usize {
+            self as usize
+        }
+    }
+
+    let radius = 6;
+    let burn = |_, _, state, counts: &NeighborCounts<Forest>| match state {
+        Forest::Tree if counts.get(Forest::Fire) > 0 => Forest::Fire,
+        Forest::Fire => Forest::Ash,
+        state => state,
+    };
+    let forest = |threads, kernel: Vec<u8>| {
+        let mut hexes = RectHashStorage::new();
+        for pos in AxialVector::default().spiral_iter(radius) {
+            hexes.insert(pos, Forest::Tree);
+        }
+        hexes.insert(AxialVector::default(), Forest::Fire);
+        let mut automaton = Automaton::new(hexes);
+        automaton.set_threads(threads);
+        automaton.set_kernel(kernel);
+        automaton
+    };
+
+    let mut automaton = forest(1, vec![1]);
+    for generation in 1..=3 {
+        assert!(!automaton.step(burn));
+        assert_eq!(automaton.generation(), generation);
+        // The new ring of fire and the previous one turned to ash.
+        let burnt = if generation == 1 {
+            1
+        } else {
+            6 * (generation - 1)
+        };
+        assert_eq!(automaton.changed().len(), 6 * generation + burnt);
+        // The fire front is a ring, ash behind it.
+        for (pos, state) in automaton.hexes().iter() {
+            let distance = pos.distance(AxialVector::default()) as usize;
+            let expected = match distance {
+                d if d < generation => Forest::Ash,
+                d if d == generation => Forest::Fire,
+                _ => Forest::Tree,
+            };
+            assert_eq!(*state, expected, "{:?} at generation {}", pos, generation);
+        }
+    }
+    // Everything burns, then nothing changes.
+    assert_eq!(automaton.run(100, burn), radius - 3 + 2);
+    assert!(automaton.hexes().hexes().all(|state| *state == Forest::Ash));
+    assert!(automaton.changed().is_empty());
+
+    // Jumping over the direct neighbors.
+    let mut automaton = forest(4, vec![0, 1]);
+    automaton.step(burn);
+    let counts = automaton.hexes().hexes().fold([0; 3], |mut counts, state| {
+        counts[state.index()] += 1;
+        counts
+    });
+    assert_eq!(counts, [127 - 12 - 1, 12, 1]);
+}
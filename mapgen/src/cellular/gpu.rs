@@ -0,0 +1,302 @@
+use super::{HexData, HexState};
+use rhombus_core::{
+    hex::{
+        coordinates::axial::AxialVector,
+        storage::{
+            hash::RectHashStorage,
+            rect::{RECT_X_LEN, RECT_Y_LEN},
+        },
+    },
+    vector::Vector2ISize,
+};
+use std::{borrow::Cow, collections::HashMap};
+use wgpu::util::DeviceExt;
+
+// One workgroup per rect, one invocation per hex. Each hex counts its wall neighbors in the rect
+// bitplanes (the 3x3 rects around its own one are looked up in `neighbors`), applies the rules and
+// flags itself in the deltas when its state changes.
+const SHADER: &str = r#"
+struct Params {
+    rect_count: u32,
+    birth: u32,
+    survive: u32,
+    groups_x: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> walls: array<vec2<u32>>;
+@group(0) @binding(2) var<storage, read> movable: array<vec2<u32>>;
+@group(0) @binding(3) var<storage, read> neighbors: array<array<u32, 9>>;
+@group(0) @binding(4) var<storage, read_write> deltas: array<atomic<u32>>;
+
+const NONE: u32 = 0xffffffffu;
+
+fn has_bit(mask: vec2<u32>, offset: u32) -> bool {
+    if offset < 32u {
+        return ((mask.x >> offset) & 1u) != 0u;
+    }
+    return ((mask.y >> (offset - 32u)) & 1u) != 0u;
+}
+
+fn is_wall(rect: u32, x: i32, y: i32) -> bool {
+    let rx = select(select(1, 2, x >= 8), 0, x < 0);
+    let ry = select(select(1, 2, y >= 8), 0, y < 0);
+    let index = neighbors[rect][ry * 3 + rx];
+    if index == NONE {
+        return false;
+    }
+    let offset = u32(x - (rx - 1) * 8) + u32(y - (ry - 1) * 8) * 8u;
+    return has_bit(walls[index], offset);
+}
+
+@compute @workgroup_size(64)
+fn main(
+    @builtin(workgroup_id) group: vec3<u32>,
+    @builtin(local_invocation_index) offset: u32,
+) {
+    let rect = group.x + group.y * params.groups_x;
+    if rect >= params.rect_count || !has_bit(movable[rect], offset) {
+        return;
+    }
+    let x = i32(offset % 8u);
+    let y = i32(offset / 8u);
+    var count = 0u;
+    count += u32(is_wall(rect, x + 1, y));
+    count += u32(is_wall(rect, x + 1, y - 1));
+    count += u32(is_wall(rect, x, y - 1));
+    count += u32(is_wall(rect, x - 1, y));
+    count += u32(is_wall(rect, x - 1, y + 1));
+    count += u32(is_wall(rect, x, y + 1));
+    let wall = has_bit(walls[rect], offset);
+    let rule = select(params.birth, params.survive, wall);
+    let next_wall = ((rule >> count) & 1u) != 0u;
+    if next_wall != wall {
+        atomicOr(&deltas[rect * 2u + offset / 32u], 1u << (offset % 32u));
+    }
+}
+"#;
+
+const NONE: u32 = u32::MAX;
+const MAX_GROUPS_X: usize = 65535;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    rect_count: u32,
+    birth: u32,
+    survive: u32,
+    groups_x: u32,
+}
+
+// Runs the radius 1 steps of the automaton on hexes with a compute shader: the rects of the world
+// are uploaded as wall and movable hex bitplanes, only the hexes whose state changes come back.
+// The GPU is shared by the worlds it is given to.
+pub struct GpuAutomaton {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl GpuAutomaton {
+    // None when there is no usable adapter, the worlds then stay on the CPU.
+    pub fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("rhombus_mapgen cellular automaton"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+            },
+            None,
+        ))
+        .ok()?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        });
+        Some(Self {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    // Same as phase2_step1 and phase2_step2 with a radius of 1, but the automaton counts are not
+    // updated. Returns whether the automaton is frozen.
+    pub(super) fn step<H>(
+        &self,
+        hexes: &mut RectHashStorage<(HexData, H)>,
+        birth: (u8, u8),
+        survive: (u8, u8),
+    ) -> bool {
+        let _span = rhombus_core::timed_span!("gpu_step", hexes = hexes.len());
+        let mut rect_origins = Vec::new();
+        let mut walls = Vec::new();
+        let mut movable = Vec::new();
+        for (rect_origin, rect) in hexes.rects() {
+            let (mut wall_mask, mut movable_mask) = (0u64, 0u64);
+            for (x, y, (hex_data, _)) in rect.iter() {
+                let bit = 1 << (x + y * RECT_X_LEN);
+                if hex_data.state != HexState::Open {
+                    wall_mask |= bit;
+                }
+                if hex_data.state != HexState::HardWall && !hex_data.border {
+                    movable_mask |= bit;
+                }
+            }
+            rect_origins.push(rect_origin);
+            walls.push(split_mask(wall_mask));
+            movable.push(split_mask(movable_mask));
+        }
+        if rect_origins.is_empty() {
+            return true;
+        }
+        let indices = rect_origins
+            .iter()
+            .enumerate()
+            .map(|(index, rect_origin)| (*rect_origin, index as u32))
+            .collect::<HashMap<_, _>>();
+        let neighbors = rect_origins
+            .iter()
+            .map(|rect_origin| {
+                let mut around = [NONE; 9];
+                for (index, rect_index) in around.iter_mut().enumerate() {
+                    let origin = Vector2ISize {
+                        x: rect_origin.x + (index % 3) as isize - 1,
+                        y: rect_origin.y + (index / 3) as isize - 1,
+                    };
+                    *rect_index = indices.get(&origin).copied().unwrap_or(NONE);
+                }
+                around
+            })
+            .collect::<Vec<_>>();
+
+        let groups_x = rect_origins.len().min(MAX_GROUPS_X);
+        let groups_y = rect_origins.len().div_ceil(groups_x);
+        let params = Params {
+            rect_count: rect_origins.len() as u32,
+            birth: count_mask(birth),
+            survive: count_mask(survive),
+            groups_x: groups_x as u32,
+        };
+        let deltas = self.run(&params, &walls, &movable, &neighbors, groups_y);
+
+        for (rect_origin, delta) in rect_origins.iter().zip(deltas.chunks(2)) {
+            let mut delta = delta[0] as u64 | (delta[1] as u64) << 32;
+            while delta != 0 {
+                let offset = delta.trailing_zeros() as usize;
+                delta &= delta - 1;
+                let position = AxialVector::new(
+                    rect_origin.x * RECT_X_LEN as isize + (offset % RECT_X_LEN) as isize,
+                    rect_origin.y * RECT_Y_LEN as isize + (offset / RECT_X_LEN) as isize,
+                );
+                let (hex_data, _) = hexes.get_mut(position).expect("changed hex");
+                hex_data.state = match hex_data.state {
+                    HexState::Open => HexState::Wall,
+                    _ => HexState::Open,
+                };
+            }
+        }
+        deltas.iter().all(|delta| *delta == 0)
+    }
+
+    fn run(
+        &self,
+        params: &Params,
+        walls: &[[u32; 2]],
+        movable: &[[u32; 2]],
+        neighbors: &[[u32; 9]],
+        groups_y: usize,
+    ) -> Vec<u32> {
+        let storage = |contents: &[u8]| {
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents,
+                    usage: wgpu::BufferUsages::STORAGE,
+                })
+        };
+        let params_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::bytes_of(params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let walls_buffer = storage(bytemuck::cast_slice(walls));
+        let movable_buffer = storage(bytemuck::cast_slice(movable));
+        let neighbors_buffer = storage(bytemuck::cast_slice(neighbors));
+        let deltas_size = (walls.len() * 2 * std::mem::size_of::<u32>()) as u64;
+        let deltas_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: deltas_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: deltas_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                &params_buffer,
+                &walls_buffer,
+                &movable_buffer,
+                &neighbors_buffer,
+                &deltas_buffer,
+            ]
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>(),
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(params.groups_x, groups_y as u32, 1);
+        }
+        encoder.copy_buffer_to_buffer(&deltas_buffer, 0, &staging_buffer, 0, deltas_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            result.expect("Cannot read the automaton deltas")
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        let deltas = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        staging_buffer.unmap();
+        deltas
+    }
+}
+
+fn split_mask(mask: u64) -> [u32; 2] {
+    [mask as u32, (mask >> 32) as u32]
+}
+
+// Bit n is set when a count of n is in the range, counts never exceed 6.
+fn count_mask(range: (u8, u8)) -> u32 {
+    (range.0..=range.1.min(31)).fold(0, |mask, count| mask | 1 << count)
+}
@@ -0,0 +1,288 @@
+use rhombus_core::hex::{
+    carve::carve_line,
+    coordinates::axial::AxialVector,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use std::collections::VecDeque;
+
+// A generated piece (dungeon level, cave pocket, room...) and the hexes at its edge which
+// corridors may start from, in its own coordinates.
+pub struct SubMap<H> {
+    pub hexes: RectHashStorage<H>,
+    pub exits: Vec<AxialVector>,
+}
+
+// Lays sub-maps out into one storage and links their exits with corridors. Corridors go around the
+// pieces but may cross each other, their walls are only raised where there is nothing yet.
+pub struct WorldComposer<H> {
+    hexes: RectHashStorage<H>,
+    occupied: HexSet,
+    corridors: HexSet,
+    exits: Vec<Vec<AxialVector>>,
+}
+
+impl<H> Default for WorldComposer<H> {
+    fn default() -> Self {
+        Self {
+            hexes: RectHashStorage::new(),
+            occupied: HexSet::new(),
+            corridors: HexSet::new(),
+            exits: Vec::new(),
+        }
+    }
+}
+
+impl<H: Clone> WorldComposer<H> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hexes(&self) -> &RectHashStorage<H> {
+        &self.hexes
+    }
+
+    pub fn into_hexes(self) -> RectHashStorage<H> {
+        self.hexes
+    }
+
+    pub fn corridors(&self) -> &HexSet {
+        &self.corridors
+    }
+
+    // The exits of a placed piece, in world coordinates.
+    pub fn exits(&self, piece: usize) -> &[AxialVector] {
+        &self.exits[piece]
+    }
+
+    // Moves the sub-map by the offset and adds it, unless it overlaps a piece or a corridor.
+    // Returns the index of the piece.
+    pub fn place(&mut self, sub_map: SubMap<H>, offset: AxialVector) -> Option<usize> {
+        let hexes = sub_map.hexes.translated(offset);
+        let positions: HexSet = HexSet::from_storage(&hexes, |_| true);
+        if hexes
+            .positions()
+            .any(|position| self.occupied.contains(position) || self.corridors.contains(position))
+        {
+            return None;
+        }
+        for (position, hex) in hexes.iter() {
+            self.hexes.insert(position, hex.clone());
+        }
+        self.occupied.union_with(&positions);
+        self.exits
+            .push(sub_map.exits.iter().map(|exit| *exit + offset).collect());
+        Some(self.exits.len() - 1)
+    }
+
+    // Digs the shortest corridor between two exits, given as piece and exit indices, if there is
+    // one no longer than max_length hexes. The corridor hexes become floor, the empty hexes along
+    // it become walls. Returns the corridor, from the first exit to the second one, both excluded.
+    pub fn connect(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        max_length: usize,
+        floor: H,
+        wall: H,
+    ) -> Option<Vec<AxialVector>> {
+        let start = self.exits[from.0][from.1];
+        let goal = self.exits[to.0][to.1];
+        let path = self.find_corridor(start, goal, max_length)?;
+        for position in &path {
+            self.hexes.insert(*position, floor.clone());
+            self.corridors.insert(*position);
+        }
+        for position in &path {
+            for neighbor in position.ring_iter(1) {
+                if !self.hexes.contains_position(neighbor) {
+                    self.hexes.insert(neighbor, wall.clone());
+                }
+            }
+        }
+        Some(path)
+    }
+
+    // Digs a straight corridor of the given width between two exits, unless the line between them
+    // crosses a piece. Wider corridors also open the walls around the exits. Returns the line, from
+    // the first exit to the second one, both excluded.
+    pub fn connect_straight(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        width: usize,
+        floor: H,
+        wall: H,
+    ) -> Option<Vec<AxialVector>> {
+        let start = self.exits[from.0][from.1];
+        let goal = self.exits[to.0][to.1];
+        let line = start.line_iter(goal).collect::<Vec<_>>();
+        let inside = &line[1..line.len().max(2) - 1];
+        if inside
+            .iter()
+            .any(|position| self.occupied.contains(*position))
+        {
+            return None;
+        }
+        let mut carved = carve_line(&mut self.hexes, start, goal, width, |_| floor.clone());
+        for position in carved.frontier().iter() {
+            if !self.hexes.contains_position(position) {
+                self.hexes.insert(position, wall.clone());
+            }
+        }
+        carved.difference_with(&self.occupied);
+        self.corridors.union_with(&carved);
+        Some(inside.to_vec())
+    }
+
+    // Breadth first search through the hexes out of the pieces.
+    fn find_corridor(
+        &self,
+        start: AxialVector,
+        goal: AxialVector,
+        max_length: usize,
+    ) -> Option<Vec<AxialVector>> {
+        let mut previous = RectHashStorage::new();
+        let mut pending = VecDeque::new();
+        previous.insert(start, (start, 0));
+        pending.push_back(start);
+        while let Some(position) = pending.pop_front() {
+            let length = previous.get(position).map_or(0, |(_, length)| *length);
+            for neighbor in position.ring_iter(1) {
+                if neighbor == goal {
+                    let mut path = Vec::new();
+                    let mut current = position;
+                    while current != start {
+                        path.push(current);
+                        current = previous.get(current).expect("previous").0;
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                if length >= max_length
+                    || self.occupied.contains(neighbor)
+                    || previous.contains_position(neighbor)
+                {
+                    continue;
+                }
+                previous.insert(neighbor, (position, length + 1));
+                pending.push_back(neighbor);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+fn test_sub_map(radius: usize) -> SubMap<bool> {
+    // A round room with its wall, open hexes being true, and exits on the east and west walls.
+    let mut hexes = RectHashStorage::new();
+    for position in AxialVector::default().spiral_iter(radius) {
+        hexes.insert(
+            position,
+            position.distance(AxialVector::default()) < radius as isize,
+        );
+    }
+    let exits = vec![
+        AxialVector::new(radius as isize, 0),
+        AxialVector::new(-(radius as isize), 0),
+    ];
+    for exit in &exits {
+        hexes.insert(*exit, true);
+    }
+    SubMap { hexes, exits }
+}
+
+#[test]
+fn test_world_composer() {
+    let mut composer = WorldComposer::new();
+    let west = composer.place(test_sub_map(3), AxialVector::new(-10, 0));
+    let east = composer.place(test_sub_map(4), AxialVector::new(10, 2));
+    assert_eq!((west, east), (Some(0), Some(1)));
+    assert_eq!(
+        composer.place(test_sub_map(2), AxialVector::new(7, 2)),
+        None
+    );
+    assert_eq!(composer.exits(0)[0], AxialVector::new(-7, 0));
+    assert_eq!(composer.hexes().len(), 37 + 61);
+
+    // From the east exit of the west room to the west exit of the east room.
+    assert_eq!(composer.connect((0, 0), (1, 1), 10, true, false), None);
+    let corridor = composer
+        .connect((0, 0), (1, 1), 20, true, false)
+        .expect("corridor");
+    let (start, goal) = (composer.exits(0)[0], composer.exits(1)[1]);
+    assert_eq!(corridor.len(), start.distance(goal) as usize - 1);
+    assert_eq!(corridor[0].distance(start), 1);
+    assert_eq!(corridor[corridor.len() - 1].distance(goal), 1);
+    assert!(corridor
+        .windows(2)
+        .all(|pair| pair[0].distance(pair[1]) == 1));
+    assert!(corridor
+        .iter()
+        .all(|position| composer.hexes().get(*position) == Some(&true)));
+    assert_eq!(composer.corridors().len(), corridor.len());
+    // Walled on both sides.
+    for position in &corridor {
+        assert!(position
+            .ring_iter(1)
+            .all(|neighbor| composer.hexes().contains_position(neighbor)));
+    }
+
+    // Around the west room from its west exit, the corridor does not go through it.
+    let around = composer
+        .connect((0, 1), (1, 0), 60, true, false)
+        .expect("corridor");
+    let west_room = AxialVector::new(-10, 0);
+    assert!(around
+        .iter()
+        .all(|position| position.distance(west_room) > 3));
+    assert_eq!(composer.place(test_sub_map(1), around[5]), None);
+    assert_eq!(composer.into_hexes().get(around[5]), Some(&true));
+}
+
+#[test]
+fn test_world_composer_connect_straight() {
+    let mut composer = WorldComposer::new();
+    composer.place(test_sub_map(3), AxialVector::new(-10, 0));
+    composer.place(test_sub_map(3), AxialVector::new(10, 0));
+    composer.place(test_sub_map(2), AxialVector::default());
+
+    // Through the middle room, then from the west room to the middle one.
+    assert_eq!(
+        composer.connect_straight((0, 0), (1, 1), 1, true, false),
+        None
+    );
+    let line = composer
+        .connect_straight((0, 0), (2, 1), 3, true, false)
+        .expect("corridor");
+    assert_eq!(
+        line,
+        (-6..=-3)
+            .map(|q| AxialVector::new(q, 0))
+            .collect::<Vec<_>>()
+    );
+    let hexes = composer.hexes();
+    for position in &line {
+        assert!(position
+            .spiral_iter(1)
+            .all(|hex| hexes.get(hex) == Some(&true)));
+        assert!(position
+            .ring_iter(2)
+            .all(|hex| hexes.contains_position(hex)));
+    }
+    // Both exits are wider, the rooms are otherwise untouched.
+    assert_eq!(hexes.get(AxialVector::new(-7, -1)), Some(&true));
+    assert_eq!(hexes.get(AxialVector::new(-7, -2)), Some(&false));
+    assert_eq!(hexes.get(AxialVector::new(-2, 1)), Some(&true));
+    assert_eq!(hexes.get(AxialVector::new(-2, 2)), Some(&false));
+    let corridors = composer.corridors();
+    assert!(line.iter().all(|position| corridors.contains(*position)));
+    assert!(corridors
+        .iter()
+        .all(|position| position.distance(AxialVector::new(-10, 0)) > 3
+            && position.distance(AxialVector::default()) > 2));
+    assert_eq!(
+        composer.place(test_sub_map(1), AxialVector::new(-5, 1)),
+        None
+    );
+}
@@ -0,0 +1,288 @@
+use rhombus_core::hex::{coordinates::axial::AxialVector, float::layout::HexLayout};
+use std::{fmt, io};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum NodeKind {
+    Room,
+    Corridor,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GraphNode {
+    pub region: usize,
+    pub kind: NodeKind,
+    // The open hex closest to the middle of the region, good enough to lay the graph out.
+    pub center: AxialVector,
+    pub size: usize,
+}
+
+// The nodes are given by their indices, the position is the door or connector hex between them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub position: AxialVector,
+}
+
+// Topology of a generated dungeon: rooms and corridors, linked where they open onto each other.
+#[derive(Clone, Default, Debug)]
+pub struct DungeonGraph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    // Edge indices of every node.
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl DungeonGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, node: GraphNode) -> usize {
+        self.nodes.push(node);
+        self.adjacency.push(Vec::new());
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, position: AxialVector) -> usize {
+        assert!(from < self.nodes.len() && to < self.nodes.len());
+        let index = self.edges.len();
+        self.edges.push(GraphEdge { from, to, position });
+        self.adjacency[from].push(index);
+        if to != from {
+            self.adjacency[to].push(index);
+        }
+        index
+    }
+
+    pub fn nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[GraphEdge] {
+        &self.edges
+    }
+
+    pub fn node_by_region(&self, region: usize) -> Option<usize> {
+        self.nodes.iter().position(|node| node.region == region)
+    }
+
+    // The other end of every edge of the node, with the edge.
+    pub fn neighbors<'a>(
+        &'a self,
+        node: usize,
+    ) -> impl 'a + Iterator<Item = (usize, &'a GraphEdge)> {
+        self.adjacency[node].iter().map(move |index| {
+            let edge = &self.edges[*index];
+            (
+                if edge.from == node {
+                    edge.to
+                } else {
+                    edge.from
+                },
+                edge,
+            )
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        if self.nodes.is_empty() {
+            return true;
+        }
+        let mut reached = vec![false; self.nodes.len()];
+        reached[0] = true;
+        let mut to_visit = vec![0];
+        while let Some(node) = to_visit.pop() {
+            for (neighbor, _) in self.neighbors(node) {
+                if !reached[neighbor] {
+                    reached[neighbor] = true;
+                    to_visit.push(neighbor);
+                }
+            }
+        }
+        reached.into_iter().all(|reached| reached)
+    }
+}
+
+impl DungeonGraph {
+    // GraphViz undirected graph, nodes are pinned to their position in the layout (the y axis is
+    // flipped, it goes up in GraphViz), `neato -n` keeps them there.
+    pub fn write_dot<W: io::Write>(&self, mut w: W, layout: &HexLayout) -> io::Result<()> {
+        writeln!(w, "graph dungeon {{")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let [x, y] = layout.to_point(node.center);
+            writeln!(
+                w,
+                "    n{} [label=\"{}\", shape={}, pos=\"{:.1},{:.1}!\", size={}];",
+                index,
+                node.region,
+                match node.kind {
+                    NodeKind::Room => "box",
+                    NodeKind::Corridor => "ellipse",
+                },
+                x,
+                0.0 - y,
+                node.size
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                w,
+                "    n{} -- n{} [label=\"{},{}\"];",
+                edge.from,
+                edge.to,
+                edge.position.q(),
+                edge.position.r()
+            )?;
+        }
+        writeln!(w, "}}")
+    }
+
+    pub fn write_graphml<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for (id, domain, name, ty) in [
+            ("region", "node", "region", "int"),
+            ("kind", "node", "kind", "string"),
+            ("q", "all", "q", "int"),
+            ("r", "all", "r", "int"),
+            ("size", "node", "size", "int"),
+        ]
+        .iter()
+        {
+            writeln!(
+                w,
+                r#"  <key id="{}" for="{}" attr.name="{}" attr.type="{}"/>"#,
+                id, domain, name, ty
+            )?;
+        }
+        writeln!(w, r#"  <graph id="dungeon" edgedefault="undirected">"#)?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            writeln!(w, r#"    <node id="n{}">"#, index)?;
+            writeln!(w, r#"      <data key="region">{}</data>"#, node.region)?;
+            writeln!(
+                w,
+                r#"      <data key="kind">{}</data>"#,
+                match node.kind {
+                    NodeKind::Room => "room",
+                    NodeKind::Corridor => "corridor",
+                }
+            )?;
+            writeln!(w, r#"      <data key="q">{}</data>"#, node.center.q())?;
+            writeln!(w, r#"      <data key="r">{}</data>"#, node.center.r())?;
+            writeln!(w, r#"      <data key="size">{}</data>"#, node.size)?;
+            writeln!(w, r#"    </node>"#)?;
+        }
+        for (index, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                w,
+                r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+                index, edge.from, edge.to
+            )?;
+            writeln!(w, r#"      <data key="q">{}</data>"#, edge.position.q())?;
+            writeln!(w, r#"      <data key="r">{}</data>"#, edge.position.r())?;
+            writeln!(w, r#"    </edge>"#)?;
+        }
+        writeln!(w, r#"  </graph>"#)?;
+        writeln!(w, r#"</graphml>"#)
+    }
+}
+
+impl fmt::Display for DungeonGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rooms = self
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Room)
+            .count();
+        write!(
+            f,
+            "{} rooms, {} corridors, {} connections",
+            rooms,
+            self.nodes.len() - rooms,
+            self.edges.len()
+        )
+    }
+}
+
+#[test]
+fn test_dungeon_graph() {
+    let mut graph = DungeonGraph::new();
+    let node = |region, kind| GraphNode {
+        region,
+        kind,
+        center: AxialVector::new(region as isize, 0),
+        size: 1,
+    };
+    let a = graph.add_node(node(3, NodeKind::Room));
+    let b = graph.add_node(node(5, NodeKind::Corridor));
+    let c = graph.add_node(node(7, NodeKind::Room));
+    assert_eq!(graph.node_by_region(5), Some(b));
+    assert_eq!(graph.node_by_region(4), None);
+    graph.add_edge(a, b, AxialVector::new(4, 0));
+    assert!(!graph.is_connected());
+    graph.add_edge(c, b, AxialVector::new(6, 0));
+    assert!(graph.is_connected());
+    assert_eq!(
+        graph.neighbors(b).map(|(node, _)| node).collect::<Vec<_>>(),
+        vec![a, c]
+    );
+    assert_eq!(
+        graph.neighbors(c).next().map(|(_, edge)| edge.position),
+        Some(AxialVector::new(6, 0))
+    );
+    assert_eq!(graph.to_string(), "2 rooms, 1 corridors, 2 connections");
+}
+
+#[cfg(test)]
+fn corridor_graph() -> DungeonGraph {
+    let mut graph = DungeonGraph::new();
+    let room = graph.add_node(GraphNode {
+        region: 0,
+        kind: NodeKind::Room,
+        center: AxialVector::new(0, 0),
+        size: 7,
+    });
+    let corridor = graph.add_node(GraphNode {
+        region: 1,
+        kind: NodeKind::Corridor,
+        center: AxialVector::new(2, -1),
+        size: 3,
+    });
+    graph.add_edge(room, corridor, AxialVector::new(1, 0));
+    graph
+}
+
+#[test]
+fn test_dungeon_graph_write_dot() {
+    let mut dot = Vec::new();
+    corridor_graph()
+        .write_dot(&mut dot, &HexLayout::pointy_top(10.0))
+        .expect("write dot");
+    assert_eq!(
+        String::from_utf8(dot).expect("utf8"),
+        "graph dungeon {\n    \
+         n0 [label=\"0\", shape=box, pos=\"0.0,0.0!\", size=7];\n    \
+         n1 [label=\"1\", shape=ellipse, pos=\"26.0,15.0!\", size=3];\n    \
+         n0 -- n1 [label=\"1,0\"];\n\
+         }\n"
+    );
+}
+
+#[test]
+fn test_dungeon_graph_write_graphml() {
+    let mut graphml = Vec::new();
+    corridor_graph()
+        .write_graphml(&mut graphml)
+        .expect("write graphml");
+    let graphml = String::from_utf8(graphml).expect("utf8");
+    assert!(graphml.starts_with("<?xml"));
+    assert_eq!(graphml.matches("<node ").count(), 2);
+    assert!(graphml.contains(r#"<edge id="e0" source="n0" target="n1">"#));
+    assert!(graphml.contains(r#"<data key="kind">corridor</data>"#));
+    assert!(graphml.trim_end().ends_with("</graphml>"));
+}
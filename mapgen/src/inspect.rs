@@ -0,0 +1,195 @@
+use rhombus_core::hex::{
+    analysis::cluster::{clusters_matching, ClusterAdjacency},
+    ascii::render_ascii,
+    coordinates::offset::HexOrientation,
+    map_file::MapFile,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use std::fmt::Write;
+
+// What the inspector finds in a map file, see the rhombus-mapgen binary.
+pub struct Inspection {
+    pub report: String,
+    // The layers whose hexes cannot be read, corrupted ones first.
+    pub invalid_layers: Vec<String>,
+}
+
+impl Inspection {
+    pub fn is_valid(&self) -> bool {
+        self.invalid_layers.is_empty()
+    }
+}
+
+// The layout, the shape, the hexes and regions of each layer, the dungeon metadata and, with
+// `preview`, the layers in ASCII, each one drawn over the previous ones with the first letter of
+// its name.
+pub fn inspect(map: &MapFile, preview: bool) -> Inspection {
+    let mut report = String::new();
+    let layout = map.layout;
+    let _ = writeln!(
+        report,
+        "version {}, {} hexes of size {} from ({}, {})",
+        map.version(),
+        match layout.orientation() {
+            HexOrientation::PointyTop => "pointy top",
+            HexOrientation::FlatTop => "flat top",
+        },
+        layout.size(),
+        layout.origin()[0],
+        layout.origin()[1]
+    );
+    match &map.shape {
+        Some(shape) => {
+            let _ = writeln!(
+                report,
+                "shape x [{}, {}], y [{}, {}], z [{}, {}] of {} hexes",
+                shape.range_x().start(),
+                shape.range_x().end(),
+                shape.range_y().start(),
+                shape.range_y().end(),
+                shape.range_z().start(),
+                shape.range_z().end(),
+                shape.len()
+            );
+        }
+        None => report.push_str("no shape\n"),
+    }
+
+    let corrupted = map.verify();
+    let mut invalid_layers = corrupted
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    let mut drawn = Vec::new();
+    for name in map.layer_names() {
+        if corrupted.contains(&name) {
+            let _ = writeln!(
+                report,
+                "layer {}: corrupted, the checksum does not match",
+                name
+            );
+            continue;
+        }
+        match map.layer_positions(name) {
+            Ok(Some(positions)) => {
+                let _ = writeln!(
+                    report,
+                    "layer {}: {} hexes in {} regions, {} bytes per hex",
+                    name,
+                    positions.len(),
+                    regions(&positions),
+                    map.value_size(name).unwrap_or(0)
+                );
+                drawn.push((name, positions));
+            }
+            Ok(None) => unreachable!(),
+            Err(err) => {
+                let _ = writeln!(report, "layer {}: {}", name, err);
+                if map.value_size(name).is_some() {
+                    invalid_layers.push(name.to_owned());
+                }
+            }
+        }
+    }
+
+    if let Some(dungeon) = &map.dungeon {
+        let _ = writeln!(
+            report,
+            "dungeon from {} with seed {}: {} rooms, {} doors",
+            dungeon.generator,
+            dungeon.seed,
+            dungeon.rooms.len(),
+            dungeon.doors.len()
+        );
+    }
+
+    if preview && !drawn.is_empty() {
+        let mut letters = RectHashStorage::new();
+        let mut legend = Vec::new();
+        for (name, positions) in &drawn {
+            let letter = name.chars().next().unwrap_or('?');
+            legend.push(format!("{} {}", letter, name));
+            for position in positions.iter() {
+                letters.insert(position, letter);
+            }
+        }
+        let _ = writeln!(report, "\n{}:", legend.join(", "));
+        report.push_str(&render_ascii(&letters, |_, letter| *letter));
+    }
+
+    Inspection {
+        report,
+        invalid_layers,
+    }
+}
+
+fn regions(positions: &HexSet) -> usize {
+    let mut hexes = RectHashStorage::new();
+    for position in positions.iter() {
+        hexes.insert(position, ());
+    }
+    clusters_matching(&hexes, |_| true, 1, ClusterAdjacency::Neighbors).len()
+}
+
+#[test]
+fn test_inspect_map_file() {
+    use rhombus_core::hex::{
+        coordinates::axial::AxialVector, float::layout::HexLayout, map_file::DungeonMetadata,
+        shape::cubic_range::CubicRangeShape,
+    };
+
+    let shape = CubicRangeShape::hexagon(AxialVector::default(), 2);
+    let mut floor = RectHashStorage::<u8>::new();
+    let mut walls = RectHashStorage::<()>::new();
+    for position in shape.iter_positions() {
+        floor.insert(position, 1);
+    }
+    // Two separate walls.
+    walls.insert(AxialVector::new(2, 0), ());
+    walls.insert(AxialVector::new(-2, 0), ());
+    let mut map = MapFile::new(HexLayout::flat_top(2.0));
+    map.shape = Some(shape.clone());
+    map.set_layer("floor", &floor).unwrap();
+    map.set_layer("walls", &walls).unwrap();
+    map.dungeon = Some(DungeonMetadata {
+        generator: "test".to_owned(),
+        seed: 7,
+        rooms: vec![shape],
+        doors: Vec::new(),
+    });
+
+    let inspection = inspect(&map, true);
+    assert!(inspection.is_valid());
+    let expected = "\
+version 2, flat top hexes of size 2 from (0, 0)
+shape x [-2, 2], y [-2, 2], z [-2, 2] of 19 hexes
+layer floor: 19 hexes in 1 regions, 1 bytes per hex
+layer walls: 2 hexes in 2 regions, 0 bytes per hex
+dungeon from test with seed 7: 1 rooms, 0 doors
+
+f floor, w walls:
+  f f f
+ f f f f
+w f f f w
+ f f f f
+  f f f
+";
+    assert_eq!(inspection.report, expected);
+
+    // The checksum of the walls broken.
+    let mut buffer = Vec::new();
+    map.write_to(&mut buffer).unwrap();
+    let walls = buffer
+        .windows(5)
+        .position(|window| window == b"walls")
+        .expect("walls layer");
+    let data_len = buffer[walls + 9];
+    buffer[walls + 17 + data_len as usize] ^= 1;
+    let map = MapFile::read_from(&mut buffer.as_slice()).unwrap();
+    let inspection = inspect(&map, false);
+    assert_eq!(inspection.invalid_layers, vec!["walls".to_owned()]);
+    assert!(inspection
+        .report
+        .contains("layer walls: corrupted, the checksum does not match\n"));
+    assert!(!inspection.report.contains("f floor"));
+}
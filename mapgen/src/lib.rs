@@ -0,0 +1,35 @@
+// Unlike the core, the generators take floating point ratios and chances: the wall ratios of the
+// cellular generator, the loop chance and the room template ratio of the rooms and mazes one. They
+// are only compared to random numbers or combined with +, -, * and /, which IEEE 754 rounds the
+// same way on every target (the x87 FPU aside), so seeded generations do not depend on the
+// platform. Transcendental functions must not be used there.
+pub mod cellular;
+pub mod composer;
+pub mod graph;
+pub mod inspect;
+pub mod patrol;
+pub mod prelude;
+pub mod rooms_and_mazes;
+
+use rand::Rng;
+use rhombus_core::hex::shape::cubic_range::CubicRangeShape;
+
+pub(crate) fn compute_cell_radius(shape: &CubicRangeShape, cell_radius_ratio_den: usize) -> usize {
+    let mut deltas = [
+        shape.range_x().end() - shape.range_x().start(),
+        shape.range_y().end() - shape.range_y().start(),
+        shape.range_z().end() - shape.range_z().start(),
+    ];
+    deltas.sort();
+    deltas[1] as usize / cell_radius_ratio_den
+}
+
+// Ranges of usize or isize are not sampled the same way on 32-bit and 64-bit targets, seeded
+// generations must not depend on the pointer width.
+pub(crate) fn gen_index<R: Rng + ?Sized>(rng: &mut R, len: usize) -> usize {
+    rng.gen_range(0, len as u32) as usize
+}
+
+pub(crate) fn gen_offset<R: Rng + ?Sized>(rng: &mut R, low: isize, high: isize) -> isize {
+    rng.gen_range(low as i32, high as i32) as isize
+}
@@ -0,0 +1,292 @@
+use crate::gen_index;
+use rand::Rng;
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector, reachable::cheapest_path, storage::set::HexSet,
+};
+
+// Random walks tried before giving up on a loop.
+const LOOP_ATTEMPTS: usize = 16;
+// The longest loop looked for, in times the minimum length, so that the search ends on open maps.
+const MAX_LOOP_RATIO: usize = 4;
+
+// A guard route: the waypoints in the order they are visited and every hex walked from the first
+// to the last one, both included. The guard walks it back and forth.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PatrolRoute {
+    pub waypoints: Vec<AxialVector>,
+    pub path: Vec<AxialVector>,
+}
+
+// A closed random walk from the start over the open hexes, of at least `min_length` hexes, each one
+// next to the following one and the last one next to the start, which is not repeated at the end.
+// No hex is walked twice, so the guard never steps back to the hex it just left. The walk goes out
+// at random until the shortest way back would make the loop long enough, then comes back the
+// cheapest way around its own steps. None if no such loop was found, e.g. in a dead end corridor.
+pub fn patrol_loop<O, R>(
+    start: AxialVector,
+    min_length: usize,
+    is_open: O,
+    rng: &mut R,
+) -> Option<Vec<AxialVector>>
+where
+    O: Fn(AxialVector) -> bool,
+    R: Rng,
+{
+    let min_length = min_length.max(3);
+    if !is_open(start) {
+        return None;
+    }
+    'attempts: for _ in 0..LOOP_ATTEMPTS {
+        let mut walk = vec![start];
+        let mut walked = HexSet::new();
+        walked.insert(start);
+        loop {
+            let current = walk[walk.len() - 1];
+            if walk.len() >= 2 && walk.len() + current.distance(start) as usize > min_length {
+                break;
+            }
+            let steps = current
+                .ring_iter(1)
+                .filter(|neighbor| is_open(*neighbor) && !walked.contains(*neighbor))
+                .collect::<Vec<_>>();
+            if steps.is_empty() {
+                continue 'attempts;
+            }
+            let next = steps[gen_index(rng, steps.len())];
+            walked.insert(next);
+            walk.push(next);
+        }
+        // Straight back to the start from its first step would be stepping back.
+        let first_step = walk[1];
+        let back = cheapest_path(
+            walk[walk.len() - 1],
+            start,
+            MAX_LOOP_RATIO * min_length,
+            |from, to| {
+                if !is_open(to)
+                    || (to != start && walked.contains(to))
+                    || (to == start && from == first_step)
+                {
+                    None
+                } else {
+                    Some(1)
+                }
+            },
+        );
+        if let Some((back, _)) = back {
+            walk.extend_from_slice(&back[1..back.len() - 1]);
+            return Some(walk);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+fn backtracks(cycle: &[AxialVector]) -> bool {
+    let len = cycle.len();
+    (0..len).any(|index| cycle[(index + len - 1) % len] == cycle[(index + 1) % len])
+}
+
+// Up to `count` waypoints spread over the region, each one the hex of the region farthest from the
+// previous ones, the first one at random, chained from the first one to the closest one left, and
+// the way between them within the region. None if the region is empty or some waypoints cannot
+// reach each other within it.
+pub fn waypoint_chain<R: Rng>(region: &HexSet, count: usize, rng: &mut R) -> Option<PatrolRoute> {
+    let mut positions = region.iter().collect::<Vec<_>>();
+    if positions.is_empty() || count == 0 {
+        return None;
+    }
+    // The order of a set depends on its hasher, the seeded routes must not.
+    positions.sort();
+    let mut spread = vec![positions[gen_index(rng, positions.len())]];
+    let mut distances = positions
+        .iter()
+        .map(|position| position.distance(spread[0]))
+        .collect::<Vec<_>>();
+    while spread.len() < count.min(positions.len()) {
+        let (index, distance) = distances
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, distance)| (**distance, std::cmp::Reverse(*index)))
+            .expect("region hex");
+        if *distance == 0 {
+            break;
+        }
+        let waypoint = positions[index];
+        spread.push(waypoint);
+        for (position, distance) in positions.iter().zip(distances.iter_mut()) {
+            *distance = (*distance).min(position.distance(waypoint));
+        }
+    }
+
+    let mut waypoints = vec![spread.swap_remove(0)];
+    while !spread.is_empty() {
+        let last = waypoints[waypoints.len() - 1];
+        let (index, _) = spread
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, waypoint)| (waypoint.distance(last), **waypoint))
+            .expect("waypoint left");
+        waypoints.push(spread.remove(index));
+    }
+
+    let mut path = vec![waypoints[0]];
+    for pair in waypoints.windows(2) {
+        let (way, _) = cheapest_path(pair[0], pair[1], usize::MAX, |_, to| {
+            if region.contains(to) {
+                Some(1)
+            } else {
+                None
+            }
+        })?;
+        path.extend_from_slice(&way[1..]);
+    }
+    Some(PatrolRoute { waypoints, path })
+}
+
+#[test]
+fn test_patrol_loop() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let room = AxialVector::default().spiral_iter(4).collect::<HexSet>();
+    let mut rng = StdRng::seed_from_u64(42);
+    for min_length in [3, 6, 12, 20].iter() {
+        let start = AxialVector::new(1, -2);
+        let walk = patrol_loop(start, *min_length, |pos| room.contains(pos), &mut rng)
+            .expect("patrol loop");
+        assert_eq!(walk[0], start);
+        assert!(walk.len() >= *min_length);
+        assert!(walk.iter().all(|pos| room.contains(*pos)));
+        for (index, pos) in walk.iter().enumerate() {
+            let next = walk[(index + 1) % walk.len()];
+            assert_eq!(pos.distance(next), 1, "{:?}", walk);
+        }
+        assert!(!backtracks(&walk), "{:?}", walk);
+        assert_eq!(walk.iter().copied().collect::<HexSet>().len(), walk.len());
+    }
+
+    // The same seed, the same loop.
+    let walk = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        patrol_loop(
+            AxialVector::default(),
+            10,
+            |pos| room.contains(pos),
+            &mut rng,
+        )
+    };
+    assert_eq!(walk(7), walk(7));
+
+    // A corridor only lets the guard come back the way it went.
+    let corridor = (0..10).map(|q| AxialVector::new(q, 0)).collect::<HexSet>();
+    assert_eq!(
+        patrol_loop(
+            AxialVector::default(),
+            4,
+            |pos| corridor.contains(pos),
+            &mut rng
+        ),
+        None
+    );
+    assert_eq!(
+        patrol_loop(
+            AxialVector::new(20, 0),
+            4,
+            |pos| room.contains(pos),
+            &mut rng
+        ),
+        None
+    );
+
+    // Around a pillar.
+    let ring = AxialVector::default().ring_iter(1).collect::<HexSet>();
+    let walk = patrol_loop(
+        AxialVector::new(1, 0),
+        3,
+        |pos| ring.contains(pos),
+        &mut rng,
+    )
+    .expect("ring loop");
+    assert_eq!(walk.len(), 6);
+}
+
+#[test]
+fn test_waypoint_chain() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(42);
+    // Two rooms joined by a corridor.
+    let mut region = AxialVector::new(-6, 0).spiral_iter(2).collect::<HexSet>();
+    region.union_with(&AxialVector::new(6, 0).spiral_iter(2).collect::<HexSet>());
+    for q in -3..=3 {
+        region.insert(AxialVector::new(q, 0));
+    }
+    let route = waypoint_chain(&region, 4, &mut rng).expect("route");
+    assert_eq!(route.waypoints.len(), 4);
+    // Both rooms are visited.
+    assert!(route.waypoints.iter().any(|waypoint| waypoint.q() < -3));
+    assert!(route.waypoints.iter().any(|waypoint| waypoint.q() > 3));
+    assert_eq!(route.path[0], route.waypoints[0]);
+    assert_eq!(route.path[route.path.len() - 1], route.waypoints[3]);
+    assert!(route.path.iter().all(|pos| region.contains(*pos)));
+    assert!(route
+        .path
+        .windows(2)
+        .all(|pair| pair[0].distance(pair[1]) == 1));
+    let mut waypoints = route.waypoints.iter();
+    let mut waypoint = waypoints.next();
+    for pos in &route.path {
+        if Some(pos) == waypoint {
+            waypoint = waypoints.next();
+        }
+    }
+    assert_eq!(waypoint, None);
+
+    // No more waypoints than hexes.
+    let single = std::iter::once(AxialVector::default()).collect::<HexSet>();
+    let route = waypoint_chain(&single, 3, &mut rng).expect("route");
+    assert_eq!(route.waypoints, vec![AxialVector::default()]);
+    assert_eq!(route.path, vec![AxialVector::default()]);
+    assert_eq!(waypoint_chain(&HexSet::new(), 3, &mut rng), None);
+
+    // Rooms apart.
+    let mut apart = AxialVector::new(-6, 0).spiral_iter(1).collect::<HexSet>();
+    apart.union_with(&AxialVector::new(6, 0).spiral_iter(1).collect::<HexSet>());
+    assert_eq!(waypoint_chain(&apart, 2, &mut rng), None);
+}
+
+#[test]
+fn test_patrol_routes_in_rooms_and_mazes() {
+    use crate::{
+        graph::NodeKind,
+        rooms_and_mazes::{HexState, RoomsAndMazesWorld},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use rhombus_core::hex::shape::cubic_range::CubicRangeShape;
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut world = RoomsAndMazesWorld::new();
+    world.set_shape(CubicRangeShape::new((-20, 20), (-20, 20), (-20, 20)));
+    world.reset(|_| ());
+    world.generate(30, &mut rng);
+    let is_open = |pos| {
+        matches!(
+            world.hexes().get(pos),
+            Some((hex, _)) if matches!(hex.state(), HexState::Open(..))
+        )
+    };
+    let graph = world.graph();
+    let mut routes = 0;
+    for node in graph.nodes() {
+        let region = world.region_hexes(node.region);
+        assert_eq!(region.len(), node.size);
+        let route = waypoint_chain(&region, 3, &mut rng).expect("route");
+        assert!(route.path.iter().all(|pos| region.contains(*pos)));
+        if node.kind == NodeKind::Room && node.size >= 7 {
+            let walk = patrol_loop(node.center, 6, is_open, &mut rng).expect("room loop");
+            assert!(walk.iter().all(|pos| is_open(*pos)));
+            routes += 1;
+        }
+    }
+    assert!(routes > 0);
+}
@@ -0,0 +1,11 @@
+// The generators and their configurations, along with the core prelude. The hex states of the
+// generators share their name, they are left in their modules.
+pub use crate::{
+    cellular::{
+        Border, CellularRules, CellularWorld, RuleCursor, RuleLevel, RulePhase, RuleStep,
+        Smoothing, Symmetry,
+    },
+    graph::{DungeonGraph, NodeKind},
+    rooms_and_mazes::{ConnectivityConfig, RoomsAndMazesWorld},
+};
+pub use rhombus_core::prelude::*;
@@ -0,0 +1,1564 @@
+use crate::{
+    gen_index, gen_offset,
+    graph::{DungeonGraph, GraphNode, NodeKind},
+};
+use rand::{Rng, RngCore};
+use rhombus_core::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+        transform::HexTransform,
+        HexCoord,
+    },
+    shape::cubic_range::CubicRangeShape,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum HexState {
+    Open(usize),
+    Wall,
+}
+
+#[derive(Debug)]
+pub struct HexData {
+    state: HexState,
+}
+
+impl HexData {
+    pub fn new(state: HexState) -> Self {
+        Self { state }
+    }
+
+    pub fn state(&self) -> HexState {
+        self.state
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TemplateCell {
+    Floor,
+    Wall,
+    Door,
+}
+
+// A prefab room. Floor hexes form the room region, walls stay walls and doors, drawn next to the
+// floor, are the only hexes the room is connected through, unless none of them leads anywhere.
+#[derive(Clone, Debug)]
+pub struct RoomTemplate {
+    name: String,
+    cells: Vec<(AxialVector, TemplateCell)>,
+}
+
+impl RoomTemplate {
+    // Rows are drawn in doubled coordinates: hexes sit every other character and each row is
+    // shifted by one character, so that the text looks like the room. `.` is a floor hex, `#` a
+    // wall and `+` a door, spaces are outside the room.
+    pub fn from_rows<S: AsRef<str>>(name: &str, rows: &[S]) -> Result<Self, String> {
+        let mut cells = Vec::new();
+        for (r, row) in rows.iter().enumerate() {
+            for (c, ch) in row.as_ref().chars().enumerate() {
+                let cell = match ch {
+                    ' ' => continue,
+                    '.' => TemplateCell::Floor,
+                    '#' => TemplateCell::Wall,
+                    '+' => TemplateCell::Door,
+                    _ => {
+                        return Err(format!(
+                            "{}: unexpected character `{}` at row {}, column {}",
+                            name, ch, r, c
+                        ))
+                    }
+                };
+                if (c + r) % 2 == 1 {
+                    return Err(format!(
+                        "{}: `{}` between two hexes at row {}, column {}",
+                        name, ch, r, c
+                    ));
+                }
+                let r = r as isize;
+                cells.push((AxialVector::new((c as isize - r) / 2, r), cell));
+            }
+        }
+        if !cells.iter().any(|(_, cell)| *cell == TemplateCell::Floor) {
+            return Err(format!("{}: no floor", name));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            cells,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cells<'a>(&'a self) -> impl 'a + Iterator<Item = (AxialVector, TemplateCell)> {
+        self.cells.iter().copied()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct RoomTemplateRows {
+    pub name: String,
+    pub rows: Vec<String>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct RoomTemplateLibrary {
+    // Probability for a new room to be picked from the templates.
+    pub ratio: f64,
+    pub templates: Vec<RoomTemplateRows>,
+}
+
+impl RoomTemplateLibrary {
+    pub fn build(&self) -> Result<Vec<RoomTemplate>, String> {
+        if !(0.0..=1.0).contains(&self.ratio) {
+            return Err(format!("ratio {} is not a probability", self.ratio));
+        }
+        self.templates
+            .iter()
+            .map(|template| RoomTemplate::from_rows(&template.name, &template.rows))
+            .collect()
+    }
+}
+
+impl Default for RoomTemplateLibrary {
+    fn default() -> Self {
+        Self {
+            ratio: 0.0,
+            templates: Vec::new(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[non_exhaustive]
+pub struct ConnectivityConfig {
+    // Probability for every other connector between two regions which just got connected to be
+    // opened as well, making loops.
+    pub loop_chance: f64,
+    // Connect the regions with the closest centers first instead of picking connectors uniformly.
+    pub prefer_shortest: bool,
+    pub max_extra_connections: Option<usize>,
+}
+
+impl ConnectivityConfig {
+    // Every region is reached once, the closest first, the dungeon has no loop.
+    pub fn tree() -> Self {
+        Self {
+            loop_chance: 0.0,
+            prefer_shortest: true,
+            max_extra_connections: Some(0),
+        }
+    }
+
+    pub fn loopy() -> Self {
+        Self {
+            loop_chance: 0.2,
+            prefer_shortest: false,
+            max_extra_connections: None,
+        }
+    }
+
+    pub fn with_loop_chance(mut self, loop_chance: f64) -> Self {
+        self.loop_chance = loop_chance;
+        self
+    }
+
+    pub fn with_prefer_shortest(mut self, prefer_shortest: bool) -> Self {
+        self.prefer_shortest = prefer_shortest;
+        self
+    }
+
+    pub fn with_max_extra_connections(mut self, max_extra_connections: Option<usize>) -> Self {
+        self.max_extra_connections = max_extra_connections;
+        self
+    }
+}
+
+impl Default for ConnectivityConfig {
+    fn default() -> Self {
+        Self {
+            loop_chance: 0.02,
+            prefer_shortest: false,
+            max_extra_connections: None,
+        }
+    }
+}
+
+type ShapeOperation = fn(&mut CubicRangeShape, usize) -> bool;
+
+// How big the rooms are and where they are tried, so that the character of a dungeon can vary
+// across it (e.g. bigger rooms near the center). A room is sampled around the origin, along with
+// the position it is moved to, and dropped if it does not fit there.
+pub trait RoomDistribution {
+    fn sample(
+        &self,
+        shape: &CubicRangeShape,
+        rng: &mut dyn RngCore,
+    ) -> (CubicRangeShape, AxialVector);
+}
+
+// A tenth of the world size anywhere, give or take a third on every side.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UniformRooms;
+
+impl RoomDistribution for UniformRooms {
+    fn sample(
+        &self,
+        shape: &CubicRangeShape,
+        rng: &mut dyn RngCore,
+    ) -> (CubicRangeShape, AxialVector) {
+        let mut deltas = [
+            shape.range_x().end() - shape.range_x().start(),
+            shape.range_y().end() - shape.range_y().start(),
+            shape.range_z().end() - shape.range_z().start(),
+        ];
+        deltas.sort();
+        let room = jittered_room(deltas[1] / 10, rng);
+        (room, random_shape_position(shape, rng))
+    }
+}
+
+// The radius of a room given by a function of its position, give or take a third on every side.
+pub struct RoomRadiusByPosition<F>(pub F);
+
+impl<F> RoomDistribution for RoomRadiusByPosition<F>
+where
+    F: Fn(AxialVector) -> isize,
+{
+    fn sample(
+        &self,
+        shape: &CubicRangeShape,
+        rng: &mut dyn RngCore,
+    ) -> (CubicRangeShape, AxialVector) {
+        let position = random_shape_position(shape, rng);
+        (jittered_room((self.0)(position).max(0), rng), position)
+    }
+}
+
+fn jittered_room(radius: isize, rng: &mut dyn RngCore) -> CubicRangeShape {
+    let mut room = CubicRangeShape::new((-radius, radius), (-radius, radius), (-radius, radius));
+    let funcs: [(ShapeOperation, ShapeOperation); 6] = [
+        (
+            CubicRangeShape::shrink_x_start,
+            CubicRangeShape::stretch_x_start,
+        ),
+        (
+            CubicRangeShape::shrink_x_end,
+            CubicRangeShape::stretch_x_end,
+        ),
+        (
+            CubicRangeShape::shrink_y_start,
+            CubicRangeShape::stretch_y_start,
+        ),
+        (
+            CubicRangeShape::shrink_y_end,
+            CubicRangeShape::stretch_y_end,
+        ),
+        (
+            CubicRangeShape::shrink_z_start,
+            CubicRangeShape::stretch_z_start,
+        ),
+        (
+            CubicRangeShape::shrink_z_end,
+            CubicRangeShape::stretch_z_end,
+        ),
+    ];
+    for (st, sh) in funcs.iter() {
+        let d = gen_offset(rng, -radius / 3, radius / 3 + 1);
+        for _ in 0..d.abs() {
+            if d > 0 {
+                st(&mut room, 2);
+            } else if d < 0 {
+                sh(&mut room, 2);
+            }
+        }
+    }
+    room
+}
+
+fn random_shape_position<R: Rng + ?Sized>(shape: &CubicRangeShape, rng: &mut R) -> AxialVector {
+    shape
+        .iter_positions()
+        .nth(gen_index(rng, shape.len()))
+        .expect("shape position")
+}
+
+// The payload H is attached to every hex and lets the user of the generator keep its own data
+// (e.g. rendering entities) in sync with the generated hexes.
+pub struct RoomsAndMazesWorld<H> {
+    shape: CubicRangeShape,
+    hexes: RectHashStorage<(HexData, H)>,
+    rooms: Vec<CubicRangeShape>,
+    next_region: usize,
+    room_templates: Vec<RoomTemplate>,
+    room_template_ratio: f64,
+    room_distribution: Box<dyn RoomDistribution + Send + Sync>,
+    // Template hexes and the walls around them, the maze does not go through them.
+    reserved: HashSet<AxialVector>,
+    doors: HashSet<AxialVector>,
+    template_regions: HashSet<usize>,
+    connectivity: ConnectivityConfig,
+    // Opened connectors, they do not belong to any region.
+    connections: HashSet<AxialVector>,
+}
+
+impl<H> RoomsAndMazesWorld<H> {
+    pub fn new() -> Self {
+        Self {
+            shape: CubicRangeShape::default(),
+            hexes: RectHashStorage::new(),
+            rooms: Vec::new(),
+            next_region: 0,
+            room_templates: Vec::new(),
+            room_template_ratio: 0.0,
+            room_distribution: Box::new(UniformRooms),
+            reserved: HashSet::new(),
+            doors: HashSet::new(),
+            template_regions: HashSet::new(),
+            connectivity: ConnectivityConfig::default(),
+            connections: HashSet::new(),
+        }
+    }
+
+    pub fn shape(&self) -> &CubicRangeShape {
+        &self.shape
+    }
+
+    pub fn hexes(&self) -> &RectHashStorage<(HexData, H)> {
+        &self.hexes
+    }
+
+    pub fn hexes_mut(&mut self) -> &mut RectHashStorage<(HexData, H)> {
+        &mut self.hexes
+    }
+
+    // Kept across resets. `ratio` is the probability for add_room to place one of the templates.
+    pub fn set_room_templates(&mut self, templates: Vec<RoomTemplate>, ratio: f64) {
+        self.room_templates = templates;
+        self.room_template_ratio = ratio;
+    }
+
+    // Kept across resets, the template rooms are placed uniformly whatever the distribution.
+    pub fn set_room_distribution<D>(&mut self, distribution: D)
+    where
+        D: 'static + RoomDistribution + Send + Sync,
+    {
+        self.room_distribution = Box::new(distribution);
+    }
+
+    pub fn connectivity(&self) -> ConnectivityConfig {
+        self.connectivity
+    }
+
+    // Kept across resets.
+    pub fn set_connectivity(&mut self, connectivity: ConnectivityConfig) {
+        assert!(
+            (0.0..=1.0).contains(&connectivity.loop_chance),
+            "loop chance {} is not a probability",
+            connectivity.loop_chance
+        );
+        self.connectivity = connectivity;
+    }
+
+    pub fn set_shape(&mut self, shape: CubicRangeShape) {
+        self.shape = shape;
+    }
+
+    // Existing hexes are dropped, callers owning resources in the payloads must release them
+    // before resetting the world.
+    pub fn reset<P>(&mut self, mut new_payload: P)
+    where
+        P: FnMut(HexState) -> H,
+    {
+        self.hexes.clear_and_shrink();
+        self.rooms.clear();
+        self.next_region = 0;
+        self.reserved.clear();
+        self.doors.clear();
+        self.template_regions.clear();
+        self.connections.clear();
+        for v in self.shape.iter_positions() {
+            self.hexes.insert(
+                v,
+                (HexData::new(HexState::Wall), new_payload(HexState::Wall)),
+            );
+        }
+    }
+
+    pub fn add_room<R: Rng>(&mut self, rng: &mut R) -> bool {
+        let _span = rhombus_core::timed_span!("add_room", rooms = self.rooms.len());
+        if !self.room_templates.is_empty() && rng.gen_bool(self.room_template_ratio) {
+            return self.add_template_room(rng);
+        }
+        let (new_room, position) = self.room_distribution.sample(&self.shape, rng);
+
+        let mut start_x = new_room.range_x().start() + position.x();
+        let delta_x = (start_x - self.shape.range_x().start() + 1) % 2;
+        start_x += delta_x;
+        let end_x = new_room.range_x().end() + position.x() + delta_x;
+
+        let mut start_z = new_room.range_z().start() + position.z();
+        let delta_z = (start_z - self.shape.range_z().start() + 1) % 2;
+        start_z += delta_z;
+        let end_z = new_room.range_z().end() + position.z() + delta_z;
+
+        let start_y = new_room.range_y().start() + position.y() - delta_x - delta_z;
+        let end_y = new_room.range_y().end() + position.y() - delta_x - delta_z;
+
+        let is_inside_shape = self.shape.range_x().start() < start_x
+            && self.shape.range_x().end() > end_x
+            && self.shape.range_y().start() < start_y
+            && self.shape.range_y().end() > end_y
+            && self.shape.range_z().start() < start_z
+            && self.shape.range_z().end() > end_z;
+        let new_room = CubicRangeShape::new((start_x, end_x), (start_y, end_y), (start_z, end_z));
+
+        if is_inside_shape && !self.rooms.iter().any(|room| room.intersects(&new_room)) {
+            for (_, (hex_data, _)) in self.hexes.view_mut(&new_room).iter_mut() {
+                hex_data.state = HexState::Open(self.next_region);
+            }
+
+            self.rooms.push(new_room);
+
+            self.next_region += 1;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn random_shape_position<R: Rng>(&self, rng: &mut R) -> AxialVector {
+        random_shape_position(&self.shape, rng)
+    }
+
+    fn add_template_room<R: Rng>(&mut self, rng: &mut R) -> bool {
+        let template = &self.room_templates[gen_index(rng, self.room_templates.len())];
+        let rotation = gen_index(rng, NUM_DIRECTIONS);
+        let origin = self.random_shape_position(rng);
+        let cells = template
+            .cells()
+            .map(|(pos, cell)| (origin + pos.rotate(rotation), cell))
+            .collect::<Vec<_>>();
+        // The floor is surrounded with walls, the doors must stay reachable from the outside.
+        let mut reserved = cells.iter().map(|(pos, _)| *pos).collect::<HashSet<_>>();
+        for (pos, _) in cells
+            .iter()
+            .filter(|(_, cell)| *cell == TemplateCell::Floor)
+        {
+            reserved.extend((0..NUM_DIRECTIONS).map(|dir| pos.neighbor(dir)));
+        }
+
+        let range = |coord: fn(&AxialVector) -> isize| {
+            (
+                reserved.iter().map(coord).min().expect("template cell"),
+                reserved.iter().map(coord).max().expect("template cell"),
+            )
+        };
+        let (start_x, end_x) = range(AxialVector::x);
+        let (start_y, end_y) = range(AxialVector::y);
+        let (start_z, end_z) = range(AxialVector::z);
+        let is_inside_shape = self.shape.range_x().start() < start_x
+            && self.shape.range_x().end() > end_x
+            && self.shape.range_y().start() < start_y
+            && self.shape.range_y().end() > end_y
+            && self.shape.range_z().start() < start_z
+            && self.shape.range_z().end() > end_z;
+        let new_room = CubicRangeShape::new((start_x, end_x), (start_y, end_y), (start_z, end_z));
+
+        if is_inside_shape
+            && !self.rooms.iter().any(|room| room.intersects(&new_room))
+            && reserved.iter().all(|pos| !self.reserved.contains(pos))
+        {
+            for (pos, cell) in cells {
+                match cell {
+                    TemplateCell::Floor => {
+                        self.hexes.get_mut(pos).expect("new room cell").0.state =
+                            HexState::Open(self.next_region);
+                    }
+                    TemplateCell::Wall => {}
+                    TemplateCell::Door => {
+                        self.doors.insert(pos);
+                    }
+                }
+            }
+            self.reserved.extend(reserved);
+
+            self.rooms.push(new_room);
+
+            self.template_regions.insert(self.next_region);
+            self.next_region += 1;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn start_maze(&self) -> MazeState {
+        MazeState {
+            next_pos: 0,
+            cells: Vec::new(),
+            region: 0,
+        }
+    }
+
+    pub fn grow_maze<R: Rng>(&mut self, state: &mut MazeState, rng: &mut R) -> bool {
+        let _span = rhombus_core::timed_span!("grow_maze", region = state.region);
+        loop {
+            if state.cells.is_empty() {
+                let start = (state.next_pos..)
+                    .zip(self.shape.iter_positions().skip(state.next_pos))
+                    .find(|(_, cell)| self.can_carve(*cell));
+                if let Some((pos, cell)) = start {
+                    state.next_pos = pos + 1;
+                    state.cells.push((cell, None));
+                    state.region = self.next_region;
+                    self.next_region += 1;
+                } else {
+                    return true;
+                }
+            }
+            if let Some((cell, via)) = state.cells.pop() {
+                if self.can_carve(cell) {
+                    if let Some((via, _)) = via {
+                        self.hexes.get_mut(via).expect("via cell").0.state =
+                            HexState::Open(state.region);
+                    }
+                    self.hexes.get_mut(cell).expect("carve cell").0.state =
+                        HexState::Open(state.region);
+                    let mut directions = Vec::new();
+                    let mut wind_d = None;
+                    for dir in 0..NUM_DIRECTIONS {
+                        let neighbour = cell + AxialVector::direction(dir) * 2;
+                        if self.can_carve(neighbour)
+                            && !self
+                                .reserved
+                                .contains(&(cell + AxialVector::direction(dir)))
+                        {
+                            if let Some((_, wind_dir)) = via {
+                                if wind_dir == dir {
+                                    wind_d = Some(directions.len())
+                                }
+                            }
+                            directions.push(dir);
+                        }
+                    }
+                    if let (Some(wind_d), Some((_, wind_dir))) = (wind_d, via) {
+                        debug_assert_eq!(directions[wind_d], wind_dir);
+                    }
+                    if !directions.is_empty() {
+                        let d = wind_d
+                            .and_then(|d| {
+                                let windy = rng.gen_bool(0.6);
+                                if windy {
+                                    Some(d)
+                                } else {
+                                    None
+                                }
+                            })
+                            .unwrap_or_else(|| gen_index(rng, directions.len()));
+                        let dir = directions[d];
+                        for (i, dir) in directions.into_iter().enumerate() {
+                            if i != d {
+                                let via = cell + AxialVector::direction(dir);
+                                let neighbour = cell + AxialVector::direction(dir) * 2;
+                                state.cells.push((neighbour, Some((via, dir))));
+                            }
+                        }
+                        let via = cell + AxialVector::direction(dir);
+                        let neighbour = cell + AxialVector::direction(dir) * 2;
+                        state.cells.push((neighbour, Some((via, dir))));
+                    }
+                    return false;
+                }
+            } else {
+                break;
+            }
+        }
+        true
+    }
+
+    fn can_carve(&self, position: AxialVector) -> bool {
+        self.shape.contains_inside(position)
+            && !self.reserved.contains(&position)
+            && ((position.x() - self.shape.range_x().start()) % 2 == 1)
+            && ((position.z() - self.shape.range_z().start()) % 2 == 1)
+            && matches!(
+                self.hexes.get(position),
+                Some((
+                    HexData {
+                        state: HexState::Wall
+                    },
+                    _
+                ))
+            )
+    }
+
+    pub fn start_connect<R: Rng>(&self, rng: &mut R) -> ConnectState {
+        let _span = rhombus_core::timed_span!("start_connect", regions = self.next_region);
+        if self.next_region <= 1 {
+            return ConnectState {
+                connectors: Vec::new(),
+                regions_to_connect: HashSet::new(),
+                extra_connections: 0,
+            };
+        }
+        let mut connectors = self
+            .hexes
+            .positions_and_hexes_with_adjacents()
+            .filter_map(|(pos, hex_with_adjacents)| {
+                if hex_with_adjacents.hex().0.state != HexState::Wall {
+                    return None;
+                }
+                let mut regions: SmallVec<[usize; 3]> = (0..NUM_DIRECTIONS)
+                    .filter_map(|dir| {
+                        hex_with_adjacents
+                            .adjacent(dir)
+                            .and_then(|(data, _)| match data.state {
+                                HexState::Open(region) => Some(region),
+                                HexState::Wall => None,
+                            })
+                    })
+                    .collect();
+                regions.sort();
+                regions.dedup();
+                debug_assert!(regions.len() <= 3);
+                if regions.len() > 1 {
+                    Some(Connector {
+                        position: pos,
+                        regions,
+                        length: 0,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        // Template rooms are only connected through their doors, as long as one of them leads
+        // somewhere.
+        let door_regions = connectors
+            .iter()
+            .filter(|connector| self.doors.contains(&connector.position))
+            .flat_map(|connector| connector.regions.iter().copied())
+            .filter(|region| self.template_regions.contains(region))
+            .collect::<HashSet<_>>();
+        connectors.retain(|connector| {
+            self.doors.contains(&connector.position)
+                || !connector
+                    .regions
+                    .iter()
+                    .any(|region| door_regions.contains(region))
+        });
+        // The storage iteration order depends on the hasher and on the platform, the connectors
+        // are picked at random and must come in a reproducible order.
+        connectors.sort_by_key(|connector| connector.position);
+        let first_region = gen_index(rng, self.next_region);
+        let regions_to_connect = (0..self.next_region)
+            .filter(|region| *region != first_region)
+            .collect();
+        if self.connectivity.prefer_shortest {
+            let centers = self.region_centers();
+            let centers = &centers;
+            for connector in &mut connectors {
+                connector.length = connector
+                    .regions
+                    .iter()
+                    .flat_map(|r1| {
+                        connector
+                            .regions
+                            .iter()
+                            .map(move |r2| centers[r1].distance(centers[r2]))
+                    })
+                    .max()
+                    .unwrap_or(0);
+            }
+        }
+        ConnectState {
+            connectors,
+            regions_to_connect,
+            extra_connections: 0,
+        }
+    }
+
+    fn region_centers(&self) -> HashMap<usize, AxialVector> {
+        let mut sums = HashMap::<usize, (AxialVector, isize)>::new();
+        for (pos, (hex_data, _)) in self.hexes.iter() {
+            if let HexState::Open(region) = hex_data.state {
+                let sum = sums.entry(region).or_default();
+                sum.0 += pos;
+                sum.1 += 1;
+            }
+        }
+        sums.into_iter()
+            .map(|(region, (sum, count))| {
+                (
+                    region,
+                    AxialVector::new(sum.q().div_euclid(count), sum.r().div_euclid(count)),
+                )
+            })
+            .collect()
+    }
+
+    pub fn connect<R: Rng>(&mut self, state: &mut ConnectState, rng: &mut R) -> bool {
+        let _span = rhombus_core::timed_span!(
+            "connect",
+            connectors = state.connectors.len(),
+            regions_to_connect = state.regions_to_connect.len()
+        );
+        if state.regions_to_connect.is_empty() {
+            return true;
+        }
+        let indices = state
+            .connectors
+            .iter()
+            .enumerate()
+            .filter_map(|(index, connector)| {
+                let one_in = connector
+                    .regions
+                    .iter()
+                    .any(|cr| !state.regions_to_connect.contains(cr));
+                let one_out = connector
+                    .regions
+                    .iter()
+                    .any(|cr| state.regions_to_connect.contains(cr));
+                if one_in && one_out {
+                    Some(index)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<usize>>();
+        let indices = if self.connectivity.prefer_shortest {
+            let min_length = indices
+                .iter()
+                .map(|index| state.connectors[*index].length)
+                .min();
+            indices
+                .into_iter()
+                .filter(|index| Some(state.connectors[*index].length) == min_length)
+                .collect()
+        } else {
+            indices
+        };
+        // Template walls may leave a room out of reach, there is nothing left to connect then.
+        if indices.is_empty() {
+            return true;
+        }
+
+        let connector = &state.connectors[indices[gen_index(rng, indices.len())]];
+
+        self.hexes
+            .get_mut(connector.position)
+            .expect("connector cell")
+            .0
+            .state = HexState::Open(0);
+        self.connections.insert(connector.position);
+        for r in &connector.regions {
+            state.regions_to_connect.remove(r);
+        }
+        let connected_regions = connector.regions.clone();
+        let mut connectors = Vec::new();
+        std::mem::swap(&mut state.connectors, &mut connectors);
+        let (drained, remaining) = connectors.into_iter().partition(|connector| {
+            connector
+                .regions
+                .iter()
+                .filter(|r1| connected_regions.iter().any(|r2| *r1 == r2))
+                .count()
+                >= 2
+        });
+        state.connectors = remaining;
+        for connector in drained {
+            let carve = !matches!(
+                self.connectivity.max_extra_connections,
+                Some(max) if state.extra_connections >= max
+            ) && rng.gen_bool(self.connectivity.loop_chance);
+            if carve {
+                state.extra_connections += 1;
+                self.connections.insert(connector.position);
+                self.hexes
+                    .get_mut(connector.position)
+                    .expect("connector cell")
+                    .0
+                    .state = HexState::Open(0);
+            }
+        }
+
+        false
+    }
+
+    pub fn start_remove_dead_ends(&self) -> RemoveDeadEndsState {
+        RemoveDeadEndsState {
+            tests: self.cell_positions(),
+            next: 0,
+            redo_tests: Vec::new(),
+        }
+    }
+
+    // Sorted so that the outcome does not depend on the storage iteration order.
+    fn cell_positions(&self) -> Vec<AxialVector> {
+        let mut positions = self
+            .hexes
+            .positions()
+            .filter(|pos| {
+                ((pos.x() - self.shape.range_x().start()) % 2 == 1)
+                    && ((pos.z() - self.shape.range_z().start()) % 2 == 1)
+            })
+            .collect::<Vec<_>>();
+        positions.sort();
+        positions
+    }
+
+    pub fn remove_dead_ends(&mut self, state: &mut RemoveDeadEndsState) -> bool {
+        let _span = rhombus_core::timed_span!("remove_dead_ends", next = state.next);
+        loop {
+            while state.next < state.tests.len() {
+                let pos = state.tests[state.next];
+                state.next += 1;
+                // Template rooms keep their shape.
+                if self.reserved.contains(&pos) {
+                    continue;
+                }
+                let hex = self.hexes.get(pos);
+                if let Some((
+                    HexData {
+                        state: HexState::Open(..),
+                    },
+                    _,
+                )) = hex
+                {
+                } else {
+                    continue;
+                }
+                let mut redo = SmallVec::<[usize; NUM_DIRECTIONS]>::new();
+                for dir in 0..NUM_DIRECTIONS {
+                    let via = self.hexes.get(pos + AxialVector::direction(dir));
+                    let adj = self.hexes.get(pos + AxialVector::direction(dir) * 2);
+                    if let (
+                        Some((
+                            HexData {
+                                state: HexState::Open(..),
+                            },
+                            _,
+                        )),
+                        Some((
+                            HexData {
+                                state: HexState::Open(..),
+                            },
+                            _,
+                        )),
+                    ) = (via, adj)
+                    {
+                        redo.push(dir);
+                    }
+                }
+                if redo.len() <= 1 {
+                    state.redo_tests.extend(
+                        redo.into_iter()
+                            .map(|dir| pos + AxialVector::direction(dir) * 2),
+                    );
+                    let mut haa = self.hexes.hex_with_adjacents_mut(pos);
+                    haa.hex().as_mut().expect("dead end cell").0.state = HexState::Wall;
+                    for dir in 0..NUM_DIRECTIONS {
+                        if let Some(adj) = haa.adjacent(dir) {
+                            if let HexState::Open(..) = adj.0.state {
+                                adj.0.state = HexState::Wall;
+                            }
+                        };
+                    }
+                    return false;
+                }
+            }
+            if !state.redo_tests.is_empty() {
+                std::mem::swap(&mut state.tests, &mut state.redo_tests);
+                state.redo_tests.clear();
+                state.next = 0;
+            } else {
+                break;
+            }
+        }
+        true
+    }
+
+    pub fn start_remove_angles(&self) -> RemoveAnglesState {
+        RemoveAnglesState {
+            tests: self.cell_positions(),
+            next: 0,
+            redo_tests: Vec::new(),
+        }
+    }
+
+    pub fn remove_angles(&mut self, state: &mut RemoveAnglesState) -> bool {
+        let _span = rhombus_core::timed_span!("remove_angles", next = state.next);
+        loop {
+            while state.next < state.tests.len() {
+                let pos = state.tests[state.next];
+                state.next += 1;
+                if self.reserved.contains(&pos) {
+                    continue;
+                }
+                let hex = self.hexes.get(pos);
+                if let Some((
+                    HexData {
+                        state: HexState::Open(..),
+                    },
+                    _,
+                )) = hex
+                {
+                } else {
+                    continue;
+                }
+                let mut redo = SmallVec::<[usize; NUM_DIRECTIONS]>::new();
+                for dir in 0..NUM_DIRECTIONS {
+                    let adj = self.hexes.get(pos + AxialVector::direction(dir));
+                    if let Some((
+                        HexData {
+                            state: HexState::Open(..),
+                        },
+                        _,
+                    )) = adj
+                    {
+                        redo.push(dir);
+                    }
+                }
+                if redo.len() == 2 && (redo[0] + 1 == redo[1] || redo[0] == 0 && redo[1] == 5) {
+                    let hex = self.hexes.get_mut(pos);
+                    hex.expect("angle cell").0.state = HexState::Wall;
+                }
+            }
+            if !state.redo_tests.is_empty() {
+                std::mem::swap(&mut state.tests, &mut state.redo_tests);
+                state.redo_tests.clear();
+                state.next = 0;
+            } else {
+                break;
+            }
+        }
+        true
+    }
+
+    // Walls which are not adjacent to any open hex are removed and given back to the caller.
+    pub fn clean_walls<F>(&mut self, mut removed: F) -> bool
+    where
+        F: FnMut((HexData, H)),
+    {
+        let _span = rhombus_core::timed_span!("clean_walls", hexes = self.hexes.len());
+        let mut remove = Vec::new();
+        for (pos, haa) in self.hexes.positions_and_hexes_with_adjacents() {
+            let mut keep = false;
+            for dir in 0..NUM_DIRECTIONS {
+                if let Some((
+                    HexData {
+                        state: HexState::Open(..),
+                    },
+                    _,
+                )) = haa.adjacent(dir)
+                {
+                    keep = true;
+                    break;
+                }
+            }
+            if !keep {
+                remove.push(pos);
+            }
+        }
+        for pos in &remove {
+            if let Some(hex) = self.hexes.remove(*pos) {
+                removed(hex);
+            }
+        }
+        !remove.is_empty()
+    }
+
+    // All the phases at once after a reset, for the callers which do not show them step by step.
+    pub fn generate<R: Rng>(&mut self, room_attempts: usize, rng: &mut R) {
+        for _ in 0..room_attempts {
+            self.add_room(rng);
+        }
+        let mut maze = self.start_maze();
+        while !self.grow_maze(&mut maze, rng) {}
+        let mut connect = self.start_connect(rng);
+        while !self.connect(&mut connect, rng) {}
+        let mut dead_ends = self.start_remove_dead_ends();
+        while !self.remove_dead_ends(&mut dead_ends) {}
+        let mut angles = self.start_remove_angles();
+        while !self.remove_angles(&mut angles) {}
+        self.clean_walls(|_| ());
+    }
+
+    // Turns or mirrors the whole dungeon around the center of its shape, the rooms, doors and
+    // connections with it, so that the same seed can be played in 12 orientations.
+    pub fn transform(&mut self, transform: HexTransform) {
+        let center = self.shape.center();
+        let transform_set = |set: &mut HashSet<AxialVector>| {
+            *set = set
+                .drain()
+                .map(|position| transform.apply_around(position, center))
+                .collect();
+        };
+        transform_set(&mut self.reserved);
+        transform_set(&mut self.doors);
+        transform_set(&mut self.connections);
+        for room in &mut self.rooms {
+            room.transform(transform, center);
+        }
+        self.hexes.transform(transform, center);
+        self.shape.transform(transform, center);
+    }
+
+    // Rooms and corridors (the regions left open) and how they are linked, through the opened
+    // connectors or wherever two regions touch.
+    pub fn graph(&self) -> DungeonGraph {
+        let _span = rhombus_core::timed_span!("graph", regions = self.next_region);
+        let region_at = |pos| match self.hexes.get(pos) {
+            Some((
+                HexData {
+                    state: HexState::Open(region),
+                },
+                _,
+            )) if !self.connections.contains(&pos) => Some(*region),
+            _ => None,
+        };
+
+        let mut regions = BTreeMap::<usize, Vec<AxialVector>>::new();
+        for pos in self.hexes.positions() {
+            if let Some(region) = region_at(pos) {
+                regions.entry(region).or_default().push(pos);
+            }
+        }
+        let mut graph = DungeonGraph::new();
+        let mut nodes = HashMap::new();
+        for (region, mut positions) in regions {
+            positions.sort();
+            let count = positions.len() as isize;
+            let sum = positions
+                .iter()
+                .fold(AxialVector::default(), |sum, pos| sum + *pos);
+            let middle = AxialVector::new(sum.q().div_euclid(count), sum.r().div_euclid(count));
+            let center = *positions
+                .iter()
+                .min_by_key(|pos| (pos.distance(middle), **pos))
+                .expect("region hex");
+            let node = graph.add_node(GraphNode {
+                region,
+                // The rooms are all added before the maze is grown.
+                kind: if region < self.rooms.len() {
+                    NodeKind::Room
+                } else {
+                    NodeKind::Corridor
+                },
+                center,
+                size: positions.len(),
+            });
+            nodes.insert(region, node);
+        }
+
+        let mut edges = BTreeMap::<(usize, usize), AxialVector>::new();
+        let mut add_edge = |r1: usize, r2: usize, pos: AxialVector| {
+            let key = (r1.min(r2), r1.max(r2));
+            let position = edges.entry(key).or_insert(pos);
+            *position = (*position).min(pos);
+        };
+        for pos in self.hexes.positions() {
+            if self.connections.contains(&pos) {
+                if matches!(
+                    self.hexes.get(pos),
+                    Some((
+                        HexData {
+                            state: HexState::Open(..)
+                        },
+                        _
+                    ))
+                ) {
+                    let mut adjacent: SmallVec<[usize; NUM_DIRECTIONS]> = (0..NUM_DIRECTIONS)
+                        .filter_map(|dir| region_at(pos.neighbor(dir)))
+                        .collect();
+                    adjacent.sort();
+                    adjacent.dedup();
+                    for (i, r1) in adjacent.iter().enumerate() {
+                        for r2 in &adjacent[i + 1..] {
+                            add_edge(*r1, *r2, pos);
+                        }
+                    }
+                }
+            } else if let Some(r1) = region_at(pos) {
+                for dir in 0..NUM_DIRECTIONS {
+                    match region_at(pos.neighbor(dir)) {
+                        Some(r2) if r2 > r1 => add_edge(r1, r2, pos),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        for ((r1, r2), position) in edges {
+            graph.add_edge(nodes[&r1], nodes[&r2], position);
+        }
+        graph
+    }
+
+    // The open hexes of a region, as numbered in the graph nodes, without the opened connectors.
+    pub fn region_hexes(&self, region: usize) -> HexSet {
+        self.hexes
+            .iter()
+            .filter(|(pos, (hex_data, _))| {
+                hex_data.state == HexState::Open(region) && !self.connections.contains(pos)
+            })
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    pub fn find_open_hex(&self) -> Option<AxialVector> {
+        let mut r = 0;
+        loop {
+            let mut end = true;
+            for pos in self.shape.center().ring_iter(r) {
+                let hex_data = self.hexes.get(pos).map(|hex| &hex.0);
+                match hex_data {
+                    Some(HexData {
+                        state: HexState::Open(..),
+                        ..
+                    }) => return Some(pos),
+                    Some(..) => end = false,
+                    None => {
+                        if self.shape.contains_position(pos) {
+                            end = false
+                        }
+                    }
+                }
+            }
+            if end {
+                return None;
+            }
+            r += 1;
+        }
+    }
+
+    pub fn random_open_hex<R: Rng>(&self, rng: &mut R) -> Option<AxialVector> {
+        self.hexes
+            .random_open_position(rng, |_, hex| matches!(hex.0.state, HexState::Open(..)))
+    }
+}
+
+impl<H> Default for RoomsAndMazesWorld<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct MazeState {
+    next_pos: usize,
+    cells: Vec<(AxialVector, Option<(AxialVector, usize)>)>,
+    region: usize,
+}
+
+#[derive(Debug)]
+pub struct Connector {
+    position: AxialVector,
+    regions: SmallVec<[usize; 3]>,
+    // Distance between the centers of the regions, only computed when the shortest are preferred.
+    length: isize,
+}
+
+impl Connector {
+    pub fn position(&self) -> AxialVector {
+        self.position
+    }
+
+    pub fn regions<'a>(&'a self) -> impl 'a + Iterator<Item = usize> {
+        self.regions.iter().copied()
+    }
+}
+
+#[derive(Debug)]
+pub struct ConnectState {
+    connectors: Vec<Connector>,
+    regions_to_connect: HashSet<usize>,
+    extra_connections: usize,
+}
+
+impl ConnectState {
+    pub fn connectors(&self) -> impl Iterator<Item = &Connector> {
+        self.connectors.iter()
+    }
+
+    pub fn regions_to_connect<'a>(&'a self) -> impl 'a + Iterator<Item = usize> {
+        self.regions_to_connect.iter().copied()
+    }
+
+    pub fn extra_connections(&self) -> usize {
+        self.extra_connections
+    }
+}
+
+#[derive(Debug)]
+pub struct RemoveDeadEndsState {
+    tests: Vec<AxialVector>,
+    next: usize,
+    redo_tests: Vec<AxialVector>,
+}
+
+#[derive(Debug)]
+pub struct RemoveAnglesState {
+    tests: Vec<AxialVector>,
+    next: usize,
+    redo_tests: Vec<AxialVector>,
+}
+
+#[test]
+fn test_rooms_and_mazes_world_should_be_connected() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut world = RoomsAndMazesWorld::new();
+    world.set_shape(CubicRangeShape::new((-30, 30), (-30, 30), (-30, 30)));
+    world.reset(|_| ());
+    for _ in 0..50 {
+        world.add_room(&mut rng);
+    }
+    let mut maze = world.start_maze();
+    while !world.grow_maze(&mut maze, &mut rng) {}
+    let mut connect = world.start_connect(&mut rng);
+    while !world.connect(&mut connect, &mut rng) {}
+    let mut dead_ends = world.start_remove_dead_ends();
+    while !world.remove_dead_ends(&mut dead_ends) {}
+    let mut angles = world.start_remove_angles();
+    while !world.remove_angles(&mut angles) {}
+    world.clean_walls(|_| ());
+
+    let is_open = |pos| match world.hexes().get(pos) {
+        Some((hex_data, _)) => matches!(hex_data.state(), HexState::Open(..)),
+        None => false,
+    };
+    let open_positions = world
+        .hexes()
+        .positions()
+        .filter(|pos| is_open(*pos))
+        .collect::<HashSet<_>>();
+    assert!(!open_positions.is_empty());
+    let start = world.random_open_hex(&mut rng).expect("open hex");
+    let mut reached = HashSet::new();
+    reached.insert(start);
+    let mut to_visit = vec![start];
+    while let Some(pos) = to_visit.pop() {
+        for dir in 0..NUM_DIRECTIONS {
+            let neighbor = pos.neighbor(dir);
+            if is_open(neighbor) && reached.insert(neighbor) {
+                to_visit.push(neighbor);
+            }
+        }
+    }
+    assert_eq!(reached, open_positions);
+}
+
+#[test]
+fn test_rooms_and_mazes_world_connectivity() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let connect = |connectivity| {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut world = RoomsAndMazesWorld::new();
+        world.set_shape(CubicRangeShape::new((-30, 30), (-30, 30), (-30, 30)));
+        world.set_connectivity(connectivity);
+        world.reset(|_| ());
+        for _ in 0..50 {
+            world.add_room(&mut rng);
+        }
+        let mut maze = world.start_maze();
+        while !world.grow_maze(&mut maze, &mut rng) {}
+        let mut connect = world.start_connect(&mut rng);
+        let mut connections = 0;
+        while !world.connect(&mut connect, &mut rng) {
+            connections += 1;
+        }
+        assert!(connections < world.next_region);
+        connect.extra_connections()
+    };
+    assert_eq!(connect(ConnectivityConfig::tree()), 0);
+    let loopy = connect(ConnectivityConfig::loopy());
+    assert!(loopy > connect(ConnectivityConfig::default()));
+    assert_eq!(
+        connect(ConnectivityConfig::loopy().with_max_extra_connections(Some(3))),
+        3
+    );
+}
+
+#[test]
+fn test_rooms_and_mazes_world_graph() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut world = RoomsAndMazesWorld::new();
+    world.set_shape(CubicRangeShape::new((-30, 30), (-30, 30), (-30, 30)));
+    world.reset(|_| ());
+    for _ in 0..50 {
+        world.add_room(&mut rng);
+    }
+    let mut maze = world.start_maze();
+    while !world.grow_maze(&mut maze, &mut rng) {}
+    let mut connect = world.start_connect(&mut rng);
+    while !world.connect(&mut connect, &mut rng) {}
+    let mut dead_ends = world.start_remove_dead_ends();
+    while !world.remove_dead_ends(&mut dead_ends) {}
+
+    let graph = world.graph();
+    assert!(graph.is_connected());
+    let rooms = graph
+        .nodes()
+        .iter()
+        .filter(|node| node.kind == NodeKind::Room)
+        .count();
+    assert_eq!(rooms, world.rooms.len());
+    assert!(graph.nodes().len() > rooms);
+    let open_len = world
+        .hexes()
+        .iter()
+        .filter(|(_, (hex_data, _))| matches!(hex_data.state(), HexState::Open(..)))
+        .count();
+    let open_connections = world
+        .connections
+        .iter()
+        .filter(|pos| {
+            matches!(
+                world.hexes().get(**pos),
+                Some((
+                    HexData {
+                        state: HexState::Open(..)
+                    },
+                    _
+                ))
+            )
+        })
+        .count();
+    assert_eq!(
+        graph.nodes().iter().map(|node| node.size).sum::<usize>() + open_connections,
+        open_len
+    );
+    for node in graph.nodes() {
+        assert!(matches!(
+            world.hexes().get(node.center),
+            Some((HexData { state: HexState::Open(region) }, _)) if *region == node.region
+        ));
+    }
+    for edge in graph.edges() {
+        assert!(edge.from != edge.to);
+        assert!(matches!(
+            world.hexes().get(edge.position),
+            Some((
+                HexData {
+                    state: HexState::Open(..)
+                },
+                _
+            ))
+        ));
+    }
+}
+
+#[test]
+fn test_rooms_and_mazes_world_should_be_deterministic() {
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::hash::Hasher;
+    let generate = || {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut world = RoomsAndMazesWorld::new();
+        world.set_shape(CubicRangeShape::new((-20, 20), (-20, 20), (-20, 20)));
+        world.reset(|_| ());
+        world.generate(30, &mut rng);
+        world.hexes().content_hash_with(|(hex_data, _), hasher| {
+            hasher.write_u64(match hex_data.state() {
+                HexState::Open(region) => region as u64,
+                HexState::Wall => u64::MAX,
+            })
+        })
+    };
+    let fingerprint = generate();
+    assert_eq!(generate(), fingerprint);
+    // Like the cellular fingerprint, it does not depend on the hasher but does depend on the StdRng
+    // stream of rand 0.7.
+    assert_eq!(fingerprint, 14_517_868_988_658_442_275);
+}
+
+#[test]
+fn test_rooms_and_mazes_world_transform() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let generate = || {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut world = RoomsAndMazesWorld::new();
+        world.set_shape(CubicRangeShape::new((-10, 14), (-16, 8), (-12, 16)));
+        world.reset(|_| ());
+        for _ in 0..20 {
+            world.add_room(&mut rng);
+        }
+        let mut maze = world.start_maze();
+        while !world.grow_maze(&mut maze, &mut rng) {}
+        let mut connect = world.start_connect(&mut rng);
+        while !world.connect(&mut connect, &mut rng) {}
+        world
+    };
+    let original = generate();
+    let center = original.shape().center();
+    let graph = original.graph();
+    for transform in HexTransform::all() {
+        let mut world = generate();
+        world.transform(transform);
+        assert_eq!(world.shape().center(), center);
+        assert_eq!(world.hexes().len(), original.hexes().len());
+        for (position, (hex_data, _)) in original.hexes().iter() {
+            let moved = transform.apply_around(position, center);
+            assert!(world.shape().contains_position(moved));
+            assert_eq!(
+                world
+                    .hexes()
+                    .get(moved)
+                    .map(|(hex_data, _)| hex_data.state()),
+                Some(hex_data.state())
+            );
+        }
+        for (room, moved) in original.rooms.iter().zip(&world.rooms) {
+            assert!(room
+                .iter_positions()
+                .all(|position| moved.contains_position(transform.apply_around(position, center))));
+        }
+        let transformed_graph = world.graph();
+        assert_eq!(transformed_graph.nodes().len(), graph.nodes().len());
+        assert_eq!(transformed_graph.edges().len(), graph.edges().len());
+    }
+}
+
+#[test]
+fn test_room_template_from_rows() {
+    let template = RoomTemplate::from_rows("corridor", &["+ . . +", " # # #"]).expect("template");
+    assert_eq!(template.name(), "corridor");
+    assert_eq!(
+        template.cells().collect::<Vec<_>>(),
+        vec![
+            (AxialVector::new(0, 0), TemplateCell::Door),
+            (AxialVector::new(1, 0), TemplateCell::Floor),
+            (AxialVector::new(2, 0), TemplateCell::Floor),
+            (AxialVector::new(3, 0), TemplateCell::Door),
+            (AxialVector::new(0, 1), TemplateCell::Wall),
+            (AxialVector::new(1, 1), TemplateCell::Wall),
+            (AxialVector::new(2, 1), TemplateCell::Wall),
+        ]
+    );
+    assert!(RoomTemplate::from_rows("odd", &["..."]).is_err());
+    assert!(RoomTemplate::from_rows("unknown", &[". x"]).is_err());
+    assert!(RoomTemplate::from_rows("no floor", &["# + #"]).is_err());
+}
+
+#[test]
+fn test_rooms_and_mazes_world_should_place_room_templates() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let pillars = RoomTemplate::from_rows(
+        "pillars",
+        &[
+            "  . . . .",
+            " . # . # .",
+            "+ . . . . . +",
+            " . # . # .",
+            "  . . . .",
+        ],
+    )
+    .expect("template");
+    let floor_len = pillars
+        .cells()
+        .filter(|(_, cell)| *cell == TemplateCell::Floor)
+        .count();
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut world = RoomsAndMazesWorld::new();
+    world.set_shape(CubicRangeShape::new((-30, 30), (-30, 30), (-30, 30)));
+    world.set_room_templates(vec![pillars], 1.0);
+    world.reset(|_| ());
+    for _ in 0..50 {
+        world.add_room(&mut rng);
+    }
+    assert!(!world.template_regions.is_empty());
+    assert_eq!(world.template_regions.len(), world.rooms.len());
+    let floor = world
+        .hexes()
+        .iter()
+        .filter(|(_, (hex_data, _))| matches!(hex_data.state(), HexState::Open(..)))
+        .map(|(pos, _)| pos)
+        .collect::<Vec<_>>();
+    assert_eq!(floor.len(), floor_len * world.template_regions.len());
+
+    let mut maze = world.start_maze();
+    while !world.grow_maze(&mut maze, &mut rng) {}
+    let mut connect = world.start_connect(&mut rng);
+    let door_regions = connect
+        .connectors()
+        .filter(|connector| world.doors.contains(&connector.position()))
+        .flat_map(|connector| connector.regions())
+        .filter(|region| world.template_regions.contains(region))
+        .collect::<HashSet<_>>();
+    assert!(!door_regions.is_empty());
+    for connector in connect.connectors() {
+        if connector
+            .regions()
+            .any(|region| door_regions.contains(&region))
+        {
+            assert!(world.doors.contains(&connector.position()));
+        }
+    }
+    while !world.connect(&mut connect, &mut rng) {}
+    let mut dead_ends = world.start_remove_dead_ends();
+    while !world.remove_dead_ends(&mut dead_ends) {}
+    let mut angles = world.start_remove_angles();
+    while !world.remove_angles(&mut angles) {}
+
+    for pos in floor {
+        assert!(matches!(
+            world.hexes().get(pos),
+            Some((
+                HexData {
+                    state: HexState::Open(..)
+                },
+                _
+            ))
+        ));
+    }
+}
+
+#[test]
+fn test_rooms_and_mazes_world_room_distribution() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut world = RoomsAndMazesWorld::<()>::new();
+    world.set_shape(CubicRangeShape::new((-30, 30), (-30, 30), (-30, 30)));
+    // Big rooms on the west half, single hex ones on the east half.
+    world.set_room_distribution(RoomRadiusByPosition(|position: AxialVector| {
+        if position.q() < 0 {
+            3
+        } else {
+            0
+        }
+    }));
+    world.reset(|_| ());
+    for _ in 0..50 {
+        world.add_room(&mut rng);
+    }
+    let (small, big): (Vec<_>, Vec<_>) = world.rooms.iter().partition(|room| room.len() == 1);
+    assert!(!small.is_empty() && !big.is_empty());
+    // Rooms are moved by one hex at most to line up with the maze.
+    assert!(small.iter().all(|room| room.center().q() >= -1));
+    assert!(big.iter().all(|room| room.center().q() <= 0));
+
+    // Back to the default, rooms of the same size everywhere.
+    let mut uniform = RoomsAndMazesWorld::<()>::new();
+    uniform.set_shape(world.shape().clone());
+    uniform.set_room_distribution(UniformRooms);
+    uniform.reset(|_| ());
+    for _ in 0..50 {
+        uniform.add_room(&mut rng);
+    }
+    assert!(uniform.rooms.iter().all(|room| room.len() > 1));
+}
@@ -0,0 +1,77 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rhombus_core::hex::{ascii::render_ascii, shape::cubic_range::CubicRangeShape};
+use rhombus_mapgen::{
+    cellular::{self, Border, CellularRules, CellularWorld, RuleCursor, RuleStep},
+    rooms_and_mazes::{self, RoomsAndMazesWorld},
+};
+use std::{fs, path::PathBuf};
+
+// The seeded outputs of the generators, stored under tests/snapshots so that a refactoring of the
+// cellular or maze code is seen to change them. Run the tests with UPDATE_SNAPSHOTS=1 to
+// (re)generate them after an intended change of output, a missing snapshot fails otherwise. Like
+// the fingerprints of the unit tests, they depend on the StdRng stream of rand 0.7.
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).expect("write snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "snapshot {} cannot be read ({}), run with UPDATE_SNAPSHOTS=1 to create it",
+            name, err
+        )
+    });
+    if expected != actual {
+        panic!(
+            "snapshot {} does not match\n--- expected\n{}--- actual\n{}",
+            name, expected, actual
+        );
+    }
+}
+
+// '.' is open, '#' is a wall and 'X' a hard wall of the border.
+#[test]
+fn snapshot_cellular() {
+    let mut world = CellularWorld::new();
+    world.reset(
+        CubicRangeShape::new((-16, 16), (-16, 16), (-16, 16)),
+        16,
+        0.45,
+        Border::default(),
+        &mut StdRng::seed_from_u64(42),
+        |_| (),
+    );
+    let rules = CellularRules::default();
+    let mut cursor = RuleCursor::default();
+    while world.run_rules_step(&rules, &mut cursor, |_| ()) != RuleStep::Done {}
+    assert_snapshot(
+        "cellular",
+        &render_ascii(world.hexes(), |_, (hex_data, _)| match hex_data.state() {
+            cellular::HexState::Open => '.',
+            cellular::HexState::HardWall => 'X',
+            _ => '#',
+        }),
+    );
+}
+
+// The open hexes show the last digit of their region, rooms and corridors alike.
+#[test]
+fn snapshot_rooms_and_mazes() {
+    let mut world = RoomsAndMazesWorld::new();
+    world.set_shape(CubicRangeShape::new((-12, 12), (-12, 12), (-12, 12)));
+    world.reset(|_| ());
+    world.generate(10, &mut StdRng::seed_from_u64(42));
+    assert_snapshot(
+        "rooms_and_mazes",
+        &render_ascii(world.hexes(), |_, (hex_data, _)| match hex_data.state() {
+            rooms_and_mazes::HexState::Open(region) => {
+                std::char::from_digit((region % 10) as u32, 10).expect("digit")
+            }
+            _ => '#',
+        }),
+    );
+}
@@ -0,0 +1,323 @@
+// Python module of the generators and the analyses, built with maturin (see pyproject.toml).
+// Positions are (q, r) tuples. They are taken from any iterable of pairs, numpy arrays of shape
+// (n, 2) included, and the lists of tuples given back turn into arrays with numpy.array.
+
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use rand::{rngs::StdRng, SeedableRng};
+use rhombus_core::hex::{
+    ascii::{parse_ascii, render_ascii},
+    coordinates::axial::AxialVector,
+    field_of_view::visible_positions,
+    reachable::{cheapest_path, reachable as reachable_hexes},
+    shape::cubic_range::CubicRangeShape,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use rhombus_mapgen::{
+    cellular::{Border, CellularRules, CellularWorld, HexState as CaveState, RuleCursor, RuleStep},
+    rooms_and_mazes::{HexState as DungeonState, RoomsAndMazesWorld},
+};
+use std::collections::HashMap;
+
+type Hex = (isize, isize);
+
+fn axial((q, r): Hex) -> AxialVector {
+    AxialVector::new(q, r)
+}
+
+fn hex(position: AxialVector) -> Hex {
+    (position.q(), position.r())
+}
+
+fn hexagon(radius: isize) -> CubicRangeShape {
+    CubicRangeShape::new((-radius, radius), (-radius, radius), (-radius, radius))
+}
+
+fn hex_set(positions: Option<&Bound<'_, PyAny>>) -> PyResult<HexSet> {
+    let mut set = HexSet::new();
+    let positions = match positions {
+        Some(positions) => positions,
+        None => return Ok(set),
+    };
+    for item in positions.try_iter()? {
+        let coordinates = item?
+            .try_iter()?
+            .map(|coordinate| coordinate?.extract::<isize>())
+            .collect::<PyResult<Vec<_>>>()?;
+        match coordinates[..] {
+            [q, r] => {
+                set.insert(AxialVector::new(q, r));
+            }
+            _ => return Err(PyValueError::new_err("positions are (q, r) pairs")),
+        }
+    }
+    Ok(set)
+}
+
+fn sorted_hexes(set: &HexSet) -> Vec<Hex> {
+    let mut hexes = set.iter().map(hex).collect::<Vec<_>>();
+    hexes.sort_unstable();
+    hexes
+}
+
+/// Number of steps between two hexes.
+#[pyfunction]
+fn distance(a: Hex, b: Hex) -> isize {
+    axial(a).distance(axial(b))
+}
+
+/// The hexes seen from the origin at most radius hexes away, the origin included, sorted. The walls
+/// block the view but are seen themselves.
+#[pyfunction]
+#[pyo3(signature = (origin, radius, walls = None))]
+fn field_of_view(
+    origin: Hex,
+    radius: usize,
+    walls: Option<&Bound<'_, PyAny>>,
+) -> PyResult<Vec<Hex>> {
+    let walls = hex_set(walls)?;
+    let origin = axial(origin);
+    let visible = visible_positions(
+        origin,
+        Some(radius),
+        &|position| walls.contains(position),
+        |position| origin.distance(position) <= radius as isize,
+    );
+    Ok(sorted_hexes(&visible))
+}
+
+/// The hexes reachable from the start in at most budget steps around the walls, as (q, r, steps)
+/// in increasing steps order.
+#[pyfunction]
+#[pyo3(signature = (start, budget, walls = None))]
+fn reachable(
+    start: Hex,
+    budget: usize,
+    walls: Option<&Bound<'_, PyAny>>,
+) -> PyResult<Vec<(isize, isize, usize)>> {
+    let walls = hex_set(walls)?;
+    Ok(reachable_hexes(axial(start), budget, |_, to| {
+        if walls.contains(to) {
+            None
+        } else {
+            Some(1)
+        }
+    })
+    .into_iter()
+    .map(|(position, steps)| (position.q(), position.r(), steps))
+    .collect())
+}
+
+/// A shortest path from the start to the goal around the walls, both ends included, None if there
+/// is none of at most max_length steps.
+#[pyfunction]
+#[pyo3(signature = (start, goal, walls = None, max_length = 1000))]
+fn find_path(
+    start: Hex,
+    goal: Hex,
+    walls: Option<&Bound<'_, PyAny>>,
+    max_length: usize,
+) -> PyResult<Option<Vec<Hex>>> {
+    let walls = hex_set(walls)?;
+    let path = cheapest_path(axial(start), axial(goal), max_length, |_, to| {
+        if walls.contains(to) {
+            None
+        } else {
+            Some(1)
+        }
+    });
+    Ok(path.map(|(path, _)| path.into_iter().map(hex).collect()))
+}
+
+/// A rooms and mazes dungeon in the hexagon of the radius around the origin, as (q, r, region)
+/// sorted by position. Open hexes belong to a region (room or corridor), walls to -1. Only the
+/// walls next to open hexes are kept.
+#[pyfunction]
+#[pyo3(signature = (radius, room_attempts = 20, seed = 0))]
+fn rooms_and_mazes(radius: isize, room_attempts: usize, seed: u64) -> Vec<(isize, isize, isize)> {
+    let mut world = RoomsAndMazesWorld::new();
+    world.set_shape(hexagon(radius));
+    world.reset(|_| ());
+    world.generate(room_attempts, &mut StdRng::seed_from_u64(seed));
+    let mut cells = world
+        .hexes()
+        .iter()
+        .map(|(position, (hex_data, _))| {
+            let region = match hex_data.state() {
+                DungeonState::Open(region) => region as isize,
+                _ => -1,
+            };
+            (position.q(), position.r(), region)
+        })
+        .collect::<Vec<_>>();
+    cells.sort_unstable();
+    cells
+}
+
+/// A cave carved by the cellular automaton with the default rules in the hexagon of the radius
+/// around the origin, as (q, r, state) sorted by position, the state being 0 for open hexes, 1 for
+/// walls and 2 for the hard walls of the border.
+#[pyfunction]
+#[pyo3(signature = (radius, seed = 0, wall_ratio = 0.55))]
+fn cellular(radius: isize, seed: u64, wall_ratio: f32) -> Vec<(isize, isize, u8)> {
+    let mut world = CellularWorld::new();
+    world.reset(
+        hexagon(radius),
+        12,
+        wall_ratio,
+        Border::default(),
+        &mut StdRng::seed_from_u64(seed),
+        |_| (),
+    );
+    let rules = CellularRules::default();
+    let mut cursor = RuleCursor::default();
+    while world.run_rules_step(&rules, &mut cursor, |_| ()) != RuleStep::Done {}
+    let mut cells = world
+        .hexes()
+        .iter()
+        .map(|(position, (hex_data, _))| {
+            let state = match hex_data.state() {
+                CaveState::Open => 0,
+                CaveState::HardWall => 2,
+                _ => 1,
+            };
+            (position.q(), position.r(), state)
+        })
+        .collect::<Vec<_>>();
+    cells.sort_unstable();
+    cells
+}
+
+/// The hexes drawn as text, a dict of (q, r) to a character. See from_ascii.
+#[pyfunction]
+fn to_ascii(hexes: HashMap<Hex, char>) -> String {
+    let mut storage = RectHashStorage::new();
+    for (position, c) in hexes {
+        storage.insert(axial(position), c);
+    }
+    render_ascii(&storage, |_, c| *c)
+}
+
+/// The hexes of a text map, every character but the spaces, as a dict of (q, r) to the
+/// character. Each row is shifted by one column from the previous one.
+#[pyfunction]
+fn from_ascii(ascii: &str) -> HashMap<Hex, char> {
+    parse_ascii(ascii, |c| if c == ' ' { None } else { Some(c) })
+        .iter()
+        .map(|(position, c)| (hex(position), *c))
+        .collect()
+}
+
+/// The hexes in the binary storage format of rhombus_core, a dict of (q, r) to an integer.
+#[pyfunction]
+fn to_bytes<'py>(py: Python<'py>, hexes: HashMap<Hex, i64>) -> PyResult<Bound<'py, PyBytes>> {
+    let mut storage = RectHashStorage::new();
+    for (position, value) in hexes {
+        storage.insert(axial(position), value);
+    }
+    let mut bytes = Vec::new();
+    storage
+        .write_to(&mut bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(PyBytes::new(py, &bytes))
+}
+
+/// The hexes of the binary storage format of rhombus_core, see to_bytes.
+#[pyfunction]
+fn from_bytes(bytes: &[u8]) -> PyResult<HashMap<Hex, i64>> {
+    let storage = RectHashStorage::<i64>::read_from(&mut &bytes[..])
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(storage
+        .iter()
+        .map(|(position, value)| (hex(position), *value))
+        .collect())
+}
+
+/// Hexagonal grid map generation and analysis.
+#[pymodule]
+fn rhombus(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(distance, module)?)?;
+    module.add_function(wrap_pyfunction!(field_of_view, module)?)?;
+    module.add_function(wrap_pyfunction!(reachable, module)?)?;
+    module.add_function(wrap_pyfunction!(find_path, module)?)?;
+    module.add_function(wrap_pyfunction!(rooms_and_mazes, module)?)?;
+    module.add_function(wrap_pyfunction!(cellular, module)?)?;
+    module.add_function(wrap_pyfunction!(to_ascii, module)?)?;
+    module.add_function(wrap_pyfunction!(from_ascii, module)?)?;
+    module.add_function(wrap_pyfunction!(to_bytes, module)?)?;
+    module.add_function(wrap_pyfunction!(from_bytes, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+fn run_python(code: &std::ffi::CStr) {
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "rhombus").unwrap();
+        rhombus(&module).unwrap();
+        let locals = pyo3::types::PyDict::new(py);
+        locals.set_item("rhombus", module).unwrap();
+        if let Err(err) = py.run(code, None, Some(&locals)) {
+            err.print(py);
+            panic!("Python code failed");
+        }
+    });
+}
+
+#[test]
+fn test_analysis_from_python() {
+    run_python(pyo3::ffi::c_str!(
+        r##"
+assert rhombus.distance((0, 0), (2, -1)) == 2
+assert len(rhombus.field_of_view((0, 0), 3)) == 37
+visible = rhombus.field_of_view((0, 0), 3, walls=[(1, 0)])
+assert (1, 0) in visible and (3, 0) not in visible
+assert rhombus.reachable((0, 0), 1)[0] == (0, 0, 0)
+assert len(rhombus.reachable((0, 0), 2, [[1, 0]])) == 17
+path = rhombus.find_path((0, 0), (3, 0), walls=[(1, 0), (2, 0)])
+assert path[0] == (0, 0) and path[-1] == (3, 0) and len(path) == 5
+assert rhombus.find_path((0, 0), (3, 0), max_length=2) is None
+try:
+    rhombus.field_of_view((0, 0), 3, walls=[(1, 0, 0)])
+    assert False
+except ValueError:
+    pass
+"##
+    ));
+}
+
+#[test]
+fn test_generators_from_python() {
+    run_python(pyo3::ffi::c_str!(
+        r##"
+dungeon = rhombus.rooms_and_mazes(15, seed=42)
+assert dungeon == rhombus.rooms_and_mazes(15, seed=42)
+assert dungeon != rhombus.rooms_and_mazes(15, seed=43)
+assert dungeon == sorted(dungeon)
+assert any(region == -1 for (_, _, region) in dungeon)
+assert any(region >= 0 for (_, _, region) in dungeon)
+cave = rhombus.cellular(20, seed=42)
+assert cave == rhombus.cellular(20, seed=42)
+assert len(cave) == 1 + 3 * 20 * 21
+assert {state for (_, _, state) in cave} == {0, 1, 2}
+"##
+    ));
+}
+
+#[test]
+fn test_import_export_from_python() {
+    run_python(pyo3::ffi::c_str!(
+        r##"
+hexes = rhombus.from_ascii("# . #\n . # .")
+assert len(hexes) == 6 and hexes[(0, 0)] == "#" and hexes[(0, 1)] == "."
+assert rhombus.from_ascii(rhombus.to_ascii(hexes)) == hexes
+values = {(q, r): q * r for (q, r) in rhombus.field_of_view((5, -3), 4)}
+data = rhombus.to_bytes(values)
+assert isinstance(data, bytes)
+assert rhombus.from_bytes(data) == values
+try:
+    rhombus.from_bytes(data[:10])
+    assert False
+except ValueError:
+    pass
+"##
+    ));
+}
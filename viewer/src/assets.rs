@@ -1,16 +1,30 @@
+use crate::theme::TerrainType;
 use amethyst::{
     assets::Handle,
     renderer::{types::Mesh, Material},
 };
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RhombusViewerAssets {
     pub square_handle: Handle<Mesh>,
     pub hex_handle: Handle<Mesh>,
+    // Beveled hex.
+    pub block_handle: Handle<Mesh>,
     pub dodec_handle: Handle<Mesh>,
     pub pointer_handle: Handle<Mesh>,
+    pub ramp_handle: Handle<Mesh>,
+    pub ring_handle: Handle<Mesh>,
     pub color_data: HashMap<Color, ColorData>,
+    // Texture atlas of the theme and hex meshes textured with the tile of each terrain type.
+    pub atlas: ColorData,
+    pub terrain_handles: HashMap<TerrainType, Handle<Mesh>>,
+    // Wall meshes by autotile index.
+    pub wall_handles: Vec<Handle<Mesh>>,
+    // Alpha blended, the entities using them also need the Transparent component.
+    pub translucent_materials: HashMap<Translucent, Handle<Material>>,
+    // Emissive, it glows whatever the lights.
+    pub highlight_material: Handle<Material>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -25,6 +39,15 @@ pub enum Color {
     White,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Translucent {
+    Water,
+    // Covers what is out of sight.
+    Fog,
+    // Previews what is about to be built.
+    Ghost,
+}
+
 #[derive(Debug, Clone)]
 pub struct ColorData {
     pub light: Handle<Material>,
@@ -1,5 +1,5 @@
 use amethyst::prelude::*;
-use rhombus_core::hex::storage::hash::RectHashStorage;
+use rhombus_core::hex::storage::{hash::RectHashStorage, interval::IntervalMap};
 
 pub trait Dispose {
     fn dispose(&mut self, data: &mut StateData<'_, GameData<'_, '_>>);
@@ -10,6 +10,15 @@ impl<Hex: Dispose> Dispose for RectHashStorage<Hex> {
         for hex in self.hexes_mut() {
             hex.dispose(data);
         }
+        self.clear_and_shrink();
+    }
+}
+
+impl<V: Dispose> Dispose for IntervalMap<V> {
+    fn dispose(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        for value in self.values_mut() {
+            value.dispose(data);
+        }
         self.clear();
     }
 }
@@ -6,7 +6,10 @@ use amethyst::{
     prelude::*,
     winit::VirtualKeyCode,
 };
-use rhombus_core::dodec::coordinates::quadric::QuadricVector;
+use rhombus_core::dodec::coordinates::{
+    direction::{axis_directions, NUM_AXES},
+    quadric::QuadricVector,
+};
 use std::sync::Arc;
 
 pub struct DodecDirectionsDemo {
@@ -54,23 +57,20 @@ impl SimpleState for DodecDirectionsDemo {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
 
-        self.create_direction(&mut data, &world, 0, 3, Color::Red);
-        self.create_direction(&mut data, &world, 6, 2, Color::Red);
-
-        self.create_direction(&mut data, &world, 1, 3, Color::Green);
-        self.create_direction(&mut data, &world, 7, 2, Color::Green);
-
-        self.create_direction(&mut data, &world, 2, 3, Color::Blue);
-        self.create_direction(&mut data, &world, 8, 2, Color::Blue);
-
-        self.create_direction(&mut data, &world, 3, 3, Color::Yellow);
-        self.create_direction(&mut data, &world, 9, 2, Color::Yellow);
-
-        self.create_direction(&mut data, &world, 4, 3, Color::Magenta);
-        self.create_direction(&mut data, &world, 10, 2, Color::Magenta);
-
-        self.create_direction(&mut data, &world, 5, 3, Color::Cyan);
-        self.create_direction(&mut data, &world, 11, 2, Color::Cyan);
+        // The positive direction of each axis is the longer one.
+        let colors: [Color; NUM_AXES] = [
+            Color::Red,
+            Color::Green,
+            Color::Blue,
+            Color::Yellow,
+            Color::Magenta,
+            Color::Cyan,
+        ];
+        for (axis, color) in colors.iter().enumerate() {
+            let [forward, backward] = axis_directions(axis);
+            self.create_direction(&mut data, &world, forward, 3, *color);
+            self.create_direction(&mut data, &world, backward, 2, *color);
+        }
     }
 
     fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
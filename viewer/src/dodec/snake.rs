@@ -1,67 +1,225 @@
-use crate::{assets::Color, snake::Snake, world::RhombusViewerWorld};
+use crate::{
+    assets::Color,
+    input::get_key_and_modifiers,
+    pacing::{set_window_title, AnimationSettings},
+    seed::DemoSeed,
+    world::RhombusViewerWorld,
+};
 use amethyst::{
+    assets::Handle,
     core::{math::Vector3, timing::Time, transform::Transform},
     ecs::prelude::*,
-    input::is_key_down,
+    input::ElementState,
     prelude::*,
+    renderer::Material,
     winit::VirtualKeyCode,
 };
-use rhombus_core::dodec::coordinates::quadric::{QuadricVector, SphereIter};
-use std::{collections::VecDeque, sync::Arc};
+use rand::seq::SliceRandom;
+use rhombus_core::dodec::{
+    coordinates::{
+        direction::{opposite, plane_directions, LAYER_PLANE},
+        quadric::QuadricVector,
+    },
+    storage::DodecHashStorage,
+};
+use std::sync::Arc;
+
+const STEP_MILLIS: u64 = 250;
+const BOUNDARY_RADIUS: usize = 5;
+
+// For each horizontal direction of the layer plane, in clockwise order, the closest direction
+// going up. The closest direction going down is the opposite of the one going up backwards.
+const UP_DIRECTIONS: [usize; 6] = [3, 3, 4, 4, 5, 5];
+const NUM_HORIZONTAL_DIRECTIONS: usize = UP_DIRECTIONS.len();
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Slope {
+    Level,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Heading {
+    horizontal: usize,
+    slope: Slope,
+}
+
+impl Heading {
+    fn direction(&self) -> usize {
+        match self.slope {
+            Slope::Level => plane_directions(LAYER_PLANE)[self.horizontal],
+            Slope::Up => UP_DIRECTIONS[self.horizontal],
+            Slope::Down => opposite(
+                UP_DIRECTIONS
+                    [(self.horizontal + NUM_HORIZONTAL_DIRECTIONS / 2) % NUM_HORIZONTAL_DIRECTIONS],
+            ),
+        }
+    }
+
+    fn turn(&mut self, clockwise_steps: usize) {
+        self.horizontal = (self.horizontal + clockwise_steps) % NUM_HORIZONTAL_DIRECTIONS;
+    }
+}
+
+impl Default for Heading {
+    fn default() -> Self {
+        Self {
+            horizontal: 0,
+            slope: Slope::Level,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SnakeState {
+    // Avoids obstacles on its own until the user steers it.
+    Autopilot,
+    Manual,
+    GameOver,
+}
 
 pub struct DodecSnakeDemo {
     position: QuadricVector,
-    snakes: Vec<Snake<Entity, SphereIter>>,
+    head: QuadricVector,
+    heading: Heading,
+    state: SnakeState,
+    trail: DodecHashStorage<Entity>,
+    boundary: Vec<Entity>,
+    boundary_enabled: bool,
     remaining_millis: u64,
+    seed: DemoSeed,
 }
 
 impl DodecSnakeDemo {
     pub fn new() -> Self {
         Self {
             position: QuadricVector::default(),
-            snakes: Vec::new(),
+            head: QuadricVector::default(),
+            heading: Heading::default(),
+            state: SnakeState::Autopilot,
+            trail: DodecHashStorage::new(),
+            boundary: Vec::new(),
+            boundary_enabled: true,
             remaining_millis: 0,
+            seed: DemoSeed::new(),
         }
     }
 
-    fn new_snake(
-        position: QuadricVector,
-        radius: usize,
+    // The same seed makes the same turns on autopilot again.
+    fn reset(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        self.clear_trail(data);
+        self.seed.restart();
+        self.head = self.position;
+        self.heading = Heading::default();
+        self.state = SnakeState::Autopilot;
+        self.remaining_millis = 0;
+        let entity = Self::push_dodec(self.head, 0.8, data, world, Color::Red);
+        self.trail.insert(self.head, entity);
+        let settings = data.world.read_resource::<AnimationSettings>();
+        set_window_title(
+            data.world,
+            &format!("{} - seed {}", settings.window_title, self.seed.seed()),
+        );
+    }
+
+    fn clear_trail(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        for entity in self.trail.dodecs() {
+            data.world.delete_entity(*entity).expect("delete entity");
+        }
+        self.trail.clear();
+    }
+
+    fn show_boundary(
+        &mut self,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
-    ) -> Snake<Entity, SphereIter> {
-        let mut state = VecDeque::new();
-        let mut iter = Self::snake_center(position).sphere_iter(radius);
-        state.push_back(Self::push_dodec(
-            iter.next().expect("first"),
-            data,
-            world,
-            Color::Red,
-        ));
-        Snake {
-            radius,
-            state,
-            iter,
+    ) {
+        for dodec in self.position.sphere_iter(BOUNDARY_RADIUS) {
+            self.boundary
+                .push(Self::push_dodec(dodec, 0.2, data, world, Color::White));
         }
     }
 
-    fn snake_center(position: QuadricVector) -> QuadricVector {
-        position
+    fn hide_boundary(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let result = data.world.delete_entities(self.boundary.as_slice());
+        self.boundary.clear();
+        result.expect("delete entities");
     }
 
-    fn snake_tail_size(radius: usize) -> usize {
-        12 * radius
+    fn is_blocked(&self, dodec: QuadricVector) -> bool {
+        self.trail.contains_position(dodec)
+            || (self.boundary_enabled && dodec.distance(self.position) >= BOUNDARY_RADIUS as isize)
+    }
+
+    fn next_heading(&mut self) -> Option<Heading> {
+        if !self.is_blocked(self.head.neighbor(self.heading.direction())) {
+            return Some(self.heading);
+        }
+        if self.state != SnakeState::Autopilot {
+            return None;
+        }
+        let mut headings = Vec::new();
+        for horizontal in 0..NUM_HORIZONTAL_DIRECTIONS {
+            for slope in [Slope::Level, Slope::Up, Slope::Down].iter() {
+                let heading = Heading {
+                    horizontal,
+                    slope: *slope,
+                };
+                if !self.is_blocked(self.head.neighbor(heading.direction())) {
+                    headings.push(heading);
+                }
+            }
+        }
+        headings.choose(self.seed.rng()).copied()
+    }
+
+    fn step(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        if self.state == SnakeState::GameOver {
+            return;
+        }
+        let mut material_storage = data.world.write_storage::<Handle<Material>>();
+        let head_entity = *self.trail.get(self.head).expect("head");
+        if let Some(heading) = self.next_heading() {
+            *material_storage.get_mut(head_entity).expect("material") =
+                world.assets.color_data[&Color::Red].dark.clone();
+            drop(material_storage);
+            self.heading = heading;
+            self.head = self.head.neighbor(heading.direction());
+            let entity = Self::push_dodec(self.head, 0.8, data, world, Color::Red);
+            self.trail.insert(self.head, entity);
+        } else if self.state == SnakeState::Autopilot {
+            // Trapped, start over so that the demo keeps going.
+            drop(material_storage);
+            self.reset(data, world);
+        } else {
+            *material_storage.get_mut(head_entity).expect("material") =
+                world.assets.color_data[&Color::Yellow].light.clone();
+            self.state = SnakeState::GameOver;
+        }
+    }
+
+    fn steer<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Heading),
+    {
+        if self.state == SnakeState::GameOver {
+            return;
+        }
+        f(&mut self.heading);
+        self.state = SnakeState::Manual;
     }
 
     fn push_dodec(
         dodec: QuadricVector,
+        scale: f32,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
         color: Color,
     ) -> Entity {
         let pos = dodec.into();
         let mut transform = Transform::default();
-        transform.set_scale(Vector3::new(0.8, 0.8, 0.8));
+        transform.set_scale(Vector3::new(scale, scale, scale));
         world.transform_quadric(pos, &mut transform);
         let material = world.assets.color_data[&color].light.clone();
         data.world
@@ -76,30 +234,74 @@ impl DodecSnakeDemo {
 impl SimpleState for DodecSnakeDemo {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
-        self.snakes = vec![Self::new_snake(self.position, 2, &mut data, &world)];
-        self.remaining_millis = 0;
+        self.seed.start(&data);
+        self.reset(&mut data, &world);
+        if self.boundary_enabled {
+            self.show_boundary(&mut data, &world);
+        }
     }
 
-    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
-        for snake in &mut self.snakes {
-            while let Some(entity) = snake.state.pop_front() {
-                data.world.delete_entity(entity).expect("delete entity");
-            }
-        }
-        self.snakes.clear();
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.clear_trail(&mut data);
+        self.hide_boundary(&mut data);
+        let settings = data.world.read_resource::<AnimationSettings>();
+        set_window_title(data.world, &settings.window_title);
     }
 
     fn handle_event(
         &mut self,
-        _: StateData<'_, GameData<'_, '_>>,
+        mut data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
-            if is_key_down(&event, VirtualKeyCode::Escape) {
-                Trans::Pop
-            } else {
-                Trans::None
+            let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+            let mut trans = Trans::None;
+            match get_key_and_modifiers(&event) {
+                Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
+                    trans = Trans::Pop;
+                }
+                Some((VirtualKeyCode::N, ElementState::Pressed, modifiers)) => {
+                    if !modifiers.shift {
+                        self.seed.renew();
+                    }
+                    self.reset(&mut data, &world);
+                }
+                Some((VirtualKeyCode::B, ElementState::Pressed, _)) => {
+                    self.boundary_enabled = !self.boundary_enabled;
+                    if self.boundary_enabled {
+                        self.show_boundary(&mut data, &world);
+                    } else {
+                        self.hide_boundary(&mut data);
+                    }
+                }
+                Some((VirtualKeyCode::Right, ElementState::Pressed, _)) => {
+                    self.steer(|heading| heading.turn(1));
+                }
+                Some((VirtualKeyCode::Left, ElementState::Pressed, _)) => {
+                    self.steer(|heading| heading.turn(NUM_HORIZONTAL_DIRECTIONS - 1));
+                }
+                Some((VirtualKeyCode::Up, ElementState::Pressed, _)) => {
+                    self.steer(|heading| {
+                        heading.slope = match heading.slope {
+                            Slope::Down => Slope::Level,
+                            Slope::Level | Slope::Up => Slope::Up,
+                        }
+                    });
+                }
+                Some((VirtualKeyCode::Down, ElementState::Pressed, _)) => {
+                    self.steer(|heading| {
+                        heading.slope = match heading.slope {
+                            Slope::Up => Slope::Level,
+                            Slope::Level | Slope::Down => Slope::Down,
+                        }
+                    });
+                }
+                Some((VirtualKeyCode::Period, ElementState::Pressed, _)) => {
+                    self.steer(|heading| heading.slope = Slope::Level);
+                }
+                _ => {}
             }
+            trans
         } else {
             Trans::None
         }
@@ -111,29 +313,10 @@ impl SimpleState for DodecSnakeDemo {
             let duration = data.world.read_resource::<Time>().delta_time();
             duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
         } + self.remaining_millis;
-        let num = delta_millis / 100;
-        self.remaining_millis = delta_millis % 100;
-        for snake in &mut self.snakes {
-            for _ in 0..num {
-                if let Some(dodec) = snake.iter.next() {
-                    snake
-                        .state
-                        .push_back(Self::push_dodec(dodec, data, &world, Color::Red));
-                } else {
-                    snake.iter = Self::snake_center(self.position).sphere_iter(snake.radius);
-                    snake.state.push_back(Self::push_dodec(
-                        snake.iter.next().expect("first"),
-                        data,
-                        &world,
-                        Color::Red,
-                    ));
-                }
-                while snake.state.len() > Self::snake_tail_size(snake.radius) {
-                    if let Some(entity) = snake.state.pop_front() {
-                        data.world.delete_entity(entity).expect("delete entity");
-                    }
-                }
-            }
+        let num = delta_millis / STEP_MILLIS;
+        self.remaining_millis = delta_millis % STEP_MILLIS;
+        for _ in 0..num {
+            self.step(data, &world);
         }
         Trans::None
     }
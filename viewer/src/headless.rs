@@ -0,0 +1,73 @@
+use amethyst::{
+    assets::{AssetStorage, Handle, Loader, Processor},
+    controls::{ArcBallControlTag, FlyControlTag},
+    core::SystemBundle,
+    ecs::prelude::*,
+    renderer::{
+        camera::Camera,
+        debug_drawing::DebugLinesComponent,
+        light::Light,
+        mtl::TextureOffset,
+        palette::Srgba,
+        rendy::texture::palette::load_from_srgba,
+        transparent::Transparent,
+        types::{Mesh, Texture},
+        Material, MaterialDefaults,
+    },
+    window::ScreenDimensions,
+    Error,
+};
+
+// Stands for the rendering and the window bundles when there is no display: the resources and the
+// components the demos use are there, but nothing is drawn. The meshes and the textures are never
+// processed, their handles stay valid though.
+pub struct HeadlessBundle {
+    width: u32,
+    height: u32,
+}
+
+impl HeadlessBundle {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for HeadlessBundle {
+    fn build(
+        self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(AssetStorage::<Mesh>::default());
+        world.insert(AssetStorage::<Texture>::default());
+        world.insert(AssetStorage::<Material>::default());
+        builder.add(Processor::<Material>::new(), "material_processor", &[]);
+        world.insert(ScreenDimensions::new(self.width, self.height, 1.0));
+
+        let texture: Handle<Texture> = world.read_resource::<Loader>().load_from_data(
+            load_from_srgba(Srgba::new(1.0, 1.0, 1.0, 1.0)).into(),
+            (),
+            &world.read_resource::<AssetStorage<Texture>>(),
+        );
+        world.insert(MaterialDefaults(Material {
+            alpha_cutoff: 0.01,
+            albedo: texture.clone(),
+            emission: texture.clone(),
+            normal: texture.clone(),
+            metallic_roughness: texture.clone(),
+            ambient_occlusion: texture.clone(),
+            cavity: texture,
+            uv_offset: TextureOffset::default(),
+        }));
+
+        world.register::<Handle<Mesh>>();
+        world.register::<Handle<Material>>();
+        world.register::<Transparent>();
+        world.register::<Camera>();
+        world.register::<Light>();
+        world.register::<DebugLinesComponent>();
+        world.register::<ArcBallControlTag>();
+        world.register::<FlyControlTag>();
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    dispose::Dispose,
+    hex::{
+        pointer::{HexPointer, VerticalDirection},
+        render::{
+            renderer::HexRenderer,
+            storage::EcsBackedStorage,
+            tile::{HexScale, TileRenderer},
+        },
+    },
+    world::RhombusViewerWorld,
+};
+use amethyst::{core::timing::Time, input::is_key_down, prelude::*, winit::VirtualKeyCode};
+use log::info;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rhombus_core::hex::{
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    storage::set::HexSet,
+};
+use std::sync::Arc;
+
+// Same map and walk every time.
+const SEED: u64 = 0x4865_7842;
+const MAP_RADIUS: usize = 40;
+const WALL_PROBABILITY: f64 = 0.25;
+const WALK_RADIUS: usize = 20;
+const WALK_STEP_SECONDS: f64 = 0.15;
+
+pub struct BenchmarkSettings {
+    pub seconds: f64,
+}
+
+pub struct HexBenchmarkDemo {
+    hexes: EcsBackedStorage<TileRenderer>,
+    walls: HexSet,
+    pointer: HexPointer,
+    walk: Vec<AxialVector>,
+    walk_index: usize,
+    elapsed_seconds: f64,
+    next_step_seconds: f64,
+    frame_seconds: Vec<f32>,
+}
+
+impl HexBenchmarkDemo {
+    pub fn new() -> Self {
+        Self {
+            hexes: EcsBackedStorage::new(TileRenderer::new(
+                HexScale {
+                    horizontal: 0.8,
+                    vertical: 0.1,
+                },
+                HexScale {
+                    horizontal: 0.8,
+                    vertical: 0.3,
+                },
+            )),
+            walls: HexSet::new(),
+            pointer: HexPointer::new_with_level_height(1.0),
+            walk: AxialVector::default().ring_iter(WALK_RADIUS).collect(),
+            walk_index: 0,
+            elapsed_seconds: 0.0,
+            next_step_seconds: 0.0,
+            frame_seconds: Vec::new(),
+        }
+    }
+
+    fn generate(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let center = AxialVector::default();
+        for position in center.spiral_iter(MAP_RADIUS) {
+            // The walk stays on the ground.
+            let on_walk = position.distance(center) == WALK_RADIUS as isize;
+            if rng.gen_bool(WALL_PROBABILITY) && !on_walk {
+                self.walls.insert(position);
+            }
+            let hex = self.hexes.renderer_mut().new_hex(false, true);
+            self.hexes.insert(position, hex, data);
+        }
+        let walls = &self.walls;
+        let (hexes, renderer) = self.hexes.parts_mut();
+        renderer.update_world(
+            hexes,
+            |position, _| walls.contains(position),
+            None,
+            |hex| hex,
+            false,
+            false,
+            &world.layout,
+            data,
+            world,
+        );
+    }
+
+    // The camera follows the pointer, which turns to face where it goes.
+    fn step(&mut self, data: &StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        let position = self.walk[self.walk_index];
+        self.walk_index = (self.walk_index + 1) % self.walk.len();
+        let next = self.walk[self.walk_index];
+        let direction = (0..6)
+            .find(|direction| position.neighbor(*direction) == next)
+            .unwrap_or_else(|| self.pointer.direction());
+        self.pointer.set_position(next, 0, data, world);
+        self.pointer
+            .set_direction(direction, VerticalDirection::Horizontal, data, world);
+    }
+
+    fn report(&mut self) {
+        if self.frame_seconds.is_empty() {
+            return;
+        }
+        self.frame_seconds
+            .sort_by(|a, b| a.partial_cmp(b).expect("frame time"));
+        let frames = self.frame_seconds.len();
+        let total = self.frame_seconds.iter().sum::<f32>();
+        let millis = |seconds: f32| seconds * 1000.0;
+        let p99 = self.frame_seconds[(frames * 99 / 100).min(frames - 1)];
+        let report = format!(
+            "{} frames in {:.1}s ({:.1} FPS), frame time min {:.2}ms, avg {:.2}ms, p99 {:.2}ms, \
+             max {:.2}ms",
+            frames,
+            total,
+            frames as f32 / total,
+            millis(self.frame_seconds[0]),
+            millis(total / frames as f32),
+            millis(p99),
+            millis(self.frame_seconds[frames - 1]),
+        );
+        info!("Benchmark: {}", report);
+        println!("{}", report);
+    }
+}
+
+impl SimpleState for HexBenchmarkDemo {
+    fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.pointer.create_entities(&mut data, &world);
+        self.generate(&mut data, &world);
+        self.pointer
+            .set_position(self.walk[self.walk_index], 0, &data, &world);
+    }
+
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.report();
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.pointer.delete_entities(&mut data, &world);
+        self.hexes.dispose(&mut data);
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match event {
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => Trans::Quit,
+            _ => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let delta_seconds = data.world.read_resource::<Time>().delta_real_seconds();
+        // The first frame also accounts for the map generation.
+        if self.elapsed_seconds > 0.0 {
+            self.frame_seconds.push(delta_seconds);
+        }
+        self.elapsed_seconds += f64::from(delta_seconds);
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        while self.next_step_seconds <= self.elapsed_seconds {
+            self.step(data, &world);
+            self.next_step_seconds += WALK_STEP_SECONDS;
+        }
+        if self.elapsed_seconds >= data.world.read_resource::<BenchmarkSettings>().seconds {
+            Trans::Quit
+        } else {
+            Trans::None
+        }
+    }
+}
@@ -1,43 +1,332 @@
 use crate::{
     assets::Color,
-    hex::pointer::{HexPointer, VerticalDirection},
+    dispose::Dispose,
+    hex::{
+        inspect::{self, HexLayer, HexTag},
+        pointer::{HexPointer, VerticalDirection},
+    },
+    systems::{
+        culling::ChunkTag, follow_me::FollowMeSettings, grid_overlay::GridSettings,
+        lighting::LightingConfig,
+    },
     world::RhombusViewerWorld,
 };
 use amethyst::{
+    assets::Handle,
     core::{math::Vector3, transform::Transform},
     ecs::prelude::*,
     input::{get_key, ElementState},
     prelude::*,
+    renderer::Material,
     winit::VirtualKeyCode,
 };
-use rhombus_core::hex::coordinates::{axial::AxialVector, direction::HexagonalDirection};
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    sync::Arc,
+use log::info;
+use rhombus_core::hex::{
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    float::line_of_sight::is_in_line_of_sight,
+    storage::{hash::RectHashStorage, interval::IntervalMap},
 };
+use std::sync::Arc;
 
 const LEVEL_HEIGHT: f32 = 0.5;
 // So that turning direction at each step leads to a nice stairway
 const BLOCK_HEIGHT: isize = 5;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+// Relative to the floor or the ceiling, in levels, as they are rendered.
+const FLOOR_TOP: f32 = 0.8;
+// Ramps are considered as high as their middle.
+const RAMP_TOP: f32 = 1.3;
+const BRIDGE_BOTTOM: f32 = 0.6;
+const CEILING_BOTTOM: f32 = 0.6;
+const CEILING_TOP: f32 = 0.8;
+const EYE_HEIGHT: f32 = 1.4;
+
+const VISION_RADIUS: isize = 12;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum BlockKind {
+    Floor,
+    // Goes up by one level towards its direction.
+    Ramp { direction: usize },
+    // Thin floor without ceiling, the space below stays open.
+    Bridge,
+}
+
+impl BlockKind {
+    // Levels taken from the floor to the ceiling.
+    fn height(self) -> isize {
+        match self {
+            BlockKind::Floor | BlockKind::Bridge => BLOCK_HEIGHT,
+            BlockKind::Ramp { .. } => BLOCK_HEIGHT + 1,
+        }
+    }
+
+    fn floor_color(self) -> Color {
+        match self {
+            BlockKind::Floor | BlockKind::Ramp { .. } => Color::White,
+            BlockKind::Bridge => Color::Yellow,
+        }
+    }
+
+    fn floor_bounds(self) -> (f32, f32) {
+        match self {
+            BlockKind::Floor => (0.0, FLOOR_TOP),
+            BlockKind::Ramp { .. } => (0.0, RAMP_TOP),
+            BlockKind::Bridge => (BRIDGE_BOTTOM, FLOOR_TOP),
+        }
+    }
+}
+
+#[derive(Debug)]
 struct VerticalBlock {
     floor: isize,
-    ceiling: isize,
+    kind: BlockKind,
     floor_entity: Entity,
-    ceiling_entity: Entity,
+    ceiling_entity: Option<Entity>,
+    visible: bool,
+}
+
+impl VerticalBlock {
+    // Whether the floor or the ceiling fills the height, in levels.
+    fn is_solid(&self, height: f32) -> bool {
+        let height = height - self.floor as f32;
+        let (floor_bottom, floor_top) = self.kind.floor_bounds();
+        let ceiling = self.kind.height() as f32;
+        (height >= floor_bottom && height < floor_top)
+            || (self.ceiling_entity.is_some()
+                && height >= ceiling + CEILING_BOTTOM
+                && height < ceiling + CEILING_TOP)
+    }
+
+    // The block is visible if the top of its floor or the bottom of its ceiling is.
+    fn surfaces(&self) -> impl Iterator<Item = f32> {
+        let floor = self.floor as f32;
+        let ceiling = (self.floor + self.kind.height()) as f32;
+        std::iter::once(floor + self.kind.floor_bounds().1)
+            .chain(self.ceiling_entity.map(|_| ceiling + CEILING_BOTTOM))
+    }
+
+    // Height at which the block is entered or left through the side facing the direction, if it can
+    // be at all.
+    fn side_height(&self, direction: usize) -> Option<isize> {
+        match self.kind {
+            BlockKind::Floor | BlockKind::Bridge => Some(self.floor),
+            BlockKind::Ramp { direction: up } if direction == up => Some(self.floor + 1),
+            BlockKind::Ramp { direction: up } if direction == (up + 3) % 6 => Some(self.floor),
+            BlockKind::Ramp { .. } => None,
+        }
+    }
+
+    fn can_move_to(&self, direction: usize, other: &VerticalBlock) -> bool {
+        match (self.kind, other.kind) {
+            // Side by side ramps make a wider slope.
+            (BlockKind::Ramp { direction: a }, BlockKind::Ramp { direction: b })
+                if a == b && direction % 3 != a % 3 =>
+            {
+                self.floor == other.floor
+            }
+            _ => match (
+                self.side_height(direction),
+                other.side_height((direction + 3) % 6),
+            ) {
+                // Only floors have steps.
+                (Some(from), Some(to))
+                    if self.kind == other.kind && self.kind == BlockKind::Floor =>
+                {
+                    (from - to).abs() <= 1
+                }
+                (Some(from), Some(to)) => from == to,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Dispose for VerticalBlock {
+    fn dispose(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entity(self.floor_entity)
+            .expect("delete entity");
+        if let Some(entity) = self.ceiling_entity.take() {
+            data.world.delete_entity(entity).expect("delete entity");
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum BuildMode {
+    // Steps up or down following the vertical direction.
+    Steps,
+    Ramps,
+    Bridges,
 }
 
 pub struct HexBumpyBuilderDemo {
-    world: BTreeMap<AxialVector, BTreeSet<VerticalBlock>>,
+    // Blocks from their floor to their ceiling.
+    world: RectHashStorage<IntervalMap<VerticalBlock>>,
     pointer: HexPointer,
+    build_mode: BuildMode,
 }
 
 impl HexBumpyBuilderDemo {
     pub fn new() -> Self {
         Self {
-            world: BTreeMap::new(),
+            world: RectHashStorage::new(),
             pointer: HexPointer::new_with_level_height(LEVEL_HEIGHT),
+            build_mode: BuildMode::Steps,
+        }
+    }
+
+    // The block the pointer stands on, the pointer is at the height of the side it entered a ramp
+    // through.
+    fn current_block(&self) -> Option<&VerticalBlock> {
+        self.world
+            .get(self.pointer.position())?
+            .containing(self.pointer.height())
+            .map(|(_, block)| block)
+    }
+
+    fn step(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        let direction = self.pointer.direction();
+        let next_pos = self.pointer.position().neighbor(direction);
+        let current = match self.current_block() {
+            Some(block) => block,
+            None => return,
+        };
+        // Ramps and steps are at most two levels away.
+        let reachable = self.world.get(next_pos).and_then(|vblock| {
+            vblock
+                .starting_in(current.floor - 2..=current.floor + 2)
+                .map(|(_, block)| block)
+                .filter(|block| current.can_move_to(direction, block))
+                .min_by_key(|block| (block.floor - current.floor).abs())
+        });
+        if let Some(block) = reachable {
+            let height = block
+                .side_height((direction + 3) % 6)
+                .unwrap_or_else(|| self.pointer.height());
+            self.pointer.set_position(next_pos, height, data, world);
+            return;
+        }
+        let exit = match current.side_height(direction) {
+            Some(height) => height,
+            // Nothing is built off the sides of a ramp.
+            None => return,
+        };
+        let (floor, kind) = match (self.build_mode, self.pointer.vertical_direction()) {
+            (BuildMode::Bridges, _) => (exit, BlockKind::Bridge),
+            (BuildMode::Ramps, VerticalDirection::Up) => (exit, BlockKind::Ramp { direction }),
+            (BuildMode::Ramps, VerticalDirection::Down) => (
+                exit - 1,
+                BlockKind::Ramp {
+                    direction: (direction + 3) % 6,
+                },
+            ),
+            (_, VerticalDirection::Horizontal) => (exit, BlockKind::Floor),
+            // Only floors have steps.
+            (BuildMode::Steps, _) if current.kind != BlockKind::Floor => (exit, BlockKind::Floor),
+            (BuildMode::Steps, VerticalDirection::Up) => (exit + 1, BlockKind::Floor),
+            (BuildMode::Steps, VerticalDirection::Down) => (exit - 1, BlockKind::Floor),
+        };
+        let range = floor..=floor + kind.height();
+        let vblock = self.world.entry(next_pos).or_insert_with(IntervalMap::new);
+        if !vblock.overlaps(range.clone()) {
+            let block = Self::new_block(data, world, next_pos, floor, kind);
+            let height = block
+                .side_height((direction + 3) % 6)
+                .expect("entered side");
+            vblock.insert(range, block).expect("free range");
+            self.pointer.set_position(next_pos, height, data, world);
+        }
+    }
+
+    fn new_block(
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+        position: AxialVector,
+        floor: isize,
+        kind: BlockKind,
+    ) -> VerticalBlock {
+        let ceiling = floor + kind.height();
+        VerticalBlock {
+            floor,
+            kind,
+            floor_entity: match kind {
+                BlockKind::Floor => Self::create_floor(data, world, position, floor),
+                BlockKind::Ramp { direction } => {
+                    Self::create_ramp(data, world, position, floor, direction)
+                }
+                BlockKind::Bridge => Self::create_bridge(data, world, position, floor),
+            },
+            ceiling_entity: match kind {
+                BlockKind::Floor | BlockKind::Ramp { .. } => {
+                    Some(Self::create_ceiling(data, world, position, ceiling))
+                }
+                BlockKind::Bridge => None,
+            },
+            visible: true,
+        }
+    }
+
+    // Ceilings and floors hide what is behind them.
+    fn update_visibility(
+        &mut self,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        let eye = self.pointer.position();
+        let eye_height = self.pointer.height() as f32 + EYE_HEIGHT;
+        let columns = &self.world;
+        let is_solid = |position: AxialVector, height: f32| {
+            matches!(
+                columns
+                    .get(position)
+                    .and_then(|vblock| vblock.containing(height.floor() as isize)),
+                Some((_, block)) if block.is_solid(height)
+            )
+        };
+        let changes = columns
+            .iter()
+            .flat_map(|(position, vblock)| vblock.values().map(move |block| (position, block)))
+            .filter_map(|(position, block)| {
+                let visible = position.distance(eye) <= VISION_RADIUS
+                    && block.surfaces().any(|height| {
+                        is_in_line_of_sight(eye, eye_height, position, height, &is_solid)
+                    });
+                if visible != block.visible {
+                    Some((position, block.floor, visible))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut material_storage = data.world.write_storage::<Handle<Material>>();
+        let material = |color: Color, visible: bool| {
+            let color_data = &world.assets.color_data[&color];
+            if visible {
+                color_data.light.clone()
+            } else {
+                color_data.dark.clone()
+            }
+        };
+        for (position, floor, visible) in changes {
+            let block = match self
+                .world
+                .get_mut(position)
+                .and_then(|vblock| vblock.get_mut(floor))
+            {
+                Some((_, block)) => block,
+                None => continue,
+            };
+            block.visible = visible;
+            if let Some(handle) = material_storage.get_mut(block.floor_entity) {
+                *handle = material(block.kind.floor_color(), visible);
+            }
+            if let Some(entity) = block.ceiling_entity {
+                if let Some(handle) = material_storage.get_mut(entity) {
+                    *handle = material(Color::Red, visible);
+                }
+            }
         }
     }
 
@@ -52,13 +341,62 @@ impl HexBumpyBuilderDemo {
         transform.set_scale(Vector3::new(0.8, 0.2, 0.8));
         // Floor is solid from 0.0 to height.
         let pos = (position, floor as f32 * LEVEL_HEIGHT + 0.2).into();
-        world.transform_axial(pos, &mut transform);
+        world.transform_hex(pos, &mut transform);
+        let material = world.assets.color_data[&Color::White].light.clone();
+        data.world
+            .create_entity()
+            .with(world.assets.block_handle.clone())
+            .with(material)
+            .with(transform)
+            .with(ChunkTag::new(position))
+            .with(HexTag::new(position, HexLayer::Floor, "bumpy builder"))
+            .build()
+    }
+
+    fn create_ramp(
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+        position: AxialVector,
+        floor: isize,
+        direction: usize,
+    ) -> Entity {
+        let mut transform = Transform::default();
+        // Height = 0.9 on the high side, the low side is as high as a floor.
+        transform.set_scale(Vector3::new(0.8, 0.45, 0.8));
+        let pos = (position, floor as f32 * LEVEL_HEIGHT + 0.45).into();
+        world.transform_hex(pos, &mut transform);
+        // The mesh goes up towards x, like the pointer.
+        transform.append_rotation_y_axis(-(direction as f32) * std::f32::consts::PI / 3.0);
         let material = world.assets.color_data[&Color::White].light.clone();
         data.world
             .create_entity()
-            .with(world.assets.hex_handle.clone())
+            .with(world.assets.ramp_handle.clone())
             .with(material)
             .with(transform)
+            .with(ChunkTag::new(position))
+            .with(HexTag::new(position, HexLayer::Ramp, "bumpy builder"))
+            .build()
+    }
+
+    fn create_bridge(
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+        position: AxialVector,
+        floor: isize,
+    ) -> Entity {
+        let mut transform = Transform::default();
+        // Height = 0.1, level with the top of the floors.
+        transform.set_scale(Vector3::new(0.8, 0.05, 0.8));
+        let pos = (position, floor as f32 * LEVEL_HEIGHT + 0.35).into();
+        world.transform_hex(pos, &mut transform);
+        let material = world.assets.color_data[&Color::Yellow].light.clone();
+        data.world
+            .create_entity()
+            .with(world.assets.block_handle.clone())
+            .with(material)
+            .with(transform)
+            .with(ChunkTag::new(position))
+            .with(HexTag::new(position, HexLayer::Bridge, "bumpy builder"))
             .build()
     }
 
@@ -72,13 +410,15 @@ impl HexBumpyBuilderDemo {
         // Height = 0.1
         transform.set_scale(Vector3::new(0.8, 0.05, 0.8));
         let pos = (position, (ceiling as f32 + 0.7) * LEVEL_HEIGHT).into();
-        world.transform_axial(pos, &mut transform);
+        world.transform_hex(pos, &mut transform);
         let material = world.assets.color_data[&Color::Red].light.clone();
         data.world
             .create_entity()
-            .with(world.assets.hex_handle.clone())
+            .with(world.assets.block_handle.clone())
             .with(material)
             .with(transform)
+            .with(ChunkTag::new(position))
+            .with(HexTag::new(position, HexLayer::Ceiling, "bumpy builder"))
             .build()
     }
 }
@@ -87,35 +427,20 @@ impl SimpleState for HexBumpyBuilderDemo {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.pointer.create_entities(&mut data, &world);
-        let vblock = self
-            .world
-            .entry(self.pointer.position())
-            .or_insert_with(BTreeSet::new);
-        vblock.insert(VerticalBlock {
-            floor: 0,
-            ceiling: BLOCK_HEIGHT,
-            floor_entity: Self::create_floor(&mut data, &world, self.pointer.position(), 0),
-            ceiling_entity: Self::create_ceiling(
-                &mut data,
-                &world,
-                self.pointer.position(),
-                BLOCK_HEIGHT,
-            ),
-        });
+        let position = self.pointer.position();
+        let block = Self::new_block(&mut data, &world, position, 0, BlockKind::Floor);
+        self.world
+            .entry(position)
+            .or_insert_with(IntervalMap::new)
+            .insert(0..=BlockKind::Floor.height(), block)
+            .expect("empty hex");
+        self.update_visibility(&mut data, &world);
     }
 
     fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.pointer.delete_entities(&mut data, &world);
-        for block in self.world.iter().flat_map(|(_, vblock)| vblock.iter()) {
-            data.world
-                .delete_entity(block.floor_entity)
-                .expect("delete entity");
-            data.world
-                .delete_entity(block.ceiling_entity)
-                .expect("delete entity");
-        }
-        self.world.clear();
+        self.world.dispose(&mut data);
     }
 
     fn handle_event(
@@ -124,6 +449,18 @@ impl SimpleState for HexBumpyBuilderDemo {
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
+            if FollowMeSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if inspect::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if GridSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if LightingConfig::handle_event(&data, &event) {
+                return Trans::None;
+            }
             let mut trans = Trans::None;
             let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
             match get_key(&event) {
@@ -136,66 +473,26 @@ impl SimpleState for HexBumpyBuilderDemo {
                 Some((VirtualKeyCode::Left, ElementState::Pressed)) => {
                     self.pointer.decrement_direction(&data, &world);
                 }
+                Some((VirtualKeyCode::C, ElementState::Pressed)) => {
+                    world.toggle_follow(&data);
+                }
                 Some((VirtualKeyCode::Up, ElementState::Pressed)) => {
                     self.pointer.increment_vertical_direction(&data, &world);
                 }
                 Some((VirtualKeyCode::Down, ElementState::Pressed)) => {
                     self.pointer.decrement_vertical_direction(&data, &world);
                 }
-                Some((VirtualKeyCode::Space, ElementState::Pressed)) => {
-                    let next_pos = self.pointer.position().neighbor(self.pointer.direction());
-                    let next_floor = match self.pointer.vertical_direction() {
-                        VerticalDirection::Horizontal => self.pointer.height(),
-                        VerticalDirection::Down => self.pointer.height() - 1,
-                        VerticalDirection::Up => self.pointer.height() + 1,
+                Some((VirtualKeyCode::B, ElementState::Pressed)) => {
+                    self.build_mode = match self.build_mode {
+                        BuildMode::Steps => BuildMode::Ramps,
+                        BuildMode::Ramps => BuildMode::Bridges,
+                        BuildMode::Bridges => BuildMode::Steps,
                     };
-                    let next_ceiling = next_floor + BLOCK_HEIGHT;
-                    let vblock = self.world.entry(next_pos).or_insert_with(BTreeSet::new);
-                    // Really need an interval tree for that
-                    enum Movement {
-                        Void,
-                        Go { height: isize },
-                        Blocked,
-                    }
-                    let mut movement = Movement::Void;
-                    for block in vblock.iter() {
-                        if (block.floor - self.pointer.height()).abs() <= 1 {
-                            // Just go regardless of the vertical direction
-                            movement = Movement::Go {
-                                height: block.floor,
-                            };
-                            break;
-                        }
-                        if block.ceiling >= next_floor {
-                            if block.floor <= next_ceiling {
-                                movement = Movement::Blocked;
-                            }
-                            break;
-                        }
-                    }
-                    match movement {
-                        Movement::Void => {
-                            vblock.insert(VerticalBlock {
-                                floor: next_floor,
-                                ceiling: next_ceiling,
-                                floor_entity: Self::create_floor(
-                                    &mut data, &world, next_pos, next_floor,
-                                ),
-                                ceiling_entity: Self::create_ceiling(
-                                    &mut data,
-                                    &world,
-                                    next_pos,
-                                    next_ceiling,
-                                ),
-                            });
-                            self.pointer
-                                .set_position(next_pos, next_floor, &data, &world);
-                        }
-                        Movement::Go { height } => {
-                            self.pointer.set_position(next_pos, height, &data, &world);
-                        }
-                        Movement::Blocked => {}
-                    }
+                    info!("Building {:?}", self.build_mode);
+                }
+                Some((VirtualKeyCode::Space, ElementState::Pressed)) => {
+                    self.step(&mut data, &world);
+                    self.update_visibility(&mut data, &world);
                 }
                 _ => {}
             }
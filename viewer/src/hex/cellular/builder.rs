@@ -1,40 +1,140 @@
 use crate::{
     hex::{
-        cellular::world::{FovState, MoveMode, World},
+        cellular::world::World,
+        inspect,
+        pointer::{HexPointer, PointerAction, PointerKeys, PointerSettings},
         render::renderer::HexRenderer,
-        shape::cubic_range::CubicRangeShape,
+        scale::WorldScale,
+        visibility::FovState,
     },
     input::get_key_and_modifiers,
+    pacing::StepPacer,
+    sound::{play_cue, SoundCue},
+    systems::{follow_me::FollowMeSettings, grid_overlay::GridSettings, lighting::LightingConfig},
     world::RhombusViewerWorld,
 };
 use amethyst::{
-    core::timing::Time, ecs::prelude::*, input::ElementState, prelude::*, winit::VirtualKeyCode,
+    config::Config, ecs::prelude::*, input::ElementState, prelude::*, winit::VirtualKeyCode,
+};
+use log::warn;
+use rhombus_core::hex::{coordinates::axial::AxialVector, shape::cubic_range::CubicRangeShape};
+use rhombus_mapgen::cellular::{
+    Border, CellularRules, HexState, NoisyWallRatio, RadialWallRatio, RuleCursor, RuleStep,
+    Symmetry, WallRatio,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
 };
-use std::sync::Arc;
 
 const CELL_RADIUS_RATIO_DEN: usize = 42;
 const WALL_RATIO: f32 = 0.5;
+const STEP_MILLIS: u64 = 500;
+const MAX_BORDER_THICKNESS: usize = 6;
 
 #[derive(Debug, PartialEq, Eq)]
 enum CellularState {
-    GrowingPhase1,
-    GrowingPhase2(usize),
+    Growing(RuleCursor),
     Grown,
-    FieldOfView(bool),
+    FieldOfView(FovState),
+}
+
+// How the big cells start before the automaton runs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Seeding {
+    Flat,
+    // Denser towards the edges.
+    Radial,
+    // Denser and sparser patches, following the seed.
+    Noisy,
+}
+
+impl Seeding {
+    fn next(self) -> Self {
+        match self {
+            Seeding::Flat => Seeding::Radial,
+            Seeding::Radial => Seeding::Noisy,
+            Seeding::Noisy => Seeding::Flat,
+        }
+    }
+}
+
+struct SeedingWallRatio {
+    seeding: Seeding,
+    seed: u64,
+}
+
+impl WallRatio for SeedingWallRatio {
+    fn wall_ratio(&self, shape: &CubicRangeShape, position: AxialVector) -> f32 {
+        match self.seeding {
+            Seeding::Flat => WALL_RATIO,
+            Seeding::Radial => RadialWallRatio {
+                center: 0.4,
+                edge: 0.6,
+            }
+            .wall_ratio(shape, position),
+            Seeding::Noisy => NoisyWallRatio {
+                base: WALL_RATIO,
+                amplitude: 0.15,
+                wavelength: 16,
+                seed: self.seed,
+            }
+            .wall_ratio(shape, position),
+        }
+    }
+}
+
+// Where the automaton rules are read from, see config/cellular.ron.
+pub struct CellularRulesSettings {
+    pub path: PathBuf,
 }
 
 pub struct HexCellularBuilder<R: HexRenderer> {
     world: World<R>,
-    remaining_millis: u64,
+    keys: PointerKeys,
+    pacer: StepPacer,
     state: CellularState,
+    border: Border,
+    seeding: Seeding,
+    rules: CellularRules,
 }
 
 impl<R: HexRenderer> HexCellularBuilder<R> {
     pub fn new(renderer: R) -> Self {
         Self {
             world: World::new(renderer),
-            remaining_millis: 0,
+            keys: PointerKeys::default(),
+            pacer: StepPacer::new(STEP_MILLIS),
             state: CellularState::Grown,
+            border: Border::default(),
+            seeding: Seeding::Flat,
+            rules: CellularRules::default(),
+        }
+    }
+
+    fn apply_pointer_action(
+        &mut self,
+        action: PointerAction,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        match action {
+            PointerAction::Move(mode) => self.world.next_position(mode, data),
+            PointerAction::TurnLeft => self.world.turn(HexPointer::decrement_direction, data),
+            PointerAction::TurnRight => self.world.turn(HexPointer::increment_direction, data),
+        }
+    }
+
+    // The rules are read again every time so that they can be tweaked without restarting.
+    fn start_growing(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        self.rules = load_rules(&data.world.read_resource::<CellularRulesSettings>().path);
+        self.state = CellularState::Growing(RuleCursor::default());
+        self.pacer.reset();
+    }
+
+    fn wall_ratio(&self) -> SeedingWallRatio {
+        SeedingWallRatio {
+            seeding: self.seeding,
+            seed: self.world.seed().seed(),
         }
     }
 
@@ -47,23 +147,50 @@ impl<R: HexRenderer> HexCellularBuilder<R> {
                 (-world_radius, world_radius),
             ),
             CELL_RADIUS_RATIO_DEN,
-            WALL_RATIO,
+            self.wall_ratio(),
+            self.border,
             data,
         );
-        self.state = CellularState::GrowingPhase1;
-        self.remaining_millis = 0;
+        self.start_growing(data);
+    }
+}
+
+// A missing file silently falls back to the default rules, a broken one is worth a warning.
+fn load_rules(path: &Path) -> CellularRules {
+    if !path.exists() {
+        return CellularRules::default();
+    }
+    match CellularRules::load(path)
+        .map_err(|err| err.to_string())
+        .and_then(|rules| {
+            rules.validate()?;
+            Ok(rules)
+        }) {
+        Ok(rules) => rules,
+        Err(err) => {
+            warn!(
+                "Failed to read cellular rules file: `{}` ({}), using the default rules.",
+                path.display(),
+                err
+            );
+            CellularRules::default()
+        }
     }
 }
 
 impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.keys.release_all();
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         world.set_camera_distance(&data, 300.0);
+        self.world.seed_mut().start(&data);
         self.reset(&mut data);
+        self.pacer.start(&data);
         self.world.update_renderer_world(true, &mut data);
     }
 
     fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.pacer.stop(&data);
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.world.clear(&mut data, &world);
     }
@@ -74,58 +201,115 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
+            if PointerSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if FollowMeSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if inspect::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if GridSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if LightingConfig::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if let Some(action) = self.keys.handle_event(&data, &event) {
+                self.apply_pointer_action(action, &mut data);
+                return Trans::None;
+            }
+            if self.pacer.handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if WorldScale::handle_event(&data, &event) {
+                self.world.update_renderer_world(true, &mut data);
+                return Trans::None;
+            }
             let mut trans = Trans::None;
             match get_key_and_modifiers(&event) {
                 Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
                     trans = Trans::Pop;
                 }
-                Some((VirtualKeyCode::N, ElementState::Pressed, _)) => {
-                    self.world
-                        .reset_world(CELL_RADIUS_RATIO_DEN, WALL_RATIO, &mut data);
-                    self.state = CellularState::GrowingPhase1;
-                    self.remaining_millis = 0;
-                }
-                Some((VirtualKeyCode::Right, ElementState::Pressed, modifiers)) => {
-                    if modifiers.shift {
-                        self.world
-                            .next_position(MoveMode::StrafeRightAhead, &mut data);
-                    } else if modifiers.ctrl {
-                        self.world
-                            .next_position(MoveMode::StrafeRightBack, &mut data);
-                    } else {
-                        self.world.increment_direction(&data);
+                Some((VirtualKeyCode::N, ElementState::Pressed, modifiers)) => {
+                    if !modifiers.shift {
+                        self.world.seed_mut().renew();
                     }
+                    self.world.reset_world(
+                        CELL_RADIUS_RATIO_DEN,
+                        self.wall_ratio(),
+                        self.border,
+                        &mut data,
+                    );
+                    self.start_growing(&data);
                 }
-                Some((VirtualKeyCode::Left, ElementState::Pressed, modifiers)) => {
-                    if modifiers.shift {
-                        self.world
-                            .next_position(MoveMode::StrafeLeftAhead, &mut data);
-                    } else if modifiers.ctrl {
-                        self.world
-                            .next_position(MoveMode::StrafeLeftBack, &mut data);
+                Some((VirtualKeyCode::B, ElementState::Pressed, modifiers)) => {
+                    self.border.thickness = if modifiers.shift {
+                        self.border.thickness.saturating_sub(1)
                     } else {
-                        self.world.decrement_direction(&data);
-                    }
+                        (self.border.thickness + 1).min(MAX_BORDER_THICKNESS)
+                    };
+                    self.world.reset_world(
+                        CELL_RADIUS_RATIO_DEN,
+                        self.wall_ratio(),
+                        self.border,
+                        &mut data,
+                    );
+                    self.start_growing(&data);
+                }
+                Some((VirtualKeyCode::O, ElementState::Pressed, _)) => {
+                    self.border.state = match self.border.state {
+                        HexState::HardWall => HexState::Open,
+                        _ => HexState::HardWall,
+                    };
+                    self.world.reset_world(
+                        CELL_RADIUS_RATIO_DEN,
+                        self.wall_ratio(),
+                        self.border,
+                        &mut data,
+                    );
+                    self.start_growing(&data);
+                }
+                Some((VirtualKeyCode::S, ElementState::Pressed, _)) => {
+                    self.seeding = self.seeding.next();
+                    self.world.reset_world(
+                        CELL_RADIUS_RATIO_DEN,
+                        self.wall_ratio(),
+                        self.border,
+                        &mut data,
+                    );
+                    self.start_growing(&data);
                 }
-                Some((VirtualKeyCode::Up, ElementState::Pressed, _)) => {
-                    self.world.next_position(MoveMode::StraightAhead, &mut data);
+                Some((VirtualKeyCode::Y, ElementState::Pressed, _)) => {
+                    self.world.set_symmetry(self.world.symmetry().next());
+                    self.world.reset_world(
+                        CELL_RADIUS_RATIO_DEN,
+                        self.wall_ratio(),
+                        self.border,
+                        &mut data,
+                    );
+                    self.start_growing(&data);
                 }
-                Some((VirtualKeyCode::Down, ElementState::Pressed, _)) => {
-                    self.world.next_position(MoveMode::StraightBack, &mut data);
+                Some((VirtualKeyCode::LBracket, ElementState::Pressed, _)) => {
+                    if self.world.step_backward(&mut data) {
+                        self.world.update_renderer_world(true, &mut data);
+                    }
+                }
+                Some((VirtualKeyCode::RBracket, ElementState::Pressed, _)) => {
+                    if self.world.step_forward(&mut data) {
+                        self.world.update_renderer_world(true, &mut data);
+                    }
                 }
                 Some((VirtualKeyCode::C, ElementState::Pressed, _)) => {
                     let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
                     world.toggle_follow(&data);
                 }
                 Some((VirtualKeyCode::V, ElementState::Pressed, _)) => {
-                    if let CellularState::FieldOfView(mut fov_enabled) = self.state {
-                        fov_enabled = !fov_enabled;
-                        self.world.change_field_of_view(if fov_enabled {
-                            FovState::Full
-                        } else {
-                            FovState::Partial
-                        });
-                        self.state = CellularState::FieldOfView(fov_enabled);
+                    if let CellularState::FieldOfView(fov_state) = self.state {
+                        let fov_state = fov_state.next();
+                        self.world.change_field_of_view(fov_state);
+                        self.state = CellularState::FieldOfView(fov_state);
                     }
                 }
                 Some((VirtualKeyCode::F, ElementState::Pressed, modifiers)) => {
@@ -136,11 +320,11 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
                             CubicRangeShape::stretch_x_start
                         },
                         CELL_RADIUS_RATIO_DEN,
-                        WALL_RATIO,
+                        self.wall_ratio(),
+                        self.border,
                         &mut data,
                     ) {
-                        self.state = CellularState::GrowingPhase1;
-                        self.remaining_millis = 0;
+                        self.start_growing(&data);
                     }
                 }
                 Some((VirtualKeyCode::G, ElementState::Pressed, modifiers)) => {
@@ -151,11 +335,11 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
                             CubicRangeShape::stretch_x_end
                         },
                         CELL_RADIUS_RATIO_DEN,
-                        WALL_RATIO,
+                        self.wall_ratio(),
+                        self.border,
                         &mut data,
                     ) {
-                        self.state = CellularState::GrowingPhase1;
-                        self.remaining_millis = 0;
+                        self.start_growing(&data);
                     }
                 }
                 Some((VirtualKeyCode::H, ElementState::Pressed, modifiers)) => {
@@ -166,11 +350,11 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
                             CubicRangeShape::stretch_y_start
                         },
                         CELL_RADIUS_RATIO_DEN,
-                        WALL_RATIO,
+                        self.wall_ratio(),
+                        self.border,
                         &mut data,
                     ) {
-                        self.state = CellularState::GrowingPhase1;
-                        self.remaining_millis = 0;
+                        self.start_growing(&data);
                     }
                 }
                 Some((VirtualKeyCode::J, ElementState::Pressed, modifiers)) => {
@@ -181,11 +365,11 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
                             CubicRangeShape::stretch_y_end
                         },
                         CELL_RADIUS_RATIO_DEN,
-                        WALL_RATIO,
+                        self.wall_ratio(),
+                        self.border,
                         &mut data,
                     ) {
-                        self.state = CellularState::GrowingPhase1;
-                        self.remaining_millis = 0;
+                        self.start_growing(&data);
                     }
                 }
                 Some((VirtualKeyCode::K, ElementState::Pressed, modifiers)) => {
@@ -196,11 +380,11 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
                             CubicRangeShape::stretch_z_start
                         },
                         CELL_RADIUS_RATIO_DEN,
-                        WALL_RATIO,
+                        self.wall_ratio(),
+                        self.border,
                         &mut data,
                     ) {
-                        self.state = CellularState::GrowingPhase1;
-                        self.remaining_millis = 0;
+                        self.start_growing(&data);
                     }
                 }
                 Some((VirtualKeyCode::L, ElementState::Pressed, modifiers)) => {
@@ -211,11 +395,11 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
                             CubicRangeShape::stretch_z_end
                         },
                         CELL_RADIUS_RATIO_DEN,
-                        WALL_RATIO,
+                        self.wall_ratio(),
+                        self.border,
                         &mut data,
                     ) {
-                        self.state = CellularState::GrowingPhase1;
-                        self.remaining_millis = 0;
+                        self.start_growing(&data);
                     }
                 }
                 _ => {}
@@ -227,53 +411,66 @@ impl<R: HexRenderer> SimpleState for HexCellularBuilder<R> {
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(action) = self.keys.repeat(data) {
+            self.apply_pointer_action(action, data);
+        }
+        let seed = self.world.seed().seed();
+        let seed = match self.seeding {
+            Seeding::Flat => format!("seed {}", seed),
+            seeding => format!("seed {} - {:?} walls", seed, seeding),
+        };
+        let mut status = match self.world.symmetry() {
+            Symmetry::None => format!("{} - {}", seed, self.world.stats()),
+            symmetry => format!("{} - {:?} - {}", seed, symmetry, self.world.stats()),
+        };
+        if let CellularState::Growing(cursor) = &self.state {
+            // The smoothing, if any, comes after the last phase.
+            if cursor.phase() == self.rules.phases.len() {
+                status = format!("Smoothing - {}", status);
+            }
+        }
+        self.pacer.set_status(data, status);
         if let CellularState::FieldOfView(..) = self.state {
             self.world.update_renderer_world(false, data);
-            self.remaining_millis = 0;
+            self.pacer.reset();
+            return Trans::None;
+        }
+        if !self.world.is_at_present() {
+            self.pacer.reset();
             return Trans::None;
         }
-        let delta_millis = {
-            let duration = data.world.read_resource::<Time>().delta_time();
-            duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
-        } + self.remaining_millis;
-        let num = delta_millis / 500;
-        self.remaining_millis = delta_millis % 500;
         let mut force_update = false;
-        for _ in 0..num {
-            match self.state {
-                CellularState::GrowingPhase1 => {
-                    self.world.cellular_automaton_phase1_step1();
-                    let frozen = self.world.cellular_automaton_phase1_step2(
-                        |count| count >= 5 && count <= 6,
-                        |count| count >= 3 && count <= 6,
-                    );
-                    if frozen {
-                        self.world.expand(data);
+        self.pacer.start_frame(data);
+        while self.pacer.next_step() {
+            match &mut self.state {
+                CellularState::Growing(cursor) => {
+                    let expanded = cursor.is_expanded();
+                    let step = self.world.run_rules_step(&self.rules, cursor, data);
+                    if cursor.is_expanded() != expanded {
                         force_update = true;
-                        self.state = CellularState::GrowingPhase2(2);
                     }
-                }
-                CellularState::GrowingPhase2(countdown) => {
-                    self.world.cellular_automaton_phase2_step1();
-                    self.world.cellular_automaton_phase2_step2(
-                        |count| count >= 3 && count <= 6,
-                        |count| count >= 3 && count <= 6,
-                    );
-                    if countdown > 1 {
-                        self.state = CellularState::GrowingPhase2(countdown - 1)
-                    } else {
-                        self.state = CellularState::Grown;
+                    match step {
+                        RuleStep::PhaseComplete => play_cue(data, SoundCue::PhaseComplete),
+                        RuleStep::Done => {
+                            self.state = CellularState::Grown;
+                            play_cue(data, SoundCue::PhaseComplete);
+                        }
+                        _ => {}
                     }
+                    self.world.record_step();
                 }
                 CellularState::Grown => {
+                    self.world.shrink_to_content(data);
+                    force_update = true;
                     self.world.create_pointer(FovState::Partial, data);
-                    self.state = CellularState::FieldOfView(false);
+                    self.state = CellularState::FieldOfView(FovState::Partial);
                 }
                 CellularState::FieldOfView(..) => {
                     break;
                 }
             }
         }
+        self.pacer.end_frame(data);
         self.world.update_renderer_world(force_update, data);
         Trans::None
     }
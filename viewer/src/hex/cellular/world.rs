@@ -1,10 +1,11 @@
 use crate::{
     dispose::Dispose,
     hex::{
-        pointer::HexPointer,
+        pointer::{HexPointer, MoveMode},
         render::renderer::HexRenderer,
-        shape::cubic_range::{CubicRangeShape, Range},
+        visibility::{turn_pointer, FovState, PointerTurn, SightSettings, VisibilityController},
     },
+    seed::DemoSeed,
     world::RhombusViewerWorld,
 };
 use amethyst::{
@@ -12,108 +13,79 @@ use amethyst::{
     prelude::*,
     renderer::{debug_drawing::DebugLinesComponent, palette::Srgba},
 };
-use rand::{thread_rng, RngCore};
 use rhombus_core::hex::{
-    coordinates::{axial::AxialVector, cubic::CubicVector, direction::HexagonalDirection},
-    field_of_view::FieldOfView,
-    storage::hash::RectHashStorage,
+    shape::cubic_range::{CubicRangeShape, ResizeAnchor},
+    storage::{
+        delta::{HexChange, StorageDelta},
+        hash::RectHashStorageStats,
+        history::StorageHistory,
+    },
 };
-use std::{collections::HashSet, sync::Arc};
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum HexState {
-    Open,
-    Wall,
-    HardWall,
-}
-
-pub struct HexData {
-    state: HexState,
-    automaton_count: u8,
-}
+use rhombus_mapgen::cellular::{
+    Border, CellularRules, CellularWorld, HexData, HexState, RuleCursor, RuleStep, Symmetry,
+    WallRatio,
+};
+use std::{num::NonZeroUsize, sync::Arc, thread};
 
 impl Dispose for HexData {
     fn dispose(&mut self, _data: &mut StateData<'_, GameData<'_, '_>>) {}
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum FovState {
-    Partial,
-    Full,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum MoveMode {
-    StraightAhead,
-    StrafeLeftAhead,
-    StrafeLeftBack,
-    StrafeRightAhead,
-    StrafeRightBack,
-    StraightBack,
-}
-
 pub struct World<R: HexRenderer> {
-    shape: CubicRangeShape,
-    cell_radius: usize,
+    generator: CellularWorld<R::Hex>,
     limits_entity: Option<Entity>,
-    hexes: RectHashStorage<(HexData, R::Hex)>,
     renderer: R,
     renderer_dirty: bool,
-    pointer: Option<(HexPointer, FovState)>,
+    pointer: Option<(HexPointer, VisibilityController)>,
+    history: StorageHistory<HexState>,
+    seed: DemoSeed,
 }
 
 impl<R: HexRenderer> World<R> {
     pub fn new(renderer: R) -> Self {
+        let mut generator = CellularWorld::new();
+        generator.set_threads(thread::available_parallelism().map_or(1, NonZeroUsize::get));
         Self {
-            shape: CubicRangeShape::default(),
-            cell_radius: 1,
+            generator,
             limits_entity: None,
-            hexes: RectHashStorage::new(),
             renderer,
             renderer_dirty: false,
             pointer: None,
+            history: StorageHistory::new(),
+            seed: DemoSeed::new(),
         }
     }
 
-    pub fn set_shape_and_reset_world(
-        &mut self,
-        shape: CubicRangeShape,
-        cell_radius_ratio_den: usize,
-        wall_ratio: f32,
-        data: &mut StateData<'_, GameData<'_, '_>>,
-    ) {
-        self.shape = shape;
-        self.reset_world(cell_radius_ratio_den, wall_ratio, data);
+    pub fn seed(&self) -> &DemoSeed {
+        &self.seed
     }
 
-    fn for_each_big_cell<F>(center: AxialVector, cell_radius: usize, mut f: F)
-    where
-        F: FnMut(AxialVector) -> bool,
-    {
-        let mut r = 0;
-        loop {
-            let mut end = true;
-            for pos in center.big_ring_iter(cell_radius, r) {
-                let keep_going = f(pos);
-                if keep_going {
-                    end = false;
-                }
-            }
-            if end {
-                break;
-            }
-            r += 1;
-        }
+    pub fn seed_mut(&mut self) -> &mut DemoSeed {
+        &mut self.seed
     }
 
-    pub fn reset_world(
+    // The same seed generates the same cave again.
+    pub fn set_shape_and_reset_world<W: WallRatio>(
         &mut self,
+        shape: CubicRangeShape,
         cell_radius_ratio_den: usize,
-        wall_ratio: f32,
+        wall_ratio: W,
+        border: Border,
         data: &mut StateData<'_, GameData<'_, '_>>,
     ) {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.clear(data, &world);
+        self.seed.restart();
+
+        let renderer = &mut self.renderer;
+        self.generator.reset(
+            shape,
+            cell_radius_ratio_den,
+            wall_ratio,
+            border,
+            self.seed.rng(),
+            |state| renderer.new_hex(state != HexState::Open, true),
+        );
 
         if let Some(entity) = self.limits_entity {
             let mut debug_lines_storage = data.world.write_storage::<DebugLinesComponent>();
@@ -126,101 +98,104 @@ impl<R: HexRenderer> World<R> {
             self.limits_entity = Some(data.world.create_entity().with(debug_lines).build());
         }
 
-        self.cell_radius = Self::compute_cell_radius(&self.shape, cell_radius_ratio_den);
-        let mut rng = thread_rng();
-        let internal_ranges: [Range; 3] = [
-            (
-                self.shape.range_x().start() + 1 + self.cell_radius as isize,
-                self.shape.range_x().end() - 1 - self.cell_radius as isize,
-            )
-                .into(),
-            (
-                self.shape.range_y().start() + 1 + self.cell_radius as isize,
-                self.shape.range_y().end() - 1 - self.cell_radius as isize,
-            )
-                .into(),
-            (
-                self.shape.range_z().start() + 1 + self.cell_radius as isize,
-                self.shape.range_z().end() - 1 - self.cell_radius as isize,
-            )
-                .into(),
-        ];
-        Self::for_each_big_cell(self.shape.center(), self.cell_radius, |pos| -> bool {
-            if !pos
-                .ring_iter(self.cell_radius)
-                .any(|v| self.shape.contains_position(v))
-            {
-                return false;
-            }
-            let cubic = CubicVector::from(pos);
-            let state = if internal_ranges[0].contains(cubic.x())
-                && internal_ranges[1].contains(cubic.y())
-                && internal_ranges[2].contains(cubic.z())
-            {
-                if ((rng.next_u32() & 0xffff) as f32 / 0x1_0000 as f32) < wall_ratio {
-                    HexState::Wall
-                } else {
-                    HexState::Open
-                }
-            } else {
-                HexState::HardWall
-            };
-            self.hexes.insert(
-                pos,
-                (
-                    HexData {
-                        state,
-                        automaton_count: 0,
-                    },
-                    self.renderer.new_hex(state != HexState::Open, true),
-                ),
-            );
-            for s in 1..=self.cell_radius {
-                for sub_pos in pos.ring_iter(s) {
-                    if self.shape.contains_position(sub_pos) {
-                        self.hexes.insert(
-                            sub_pos,
+        self.history.reset(self.generator.states());
+        self.renderer_dirty = true;
+    }
+
+    pub fn reset_world<W: WallRatio>(
+        &mut self,
+        cell_radius_ratio_den: usize,
+        wall_ratio: W,
+        border: Border,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        let shape = self.generator.shape().clone();
+        self.set_shape_and_reset_world(shape, cell_radius_ratio_den, wall_ratio, border, data);
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        self.generator.symmetry()
+    }
+
+    // Applies from the next reset.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.generator.set_symmetry(symmetry);
+    }
+
+    pub fn record_step(&mut self) {
+        self.history.record(self.generator.states());
+    }
+
+    pub fn is_at_present(&self) -> bool {
+        self.history.is_at_present()
+    }
+
+    pub fn step_backward(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> bool {
+        if let Some(delta) = self.history.step_backward().cloned() {
+            self.apply_delta(&delta, data);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn step_forward(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> bool {
+        if let Some(delta) = self.history.step_forward().cloned() {
+            self.apply_delta(&delta, data);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn apply_delta(
+        &mut self,
+        delta: &StorageDelta<HexState>,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        for (pos, change) in delta.iter() {
+            match change {
+                HexChange::Set(state) => {
+                    let hexes = self.generator.hexes_mut();
+                    if let Some(hex) = hexes.get_mut(pos) {
+                        hex.0.set_state(*state);
+                    } else {
+                        hexes.insert(
+                            pos,
                             (
-                                HexData {
-                                    state,
-                                    automaton_count: 0,
-                                },
-                                self.renderer.new_hex(state != HexState::Open, true),
+                                HexData::new(*state),
+                                self.renderer.new_hex(*state != HexState::Open, true),
                             ),
                         );
                     }
                 }
+                HexChange::Remove => {
+                    if let Some(mut hex) = self.generator.hexes_mut().remove(pos) {
+                        hex.dispose(data);
+                    }
+                }
             }
-            true
-        });
+        }
         self.renderer_dirty = true;
     }
 
-    pub fn try_resize_shape(
+    pub fn try_resize_shape<W: WallRatio>(
         &mut self,
         resize: fn(&mut CubicRangeShape, usize) -> bool,
         cell_radius_ratio_den: usize,
-        wall_ratio: f32,
+        wall_ratio: W,
+        border: Border,
         data: &mut StateData<'_, GameData<'_, '_>>,
     ) -> bool {
-        if resize(&mut self.shape, 1) {
-            self.reset_world(cell_radius_ratio_den, wall_ratio, data);
+        let mut shape = self.generator.shape().clone();
+        if shape.resize(resize, 1, ResizeAnchor::Center) {
+            self.set_shape_and_reset_world(shape, cell_radius_ratio_den, wall_ratio, border, data);
             true
         } else {
             false
         }
     }
 
-    fn compute_cell_radius(shape: &CubicRangeShape, cell_radius_ratio_den: usize) -> usize {
-        let mut deltas = [
-            shape.range_x().end() - shape.range_x().start(),
-            shape.range_y().end() - shape.range_y().start(),
-            shape.range_z().end() - shape.range_z().start(),
-        ];
-        deltas.sort();
-        deltas[1] as usize / cell_radius_ratio_den
-    }
-
     pub fn clear(
         &mut self,
         data: &mut StateData<'_, GameData<'_, '_>>,
@@ -228,7 +203,7 @@ impl<R: HexRenderer> World<R> {
     ) {
         self.delete_pointer(data, world);
         self.renderer.clear(data);
-        self.hexes.dispose(data);
+        self.generator.hexes_mut().dispose(data);
         if let Some(entity) = self.limits_entity.take() {
             data.world.delete_entity(entity).expect("delete entity");
         }
@@ -246,7 +221,8 @@ impl<R: HexRenderer> World<R> {
 
     fn add_limit_lines(&self, debug_lines: &mut DebugLinesComponent, world: &RhombusViewerWorld) {
         let translations = self
-            .shape
+            .generator
+            .shape()
             .vertices()
             .iter()
             .map(|v| world.axial_translation((*v, 2.0).into()))
@@ -260,209 +236,38 @@ impl<R: HexRenderer> World<R> {
         debug_lines.add_line(translations[5].into(), translations[0].into(), color);
     }
 
-    pub fn cellular_automaton_phase1_step1(&mut self) {
-        Self::for_each_big_cell(self.shape.center(), self.cell_radius, |pos| -> bool {
-            if let Some((
-                HexData {
-                    automaton_count, ..
-                },
-                _,
-            )) = self.hexes.get_mut(pos)
-            {
-                *automaton_count = 0;
-                true
-            } else {
-                false
-            }
-        });
-        Self::for_each_big_cell(self.shape.center(), self.cell_radius, |pos| -> bool {
-            if let Some((
-                HexData {
-                    state: hex_state, ..
-                },
-                _,
-            )) = self.hexes.get(pos)
-            {
-                let is_wall = match hex_state {
-                    HexState::Wall | HexState::HardWall => true,
-                    HexState::Open => false,
-                };
-                if is_wall {
-                    for neighbor in pos.big_ring_iter(self.cell_radius, 1) {
-                        if let Some((hex_data, _)) = self.hexes.get_mut(neighbor) {
-                            hex_data.automaton_count += 1;
-                        }
-                    }
-                }
-                true
-            } else {
-                false
-            }
-        });
-    }
-
-    pub fn cellular_automaton_phase2_step1(&mut self) {
-        for (hex_data, _) in self.hexes.hexes_mut() {
-            hex_data.automaton_count = 0;
-        }
-        let positions = self.hexes.positions().collect::<Vec<_>>();
-        for pos in positions {
-            let hex_state = self.hexes.get(pos).unwrap().0.state;
-            let is_wall = match hex_state {
-                HexState::Wall | HexState::HardWall => true,
-                HexState::Open => false,
-            };
-            if is_wall {
-                for neighbor in pos.ring_iter(1) {
-                    if let Some((hex_data, _)) = self.hexes.get_mut(neighbor) {
-                        hex_data.automaton_count += 1;
-                    }
-                }
-            }
-        }
-    }
-
-    fn cellular_automaton_step2_internal<RaiseF, RemainF>(
-        hex_data: &mut HexData,
-        raise_wall_test: RaiseF,
-        remain_wall_test: RemainF,
-    ) -> bool
-    where
-        RaiseF: Fn(u8) -> bool,
-        RemainF: Fn(u8) -> bool,
-    {
-        let mut frozen = true;
-        match hex_data.state {
-            HexState::Wall => {
-                if !remain_wall_test(hex_data.automaton_count) {
-                    hex_data.state = HexState::Open;
-                    frozen = false;
-                }
-            }
-            HexState::Open => {
-                if raise_wall_test(hex_data.automaton_count) {
-                    hex_data.state = HexState::Wall;
-                    frozen = false;
-                }
-            }
-            HexState::HardWall => {}
-        }
-        frozen
-    }
-
-    pub fn cellular_automaton_phase1_step2<RaiseF, RemainF>(
-        &mut self,
-        raise_wall_test: RaiseF,
-        remain_wall_test: RemainF,
-    ) -> bool
-    where
-        RaiseF: Fn(u8) -> bool,
-        RemainF: Fn(u8) -> bool,
-    {
-        let mut frozen = true;
-        Self::for_each_big_cell(self.shape.center(), self.cell_radius, |pos| -> bool {
-            if let Some((hex_data, _)) = self.hexes.get_mut(pos) {
-                if !Self::cellular_automaton_step2_internal(
-                    hex_data,
-                    &raise_wall_test,
-                    &remain_wall_test,
-                ) {
-                    frozen = false;
-                }
-                true
-            } else {
-                false
-            }
-        });
-        if !frozen {
-            self.renderer_dirty = true;
-        }
-        frozen
-    }
-
-    pub fn cellular_automaton_phase2_step2<RaiseF, RemainF>(
+    pub fn run_rules_step(
         &mut self,
-        raise_wall_test: RaiseF,
-        remain_wall_test: RemainF,
-    ) -> bool
-    where
-        RaiseF: Fn(u8) -> bool,
-        RemainF: Fn(u8) -> bool,
-    {
-        let mut frozen = true;
-        for (hex_data, _) in self.hexes.hexes_mut() {
-            if !Self::cellular_automaton_step2_internal(
-                hex_data,
-                &raise_wall_test,
-                &remain_wall_test,
-            ) {
-                frozen = false;
-            }
-        }
-        if !frozen {
-            self.renderer_dirty = true;
-        }
-        frozen
+        rules: &CellularRules,
+        cursor: &mut RuleCursor,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) -> RuleStep {
+        let step = self
+            .generator
+            .run_rules_step(rules, cursor, |mut hex| hex.dispose(data));
+        self.renderer_dirty = true;
+        step
     }
 
-    pub fn expand(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
-        if self.cell_radius <= 0 {
+    // Drops the walls far from the caves once grown, the limits and the history following the new
+    // shape.
+    pub fn shrink_to_content(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        if !self.generator.shrink_to_content(|mut hex| hex.dispose(data)) {
             return;
         }
-        Self::for_each_big_cell(self.shape.center(), self.cell_radius, |pos| -> bool {
-            if let Some((
-                HexData {
-                    state: hex_state, ..
-                },
-                _,
-            )) = self.hexes.get(pos)
-            {
-                let hex_state = *hex_state;
-                if !self.shape.contains_position(pos) {
-                    self.hexes.remove(pos).map(|mut hex| hex.dispose(data));
-                }
-                for s in 1..=self.cell_radius {
-                    for sub_pos in pos.ring_iter(s) {
-                        if self.shape.contains_position(sub_pos) {
-                            let hex = self
-                                .hexes
-                                .get_mut(sub_pos)
-                                .expect("The entire shape is paved with tiles");
-                            hex.0 = HexData {
-                                state: hex_state,
-                                automaton_count: 0,
-                            };
-                        }
-                    }
-                }
-                true
-            } else {
-                false
-            }
-        });
+        if let Some(entity) = self.limits_entity {
+            let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+            let mut debug_lines_storage = data.world.write_storage::<DebugLinesComponent>();
+            let debug_lines = debug_lines_storage.get_mut(entity).expect("Debug lines");
+            debug_lines.clear();
+            self.add_limit_lines(debug_lines, &world);
+        }
+        self.history.reset(self.generator.states());
         self.renderer_dirty = true;
     }
 
-    fn find_open_hex(&self) -> Option<AxialVector> {
-        let mut r = 0;
-        loop {
-            let mut end = true;
-            for pos in self.shape.center().ring_iter(r) {
-                let hex_data = self.hexes.get(pos).map(|hex| &hex.0);
-                match hex_data {
-                    Some(HexData {
-                        state: HexState::Open,
-                        ..
-                    }) => return Some(pos),
-                    Some(..) => end = false,
-                    None => (),
-                }
-            }
-            if end {
-                return None;
-            }
-            r += 1;
-        }
+    pub fn stats(&self) -> RectHashStorageStats {
+        self.generator.hexes().stats()
     }
 
     pub fn create_pointer(
@@ -473,56 +278,35 @@ impl<R: HexRenderer> World<R> {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.delete_pointer(data, &world);
 
-        if let Some(hex) = self.find_open_hex() {
+        if let Some(hex) = self.generator.random_open_hex(self.seed.rng()) {
             let mut pointer = HexPointer::new_with_level_height(1.0);
             pointer.set_position(hex, 0, data, &world);
             pointer.create_entities(data, &world);
-            self.pointer = Some((pointer, fov_state));
+            self.pointer = Some((pointer, VisibilityController::new(fov_state)));
             self.renderer_dirty = true;
         }
     }
 
-    pub fn increment_direction(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
-        if let Some((pointer, _)) = &mut self.pointer {
-            let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
-            pointer.increment_direction(data, &world);
-        }
-    }
-
-    pub fn decrement_direction(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
-        if let Some((pointer, _)) = &mut self.pointer {
-            let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
-            pointer.decrement_direction(data, &world);
-        }
+    pub fn turn(&mut self, turn: PointerTurn, data: &StateData<'_, GameData<'_, '_>>) {
+        self.renderer_dirty |= turn_pointer(&mut self.pointer, turn, data);
     }
 
     pub fn next_position(&mut self, mode: MoveMode, data: &mut StateData<'_, GameData<'_, '_>>) {
         if let Some((pointer, _)) = &mut self.pointer {
-            let direction = match mode {
-                MoveMode::StraightAhead => pointer.direction(),
-                MoveMode::StrafeLeftAhead => (pointer.direction() + 5) % 6,
-                MoveMode::StrafeLeftBack => (pointer.direction() + 4) % 6,
-                MoveMode::StrafeRightAhead => (pointer.direction() + 1) % 6,
-                MoveMode::StrafeRightBack => (pointer.direction() + 2) % 6,
-                MoveMode::StraightBack => (pointer.direction() + 3) % 6,
-            };
-            let next = pointer.position().neighbor(direction);
-            if let Some(HexData {
-                state: HexState::Open,
-                ..
-            }) = self.hexes.get(next).map(|hex| &hex.0)
-            {
-                let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
-                pointer.set_position(next, 0, data, &world);
+            let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+            let hexes = self.generator.hexes();
+            let can_enter = |next| hexes.get(next).map(|hex| hex.0.state()) == Some(HexState::Open);
+            if pointer.try_move(mode, can_enter, data, &world) {
                 self.renderer_dirty = true;
             }
         }
     }
 
     pub fn change_field_of_view(&mut self, fov_state: FovState) {
-        if let Some((_, pointer_fov_state)) = &mut self.pointer {
-            *pointer_fov_state = fov_state;
-            self.renderer_dirty = true;
+        if let Some((_, visibility)) = &mut self.pointer {
+            if visibility.set_fov_state(fov_state) {
+                self.renderer_dirty = true;
+            }
         }
     }
 
@@ -531,53 +315,17 @@ impl<R: HexRenderer> World<R> {
         force: bool,
         data: &mut StateData<'_, GameData<'_, '_>>,
     ) {
-        if !self.renderer_dirty {
+        if !force && !self.renderer_dirty {
             return;
         }
 
-        let (visible_positions, visible_only) = if let Some((pointer, fov_state)) = &self.pointer {
-            let mut visible_positions = HashSet::new();
-            visible_positions.insert(pointer.position());
-            let mut fov = FieldOfView::default();
-            fov.start(pointer.position());
-            let is_obstacle = |pos| {
-                let hex_data = self.hexes.get(pos).map(|hex| &hex.0);
-                match hex_data {
-                    Some(HexData {
-                        state: HexState::Open,
-                        ..
-                    }) => false,
-                    Some(HexData {
-                        state: HexState::Wall,
-                        ..
-                    })
-                    | Some(HexData {
-                        state: HexState::HardWall,
-                        ..
-                    }) => true,
-                    None => false,
-                }
-            };
-            loop {
-                let prev_len = visible_positions.len();
-                for pos in fov.iter() {
-                    let key = pointer.position() + pos;
-                    if self.hexes.contains_position(key) {
-                        let inserted = visible_positions.insert(key);
-                        debug_assert!(inserted);
-                    }
-                }
-                if visible_positions.len() == prev_len {
-                    break;
-                }
-                fov.next_radius(&is_obstacle);
-            }
-            (
-                Some(visible_positions),
-                match fov_state {
-                    FovState::Partial => false,
-                    FovState::Full => true,
-                },
+        let (visible_positions, visible_only) = if let Some((pointer, visibility)) = &self.pointer {
+            visibility.update(
+                self.generator.hexes(),
+                pointer.position(),
+                pointer.direction(),
+                &data.world.read_resource::<SightSettings>(),
+                |_, hex| hex.0.state() != HexState::Open,
             )
         } else {
             (None, false)
@@ -586,16 +334,13 @@ impl<R: HexRenderer> World<R> {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
 
         self.renderer.update_world(
-            &mut self.hexes,
-            |_, hex| hex.0.state != HexState::Open,
-            |pos, _| {
-                visible_positions
-                    .as_ref()
-                    .map_or(true, |vp| vp.contains(&pos))
-            },
+            self.generator.hexes_mut(),
+            |_, hex| hex.0.state() != HexState::Open,
+            visible_positions.as_ref(),
             |hex| &mut hex.1,
             visible_only,
             force,
+            &world.layout,
             data,
             &world,
         );
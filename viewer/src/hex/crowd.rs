@@ -0,0 +1,425 @@
+use crate::{
+    assets::Color,
+    dispose::Dispose,
+    hex::{
+        inspect::{HexLayer, HexTag},
+        render::{
+            renderer::HexRenderer,
+            storage::EcsBackedStorage,
+            tile::{HexScale, TileRenderer},
+        },
+    },
+    input::get_key_and_modifiers,
+    pacing::{set_window_title, AnimationSettings},
+    systems::{spatial::EntitySpatialMap, tween::HeightTweenTag},
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    core::{math::Vector3, timing::Time, Transform},
+    ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    winit::VirtualKeyCode,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector, flow_field::FlowField, shape::cubic_range::CubicRangeShape,
+    storage::set::HexSet,
+};
+use rhombus_mapgen::{
+    graph::NodeKind,
+    rooms_and_mazes::{HexState, RoomsAndMazesWorld},
+};
+use std::{collections::HashMap, sync::Arc};
+
+const SEED: u64 = 0x4372_6f77;
+const MAP_RADIUS: isize = 24;
+const ROOM_ATTEMPTS: usize = 40;
+const SPAWN_ROOMS: usize = 3;
+const AGENTS: usize = 80;
+const SPAWN_SECONDS: f32 = 0.1;
+// In hex sizes per second.
+const AGENT_SPEED: f32 = 3.0;
+// Closer than that, in hex sizes, agents push each other away.
+const SEPARATION_RADIUS: f32 = 0.6;
+const SEPARATION_WEIGHT: f32 = 1.5;
+const AGENT_HEIGHT: f32 = 0.3;
+const POP_SECONDS: f32 = 0.4;
+const STATUS_SECONDS: f32 = 1.0;
+
+struct Agent {
+    entity: Entity,
+    // In the layout plane.
+    point: [f32; 2],
+    hex: AxialVector,
+}
+
+// Agents stream from the rooms the farthest from the goal and walk down a flow field, keeping
+// apart from the ones on the hexes around them thanks to the EntitySpatialMap. Arrived agents pop
+// up again in a spawn room, so that the crowd keeps flowing.
+pub struct HexCrowdDemo {
+    seed: u64,
+    hexes: EcsBackedStorage<TileRenderer>,
+    field: Option<FlowField>,
+    spawns: Vec<AxialVector>,
+    next_spawn: usize,
+    agents: Vec<Agent>,
+    spawn_seconds: f32,
+    arrived: usize,
+    // Agents looked at by the separation, counted for the status.
+    neighbor_checks: usize,
+    status_seconds: f32,
+}
+
+impl HexCrowdDemo {
+    pub fn new() -> Self {
+        Self {
+            seed: SEED,
+            hexes: EcsBackedStorage::new(TileRenderer::new(
+                HexScale {
+                    horizontal: 0.8,
+                    vertical: 0.1,
+                },
+                HexScale {
+                    horizontal: 0.8,
+                    vertical: 0.3,
+                },
+            )),
+            field: None,
+            spawns: Vec::new(),
+            next_spawn: 0,
+            agents: Vec::new(),
+            spawn_seconds: 0.0,
+            arrived: 0,
+            neighbor_checks: 0,
+            status_seconds: 0.0,
+        }
+    }
+
+    fn generate(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        let mut dungeon = RoomsAndMazesWorld::new();
+        dungeon.set_shape(CubicRangeShape::new(
+            (-MAP_RADIUS, MAP_RADIUS),
+            (-MAP_RADIUS, MAP_RADIUS),
+            (-MAP_RADIUS, MAP_RADIUS),
+        ));
+        dungeon.reset(|_| ());
+        dungeon.generate(ROOM_ATTEMPTS, &mut StdRng::seed_from_u64(self.seed));
+
+        let mut walls = HexSet::new();
+        let mut regions = HashMap::new();
+        for (position, (hex_data, _)) in dungeon.hexes().iter() {
+            match hex_data.state() {
+                HexState::Open(region) => {
+                    regions.insert(position, region);
+                }
+                _ => {
+                    walls.insert(position);
+                }
+            }
+            let hex = self.hexes.renderer_mut().new_hex(false, true);
+            self.hexes.insert(position, hex, data);
+        }
+        {
+            let (hexes, renderer) = self.hexes.parts_mut();
+            renderer.update_world(
+                hexes,
+                |position, _| walls.contains(position),
+                None,
+                |hex| hex,
+                false,
+                false,
+                &world.layout,
+                data,
+                world,
+            );
+        }
+
+        // The goal is the middle of the room closest to the center of the map.
+        let graph = dungeon.graph();
+        let mut rooms = graph
+            .nodes()
+            .iter()
+            .filter(|node| node.kind == NodeKind::Room)
+            .collect::<Vec<_>>();
+        rooms.sort_by_key(|room| (room.center.distance(AxialVector::default()), room.region));
+        let goal = match rooms.first() {
+            Some(room) => room.center,
+            None => return,
+        };
+        let is_open = |position: AxialVector| regions.contains_key(&position);
+        let field = FlowField::new(
+            std::iter::once(goal),
+            usize::MAX,
+            |from: AxialVector, to: AxialVector| {
+                if is_open(from) && is_open(to) {
+                    Some(1)
+                } else {
+                    None
+                }
+            },
+        );
+        // The agents come from the rooms the farthest from the goal.
+        rooms.sort_by_key(|room| std::cmp::Reverse((field.cost(room.center), room.region)));
+        let spawn_regions = rooms
+            .iter()
+            .take(SPAWN_ROOMS)
+            .filter(|room| room.center != goal)
+            .map(|room| room.region)
+            .collect::<Vec<_>>();
+        self.spawns = regions
+            .iter()
+            .filter(|(position, region)| {
+                spawn_regions.contains(*region) && field.cost(**position).is_some()
+            })
+            .map(|(position, _)| *position)
+            .collect();
+        self.spawns.sort();
+        self.field = Some(field);
+    }
+
+    fn spawn_hex(&mut self) -> Option<AxialVector> {
+        if self.spawns.is_empty() {
+            return None;
+        }
+        // Spread over the spawn hexes rather than piling up on the first ones.
+        self.next_spawn = (self.next_spawn + 7) % self.spawns.len();
+        Some(self.spawns[self.next_spawn])
+    }
+
+    // The agent rises from under the ground, the TweenSystem moving it up.
+    fn pop_up(entity: Entity, data: &StateData<'_, GameData<'_, '_>>) {
+        let start = data.world.read_resource::<Time>().absolute_time_seconds() as f32;
+        data.world
+            .write_storage::<HeightTweenTag>()
+            .insert(
+                entity,
+                HeightTweenTag {
+                    from: -AGENT_HEIGHT,
+                    to: AGENT_HEIGHT,
+                    start,
+                    duration: POP_SECONDS,
+                },
+            )
+            .expect("insert HeightTweenTag");
+    }
+
+    fn spawn(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        let hex = match self.spawn_hex() {
+            Some(hex) => hex,
+            None => return,
+        };
+        let mut transform = Transform::default();
+        transform.set_scale(Vector3::new(0.3, AGENT_HEIGHT, 0.3));
+        world.transform_hex((hex, -AGENT_HEIGHT).into(), &mut transform);
+        let entity = data
+            .world
+            .create_entity()
+            .with(world.assets.hex_handle.clone())
+            .with(world.assets.color_data[&Color::Cyan].light.clone())
+            .with(transform)
+            .with(HexTag::new(hex, HexLayer::Agent, "crowd"))
+            .build();
+        Self::pop_up(entity, data);
+        self.agents.push(Agent {
+            entity,
+            point: world.layout.to_point(hex),
+            hex,
+        });
+    }
+
+    fn respawn(
+        &mut self,
+        index: usize,
+        data: &StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        if let Some(hex) = self.spawn_hex() {
+            let agent = &mut self.agents[index];
+            agent.hex = hex;
+            agent.point = world.layout.to_point(hex);
+            Self::pop_up(agent.entity, data);
+        }
+    }
+
+    // Down the flow field, away from the neighbors too close.
+    fn steer(
+        &mut self,
+        delta_seconds: f32,
+        data: &StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        let field = match &self.field {
+            Some(field) => field,
+            None => return,
+        };
+        let size = world.layout.size();
+        let points = self
+            .agents
+            .iter()
+            .map(|agent| (agent.entity, agent.point))
+            .collect::<HashMap<_, _>>();
+        let mut arrivals = Vec::new();
+        {
+            let spatial_map = data.world.read_resource::<EntitySpatialMap>();
+            for (index, agent) in self.agents.iter_mut().enumerate() {
+                let next = match field.next(agent.hex) {
+                    Some(next) => next,
+                    None => {
+                        arrivals.push(index);
+                        continue;
+                    }
+                };
+                let target = world.layout.to_point(next);
+                let (dx, dy) = (target[0] - agent.point[0], target[1] - agent.point[1]);
+                let distance = dx.hypot(dy).max(1e-3);
+                let mut velocity = [dx / distance, dy / distance];
+                for (_, other) in spatial_map.entities_in_range(agent.hex, 1) {
+                    let other_point = match points.get(&other) {
+                        Some(point) if other != agent.entity => point,
+                        _ => continue,
+                    };
+                    self.neighbor_checks += 1;
+                    let (ax, ay) = (
+                        agent.point[0] - other_point[0],
+                        agent.point[1] - other_point[1],
+                    );
+                    let apart = ax.hypot(ay) / size;
+                    if apart > 1e-3 && apart < SEPARATION_RADIUS {
+                        let push = SEPARATION_WEIGHT * (SEPARATION_RADIUS - apart)
+                            / (SEPARATION_RADIUS * apart * size);
+                        velocity[0] += ax * push;
+                        velocity[1] += ay * push;
+                    }
+                }
+                let step = AGENT_SPEED * size * delta_seconds;
+                let moved = [
+                    agent.point[0] + velocity[0] * step,
+                    agent.point[1] + velocity[1] * step,
+                ];
+                // Pushed into a wall, the agent only follows the field, and waits when even that
+                // would cut through the corner of a wall.
+                let followed = [
+                    agent.point[0] + dx / distance * step,
+                    agent.point[1] + dy / distance * step,
+                ];
+                if let Some(point) = [moved, followed]
+                    .iter()
+                    .find(|point| field.cost(world.layout.from_point(**point)).is_some())
+                {
+                    agent.point = *point;
+                    agent.hex = world.layout.from_point(*point);
+                }
+            }
+        }
+        self.arrived += arrivals.len();
+        for index in arrivals {
+            self.respawn(index, data, world);
+        }
+    }
+
+    // The horizontal translation and the tag follow the agents, the TweenSystem owns the height.
+    fn update_entities(&self, data: &StateData<'_, GameData<'_, '_>>) {
+        let mut transforms = data.world.write_storage::<Transform>();
+        let mut tags = data.world.write_storage::<HexTag>();
+        for agent in &self.agents {
+            if let Some(transform) = transforms.get_mut(agent.entity) {
+                let translation = transform.translation_mut();
+                translation[0] = agent.point[0];
+                translation[2] = -agent.point[1];
+            }
+            // Only written when it changes, so that the spatial map is not told about every agent
+            // every frame.
+            let moved = matches!(tags.get(agent.entity), Some(tag) if tag.position != agent.hex);
+            if moved {
+                if let Some(tag) = tags.get_mut(agent.entity) {
+                    tag.position = agent.hex;
+                }
+            }
+        }
+    }
+
+    fn update_status(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        let title = format!(
+            "{} - crowd of {} agents, {} arrived, {} neighbor checks per second",
+            data.world.read_resource::<AnimationSettings>().window_title,
+            self.agents.len(),
+            self.arrived,
+            (self.neighbor_checks as f32 / STATUS_SECONDS) as usize,
+        );
+        set_window_title(data.world, &title);
+        self.neighbor_checks = 0;
+    }
+
+    fn clear(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        for agent in self.agents.drain(..) {
+            data.world
+                .delete_entity(agent.entity)
+                .expect("delete entity");
+        }
+        self.hexes.dispose(data);
+        self.field = None;
+        self.spawns.clear();
+        self.next_spawn = 0;
+        self.spawn_seconds = 0.0;
+        self.arrived = 0;
+        self.neighbor_checks = 0;
+        self.status_seconds = 0.0;
+    }
+}
+
+impl SimpleState for HexCrowdDemo {
+    fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        world.set_camera_distance(&data, 150.0);
+        self.generate(&mut data, &world);
+    }
+
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.clear(&mut data);
+        let settings = data.world.read_resource::<AnimationSettings>();
+        set_window_title(data.world, &settings.window_title);
+    }
+
+    fn handle_event(
+        &mut self,
+        mut data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = event {
+            match get_key_and_modifiers(&event) {
+                Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => Trans::Pop,
+                Some((VirtualKeyCode::N, ElementState::Pressed, _)) => {
+                    // Another dungeon, another crowd.
+                    self.clear(&mut data);
+                    self.seed = self.seed.wrapping_add(1);
+                    let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+                    self.generate(&mut data, &world);
+                    Trans::None
+                }
+                _ => Trans::None,
+            }
+        } else {
+            Trans::None
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        // Large frames are cut down, the agents would jump through the walls otherwise.
+        let delta_seconds = data.world.read_resource::<Time>().delta_seconds().min(0.1);
+        self.spawn_seconds += delta_seconds;
+        while self.agents.len() < AGENTS && self.spawn_seconds >= SPAWN_SECONDS {
+            self.spawn_seconds -= SPAWN_SECONDS;
+            self.spawn(data, &world);
+        }
+        self.steer(delta_seconds, data, &world);
+        self.update_entities(data);
+        self.status_seconds += delta_seconds;
+        if self.status_seconds >= STATUS_SECONDS {
+            self.status_seconds -= STATUS_SECONDS;
+            self.update_status(data);
+        }
+        Trans::None
+    }
+}
@@ -1,14 +1,18 @@
 use crate::{
     dispose::Dispose,
     hex::{
-        pointer::{HexPointer, VerticalDirection},
+        inspect,
+        pointer::{
+            HexPointer, MoveMode, PointerAction, PointerKeys, PointerSettings, VerticalDirection,
+        },
         render::{
             renderer::HexRenderer,
+            storage::EcsBackedStorage,
             tile::{HexScale, TileRenderer},
         },
-        shape::cubic_range::CubicRangeShape,
     },
     input::get_key_and_modifiers,
+    systems::{follow_me::FollowMeSettings, grid_overlay::GridSettings, lighting::LightingConfig},
     world::RhombusViewerWorld,
 };
 use amethyst::{
@@ -18,34 +22,22 @@ use amethyst::{
     renderer::{debug_drawing::DebugLinesComponent, palette::Srgba},
     winit::VirtualKeyCode,
 };
-use rhombus_core::hex::{
-    coordinates::direction::HexagonalDirection, storage::hash::RectHashStorage,
-};
+use rhombus_core::hex::shape::cubic_range::{CubicRangeShape, ResizeAnchor};
 use std::sync::Arc;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum MoveMode {
-    StraightAhead,
-    StrafeLeftAhead,
-    StrafeLeftBack,
-    StrafeRightAhead,
-    StrafeRightBack,
-    StraightBack,
-}
-
 pub struct HexCubicRangeShapeDemo {
     shape: CubicRangeShape,
+    anchor: ResizeAnchor,
     limits_entity: Option<Entity>,
-    world: RectHashStorage<<TileRenderer as HexRenderer>::Hex>,
-    renderer: TileRenderer,
+    world: EcsBackedStorage<TileRenderer>,
     pointer: HexPointer,
+    keys: PointerKeys,
 }
 
 impl HexCubicRangeShapeDemo {
     pub fn new() -> Self {
         let shape = CubicRangeShape::new((-2, 2), (-2, 2), (-2, 2));
-        let world = RectHashStorage::new();
-        let renderer = TileRenderer::new(
+        let world = EcsBackedStorage::new(TileRenderer::new(
             HexScale {
                 horizontal: 0.8,
                 vertical: 0.1,
@@ -54,14 +46,15 @@ impl HexCubicRangeShapeDemo {
                 horizontal: 0.8,
                 vertical: 0.3,
             },
-        );
+        ));
         let pointer = HexPointer::new_with_level_height(1.0);
         Self {
             shape,
+            anchor: ResizeAnchor::OppositeEdge,
             limits_entity: None,
             world,
-            renderer,
             pointer,
+            keys: PointerKeys::default(),
         }
     }
 
@@ -70,7 +63,6 @@ impl HexCubicRangeShapeDemo {
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) {
-        self.renderer.clear(data);
         self.world.dispose(data);
 
         if let Some(entity) = self.limits_entity {
@@ -101,23 +93,19 @@ impl HexCubicRangeShapeDemo {
                 world,
             );
         }
-        for v in self.shape.vertices().iter() {
-            self.world
-                .insert(*v, self.renderer.new_hex(true, true))
-                .map(|mut hex| hex.dispose(data));
-            self.renderer
-                .update_hex(*v, &mut self.world.get_mut(*v).unwrap(), data, world);
-        }
         let center = self.shape.center();
-        self.world
-            .insert(center, self.renderer.new_hex(false, true))
-            .map(|mut hex| hex.dispose(data));
-        self.renderer.update_hex(
-            center,
-            &mut self.world.get_mut(center).unwrap(),
-            data,
-            world,
-        );
+        for (v, wall) in self
+            .shape
+            .vertices()
+            .iter()
+            .map(|v| (*v, true))
+            .chain(std::iter::once((center, false)))
+        {
+            let hex = self.world.renderer_mut().new_hex(wall, true);
+            self.world.insert(v, hex, data);
+            let (hex, renderer) = self.world.hex_and_renderer_mut(v).unwrap();
+            renderer.update_hex(v, hex, data, world);
+        }
     }
 
     fn try_resize_shape(
@@ -126,10 +114,19 @@ impl HexCubicRangeShapeDemo {
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) {
-        resize(&mut self.shape, 1);
+        self.shape.resize(resize, 1, self.anchor);
         self.reset_shape(data, world);
     }
 
+    fn next_anchor(&mut self) {
+        self.anchor = match self.anchor {
+            ResizeAnchor::OppositeEdge => ResizeAnchor::Center,
+            ResizeAnchor::Center => ResizeAnchor::Vertex(0),
+            ResizeAnchor::Vertex(vertex) if vertex < 5 => ResizeAnchor::Vertex(vertex + 1),
+            ResizeAnchor::Vertex(..) => ResizeAnchor::OppositeEdge,
+        };
+    }
+
     fn add_limit_lines(&self, debug_lines: &mut DebugLinesComponent, world: &RhombusViewerWorld) {
         let translations = self
             .shape
@@ -147,18 +144,17 @@ impl HexCubicRangeShapeDemo {
     }
 
     fn next_position(&mut self, mode: MoveMode, data: &mut StateData<'_, GameData<'_, '_>>) {
-        let direction = match mode {
-            MoveMode::StraightAhead => self.pointer.direction(),
-            MoveMode::StrafeLeftAhead => (self.pointer.direction() + 5) % 6,
-            MoveMode::StrafeLeftBack => (self.pointer.direction() + 4) % 6,
-            MoveMode::StrafeRightAhead => (self.pointer.direction() + 1) % 6,
-            MoveMode::StrafeRightBack => (self.pointer.direction() + 2) % 6,
-            MoveMode::StraightBack => (self.pointer.direction() + 3) % 6,
-        };
-        let next = self.pointer.position().neighbor(direction);
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
-        self.pointer.set_position(next, 0, data, &world);
-        if self.shape.contains_position(next) {
+        let clamp = data
+            .world
+            .read_resource::<PointerSettings>()
+            .clamp_to_bounds;
+        let shape = &self.shape;
+        let can_enter = |next| !clamp || shape.contains_position(next);
+        if !self.pointer.try_move(mode, can_enter, data, &world) {
+            return;
+        }
+        if self.shape.contains_position(self.pointer.position()) {
             self.pointer.set_direction(
                 self.pointer.direction(),
                 VerticalDirection::Horizontal,
@@ -174,10 +170,24 @@ impl HexCubicRangeShapeDemo {
             );
         }
     }
+
+    fn apply_pointer_action(
+        &mut self,
+        action: PointerAction,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        match action {
+            PointerAction::Move(mode) => self.next_position(mode, data),
+            PointerAction::TurnLeft => self.pointer.decrement_direction(data, &world),
+            PointerAction::TurnRight => self.pointer.increment_direction(data, &world),
+        }
+    }
 }
 
 impl SimpleState for HexCubicRangeShapeDemo {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.keys.release_all();
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.pointer.create_entities(&mut data, &world);
         self.reset_shape(&mut data, &world);
@@ -186,7 +196,6 @@ impl SimpleState for HexCubicRangeShapeDemo {
     fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.pointer.delete_entities(&mut data, &world);
-        self.renderer.clear(&mut data);
         self.world.dispose(&mut data);
         if let Some(entity) = self.limits_entity.take() {
             data.world.delete_entity(entity).expect("delete entity");
@@ -199,23 +208,36 @@ impl SimpleState for HexCubicRangeShapeDemo {
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
+            if PointerSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if FollowMeSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if inspect::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if GridSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if LightingConfig::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if let Some(action) = self.keys.handle_event(&data, &event) {
+                self.apply_pointer_action(action, &mut data);
+                return Trans::None;
+            }
             let mut trans = Trans::None;
             let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
             match get_key_and_modifiers(&event) {
                 Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
                     trans = Trans::Pop;
                 }
-                Some((VirtualKeyCode::Right, ElementState::Pressed, _)) => {
-                    self.pointer.increment_direction(&data, &world);
-                }
-                Some((VirtualKeyCode::Left, ElementState::Pressed, _)) => {
-                    self.pointer.decrement_direction(&data, &world);
+                Some((VirtualKeyCode::C, ElementState::Pressed, _)) => {
+                    world.toggle_follow(&data);
                 }
-                Some((VirtualKeyCode::Up, ElementState::Pressed, _)) => {
-                    self.next_position(MoveMode::StraightAhead, &mut data);
-                }
-                Some((VirtualKeyCode::Down, ElementState::Pressed, _)) => {
-                    self.next_position(MoveMode::StraightBack, &mut data);
+                Some((VirtualKeyCode::A, ElementState::Pressed, _)) => {
+                    self.next_anchor();
                 }
                 Some((VirtualKeyCode::F, ElementState::Pressed, modifiers)) => {
                     self.try_resize_shape(
@@ -290,4 +312,11 @@ impl SimpleState for HexCubicRangeShapeDemo {
             Trans::None
         }
     }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(action) = self.keys.repeat(data) {
+            self.apply_pointer_action(action, data);
+        }
+        Trans::None
+    }
 }
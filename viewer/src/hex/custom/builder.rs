@@ -1,9 +1,22 @@
 use crate::{
     hex::{
-        custom::world::{FovState, MoveMode, World},
+        custom::{
+            scenario::{load_scenarios, CustomScenariosSettings},
+            world::World,
+        },
+        inspect,
+        pointer::{HexPointer, PointerAction, PointerKeys, PointerSettings},
         render::renderer::HexRenderer,
+        scale::WorldScale,
+        visibility::FovState,
     },
     input::get_key_and_modifiers,
+    systems::{
+        event_log::{log_event, EventKind},
+        follow_me::FollowMeSettings,
+        grid_overlay::GridSettings,
+        lighting::LightingConfig,
+    },
     world::RhombusViewerWorld,
 };
 use amethyst::{ecs::prelude::*, input::ElementState, prelude::*, winit::VirtualKeyCode};
@@ -13,11 +26,12 @@ use std::sync::Arc;
 enum CustomState {
     Growing,
     Grown,
-    FieldOfView(bool),
+    FieldOfView(FovState),
 }
 
 pub struct HexCustomBuilder<R: HexRenderer> {
     world: World<R>,
+    keys: PointerKeys,
     state: CustomState,
 }
 
@@ -25,18 +39,35 @@ impl<R: HexRenderer> HexCustomBuilder<R> {
     pub fn new(renderer: R) -> Self {
         Self {
             world: World::new(renderer),
+            keys: PointerKeys::default(),
             state: CustomState::Grown,
         }
     }
 
+    // The scenarios are read again every time so that they can be tweaked without restarting.
     fn reset(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let scenarios = load_scenarios(&data.world.read_resource::<CustomScenariosSettings>().path);
+        self.world.set_scenarios(scenarios);
         self.world.reset_world(data);
         self.state = CustomState::Growing;
     }
+
+    fn apply_pointer_action(
+        &mut self,
+        action: PointerAction,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        match action {
+            PointerAction::Move(mode) => self.world.next_position(mode, data),
+            PointerAction::TurnLeft => self.world.turn(HexPointer::decrement_direction, data),
+            PointerAction::TurnRight => self.world.turn(HexPointer::increment_direction, data),
+        }
+    }
 }
 
 impl<R: HexRenderer> SimpleState for HexCustomBuilder<R> {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.keys.release_all();
         self.reset(&mut data);
         self.world.update_renderer_world(true, &mut data);
     }
@@ -52,57 +83,57 @@ impl<R: HexRenderer> SimpleState for HexCustomBuilder<R> {
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
+            if WorldScale::handle_event(&data, &event) {
+                self.world.update_renderer_world(true, &mut data);
+                return Trans::None;
+            }
+            if PointerSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if FollowMeSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if inspect::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if GridSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if LightingConfig::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if let Some(action) = self.keys.handle_event(&data, &event) {
+                self.apply_pointer_action(action, &mut data);
+                return Trans::None;
+            }
             let mut trans = Trans::None;
             match get_key_and_modifiers(&event) {
                 Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
                     trans = Trans::Pop;
                 }
                 Some((VirtualKeyCode::N, ElementState::Pressed, _)) => {
-                    self.world.next_mode();
-                    self.world.reset_world(&mut data);
-                    self.state = CustomState::Growing;
-                }
-                Some((VirtualKeyCode::Right, ElementState::Pressed, modifiers)) => {
-                    if modifiers.shift {
-                        self.world
-                            .next_position(MoveMode::StrafeRightAhead, &mut data);
-                    } else if modifiers.ctrl {
-                        self.world
-                            .next_position(MoveMode::StrafeRightBack, &mut data);
-                    } else {
-                        self.world.increment_direction(&data);
-                    }
-                }
-                Some((VirtualKeyCode::Left, ElementState::Pressed, modifiers)) => {
-                    if modifiers.shift {
-                        self.world
-                            .next_position(MoveMode::StrafeLeftAhead, &mut data);
-                    } else if modifiers.ctrl {
-                        self.world
-                            .next_position(MoveMode::StrafeLeftBack, &mut data);
-                    } else {
-                        self.world.decrement_direction(&data);
-                    }
-                }
-                Some((VirtualKeyCode::Up, ElementState::Pressed, _)) => {
-                    self.world.next_position(MoveMode::StraightAhead, &mut data);
-                }
-                Some((VirtualKeyCode::Down, ElementState::Pressed, _)) => {
-                    self.world.next_position(MoveMode::StraightBack, &mut data);
+                    let scenario = self.world.next_scenario();
+                    log_event(
+                        data.world,
+                        EventKind::Generation,
+                        format!("Scenario: {}", scenario),
+                    );
+                    self.reset(&mut data);
                 }
                 Some((VirtualKeyCode::C, ElementState::Pressed, _)) => {
                     let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
                     world.toggle_follow(&data);
                 }
                 Some((VirtualKeyCode::V, ElementState::Pressed, _)) => {
-                    if let CustomState::FieldOfView(mut fov_enabled) = self.state {
-                        fov_enabled = !fov_enabled;
-                        self.world.change_field_of_view(if fov_enabled {
-                            FovState::Full
-                        } else {
-                            FovState::Partial
-                        });
-                        self.state = CustomState::FieldOfView(fov_enabled);
+                    if let CustomState::FieldOfView(fov_state) = self.state {
+                        let fov_state = fov_state.next();
+                        self.world.change_field_of_view(fov_state);
+                        self.state = CustomState::FieldOfView(fov_state);
+                        log_event(
+                            data.world,
+                            EventKind::View,
+                            format!("Field of view: {:?}", fov_state),
+                        );
                     }
                 }
                 _ => {}
@@ -114,6 +145,9 @@ impl<R: HexRenderer> SimpleState for HexCustomBuilder<R> {
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(action) = self.keys.repeat(data) {
+            self.apply_pointer_action(action, data);
+        }
         if let CustomState::FieldOfView(..) = self.state {
             self.world.update_renderer_world(false, data);
             return Trans::None;
@@ -127,7 +161,7 @@ impl<R: HexRenderer> SimpleState for HexCustomBuilder<R> {
             }
             CustomState::Grown => {
                 self.world.create_pointer(FovState::Partial, data);
-                self.state = CustomState::FieldOfView(false);
+                self.state = CustomState::FieldOfView(FovState::Partial);
             }
             CustomState::FieldOfView(..) => {}
         }
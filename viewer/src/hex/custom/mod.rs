@@ -1,2 +1,3 @@
 pub mod builder;
+pub mod scenario;
 pub mod world;
@@ -0,0 +1,143 @@
+use crate::hex::custom::world::HexState;
+use amethyst::config::Config;
+use log::warn;
+use rhombus_core::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Where the scenarios are read from, see config/custom.ron.
+pub struct CustomScenariosSettings {
+    pub path: PathBuf,
+}
+
+// Applied in order, positions are axial (q, r) coordinates.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ScenarioItem {
+    // Open hexes up to the radius, walled around where there is nothing yet.
+    Room {
+        center: (isize, isize),
+        radius: usize,
+    },
+    // Straight open line, walled on both sides where there is nothing yet.
+    Corridor {
+        start: (isize, isize),
+        end: (isize, isize),
+    },
+    Open(Vec<(isize, isize)>),
+    // Obstacles replace anything.
+    Walls(Vec<(isize, isize)>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub items: Vec<ScenarioItem>,
+}
+
+impl Scenario {
+    pub fn hexes(&self) -> RectHashStorage<HexState> {
+        let mut hexes = RectHashStorage::new();
+        let position = |(q, r): (isize, isize)| AxialVector::new(q, r);
+        for item in &self.items {
+            let opened = match item {
+                ScenarioItem::Room { center, radius } => {
+                    position(*center).spiral_iter(*radius).collect::<Vec<_>>()
+                }
+                ScenarioItem::Corridor { start, end } => {
+                    position(*start).line_iter(position(*end)).collect()
+                }
+                ScenarioItem::Open(positions) => positions.iter().copied().map(position).collect(),
+                ScenarioItem::Walls(positions) => {
+                    for wall in positions {
+                        hexes.insert(position(*wall), HexState::Wall);
+                    }
+                    continue;
+                }
+            };
+            for open in &opened {
+                hexes.insert(*open, HexState::Open);
+            }
+            if let ScenarioItem::Open(..) = item {
+                continue;
+            }
+            for open in &opened {
+                for neighbor in open.ring_iter(1) {
+                    hexes.entry(neighbor).or_insert(HexState::Wall);
+                }
+            }
+        }
+        hexes
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScenarioLibrary {
+    pub scenarios: Vec<Scenario>,
+}
+
+impl ScenarioLibrary {
+    fn validate(&self) -> Result<(), String> {
+        if self.scenarios.is_empty() {
+            return Err("no scenario".to_string());
+        }
+        for scenario in &self.scenarios {
+            if !scenario
+                .hexes()
+                .hexes()
+                .any(|state| *state == HexState::Open)
+            {
+                return Err(format!("scenario {} has no open hex", scenario.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The hexagons and the corridor the custom world always had.
+impl Default for ScenarioLibrary {
+    fn default() -> Self {
+        let hexagon = |radius| Scenario {
+            name: format!("hexagon {}", radius),
+            items: vec![ScenarioItem::Room {
+                center: (0, 0),
+                radius,
+            }],
+        };
+        Self {
+            scenarios: vec![
+                hexagon(0),
+                hexagon(1),
+                Scenario {
+                    name: "corridor".to_string(),
+                    items: vec![ScenarioItem::Corridor {
+                        start: (0, 0),
+                        end: (1, 0),
+                    }],
+                },
+            ],
+        }
+    }
+}
+
+// A missing file means the default scenarios, a broken one is worth a warning.
+pub fn load_scenarios(path: &Path) -> Vec<Scenario> {
+    if !path.exists() {
+        return ScenarioLibrary::default().scenarios;
+    }
+    match ScenarioLibrary::load(path)
+        .map_err(|err| err.to_string())
+        .and_then(|library| {
+            library.validate()?;
+            Ok(library)
+        }) {
+        Ok(library) => library.scenarios,
+        Err(err) => {
+            warn!(
+                "Failed to read custom scenarios file: `{}` ({}), using the default scenarios.",
+                path.display(),
+                err
+            );
+            ScenarioLibrary::default().scenarios
+        }
+    }
+}
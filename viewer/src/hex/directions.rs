@@ -36,7 +36,7 @@ impl HexDirectionsDemo {
             let mut transform = Transform::default();
             transform.set_scale(Vector3::new(0.3, 0.1, 0.3));
             let pos = (origin, 0.0).into();
-            world.transform_axial(pos, &mut transform);
+            world.transform_hex(pos, &mut transform);
             let material = world.assets.color_data[&color].light.clone();
             self.entities.push(
                 data.world
@@ -0,0 +1,796 @@
+use crate::{
+    assets::{Color, Translucent},
+    dispose::Dispose,
+    hex::{
+        highlight::{HexHighlight, HighlightKind},
+        inspect::{self, HexLayer, HexTag},
+        pointer::{HexPointer, PointerAction, PointerKeys, PointerSettings},
+        render::{
+            renderer::HexRenderer,
+            tile::{HexScale, TileRenderer},
+        },
+    },
+    input::get_key_and_modifiers,
+    pacing::{set_window_title, AnimationSettings},
+    systems::{
+        event_log::{log_event, EventKind},
+        file_watch::FileChanges,
+        follow_me::FollowMeSettings,
+        grid_overlay::{GridSettings, RangeOverlay},
+        lighting::LightingConfig,
+        path_preview::PathPreview,
+    },
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    core::{math::Vector3, transform::Transform},
+    ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    winit::VirtualKeyCode,
+};
+use log::info;
+use rhombus_core::hex::{
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    float::{
+        export::{write_svg, write_tmx, TmxLayer},
+        layout::HexLayout,
+    },
+    flood_fill::flood_fill,
+    map_file::MapFile,
+    reachable::reachable,
+    storage::{
+        hash::{EmptyRectPolicy, RectHashStorage},
+        history::StorageHistory,
+    },
+};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write as _},
+    path::Path,
+    sync::Arc,
+    time::SystemTime,
+};
+
+// Written to and read from the current directory.
+pub const MAP_PATH: &str = "editor.rhmap";
+const TMX_PATH: &str = "editor.tmx";
+const SVG_PATH: &str = "editor.svg";
+
+// Larger areas are not considered enclosed.
+const FILL_LIMIT: usize = 4096;
+const TMX_HEX_SIDE: u32 = 16;
+// Movement points of the range tool, markers cost two of them.
+const RANGE_BUDGET: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Layer {
+    Terrain,
+    Walls,
+    Markers,
+}
+
+impl Layer {
+    fn next(self) -> Self {
+        match self {
+            Layer::Terrain => Layer::Walls,
+            Layer::Walls => Layer::Markers,
+            Layer::Markers => Layer::Terrain,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Layer::Terrain => "terrain",
+            Layer::Walls => "walls",
+            Layer::Markers => "markers",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Tool {
+    Brush,
+    // From the marked hex to the pointer.
+    Line,
+    // The enclosed area around the pointer.
+    Fill,
+    // From the marked origin to the pointer, and the area around the pointer.
+    Measure,
+    // The hexes the pointer can walk to, around walls and through markers.
+    Range,
+}
+
+// The terrain layer is the presence of the hex in the map, walls and markers stand on it.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+struct EditorHex {
+    wall: bool,
+    marker: bool,
+}
+
+impl EditorHex {
+    fn has(&self, layer: Layer) -> bool {
+        match layer {
+            Layer::Terrain => true,
+            Layer::Walls => self.wall,
+            Layer::Markers => self.marker,
+        }
+    }
+}
+
+// Each layer is saved as a layer of the map file, walls and markers being optional.
+fn to_map_file(map: &RectHashStorage<EditorHex>, layout: HexLayout) -> io::Result<MapFile> {
+    let mut file = MapFile::new(layout);
+    for layer in &[Layer::Terrain, Layer::Walls, Layer::Markers] {
+        let mut hexes = RectHashStorage::<()>::new();
+        for (position, hex) in map.iter() {
+            if hex.has(*layer) {
+                hexes.insert(position, ());
+            }
+        }
+        file.set_layer(layer.name(), &hexes)?;
+    }
+    Ok(file)
+}
+
+fn from_map_file(file: &MapFile) -> io::Result<RectHashStorage<EditorHex>> {
+    let terrain: RectHashStorage<()> = file
+        .layer(Layer::Terrain.name())?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No terrain layer".to_owned()))?;
+    let mut map = RectHashStorage::new();
+    for (position, _) in terrain.iter() {
+        map.insert(position, EditorHex::default());
+    }
+    let walls: Option<RectHashStorage<()>> = file.layer(Layer::Walls.name())?;
+    let markers: Option<RectHashStorage<()>> = file.layer(Layer::Markers.name())?;
+    for (position, hex) in map.iter_mut() {
+        hex.wall = walls
+            .as_ref()
+            .map_or(false, |walls| walls.contains_position(position));
+        hex.marker = markers
+            .as_ref()
+            .map_or(false, |markers| markers.contains_position(position));
+    }
+    Ok(map)
+}
+
+// What is currently displayed of a map hex.
+struct RenderedHex {
+    hex: EditorHex,
+    tile: <TileRenderer as HexRenderer>::Hex,
+}
+
+impl Dispose for RenderedHex {
+    fn dispose(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        self.tile.dispose(data);
+    }
+}
+
+pub struct HexEditorDemo {
+    map: RectHashStorage<EditorHex>,
+    history: StorageHistory<EditorHex>,
+    // Brought in line with the map after every change.
+    rendered: RectHashStorage<RenderedHex>,
+    markers: RectHashStorage<Entity>,
+    renderer: TileRenderer,
+    // Ghost hexes of the line about to be drawn, slightly larger than the ground tiles.
+    preview: RectHashStorage<<TileRenderer as HexRenderer>::Hex>,
+    preview_renderer: TileRenderer,
+    marked_highlight: HexHighlight,
+    pointer: HexPointer,
+    keys: PointerKeys,
+    layer: Layer,
+    tool: Tool,
+    line_start: Option<AxialVector>,
+    measure_origin: Option<AxialVector>,
+    // When the map file was last saved, it does not need to be loaded again then.
+    saved: Option<SystemTime>,
+}
+
+impl HexEditorDemo {
+    pub fn new() -> Self {
+        Self {
+            map: Self::new_map(),
+            history: StorageHistory::new(),
+            rendered: RectHashStorage::new(),
+            markers: RectHashStorage::new(),
+            renderer: TileRenderer::new(
+                HexScale {
+                    horizontal: 0.9,
+                    vertical: 0.1,
+                },
+                HexScale {
+                    horizontal: 0.9,
+                    vertical: 0.3,
+                },
+            ),
+            preview: RectHashStorage::new(),
+            preview_renderer: TileRenderer::new(
+                HexScale {
+                    horizontal: 0.95,
+                    vertical: 0.15,
+                },
+                HexScale {
+                    horizontal: 0.95,
+                    vertical: 0.15,
+                },
+            ),
+            marked_highlight: HexHighlight::new(HighlightKind::Selection),
+            pointer: HexPointer::new_with_level_height(1.0),
+            keys: PointerKeys::default(),
+            layer: Layer::Terrain,
+            tool: Tool::Brush,
+            line_start: None,
+            measure_origin: None,
+            saved: None,
+        }
+    }
+
+    // Erased areas give their memory back.
+    fn new_map() -> RectHashStorage<EditorHex> {
+        let mut map = RectHashStorage::new();
+        map.set_empty_rect_policy(EmptyRectPolicy::Prune);
+        map
+    }
+
+    fn snapshot(&self) -> RectHashStorage<EditorHex> {
+        let mut snapshot = RectHashStorage::new();
+        for (position, hex) in self.map.iter() {
+            snapshot.insert(position, *hex);
+        }
+        snapshot
+    }
+
+    fn paint(&mut self, position: AxialVector, on: bool) {
+        match (self.layer, on) {
+            (Layer::Terrain, true) => {
+                self.map.entry(position).or_insert_with(EditorHex::default);
+            }
+            (Layer::Terrain, false) => {
+                self.map.remove(position);
+            }
+            (Layer::Walls, _) => {
+                self.map
+                    .entry(position)
+                    .or_insert_with(EditorHex::default)
+                    .wall = on;
+            }
+            (Layer::Markers, _) => {
+                self.map
+                    .entry(position)
+                    .or_insert_with(EditorHex::default)
+                    .marker = on;
+            }
+        }
+    }
+
+    fn apply_tool(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let position = self.pointer.position();
+        match self.tool {
+            Tool::Brush => {
+                let on = !matches!(self.map.get(position), Some(hex) if hex.has(self.layer));
+                self.paint(position, on);
+            }
+            Tool::Line => {
+                if let Some(start) = self.line_start.take() {
+                    for hex in start.line_iter(position) {
+                        self.paint(hex, true);
+                    }
+                } else {
+                    self.line_start = Some(position);
+                    return;
+                }
+            }
+            Tool::Fill => {
+                let map = &self.map;
+                let is_open = |hex| !matches!(map.get(hex), Some(EditorHex { wall: true, .. }));
+                if let Some(area) = flood_fill(position, FILL_LIMIT, is_open) {
+                    for hex in area.iter() {
+                        self.paint(hex, true);
+                    }
+                } else {
+                    log_event(
+                        data.world,
+                        EventKind::Error,
+                        format!("The area around {:?} is not enclosed", position),
+                    );
+                    return;
+                }
+            }
+            Tool::Measure => {
+                self.measure_origin = Some(position);
+                return;
+            }
+            Tool::Range => return,
+        }
+        self.record(data);
+    }
+
+    fn record(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        // Painting after undoing forgets the undone steps.
+        self.history.truncate();
+        let snapshot = self.snapshot();
+        self.history.record(snapshot);
+        self.sync(data);
+    }
+
+    fn undo(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        if let Some(delta) = self.history.step_backward().cloned() {
+            delta.apply_into(&mut self.map);
+            self.sync(data);
+        }
+    }
+
+    fn redo(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        if let Some(delta) = self.history.step_forward().cloned() {
+            delta.apply_into(&mut self.map);
+            self.sync(data);
+        }
+    }
+
+    // Brings the rendered hexes and markers in line with the map.
+    fn sync(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        let removed = self
+            .rendered
+            .positions()
+            .filter(|position| !self.map.contains_position(*position))
+            .collect::<Vec<_>>();
+        for position in removed {
+            if let Some(mut hex) = self.rendered.remove(position) {
+                hex.dispose(data);
+            }
+            if let Some(entity) = self.markers.remove(position) {
+                data.world.delete_entity(entity).expect("delete entity");
+            }
+        }
+        let changed = self
+            .map
+            .iter()
+            .filter(|(position, hex)| self.rendered.get(*position).map(|r| r.hex) != Some(**hex))
+            .map(|(position, hex)| (position, *hex))
+            .collect::<Vec<_>>();
+        for (position, hex) in changed {
+            let tile = self.renderer.new_hex(hex.wall, true);
+            if let Some(mut old) = self.rendered.insert(position, RenderedHex { hex, tile }) {
+                old.dispose(data);
+            }
+            self.renderer.update_hex(
+                position,
+                &mut self.rendered.get_mut(position).expect("rendered hex").tile,
+                data,
+                &world,
+            );
+            match (hex.marker, self.markers.contains_position(position)) {
+                (true, false) => {
+                    let entity = Self::create_marker(position, data, &world);
+                    self.markers.insert(position, entity);
+                }
+                (false, true) => {
+                    if let Some(entity) = self.markers.remove(position) {
+                        data.world.delete_entity(entity).expect("delete entity");
+                    }
+                }
+                _ => {}
+            }
+        }
+        // The measurements depend on the map.
+        self.update_preview(data);
+    }
+
+    fn update_preview(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.preview.dispose(data);
+        let marked = match self.tool {
+            Tool::Line => self.line_start,
+            Tool::Measure => self.measure_origin,
+            Tool::Brush | Tool::Fill | Tool::Range => None,
+        };
+        // Above the preview.
+        self.marked_highlight
+            .set_position(marked.map(|start| (start, 0.3)), data, &world);
+        let path = marked.map_or_else(Vec::new, |start| {
+            start.line_iter(self.pointer.position()).collect::<Vec<_>>()
+        });
+        if self.tool == Tool::Line {
+            for position in &path {
+                let mut hex = self.preview_renderer.new_hex(false, true);
+                hex.set_translucent(Some(Translucent::Ghost));
+                self.preview_renderer
+                    .update_hex(*position, &mut hex, data, &world);
+                self.preview.insert(*position, hex);
+            }
+        }
+        let range = if self.tool == Tool::Range {
+            self.range()
+        } else {
+            Vec::new()
+        };
+        self.update_title(&path, &range, data);
+        // Above the ghost hexes.
+        *data.world.write_resource::<PathPreview>() = PathPreview { path, height: 0.4 };
+        *data.world.write_resource::<RangeOverlay>() = RangeOverlay {
+            hexes: range.iter().map(|(position, _)| *position).collect(),
+        };
+    }
+
+    // Walls and missing terrain cannot be entered.
+    fn range(&self) -> Vec<(AxialVector, usize)> {
+        let map = &self.map;
+        let cost = |_: AxialVector, to: AxialVector| match map.get(to) {
+            Some(EditorHex {
+                wall: false,
+                marker,
+            }) => Some(if *marker { 2 } else { 1 }),
+            _ => None,
+        };
+        reachable(self.pointer.position(), RANGE_BUDGET, cost)
+    }
+
+    // The measurements go to the window title.
+    fn update_title(
+        &self,
+        path: &[AxialVector],
+        range: &[(AxialVector, usize)],
+        data: &StateData<'_, GameData<'_, '_>>,
+    ) {
+        let settings = data.world.read_resource::<AnimationSettings>();
+        if self.tool == Tool::Range {
+            let farthest = range
+                .iter()
+                .map(|(position, _)| position.distance(self.pointer.position()))
+                .max()
+                .unwrap_or(0);
+            set_window_title(
+                data.world,
+                &format!(
+                    "{} - {} hexes within {} moves, {} away at most",
+                    settings.window_title,
+                    range.len(),
+                    RANGE_BUDGET,
+                    farthest
+                ),
+            );
+            return;
+        }
+        if self.tool != Tool::Measure {
+            set_window_title(data.world, &settings.window_title);
+            return;
+        }
+        let position = self.pointer.position();
+        let map = &self.map;
+        let line = if let Some(origin) = self.measure_origin {
+            let is_wall = |hex: &&AxialVector| matches!(map.get(**hex), Some(hex) if hex.wall);
+            format!(
+                "distance {} (line of {} hexes, {} walls)",
+                origin.distance(position),
+                path.len(),
+                path.iter().filter(is_wall).count()
+            )
+        } else {
+            "no origin (Space marks it)".to_owned()
+        };
+        // The ground connected to the pointer.
+        let is_open = |hex| matches!(map.get(hex), Some(EditorHex { wall: false, .. }));
+        let area = match flood_fill(position, FILL_LIMIT, is_open) {
+            Some(area) if area.is_empty() => "no area".to_owned(),
+            Some(area) => {
+                let markers = area
+                    .iter()
+                    .filter(|hex| matches!(map.get(*hex), Some(hex) if hex.marker))
+                    .count();
+                format!("area of {} hexes ({} markers)", area.len(), markers)
+            }
+            None => format!("area of more than {} hexes", FILL_LIMIT),
+        };
+        set_window_title(
+            data.world,
+            &format!("{} - {}, {}", settings.window_title, line, area),
+        );
+    }
+
+    fn create_marker(
+        position: AxialVector,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) -> Entity {
+        let mut transform = Transform::default();
+        transform.set_scale(Vector3::new(0.4, 0.2, 0.4));
+        world.transform_hex((position, 0.4).into(), &mut transform);
+        data.world
+            .create_entity()
+            .with(world.assets.hex_handle.clone())
+            .with(world.assets.color_data[&Color::Yellow].light.clone())
+            .with(transform)
+            .with(HexTag::new(position, HexLayer::Marker, "editor"))
+            .build()
+    }
+
+    fn clear(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        self.renderer.clear(data);
+        self.rendered.dispose(data);
+        self.preview_renderer.clear(data);
+        self.preview.dispose(data);
+        self.marked_highlight.dispose(data);
+        *data.world.write_resource::<PathPreview>() = PathPreview::default();
+        *data.world.write_resource::<RangeOverlay>() = RangeOverlay::default();
+        let title = data
+            .world
+            .read_resource::<AnimationSettings>()
+            .window_title
+            .clone();
+        set_window_title(data.world, &title);
+        for entity in self.markers.hexes() {
+            data.world.delete_entity(*entity).expect("delete entity");
+        }
+        self.markers.clear();
+    }
+
+    fn save(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        let layout = data.world.read_resource::<Arc<RhombusViewerWorld>>().layout;
+        let result = File::create(MAP_PATH).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            to_map_file(&self.map, layout)?.write_to(&mut writer)?;
+            writer.flush()
+        });
+        match result {
+            Ok(()) => {
+                self.saved = map_modified();
+                log_event(
+                    data.world,
+                    EventKind::File,
+                    format!("Map saved to {}", MAP_PATH),
+                );
+            }
+            Err(err) => log_event(
+                data.world,
+                EventKind::Error,
+                format!("Failed to save the map ({})", err),
+            ),
+        }
+    }
+
+    fn load(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let result = File::open(MAP_PATH)
+            .and_then(|file| MapFile::read_from(&mut BufReader::new(file)))
+            .and_then(|file| from_map_file(&file));
+        match result {
+            Ok(mut map) => {
+                map.set_empty_rect_policy(EmptyRectPolicy::Prune);
+                self.map = map;
+                let snapshot = self.snapshot();
+                self.history.reset(snapshot);
+                self.line_start = None;
+                self.measure_origin = None;
+                self.sync(data);
+                log_event(
+                    data.world,
+                    EventKind::File,
+                    format!("Map loaded from {}", MAP_PATH),
+                );
+            }
+            Err(err) => log_event(
+                data.world,
+                EventKind::Error,
+                format!("Failed to load the map ({})", err),
+            ),
+        }
+    }
+
+    fn export(&self, world: &RhombusViewerWorld, data: &StateData<'_, GameData<'_, '_>>) {
+        let terrain = |_: &EditorHex| 1u32;
+        let walls = |hex: &EditorHex| if hex.wall { 2u32 } else { 0 };
+        let markers = |hex: &EditorHex| if hex.marker { 3u32 } else { 0 };
+        let result = File::create(TMX_PATH)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                write_tmx(
+                    &mut writer,
+                    &self.map,
+                    world.layout.orientation(),
+                    TMX_HEX_SIDE,
+                    &[
+                        TmxLayer {
+                            name: "terrain",
+                            tile: &terrain,
+                        },
+                        TmxLayer {
+                            name: "walls",
+                            tile: &walls,
+                        },
+                        TmxLayer {
+                            name: "markers",
+                            tile: &markers,
+                        },
+                    ],
+                )?;
+                writer.flush()
+            })
+            .and_then(|_| File::create(SVG_PATH))
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                write_svg(&mut writer, &self.map, &world.layout, |hex| {
+                    Some(match (hex.wall, hex.marker) {
+                        (_, true) => "gold",
+                        (true, false) => "dimgray",
+                        (false, false) => "tan",
+                    })
+                })?;
+                writer.flush()
+            });
+        match result {
+            Ok(()) => log_event(
+                data.world,
+                EventKind::File,
+                format!("Map exported to {} and {}", TMX_PATH, SVG_PATH),
+            ),
+            Err(err) => log_event(
+                data.world,
+                EventKind::Error,
+                format!("Failed to export the map ({})", err),
+            ),
+        }
+    }
+
+    fn move_pointer(
+        &mut self,
+        direction: usize,
+        data: &StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        let next = self.pointer.position().neighbor(direction);
+        self.pointer.set_position(next, 0, data, world);
+    }
+
+    fn apply_pointer_action(
+        &mut self,
+        action: PointerAction,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        match action {
+            PointerAction::Move(mode) => {
+                self.pointer.face_camera(data, &world);
+                self.move_pointer(mode.direction(self.pointer.direction()), data, &world);
+                self.update_preview(data);
+            }
+            PointerAction::TurnLeft => self.pointer.decrement_direction(data, &world),
+            PointerAction::TurnRight => self.pointer.increment_direction(data, &world),
+        }
+    }
+}
+
+fn map_modified() -> Option<SystemTime> {
+    std::fs::metadata(MAP_PATH)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl SimpleState for HexEditorDemo {
+    fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.keys.release_all();
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.pointer.create_entities(&mut data, &world);
+        self.map
+            .insert(self.pointer.position(), EditorHex::default());
+        let snapshot = self.snapshot();
+        self.history.reset(snapshot);
+        self.sync(&mut data);
+    }
+
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.pointer.delete_entities(&mut data, &world);
+        self.clear(&mut data);
+    }
+
+    fn handle_event(
+        &mut self,
+        mut data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = event {
+            if PointerSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if FollowMeSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if inspect::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if GridSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if LightingConfig::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if let Some(action) = self.keys.handle_event(&data, &event) {
+                self.apply_pointer_action(action, &mut data);
+                return Trans::None;
+            }
+            let mut trans = Trans::None;
+            let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+            match get_key_and_modifiers(&event) {
+                Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
+                    trans = Trans::Pop;
+                }
+                Some((VirtualKeyCode::C, ElementState::Pressed, _)) => {
+                    world.toggle_follow(&data);
+                }
+                Some((VirtualKeyCode::Key1, ElementState::Pressed, _)) => {
+                    self.tool = Tool::Brush;
+                    self.update_preview(&mut data);
+                    info!("Tool: {:?}", self.tool);
+                }
+                Some((VirtualKeyCode::Key2, ElementState::Pressed, _)) => {
+                    self.tool = Tool::Line;
+                    self.line_start = None;
+                    self.update_preview(&mut data);
+                    info!("Tool: {:?}", self.tool);
+                }
+                Some((VirtualKeyCode::Key3, ElementState::Pressed, _)) => {
+                    self.tool = Tool::Fill;
+                    self.update_preview(&mut data);
+                    info!("Tool: {:?}", self.tool);
+                }
+                Some((VirtualKeyCode::Key4, ElementState::Pressed, _)) => {
+                    self.tool = Tool::Measure;
+                    self.update_preview(&mut data);
+                    info!("Tool: {:?}", self.tool);
+                }
+                Some((VirtualKeyCode::Key5, ElementState::Pressed, _)) => {
+                    self.tool = Tool::Range;
+                    self.update_preview(&mut data);
+                    info!("Tool: {:?}", self.tool);
+                }
+                Some((VirtualKeyCode::L, ElementState::Pressed, _)) => {
+                    self.layer = self.layer.next();
+                    info!("Layer: {:?}", self.layer);
+                }
+                Some((VirtualKeyCode::Space, ElementState::Pressed, _)) => {
+                    self.apply_tool(&mut data);
+                    self.update_preview(&mut data);
+                }
+                Some((VirtualKeyCode::Z, ElementState::Pressed, modifiers)) => {
+                    if modifiers.shift {
+                        self.redo(&mut data);
+                    } else {
+                        self.undo(&mut data);
+                    }
+                }
+                Some((VirtualKeyCode::Y, ElementState::Pressed, _)) => {
+                    self.redo(&mut data);
+                }
+                Some((VirtualKeyCode::S, ElementState::Pressed, _)) => {
+                    self.save(&data);
+                }
+                Some((VirtualKeyCode::O, ElementState::Pressed, _)) => {
+                    self.load(&mut data);
+                }
+                Some((VirtualKeyCode::E, ElementState::Pressed, _)) => {
+                    self.export(&world, &data);
+                }
+                _ => {}
+            }
+            trans
+        } else {
+            Trans::None
+        }
+    }
+
+    // The map file edited elsewhere (or replaced by another one) is loaded right away.
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(action) = self.keys.repeat(data) {
+            self.apply_pointer_action(action, data);
+        }
+        let changed = data
+            .world
+            .read_resource::<FileChanges>()
+            .contains(Path::new(MAP_PATH));
+        if changed && map_modified() != self.saved {
+            self.load(data);
+        }
+        Trans::None
+    }
+}
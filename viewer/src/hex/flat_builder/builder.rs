@@ -0,0 +1,156 @@
+use crate::{
+    dispose::Dispose,
+    hex::{
+        flat_builder::logic::{FlatBuilder, HexState, Tool},
+        inspect,
+        pointer::{HexPointer, PointerSettings},
+        render::{
+            renderer::HexRenderer,
+            storage::EcsBackedStorage,
+            tile::{HexScale, TileRenderer},
+        },
+    },
+    systems::{follow_me::FollowMeSettings, grid_overlay::GridSettings, lighting::LightingConfig},
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    input::{get_key, ElementState},
+    prelude::*,
+    winit::VirtualKeyCode,
+};
+use std::sync::Arc;
+
+// Thin adapter of the flat builder logic: the commands go to the logic, then the changed hexes are
+// rendered again and the pointer follows.
+pub struct HexFlatBuilderDemo {
+    builder: FlatBuilder,
+    world: EcsBackedStorage<TileRenderer>,
+    pointer: HexPointer,
+}
+
+impl HexFlatBuilderDemo {
+    pub fn new() -> Self {
+        let world = EcsBackedStorage::new(TileRenderer::new(
+            HexScale {
+                horizontal: 0.8,
+                vertical: 0.1,
+            },
+            HexScale {
+                horizontal: 0.8,
+                vertical: 0.3,
+            },
+        ));
+        let pointer = HexPointer::new_with_level_height(1.0);
+        Self {
+            builder: FlatBuilder::new(),
+            world,
+            pointer,
+        }
+    }
+
+    fn sync(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        for position in self.builder.take_changes() {
+            let wall = self.builder.hexes().get(position) == Some(&HexState::Wall);
+            let hex = self.world.renderer_mut().new_hex(wall, true);
+            self.world.insert(position, hex, data);
+            let (hex, renderer) = self.world.hex_and_renderer_mut(position).unwrap();
+            renderer.update_hex(position, hex, data, world);
+        }
+        if self.pointer.direction() != self.builder.direction() {
+            self.pointer.set_direction(
+                self.builder.direction(),
+                self.pointer.vertical_direction(),
+                data,
+                world,
+            );
+        }
+        self.pointer
+            .set_position(self.builder.position(), 0, data, world);
+    }
+}
+
+impl SimpleState for HexFlatBuilderDemo {
+    fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.pointer.create_entities(&mut data, &world);
+        self.sync(&mut data, &world);
+    }
+
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.pointer.delete_entities(&mut data, &world);
+        self.world.dispose(&mut data);
+    }
+
+    fn handle_event(
+        &mut self,
+        mut data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = event {
+            if PointerSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if FollowMeSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if inspect::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if GridSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if LightingConfig::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            let mut trans = Trans::None;
+            let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+            match get_key(&event) {
+                Some((VirtualKeyCode::Escape, ElementState::Pressed)) => {
+                    trans = Trans::Pop;
+                }
+                Some((VirtualKeyCode::Key1, ElementState::Pressed)) => {
+                    self.builder.select_tool(Tool::Walk);
+                }
+                Some((VirtualKeyCode::Key2, ElementState::Pressed)) => {
+                    self.builder.select_tool(Tool::Line);
+                }
+                Some((VirtualKeyCode::Key3, ElementState::Pressed)) => {
+                    self.builder.select_tool(Tool::Fill);
+                }
+                Some((VirtualKeyCode::Key4, ElementState::Pressed)) => {
+                    self.builder.select_tool(Tool::Rectangle);
+                }
+                Some((VirtualKeyCode::Key5, ElementState::Pressed)) => {
+                    self.builder.select_tool(Tool::Hexagon);
+                }
+                Some((VirtualKeyCode::Key6, ElementState::Pressed)) => {
+                    self.builder.select_tool(Tool::Corridor);
+                }
+                Some((VirtualKeyCode::Space, ElementState::Pressed)) => {
+                    self.builder.apply_tool();
+                }
+                Some((VirtualKeyCode::Up, ElementState::Pressed)) => {
+                    // The camera decides in the camera relative mode.
+                    self.pointer.face_camera(&data, &world);
+                    self.builder.set_direction(self.pointer.direction());
+                    self.builder.forward();
+                }
+                Some((VirtualKeyCode::Right, ElementState::Pressed)) => {
+                    self.builder.turn_right();
+                }
+                Some((VirtualKeyCode::Left, ElementState::Pressed)) => {
+                    self.builder.turn_left();
+                }
+                Some((VirtualKeyCode::C, ElementState::Pressed)) => {
+                    world.toggle_follow(&data);
+                }
+                _ => {}
+            }
+            self.sync(&mut data, &world);
+            trans
+        } else {
+            Trans::None
+        }
+    }
+}
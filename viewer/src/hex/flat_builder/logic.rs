@@ -0,0 +1,334 @@
+use log::{info, warn};
+use rhombus_core::hex::{
+    carve::carve_line_to_open,
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    flood_fill::flood_fill,
+    shape::cubic_range::CubicRangeShape,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+
+// Larger areas are not considered enclosed.
+const FILL_LIMIT: usize = 4096;
+const STAMP_RADIUS: isize = 3;
+const STAMP_HALF_WIDTH: isize = 5;
+const STAMP_HALF_HEIGHT: isize = 3;
+const CORRIDOR_WIDTH: usize = 3;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HexState {
+    Open,
+    Wall,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tool {
+    // Walks and raises walls along the way.
+    Walk,
+    // Raises a straight wall from the marked hex to the pointer.
+    Line,
+    // Opens the enclosed area around the pointer.
+    Fill,
+    // Walled rooms centered on the pointer.
+    Rectangle,
+    Hexagon,
+    // Opens a walled corridor from the marked hex towards the pointer, up to the first open area.
+    Corridor,
+}
+
+// The map and the pointer of the flat builder, without any entity: the demo state renders the
+// changed hexes and moves the pointer entities after each command.
+pub struct FlatBuilder {
+    hexes: RectHashStorage<HexState>,
+    position: AxialVector,
+    direction: usize,
+    tool: Tool,
+    line_start: Option<AxialVector>,
+    changes: Vec<AxialVector>,
+}
+
+impl FlatBuilder {
+    pub fn new() -> Self {
+        let mut builder = Self {
+            hexes: RectHashStorage::new(),
+            position: AxialVector::default(),
+            direction: 0,
+            tool: Tool::Walk,
+            line_start: None,
+            changes: Vec::new(),
+        };
+        builder.set_state(builder.position, HexState::Open);
+        builder
+    }
+
+    pub fn hexes(&self) -> &RectHashStorage<HexState> {
+        &self.hexes
+    }
+
+    pub fn position(&self) -> AxialVector {
+        self.position
+    }
+
+    pub fn direction(&self) -> usize {
+        self.direction
+    }
+
+    // Positions whose state was set since the previous call, possibly several times.
+    pub fn take_changes(&mut self) -> Vec<AxialVector> {
+        std::mem::take(&mut self.changes)
+    }
+
+    fn set_state(&mut self, position: AxialVector, state: HexState) {
+        self.hexes.insert(position, state);
+        self.changes.push(position);
+    }
+
+    fn raise_wall(&mut self, position: AxialVector) {
+        self.set_state(position, HexState::Wall);
+    }
+
+    fn state(&self, position: AxialVector) -> Option<HexState> {
+        self.hexes.get(position).copied()
+    }
+
+    pub fn select_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.line_start = None;
+        info!("Tool: {:?}", tool);
+    }
+
+    pub fn set_direction(&mut self, direction: usize) {
+        self.direction = direction;
+    }
+
+    pub fn turn_right(&mut self) {
+        self.direction = (self.direction + 1) % 6;
+    }
+
+    pub fn turn_left(&mut self) {
+        self.direction = (self.direction + 5) % 6;
+    }
+
+    pub fn forward(&mut self) {
+        let next = self.position.neighbor(self.direction);
+        if self.tool != Tool::Walk {
+            // The editing tools move the pointer freely.
+            self.position = next;
+            return;
+        }
+        match self.state(next) {
+            Some(HexState::Open) => {}
+            Some(HexState::Wall) => return,
+            None => {
+                self.set_state(next, HexState::Open);
+                // Left
+                self.raise_wall(self.position.neighbor((self.direction + 1) % 6));
+                // Right
+                self.raise_wall(self.position.neighbor((self.direction + 5) % 6));
+                // Ahead
+                let ahead_left = next.neighbor((self.direction + 1) % 6);
+                let ahead = next.neighbor(self.direction);
+                let ahead_right = next.neighbor((self.direction + 5) % 6);
+                match (
+                    self.state(ahead_left),
+                    self.state(ahead),
+                    self.state(ahead_right),
+                ) {
+                    (Some(HexState::Open), _, _) | (_, _, Some(HexState::Open)) => {
+                        self.raise_wall(ahead);
+                    }
+                    (_, Some(HexState::Open), _) => {
+                        self.raise_wall(ahead_left);
+                        self.raise_wall(ahead_right);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.position = next;
+    }
+
+    pub fn apply_tool(&mut self) {
+        let position = self.position;
+        match self.tool {
+            Tool::Walk => {}
+            Tool::Line | Tool::Corridor => {
+                if let Some(start) = self.line_start.take() {
+                    if self.tool == Tool::Line {
+                        for hex in start.line_iter(position) {
+                            self.raise_wall(hex);
+                        }
+                    } else {
+                        self.carve_corridor(start, position);
+                    }
+                } else {
+                    self.line_start = Some(position);
+                }
+            }
+            Tool::Fill => {
+                let hexes = &self.hexes;
+                let is_open = |hex| hexes.get(hex) != Some(&HexState::Wall);
+                if let Some(area) = flood_fill(position, FILL_LIMIT, is_open) {
+                    for hex in area.iter() {
+                        if self.state(hex) != Some(HexState::Open) {
+                            self.set_state(hex, HexState::Open);
+                        }
+                    }
+                } else {
+                    warn!("The area around {:?} is not enclosed", position);
+                }
+            }
+            Tool::Rectangle => {
+                let mut shape = HexSet::new();
+                for r in -STAMP_HALF_HEIGHT..=STAMP_HALF_HEIGHT {
+                    // Columns of the offset coordinates, so that the room looks rectangular.
+                    for column in -STAMP_HALF_WIDTH..=STAMP_HALF_WIDTH {
+                        shape.insert(position + AxialVector::new(column - r.div_euclid(2), r));
+                    }
+                }
+                self.stamp(&shape);
+            }
+            Tool::Hexagon => {
+                let mut shape = CubicRangeShape::new(
+                    (-STAMP_RADIUS, STAMP_RADIUS),
+                    (-STAMP_RADIUS, STAMP_RADIUS),
+                    (-STAMP_RADIUS, STAMP_RADIUS),
+                );
+                shape.translate(position);
+                self.stamp(&shape.iter_positions().collect());
+            }
+        }
+    }
+
+    fn carve_corridor(&mut self, start: AxialVector, end: AxialVector) {
+        let carved = carve_line_to_open(
+            &mut self.hexes,
+            start,
+            end,
+            CORRIDOR_WIDTH,
+            |hex| *hex == HexState::Open,
+            |_| HexState::Open,
+        );
+        self.changes.extend(carved.iter());
+        for hex in carved.frontier().iter() {
+            if self.state(hex).is_none() {
+                self.raise_wall(hex);
+            }
+        }
+    }
+
+    // The border of the shape is walled, its inside is open.
+    fn stamp(&mut self, shape: &HexSet) {
+        for hex in shape.iter() {
+            let border = hex.ring_iter(1).any(|neighbor| !shape.contains(neighbor));
+            let state = if border {
+                HexState::Wall
+            } else {
+                HexState::Open
+            };
+            self.set_state(hex, state);
+        }
+    }
+}
+
+#[test]
+fn test_walk_raises_walls() {
+    let mut builder = FlatBuilder::new();
+    assert_eq!(builder.take_changes(), vec![AxialVector::default()]);
+    builder.forward();
+    let origin = AxialVector::default();
+    let next = origin.neighbor(0);
+    assert_eq!(builder.position(), next);
+    assert_eq!(builder.hexes().get(next), Some(&HexState::Open));
+    assert_eq!(
+        builder.hexes().get(origin.neighbor(1)),
+        Some(&HexState::Wall)
+    );
+    assert_eq!(
+        builder.hexes().get(origin.neighbor(5)),
+        Some(&HexState::Wall)
+    );
+    assert_eq!(builder.take_changes().len(), 3);
+
+    // Blocked by the wall on the left.
+    for _ in 0..5 {
+        builder.turn_left();
+    }
+    assert_eq!(builder.direction(), 1);
+    builder.position = origin;
+    builder.forward();
+    assert_eq!(builder.position(), origin);
+    assert!(builder.take_changes().is_empty());
+}
+
+#[test]
+fn test_free_move_and_line() {
+    let mut builder = FlatBuilder::new();
+    builder.select_tool(Tool::Line);
+    builder.apply_tool();
+    for _ in 0..3 {
+        builder.forward();
+    }
+    builder.apply_tool();
+    for hex in AxialVector::default().line_iter(builder.position()) {
+        assert_eq!(builder.hexes().get(hex), Some(&HexState::Wall));
+    }
+    assert_eq!(builder.hexes().len(), 4);
+}
+
+#[test]
+fn test_hexagon_then_fill() {
+    let mut builder = FlatBuilder::new();
+    builder.select_tool(Tool::Fill);
+    builder.apply_tool();
+    assert_eq!(builder.take_changes().len(), 1);
+
+    builder.select_tool(Tool::Hexagon);
+    builder.apply_tool();
+    let origin = AxialVector::default();
+    assert_eq!(
+        builder
+            .hexes()
+            .get(origin + AxialVector::direction(0) * STAMP_RADIUS),
+        Some(&HexState::Wall)
+    );
+    assert_eq!(builder.hexes().get(origin), Some(&HexState::Open));
+    let len = builder.hexes().len();
+    builder.take_changes();
+
+    // Already open and enclosed.
+    builder.select_tool(Tool::Fill);
+    builder.apply_tool();
+    assert!(builder.take_changes().is_empty());
+    assert_eq!(builder.hexes().len(), len);
+}
+
+#[test]
+fn test_corridor() {
+    let mut builder = FlatBuilder::new();
+    builder.select_tool(Tool::Hexagon);
+    builder.apply_tool();
+    builder.take_changes();
+
+    // From outside towards the room, the corridor goes through its wall and stops there.
+    builder.select_tool(Tool::Corridor);
+    builder.position = AxialVector::new(10, 0);
+    builder.apply_tool();
+    builder.position = AxialVector::default();
+    builder.apply_tool();
+    assert!(!builder.take_changes().is_empty());
+    for q in 0..=10 {
+        assert_eq!(
+            builder.hexes().get(AxialVector::new(q, 0)),
+            Some(&HexState::Open)
+        );
+    }
+    assert_eq!(
+        builder.hexes().get(AxialVector::new(7, -2)),
+        Some(&HexState::Wall)
+    );
+    // The room wall is open around the corridor.
+    assert_eq!(
+        builder.hexes().get(AxialVector::new(3, -1)),
+        Some(&HexState::Open)
+    );
+}
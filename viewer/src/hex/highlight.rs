@@ -0,0 +1,110 @@
+use crate::{
+    dispose::Dispose,
+    systems::highlight::{HoverTag, PulseTag},
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    core::{math::Vector3, transform::Transform},
+    ecs::prelude::*,
+    prelude::*,
+};
+use rhombus_core::hex::coordinates::axial::AxialVector;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HighlightKind {
+    // Glowing ring pulsing around the hex.
+    Selection,
+    // Spinning gem hovering above the hex.
+    Target,
+}
+
+// A single highlight entity, moved from hex to hex and animated by the HighlightSystem.
+pub struct HexHighlight {
+    kind: HighlightKind,
+    position: Option<(AxialVector, f32)>,
+    entity: Option<Entity>,
+}
+
+impl HexHighlight {
+    pub fn new(kind: HighlightKind) -> Self {
+        Self {
+            kind,
+            position: None,
+            entity: None,
+        }
+    }
+
+    pub fn position(&self) -> Option<AxialVector> {
+        self.position.map(|(position, _)| position)
+    }
+
+    // The height is the one of the top of the hex.
+    pub fn set_position(
+        &mut self,
+        position: Option<(AxialVector, f32)>,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        if position == self.position {
+            return;
+        }
+        self.dispose(data);
+        self.position = position;
+        if let Some((position, height)) = position {
+            self.entity = Some(self.create_entity(position, height, data, world));
+        }
+    }
+
+    fn create_entity(
+        &self,
+        position: AxialVector,
+        height: f32,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) -> Entity {
+        let mut transform = Transform::default();
+        match self.kind {
+            HighlightKind::Selection => {
+                transform.set_scale(Vector3::new(1.0, 0.05, 1.0));
+                world.transform_hex((position, height + 0.05).into(), &mut transform);
+                let pulse_tag = PulseTag {
+                    scale: *transform.scale(),
+                    period: 1.2,
+                    amplitude: 0.08,
+                };
+                data.world
+                    .create_entity()
+                    .with(world.assets.ring_handle.clone())
+                    .with(world.assets.highlight_material.clone())
+                    .with(transform)
+                    .with(pulse_tag)
+                    .build()
+            }
+            HighlightKind::Target => {
+                transform.set_scale(Vector3::new(0.25, 0.25, 0.25));
+                world.transform_axial((position, height + 0.8).into(), &mut transform);
+                data.world
+                    .create_entity()
+                    .with(world.assets.dodec_handle.clone())
+                    .with(world.assets.highlight_material.clone())
+                    .with(transform)
+                    .with(HoverTag {
+                        height: height + 0.8,
+                        period: 2.0,
+                        amplitude: 0.15,
+                        spin_speed: 1.5,
+                    })
+                    .build()
+            }
+        }
+    }
+}
+
+impl Dispose for HexHighlight {
+    fn dispose(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        self.position = None;
+        if let Some(entity) = self.entity.take() {
+            data.world.delete_entity(entity).expect("delete entity");
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    input::get_key_and_modifiers,
+    systems::{culling::ChunkTag, spatial::EntitySpatialMap},
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    core::{Hidden, Transform},
+    ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    winit::{Event, VirtualKeyCode},
+};
+use log::info;
+use rhombus_core::hex::coordinates::axial::AxialVector;
+use std::sync::Arc;
+
+// Farther than that from the center of its hex, an entity is reported as misplaced.
+const MISPLACED_THRESHOLD: f32 = 0.01;
+
+// The entities around the pointer are only counted, up to that many hexes away.
+const NEARBY_RANGE: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HexLayer {
+    Tile,
+    Square,
+    Floor,
+    Ramp,
+    Bridge,
+    Ceiling,
+    Marker,
+    Pointer,
+    Light,
+    Agent,
+}
+
+// Where an entity stands and who created it, so that orphaned or misplaced entities can be traced
+// back to their hex.
+#[derive(Clone, Copy, Debug)]
+pub struct HexTag {
+    pub position: AxialVector,
+    pub layer: HexLayer,
+    // The module expected to delete the entity.
+    pub owner: &'static str,
+}
+
+impl HexTag {
+    pub fn new(position: AxialVector, layer: HexLayer, owner: &'static str) -> Self {
+        Self {
+            position,
+            layer,
+            owner,
+        }
+    }
+}
+
+// Flagged for the EntitySpatialMap.
+impl Component for HexTag {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+// I logs the entities tagged with the position of the pointer. Returns true if it handled the
+// event.
+pub fn handle_event(data: &StateData<'_, GameData<'_, '_>>, event: &Event) -> bool {
+    match get_key_and_modifiers(event) {
+        Some((VirtualKeyCode::I, ElementState::Pressed, _)) => {
+            log_pointed_entities(data);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn log_pointed_entities(data: &StateData<'_, GameData<'_, '_>>) {
+    let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+    let hex_tags = data.world.read_storage::<HexTag>();
+    let chunk_tags = data.world.read_storage::<ChunkTag>();
+    let transforms = data.world.read_storage::<Transform>();
+    let hiddens = data.world.read_storage::<Hidden>();
+    let position = match hex_tags.join().find(|tag| tag.layer == HexLayer::Pointer) {
+        Some(tag) => tag.position,
+        None => {
+            info!("No pointer to inspect");
+            return;
+        }
+    };
+    let expected = world.axial_translation((position, 0.0).into());
+    let spatial_map = data.world.read_resource::<EntitySpatialMap>();
+    info!("Entities at {:?}:", position);
+    for entity in spatial_map.entities_at(position) {
+        let entity = *entity;
+        let tag = match hex_tags.get(entity) {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let placement = match transforms.get(entity).map(Transform::translation) {
+            Some(translation)
+                if (translation[0] - expected[0]).abs() >= MISPLACED_THRESHOLD
+                    || (translation[2] - expected[2]).abs() >= MISPLACED_THRESHOLD =>
+            {
+                format!("misplaced at {:?}", [translation[0], translation[2]])
+            }
+            Some(..) => "in place".to_owned(),
+            None => "no transform".to_owned(),
+        };
+        info!(
+            "  {:?}: {:?} owned by the {}, {}, {:?}{}",
+            entity,
+            tag.layer,
+            tag.owner,
+            placement,
+            chunk_tags.get(entity),
+            if hiddens.contains(entity) {
+                ", hidden"
+            } else {
+                ""
+            }
+        );
+    }
+    info!(
+        "  {} more entities within {} hexes",
+        spatial_map
+            .entities_in_range(position, NEARBY_RANGE)
+            .filter(|(nearby, _)| *nearby != position)
+            .count(),
+        NEARBY_RANGE
+    );
+}
@@ -0,0 +1,75 @@
+use crate::{
+    dispose::Dispose,
+    hex::inspect::{HexLayer, HexTag},
+    systems::lighting::FlickerTag,
+    theme::HexLight,
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    core::transform::Transform,
+    ecs::prelude::*,
+    prelude::*,
+    renderer::{
+        light::{Light, PointLight},
+        palette::Srgb,
+    },
+};
+use rhombus_core::hex::coordinates::axial::AxialVector;
+
+// The point lights of a map, all replaced at once when the map is generated again.
+#[derive(Default)]
+pub struct HexLights {
+    entities: Vec<Entity>,
+}
+
+impl HexLights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set<I>(
+        &mut self,
+        lights: I,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) where
+        I: IntoIterator<Item = (AxialVector, HexLight)>,
+    {
+        self.dispose(data);
+        for (position, light) in lights {
+            let (r, g, b) = light.color;
+            let point_light = PointLight {
+                color: Srgb::new(r, g, b),
+                intensity: light.intensity,
+                radius: light.radius,
+                ..Default::default()
+            };
+            let mut transform = Transform::default();
+            world.transform_axial((position, light.height).into(), &mut transform);
+            let builder = data
+                .world
+                .create_entity()
+                .with(Light::from(point_light))
+                .with(transform)
+                .with(HexTag::new(position, HexLayer::Light, "hex lights"));
+            self.entities.push(if light.flicker {
+                builder
+                    .with(FlickerTag {
+                        intensity: light.intensity,
+                        seed: (position.q() * 31 + position.r() * 17) as f32,
+                    })
+                    .build()
+            } else {
+                builder.build()
+            });
+        }
+    }
+}
+
+impl Dispose for HexLights {
+    fn dispose(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        for entity in self.entities.drain(..) {
+            data.world.delete_entity(entity).expect("delete entity");
+        }
+    }
+}
@@ -4,37 +4,33 @@ use crate::hex::render::{
     edge::EdgeRenderer,
     multi::MultiRenderer,
     square::{SquareRenderer, SquareScale},
-    tile::{HexScale, TileRenderer},
+    tile::TileRenderer,
 };
 
+pub mod benchmark;
 pub mod bumpy_builder;
 pub mod cellular;
+pub mod crowd;
 pub mod cubic_range_shape;
 pub mod custom;
 pub mod directions;
+pub mod editor;
 pub mod flat_builder;
+pub mod highlight;
+pub mod inspect;
+pub mod lights;
 pub mod pointer;
 pub mod render;
 pub mod ring;
 pub mod rooms_and_mazes;
-pub mod shape;
+pub mod scale;
 pub mod snake;
-
-const HEX_SCALE_HORIZONTAL: f32 = 0.8;
-const GROUND_HEX_SCALE_VERTICAL: f32 = 0.1;
-const WALL_HEX_SCALE_VERTICAL: f32 = 1.0;
+pub mod traversal;
+pub mod visibility;
+pub mod wildfire;
 
 pub fn new_tile_renderer() -> TileRenderer {
-    TileRenderer::new(
-        HexScale {
-            horizontal: HEX_SCALE_HORIZONTAL,
-            vertical: GROUND_HEX_SCALE_VERTICAL,
-        },
-        HexScale {
-            horizontal: HEX_SCALE_HORIZONTAL,
-            vertical: WALL_HEX_SCALE_VERTICAL,
-        },
-    )
+    TileRenderer::following_world_scale().with_fog_of_war()
 }
 
 const SQUARE_SCALE_HORIZONTAL: f32 = 0.7;
@@ -1,22 +1,293 @@
 use crate::{
     assets::{Color, RhombusViewerAssets},
-    world::RhombusViewerWorld,
+    hex::inspect::{HexLayer, HexTag},
+    input::get_key_and_modifiers,
+    sound::{play_cue, SoundCue},
+    systems::{
+        event_log::{log_event, EventKind},
+        highlight::BumpTag,
+        tween::HeightTweenTag,
+    },
+    world::{hex_rotation, RhombusViewerWorld},
 };
 use amethyst::{
     assets::Handle,
+    config::Config,
     core::{
         math::Vector3,
+        timing::Time,
         transform::{Parent, Transform},
     },
     ecs::prelude::*,
+    input::ElementState,
     prelude::*,
     renderer::{
         light::{Light, PointLight},
         palette::Srgb,
         Material,
     },
+    winit::{Event, VirtualKeyCode, WindowEvent},
 };
-use rhombus_core::hex::coordinates::axial::AxialVector;
+use log::{info, warn};
+use rhombus_core::hex::coordinates::{axial::AxialVector, direction::HexagonalDirection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Offset of the pointer mesh from the center of its hex, towards the direction it faces.
+const POINTER_OFFSET: f32 = 0.7;
+const BUMP_DURATION: f32 = 0.15;
+const BUMP_DISTANCE: f32 = 0.25;
+// Seconds to climb or go down to another height.
+const HEIGHT_TWEEN_DURATION: f32 = 0.2;
+
+// Shared by the demos, so that the movement mode survives switching between them.
+#[derive(Default)]
+pub struct PointerSettings {
+    // The pointer turns towards where the camera looks before moving, so that Up moves away from
+    // the camera whatever the rotation of the map.
+    pub camera_relative: bool,
+    // The pointer does not leave the shape in the demos where it can move freely.
+    pub clamp_to_bounds: bool,
+}
+
+impl PointerSettings {
+    // R toggles the camera relative movement. Returns true if it changed.
+    pub fn handle_event(data: &StateData<'_, GameData<'_, '_>>, event: &Event) -> bool {
+        match get_key_and_modifiers(event) {
+            Some((VirtualKeyCode::R, ElementState::Pressed, _)) => {
+                let mut settings = data.world.write_resource::<PointerSettings>();
+                settings.camera_relative = !settings.camera_relative;
+                info!(
+                    "Pointer movement relative to the {}",
+                    if settings.camera_relative {
+                        "camera"
+                    } else {
+                        "map"
+                    }
+                );
+                true
+            }
+            Some((VirtualKeyCode::U, ElementState::Pressed, _)) => {
+                let mut settings = data.world.write_resource::<PointerSettings>();
+                settings.clamp_to_bounds = !settings.clamp_to_bounds;
+                info!(
+                    "Pointer {}",
+                    if settings.clamp_to_bounds {
+                        "clamped to the bounds"
+                    } else {
+                        "free to leave the bounds"
+                    }
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveMode {
+    StraightAhead,
+    StrafeLeftAhead,
+    StrafeLeftBack,
+    StrafeRightAhead,
+    StrafeRightBack,
+    StraightBack,
+}
+
+impl MoveMode {
+    // The direction of the move for a pointer facing the given direction.
+    pub fn direction(self, facing: usize) -> usize {
+        match self {
+            MoveMode::StraightAhead => facing,
+            MoveMode::StrafeLeftAhead => (facing + 5) % 6,
+            MoveMode::StrafeLeftBack => (facing + 4) % 6,
+            MoveMode::StrafeRightAhead => (facing + 1) % 6,
+            MoveMode::StrafeRightBack => (facing + 2) % 6,
+            MoveMode::StraightBack => (facing + 3) % 6,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PointerAction {
+    Move(MoveMode),
+    TurnLeft,
+    TurnRight,
+}
+
+// How the arrow keys held down repeat their pointer action, in seconds, see config/pointer.ron.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PointerRepeatSettings {
+    pub delay: f64,
+    pub interval: f64,
+}
+
+impl Default for PointerRepeatSettings {
+    fn default() -> Self {
+        Self {
+            delay: 0.3,
+            interval: 0.12,
+        }
+    }
+}
+
+impl PointerRepeatSettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.delay.is_nan() || self.delay < 0.0 {
+            return Err("delay must not be negative".to_owned());
+        }
+        if self.interval.is_nan() || self.interval <= 0.0 {
+            return Err("interval must be positive".to_owned());
+        }
+        Ok(())
+    }
+}
+
+// A missing file means the default settings, a broken one is worth a warning.
+pub fn load_pointer_repeat_settings(path: &Path) -> PointerRepeatSettings {
+    if !path.exists() {
+        return PointerRepeatSettings::default();
+    }
+    match PointerRepeatSettings::load(path)
+        .map_err(|err| err.to_string())
+        .and_then(|settings| {
+            settings.validate()?;
+            Ok(settings)
+        }) {
+        Ok(settings) => settings,
+        Err(err) => {
+            warn!(
+                "Failed to read pointer file: `{}` ({}), using the default settings.",
+                path.display(),
+                err
+            );
+            PointerRepeatSettings::default()
+        }
+    }
+}
+
+// The arrow keys held down, turned into pointer actions: Up and Down move, Left and Right turn, or
+// strafe when held with Up or Down, or with Shift (ahead) or Ctrl (back). Each demo keeps its own
+// and asks for the repeats on every update: the action of the keys still held comes again after a
+// delay, then at a steady pace, whatever the key repeat of the system.
+#[derive(Default)]
+pub struct PointerKeys {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+    shift: bool,
+    ctrl: bool,
+    next_repeat: Option<f64>,
+}
+
+impl PointerKeys {
+    // The action of an arrow key being pressed. The keys are all released when the window loses
+    // the focus, it would miss their release.
+    pub fn handle_event(
+        &mut self,
+        data: &StateData<'_, GameData<'_, '_>>,
+        event: &Event,
+    ) -> Option<PointerAction> {
+        if let Event::WindowEvent {
+            event: WindowEvent::Focused(false),
+            ..
+        } = event
+        {
+            self.release_all();
+            return None;
+        }
+        let (key, state, modifiers) = get_key_and_modifiers(event)?;
+        let now = data.world.read_resource::<Time>().absolute_time_seconds();
+        let settings = *data.world.read_resource::<PointerRepeatSettings>();
+        self.shift = modifiers.shift;
+        self.ctrl = modifiers.ctrl;
+        self.key(key, state == ElementState::Pressed, now, &settings)
+    }
+
+    // The action of the keys held down if it is time to repeat it, at most once per call.
+    pub fn repeat(&mut self, data: &StateData<'_, GameData<'_, '_>>) -> Option<PointerAction> {
+        let now = data.world.read_resource::<Time>().absolute_time_seconds();
+        let settings = *data.world.read_resource::<PointerRepeatSettings>();
+        self.repeat_at(now, &settings)
+    }
+
+    pub fn release_all(&mut self) {
+        *self = Self::default();
+    }
+
+    fn key(
+        &mut self,
+        key: VirtualKeyCode,
+        pressed: bool,
+        now: f64,
+        settings: &PointerRepeatSettings,
+    ) -> Option<PointerAction> {
+        let held = match key {
+            VirtualKeyCode::Up => &mut self.up,
+            VirtualKeyCode::Down => &mut self.down,
+            VirtualKeyCode::Left => &mut self.left,
+            VirtualKeyCode::Right => &mut self.right,
+            _ => return None,
+        };
+        // The repeats of the system.
+        if *held == pressed {
+            return None;
+        }
+        *held = pressed;
+        let action = self.action();
+        self.next_repeat = action.map(|_| now + settings.delay);
+        if pressed {
+            action
+        } else {
+            None
+        }
+    }
+
+    fn repeat_at(&mut self, now: f64, settings: &PointerRepeatSettings) -> Option<PointerAction> {
+        let at = self.next_repeat.filter(|at| now >= *at)?;
+        // Without catching up after a long frame.
+        let next = at + settings.interval;
+        self.next_repeat = Some(if next < now {
+            now + settings.interval
+        } else {
+            next
+        });
+        self.action()
+    }
+
+    fn action(&self) -> Option<PointerAction> {
+        let ahead = match (self.up, self.down) {
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            _ => None,
+        };
+        let right = match (self.left, self.right) {
+            (true, false) => Some(false),
+            (false, true) => Some(true),
+            _ => None,
+        };
+        let strafe = |right, ahead| {
+            PointerAction::Move(match (right, ahead) {
+                (true, true) => MoveMode::StrafeRightAhead,
+                (true, false) => MoveMode::StrafeRightBack,
+                (false, true) => MoveMode::StrafeLeftAhead,
+                (false, false) => MoveMode::StrafeLeftBack,
+            })
+        };
+        match (ahead, right) {
+            (Some(ahead), Some(right)) => Some(strafe(right, ahead)),
+            (Some(true), None) => Some(PointerAction::Move(MoveMode::StraightAhead)),
+            (Some(false), None) => Some(PointerAction::Move(MoveMode::StraightBack)),
+            (None, Some(right)) if self.shift => Some(strafe(right, true)),
+            (None, Some(right)) if self.ctrl => Some(strafe(right, false)),
+            (None, Some(true)) => Some(PointerAction::TurnRight),
+            (None, Some(false)) => Some(PointerAction::TurnLeft),
+            (None, None) => None,
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum VerticalDirection {
@@ -76,16 +347,44 @@ impl HexPointer {
         world: &RhombusViewerWorld,
     ) {
         let update_rot_trans = self.position != position || self.height != height;
+        let update_height = self.height != height;
 
         self.position = position;
         self.height = height;
 
         let mut transform_storage = data.world.write_storage::<Transform>();
+        let mut height_tween_storage = data.world.write_storage::<HeightTweenTag>();
+        let start = data.world.read_resource::<Time>().absolute_time_seconds() as f32;
+        // From wherever the entity is, possibly halfway through the previous climb.
+        let mut tween_height = |entity: Entity, transform: &Transform, from: f32| {
+            if update_height {
+                height_tween_storage
+                    .insert(
+                        entity,
+                        HeightTweenTag {
+                            from,
+                            to: transform.translation()[1],
+                            start,
+                            duration: HEIGHT_TWEEN_DURATION,
+                        },
+                    )
+                    .expect("insert HeightTweenTag");
+            }
+        };
 
         if let Some(entities) = &self.entities {
             if update_rot_trans {
                 if let Some(transform) = transform_storage.get_mut(entities.pointer_rot_trans) {
+                    let from = transform.translation()[1];
                     self.set_pointer_rot_trans_transform(transform, world);
+                    tween_height(entities.pointer_rot_trans, transform, from);
+                }
+                if let Some(tag) = data
+                    .world
+                    .write_storage::<HexTag>()
+                    .get_mut(entities.pointer_rot_trans)
+                {
+                    tag.position = position;
                 }
             }
         }
@@ -93,12 +392,65 @@ impl HexPointer {
         if let Some(light) = &self.light {
             if update_rot_trans {
                 if let Some(transform) = transform_storage.get_mut(*light) {
+                    let from = transform.translation()[1];
                     self.set_light_trans_transform(transform, world);
+                    tween_height(*light, transform, from);
                 }
             }
         }
     }
 
+    // Moves to the neighbor in the direction of the move if it can enter it, otherwise bumps
+    // against it. Returns true if the pointer moved.
+    pub fn try_move<F>(
+        &mut self,
+        mode: MoveMode,
+        can_enter: F,
+        data: &StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) -> bool
+    where
+        F: FnOnce(AxialVector) -> bool,
+    {
+        self.face_camera(data, world);
+        let next = self.position.neighbor(mode.direction(self.direction));
+        if can_enter(next) {
+            self.set_position(next, self.height, data, world);
+            play_cue(data, SoundCue::PointerMove);
+            log_event(
+                data.world,
+                EventKind::Pointer,
+                format!("Moved to {:?}", (next.q(), next.r())),
+            );
+            true
+        } else {
+            self.bump(mode.direction(self.direction), data);
+            false
+        }
+    }
+
+    // The pointer nudges towards the hex in the given direction and comes back.
+    pub fn bump(&self, direction: usize, data: &StateData<'_, GameData<'_, '_>>) {
+        play_cue(data, SoundCue::Bump);
+        if let Some(entities) = &self.entities {
+            // The pointer mesh is in the frame of the direction the pointer faces.
+            let angle = ((direction + 6 - self.direction) % 6) as f32 * std::f32::consts::PI / 3.0;
+            let start = data.world.read_resource::<Time>().absolute_time_seconds() as f32;
+            data.world
+                .write_storage::<BumpTag>()
+                .insert(
+                    entities.pointer,
+                    BumpTag {
+                        translation: Vector3::new(POINTER_OFFSET, 0.0, 0.0),
+                        offset: Vector3::new(angle.cos(), 0.0, angle.sin()) * BUMP_DISTANCE,
+                        start,
+                        duration: BUMP_DURATION,
+                    },
+                )
+                .expect("insert BumpTag");
+        }
+    }
+
     /* Directions */
 
     pub fn direction(&self) -> usize {
@@ -167,6 +519,24 @@ impl HexPointer {
         );
     }
 
+    // Only in camera relative mode, see PointerSettings.
+    pub fn face_camera(
+        &mut self,
+        data: &StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        if !data
+            .world
+            .read_resource::<PointerSettings>()
+            .camera_relative
+        {
+            return;
+        }
+        if let Some(direction) = world.camera_hex_direction(data) {
+            self.set_direction(direction, self.vertical_direction, data, world);
+        }
+    }
+
     pub fn set_direction(
         &mut self,
         direction: usize,
@@ -239,11 +609,16 @@ impl HexPointer {
     ) -> HexPointerEntities {
         let mut transform = Transform::default();
         self.set_pointer_rot_trans_transform(&mut transform, world);
-        let pointer_rot_trans = data.world.create_entity().with(transform).build();
+        let pointer_rot_trans = data
+            .world
+            .create_entity()
+            .with(transform)
+            .with(HexTag::new(self.position, HexLayer::Pointer, "pointer"))
+            .build();
 
         let mut transform = Transform::default();
         transform.set_scale(Vector3::new(0.3, 0.1, 0.3));
-        transform.set_translation_x(0.7);
+        transform.set_translation_x(POINTER_OFFSET);
         let material = Self::get_pointer_material(self.vertical_direction, &world.assets);
         let pointer = data
             .world
@@ -291,7 +666,9 @@ impl HexPointer {
     ) {
         let pos = (self.position, 0.7 + self.height as f32 * self.level_height).into();
         world.transform_axial(pos, transform);
-        transform.set_rotation_y_axis(-(self.direction as f32) * std::f32::consts::PI / 3.0);
+        transform.set_rotation_y_axis(
+            hex_rotation(&world.layout) - (self.direction as f32) * std::f32::consts::PI / 3.0,
+        );
         match self.vertical_direction {
             VerticalDirection::Horizontal => {}
             VerticalDirection::Up => {
@@ -320,3 +697,68 @@ impl HexPointer {
         assets.color_data[&color].light.clone()
     }
 }
+
+#[test]
+fn test_pointer_keys() {
+    let settings = PointerRepeatSettings {
+        delay: 0.3,
+        interval: 0.1,
+    };
+    let mut keys = PointerKeys::default();
+    let key = |keys: &mut PointerKeys, key, pressed, now| keys.key(key, pressed, now, &settings);
+
+    assert_eq!(
+        key(&mut keys, VirtualKeyCode::Up, true, 0.0),
+        Some(PointerAction::Move(MoveMode::StraightAhead))
+    );
+    // The system repeats are ignored, the pace is ours.
+    assert_eq!(key(&mut keys, VirtualKeyCode::Up, true, 0.05), None);
+    assert_eq!(keys.repeat_at(0.2, &settings), None);
+    assert_eq!(
+        keys.repeat_at(0.31, &settings),
+        Some(PointerAction::Move(MoveMode::StraightAhead))
+    );
+    assert_eq!(keys.repeat_at(0.36, &settings), None);
+    assert!(keys.repeat_at(0.41, &settings).is_some());
+    // One repeat after a long frame.
+    assert!(keys.repeat_at(2.0, &settings).is_some());
+    assert_eq!(keys.repeat_at(2.05, &settings), None);
+
+    // Up and Right together strafe, whichever comes first.
+    assert_eq!(
+        key(&mut keys, VirtualKeyCode::Right, true, 2.1),
+        Some(PointerAction::Move(MoveMode::StrafeRightAhead))
+    );
+    assert_eq!(
+        keys.repeat_at(2.41, &settings),
+        Some(PointerAction::Move(MoveMode::StrafeRightAhead))
+    );
+    // Up alone again, after the delay.
+    assert_eq!(key(&mut keys, VirtualKeyCode::Right, false, 2.45), None);
+    assert_eq!(keys.repeat_at(2.5, &settings), None);
+    assert_eq!(
+        keys.repeat_at(2.76, &settings),
+        Some(PointerAction::Move(MoveMode::StraightAhead))
+    );
+    assert_eq!(key(&mut keys, VirtualKeyCode::Up, false, 2.8), None);
+    assert_eq!(keys.repeat_at(5.0, &settings), None);
+
+    assert_eq!(
+        key(&mut keys, VirtualKeyCode::Left, true, 6.0),
+        Some(PointerAction::TurnLeft)
+    );
+    assert_eq!(
+        key(&mut keys, VirtualKeyCode::Down, true, 6.1),
+        Some(PointerAction::Move(MoveMode::StrafeLeftBack))
+    );
+    keys.release_all();
+    assert_eq!(keys.repeat_at(7.0, &settings), None);
+
+    // The modifiers still strafe.
+    keys.ctrl = true;
+    assert_eq!(
+        key(&mut keys, VirtualKeyCode::Right, true, 8.0),
+        Some(PointerAction::Move(MoveMode::StrafeRightBack))
+    );
+    assert_eq!(key(&mut keys, VirtualKeyCode::A, true, 8.0), None);
+}
@@ -1,12 +1,18 @@
-use crate::{dispose::Dispose, hex::render::renderer::HexRenderer, world::RhombusViewerWorld};
+use crate::{
+    dispose::Dispose,
+    hex::render::renderer::{is_visible, HexRenderer},
+    world::{fractional_axial_translation, RhombusViewerWorld},
+};
 use amethyst::{
     ecs::prelude::*,
     prelude::*,
     renderer::{debug_drawing::DebugLinesComponent, palette::Srgba},
 };
 use rhombus_core::hex::{
-    coordinates::axial::AxialVector, largest_area::LargestAreaIterator,
-    storage::hash::RectHashStorage,
+    coordinates::axial::AxialVector,
+    float::layout::HexLayout,
+    largest_area::LargestAreaIterator,
+    storage::{hash::RectHashStorage, set::HexSet},
 };
 
 pub struct AreaRenderer {
@@ -26,21 +32,21 @@ impl HexRenderer for AreaRenderer {
         ()
     }
 
-    fn update_world<'a, StorageHex, MapHex, Wall, Visible>(
+    fn update_world<'a, StorageHex, MapHex, Wall>(
         &mut self,
         hexes: &mut RectHashStorage<StorageHex>,
         is_wall_hex: Wall,
-        is_visible_hex: Visible,
+        visible_positions: Option<&HexSet>,
         _get_renderer_hex: MapHex,
         visible_only: bool,
         _force: bool,
+        layout: &HexLayout,
         data: &mut StateData<'_, GameData<'_, '_>>,
-        world: &RhombusViewerWorld,
+        _world: &RhombusViewerWorld,
     ) where
-        StorageHex: 'a + Dispose,
+        StorageHex: 'a + Dispose + Sync,
         MapHex: Fn(&mut StorageHex) -> &mut Self::Hex,
-        Wall: Fn(AxialVector, &StorageHex) -> bool,
-        Visible: Fn(AxialVector, &StorageHex) -> bool,
+        Wall: Fn(AxialVector, &StorageHex) -> bool + Sync,
     {
         self.clear(data);
 
@@ -50,7 +56,7 @@ impl HexRenderer for AreaRenderer {
         let mut ground_acc = ground_lai.start_accumulation();
 
         for (position, hex) in hexes.iter() {
-            if !visible_only || is_visible_hex(position, hex) {
+            if !visible_only || is_visible(visible_positions, position) {
                 if is_wall_hex(position, hex) {
                     &mut wall_acc
                 } else {
@@ -74,26 +80,18 @@ impl HexRenderer for AreaRenderer {
                     break;
                 }
                 if let Some((range_q, range_r)) = area.1 {
-                    let mut p1 = world.axial_translation(
-                        (AxialVector::new(*range_q.start(), *range_r.start()), 1.0).into(),
-                    );
-                    p1[0] -= 3.0_f32.sqrt() / 2.0;
-                    p1[2] += 0.5;
-                    let mut p2 = world.axial_translation(
-                        (AxialVector::new(*range_q.start(), *range_r.end()), 1.0).into(),
-                    );
-                    p2[0] -= 1.0 / (3.0_f32.sqrt() * 2.0);
-                    p2[2] -= 0.5;
-                    let mut p3 = world.axial_translation(
-                        (AxialVector::new(*range_q.end(), *range_r.end()), 1.0).into(),
-                    );
-                    p3[0] += 3.0_f32.sqrt() / 2.0;
-                    p3[2] -= 0.5;
-                    let mut p4 = world.axial_translation(
-                        (AxialVector::new(*range_q.end(), *range_r.start()), 1.0).into(),
-                    );
-                    p4[0] += 1.0 / (3.0_f32.sqrt() * 2.0);
-                    p4[2] += 0.5;
+                    // The outline stays a third of a hex away from the extreme hex centers.
+                    let third = 1.0 / 3.0;
+                    let (start_q, end_q) = (*range_q.start() as f32, *range_q.end() as f32);
+                    let (start_r, end_r) = (*range_r.start() as f32, *range_r.end() as f32);
+                    let p1 =
+                        fractional_axial_translation(layout, start_q - third, start_r - third, 1.0);
+                    let p2 =
+                        fractional_axial_translation(layout, start_q - third, end_r + third, 1.0);
+                    let p3 =
+                        fractional_axial_translation(layout, end_q + third, end_r + third, 1.0);
+                    let p4 =
+                        fractional_axial_translation(layout, end_q + third, start_r - third, 1.0);
                     debug_lines.add_line(p1.into(), p2.into(), *color);
                     debug_lines.add_line(p2.into(), p3.into(), *color);
                     debug_lines.add_line(p3.into(), p4.into(), *color);
@@ -1,5 +1,8 @@
 use crate::{
-    assets::Color, dispose::Dispose, hex::render::renderer::HexRenderer, world::RhombusViewerWorld,
+    assets::Color,
+    dispose::Dispose,
+    hex::render::renderer::{is_visible, HexRenderer},
+    world::{fractional_axial_translation, RhombusViewerWorld},
 };
 use amethyst::{
     core::{math::Vector3, Transform},
@@ -12,7 +15,8 @@ use rhombus_core::hex::{
         axial::AxialVector,
         direction::{HexagonalDirection, NUM_DIRECTIONS},
     },
-    storage::hash::RectHashStorage,
+    float::layout::HexLayout,
+    storage::{hash::RectHashStorage, set::HexSet},
 };
 use smallvec::alloc::collections::BTreeMap;
 
@@ -56,7 +60,7 @@ impl AreaEdgeRenderer {
         get_renderer_hex: MapHex,
         visible_only: bool,
         debug_lines: &mut DebugLinesComponent,
-        world: &RhombusViewerWorld,
+        layout: &HexLayout,
     ) where
         StorageHex: Dispose,
         MapHex: Fn(&mut StorageHex) -> &mut <Self as HexRenderer>::Hex,
@@ -115,9 +119,6 @@ impl AreaEdgeRenderer {
                 }
             }
         }
-        let small = 3.0_f32.sqrt() / 2.0;
-        let small_1_2 = small * 0.5;
-        let small_3_2 = small * 1.5;
         for (visible, lines) in [(true, visible_lines), (false, invisible_lines)].iter_mut() {
             let floor_color = if *visible {
                 Srgba::new(0.5, 0.5, 0.0, 1.0)
@@ -129,31 +130,20 @@ impl AreaEdgeRenderer {
             } else {
                 Srgba::new(0.15, 0.0, 0.0, 1.0)
             };
+            let colors = (floor_color, ceiling_color);
             for (index, lines) in &mut lines[0] {
                 if lines.is_empty() {
                     continue;
                 }
                 lines.sort();
                 let add = |debug_lines: &mut DebugLinesComponent, start: isize, end: isize| {
-                    let start_tr = world.axial_translation(
-                        (AxialVector::new(*index, start.div_euclid(2)), 0.0).into(),
-                    );
-                    let start_x = if start & 1 == 0 { small_1_2 } else { small };
-                    let start_z = if start & 1 == 0 { 0.75 } else { 0.0 };
-                    let end_tr = world.axial_translation(
-                        (AxialVector::new(*index, end.div_euclid(2)), 0.0).into(),
-                    );
-                    let end_x = if end & 1 == 0 { small } else { small_3_2 };
-                    let end_z = if end & 1 == 0 { 0.0 } else { -0.75 };
-                    debug_lines.add_line(
-                        [start_tr[0] + start_x, 0.0, start_tr[2] + start_z].into(),
-                        [end_tr[0] + end_x, 0.0, end_tr[2] + end_z].into(),
-                        floor_color,
-                    );
-                    debug_lines.add_line(
-                        [start_tr[0] + start_x, 1.0, start_tr[2] + start_z].into(),
-                        [end_tr[0] + end_x, 1.0, end_tr[2] + end_z].into(),
-                        ceiling_color,
+                    let q = *index as f32 + 0.5;
+                    add_wall_lines(
+                        debug_lines,
+                        layout,
+                        (q, (start - 1) as f32 / 2.0),
+                        (q, end as f32 / 2.0),
+                        colors,
                     );
                 };
                 let mut state = (lines[0], lines[0]);
@@ -173,27 +163,13 @@ impl AreaEdgeRenderer {
                 }
                 lines.sort();
                 let add = |debug_lines: &mut DebugLinesComponent, start: isize, end: isize| {
-                    let start_tr = world.axial_translation(
-                        (AxialVector::new(start.div_euclid(2), *index), 0.0).into(),
-                    );
-                    let start_x = if start & 1 == 0 {
-                        -small_1_2
-                    } else {
-                        small_1_2
-                    };
-                    let end_tr = world.axial_translation(
-                        (AxialVector::new(end.div_euclid(2), *index), 0.0).into(),
-                    );
-                    let end_x = if end & 1 == 0 { small_1_2 } else { small_3_2 };
-                    debug_lines.add_line(
-                        [start_tr[0] + start_x, 0.0, start_tr[2] + 0.75].into(),
-                        [end_tr[0] + end_x, 0.0, end_tr[2] + 0.75].into(),
-                        floor_color,
-                    );
-                    debug_lines.add_line(
-                        [start_tr[0] + start_x, 1.0, start_tr[2] + 0.75].into(),
-                        [end_tr[0] + end_x, 1.0, end_tr[2] + 0.75].into(),
-                        ceiling_color,
+                    let r = *index as f32 - 0.5;
+                    add_wall_lines(
+                        debug_lines,
+                        layout,
+                        (start as f32 / 2.0, r),
+                        ((end + 1) as f32 / 2.0, r),
+                        colors,
                     );
                 };
                 let mut state = (lines[0], lines[0]);
@@ -213,33 +189,13 @@ impl AreaEdgeRenderer {
                 }
                 lines.sort();
                 let add = |debug_lines: &mut DebugLinesComponent, start: isize, end: isize| {
-                    let start_tr = world.axial_translation(
-                        (
-                            AxialVector::new(start.div_euclid(2), *index - start.div_euclid(2)),
-                            0.0,
-                        )
-                            .into(),
-                    );
-                    let start_x = if start & 1 == 0 { -small } else { -small_1_2 };
-                    let start_z = if start & 1 == 0 { 0.0 } else { 0.75 };
-                    let end_tr = world.axial_translation(
-                        (
-                            AxialVector::new(end.div_euclid(2), *index - end.div_euclid(2)),
-                            0.0,
-                        )
-                            .into(),
-                    );
-                    let end_x = if end & 1 == 0 { -small_1_2 } else { 0.0 };
-                    let end_z = if end & 1 == 0 { 0.75 } else { 1.5 };
-                    debug_lines.add_line(
-                        [start_tr[0] + start_x, 0.0, start_tr[2] + start_z].into(),
-                        [end_tr[0] + end_x, 0.0, end_tr[2] + end_z].into(),
-                        floor_color,
-                    );
-                    debug_lines.add_line(
-                        [start_tr[0] + start_x, 1.0, start_tr[2] + start_z].into(),
-                        [end_tr[0] + end_x, 1.0, end_tr[2] + end_z].into(),
-                        ceiling_color,
+                    let (start, end) = (start as f32 / 2.0, end as f32 / 2.0);
+                    add_wall_lines(
+                        debug_lines,
+                        layout,
+                        (start - 0.5, *index as f32 - start),
+                        (end, *index as f32 - end - 0.5),
+                        colors,
                     );
                 };
                 let mut state = (lines[0], lines[0]);
@@ -257,6 +213,23 @@ impl AreaEdgeRenderer {
     }
 }
 
+// Walls run along hex edges, their ends are given in fractional axial coordinates.
+fn add_wall_lines(
+    debug_lines: &mut DebugLinesComponent,
+    layout: &HexLayout,
+    start: (f32, f32),
+    end: (f32, f32),
+    (floor_color, ceiling_color): (Srgba, Srgba),
+) {
+    for (altitude, color) in [(0.0, floor_color), (1.0, ceiling_color)].iter() {
+        debug_lines.add_line(
+            fractional_axial_translation(layout, start.0, start.1, *altitude).into(),
+            fractional_axial_translation(layout, end.0, end.1, *altitude).into(),
+            *color,
+        );
+    }
+}
+
 impl HexRenderer for AreaEdgeRenderer {
     type Hex = Hex;
 
@@ -268,21 +241,21 @@ impl HexRenderer for AreaEdgeRenderer {
         }
     }
 
-    fn update_world<'a, StorageHex, MapHex, Wall, Visible>(
+    fn update_world<'a, StorageHex, MapHex, Wall>(
         &mut self,
         hexes: &mut RectHashStorage<StorageHex>,
         is_wall_hex: Wall,
-        is_visible_hex: Visible,
+        visible_positions: Option<&HexSet>,
         get_renderer_hex: MapHex,
         visible_only: bool,
         _force: bool,
+        layout: &HexLayout,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) where
-        StorageHex: 'a + Dispose,
+        StorageHex: 'a + Dispose + Sync,
         MapHex: Fn(&mut StorageHex) -> &mut Self::Hex,
-        Wall: Fn(AxialVector, &StorageHex) -> bool,
-        Visible: Fn(AxialVector, &StorageHex) -> bool,
+        Wall: Fn(AxialVector, &StorageHex) -> bool + Sync,
     {
         if self.plane.is_none() {
             let mut transform = Transform::default();
@@ -302,7 +275,7 @@ impl HexRenderer for AreaEdgeRenderer {
         let mut dirty = self.entity.is_none() || self.previous_visible_only != visible_only;
         for (position, mut hex_with_adjacents) in hexes.positions_and_hexes_with_adjacents_mut() {
             let wall = is_wall_hex(position, hex_with_adjacents.hex());
-            let visible = is_visible_hex(position, hex_with_adjacents.hex());
+            let visible = is_visible(visible_positions, position);
             let hex = get_renderer_hex(hex_with_adjacents.hex());
             hex.wall = wall;
             hex.visible = visible;
@@ -310,7 +283,7 @@ impl HexRenderer for AreaEdgeRenderer {
                 let dir_1 = edge_num;
                 let adjacent_1_wall = hex_with_adjacents.adjacent(dir_1).and_then(|adj| {
                     let adj_wall = is_wall_hex(position.neighbor(dir_1), adj);
-                    let adj_visible = is_visible_hex(position.neighbor(dir_1), adj);
+                    let adj_visible = is_visible(visible_positions, position.neighbor(dir_1));
                     if adj_visible == visible {
                         Some(adj_wall)
                     } else {
@@ -320,7 +293,7 @@ impl HexRenderer for AreaEdgeRenderer {
                 let dir_2 = (edge_num + 1) % NUM_DIRECTIONS;
                 let adjacent_2_wall = hex_with_adjacents.adjacent(dir_2).and_then(|adj| {
                     let adj_wall = is_wall_hex(position.neighbor(dir_2), adj);
-                    let adj_visible = is_visible_hex(position.neighbor(dir_2), adj);
+                    let adj_visible = is_visible(visible_positions, position.neighbor(dir_2));
                     if adj_visible == visible {
                         Some(adj_wall)
                     } else {
@@ -351,7 +324,7 @@ impl HexRenderer for AreaEdgeRenderer {
                 let mut debug_lines_storage = data.world.write_storage::<DebugLinesComponent>();
                 let debug_lines = debug_lines_storage.get_mut(entity).expect("Debug lines");
                 debug_lines.clear();
-                self.add_lines(hexes, get_renderer_hex, visible_only, debug_lines, world);
+                self.add_lines(hexes, get_renderer_hex, visible_only, debug_lines, layout);
             } else {
                 let mut debug_lines = DebugLinesComponent::with_capacity(100);
                 self.add_lines(
@@ -359,7 +332,7 @@ impl HexRenderer for AreaEdgeRenderer {
                     get_renderer_hex,
                     visible_only,
                     &mut debug_lines,
-                    world,
+                    layout,
                 );
                 self.entity = Some(data.world.create_entity().with(debug_lines).build());
             }
@@ -0,0 +1,187 @@
+use crate::{
+    assets::{Color, ColorData, Translucent},
+    hex::inspect::{HexLayer, HexTag},
+    systems::culling::ChunkTag,
+    theme::TerrainType,
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    assets::Handle,
+    core::transform::Transform,
+    ecs::prelude::*,
+    prelude::*,
+    renderer::{transparent::Transparent, types::Mesh, Material},
+};
+use rhombus_core::hex::coordinates::axial::AxialVector;
+use std::{num::NonZeroUsize, thread};
+
+// The renderers stage their commands on as many threads as the machine runs.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+// The meshes and materials are named rather than held so that the commands can be staged, and
+// compared in tests, without the assets.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MeshKind {
+    Ground,
+    // By autotile index.
+    Wall(usize),
+    Square,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MaterialKind {
+    // The light or dark side of the theme atlas.
+    Atlas { light: bool },
+    Color { color: Color, light: bool },
+    // Alpha blended, the entity gets the Transparent component.
+    Translucent(Translucent),
+}
+
+impl MaterialKind {
+    fn handle(self, world: &RhombusViewerWorld) -> Handle<Material> {
+        let side = |data: &ColorData, light| {
+            if light {
+                data.light.clone()
+            } else {
+                data.dark.clone()
+            }
+        };
+        match self {
+            MaterialKind::Atlas { light } => side(&world.assets.atlas, light),
+            MaterialKind::Color { color, light } => side(&world.assets.color_data[&color], light),
+            MaterialKind::Translucent(translucent) => {
+                world.assets.translucent_materials[&translucent].clone()
+            }
+        }
+    }
+}
+
+impl MeshKind {
+    fn handle(self, world: &RhombusViewerWorld) -> Handle<Mesh> {
+        match self {
+            MeshKind::Ground => world.assets.terrain_handles[&TerrainType::Ground].clone(),
+            MeshKind::Wall(index) => world.assets.wall_handles[index].clone(),
+            MeshKind::Square => world.assets.square_handle.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EntityLook {
+    pub transform: Transform,
+    pub mesh: MeshKind,
+    pub material: MaterialKind,
+}
+
+// What a renderer asks of the ECS world. The commands are staged while going over the hexes, the
+// heavy part on several threads, then applied in a single pass so that the storages are fetched
+// once per update instead of once per hex.
+#[derive(Clone, Debug)]
+pub enum HexCommand {
+    Create {
+        position: AxialVector,
+        layer: HexLayer,
+        owner: &'static str,
+        // Hidden with its storage rect when out of the view.
+        chunk: bool,
+        look: EntityLook,
+    },
+    // Only the parts which changed.
+    Update {
+        entity: Entity,
+        transform: Option<Transform>,
+        mesh: Option<MeshKind>,
+        material: Option<MaterialKind>,
+    },
+    Delete {
+        entity: Entity,
+    },
+}
+
+// The entities created, in the order of their commands, for the renderers to store them back in
+// their hexes.
+pub fn apply_commands(
+    commands: Vec<HexCommand>,
+    data: &mut StateData<'_, GameData<'_, '_>>,
+    world: &RhombusViewerWorld,
+) -> Vec<(AxialVector, Entity)> {
+    let mut created = Vec::new();
+    let mut deleted = Vec::new();
+    {
+        let entities = data.world.entities();
+        let mut transform_storage = data.world.write_storage::<Transform>();
+        let mut mesh_storage = data.world.write_storage::<Handle<Mesh>>();
+        let mut material_storage = data.world.write_storage::<Handle<Material>>();
+        let mut transparent_storage = data.world.write_storage::<Transparent>();
+        let mut chunk_storage = data.world.write_storage::<ChunkTag>();
+        let mut tag_storage = data.world.write_storage::<HexTag>();
+        let mut set_material = |entity, material: MaterialKind| {
+            material_storage
+                .insert(entity, material.handle(world))
+                .expect("insert Material");
+            if let MaterialKind::Translucent(..) = material {
+                transparent_storage
+                    .insert(entity, Transparent)
+                    .expect("insert Transparent");
+            } else {
+                transparent_storage.remove(entity);
+            }
+        };
+        for command in commands {
+            match command {
+                HexCommand::Create {
+                    position,
+                    layer,
+                    owner,
+                    chunk,
+                    look,
+                } => {
+                    let entity = entities.create();
+                    transform_storage
+                        .insert(entity, look.transform)
+                        .expect("insert Transform");
+                    mesh_storage
+                        .insert(entity, look.mesh.handle(world))
+                        .expect("insert Mesh");
+                    set_material(entity, look.material);
+                    if chunk {
+                        chunk_storage
+                            .insert(entity, ChunkTag::new(position))
+                            .expect("insert ChunkTag");
+                    }
+                    tag_storage
+                        .insert(entity, HexTag::new(position, layer, owner))
+                        .expect("insert HexTag");
+                    created.push((position, entity));
+                }
+                HexCommand::Update {
+                    entity,
+                    transform,
+                    mesh,
+                    material,
+                } => {
+                    if let Some(transform) = transform {
+                        *transform_storage
+                            .get_mut(entity)
+                            .expect("An hex always has a Transform") = transform;
+                    }
+                    if let Some(mesh) = mesh {
+                        *mesh_storage
+                            .get_mut(entity)
+                            .expect("An hex always has a Mesh") = mesh.handle(world);
+                    }
+                    if let Some(material) = material {
+                        set_material(entity, material);
+                    }
+                }
+                HexCommand::Delete { entity } => deleted.push(entity),
+            }
+        }
+    }
+    data.world
+        .delete_entities(&deleted)
+        .expect("delete entities");
+    created
+}
@@ -1,5 +1,8 @@
 use crate::{
-    assets::Color, dispose::Dispose, hex::render::renderer::HexRenderer, world::RhombusViewerWorld,
+    assets::Color,
+    dispose::Dispose,
+    hex::render::renderer::{is_visible, HexRenderer},
+    world::{corner_translation, RhombusViewerWorld},
 };
 use amethyst::{
     core::{math::Vector3, Transform},
@@ -12,7 +15,8 @@ use rhombus_core::hex::{
         axial::AxialVector,
         direction::{HexagonalDirection, NUM_DIRECTIONS},
     },
-    storage::hash::RectHashStorage,
+    float::layout::HexLayout,
+    storage::{hash::RectHashStorage, set::HexSet},
 };
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -86,7 +90,7 @@ impl EdgeRenderer {
         get_renderer_hex: MapHex,
         visible_only: bool,
         debug_lines: &mut DebugLinesComponent,
-        world: &RhombusViewerWorld,
+        layout: &HexLayout,
     ) where
         StorageHex: Dispose,
         MapHex: Fn(&mut StorageHex) -> &mut <Self as HexRenderer>::Hex,
@@ -96,33 +100,16 @@ impl EdgeRenderer {
             if visible_only && !hex.visible {
                 continue;
             }
-            let translation = world.axial_translation((position, 0.0).into());
-            let small = 3.0_f32.sqrt() / 2.0;
-            for (dir, vertices, first_half) in [
-                (0, [(small, -0.5), (small, 0.5)], true),
-                (1, [(small, 0.5), (0.0, 1.0)], true),
-                (2, [(0.0, 1.0), (-small, 0.5)], true),
-                (3, [(-small, 0.5), (-small, -0.5)], false),
-                (4, [(-small, -0.5), (0.0, -1.0)], false),
-                (5, [(0.0, -1.0), (small, -0.5)], false),
-            ]
-            .iter()
-            {
-                if *first_half || hex.edges[*dir] != Edge::SameAltitude {
-                    if let Some(color) = Self::get_color(hex, hex.edges[*dir]) {
+            let pos = (position, if hex.wall { 1.0 } else { 0.0 }).into();
+            for dir in 0..NUM_DIRECTIONS {
+                // Directions and corners turn in opposite ways: the edge facing direction d
+                // joins corners 1 - d and -d.
+                let first_half = dir < 3;
+                if first_half || hex.edges[dir] != Edge::SameAltitude {
+                    if let Some(color) = Self::get_color(hex, hex.edges[dir]) {
                         debug_lines.add_line(
-                            [
-                                translation[0] + vertices[0].0,
-                                translation[1] + if hex.wall { 1.0 } else { 0.0 },
-                                translation[2] + vertices[0].1,
-                            ]
-                            .into(),
-                            [
-                                translation[0] + vertices[1].0,
-                                translation[1] + if hex.wall { 1.0 } else { 0.0 },
-                                translation[2] + vertices[1].1,
-                            ]
-                            .into(),
+                            corner_translation(layout, pos, (7 - dir) % 6).into(),
+                            corner_translation(layout, pos, (6 - dir) % 6).into(),
                             color,
                         );
                     }
@@ -143,21 +130,21 @@ impl HexRenderer for EdgeRenderer {
         }
     }
 
-    fn update_world<'a, StorageHex, MapHex, Wall, Visible>(
+    fn update_world<'a, StorageHex, MapHex, Wall>(
         &mut self,
         hexes: &mut RectHashStorage<StorageHex>,
         is_wall_hex: Wall,
-        is_visible_hex: Visible,
+        visible_positions: Option<&HexSet>,
         get_renderer_hex: MapHex,
         visible_only: bool,
         force: bool,
+        layout: &HexLayout,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) where
-        StorageHex: 'a + Dispose,
+        StorageHex: 'a + Dispose + Sync,
         MapHex: Fn(&mut StorageHex) -> &mut Self::Hex,
-        Wall: Fn(AxialVector, &StorageHex) -> bool,
-        Visible: Fn(AxialVector, &StorageHex) -> bool,
+        Wall: Fn(AxialVector, &StorageHex) -> bool + Sync,
     {
         if self.plane.is_none() {
             let mut transform = Transform::default();
@@ -177,7 +164,7 @@ impl HexRenderer for EdgeRenderer {
         let mut dirty = self.entity.is_none() || self.previous_visible_only != visible_only;
         for (position, mut hex_with_adjacents) in hexes.positions_and_hexes_with_adjacents_mut() {
             let wall = is_wall_hex(position, hex_with_adjacents.hex());
-            let visible = is_visible_hex(position, hex_with_adjacents.hex());
+            let visible = is_visible(visible_positions, position);
             let hex = get_renderer_hex(hex_with_adjacents.hex());
             if force || hex.wall != wall || hex.visible != visible {
                 hex.wall = wall;
@@ -185,7 +172,8 @@ impl HexRenderer for EdgeRenderer {
                 for dir in 0..NUM_DIRECTIONS {
                     let hex_edge = if let Some(adjacent) = hex_with_adjacents.adjacent(dir) {
                         let adjacent_wall = is_wall_hex(position.neighbor(dir), adjacent);
-                        let adjacent_visible = is_visible_hex(position.neighbor(dir), adjacent);
+                        let adjacent_visible =
+                            is_visible(visible_positions, position.neighbor(dir));
                         let adjacent = get_renderer_hex(adjacent);
                         let (hex_edge, adjacent_edge) = if adjacent_visible == visible {
                             match (wall, adjacent_wall) {
@@ -213,7 +201,7 @@ impl HexRenderer for EdgeRenderer {
                 let mut debug_lines_storage = data.world.write_storage::<DebugLinesComponent>();
                 let debug_lines = debug_lines_storage.get_mut(entity).expect("Debug lines");
                 debug_lines.clear();
-                self.add_lines(hexes, get_renderer_hex, visible_only, debug_lines, world);
+                self.add_lines(hexes, get_renderer_hex, visible_only, debug_lines, layout);
             } else {
                 let mut debug_lines = DebugLinesComponent::with_capacity(100);
                 self.add_lines(
@@ -221,7 +209,7 @@ impl HexRenderer for EdgeRenderer {
                     get_renderer_hex,
                     visible_only,
                     &mut debug_lines,
-                    world,
+                    layout,
                 );
                 self.entity = Some(data.world.create_entity().with(debug_lines).build());
             }
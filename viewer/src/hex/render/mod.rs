@@ -1,7 +1,9 @@
 pub mod area;
 pub mod area_edge;
+pub mod commands;
 pub mod edge;
 pub mod multi;
 pub mod renderer;
 pub mod square;
+pub mod storage;
 pub mod tile;
@@ -1,6 +1,10 @@
 use crate::{dispose::Dispose, hex::render::renderer::HexRenderer, world::RhombusViewerWorld};
 use amethyst::prelude::*;
-use rhombus_core::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector,
+    float::layout::HexLayout,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
 
 pub struct MultiRenderer<R1, R2> {
     r1: R1,
@@ -27,45 +31,47 @@ where
         )
     }
 
-    fn update_world<'a, StorageHex, MapHex, Wall, Visible>(
+    fn update_world<'a, StorageHex, MapHex, Wall>(
         &mut self,
         hexes: &mut RectHashStorage<StorageHex>,
         is_wall_hex: Wall,
-        is_visible_hex: Visible,
+        visible_positions: Option<&HexSet>,
         get_renderer_hex: MapHex,
         visible_only: bool,
         force: bool,
+        layout: &HexLayout,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) where
-        StorageHex: 'a + Dispose,
+        StorageHex: 'a + Dispose + Sync,
         MapHex: Fn(&mut StorageHex) -> &mut Self::Hex,
-        Wall: Fn(AxialVector, &StorageHex) -> bool,
-        Visible: Fn(AxialVector, &StorageHex) -> bool,
+        Wall: Fn(AxialVector, &StorageHex) -> bool + Sync,
     {
         self.r1.update_world(
             hexes,
             &is_wall_hex,
-            &is_visible_hex,
+            visible_positions,
             // Ref of tuple to tuple of refs: it is supposedly safe because both the input ref and
             // the output ref are bound together, despite the fact that the ref to the tuple
             // returned by get_renderer_hex is floating in the middle.
             |hex| unsafe { &mut *(&mut get_renderer_hex(hex).0 as *mut R1::Hex) },
             visible_only,
             force,
+            layout,
             data,
             world,
         );
         self.r2.update_world(
             hexes,
             &is_wall_hex,
-            &is_visible_hex,
+            visible_positions,
             // Ref of tuple to tuple of refs: it is supposedly safe because both the input ref and
             // the output ref are bound together, despite the fact that the ref to the tuple
             // returned by get_renderer_hex is floating in the middle.
             |hex| unsafe { &mut *(&mut get_renderer_hex(hex).1 as *mut R2::Hex) },
             visible_only,
             force,
+            layout,
             data,
             world,
         );
@@ -1,27 +1,38 @@
 use crate::{dispose::Dispose, world::RhombusViewerWorld};
 use amethyst::prelude::*;
-use rhombus_core::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector,
+    float::layout::HexLayout,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
 
 pub trait HexRenderer {
-    type Hex: Dispose;
+    type Hex: Dispose + Sync;
 
     fn new_hex(&mut self, wall: bool, visible: bool) -> Self::Hex;
 
-    fn update_world<'a, StorageHex, MapHex, Wall, Visible>(
+    fn update_world<'a, StorageHex, MapHex, Wall>(
         &mut self,
         hexes: &mut RectHashStorage<StorageHex>,
         is_wall_hex: Wall,
-        is_visible_hex: Visible,
+        visible_positions: Option<&HexSet>,
         get_renderer_hex: MapHex,
         visible_only: bool,
         force: bool,
+        layout: &HexLayout,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) where
-        StorageHex: 'a + Dispose,
+        StorageHex: 'a + Dispose + Sync,
         MapHex: Fn(&mut StorageHex) -> &mut Self::Hex,
-        Wall: Fn(AxialVector, &StorageHex) -> bool,
-        Visible: Fn(AxialVector, &StorageHex) -> bool;
+        Wall: Fn(AxialVector, &StorageHex) -> bool + Sync;
 
     fn clear(&mut self, data: &mut StateData<'_, GameData<'_, '_>>);
 }
+
+// Without a field of view, everything is visible.
+pub fn is_visible(visible_positions: Option<&HexSet>, position: AxialVector) -> bool {
+    visible_positions.map_or(true, |visible_positions| {
+        visible_positions.contains(position)
+    })
+}
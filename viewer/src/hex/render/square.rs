@@ -1,14 +1,27 @@
 use crate::{
-    assets::Color, dispose::Dispose, hex::render::renderer::HexRenderer, world::RhombusViewerWorld,
+    assets::Color,
+    dispose::Dispose,
+    hex::{
+        inspect::HexLayer,
+        render::{
+            commands::{
+                apply_commands, default_threads, EntityLook, HexCommand, MaterialKind, MeshKind,
+            },
+            renderer::{is_visible, HexRenderer},
+        },
+    },
+    world::{transform_axial, RhombusViewerWorld},
 };
 use amethyst::{
-    assets::Handle,
     core::{math::Vector3, transform::Transform},
     ecs::prelude::*,
     prelude::*,
-    renderer::Material,
 };
-use rhombus_core::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector,
+    float::layout::HexLayout,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct SquareScale {
@@ -34,6 +47,17 @@ impl Dispose for Hex {
 pub struct SquareRenderer {
     ground_scale: SquareScale,
     wall_scale: SquareScale,
+    threads: usize,
+}
+
+// What the entities of a hex must show, computed in parallel.
+#[derive(Clone, Copy, Debug)]
+struct SquareTarget {
+    position: AxialVector,
+    wall: bool,
+    visible: bool,
+    // Whether the hex has entities at all.
+    shown: bool,
 }
 
 impl SquareRenderer {
@@ -41,6 +65,7 @@ impl SquareRenderer {
         Self {
             ground_scale,
             wall_scale,
+            threads: default_threads(),
         }
     }
 
@@ -52,123 +77,111 @@ impl SquareRenderer {
         }
     }
 
-    fn get_material(
-        &self,
-        wall: bool,
-        visible: bool,
-        world: &RhombusViewerWorld,
-    ) -> Handle<Material> {
-        let color = if wall { Color::Red } else { Color::White };
-        if visible {
-            world.assets.color_data[&color].light.clone()
-        } else {
-            world.assets.color_data[&color].dark.clone()
+    fn get_material(wall: bool, visible: bool) -> MaterialKind {
+        MaterialKind::Color {
+            color: if wall { Color::Red } else { Color::White },
+            light: visible,
         }
     }
 
-    fn create_hex(
+    // One square faces up, the other one down.
+    fn get_transforms(
         position: AxialVector,
         scale: SquareScale,
-        material: Handle<Material>,
-        data: &mut StateData<'_, GameData<'_, '_>>,
-        world: &RhombusViewerWorld,
-    ) -> (Entity, Entity) {
-        let mut entities = [-1.0, 1.0].iter().map(|sign| {
+        layout: &HexLayout,
+    ) -> [Transform; 2] {
+        let horizontal = scale.horizontal * layout.size();
+        let transform = |sign: f32| {
             let mut transform = Transform::default();
             transform.set_rotation_x_axis(sign * std::f32::consts::FRAC_PI_2);
-            transform.set_scale(Vector3::new(scale.horizontal, scale.horizontal, 1.0));
+            transform.set_scale(Vector3::new(horizontal, horizontal, 1.0));
             let pos = (position, 0.0).into();
-            world.transform_axial(pos, &mut transform);
-            data.world
-                .create_entity()
-                .with(world.assets.square_handle.clone())
-                .with(material.clone())
-                .with(transform)
-                .build()
-        });
-        (entities.next().unwrap(), entities.next().unwrap())
+            transform_axial(layout, pos, &mut transform);
+            transform
+        };
+        [transform(-1.0), transform(1.0)]
     }
 
-    pub fn update_hex(
+    // The commands bringing the entities of the hex to the target, the hex records what they
+    // show.
+    fn stage(
         &self,
-        position: AxialVector,
         hex: &mut Hex,
-        data: &mut StateData<'_, GameData<'_, '_>>,
-        world: &RhombusViewerWorld,
+        target: SquareTarget,
+        force: bool,
+        layout: &HexLayout,
+        commands: &mut Vec<HexCommand>,
     ) {
-        let scale = self.get_scale(hex.wall);
-        let material = self.get_material(hex.wall, hex.visible, world);
-        if let Some(entities) = hex.entities {
-            for entity in [entities.0, entities.1].iter() {
-                Self::update_hex_transform(
-                    *entity,
-                    scale,
-                    &mut data.world.write_storage::<Transform>(),
-                );
-                Self::update_hex_color(
-                    *entity,
-                    material.clone(),
-                    &mut data.world.write_storage::<Handle<Material>>(),
-                );
+        let SquareTarget {
+            position,
+            wall,
+            visible,
+            shown,
+        } = target;
+        if !shown {
+            if let Some(entities) = hex.entities.take() {
+                commands.push(HexCommand::Delete { entity: entities.0 });
+                commands.push(HexCommand::Delete { entity: entities.1 });
             }
-        } else {
-            hex.entities = Some(Self::create_hex(position, scale, material, data, world));
+            return;
         }
-    }
-
-    fn update_hex_internal(
-        &self,
-        hex: &mut Hex,
-        wall: bool,
-        visible: bool,
-        scale: SquareScale,
-        force: bool,
-        world: &RhombusViewerWorld,
-        transform_storage: &mut WriteStorage<Transform>,
-        material_storage: &mut WriteStorage<Handle<Material>>,
-    ) {
+        let transforms = Self::get_transforms(position, self.get_scale(wall), layout);
+        let material = Self::get_material(wall, visible);
         if let Some(entities) = hex.entities {
-            if force || hex.wall != wall {
-                for entity in [entities.0, entities.1].iter() {
-                    Self::update_hex_transform(*entity, scale, transform_storage);
-                }
-            }
-            if force || hex.wall != wall || hex.visible != visible {
-                for entity in [entities.0, entities.1].iter() {
-                    Self::update_hex_color(
-                        *entity,
-                        self.get_material(wall, visible, world),
-                        material_storage,
-                    );
+            let shape_changed = force || hex.wall != wall;
+            let color_changed = shape_changed || hex.visible != visible;
+            if color_changed {
+                for (entity, transform) in [entities.0, entities.1].iter().zip(transforms.iter()) {
+                    commands.push(HexCommand::Update {
+                        entity: *entity,
+                        transform: if shape_changed {
+                            Some(transform.clone())
+                        } else {
+                            None
+                        },
+                        mesh: None,
+                        material: Some(material),
+                    });
                 }
             }
         } else {
-            unreachable!();
+            for transform in transforms.iter() {
+                commands.push(HexCommand::Create {
+                    position,
+                    layer: HexLayer::Square,
+                    owner: "square renderer",
+                    chunk: false,
+                    look: EntityLook {
+                        transform: transform.clone(),
+                        mesh: MeshKind::Square,
+                        material,
+                    },
+                });
+            }
         }
         hex.wall = wall;
         hex.visible = visible;
     }
 
-    fn update_hex_transform(
-        entity: Entity,
-        scale: SquareScale,
-        transform_storage: &mut WriteStorage<Transform>,
-    ) {
-        let transform = transform_storage
-            .get_mut(entity)
-            .expect("An hex always has a Transform");
-        transform.set_scale(Vector3::new(scale.horizontal, scale.horizontal, 1.0));
-        transform.translation_mut()[1] = 0.0;
-    }
-
-    fn update_hex_color(
-        entity: Entity,
-        material: Handle<Material>,
-        material_storage: &mut WriteStorage<Handle<Material>>,
+    pub fn update_hex(
+        &self,
+        position: AxialVector,
+        hex: &mut Hex,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
     ) {
-        *material_storage
-            .get_mut(entity)
-            .expect("An hex always has a Material") = material;
+        let target = SquareTarget {
+            position,
+            wall: hex.wall,
+            visible: hex.visible,
+            shown: true,
+        };
+        let mut commands = Vec::new();
+        self.stage(hex, target, true, &world.layout, &mut commands);
+        let created = apply_commands(commands, data, world);
+        if let [(_, first), (_, second)] = created[..] {
+            hex.entities = Some((first, second));
+        }
     }
 }
 
@@ -183,69 +196,40 @@ impl HexRenderer for SquareRenderer {
         }
     }
 
-    fn update_world<'a, StorageHex, MapHex, Wall, Visible>(
+    fn update_world<'a, StorageHex, MapHex, Wall>(
         &mut self,
         hexes: &mut RectHashStorage<StorageHex>,
         is_wall_hex: Wall,
-        is_visible_hex: Visible,
+        visible_positions: Option<&HexSet>,
         get_renderer_hex: MapHex,
         visible_only: bool,
         force: bool,
+        layout: &HexLayout,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) where
-        StorageHex: 'a + Dispose,
+        StorageHex: 'a + Dispose + Sync,
         MapHex: Fn(&mut StorageHex) -> &mut Self::Hex,
-        Wall: Fn(AxialVector, &StorageHex) -> bool,
-        Visible: Fn(AxialVector, &StorageHex) -> bool,
+        Wall: Fn(AxialVector, &StorageHex) -> bool + Sync,
     {
-        let ground_scale = self.get_scale(false);
-        let wall_scale = self.get_scale(true);
-        {
-            let mut transform_storage = data.world.write_storage::<Transform>();
-            let mut material_storage = data.world.write_storage::<Handle<Material>>();
-            for (pos, hex) in hexes.iter_mut() {
-                let wall = is_wall_hex(pos, hex);
-                let visible = is_visible_hex(pos, hex);
-                let renderer_hex = get_renderer_hex(hex);
-                if !visible_only || visible {
-                    if renderer_hex.entities.is_some() {
-                        self.update_hex_internal(
-                            renderer_hex,
-                            wall,
-                            visible,
-                            if wall { wall_scale } else { ground_scale },
-                            force,
-                            world,
-                            &mut transform_storage,
-                            &mut material_storage,
-                        );
-                    }
-                }
+        let target = |pos: AxialVector, hex: &StorageHex, _: &RectHashStorage<StorageHex>| {
+            let visible = is_visible(visible_positions, pos);
+            SquareTarget {
+                position: pos,
+                wall: is_wall_hex(pos, hex),
+                visible,
+                shown: !visible_only || visible,
             }
-        }
-        {
-            for (pos, hex) in hexes.iter_mut() {
-                let wall = is_wall_hex(pos, hex);
-                let visible = is_visible_hex(pos, hex);
-                let renderer_hex = get_renderer_hex(hex);
-                if !visible_only || visible {
-                    if renderer_hex.entities.is_none() {
-                        renderer_hex.entities = Some(Self::create_hex(
-                            pos,
-                            if wall { wall_scale } else { ground_scale },
-                            self.get_material(wall, visible, world),
-                            data,
-                            world,
-                        ));
-                        renderer_hex.wall = wall;
-                        renderer_hex.visible = visible;
-                    }
-                } else {
-                    if let Some(entities) = renderer_hex.entities.take() {
-                        data.world.delete_entity(entities.0).expect("delete entity");
-                        data.world.delete_entity(entities.1).expect("delete entity");
-                    }
+        };
+        let mut commands = Vec::new();
+        hexes.par_update(self.threads, target, |hex, target| {
+            self.stage(get_renderer_hex(hex), target, force, layout, &mut commands);
+        });
+        // The two squares of a hex are created one after the other.
+        for pair in apply_commands(commands, data, world).chunks(2) {
+            if let [(pos, first), (_, second)] = *pair {
+                if let Some(hex) = hexes.get_mut(pos) {
+                    get_renderer_hex(hex).entities = Some((first, second));
                 }
             }
         }
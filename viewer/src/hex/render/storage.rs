@@ -0,0 +1,69 @@
+use crate::{dispose::Dispose, hex::render::renderer::HexRenderer};
+use amethyst::prelude::*;
+use log::error;
+use rhombus_core::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+
+// Hexes owning entities together with the renderer which created them, so that a single dispose
+// call deletes all of them. Entities cannot be deleted without the ECS world, dropping the
+// storage before disposing it is reported as a leak.
+pub struct EcsBackedStorage<R: HexRenderer, Hex: Dispose = <R as HexRenderer>::Hex> {
+    hexes: RectHashStorage<Hex>,
+    renderer: R,
+}
+
+impl<R: HexRenderer, Hex: Dispose> EcsBackedStorage<R, Hex> {
+    pub fn new(renderer: R) -> Self {
+        Self {
+            hexes: RectHashStorage::new(),
+            renderer,
+        }
+    }
+
+    pub fn hexes(&self) -> &RectHashStorage<Hex> {
+        &self.hexes
+    }
+
+    pub fn renderer_mut(&mut self) -> &mut R {
+        &mut self.renderer
+    }
+
+    pub fn hex_and_renderer_mut(&mut self, position: AxialVector) -> Option<(&mut Hex, &mut R)> {
+        let renderer = &mut self.renderer;
+        self.hexes.get_mut(position).map(|hex| (hex, renderer))
+    }
+
+    // Replaced or removed hexes must be disposed, prefer insert.
+    pub fn parts_mut(&mut self) -> (&mut RectHashStorage<Hex>, &mut R) {
+        (&mut self.hexes, &mut self.renderer)
+    }
+
+    // The replaced hex, if any, is disposed.
+    pub fn insert(
+        &mut self,
+        position: AxialVector,
+        hex: Hex,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        if let Some(mut old) = self.hexes.insert(position, hex) {
+            old.dispose(data);
+        }
+    }
+}
+
+impl<R: HexRenderer, Hex: Dispose> Dispose for EcsBackedStorage<R, Hex> {
+    fn dispose(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        self.renderer.clear(data);
+        self.hexes.dispose(data);
+    }
+}
+
+impl<R: HexRenderer, Hex: Dispose> Drop for EcsBackedStorage<R, Hex> {
+    fn drop(&mut self) {
+        if !self.hexes.is_empty() {
+            error!(
+                "{} hexes dropped without being disposed, their entities leak",
+                self.hexes.len()
+            );
+        }
+    }
+}
@@ -1,16 +1,31 @@
 use crate::{
-    assets::Color, dispose::Dispose, hex::render::renderer::HexRenderer, world::RhombusViewerWorld,
+    assets::Translucent,
+    dispose::Dispose,
+    hex::{
+        inspect::HexLayer,
+        render::{
+            commands::{
+                apply_commands, default_threads, EntityLook, HexCommand, MaterialKind, MeshKind,
+            },
+            renderer::{is_visible, HexRenderer},
+        },
+        scale::WorldScale,
+    },
+    world::{hex_rotation, transform_hex, RhombusViewerWorld},
 };
 use amethyst::{
-    assets::Handle,
     core::{math::Vector3, transform::Transform},
     ecs::prelude::*,
     prelude::*,
-    renderer::Material,
 };
-use rhombus_core::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+use rhombus_core::hex::{
+    analysis::autotile::{autotile_index, neighbor_mask},
+    coordinates::axial::AxialVector,
+    float::layout::HexLayout,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct HexScale {
     pub horizontal: f32,
     pub vertical: f32,
@@ -20,7 +35,22 @@ pub struct HexScale {
 pub struct Hex {
     entity: Option<Entity>,
     wall: bool,
+    // The neighbor mask of the walls around a wall, which picks its mesh.
+    walls: u8,
     visible: bool,
+    // Replaces the atlas material, whether the hex is visible or not.
+    translucent: Option<Translucent>,
+    translucent_changed: bool,
+}
+
+impl Hex {
+    // Applied by the next update of the hex.
+    pub fn set_translucent(&mut self, translucent: Option<Translucent>) {
+        if translucent != self.translucent {
+            self.translucent = translucent;
+            self.translucent_changed = true;
+        }
+    }
 }
 
 impl Dispose for Hex {
@@ -34,6 +64,20 @@ impl Dispose for Hex {
 pub struct TileRenderer {
     ground_scale: HexScale,
     wall_scale: HexScale,
+    follow_world_scale: bool,
+    fog_of_war: bool,
+    threads: usize,
+}
+
+// What the entity of a hex must show, computed in parallel.
+#[derive(Clone, Copy, Debug)]
+struct TileTarget {
+    position: AxialVector,
+    wall: bool,
+    walls: u8,
+    visible: bool,
+    // Whether the hex has an entity at all.
+    shown: bool,
 }
 
 impl TileRenderer {
@@ -41,9 +85,44 @@ impl TileRenderer {
         Self {
             ground_scale,
             wall_scale,
+            follow_world_scale: false,
+            fog_of_war: false,
+            threads: default_threads(),
+        }
+    }
+
+    // The scales are taken from the WorldScale resource each time the world is updated.
+    pub fn following_world_scale() -> Self {
+        let world_scale = WorldScale::default();
+        Self {
+            ground_scale: world_scale.ground(),
+            wall_scale: world_scale.wall(),
+            follow_world_scale: true,
+            fog_of_war: false,
+            threads: default_threads(),
         }
     }
 
+    // The hexes out of sight are covered with fog instead of being darkened.
+    pub fn with_fog_of_war(mut self) -> Self {
+        self.fog_of_war = true;
+        self
+    }
+
+    fn sync_world_scale(&mut self, data: &StateData<'_, GameData<'_, '_>>) -> bool {
+        if !self.follow_world_scale {
+            return false;
+        }
+        let world_scale = *data.world.read_resource::<WorldScale>();
+        let (ground_scale, wall_scale) = (world_scale.ground(), world_scale.wall());
+        if ground_scale == self.ground_scale && wall_scale == self.wall_scale {
+            return false;
+        }
+        self.ground_scale = ground_scale;
+        self.wall_scale = wall_scale;
+        true
+    }
+
     fn get_scale(&self, wall: bool) -> HexScale {
         if wall {
             self.wall_scale
@@ -52,27 +131,45 @@ impl TileRenderer {
         }
     }
 
-    fn get_material(
-        &self,
-        wall: bool,
-        visible: bool,
-        world: &RhombusViewerWorld,
-    ) -> Handle<Material> {
-        let color = if wall { Color::Red } else { Color::White };
-        if visible {
-            world.assets.color_data[&color].light.clone()
+    fn get_translucent(&self, visible: bool, hex: &Hex) -> Option<Translucent> {
+        hex.translucent.or_else(|| {
+            if self.fog_of_war && !visible {
+                Some(Translucent::Fog)
+            } else {
+                None
+            }
+        })
+    }
+
+    // The textures of all the terrain types are in the atlas, only the meshes differ.
+    fn get_material(visible: bool, translucent: Option<Translucent>) -> MaterialKind {
+        match translucent {
+            Some(translucent) => MaterialKind::Translucent(translucent),
+            None => MaterialKind::Atlas { light: visible },
+        }
+    }
+
+    // And how many directions it is turned.
+    fn get_mesh(wall: bool, walls: u8) -> (MeshKind, usize) {
+        if wall {
+            let (index, turns) = autotile_index(walls);
+            (MeshKind::Wall(index), turns)
         } else {
-            world.assets.color_data[&color].dark.clone()
+            (MeshKind::Ground, 0)
         }
     }
 
-    fn create_hex(
+    // The meshes turn the other way round than the directions.
+    fn get_rotation(turns: usize, layout: &HexLayout) -> f32 {
+        hex_rotation(layout) - std::f32::consts::FRAC_PI_3 * turns as f32
+    }
+
+    fn get_transform(
         position: AxialVector,
         scale: HexScale,
-        material: Handle<Material>,
-        data: &mut StateData<'_, GameData<'_, '_>>,
-        world: &RhombusViewerWorld,
-    ) -> Entity {
+        turns: usize,
+        layout: &HexLayout,
+    ) -> Transform {
         let mut transform = Transform::default();
         transform.set_scale(Vector3::new(
             scale.horizontal,
@@ -80,89 +177,93 @@ impl TileRenderer {
             scale.horizontal,
         ));
         let pos = (position, scale.vertical).into();
-        world.transform_axial(pos, &mut transform);
-        data.world
-            .create_entity()
-            .with(world.assets.hex_handle.clone())
-            .with(material)
-            .with(transform)
-            .build()
+        transform_hex(layout, pos, &mut transform);
+        transform.set_rotation_y_axis(Self::get_rotation(turns, layout));
+        transform
     }
 
-    pub fn update_hex(
+    // The commands bringing the entity of the hex to the target, the hex records what it shows.
+    fn stage(
         &self,
-        position: AxialVector,
         hex: &mut Hex,
-        data: &mut StateData<'_, GameData<'_, '_>>,
-        world: &RhombusViewerWorld,
-    ) {
-        let scale = self.get_scale(hex.wall);
-        let material = self.get_material(hex.wall, hex.visible, world);
-        if let Some(entity) = hex.entity {
-            Self::update_hex_transform(entity, scale, &mut data.world.write_storage::<Transform>());
-            Self::update_hex_color(
-                entity,
-                material,
-                &mut data.world.write_storage::<Handle<Material>>(),
-            );
-        } else {
-            hex.entity = Some(Self::create_hex(position, scale, material, data, world));
-        }
-    }
-
-    fn update_hex_internal(
-        &self,
-        hex: &mut Hex,
-        wall: bool,
-        visible: bool,
-        scale: HexScale,
+        target: TileTarget,
         force: bool,
-        world: &RhombusViewerWorld,
-        transform_storage: &mut WriteStorage<Transform>,
-        material_storage: &mut WriteStorage<Handle<Material>>,
+        layout: &HexLayout,
+        commands: &mut Vec<HexCommand>,
     ) {
-        if let Some(entity) = hex.entity {
-            if force || hex.wall != wall {
-                Self::update_hex_transform(entity, scale, transform_storage);
+        let TileTarget {
+            position,
+            wall,
+            walls,
+            visible,
+            shown,
+        } = target;
+        if !shown {
+            if let Some(entity) = hex.entity.take() {
+                commands.push(HexCommand::Delete { entity });
             }
-            if force || hex.wall != wall || hex.visible != visible {
-                Self::update_hex_color(
+            return;
+        }
+        let (mesh, turns) = Self::get_mesh(wall, walls);
+        let transform = || Self::get_transform(position, self.get_scale(wall), turns, layout);
+        let material = || Self::get_material(visible, self.get_translucent(visible, hex));
+        if let Some(entity) = hex.entity {
+            let shape_changed = force || hex.wall != wall || hex.walls != walls;
+            let color_changed = force || hex.visible != visible || hex.translucent_changed;
+            if shape_changed || color_changed {
+                commands.push(HexCommand::Update {
                     entity,
-                    self.get_material(wall, visible, world),
-                    material_storage,
-                );
+                    transform: if shape_changed {
+                        Some(transform())
+                    } else {
+                        None
+                    },
+                    mesh: if shape_changed { Some(mesh) } else { None },
+                    material: if color_changed {
+                        Some(material())
+                    } else {
+                        None
+                    },
+                });
             }
         } else {
-            unreachable!();
+            commands.push(HexCommand::Create {
+                position,
+                layer: HexLayer::Tile,
+                owner: "tile renderer",
+                chunk: true,
+                look: EntityLook {
+                    transform: transform(),
+                    mesh,
+                    material: material(),
+                },
+            });
         }
         hex.wall = wall;
+        hex.walls = walls;
         hex.visible = visible;
+        hex.translucent_changed = false;
     }
 
-    fn update_hex_transform(
-        entity: Entity,
-        scale: HexScale,
-        transform_storage: &mut WriteStorage<Transform>,
-    ) {
-        let transform = transform_storage
-            .get_mut(entity)
-            .expect("An hex always has a Transform");
-        transform.set_scale(Vector3::new(
-            scale.horizontal,
-            scale.vertical,
-            scale.horizontal,
-        ));
-        transform.translation_mut()[1] = scale.vertical;
-    }
-
-    fn update_hex_color(
-        entity: Entity,
-        material: Handle<Material>,
-        material_storage: &mut WriteStorage<Handle<Material>>,
+    pub fn update_hex(
+        &self,
+        position: AxialVector,
+        hex: &mut Hex,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
     ) {
-        *material_storage
-            .get_mut(entity)
-            .expect("An hex always has a Material") = material;
+        let target = TileTarget {
+            position,
+            wall: hex.wall,
+            walls: hex.walls,
+            visible: hex.visible,
+            shown: true,
+        };
+        let mut commands = Vec::new();
+        self.stage(hex, target, true, &world.layout, &mut commands);
+        if let Some((_, entity)) = apply_commands(commands, data, world).pop() {
+            hex.entity = Some(entity);
+        }
     }
 }
 
@@ -173,76 +274,150 @@ impl HexRenderer for TileRenderer {
         Hex {
             entity: None,
             wall,
+            walls: 0,
             visible,
+            translucent: None,
+            translucent_changed: false,
         }
     }
 
-    fn update_world<'a, StorageHex, MapHex, Wall, Visible>(
+    fn update_world<'a, StorageHex, MapHex, Wall>(
         &mut self,
         hexes: &mut RectHashStorage<StorageHex>,
         is_wall_hex: Wall,
-        is_visible_hex: Visible,
+        visible_positions: Option<&HexSet>,
         get_renderer_hex: MapHex,
         visible_only: bool,
         force: bool,
+        layout: &HexLayout,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
     ) where
-        StorageHex: 'a + Dispose,
+        StorageHex: 'a + Dispose + Sync,
         MapHex: Fn(&mut StorageHex) -> &mut Self::Hex,
-        Wall: Fn(AxialVector, &StorageHex) -> bool,
-        Visible: Fn(AxialVector, &StorageHex) -> bool,
+        Wall: Fn(AxialVector, &StorageHex) -> bool + Sync,
     {
-        let ground_scale = self.get_scale(false);
-        let wall_scale = self.get_scale(true);
-        {
-            let mut transform_storage = data.world.write_storage::<Transform>();
-            let mut material_storage = data.world.write_storage::<Handle<Material>>();
-            for (pos, hex) in hexes.iter_mut() {
-                let wall = is_wall_hex(pos, hex);
-                let visible = is_visible_hex(pos, hex);
-                let renderer_hex = get_renderer_hex(hex);
-                if !visible_only || visible {
-                    if renderer_hex.entity.is_some() {
-                        self.update_hex_internal(
-                            renderer_hex,
-                            wall,
-                            visible,
-                            if wall { wall_scale } else { ground_scale },
-                            force,
-                            world,
-                            &mut transform_storage,
-                            &mut material_storage,
-                        );
-                    }
-                }
+        let force = self.sync_world_scale(data) || force;
+        // The neighbors are read from the storage, every rect being computed by any thread.
+        let target = |pos, hex: &StorageHex, storage: &RectHashStorage<StorageHex>| {
+            let wall = is_wall_hex(pos, hex);
+            let walls = if wall {
+                neighbor_mask(pos, |neighbor| {
+                    storage
+                        .get(neighbor)
+                        .filter(|other| is_wall_hex(neighbor, other))
+                        .is_some()
+                })
+            } else {
+                0
+            };
+            let visible = is_visible(visible_positions, pos);
+            TileTarget {
+                position: pos,
+                wall,
+                walls,
+                visible,
+                shown: !visible_only || visible,
             }
-        }
-        {
-            for (pos, hex) in hexes.iter_mut() {
-                let wall = is_wall_hex(pos, hex);
-                let visible = is_visible_hex(pos, hex);
-                let renderer_hex = get_renderer_hex(hex);
-                if !visible_only || visible {
-                    if renderer_hex.entity.is_none() {
-                        renderer_hex.entity = Some(Self::create_hex(
-                            pos,
-                            if wall { wall_scale } else { ground_scale },
-                            self.get_material(wall, visible, world),
-                            data,
-                            world,
-                        ));
-                        renderer_hex.wall = wall;
-                        renderer_hex.visible = visible;
-                    }
-                } else {
-                    if let Some(entity) = renderer_hex.entity.take() {
-                        data.world.delete_entity(entity).expect("delete entity");
-                    }
-                }
+        };
+        let mut commands = Vec::new();
+        hexes.par_update(self.threads, target, |hex, target| {
+            self.stage(get_renderer_hex(hex), target, force, layout, &mut commands);
+        });
+        for (pos, entity) in apply_commands(commands, data, world) {
+            if let Some(hex) = hexes.get_mut(pos) {
+                get_renderer_hex(hex).entity = Some(entity);
             }
         }
     }
 
     fn clear(&mut self, _data: &mut StateData<'_, GameData<'_, '_>>) {}
 }
+
+#[test]
+fn test_tile_renderer_stage() {
+    let renderer = TileRenderer::new(
+        HexScale {
+            horizontal: 0.8,
+            vertical: 0.1,
+        },
+        HexScale {
+            horizontal: 1.0,
+            vertical: 1.0,
+        },
+    )
+    .with_fog_of_war();
+    let layout = HexLayout::pointy_top(2.0);
+    let position = AxialVector::new(2, -1);
+    let target = |wall, walls, visible, shown| TileTarget {
+        position,
+        wall,
+        walls,
+        visible,
+        shown,
+    };
+    let mut hex = Hex {
+        entity: None,
+        wall: false,
+        walls: 0,
+        visible: true,
+        translucent: None,
+        translucent_changed: false,
+    };
+    let stage = |hex: &mut Hex, target, force| {
+        let mut commands = Vec::new();
+        renderer.stage(hex, target, force, &layout, &mut commands);
+        commands
+    };
+
+    let commands = stage(&mut hex, target(false, 0, true, true), false);
+    assert!(matches!(
+        &commands[..],
+        [HexCommand::Create { position: created, layer: HexLayer::Tile, chunk: true, look, .. }]
+            if *created == position
+                && look.mesh == MeshKind::Ground
+                && look.material == MaterialKind::Atlas { light: true }
+    ));
+
+    let mut ecs = World::new();
+    let entity = ecs.create_entity().build();
+    hex.entity = Some(entity);
+    assert!(stage(&mut hex, target(false, 0, true, true), false).is_empty());
+    assert_eq!(stage(&mut hex, target(false, 0, true, true), true).len(), 1);
+
+    // A wall turns into its autotile mesh, the material stays.
+    let (index, _) = autotile_index(0b11);
+    let commands = stage(&mut hex, target(true, 0b11, true, true), false);
+    assert!(matches!(
+        &commands[..],
+        [HexCommand::Update {
+            entity: updated,
+            transform: Some(_),
+            mesh: Some(mesh),
+            material: None,
+        }] if *updated == entity && *mesh == MeshKind::Wall(index)
+    ));
+
+    // Out of sight, the fog covers it.
+    let commands = stage(&mut hex, target(true, 0b11, false, true), false);
+    assert!(matches!(
+        &commands[..],
+        [HexCommand::Update { transform: None, mesh: None, material: Some(material), .. }]
+            if *material == MaterialKind::Translucent(Translucent::Fog)
+    ));
+    hex.set_translucent(Some(Translucent::Fog));
+    assert!(matches!(
+        &stage(&mut hex, target(true, 0b11, false, true), false)[..],
+        [HexCommand::Update {
+            material: Some(_),
+            ..
+        }]
+    ));
+
+    let commands = stage(&mut hex, target(true, 0b11, false, false), false);
+    assert!(
+        matches!(&commands[..], [HexCommand::Delete { entity: deleted }] if *deleted == entity)
+    );
+    assert_eq!(hex.entity, None);
+    assert!(stage(&mut hex, target(true, 0b11, false, false), false).is_empty());
+}
@@ -33,7 +33,7 @@ impl SimpleState for HexRingDemo {
                 let mut transform = Transform::default();
                 transform.set_scale(Vector3::new(0.8, 0.08, 0.8));
                 let pos = (hex, 0.0).into();
-                world.transform_axial(pos, &mut transform);
+                world.transform_hex(pos, &mut transform);
                 let material = world.assets.color_data[&Color::Red].light.clone();
                 self.entities.push(
                     data.world
@@ -1,21 +1,41 @@
 use crate::{
     hex::{
+        inspect,
+        pointer::{HexPointer, PointerAction, PointerKeys, PointerSettings},
         render::renderer::HexRenderer,
-        rooms_and_mazes::world::{
-            ConnectState, FovState, MazeState, MoveMode, RemoveAnglesState, RemoveDeadEndsState,
-            World,
-        },
-        shape::cubic_range::CubicRangeShape,
+        rooms_and_mazes::world::World,
+        scale::WorldScale,
+        visibility::FovState,
     },
     input::get_key_and_modifiers,
+    pacing::StepPacer,
+    sound::{play_cue, SoundCue},
+    systems::{
+        event_log::{log_event, EventKind},
+        follow_me::FollowMeSettings,
+        grid_overlay::GridSettings,
+        lighting::LightingConfig,
+    },
     world::RhombusViewerWorld,
 };
 use amethyst::{
-    core::timing::Time, ecs::prelude::*, input::ElementState, prelude::*, winit::VirtualKeyCode,
+    config::Config, ecs::prelude::*, input::ElementState, prelude::*, winit::VirtualKeyCode,
+};
+use log::warn;
+use rhombus_core::hex::{float::layout::HexLayout, shape::cubic_range::CubicRangeShape};
+use rhombus_mapgen::rooms_and_mazes::{
+    ConnectState, ConnectivityConfig, MazeState, RemoveAnglesState, RemoveDeadEndsState,
+    RoomTemplate, RoomTemplateLibrary,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write as _},
+    path::{Path, PathBuf},
+    sync::Arc,
 };
-use std::sync::Arc;
 
 const ROOM_ROUNDS: usize = 100;
+const STEP_MILLIS: u64 = 5;
 
 #[derive(Debug)]
 enum BuilderState {
@@ -25,25 +45,76 @@ enum BuilderState {
     RemoveDeadEnds(RemoveDeadEndsState),
     RemoveAngles(RemoveAnglesState),
     Grown,
-    FieldOfView(bool),
+    FieldOfView(FovState),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Connectivity {
+    Default,
+    Tree,
+    Loopy,
+}
+
+impl Connectivity {
+    fn next(self) -> Self {
+        match self {
+            Connectivity::Default => Connectivity::Tree,
+            Connectivity::Tree => Connectivity::Loopy,
+            Connectivity::Loopy => Connectivity::Default,
+        }
+    }
+
+    fn config(self) -> ConnectivityConfig {
+        match self {
+            Connectivity::Default => ConnectivityConfig::default(),
+            Connectivity::Tree => ConnectivityConfig::tree(),
+            Connectivity::Loopy => ConnectivityConfig::loopy(),
+        }
+    }
+}
+
+// Where the room templates are read from, see config/rooms.ron.
+pub struct RoomTemplatesSettings {
+    pub path: PathBuf,
 }
 
 pub struct HexRoomsAndMazesBuilder<R: HexRenderer> {
     world: World<R>,
-    remaining_millis: u64,
+    keys: PointerKeys,
+    pacer: StepPacer,
     state: BuilderState,
+    connectivity: Connectivity,
 }
 
 impl<R: HexRenderer> HexRoomsAndMazesBuilder<R> {
     pub fn new(renderer: R) -> Self {
         Self {
             world: World::new(renderer),
-            remaining_millis: 0,
+            keys: PointerKeys::default(),
+            pacer: StepPacer::new(STEP_MILLIS),
             state: BuilderState::Grown,
+            connectivity: Connectivity::Default,
+        }
+    }
+
+    fn apply_pointer_action(
+        &mut self,
+        action: PointerAction,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        match action {
+            PointerAction::Move(mode) => self.world.next_position(mode, data),
+            PointerAction::TurnLeft => self.world.turn(HexPointer::decrement_direction, data),
+            PointerAction::TurnRight => self.world.turn(HexPointer::increment_direction, data),
         }
     }
 
+    // The templates are read again every time so that they can be tweaked without restarting.
     fn reset(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let (templates, ratio) =
+            load_room_templates(&data.world.read_resource::<RoomTemplatesSettings>().path);
+        self.world.set_room_templates(templates, ratio);
+        self.world.set_connectivity(self.connectivity.config());
         let world_radius = 42;
         self.world.set_shape_and_reset_world(
             CubicRangeShape::new(
@@ -54,19 +125,83 @@ impl<R: HexRenderer> HexRoomsAndMazesBuilder<R> {
             data,
         );
         self.state = BuilderState::Rooms(ROOM_ROUNDS);
-        self.remaining_millis = 0;
+        self.pacer.reset();
+        log_event(
+            data.world,
+            EventKind::Generation,
+            format!("Rooms, seed {}", self.world.seed().seed()),
+        );
+    }
+}
+
+// A missing file means no templates, a broken one is worth a warning.
+fn load_room_templates(path: &Path) -> (Vec<RoomTemplate>, f64) {
+    if !path.exists() {
+        return (Vec::new(), 0.0);
+    }
+    match RoomTemplateLibrary::load(path)
+        .map_err(|err| err.to_string())
+        .and_then(|library| Ok((library.build()?, library.ratio)))
+    {
+        Ok(templates) => templates,
+        Err(err) => {
+            warn!(
+                "Failed to read room templates file: `{}` ({}), using plain rooms only.",
+                path.display(),
+                err
+            );
+            (Vec::new(), 0.0)
+        }
+    }
+}
+
+// Written in the current directory, `neato -n -Tsvg dungeon.dot` keeps the nodes where they are
+// on the map.
+fn export_graph(
+    world: &World<impl HexRenderer>,
+    layout: &HexLayout,
+    data: &StateData<'_, GameData<'_, '_>>,
+) {
+    let graph = world.graph();
+    let result = File::create("dungeon.dot")
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            graph.write_dot(&mut writer, layout)?;
+            writer.flush()
+        })
+        .and_then(|_| File::create("dungeon.graphml"))
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            graph.write_graphml(&mut writer)?;
+            writer.flush()
+        });
+    match result {
+        Ok(()) => log_event(
+            data.world,
+            EventKind::Generation,
+            "Dungeon graph written to dungeon.dot and dungeon.graphml".to_string(),
+        ),
+        Err(err) => log_event(
+            data.world,
+            EventKind::Error,
+            format!("Failed to write the dungeon graph ({})", err),
+        ),
     }
 }
 
 impl<R: HexRenderer> SimpleState for HexRoomsAndMazesBuilder<R> {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.keys.release_all();
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         world.set_camera_distance(&data, 300.0);
+        self.world.seed_mut().start(&data);
         self.reset(&mut data);
+        self.pacer.start(&data);
         self.world.update_renderer_world(true, &mut data);
     }
 
     fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.pacer.stop(&data);
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
         self.world.clear(&mut data, &world);
     }
@@ -77,57 +212,68 @@ impl<R: HexRenderer> SimpleState for HexRoomsAndMazesBuilder<R> {
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
+            if PointerSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if FollowMeSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if inspect::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if GridSettings::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if LightingConfig::handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if let Some(action) = self.keys.handle_event(&data, &event) {
+                self.apply_pointer_action(action, &mut data);
+                return Trans::None;
+            }
+            if self.pacer.handle_event(&data, &event) {
+                return Trans::None;
+            }
+            if WorldScale::handle_event(&data, &event) {
+                self.world.update_renderer_world(true, &mut data);
+                return Trans::None;
+            }
             let mut trans = Trans::None;
             match get_key_and_modifiers(&event) {
                 Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
                     trans = Trans::Pop;
                 }
-                Some((VirtualKeyCode::N, ElementState::Pressed, _)) => {
-                    self.world.reset_world(&mut data);
-                    self.state = BuilderState::Rooms(ROOM_ROUNDS);
-                    self.remaining_millis = 0;
-                }
-                Some((VirtualKeyCode::Right, ElementState::Pressed, modifiers)) => {
-                    if modifiers.shift {
-                        self.world
-                            .next_position(MoveMode::StrafeRightAhead, &mut data);
-                    } else if modifiers.ctrl {
-                        self.world
-                            .next_position(MoveMode::StrafeRightBack, &mut data);
-                    } else {
-                        self.world.increment_direction(&data);
-                    }
-                }
-                Some((VirtualKeyCode::Left, ElementState::Pressed, modifiers)) => {
-                    if modifiers.shift {
-                        self.world
-                            .next_position(MoveMode::StrafeLeftAhead, &mut data);
-                    } else if modifiers.ctrl {
-                        self.world
-                            .next_position(MoveMode::StrafeLeftBack, &mut data);
-                    } else {
-                        self.world.decrement_direction(&data);
+                Some((VirtualKeyCode::N, ElementState::Pressed, modifiers)) => {
+                    if !modifiers.shift {
+                        self.world.seed_mut().renew();
                     }
+                    self.reset(&mut data);
                 }
-                Some((VirtualKeyCode::Up, ElementState::Pressed, _)) => {
-                    self.world.next_position(MoveMode::StraightAhead, &mut data);
-                }
-                Some((VirtualKeyCode::Down, ElementState::Pressed, _)) => {
-                    self.world.next_position(MoveMode::StraightBack, &mut data);
+                Some((VirtualKeyCode::L, ElementState::Pressed, _)) => {
+                    self.connectivity = self.connectivity.next();
+                    self.reset(&mut data);
                 }
                 Some((VirtualKeyCode::C, ElementState::Pressed, _)) => {
                     let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
                     world.toggle_follow(&data);
                 }
+                Some((VirtualKeyCode::G, ElementState::Pressed, _)) => {
+                    if let BuilderState::FieldOfView(..) = self.state {
+                        let world =
+                            (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+                        export_graph(&self.world, &world.layout, &data);
+                    }
+                }
                 Some((VirtualKeyCode::V, ElementState::Pressed, _)) => {
-                    if let BuilderState::FieldOfView(mut fov_enabled) = self.state {
-                        fov_enabled = !fov_enabled;
-                        self.world.change_field_of_view(if fov_enabled {
-                            FovState::Full
-                        } else {
-                            FovState::Partial
-                        });
-                        self.state = BuilderState::FieldOfView(fov_enabled);
+                    if let BuilderState::FieldOfView(fov_state) = self.state {
+                        let fov_state = fov_state.next();
+                        self.world.change_field_of_view(fov_state);
+                        self.state = BuilderState::FieldOfView(fov_state);
+                        log_event(
+                            data.world,
+                            EventKind::View,
+                            format!("Field of view: {:?}", fov_state),
+                        );
                     }
                 }
                 _ => {}
@@ -139,60 +285,94 @@ impl<R: HexRenderer> SimpleState for HexRoomsAndMazesBuilder<R> {
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(action) = self.keys.repeat(data) {
+            self.apply_pointer_action(action, data);
+        }
+        self.pacer.set_status(
+            data,
+            format!(
+                "seed {}, {}, {:?} connectivity",
+                self.world.seed().seed(),
+                self.world.stats(),
+                self.connectivity
+            ),
+        );
         if let BuilderState::FieldOfView(..) = self.state {
             self.world.update_renderer_world(false, data);
-            self.remaining_millis = 0;
+            self.pacer.reset();
             return Trans::None;
         }
-        let delta_millis = {
-            let duration = data.world.read_resource::<Time>().delta_time();
-            duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
-        } + self.remaining_millis;
-        let num = delta_millis / 5;
-        self.remaining_millis = delta_millis % 5;
         let mut force_update = false;
-        for _ in 0..num {
+        self.pacer.start_frame(data);
+        while self.pacer.next_step() {
             match &mut self.state {
                 BuilderState::Rooms(countdown) => {
                     self.world.add_room();
                     self.state = if *countdown > 1 {
                         BuilderState::Rooms(*countdown - 1)
                     } else {
+                        play_cue(data, SoundCue::PhaseComplete);
+                        log_event(data.world, EventKind::Generation, "Maze".to_string());
                         BuilderState::Maze(self.world.start_maze())
                     };
                 }
                 BuilderState::Maze(state) => {
                     if self.world.grow_maze(state) {
+                        play_cue(data, SoundCue::PhaseComplete);
+                        log_event(data.world, EventKind::Generation, "Connect".to_string());
                         self.state = BuilderState::Connect(self.world.start_connect());
+                    } else {
+                        play_cue(data, SoundCue::WallCarve);
                     }
                 }
                 BuilderState::Connect(state) => {
                     if self.world.connect(state) {
+                        play_cue(data, SoundCue::PhaseComplete);
+                        log_event(
+                            data.world,
+                            EventKind::Generation,
+                            "Remove dead ends".to_string(),
+                        );
                         self.state =
                             BuilderState::RemoveDeadEnds(self.world.start_remove_dead_ends());
                     }
                 }
                 BuilderState::RemoveDeadEnds(state) => {
                     if self.world.remove_dead_ends(state) {
+                        play_cue(data, SoundCue::PhaseComplete);
+                        log_event(
+                            data.world,
+                            EventKind::Generation,
+                            "Remove angles".to_string(),
+                        );
                         self.state = BuilderState::RemoveAngles(self.world.start_remove_angles());
                     }
                 }
                 BuilderState::RemoveAngles(state) => {
                     if self.world.remove_angles(state) {
                         self.world.clean_walls(data);
+                        let graph = self.world.graph();
+                        log_event(
+                            data.world,
+                            EventKind::Generation,
+                            format!("Dungeon graph: {}", graph),
+                        );
+                        self.world.place_lights(&graph, data);
                         force_update = true;
+                        play_cue(data, SoundCue::PhaseComplete);
                         self.state = BuilderState::Grown;
                     }
                 }
                 BuilderState::Grown => {
                     self.world.create_pointer(FovState::Partial, data);
-                    self.state = BuilderState::FieldOfView(false);
+                    self.state = BuilderState::FieldOfView(FovState::Partial);
                 }
                 BuilderState::FieldOfView(..) => {
                     break;
                 }
             }
         }
+        self.pacer.end_frame(data);
         self.world.update_renderer_world(force_update, data);
         Trans::None
     }
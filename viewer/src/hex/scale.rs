@@ -0,0 +1,99 @@
+use crate::{hex::render::tile::HexScale, input::get_key_and_modifiers};
+use amethyst::{
+    input::ElementState,
+    prelude::*,
+    winit::{Event, VirtualKeyCode},
+};
+
+const HEX_SCALE_HORIZONTAL: f32 = 0.8;
+const GROUND_HEX_SCALE_VERTICAL: f32 = 0.1;
+const WALL_HEX_SCALE_VERTICAL: f32 = 1.0;
+
+const HORIZONTAL_STEP: f32 = 0.05;
+const MIN_HORIZONTAL: f32 = 0.1;
+const MAX_HORIZONTAL: f32 = 1.0;
+
+const VERTICAL_STEP: f32 = 0.05;
+const MIN_VERTICAL: f32 = 0.05;
+const MAX_VERTICAL: f32 = 3.0;
+
+// Proportions of the tiles, tile renderers following it rebuild their transforms whenever it
+// changes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WorldScale {
+    pub horizontal: f32,
+    pub ground_vertical: f32,
+    pub wall_vertical: f32,
+}
+
+impl Default for WorldScale {
+    fn default() -> Self {
+        Self {
+            horizontal: HEX_SCALE_HORIZONTAL,
+            ground_vertical: GROUND_HEX_SCALE_VERTICAL,
+            wall_vertical: WALL_HEX_SCALE_VERTICAL,
+        }
+    }
+}
+
+impl WorldScale {
+    pub fn ground(&self) -> HexScale {
+        HexScale {
+            horizontal: self.horizontal,
+            vertical: self.ground_vertical,
+        }
+    }
+
+    pub fn wall(&self) -> HexScale {
+        HexScale {
+            horizontal: self.horizontal,
+            vertical: self.wall_vertical,
+        }
+    }
+
+    // X, Z and W grow the tiles, the floors and the walls (shift shrinks them), 0 restores the
+    // default scale. Returns true if the scale changed.
+    pub fn handle_event(data: &StateData<'_, GameData<'_, '_>>, event: &Event) -> bool {
+        let mut scale = data.world.write_resource::<WorldScale>();
+        let previous = *scale;
+        match get_key_and_modifiers(event) {
+            Some((VirtualKeyCode::X, ElementState::Pressed, modifiers)) => {
+                scale.horizontal = step(
+                    scale.horizontal,
+                    HORIZONTAL_STEP,
+                    modifiers.shift,
+                    MIN_HORIZONTAL,
+                    MAX_HORIZONTAL,
+                );
+            }
+            Some((VirtualKeyCode::Z, ElementState::Pressed, modifiers)) => {
+                scale.ground_vertical = step(
+                    scale.ground_vertical,
+                    VERTICAL_STEP,
+                    modifiers.shift,
+                    MIN_VERTICAL,
+                    MAX_VERTICAL,
+                );
+            }
+            Some((VirtualKeyCode::W, ElementState::Pressed, modifiers)) => {
+                scale.wall_vertical = step(
+                    scale.wall_vertical,
+                    VERTICAL_STEP,
+                    modifiers.shift,
+                    MIN_VERTICAL,
+                    MAX_VERTICAL,
+                );
+            }
+            Some((VirtualKeyCode::Key0, ElementState::Pressed, _)) => {
+                *scale = WorldScale::default();
+            }
+            _ => return false,
+        }
+        *scale != previous
+    }
+}
+
+fn step(value: f32, step: f32, decrease: bool, min: f32, max: f32) -> f32 {
+    let value = if decrease { value - step } else { value + step };
+    value.max(min).min(max)
+}
@@ -1 +0,0 @@
-pub mod cubic_range;
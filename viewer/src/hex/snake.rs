@@ -1,69 +1,268 @@
-use crate::{assets::Color, snake::Snake, world::RhombusViewerWorld};
+use crate::{
+    assets::Color,
+    input::get_key_and_modifiers,
+    pacing::{set_window_title, AnimationSettings},
+    seed::DemoSeed,
+    world::RhombusViewerWorld,
+};
 use amethyst::{
+    assets::Handle,
     core::{math::Vector3, timing::Time, transform::Transform},
     ecs::prelude::*,
-    input::is_key_down,
+    input::ElementState,
     prelude::*,
+    renderer::Material,
     winit::VirtualKeyCode,
 };
-use rhombus_core::hex::coordinates::{axial::AxialVector, ring::RingIter};
+use rand::seq::SliceRandom;
+use rhombus_core::hex::{
+    coordinates::{
+        axial::AxialVector,
+        direction::{HexagonalDirection, NUM_DIRECTIONS},
+    },
+    storage::hash::RectHashStorage,
+};
 use std::{collections::VecDeque, sync::Arc};
 
+const STEP_MILLIS: u64 = 200;
+const ARENA_RADIUS: usize = 6;
+const INITIAL_LENGTH: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Cell {
+    Snake,
+    Food,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SnakeState {
+    // Chases the food on its own until the user steers it.
+    Autopilot,
+    Manual,
+    GameOver,
+}
+
 pub struct HexSnakeDemo {
     position: AxialVector,
-    snakes: Vec<Snake<Entity, RingIter<AxialVector>>>,
+    cells: RectHashStorage<(Cell, Entity)>,
+    body: VecDeque<AxialVector>,
+    direction: usize,
+    state: SnakeState,
+    score: usize,
+    walls: Vec<Entity>,
     remaining_millis: u64,
+    seed: DemoSeed,
 }
 
 impl HexSnakeDemo {
     pub fn new() -> Self {
         Self {
             position: AxialVector::default(),
-            snakes: Vec::new(),
+            cells: RectHashStorage::new(),
+            body: VecDeque::new(),
+            direction: 0,
+            state: SnakeState::Autopilot,
+            score: 0,
+            walls: Vec::new(),
             remaining_millis: 0,
+            seed: DemoSeed::new(),
+        }
+    }
+
+    // The same seed places the food at the same places again.
+    fn reset(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        self.clear_cells(data);
+        self.seed.restart();
+        self.direction = 0;
+        self.state = SnakeState::Autopilot;
+        self.score = 0;
+        self.remaining_millis = 0;
+        let mut hex = self.position;
+        for _ in 0..INITIAL_LENGTH {
+            self.push_head(hex, data, world);
+            hex = hex.neighbor(self.direction);
+        }
+        self.spawn_food(data, world);
+        self.update_title(data);
+    }
+
+    fn clear_cells(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        for (_, entity) in self.cells.hexes() {
+            data.world.delete_entity(*entity).expect("delete entity");
+        }
+        self.cells.clear();
+        self.body.clear();
+    }
+
+    fn is_in_arena(&self, hex: AxialVector) -> bool {
+        hex.distance(self.position) <= ARENA_RADIUS as isize
+    }
+
+    fn is_blocked(&self, hex: AxialVector) -> bool {
+        if !self.is_in_arena(hex) {
+            return true;
+        }
+        match self.cells.get(hex) {
+            // The tail moves away at the same time, the snake cannot be eating then.
+            Some((Cell::Snake, _)) => self.body.front() != Some(&hex),
+            Some((Cell::Food, _)) | None => false,
+        }
+    }
+
+    fn head(&self) -> AxialVector {
+        *self.body.back().expect("head")
+    }
+
+    fn food(&self) -> Option<AxialVector> {
+        self.cells
+            .iter()
+            .find(|(_, (cell, _))| *cell == Cell::Food)
+            .map(|(pos, _)| pos)
+    }
+
+    fn next_direction(&self) -> Option<usize> {
+        let head = self.head();
+        if self.state != SnakeState::Autopilot {
+            return Some(self.direction).filter(|dir| !self.is_blocked(head.neighbor(*dir)));
+        }
+        // Greedy: get closer to the food, prefer going straight on ties.
+        let food = self.food().unwrap_or(self.position);
+        (0..NUM_DIRECTIONS)
+            .map(|offset| (self.direction + offset) % NUM_DIRECTIONS)
+            .filter(|dir| !self.is_blocked(head.neighbor(*dir)))
+            .min_by_key(|dir| head.neighbor(*dir).distance(food))
+    }
+
+    fn step(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        if self.state == SnakeState::GameOver {
+            return;
+        }
+        let direction = if let Some(direction) = self.next_direction() {
+            direction
+        } else if self.state == SnakeState::Autopilot {
+            // Trapped, start over so that the demo keeps going.
+            self.reset(data, world);
+            return;
+        } else {
+            let material = world.assets.color_data[&Color::Yellow].light.clone();
+            self.set_head_material(material, data);
+            self.state = SnakeState::GameOver;
+            self.update_title(data);
+            return;
+        };
+        self.direction = direction;
+        let next = self.head().neighbor(direction);
+        let eating = matches!(self.cells.get(next), Some((Cell::Food, _)));
+        if eating {
+            if let Some((_, entity)) = self.cells.remove(next) {
+                data.world.delete_entity(entity).expect("delete entity");
+            }
+            self.score += 1;
+        } else if let Some(tail) = self.body.pop_front() {
+            if let Some((_, entity)) = self.cells.remove(tail) {
+                data.world.delete_entity(entity).expect("delete entity");
+            }
+        }
+        self.push_head(next, data, world);
+        if eating {
+            self.spawn_food(data, world);
+            self.update_title(data);
+        }
+    }
+
+    fn push_head(
+        &mut self,
+        hex: AxialVector,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        // The head is lit, the rest of the body is dark.
+        let material = world.assets.color_data[&Color::Red].dark.clone();
+        self.set_head_material(material, data);
+        let material = world.assets.color_data[&Color::Red].light.clone();
+        let entity = Self::push_hex(hex, 0.2, material, data, world);
+        self.cells.insert(hex, (Cell::Snake, entity));
+        self.body.push_back(hex);
+    }
+
+    fn set_head_material(
+        &self,
+        material: Handle<Material>,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+    ) {
+        if let Some((_, entity)) = self.body.back().and_then(|head| self.cells.get(*head)) {
+            *data
+                .world
+                .write_storage::<Handle<Material>>()
+                .get_mut(*entity)
+                .expect("An hex always has a Material") = material;
         }
     }
 
-    fn new_snake(
-        position: AxialVector,
-        radius: usize,
+    fn spawn_food(
+        &mut self,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        let free = self
+            .position
+            .spiral_iter(ARENA_RADIUS)
+            .filter(|hex| !self.cells.contains_position(*hex))
+            .collect::<Vec<_>>();
+        if let Some(hex) = free.choose(self.seed.rng()) {
+            let material = world.assets.color_data[&Color::Green].light.clone();
+            let entity = Self::push_hex(*hex, 0.2, material, data, world);
+            self.cells.insert(*hex, (Cell::Food, entity));
+        }
+    }
+
+    fn show_walls(
+        &mut self,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
-    ) -> Snake<Entity, RingIter<AxialVector>> {
-        let mut state = VecDeque::new();
-        let mut iter = Self::snake_center(position).ring_iter(radius);
-        state.push_back(Self::push_hex(
-            iter.next().expect("first"),
-            data,
-            &world,
-            Color::Red,
-        ));
-        Snake {
-            radius,
-            state,
-            iter,
+    ) {
+        for hex in self.position.ring_iter(ARENA_RADIUS + 1) {
+            let material = world.assets.color_data[&Color::White].dark.clone();
+            self.walls
+                .push(Self::push_hex(hex, 0.4, material, data, world));
         }
     }
 
-    fn snake_center(position: AxialVector) -> AxialVector {
-        position
+    fn hide_walls(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let result = data.world.delete_entities(self.walls.as_slice());
+        self.walls.clear();
+        result.expect("delete entities");
     }
 
-    fn snake_tail_size(radius: usize) -> usize {
-        3 * radius
+    fn update_title(&self, data: &StateData<'_, GameData<'_, '_>>) {
+        let settings = data.world.read_resource::<AnimationSettings>();
+        set_window_title(
+            data.world,
+            &format!(
+                "{} - seed {} - score {}{}",
+                settings.window_title,
+                self.seed.seed(),
+                self.score,
+                if self.state == SnakeState::GameOver {
+                    " (game over, N restarts)"
+                } else {
+                    ""
+                }
+            ),
+        );
     }
 
     fn push_hex(
         hex: AxialVector,
+        height: f32,
+        material: Handle<Material>,
         data: &mut StateData<'_, GameData<'_, '_>>,
         world: &RhombusViewerWorld,
-        color: Color,
     ) -> Entity {
         let mut transform = Transform::default();
-        transform.set_scale(Vector3::new(0.8, 0.08, 0.8));
-        let pos = (hex, 0.0).into();
-        world.transform_axial(pos, &mut transform);
-        let material = world.assets.color_data[&color].light.clone();
+        transform.set_scale(Vector3::new(0.8, height / 2.0, 0.8));
+        let pos = (hex, height / 2.0).into();
+        world.transform_hex(pos, &mut transform);
         data.world
             .create_entity()
             .with(world.assets.hex_handle.clone())
@@ -76,33 +275,51 @@ impl HexSnakeDemo {
 impl SimpleState for HexSnakeDemo {
     fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
         let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
-        self.snakes = vec![
-            Self::new_snake(self.position, 1, &mut data, &world),
-            Self::new_snake(self.position, 3, &mut data, &world),
-        ];
-        self.remaining_millis = 0;
+        self.show_walls(&mut data, &world);
+        self.seed.start(&data);
+        self.reset(&mut data, &world);
     }
 
-    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
-        for snake in &mut self.snakes {
-            while let Some(entity) = snake.state.pop_front() {
-                data.world.delete_entity(entity).expect("delete entity");
-            }
-        }
-        self.snakes.clear();
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.clear_cells(&mut data);
+        self.hide_walls(&mut data);
+        let settings = data.world.read_resource::<AnimationSettings>();
+        set_window_title(data.world, &settings.window_title);
     }
 
     fn handle_event(
         &mut self,
-        _: StateData<'_, GameData<'_, '_>>,
+        mut data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
-            if is_key_down(&event, VirtualKeyCode::Escape) {
-                Trans::Pop
-            } else {
-                Trans::None
+            let mut trans = Trans::None;
+            match get_key_and_modifiers(&event) {
+                Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
+                    trans = Trans::Pop;
+                }
+                Some((VirtualKeyCode::N, ElementState::Pressed, modifiers)) => {
+                    if !modifiers.shift {
+                        self.seed.renew();
+                    }
+                    let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+                    self.reset(&mut data, &world);
+                }
+                Some((VirtualKeyCode::Right, ElementState::Pressed, _)) => {
+                    if self.state != SnakeState::GameOver {
+                        self.direction = (self.direction + 1) % NUM_DIRECTIONS;
+                        self.state = SnakeState::Manual;
+                    }
+                }
+                Some((VirtualKeyCode::Left, ElementState::Pressed, _)) => {
+                    if self.state != SnakeState::GameOver {
+                        self.direction = (self.direction + NUM_DIRECTIONS - 1) % NUM_DIRECTIONS;
+                        self.state = SnakeState::Manual;
+                    }
+                }
+                _ => {}
             }
+            trans
         } else {
             Trans::None
         }
@@ -114,29 +331,10 @@ impl SimpleState for HexSnakeDemo {
             let duration = data.world.read_resource::<Time>().delta_time();
             duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
         } + self.remaining_millis;
-        let num = delta_millis / 100;
-        self.remaining_millis = delta_millis % 100;
-        for snake in &mut self.snakes {
-            for _ in 0..num {
-                if let Some(hex) = snake.iter.next() {
-                    snake
-                        .state
-                        .push_back(Self::push_hex(hex, data, &world, Color::Red));
-                } else {
-                    snake.iter = Self::snake_center(self.position).ring_iter(snake.radius);
-                    snake.state.push_back(Self::push_hex(
-                        snake.iter.next().expect("first"),
-                        data,
-                        &world,
-                        Color::Red,
-                    ));
-                }
-                while snake.state.len() > Self::snake_tail_size(snake.radius) {
-                    if let Some(entity) = snake.state.pop_front() {
-                        data.world.delete_entity(entity).expect("delete entity");
-                    }
-                }
-            }
+        let num = delta_millis / STEP_MILLIS;
+        self.remaining_millis = delta_millis % STEP_MILLIS;
+        for _ in 0..num {
+            self.step(data, &world);
         }
         Trans::None
     }
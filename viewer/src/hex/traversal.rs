@@ -0,0 +1,277 @@
+use crate::{
+    assets::Color, input::get_key_and_modifiers, pacing::StepPacer, world::RhombusViewerWorld,
+};
+use amethyst::{
+    assets::Handle,
+    core::{math::Vector3, transform::Transform},
+    ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    renderer::Material,
+    winit::VirtualKeyCode,
+};
+use rhombus_core::hex::coordinates::{
+    axial::AxialVector,
+    direction::{HexagonalDirection, NUM_DIRECTIONS},
+};
+use std::sync::Arc;
+
+const STEP_MILLIS: u64 = 300;
+const MAX_RADIUS: usize = 6;
+const MAX_CELL_RADIUS: usize = 3;
+// Number of steps the complete traversal stays on screen before it starts over.
+const HOLD_STEPS: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Traversal {
+    Ring,
+    Spiral,
+    BigRing,
+}
+
+impl Traversal {
+    fn next(self) -> Self {
+        match self {
+            Traversal::Ring => Traversal::Spiral,
+            Traversal::Spiral => Traversal::BigRing,
+            Traversal::BigRing => Traversal::Ring,
+        }
+    }
+}
+
+struct TraversalStep {
+    hex: AxialVector,
+    // Direction of the move from the previous step, none for the first step or a jump.
+    direction: Option<usize>,
+}
+
+pub struct HexTraversalDemo {
+    position: AxialVector,
+    traversal: Traversal,
+    radius: usize,
+    cell_radius: usize,
+    steps: Vec<TraversalStep>,
+    index: usize,
+    entities: Vec<Entity>,
+    current: Option<Entity>,
+    pacer: StepPacer,
+}
+
+impl HexTraversalDemo {
+    pub fn new() -> Self {
+        Self {
+            position: AxialVector::default(),
+            traversal: Traversal::Ring,
+            radius: 2,
+            cell_radius: 1,
+            steps: Vec::new(),
+            index: 0,
+            entities: Vec::new(),
+            current: None,
+            pacer: StepPacer::new(STEP_MILLIS),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self.traversal {
+            Traversal::Ring => format!("ring_iter({})", self.radius),
+            Traversal::Spiral => format!("spiral_iter({})", self.radius),
+            Traversal::BigRing => format!("big_ring_iter({}, {})", self.cell_radius, self.radius),
+        }
+    }
+
+    // The vector of a single move in the given direction.
+    fn stride(&self, direction: usize) -> AxialVector {
+        match self.traversal {
+            Traversal::Ring | Traversal::Spiral => AxialVector::direction(direction),
+            Traversal::BigRing => {
+                AxialVector::direction(direction) * (self.cell_radius as isize + 1)
+                    + AxialVector::direction((direction + 1) % NUM_DIRECTIONS)
+                        * self.cell_radius as isize
+            }
+        }
+    }
+
+    fn restart(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        self.delete_entities(data);
+        let hexes = match self.traversal {
+            Traversal::Ring => self.position.ring_iter(self.radius).collect::<Vec<_>>(),
+            Traversal::Spiral => self.position.spiral_iter(self.radius).collect(),
+            Traversal::BigRing => self
+                .position
+                .big_ring_iter(self.cell_radius, self.radius)
+                .collect(),
+        };
+        self.steps = hexes
+            .iter()
+            .enumerate()
+            .map(|(i, hex)| TraversalStep {
+                hex: *hex,
+                direction: if i > 0 {
+                    let stride = *hex - hexes[i - 1];
+                    (0..NUM_DIRECTIONS).find(|dir| self.stride(*dir) == stride)
+                } else {
+                    None
+                },
+            })
+            .collect();
+        self.index = 0;
+        self.pacer.reset();
+        self.update_status(data);
+    }
+
+    fn step(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        if self.index >= self.steps.len() + HOLD_STEPS {
+            self.restart(data);
+            return;
+        }
+        if self.index < self.steps.len() {
+            // The current hex is lit, the ones already visited are dark.
+            if let Some(previous) = self.current {
+                *data
+                    .world
+                    .write_storage::<Handle<Material>>()
+                    .get_mut(previous)
+                    .expect("An hex always has a Material") =
+                    world.assets.color_data[&Color::Red].dark.clone();
+            }
+            let hex = self.steps[self.index].hex;
+            if self.traversal == Traversal::BigRing {
+                // Show the cell the big ring is made of.
+                for cell_hex in hex.spiral_iter(self.cell_radius).skip(1) {
+                    let material = world.assets.color_data[&Color::Blue].dark.clone();
+                    let entity = Self::create_hex(cell_hex, 0.04, material, data, world);
+                    self.entities.push(entity);
+                }
+            }
+            let material = world.assets.color_data[&Color::Red].light.clone();
+            let entity = Self::create_hex(hex, 0.08, material, data, world);
+            self.entities.push(entity);
+            self.current = Some(entity);
+        }
+        self.index += 1;
+        self.update_status(data);
+    }
+
+    fn update_status(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        let status = if self.index == 0 {
+            format!("{}: {} hexes", self.name(), self.steps.len())
+        } else {
+            let current = self.index.min(self.steps.len());
+            let direction = match (current, self.steps[current - 1].direction) {
+                (1, _) => "start".to_string(),
+                (_, Some(direction)) => format!("direction {}", direction),
+                (_, None) => "jump".to_string(),
+            };
+            format!(
+                "{}: index {} of {}, {}",
+                self.name(),
+                current - 1,
+                self.steps.len(),
+                direction
+            )
+        };
+        self.pacer.set_status(data, status);
+    }
+
+    fn create_hex(
+        hex: AxialVector,
+        height: f32,
+        material: Handle<Material>,
+        data: &mut StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) -> Entity {
+        let mut transform = Transform::default();
+        transform.set_scale(Vector3::new(0.8, height, 0.8));
+        let pos = (hex, 0.0).into();
+        world.transform_hex(pos, &mut transform);
+        data.world
+            .create_entity()
+            .with(world.assets.hex_handle.clone())
+            .with(material)
+            .with(transform)
+            .build()
+    }
+
+    fn delete_entities(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        let result = data.world.delete_entities(self.entities.as_slice());
+        self.entities.clear();
+        self.current = None;
+        result.expect("delete entities");
+    }
+}
+
+impl SimpleState for HexTraversalDemo {
+    fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.pacer.start(&data);
+        self.restart(&mut data);
+    }
+
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.pacer.stop(&data);
+        self.delete_entities(&mut data);
+    }
+
+    fn handle_event(
+        &mut self,
+        mut data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = event {
+            if self.pacer.handle_event(&data, &event) {
+                return Trans::None;
+            }
+            let mut trans = Trans::None;
+            match get_key_and_modifiers(&event) {
+                Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => {
+                    trans = Trans::Pop;
+                }
+                Some((VirtualKeyCode::N, ElementState::Pressed, _)) => {
+                    self.restart(&mut data);
+                }
+                Some((VirtualKeyCode::M, ElementState::Pressed, _)) => {
+                    self.traversal = self.traversal.next();
+                    self.restart(&mut data);
+                }
+                Some((VirtualKeyCode::Up, ElementState::Pressed, _)) => {
+                    if self.radius < MAX_RADIUS {
+                        self.radius += 1;
+                        self.restart(&mut data);
+                    }
+                }
+                Some((VirtualKeyCode::Down, ElementState::Pressed, _)) => {
+                    if self.radius > 0 {
+                        self.radius -= 1;
+                        self.restart(&mut data);
+                    }
+                }
+                Some((VirtualKeyCode::Right, ElementState::Pressed, _)) => {
+                    if self.cell_radius < MAX_CELL_RADIUS {
+                        self.cell_radius += 1;
+                        self.restart(&mut data);
+                    }
+                }
+                Some((VirtualKeyCode::Left, ElementState::Pressed, _)) => {
+                    if self.cell_radius > 0 {
+                        self.cell_radius -= 1;
+                        self.restart(&mut data);
+                    }
+                }
+                _ => {}
+            }
+            trans
+        } else {
+            Trans::None
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        self.pacer.start_frame(data);
+        while self.pacer.next_step() {
+            self.step(data, &world);
+        }
+        self.pacer.end_frame(data);
+        Trans::None
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{hex::pointer::HexPointer, world::RhombusViewerWorld};
+use amethyst::prelude::*;
+use rhombus_core::hex::{
+    analysis::sector::Observer,
+    coordinates::axial::AxialVector,
+    field_of_view::visible_positions_in_sight,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FovState {
+    // Hexes out of sight are rendered dark.
+    Partial,
+    // Hexes out of sight are not rendered at all.
+    Full,
+    // Everything is rendered as if in sight.
+    Disabled,
+}
+
+impl FovState {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Partial => Self::Full,
+            Self::Full => Self::Disabled,
+            Self::Disabled => Self::Partial,
+        }
+    }
+}
+
+// How far the pointer sees, without limit when None. The rear distance applies to the 3 sectors
+// behind the pointer, it cannot exceed the view distance.
+pub struct SightSettings {
+    pub view_distance: Option<usize>,
+    pub rear_view_distance: Option<usize>,
+}
+
+impl SightSettings {
+    fn sector_distances(&self) -> [usize; 6] {
+        let front = self.view_distance.unwrap_or(usize::MAX);
+        let rear = self
+            .rear_view_distance
+            .map_or(front, |rear| rear.min(front));
+        [front, front, rear, rear, rear, front]
+    }
+}
+
+// Positions seen from the given one facing the given direction, missing hexes do not block the
+// view.
+pub fn compute_visible_set<H, O>(
+    storage: &RectHashStorage<H>,
+    position: AxialVector,
+    facing: usize,
+    sight: &SightSettings,
+    is_obstacle: O,
+) -> HexSet
+where
+    O: Fn(AxialVector, &H) -> bool,
+{
+    let is_obstacle = |pos| storage.get(pos).map_or(false, |hex| is_obstacle(pos, hex));
+    let observer = Observer::new(position, facing);
+    visible_positions_in_sight(
+        position,
+        sight.view_distance,
+        observer.sight(sight.sector_distances()),
+        &is_obstacle,
+        |pos| storage.contains_position(pos),
+    )
+}
+
+pub struct VisibilityController {
+    fov_state: FovState,
+}
+
+impl VisibilityController {
+    pub fn new(fov_state: FovState) -> Self {
+        Self { fov_state }
+    }
+
+    // Whether the state changed, in which case the world needs to be rendered again.
+    pub fn set_fov_state(&mut self, fov_state: FovState) -> bool {
+        let changed = fov_state != self.fov_state;
+        self.fov_state = fov_state;
+        changed
+    }
+
+    // The visible positions and whether only them are rendered, as expected by
+    // HexRenderer::update_world.
+    pub fn update<H, O>(
+        &self,
+        storage: &RectHashStorage<H>,
+        position: AxialVector,
+        facing: usize,
+        sight: &SightSettings,
+        is_obstacle: O,
+    ) -> (Option<HexSet>, bool)
+    where
+        O: Fn(AxialVector, &H) -> bool,
+    {
+        if self.fov_state == FovState::Disabled {
+            return (None, false);
+        }
+        (
+            Some(compute_visible_set(
+                storage,
+                position,
+                facing,
+                sight,
+                is_obstacle,
+            )),
+            self.fov_state == FovState::Full,
+        )
+    }
+}
+
+// HexPointer::increment_direction or HexPointer::decrement_direction.
+pub type PointerTurn = fn(&mut HexPointer, &StateData<'_, GameData<'_, '_>>, &RhombusViewerWorld);
+
+// Turns the pointer of a world, if any, and returns whether the world needs to be rendered again:
+// the rear sectors may not see as far, so what is visible depends on the facing.
+pub fn turn_pointer(
+    pointer: &mut Option<(HexPointer, VisibilityController)>,
+    turn: PointerTurn,
+    data: &StateData<'_, GameData<'_, '_>>,
+) -> bool {
+    if let Some((pointer, _)) = pointer {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        turn(pointer, data, &world);
+        true
+    } else {
+        false
+    }
+}
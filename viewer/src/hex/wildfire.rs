@@ -0,0 +1,308 @@
+use crate::{
+    assets::Color, input::get_key_and_modifiers, pacing::StepPacer, world::RhombusViewerWorld,
+};
+use amethyst::{
+    assets::Handle,
+    core::{math::Vector3, Transform},
+    ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    renderer::Material,
+    winit::VirtualKeyCode,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector,
+    shape::cubic_range::CubicRangeShape,
+    storage::{hash::RectHashStorage, set::HexSet},
+};
+use rhombus_mapgen::cellular::{
+    automaton::{Automaton, CellState, NeighborCounts},
+    Border, CellularRules, CellularWorld, HexState, RuleCursor, RuleStep,
+};
+use std::sync::Arc;
+
+const SEED: u64 = 0x4669_7265;
+const MAP_RADIUS: isize = 30;
+const WALL_RATIO: f32 = 0.45;
+const STEP_MILLIS: u64 = 100;
+// The chance for a tree to catch fire from each burning neighbor.
+const SPREAD: f64 = 0.6;
+// The chance for a tree to be struck by lightning, every generation.
+const LIGHTNING: f64 = 0.000_02;
+// The chance for a tree to grow again on ash, every generation.
+const REGROWTH: f64 = 0.01;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Forest {
+    Rock,
+    Tree,
+    Fire,
+    Ash,
+}
+
+impl CellState for Forest {
+    const COUNT: usize = 4;
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Forest {
+    fn color(self) -> Color {
+        match self {
+            Forest::Rock => Color::White,
+            Forest::Tree => Color::Green,
+            Forest::Fire => Color::Red,
+            Forest::Ash => Color::Black,
+        }
+    }
+
+    fn height(self) -> f32 {
+        match self {
+            Forest::Rock => 0.5,
+            Forest::Tree => 0.3,
+            Forest::Fire => 0.4,
+            Forest::Ash => 0.05,
+        }
+    }
+}
+
+// A draw in [0, 1) which only depends on the seed, the generation and the position, so that the
+// simulation does not depend on the threads of the automaton.
+fn chance(seed: u64, generation: usize, position: AxialVector) -> f64 {
+    let mut x = seed
+        ^ (generation as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (position.q() as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+        ^ (position.r() as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+    // SplitMix64 finalizer.
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Fire spreads through the trees, leaves ash behind, and trees grow again on the ash. Lightning
+// now and then starts new fires.
+fn burn(
+    seed: u64,
+) -> impl Fn(AxialVector, usize, Forest, &NeighborCounts<Forest>) -> Forest + Sync {
+    move |position, generation, state, counts| {
+        let draw = chance(seed, generation, position);
+        match state {
+            Forest::Tree => {
+                let fires = i32::from(counts.get(Forest::Fire));
+                if draw < 1.0 - (1.0 - SPREAD).powi(fires) + LIGHTNING {
+                    Forest::Fire
+                } else {
+                    Forest::Tree
+                }
+            }
+            Forest::Fire => Forest::Ash,
+            Forest::Ash if draw < REGROWTH => Forest::Tree,
+            state => state,
+        }
+    }
+}
+
+// A wildfire simulated on caves generated by the cellular automaton, the rocks being the walls:
+// the generation subsystem also runs the game world. The automaton steps at the pace of the
+// StepPacer (`+`, `-`, `P` and `.` as in the generators) whatever the rendering, which then only
+// updates the hexes changed by the steps of the frame. `L` strikes a tree, `N` grows another
+// forest.
+pub struct HexWildfireDemo {
+    seed: u64,
+    pacer: StepPacer,
+    automaton: Option<Automaton<Forest>>,
+    entities: RectHashStorage<Entity>,
+    changed: HexSet,
+    rng: StdRng,
+}
+
+impl HexWildfireDemo {
+    pub fn new() -> Self {
+        Self {
+            seed: SEED,
+            pacer: StepPacer::new(STEP_MILLIS),
+            automaton: None,
+            entities: RectHashStorage::new(),
+            changed: HexSet::new(),
+            rng: StdRng::seed_from_u64(SEED),
+        }
+    }
+
+    fn generate(&mut self, data: &mut StateData<'_, GameData<'_, '_>>, world: &RhombusViewerWorld) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+        let mut caves = CellularWorld::<()>::new();
+        caves.reset(
+            CubicRangeShape::new(
+                (-MAP_RADIUS, MAP_RADIUS),
+                (-MAP_RADIUS, MAP_RADIUS),
+                (-MAP_RADIUS, MAP_RADIUS),
+            ),
+            12,
+            WALL_RATIO,
+            Border::default(),
+            &mut self.rng,
+            |_| (),
+        );
+        let rules = CellularRules::default();
+        let mut cursor = RuleCursor::default();
+        while caves.run_rules_step(&rules, &mut cursor, |_| ()) != RuleStep::Done {}
+
+        let mut forest = RectHashStorage::new();
+        for (position, state) in caves.states().iter() {
+            let state = match state {
+                HexState::Open => Forest::Tree,
+                _ => Forest::Rock,
+            };
+            forest.insert(position, state);
+            let mut transform = Transform::default();
+            transform.set_scale(Vector3::new(0.9, state.height(), 0.9));
+            world.transform_hex((position, state.height()).into(), &mut transform);
+            let entity = data
+                .world
+                .create_entity()
+                .with(world.assets.hex_handle.clone())
+                .with(world.assets.color_data[&state.color()].light.clone())
+                .with(transform)
+                .build();
+            self.entities.insert(position, entity);
+        }
+        self.automaton = Some(Automaton::new(forest));
+    }
+
+    fn strike(&mut self) {
+        let automaton = match &mut self.automaton {
+            Some(automaton) => automaton,
+            None => return,
+        };
+        if let Some(position) = automaton
+            .hexes()
+            .random_open_position(&mut self.rng, |_, state| *state == Forest::Tree)
+        {
+            if let Some(state) = automaton.hexes_mut().get_mut(position) {
+                *state = Forest::Fire;
+                self.changed.insert(position);
+            }
+        }
+    }
+
+    // Only the hexes changed since the last frame.
+    fn update_entities(
+        &mut self,
+        data: &StateData<'_, GameData<'_, '_>>,
+        world: &RhombusViewerWorld,
+    ) {
+        let automaton = match &self.automaton {
+            Some(automaton) => automaton,
+            None => return,
+        };
+        let mut transforms = data.world.write_storage::<Transform>();
+        let mut materials = data.world.write_storage::<Handle<Material>>();
+        for position in self.changed.iter() {
+            let (state, entity) =
+                match (automaton.hexes().get(position), self.entities.get(position)) {
+                    (Some(state), Some(entity)) => (*state, *entity),
+                    _ => continue,
+                };
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.translation_mut()[1] = state.height();
+                transform.scale_mut()[1] = state.height();
+            }
+            if let Some(material) = materials.get_mut(entity) {
+                *material = world.assets.color_data[&state.color()].light.clone();
+            }
+        }
+        self.changed.clear();
+    }
+
+    fn status(&self) -> String {
+        let automaton = match &self.automaton {
+            Some(automaton) => automaton,
+            None => return String::new(),
+        };
+        let mut counts = [0; Forest::COUNT];
+        for state in automaton.hexes().hexes() {
+            counts[state.index()] += 1;
+        }
+        format!(
+            "seed {}, generation {}, {} trees, {} burning, {} ash",
+            self.seed,
+            automaton.generation(),
+            counts[Forest::Tree.index()],
+            counts[Forest::Fire.index()],
+            counts[Forest::Ash.index()],
+        )
+    }
+
+    fn clear(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
+        for entity in self.entities.hexes() {
+            data.world.delete_entity(*entity).expect("delete entity");
+        }
+        self.entities = RectHashStorage::new();
+        self.changed.clear();
+        self.automaton = None;
+    }
+}
+
+impl SimpleState for HexWildfireDemo {
+    fn on_start(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        world.set_camera_distance(&data, 200.0);
+        self.generate(&mut data, &world);
+        self.strike();
+        self.pacer.start(&data);
+    }
+
+    fn on_stop(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
+        self.pacer.stop(&data);
+        self.clear(&mut data);
+    }
+
+    fn handle_event(
+        &mut self,
+        mut data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = event {
+            if self.pacer.handle_event(&data, &event) {
+                return Trans::None;
+            }
+            match get_key_and_modifiers(&event) {
+                Some((VirtualKeyCode::Escape, ElementState::Pressed, _)) => return Trans::Pop,
+                Some((VirtualKeyCode::L, ElementState::Pressed, _)) => self.strike(),
+                Some((VirtualKeyCode::N, ElementState::Pressed, _)) => {
+                    self.clear(&mut data);
+                    self.seed = self.seed.wrapping_add(1);
+                    let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+                    self.generate(&mut data, &world);
+                    self.strike();
+                    self.pacer.reset();
+                }
+                _ => {}
+            }
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = (*data.world.read_resource::<Arc<RhombusViewerWorld>>()).clone();
+        if let Some(automaton) = &mut self.automaton {
+            let transition = burn(self.seed);
+            self.pacer.start_frame(data);
+            while self.pacer.next_step() {
+                automaton.step(&transition);
+                self.changed.extend(automaton.changed().iter().copied());
+            }
+            self.pacer.end_frame(data);
+        }
+        self.update_entities(data, &world);
+        let status = self.status();
+        self.pacer.set_status(data, status);
+        Trans::None
+    }
+}
@@ -6,38 +6,91 @@ extern crate derive_new;
 pub mod assets;
 pub mod dispose;
 pub mod dodec;
+#[cfg(feature = "headless")]
+pub mod headless;
 pub mod hex;
 pub mod input;
-pub mod snake;
+pub mod mesh;
+pub mod pacing;
+pub mod playlist;
+pub mod seed;
+pub mod sound;
 pub mod systems;
+pub mod theme;
 pub mod world;
 
+#[cfg(feature = "headless")]
+use crate::headless::HeadlessBundle;
+#[cfg(feature = "sound")]
+use crate::sound::{SoundCues, SoundSettings};
+#[cfg(not(feature = "headless"))]
+use crate::systems::{event_log::EventLogSystemDesc, metrics::MetricsOverlaySystemDesc};
 use crate::{
-    assets::{Color, ColorData, RhombusViewerAssets},
+    assets::{Color, ColorData, RhombusViewerAssets, Translucent},
     dodec::{directions::DodecDirectionsDemo, snake::DodecSnakeDemo, sphere::DodecSphereDemo},
     hex::{
-        bumpy_builder::HexBumpyBuilderDemo, cellular::builder::HexCellularBuilder,
-        cubic_range_shape::HexCubicRangeShapeDemo, custom::builder::HexCustomBuilder,
-        directions::HexDirectionsDemo, flat_builder::HexFlatBuilderDemo, new_area_edge_renderer,
-        new_edge_renderer, new_multi_renderer, ring::HexRingDemo,
-        rooms_and_mazes::builder::HexRoomsAndMazesBuilder, snake::HexSnakeDemo,
+        benchmark::{BenchmarkSettings, HexBenchmarkDemo},
+        bumpy_builder::HexBumpyBuilderDemo,
+        cellular::builder::{CellularRulesSettings, HexCellularBuilder},
+        crowd::HexCrowdDemo,
+        cubic_range_shape::HexCubicRangeShapeDemo,
+        custom::{builder::HexCustomBuilder, scenario::CustomScenariosSettings},
+        directions::HexDirectionsDemo,
+        editor::{HexEditorDemo, MAP_PATH},
+        flat_builder::builder::HexFlatBuilderDemo,
+        inspect::HexTag,
+        new_area_edge_renderer, new_edge_renderer, new_multi_renderer,
+        pointer::{load_pointer_repeat_settings, PointerSettings},
+        ring::HexRingDemo,
+        rooms_and_mazes::builder::{HexRoomsAndMazesBuilder, RoomTemplatesSettings},
+        scale::WorldScale,
+        snake::HexSnakeDemo,
+        traversal::HexTraversalDemo,
+        visibility::SightSettings,
+        wildfire::HexWildfireDemo,
     },
+    input::get_key_and_modifiers,
+    mesh::{dodec_vertices, hex_ring_vertices, HexPrism},
+    pacing::{set_window_title, AnimationSettings},
+    playlist::{load_playlist, Playlist},
+    seed::SeedSettings,
     systems::{
+        camera_aspect::CameraAspectSystem,
         camera_distance::CameraDistanceSystemDesc,
-        follow_me::{FollowMeSystem, FollowMeTag, FollowMyRotationSystem, FollowMyRotationTag},
+        culling::ChunkCullingSystem,
+        event_log::EventLog,
+        file_watch::FileWatchSystem,
+        follow_me::{
+            load_follow_settings, FollowMeSettings, FollowMeSystem, FollowMeTag,
+            FollowMyRotationSystem, FollowMyRotationTag,
+        },
+        grid_overlay::{GridOverlaySystem, GridSettings},
+        highlight::HighlightSystem,
+        hot_reload::{DisplayReloadSystem, ThemeReloadSystem},
+        lighting::{LightingConfig, LightingSystem, SunTag},
+        metrics::{Metrics, MetricsSystem},
+        path_preview::PathPreviewSystem,
+        spatial::EntitySpatialMapSystemDesc,
+        tween::TweenSystem,
     },
+    theme::{load_theme, TerrainType, ThemeLights, ThemeSettings},
     world::RhombusViewerWorld,
 };
+#[cfg(feature = "sound")]
+use amethyst::audio::AudioBundle;
 use amethyst::{
     assets::{AssetLoaderSystemData, ProgressCounter},
-    controls::{ArcBallControlBundle, ArcBallControlTag, FlyControlTag},
+    config::Config,
+    controls::{ArcBallControlTag, FlyControlTag},
     core::{
+        frame_limiter::FrameRateLimitStrategy,
         math::Vector3,
+        shrev::EventChannel,
         timing::Time,
         transform::{Parent, Transform, TransformBundle},
     },
     ecs::prelude::*,
-    input::{is_key_down, InputBundle, StringBindings},
+    input::{is_key_down, ElementState, InputBundle, StringBindings},
     prelude::*,
     renderer::{
         camera::Camera,
@@ -45,20 +98,39 @@ use amethyst::{
         formats::mesh::ObjFormat,
         light::{DirectionalLight, Light},
         palette::{Srgb, Srgba},
-        plugins::{RenderDebugLines, RenderToWindow},
         rendy::{
             mesh::{Normal, Position, TexCoord},
             texture::palette::load_from_srgba,
         },
         shape::Shape,
-        types::{DefaultBackend, Mesh, Texture},
-        Material, MaterialDefaults, RenderShaded3D, RenderingBundle,
+        types::{Mesh, Texture},
+        Material, MaterialDefaults,
     },
     utils::{application_root_dir, fps_counter::FpsCounterBundle},
+    window::{DisplayConfig, ScreenDimensions, Window},
     winit::VirtualKeyCode,
-    Application, Error, GameDataBuilder, LoggerConfig, SimpleState, StateEvent,
+    Application, Error, GameDataBuilder, LogLevelFilter, LoggerConfig, SimpleState, StateEvent,
+    TransEvent,
+};
+#[cfg(not(feature = "headless"))]
+use amethyst::{
+    controls::ArcBallControlBundle,
+    renderer::{
+        plugins::{RenderDebugLines, RenderToWindow},
+        types::DefaultBackend,
+        RenderShaded3D, RenderingBundle,
+    },
+    ui::{RenderUi, UiBundle},
+};
+use rhombus_core::hex::{coordinates::offset::HexOrientation, float::layout::HexLayout};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
-use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf, sync::Arc};
 use structopt::StructOpt;
 
 const LOGGER_CONFIG: &str = "config/logger.yaml";
@@ -66,7 +138,20 @@ const LOGGER_CONFIG: &str = "config/logger.yaml";
 const WIDTH: u32 = 640;
 const HEIGHT: u32 = 480;
 
-const MAX_ROTATED_DEMOS: usize = 6;
+const FRAME_CAP_FPS: u32 = 60;
+
+// The number keys start the demos of the playlist.
+const DEMO_KEYS: [VirtualKeyCode; 9] = [
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+];
 
 const DEMO_HEX_DIRECTIONS: usize = 0;
 const DEMO_HEX_RING: usize = 1;
@@ -76,45 +161,83 @@ const DEMO_DODEC_SPHERE: usize = 4;
 const DEMO_DODEC_SNAKE: usize = 5;
 
 const HEX_CUBIC_RANGE_SHAPE: usize = 10;
+const HEX_TRAVERSAL: usize = 11;
 
 const HEX_FLAT_BUILDER: usize = 100;
 const HEX_BUMPY_BUILDER: usize = 101;
 const HEX_CELLULAR_BUILDER: usize = 102;
 const HEX_CUSTOM_BUILDER: usize = 103;
+const HEX_EDITOR: usize = 104;
 const HEX_RAM_BUILDER: usize = 200;
+const HEX_BENCHMARK: usize = 300;
+const HEX_CROWD: usize = 301;
+const HEX_WILDFIRE: usize = 302;
 
 enum RhombusViewerAnimation {
     Fixed { demo_num: usize },
-    Rotating { demo_num: usize },
+    // Goes through the demos of the playlist, index is the next one.
+    Rotating { index: usize, paused: bool },
 }
 
 struct RhombusViewer {
     animation: RhombusViewerAnimation,
+    playlist: Playlist,
     last_resume_time: f64,
+    // When the running demo of the playlist started and how long it may run.
+    running_demo: Option<(f64, Option<f64>)>,
+    // The --speed one, while a demo of the playlist runs at its own speed.
+    default_speed: Option<f64>,
     progress_counter: ProgressCounter,
     origin: Option<Entity>,
     follower: Option<Entity>,
-    draw_axes: bool,
+    // None when the axes are not drawn.
+    axes_extent: Option<f32>,
+    fullscreen: bool,
+    animation_settings: Option<AnimationSettings>,
+    // The title of the window without the carousel label.
+    window_title: String,
+    // The last title set by the carousel.
+    label: String,
+    layout: HexLayout,
+    // Counted down once a demo started, then the viewer quits.
+    remaining_frames: Option<u64>,
+    demo_started: bool,
 }
 
 impl RhombusViewer {
-    fn new(demo_num: Option<usize>, draw_axes: bool) -> Self {
-        let first_demo_num = demo_num.unwrap_or(0);
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        demo_num: Option<usize>,
+        playlist: Playlist,
+        axes_extent: Option<f32>,
+        fullscreen: bool,
+        animation_settings: AnimationSettings,
+        layout: HexLayout,
+        frames: Option<u64>,
+    ) -> Self {
         Self {
-            animation: if demo_num.is_some() {
-                RhombusViewerAnimation::Fixed {
-                    demo_num: first_demo_num,
-                }
-            } else {
-                RhombusViewerAnimation::Rotating {
-                    demo_num: first_demo_num,
-                }
+            animation: match demo_num {
+                Some(demo_num) => RhombusViewerAnimation::Fixed { demo_num },
+                None => RhombusViewerAnimation::Rotating {
+                    index: 0,
+                    paused: false,
+                },
             },
+            playlist,
             last_resume_time: 0.0,
+            running_demo: None,
+            default_speed: None,
             progress_counter: ProgressCounter::default(),
             origin: None,
             follower: None,
-            draw_axes,
+            axes_extent,
+            fullscreen,
+            window_title: animation_settings.window_title.clone(),
+            label: String::new(),
+            animation_settings: Some(animation_settings),
+            layout,
+            remaining_frames: frames,
+            demo_started: false,
         }
     }
 
@@ -129,6 +252,8 @@ impl RhombusViewer {
             DEMO_DODEC_SNAKE => Box::new(DodecSnakeDemo::new()),
             // Cubic range shape
             HEX_CUBIC_RANGE_SHAPE => Box::new(HexCubicRangeShapeDemo::new()),
+            // Iterators
+            HEX_TRAVERSAL => Box::new(HexTraversalDemo::new()),
             // Flat hex builders
             HEX_FLAT_BUILDER => Box::new(HexFlatBuilderDemo::new()),
             // Bumpy hex builders
@@ -140,12 +265,45 @@ impl RhombusViewer {
                 new_edge_renderer(),
                 new_area_edge_renderer(),
             ))),
+            // Map editor
+            HEX_EDITOR => Box::new(HexEditorDemo::new()),
             // Rooms and mazes hex builder
             HEX_RAM_BUILDER => Box::new(HexRoomsAndMazesBuilder::new(new_area_edge_renderer())),
+            // Rendering benchmark
+            HEX_BENCHMARK => Box::new(HexBenchmarkDemo::new()),
+            // Crowd streaming down a flow field
+            HEX_CROWD => Box::new(HexCrowdDemo::new()),
+            // Fire spreading through a forest, simulated by a multi-state automaton
+            HEX_WILDFIRE => Box::new(HexWildfireDemo::new()),
             _ => unimplemented!(),
         };
         Trans::Push(new_state)
     }
+
+    // In the window title, and while a demo runs in the title the demos restore after showing their
+    // own messages.
+    fn show_label(&mut self, data: &StateData<'_, GameData<'_, '_>>, label: &str, in_demo: bool) {
+        let title = format!("{} - {}", self.window_title, label);
+        if title == self.label {
+            return;
+        }
+        set_window_title(data.world, &title);
+        data.world
+            .write_resource::<AnimationSettings>()
+            .window_title = if in_demo {
+            title.clone()
+        } else {
+            self.window_title.clone()
+        };
+        self.label = title;
+    }
+}
+
+// The demo numbers by their names on the command line.
+fn demo_num(name: &str) -> Option<usize> {
+    DemoOption::from_iter_safe(&["rhombus_viewer", name])
+        .ok()
+        .map(|demo| demo as usize)
 }
 
 impl SimpleState for RhombusViewer {
@@ -154,21 +312,31 @@ impl SimpleState for RhombusViewer {
             .world
             .read_resource::<Time>()
             .absolute_real_time_seconds();
-        if self.draw_axes {
+        if self.fullscreen {
+            let window = data.world.read_resource::<Window>();
+            window.set_fullscreen(Some(window.get_current_monitor()));
+        }
+        if let Some(animation_settings) = self.animation_settings.take() {
+            data.world.insert(animation_settings);
+        }
+        data.world.insert(WorldScale::default());
+        data.world.insert(PointerSettings::default());
+        data.world.register::<HexTag>();
+        if let Some(extent) = self.axes_extent {
             let mut debug_lines_component = DebugLinesComponent::with_capacity(100);
             debug_lines_component.add_direction(
                 [-1.0, 0.0, 0.0].into(),
-                [5.0, 0.0, 0.0].into(),
+                [extent, 0.0, 0.0].into(),
                 Srgba::new(0.5, 0.0, 0.0, 1.0),
             );
             debug_lines_component.add_direction(
                 [0.0, -1.0, 0.0].into(),
-                [0.0, 5.0, 0.0].into(),
+                [0.0, extent, 0.0].into(),
                 Srgba::new(0.0, 0.5, 0.0, 1.0),
             );
             debug_lines_component.add_direction(
                 [0.0, 0.0, -1.0].into(),
-                [0.0, 0.0, 5.0].into(),
+                [0.0, 0.0, extent].into(),
                 Srgba::new(0.0, 0.0, 0.5, 1.0),
             );
             data.world
@@ -186,14 +354,34 @@ impl SimpleState for RhombusViewer {
                 )
             });
             let hex_handle = data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
-                loader.load("mesh/hex.obj", ObjFormat, &mut self.progress_counter)
+                loader.load_from_data(
+                    HexPrism::default().vertices().into(),
+                    &mut self.progress_counter,
+                )
+            });
+            let block_handle = data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
+                loader.load_from_data(
+                    HexPrism {
+                        bevel: 0.1,
+                        ..Default::default()
+                    }
+                    .vertices()
+                    .into(),
+                    &mut self.progress_counter,
+                )
             });
             let dodec_handle = data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
-                loader.load("mesh/dodec.obj", ObjFormat, &mut self.progress_counter)
+                loader.load_from_data(dodec_vertices().into(), &mut self.progress_counter)
             });
             let pointer_handle = data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
                 loader.load("mesh/pointer.obj", ObjFormat, &mut self.progress_counter)
             });
+            let ramp_handle = data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
+                loader.load("mesh/ramp.obj", ObjFormat, &mut self.progress_counter)
+            });
+            let ring_handle = data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
+                loader.load_from_data(hex_ring_vertices(0.8).into(), &mut self.progress_counter)
+            });
             let mat_defaults = data.world.read_resource::<MaterialDefaults>().0.clone();
             let color_data = [
                 (Color::Black, (0.0, 0.0, 0.0, 1.0), (0.0, 0.0, 0.0, 1.0)),
@@ -235,16 +423,140 @@ impl SimpleState for RhombusViewer {
             })
             .collect::<HashMap<_, _>>();
 
+            let theme = load_theme(&data.world.read_resource::<ThemeSettings>().path);
+            data.world.insert(theme.lights.clone());
+            let mut load_atlas = |brightness: f32| {
+                let texture = data
+                    .world
+                    .exec(|loader: AssetLoaderSystemData<'_, Texture>| {
+                        loader.load_from_data(
+                            theme.atlas_texture(brightness).into(),
+                            &mut self.progress_counter,
+                        )
+                    });
+                data.world
+                    .exec(|loader: AssetLoaderSystemData<'_, Material>| {
+                        loader.load_from_data(
+                            Material {
+                                albedo: texture,
+                                ..mat_defaults.clone()
+                            },
+                            &mut self.progress_counter,
+                        )
+                    })
+            };
+            let atlas = ColorData {
+                light: load_atlas(1.0),
+                dark: load_atlas(0.5),
+            };
+            let terrain_handles = TerrainType::ALL
+                .iter()
+                .map(|terrain| {
+                    let handle = data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
+                        loader.load_from_data(
+                            HexPrism::default()
+                                .vertices()
+                                .with_atlas_tile(theme.tile(*terrain), theme.tiles.len())
+                                .into(),
+                            &mut self.progress_counter,
+                        )
+                    });
+                    (*terrain, handle)
+                })
+                .collect::<HashMap<_, _>>();
+            let wall_handles = theme
+                .wall_meshes()
+                .into_iter()
+                .map(|vertices| {
+                    data.world.exec(|loader: AssetLoaderSystemData<'_, Mesh>| {
+                        loader.load_from_data(vertices.into(), &mut self.progress_counter)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let translucent_materials = [
+                (Translucent::Water, (0.1, 0.35, 0.8, 0.5)),
+                (Translucent::Fog, (0.05, 0.05, 0.08, 0.7)),
+                (Translucent::Ghost, (0.7, 0.85, 1.0, 0.3)),
+            ]
+            .iter()
+            .map(|(translucent, rgba)| {
+                let texture = data
+                    .world
+                    .exec(|loader: AssetLoaderSystemData<'_, Texture>| {
+                        loader.load_from_data(
+                            load_from_srgba(Srgba::new(rgba.0, rgba.1, rgba.2, rgba.3)).into(),
+                            &mut self.progress_counter,
+                        )
+                    });
+                let material = data
+                    .world
+                    .exec(|loader: AssetLoaderSystemData<'_, Material>| {
+                        loader.load_from_data(
+                            Material {
+                                albedo: texture,
+                                ..mat_defaults.clone()
+                            },
+                            &mut self.progress_counter,
+                        )
+                    });
+                (*translucent, material)
+            })
+            .collect::<HashMap<_, _>>();
+
+            let highlight_material = {
+                let texture = data
+                    .world
+                    .exec(|loader: AssetLoaderSystemData<'_, Texture>| {
+                        loader.load_from_data(
+                            load_from_srgba(Srgba::new(1.0, 0.85, 0.3, 1.0)).into(),
+                            &mut self.progress_counter,
+                        )
+                    });
+                data.world
+                    .exec(|loader: AssetLoaderSystemData<'_, Material>| {
+                        loader.load_from_data(
+                            Material {
+                                albedo: texture.clone(),
+                                emission: texture,
+                                ..mat_defaults
+                            },
+                            &mut self.progress_counter,
+                        )
+                    })
+            };
+
             RhombusViewerAssets {
                 square_handle,
                 hex_handle,
+                block_handle,
                 dodec_handle,
                 pointer_handle,
+                ramp_handle,
+                ring_handle,
                 color_data,
+                atlas,
+                terrain_handles,
+                wall_handles,
+                translucent_materials,
+                highlight_material,
             }
         };
 
-        for (intensity, direction_y) in [(0.3, -1.0), (0.15, 1.0)].iter() {
+        #[cfg(feature = "sound")]
+        {
+            let sound_settings = *data.world.read_resource::<SoundSettings>();
+            let sound_cues =
+                SoundCues::load(data.world, sound_settings, &mut self.progress_counter);
+            data.world.insert(sound_cues);
+        }
+
+        let directional_lights = data
+            .world
+            .read_resource::<ThemeLights>()
+            .directional
+            .clone();
+        for (intensity, direction_y) in directional_lights.iter() {
             let mut light = DirectionalLight::default();
             light.color = Srgb::new(1.0, 1.0, 1.0);
             light.intensity = *intensity;
@@ -255,11 +567,17 @@ impl SimpleState for RhombusViewer {
 
             light_transform.set_translation_xyz(0.0, 10.0, 0.0);
 
-            data.world
-                .create_entity()
-                .with(light)
-                .with(light_transform)
-                .build();
+            let builder = data.world.create_entity().with(light).with(light_transform);
+            // The one shining downwards follows the day.
+            if *direction_y < 0.0 {
+                builder
+                    .with(SunTag {
+                        intensity: *intensity,
+                    })
+                    .build();
+            } else {
+                builder.build();
+            }
         }
 
         // Origin with default orientation
@@ -285,6 +603,7 @@ impl SimpleState for RhombusViewer {
         let mut follower_transform = Transform::default();
         //follower_transform.set_scale(Vector3::new(0.2, 0.05, 0.2));
         follower_transform.prepend_rotation_y_axis(std::f32::consts::PI / 2.0);
+        let translation_ratio = data.world.read_resource::<FollowMeSettings>().translation;
         let follower = data
             .world
             .create_entity()
@@ -292,7 +611,7 @@ impl SimpleState for RhombusViewer {
             //.with(assets.pointer_handle.clone())
             //.with(assets.color_data[&Color::Magenta].light.clone())
             .with(FollowMeTag {
-                target: Some((origin, 0.1)),
+                target: Some((origin, translation_ratio)),
                 rotation_target: None,
             })
             .build();
@@ -321,14 +640,15 @@ impl SimpleState for RhombusViewer {
             origin_camera,
             follower,
             follower_camera,
+            self.layout,
         ));
         data.world.insert(world);
 
-        let camera = Camera::perspective(
-            WIDTH as f32 / HEIGHT as f32,
-            std::f32::consts::FRAC_PI_4,
-            0.1,
-        );
+        let aspect_ratio = data
+            .world
+            .read_resource::<ScreenDimensions>()
+            .aspect_ratio();
+        let camera = Camera::perspective(aspect_ratio, std::f32::consts::FRAC_PI_4, 0.1);
 
         data.world
             .create_entity()
@@ -359,8 +679,14 @@ impl SimpleState for RhombusViewer {
             .world
             .read_resource::<Time>()
             .absolute_real_time_seconds();
+        self.running_demo = None;
+        if let Some(speed) = self.default_speed.take() {
+            data.world.write_resource::<AnimationSettings>().speed = speed;
+        }
     }
 
+    // Between the demos of the carousel, Space pauses it, Left and Right choose the next demo and
+    // the number keys start one right away.
     fn handle_event(
         &mut self,
         _: StateData<'_, GameData<'_, '_>>,
@@ -368,13 +694,34 @@ impl SimpleState for RhombusViewer {
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = event {
             if is_key_down(&event, VirtualKeyCode::Escape) {
-                Trans::Quit
-            } else {
-                Trans::None
+                return Trans::Quit;
+            }
+            let demos = self.playlist.demos.len();
+            if let RhombusViewerAnimation::Rotating { index, paused } = &mut self.animation {
+                match get_key_and_modifiers(&event) {
+                    Some((VirtualKeyCode::Space, ElementState::Pressed, _)) => {
+                        *paused = !*paused;
+                    }
+                    Some((VirtualKeyCode::Left, ElementState::Pressed, _)) => {
+                        *index = (*index + demos - 1) % demos;
+                    }
+                    Some((VirtualKeyCode::Right, ElementState::Pressed, _)) => {
+                        *index = (*index + 1) % demos;
+                    }
+                    Some((key, ElementState::Pressed, _)) => {
+                        if let Some(num) =
+                            DEMO_KEYS.iter().take(demos).position(|digit| *digit == key)
+                        {
+                            *index = num;
+                            *paused = false;
+                            self.last_resume_time = std::f64::NEG_INFINITY;
+                        }
+                    }
+                    _ => {}
+                }
             }
-        } else {
-            Trans::None
         }
+        Trans::None
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
@@ -382,26 +729,89 @@ impl SimpleState for RhombusViewer {
             .world
             .read_resource::<Time>()
             .absolute_real_time_seconds();
-        if !self.progress_counter.is_complete() {
+        // Without a renderer, nothing processes the meshes and the textures.
+        if !cfg!(feature = "headless") && !self.progress_counter.is_complete() {
             return Trans::None;
         }
-        if time - self.last_resume_time > 1.0 {
-            match &mut self.animation {
-                RhombusViewerAnimation::Fixed { demo_num } => Self::transition(*demo_num),
-                RhombusViewerAnimation::Rotating { demo_num } => {
-                    let trans = Self::transition(*demo_num);
-                    let next_demo_num = (*demo_num + 1) % MAX_ROTATED_DEMOS;
-                    *demo_num = next_demo_num;
-                    trans
+        match self.animation {
+            RhombusViewerAnimation::Fixed { demo_num } => {
+                if time - self.last_resume_time > self.playlist.restart_delay {
+                    self.demo_started = true;
+                    Self::transition(demo_num)
+                } else {
+                    Trans::None
+                }
+            }
+            RhombusViewerAnimation::Rotating { index, paused } => {
+                // The countdown starts over on resume.
+                if paused {
+                    self.last_resume_time = time;
+                }
+                let remaining = self.playlist.delay - (time - self.last_resume_time);
+                if paused {
+                    let label = format!("next: {}, paused", self.playlist.label(index));
+                    self.show_label(data, &label, false);
+                    Trans::None
+                } else if remaining > 0.0 {
+                    let label = format!(
+                        "next: {} in {}s",
+                        self.playlist.label(index),
+                        remaining.ceil()
+                    );
+                    self.show_label(data, &label, false);
+                    Trans::None
+                } else {
+                    let label = self.playlist.label(index);
+                    self.show_label(data, &label, true);
+                    let demo = self.playlist.demos[index].clone();
+                    if let Some(speed) = demo.speed {
+                        let mut settings = data.world.write_resource::<AnimationSettings>();
+                        self.default_speed = Some(settings.speed);
+                        settings.speed = speed;
+                    }
+                    self.running_demo = Some((time, demo.duration));
+                    self.demo_started = true;
+                    self.animation = RhombusViewerAnimation::Rotating {
+                        index: (index + 1) % self.playlist.demos.len(),
+                        paused,
+                    };
+                    Self::transition(demo_num(&demo.demo).expect("playlist demo"))
                 }
             }
-        } else {
-            Trans::None
+        }
+    }
+
+    // Called under the demos too.
+    fn shadow_update(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some((start, Some(duration))) = self.running_demo {
+            let time = data
+                .world
+                .read_resource::<Time>()
+                .absolute_real_time_seconds();
+            if time - start > duration {
+                data.world
+                    .write_resource::<EventChannel<TransEvent<GameData<'static, 'static>, StateEvent>>>()
+                    .single_write(Box::new(|| Trans::Pop));
+                self.running_demo = None;
+            }
+        }
+        match &mut self.remaining_frames {
+            Some(0) => {
+                data.world
+                    .write_resource::<EventChannel<TransEvent<GameData<'static, 'static>, StateEvent>>>()
+                    .single_write(Box::new(|| Trans::Quit));
+                self.remaining_frames = None;
+            }
+            Some(remaining) if self.demo_started => *remaining -= 1,
+            _ => {}
         }
     }
 }
 
-fn logger_setup(logger_config_path: Option<PathBuf>) -> Result<(), Error> {
+fn logger_setup(
+    logger_config_path: Option<PathBuf>,
+    log_level: Option<LogLevelFilter>,
+) -> Result<(), Error> {
     let is_user_specified = logger_config_path.is_some();
 
     // If the user specified a logger configuration path, use that.
@@ -414,29 +824,43 @@ fn logger_setup(logger_config_path: Option<PathBuf>) -> Result<(), Error> {
         logger_config_path
     };
 
-    let logger_config: LoggerConfig = if logger_config_path.exists() {
-        let logger_file = File::open(&logger_config_path)?;
-        let mut logger_file_reader = BufReader::new(logger_file);
-        let logger_config = serde_yaml::from_reader(&mut logger_file_reader)?;
-
-        Ok(logger_config)
-    } else if is_user_specified {
-        let message = format!(
-            "Failed to read logger configuration file: `{}`.",
-            logger_config_path.display()
-        );
-        eprintln!("{}", message);
-
-        Err(Error::from_string(message))
+    // The logger is not started yet, warnings go to stderr. A broken logger configuration is not
+    // worth giving up on the viewer.
+    let mut logger_config = if logger_config_path.exists() {
+        read_logger_config(&logger_config_path).unwrap_or_else(|err| {
+            eprintln!(
+                "Failed to read logger configuration file: `{}` ({}), using the default \
+                 configuration.",
+                logger_config_path.display(),
+                err
+            );
+            LoggerConfig::default()
+        })
     } else {
-        Ok(LoggerConfig::default())
-    }?;
+        if is_user_specified {
+            eprintln!(
+                "Logger configuration file not found: `{}`, using the default configuration.",
+                logger_config_path.display()
+            );
+        }
+        LoggerConfig::default()
+    };
+
+    if let Some(log_level) = log_level {
+        logger_config.level_filter = log_level;
+    }
 
     amethyst::Logger::from_config(logger_config).start();
 
     Ok(())
 }
 
+fn read_logger_config(logger_config_path: &Path) -> Result<LoggerConfig, Error> {
+    let logger_file = File::open(logger_config_path)?;
+    let mut logger_file_reader = BufReader::new(logger_file);
+    Ok(serde_yaml::from_reader(&mut logger_file_reader)?)
+}
+
 #[derive(StructOpt, Debug, Clone, Copy)]
 enum DemoOption {
     #[structopt(name = "hex-directions")]
@@ -454,6 +878,8 @@ enum DemoOption {
 
     #[structopt(name = "hex-cubic-range-shape")]
     HexCubicRangeShape = HEX_CUBIC_RANGE_SHAPE as isize,
+    #[structopt(name = "hex-traversal")]
+    HexTraversal = HEX_TRAVERSAL as isize,
 
     #[structopt(name = "hex-flat-builder")]
     HexFlatBuilder = HEX_FLAT_BUILDER as isize,
@@ -463,14 +889,203 @@ enum DemoOption {
     HexCellularBuilder = HEX_CELLULAR_BUILDER as isize,
     #[structopt(name = "hex-custom-builder")]
     HexCustomBuilder = HEX_CUSTOM_BUILDER as isize,
+    #[structopt(name = "hex-editor")]
+    HexEditor = HEX_EDITOR as isize,
     #[structopt(name = "hex-ram-builder")]
     HexRamBuilder = HEX_RAM_BUILDER as isize,
+
+    #[structopt(name = "hex-crowd")]
+    HexCrowd = HEX_CROWD as isize,
+    #[structopt(name = "hex-wildfire")]
+    HexWildfire = HEX_WILDFIRE as isize,
+}
+
+#[derive(StructOpt, Debug)]
+struct DisplayOptions {
+    #[structopt(long, help = "Window width, overrides config/display.ron")]
+    width: Option<u32>,
+    #[structopt(long, help = "Window height, overrides config/display.ron")]
+    height: Option<u32>,
+    #[structopt(long, help = "Switch to fullscreen on the current monitor")]
+    fullscreen: bool,
+    #[structopt(long, help = "Cap the frame rate at 60 FPS")]
+    frame_cap: bool,
+    #[structopt(long, conflicts_with = "frame-cap", help = "Do not cap the frame rate")]
+    no_frame_cap: bool,
+}
+
+impl DisplayOptions {
+    fn merge_into(&self, display_config: &mut DisplayConfig) {
+        if self.width.is_some() || self.height.is_some() {
+            let (width, height) = display_config.dimensions.unwrap_or((WIDTH, HEIGHT));
+            display_config.dimensions =
+                Some((self.width.unwrap_or(width), self.height.unwrap_or(height)));
+        }
+    }
+
+    // A frame cap by the frame limiter, not vsync: Amethyst does not expose the swapchain present
+    // mode.
+    fn frame_limit(&self) -> Option<(FrameRateLimitStrategy, u32)> {
+        if self.frame_cap {
+            Some((
+                FrameRateLimitStrategy::SleepAndYield(Duration::from_millis(2)),
+                FRAME_CAP_FPS,
+            ))
+        } else if self.no_frame_cap {
+            Some((FrameRateLimitStrategy::Unlimited, 0))
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(StructOpt, Debug)]
 struct Options {
     #[structopt(subcommand)]
     demo: Option<DemoOption>,
+    #[structopt(flatten)]
+    display: DisplayOptions,
+    #[structopt(
+        long,
+        default_value = "1.0",
+        help = "Generator animation speed, can be changed with +/- (P pauses, . steps once)"
+    )]
+    speed: f64,
+    #[structopt(
+        long,
+        default_value = "4",
+        help = "Milliseconds of generator steps per frame at most, the animation slows down beyond"
+    )]
+    frame_budget: u64,
+    #[structopt(
+        long,
+        help = "Seed of the generators and the snakes when they start, random by default (N draws \
+                a new one, Shift+N starts over with the same one)"
+    )]
+    seed: Option<u64>,
+    #[structopt(long, help = "Render hexes flat-top instead of pointy-top")]
+    flat_top: bool,
+    #[structopt(
+        long,
+        default_value = "1.0",
+        help = "Distance between the center of a hex and its vertices"
+    )]
+    hex_size: f32,
+    #[structopt(
+        long,
+        default_value = "5.0",
+        help = "Length of the axes drawn in the rotating demos"
+    )]
+    axes_extent: f32,
+    #[structopt(
+        long,
+        default_value = "10",
+        help = "Radius of the grid overlay (D shows it in the demos with a pointer)"
+    )]
+    grid_radius: usize,
+    #[structopt(
+        long,
+        help = "How far the pointer sees in the generators, without limit by default"
+    )]
+    view_distance: Option<usize>,
+    #[structopt(
+        long,
+        help = "How far the pointer sees behind it in the generators, the view distance by default"
+    )]
+    rear_view_distance: Option<usize>,
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Seconds of a day and night cycle of the lights, none when 0 (Q pauses the lights)"
+    )]
+    day_length: f32,
+    #[structopt(
+        long,
+        default_value = "0.2",
+        help = "How much the torches flicker around their intensity"
+    )]
+    flicker: f32,
+    #[structopt(
+        long,
+        env = "RHOMBUS_LOGGER_CONFIG",
+        parse(from_os_str),
+        help = "Logger configuration file, defaults to config/logger.yaml"
+    )]
+    logger_config: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_LOG_LEVEL",
+        help = "Log level (off, error, warn, info, debug, trace), overrides the logger configuration"
+    )]
+    log_level: Option<LogLevelFilter>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_CELLULAR_RULES",
+        parse(from_os_str),
+        help = "Cellular automaton rules file, defaults to config/cellular.ron"
+    )]
+    cellular_rules: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_ROOM_TEMPLATES",
+        parse(from_os_str),
+        help = "Rooms and mazes room templates file, defaults to config/rooms.ron"
+    )]
+    room_templates: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_CUSTOM_SCENARIOS",
+        parse(from_os_str),
+        help = "Custom builder scenarios file, defaults to config/custom.ron"
+    )]
+    custom_scenarios: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_FOLLOW",
+        parse(from_os_str),
+        help = "Camera follow smoothness file, defaults to config/follow.ron"
+    )]
+    follow: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_POINTER",
+        parse(from_os_str),
+        help = "Pointer key repeat file, defaults to config/pointer.ron"
+    )]
+    pointer: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_THEME",
+        parse(from_os_str),
+        help = "Tiles theme file, defaults to config/theme.ron"
+    )]
+    theme: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "RHOMBUS_PLAYLIST",
+        parse(from_os_str),
+        help = "Demo carousel playlist file, defaults to config/playlist.ron"
+    )]
+    playlist: Option<PathBuf>,
+    #[structopt(
+        long,
+        value_name = "seconds",
+        help = "Walk through a large generated map for that many seconds instead of running a demo, \
+                then print the frame time statistics"
+    )]
+    benchmark: Option<f64>,
+    #[structopt(
+        long,
+        help = "Quit once the demo ran for that many frames, to check that it starts and stops \
+                cleanly"
+    )]
+    frames: Option<u64>,
+    #[cfg(feature = "sound")]
+    #[structopt(long, help = "Disable sound cues")]
+    no_sound: bool,
+    #[cfg(feature = "sound")]
+    #[structopt(long, default_value = "0.5", help = "Sound cues volume")]
+    volume: f32,
 }
 
 fn main() -> amethyst::Result<()> {
@@ -480,42 +1095,225 @@ fn main() -> amethyst::Result<()> {
     let display_config_path = app_root.join("config/display.ron");
     let assets_dir = app_root.join("assets/");
 
-    logger_setup(None)?;
+    logger_setup(options.logger_config.clone(), options.log_level)?;
+
+    let mut display_config = DisplayConfig::load(&display_config_path)?;
+    options.display.merge_into(&mut display_config);
+    let theme_path = options
+        .theme
+        .clone()
+        .unwrap_or_else(|| app_root.join("config/theme.ron"));
 
-    let draw_axes = options
-        .demo
-        .map(|demo| demo as usize <= MAX_ROTATED_DEMOS)
+    let animation_settings = AnimationSettings {
+        speed: options.speed,
+        frame_budget_millis: options.frame_budget,
+        window_title: display_config.title.clone(),
+    };
+
+    let demo_num = if options.benchmark.is_some() {
+        Some(HEX_BENCHMARK)
+    } else {
+        options.demo.map(|demo| demo as usize)
+    };
+    let draw_axes = demo_num
+        .map(|demo_num| demo_num <= DEMO_DODEC_SNAKE)
         .unwrap_or(true);
+    let axes_extent = if draw_axes {
+        Some(options.axes_extent)
+    } else {
+        None
+    };
 
     let game_data = GameDataBuilder::default()
         .with_bundle(FpsCounterBundle::default())?
         .with_bundle(TransformBundle::new())?
-        .with_bundle(InputBundle::<StringBindings>::new())?
-        .with_bundle(ArcBallControlBundle::<StringBindings>::new())?
-        .with(FollowMeSystem, "follow_me_system", &["arc_ball_rotation"])
+        .with_bundle(InputBundle::<StringBindings>::new())?;
+    // The arc ball grabs the cursor of the window.
+    #[cfg(not(feature = "headless"))]
+    let game_data = game_data.with_bundle(ArcBallControlBundle::<StringBindings>::new())?;
+    let follow_dependencies: &[&str] = if cfg!(feature = "headless") {
+        &["tween_system"]
+    } else {
+        &["arc_ball_rotation", "tween_system"]
+    };
+    let game_data = game_data
+        .with(TweenSystem, "tween_system", &[])
+        .with(FollowMeSystem, "follow_me_system", follow_dependencies)
         .with(
             FollowMyRotationSystem,
             "follow_my_rotation_system",
-            &["arc_ball_rotation"],
+            follow_dependencies,
         )
         .with_system_desc(
             CameraDistanceSystemDesc::default(),
             "camera_distance_system",
             &["input_system"],
         )
-        .with_bundle({
-            RenderingBundle::<DefaultBackend>::new()
-                .with_plugin(
-                    RenderToWindow::from_config_path(display_config_path)?
-                        .with_clear([0.02, 0.02, 0.02, 1.0]),
-                )
-                .with_plugin(RenderShaded3D::default())
-                .with_plugin(RenderDebugLines::default())
-        })?;
+        .with(CameraAspectSystem::default(), "camera_aspect_system", &[])
+        .with(HighlightSystem, "highlight_system", &[])
+        .with(LightingSystem::default(), "lighting_system", &[])
+        .with(GridOverlaySystem::default(), "grid_overlay_system", &[])
+        .with(
+            ChunkCullingSystem,
+            "chunk_culling_system",
+            &["transform_system"],
+        )
+        .with(PathPreviewSystem::default(), "path_preview_system", &[])
+        .with_system_desc(
+            EntitySpatialMapSystemDesc::default(),
+            "entity_spatial_map_system",
+            &[],
+        )
+        .with(
+            MetricsSystem,
+            "metrics_system",
+            &["entity_spatial_map_system"],
+        )
+        // Edited files are applied without restarting.
+        .with(
+            FileWatchSystem::new(&[
+                display_config_path.as_path(),
+                theme_path.as_path(),
+                Path::new(MAP_PATH),
+            ]),
+            "file_watch_system",
+            &[],
+        )
+        .with(
+            ThemeReloadSystem,
+            "theme_reload_system",
+            &["file_watch_system"],
+        )
+        .with(
+            DisplayReloadSystem::new(
+                display_config_path.clone(),
+                options.display.width.is_some() || options.display.height.is_some(),
+            ),
+            "display_reload_system",
+            &["file_watch_system"],
+        );
+    // The event log panel, the events are still logged without a window, and the performance
+    // overlay.
+    #[cfg(not(feature = "headless"))]
+    let game_data = game_data
+        .with_bundle(UiBundle::<StringBindings>::new())?
+        .with_system_desc(
+            EventLogSystemDesc::default(),
+            "event_log_system",
+            &["input_system"],
+        )
+        .with_system_desc(
+            MetricsOverlaySystemDesc::default(),
+            "metrics_overlay_system",
+            &["input_system", "metrics_system"],
+        );
+    #[cfg(feature = "headless")]
+    let game_data = game_data.with_bundle(HeadlessBundle::new(
+        display_config.dimensions.map_or(WIDTH, |(width, _)| width),
+        display_config
+            .dimensions
+            .map_or(HEIGHT, |(_, height)| height),
+    ))?;
+    #[cfg(not(feature = "headless"))]
+    let game_data = game_data.with_bundle({
+        RenderingBundle::<DefaultBackend>::new()
+            .with_plugin(
+                RenderToWindow::from_config(display_config).with_clear([0.02, 0.02, 0.02, 1.0]),
+            )
+            // Also draws the entities with the Transparent component, back to front after the
+            // opaque ones.
+            .with_plugin(RenderShaded3D::default())
+            .with_plugin(RenderDebugLines::default())
+            .with_plugin(RenderUi::default())
+    })?;
+    #[cfg(feature = "sound")]
+    let game_data = game_data.with_bundle(AudioBundle::default())?;
+
+    let playlist = load_playlist(
+        &options
+            .playlist
+            .clone()
+            .unwrap_or_else(|| app_root.join("config/playlist.ron")),
+        |name| self::demo_num(name).is_some(),
+    );
 
-    let app = RhombusViewer::new(options.demo.map(|demo| demo as usize), draw_axes);
+    let app = RhombusViewer::new(
+        demo_num,
+        playlist,
+        axes_extent,
+        options.display.fullscreen,
+        animation_settings,
+        HexLayout::new(
+            if options.flat_top {
+                HexOrientation::FlatTop
+            } else {
+                HexOrientation::PointyTop
+            },
+            options.hex_size,
+            [0.0, 0.0],
+        ),
+        options.frames,
+    );
 
-    let mut game = Application::new(assets_dir, app, game_data)?;
+    let mut game_builder = Application::build(assets_dir, app)?
+        .with_resource(CellularRulesSettings {
+            path: options
+                .cellular_rules
+                .clone()
+                .unwrap_or_else(|| app_root.join("config/cellular.ron")),
+        })
+        .with_resource(RoomTemplatesSettings {
+            path: options
+                .room_templates
+                .clone()
+                .unwrap_or_else(|| app_root.join("config/rooms.ron")),
+        })
+        .with_resource(CustomScenariosSettings {
+            path: options
+                .custom_scenarios
+                .clone()
+                .unwrap_or_else(|| app_root.join("config/custom.ron")),
+        })
+        .with_resource(load_follow_settings(
+            &options
+                .follow
+                .clone()
+                .unwrap_or_else(|| app_root.join("config/follow.ron")),
+        ))
+        .with_resource(load_pointer_repeat_settings(
+            &options
+                .pointer
+                .clone()
+                .unwrap_or_else(|| app_root.join("config/pointer.ron")),
+        ))
+        .with_resource(GridSettings::new(options.grid_radius))
+        .with_resource(SeedSettings { seed: options.seed })
+        .with_resource(LightingConfig {
+            day_length: options.day_length,
+            flicker: options.flicker,
+            paused: false,
+        })
+        .with_resource(SightSettings {
+            view_distance: options.view_distance,
+            rear_view_distance: options.rear_view_distance,
+        })
+        .with_resource(ThemeSettings { path: theme_path })
+        .with_resource(EventLog::default())
+        .with_resource(Metrics::default());
+    if let Some(seconds) = options.benchmark {
+        game_builder = game_builder.with_resource(BenchmarkSettings { seconds });
+    }
+    #[cfg(feature = "sound")]
+    {
+        game_builder = game_builder.with_resource(SoundSettings {
+            enabled: !options.no_sound,
+            volume: options.volume,
+        });
+    }
+    if let Some((strategy, max_fps)) = options.display.frame_limit() {
+        game_builder = game_builder.with_frame_limit(strategy, max_fps);
+    }
+    let mut game = game_builder.build(game_data)?;
 
     game.run();
 
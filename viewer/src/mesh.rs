@@ -0,0 +1,285 @@
+use amethyst::renderer::{
+    rendy::mesh::{MeshBuilder, Normal, Position, Tangent, TexCoord},
+    types::MeshData,
+};
+
+// Flat shaded triangle list with positions, normals, tangents and texture coordinates.
+#[derive(Default, Debug)]
+pub struct MeshVertices {
+    positions: Vec<Position>,
+    normals: Vec<Normal>,
+    tangents: Vec<Tangent>,
+    tex_coords: Vec<TexCoord>,
+}
+
+// Texture coordinates stay that far from the atlas tile borders so that filtering does not bleed
+// into the neighbor tiles.
+const ATLAS_TILE_MARGIN: f32 = 0.02;
+
+impl MeshVertices {
+    // Convex and planar polygon, counterclockwise when seen from the outside. Degenerate polygons
+    // (zero bevel for instance) are skipped. The whole texture is stretched over the polygon.
+    pub fn push_polygon(&mut self, vertices: &[[f32; 3]]) {
+        let normal = cross(sub(vertices[1], vertices[0]), sub(vertices[2], vertices[0]));
+        let (normal, tangent) = match (normalize(normal), normalize(sub(vertices[1], vertices[0])))
+        {
+            (Some(normal), Some(tangent)) => (normal, tangent),
+            _ => return,
+        };
+        let bitangent = cross(normal, tangent);
+        let planar = vertices
+            .iter()
+            .map(|vertex| {
+                let offset = sub(*vertex, vertices[0]);
+                [dot(offset, tangent), dot(offset, bitangent)]
+            })
+            .collect::<Vec<_>>();
+        let (mut min, mut max) = (planar[0], planar[0]);
+        for [u, v] in &planar {
+            min = [min[0].min(*u), min[1].min(*v)];
+            max = [max[0].max(*u), max[1].max(*v)];
+        }
+        let tex_coord = |index: usize| {
+            TexCoord([
+                (planar[index][0] - min[0]) / (max[0] - min[0]),
+                (planar[index][1] - min[1]) / (max[1] - min[1]),
+            ])
+        };
+        for index in 1..vertices.len() - 1 {
+            for vertex in &[0, index, index + 1] {
+                self.positions.push(Position(vertices[*vertex]));
+                self.normals.push(Normal(normal));
+                self.tangents
+                    .push(Tangent([tangent[0], tangent[1], tangent[2], 1.0]));
+                self.tex_coords.push(tex_coord(*vertex));
+            }
+        }
+    }
+
+    // Moves the texture coordinates into a tile of an atlas made of a single row of tiles.
+    pub fn with_atlas_tile(mut self, tile: usize, tiles: usize) -> Self {
+        let scale = 1.0 - 2.0 * ATLAS_TILE_MARGIN;
+        for TexCoord([u, v]) in &mut self.tex_coords {
+            *u = (tile as f32 + ATLAS_TILE_MARGIN + *u * scale) / tiles as f32;
+            *v = ATLAS_TILE_MARGIN + *v * scale;
+        }
+        self
+    }
+}
+
+impl From<MeshVertices> for MeshBuilder<'static> {
+    fn from(vertices: MeshVertices) -> Self {
+        MeshBuilder::new()
+            .with_vertices(vertices.positions)
+            .with_vertices(vertices.normals)
+            .with_vertices(vertices.tangents)
+            .with_vertices(vertices.tex_coords)
+    }
+}
+
+// What the mesh loaders take.
+impl From<MeshVertices> for MeshData {
+    fn from(vertices: MeshVertices) -> Self {
+        MeshBuilder::from(vertices).into()
+    }
+}
+
+// Pointy top hexagonal prism from -1 to 1 in all directions, like the hexes of the layout. The
+// insets shrink the top and bottom faces (relatively to the hex size) and the bevel cuts their
+// edges.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct HexPrism {
+    pub top_inset: f32,
+    pub bottom_inset: f32,
+    pub bevel: f32,
+}
+
+impl HexPrism {
+    pub fn vertices(&self) -> MeshVertices {
+        // Rings of corners from the bottom to the top.
+        let rings = [
+            (-1.0, 1.0 - self.bottom_inset - self.bevel),
+            (-1.0 + self.bevel, 1.0 - self.bottom_inset),
+            (1.0 - self.bevel, 1.0 - self.top_inset),
+            (1.0, 1.0 - self.top_inset - self.bevel),
+        ]
+        .iter()
+        .map(|(y, scale)| {
+            let mut ring = [[0.0; 3]; 6];
+            for (corner, vertex) in ring.iter_mut().enumerate() {
+                let [x, z] = hex_corner(corner);
+                *vertex = [x * scale, *y, z * scale];
+            }
+            ring
+        })
+        .collect::<Vec<_>>();
+        let mut vertices = MeshVertices::default();
+        vertices.push_polygon(&rings[0]);
+        for pair in rings.windows(2) {
+            push_sides(&mut vertices, &pair[0], &pair[1]);
+        }
+        let mut top = rings[3];
+        top.reverse();
+        vertices.push_polygon(&top);
+        vertices
+    }
+}
+
+// Wall hex shaped like a HexPrism without insets, but only the top edges facing away from the
+// neighbor walls are beveled: bit d of `walls` is set when the neighbor in the direction d, on the
+// side between the corners d - 1 and d, is a wall. Joined walls then read as one surface.
+pub fn wall_vertices(bevel: f32, walls: u8) -> MeshVertices {
+    let beveled = |side: usize| walls & (1 << (side % 6)) == 0;
+    let apothem = f32::sqrt(3.0) / 2.0;
+    // The corners of the top, where the lines of the sides meet once the beveled ones are moved
+    // inwards. The corner k is between the sides k and k + 1.
+    let mut top = [[0.0; 3]; 6];
+    for (corner, vertex) in top.iter_mut().enumerate() {
+        let inset = |side: usize| apothem * (1.0 - if beveled(side) { bevel } else { 0.0 });
+        let (a, b) = (inset(corner), inset(corner + 1));
+        let angle = |side: usize| std::f32::consts::PI / 3.0 * side as f32;
+        let (first, second) = (angle(corner), angle(corner + 1));
+        let det = (second - first).sin();
+        *vertex = [
+            (a * second.sin() - b * first.sin()) / det,
+            1.0,
+            (b * first.cos() - a * second.cos()) / det,
+        ];
+    }
+    let ring = |y: f32| {
+        let mut ring = [[0.0; 3]; 6];
+        for (corner, vertex) in ring.iter_mut().enumerate() {
+            let [x, z] = hex_corner(corner);
+            *vertex = [x, y, z];
+        }
+        ring
+    };
+    let (bottom, upper) = (ring(-1.0), ring(1.0 - bevel));
+    let mut vertices = MeshVertices::default();
+    vertices.push_polygon(&bottom);
+    for corner in 0..6 {
+        let (next, side) = ((corner + 1) % 6, corner + 1);
+        if beveled(side) {
+            vertices.push_polygon(&[bottom[next], bottom[corner], upper[corner], upper[next]]);
+            vertices.push_polygon(&[upper[next], upper[corner], top[corner], top[next]]);
+        } else {
+            // Up to the top, but down to the bevels of the neighbor sides at the corners.
+            let mut side_vertices = vec![bottom[next], bottom[corner]];
+            if beveled(corner) {
+                side_vertices.push(upper[corner]);
+            }
+            side_vertices.push(top[corner]);
+            side_vertices.push(top[next]);
+            if beveled(side + 1) {
+                side_vertices.push(upper[next]);
+            }
+            vertices.push_polygon(&side_vertices);
+        }
+    }
+    top.reverse();
+    vertices.push_polygon(&top);
+    vertices
+}
+
+// Rhombic dodecahedron from -1 to 1 vertically, its middle faces are the sides of a hex prism.
+pub fn dodec_vertices() -> MeshVertices {
+    let (low, high) = (0.5f32.sqrt() / 2.0, (1.0 + 0.5f32.sqrt() / 2.0) / 2.0);
+    let ring = |ys: [f32; 2]| {
+        let mut ring = [[0.0; 3]; 6];
+        for (corner, vertex) in ring.iter_mut().enumerate() {
+            let [x, z] = hex_corner(corner);
+            *vertex = [x, ys[corner % 2], z];
+        }
+        ring
+    };
+    let (bottom, top) = (ring([-high, -low]), ring([low, high]));
+    let mut vertices = MeshVertices::default();
+    for corner in &[0, 2, 4] {
+        vertices.push_polygon(&[
+            [0.0, -1.0, 0.0],
+            bottom[*corner],
+            bottom[corner + 1],
+            bottom[(corner + 2) % 6],
+        ]);
+        vertices.push_polygon(&[
+            [0.0, 1.0, 0.0],
+            top[(corner + 3) % 6],
+            top[(corner + 2) % 6],
+            top[corner + 1],
+        ]);
+    }
+    push_sides(&mut vertices, &bottom, &top);
+    vertices
+}
+
+// Flat hexagonal ring from -1 to 1 vertically, the hole is `inner` times the size of the hex.
+pub fn hex_ring_vertices(inner: f32) -> MeshVertices {
+    let ring = |y: f32, scale: f32| {
+        let mut ring = [[0.0; 3]; 6];
+        for (corner, vertex) in ring.iter_mut().enumerate() {
+            let [x, z] = hex_corner(corner);
+            *vertex = [x * scale, y, z * scale];
+        }
+        ring
+    };
+    let (outer_bottom, outer_top) = (ring(-1.0, 1.0), ring(1.0, 1.0));
+    let (inner_bottom, inner_top) = (ring(-1.0, inner), ring(1.0, inner));
+    let mut vertices = MeshVertices::default();
+    push_sides(&mut vertices, &outer_bottom, &outer_top);
+    // Upside down so that they face the center.
+    push_sides(&mut vertices, &inner_top, &inner_bottom);
+    for corner in 0..6 {
+        let next = (corner + 1) % 6;
+        vertices.push_polygon(&[
+            outer_top[next],
+            outer_top[corner],
+            inner_top[corner],
+            inner_top[next],
+        ]);
+        vertices.push_polygon(&[
+            inner_bottom[next],
+            inner_bottom[corner],
+            outer_bottom[corner],
+            outer_bottom[next],
+        ]);
+    }
+    vertices
+}
+
+// Corners at 30 + 60 * corner degrees, in the x z plane.
+fn hex_corner(corner: usize) -> [f32; 2] {
+    let angle = std::f32::consts::PI / 6.0 * (2 * corner + 1) as f32;
+    [angle.cos(), angle.sin()]
+}
+
+fn push_sides(vertices: &mut MeshVertices, lower: &[[f32; 3]; 6], upper: &[[f32; 3]; 6]) {
+    for corner in 0..6 {
+        let next = (corner + 1) % 6;
+        vertices.push_polygon(&[lower[next], lower[corner], upper[corner], upper[next]]);
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> Option<[f32; 3]> {
+    let length = dot(a, a).sqrt();
+    if length > 1e-6 {
+        Some([a[0] / length, a[1] / length, a[2] / length])
+    } else {
+        None
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{input::get_key_and_modifiers, systems::metrics::Metrics};
+use amethyst::{
+    core::timing::Time,
+    input::ElementState,
+    prelude::*,
+    window::Window,
+    winit::{Event, VirtualKeyCode},
+};
+use std::time::{Duration, Instant};
+
+const MIN_SPEED: f64 = 1.0 / 64.0;
+const MAX_SPEED: f64 = 64.0;
+
+pub struct AnimationSettings {
+    pub speed: f64,
+    pub frame_budget_millis: u64,
+    pub window_title: String,
+}
+
+// Without a display there is no window to show the title in.
+pub fn set_window_title(world: &World, title: &str) {
+    if let Some(window) = world.try_fetch::<Window>() {
+        window.set_title(title);
+    }
+}
+
+// Tells how many steps are due each frame at the current speed, and runs them until the frame
+// budget is spent: the steps left are dropped so that the animation slows down instead of the
+// frame rate when the steps are expensive.
+pub struct StepPacer {
+    step_seconds: f64,
+    remaining_seconds: f64,
+    speed: f64,
+    paused: bool,
+    pending_steps: usize,
+    frame_budget: Duration,
+    frame_start: Instant,
+    due_steps: usize,
+    frame_steps: usize,
+    shown_frame_steps: usize,
+    status: String,
+    // The start of the step running, then how long the last one took, for the Metrics.
+    step_start: Option<Instant>,
+    last_step: Option<Duration>,
+}
+
+impl StepPacer {
+    pub fn new(step_millis: u64) -> Self {
+        Self {
+            step_seconds: step_millis as f64 / 1000.0,
+            remaining_seconds: 0.0,
+            speed: 1.0,
+            paused: false,
+            pending_steps: 0,
+            frame_budget: Duration::default(),
+            frame_start: Instant::now(),
+            due_steps: 0,
+            frame_steps: 0,
+            shown_frame_steps: 0,
+            status: String::new(),
+            step_start: None,
+            last_step: None,
+        }
+    }
+
+    pub fn start(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        let settings = data.world.read_resource::<AnimationSettings>();
+        self.speed = settings.speed;
+        self.frame_budget = Duration::from_millis(settings.frame_budget_millis);
+        self.reset();
+        self.update_title(data);
+    }
+
+    pub fn stop(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        let settings = data.world.read_resource::<AnimationSettings>();
+        set_window_title(data.world, &settings.window_title);
+    }
+
+    pub fn reset(&mut self) {
+        self.remaining_seconds = 0.0;
+        self.pending_steps = 0;
+        self.due_steps = 0;
+    }
+
+    pub fn handle_event(&mut self, data: &StateData<'_, GameData<'_, '_>>, event: &Event) -> bool {
+        match get_key_and_modifiers(event) {
+            Some((VirtualKeyCode::Add, ElementState::Pressed, _))
+            | Some((VirtualKeyCode::Equals, ElementState::Pressed, _)) => {
+                self.set_speed(data, self.speed * 2.0);
+            }
+            Some((VirtualKeyCode::Subtract, ElementState::Pressed, _))
+            | Some((VirtualKeyCode::Minus, ElementState::Pressed, _)) => {
+                self.set_speed(data, self.speed / 2.0);
+            }
+            Some((VirtualKeyCode::P, ElementState::Pressed, _)) => {
+                self.paused = !self.paused;
+                self.reset();
+            }
+            Some((VirtualKeyCode::Period, ElementState::Pressed, _)) => {
+                self.paused = true;
+                self.pending_steps += 1;
+            }
+            _ => return false,
+        }
+        self.update_title(data);
+        true
+    }
+
+    // Call next_step until it returns false between start_frame and end_frame.
+    pub fn start_frame(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        self.due_steps = self.steps(data);
+        self.frame_steps = 0;
+        self.frame_start = Instant::now();
+    }
+
+    // At least one step runs when one is due, even if it alone takes longer than the budget.
+    pub fn next_step(&mut self) -> bool {
+        if let Some(step_start) = self.step_start.take() {
+            self.last_step = Some(step_start.elapsed());
+        }
+        if self.due_steps == 0
+            || (self.frame_steps > 0 && self.frame_start.elapsed() >= self.frame_budget)
+        {
+            self.due_steps = 0;
+            return false;
+        }
+        self.due_steps -= 1;
+        self.frame_steps += 1;
+        self.step_start = Some(Instant::now());
+        true
+    }
+
+    pub fn end_frame(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        if let Some(last_step) = self.last_step.take() {
+            data.world
+                .write_resource::<Metrics>()
+                .record_step(last_step);
+        }
+        if self.frame_steps > 0 && self.frame_steps != self.shown_frame_steps {
+            self.shown_frame_steps = self.frame_steps;
+            self.update_title(data);
+        }
+    }
+
+    fn steps(&mut self, data: &StateData<'_, GameData<'_, '_>>) -> usize {
+        if self.paused {
+            return std::mem::take(&mut self.pending_steps);
+        }
+        self.remaining_seconds += data.world.read_resource::<Time>().delta_seconds() as f64;
+        let step_seconds = self.step_seconds / self.speed;
+        let steps = (self.remaining_seconds / step_seconds).floor();
+        self.remaining_seconds -= steps * step_seconds;
+        steps as usize
+    }
+
+    // Extra information appended to the window title.
+    pub fn set_status(&mut self, data: &StateData<'_, GameData<'_, '_>>, status: String) {
+        if self.status != status {
+            self.status = status;
+            self.update_title(data);
+        }
+    }
+
+    fn set_speed(&mut self, data: &StateData<'_, GameData<'_, '_>>, speed: f64) {
+        self.speed = speed.max(MIN_SPEED).min(MAX_SPEED);
+        data.world.write_resource::<AnimationSettings>().speed = self.speed;
+    }
+
+    fn update_title(&self, data: &StateData<'_, GameData<'_, '_>>) {
+        let settings = data.world.read_resource::<AnimationSettings>();
+        set_window_title(
+            data.world,
+            &format!(
+                "{} - speed x{}{} - {} steps/frame{}{}",
+                settings.window_title,
+                self.speed,
+                if self.paused { " (paused)" } else { "" },
+                self.shown_frame_steps,
+                if self.status.is_empty() { "" } else { " - " },
+                self.status
+            ),
+        );
+    }
+}
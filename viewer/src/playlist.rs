@@ -0,0 +1,117 @@
+use amethyst::config::Config;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// A demo of the carousel, by its name on the command line. It runs until it stops or for
+// `duration` seconds, at `speed` instead of the --speed one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistDemo {
+    pub demo: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub speed: Option<f64>,
+}
+
+// What the carousel goes through when no demo is given on the command line, see
+// config/playlist.ron.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playlist {
+    // Seconds before the next demo of the carousel, long enough to read its name and to pause the
+    // carousel.
+    pub delay: f64,
+    // Seconds before a demo given on the command line starts again once it stops.
+    pub restart_delay: f64,
+    pub demos: Vec<PlaylistDemo>,
+}
+
+impl Default for Playlist {
+    fn default() -> Self {
+        Self {
+            delay: 3.0,
+            restart_delay: 1.0,
+            demos: [
+                ("hex-directions", "hex directions"),
+                ("hex-ring", "hex ring"),
+                ("hex-snake", "hex snake"),
+                ("dodec-directions", "dodec directions"),
+                ("dodec-sphere", "dodec sphere"),
+                ("dodec-snake", "dodec snake"),
+            ]
+            .iter()
+            .map(|(demo, label)| PlaylistDemo {
+                demo: demo.to_string(),
+                label: Some(label.to_string()),
+                duration: None,
+                speed: None,
+            })
+            .collect(),
+        }
+    }
+}
+
+impl Playlist {
+    fn validate<D>(&self, is_demo: D) -> Result<(), String>
+    where
+        D: Fn(&str) -> bool,
+    {
+        if self.demos.is_empty() {
+            return Err("No demo to play".to_string());
+        }
+        if self.delay < 0.0 || self.restart_delay < 0.0 {
+            return Err("The delays must not be negative".to_string());
+        }
+        for demo in &self.demos {
+            if !is_demo(&demo.demo) {
+                return Err(format!("Unknown demo {}", demo.demo));
+            }
+            if matches!(demo.duration, Some(duration) if duration <= 0.0) {
+                return Err(format!("The duration of {} must be positive", demo.demo));
+            }
+            if matches!(demo.speed, Some(speed) if speed <= 0.0) {
+                return Err(format!("The speed of {} must be positive", demo.demo));
+            }
+        }
+        Ok(())
+    }
+
+    // Its label, or its name, and where it is in the playlist.
+    pub fn label(&self, index: usize) -> String {
+        let demo = &self.demos[index];
+        format!(
+            "{} ({}/{})",
+            demo.label.as_ref().unwrap_or(&demo.demo),
+            index + 1,
+            self.demos.len()
+        )
+    }
+}
+
+// A missing file means the default playlist, a broken one is worth a warning.
+pub fn load_playlist<D>(path: &Path, is_demo: D) -> Playlist
+where
+    D: Fn(&str) -> bool,
+{
+    if !path.exists() {
+        return Playlist::default();
+    }
+    match Playlist::load(path)
+        .map_err(|err| err.to_string())
+        .and_then(|playlist| {
+            playlist.validate(is_demo)?;
+            Ok(playlist)
+        }) {
+        Ok(playlist) => playlist,
+        Err(err) => {
+            warn!(
+                "Failed to read playlist file: `{}` ({}), using the default playlist.",
+                path.display(),
+                err
+            );
+            Playlist::default()
+        }
+    }
+}
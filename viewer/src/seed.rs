@@ -0,0 +1,55 @@
+use amethyst::prelude::*;
+use log::info;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+// The --seed one, for the first run of every demo using random numbers.
+pub struct SeedSettings {
+    pub seed: Option<u64>,
+}
+
+// The random numbers of a demo come from a generator seeded with a known seed, shown in the
+// window title and logged, so that an interesting map can be generated again with --seed.
+pub struct DemoSeed {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl DemoSeed {
+    pub fn new() -> Self {
+        Self {
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    // The seed of the command line if any, a random one otherwise.
+    pub fn start(&mut self, data: &StateData<'_, GameData<'_, '_>>) {
+        match data.world.read_resource::<SeedSettings>().seed {
+            Some(seed) => self.set(seed),
+            None => self.renew(),
+        }
+    }
+
+    pub fn renew(&mut self) {
+        self.set(thread_rng().gen());
+    }
+
+    // Draws the same numbers again.
+    pub fn restart(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+
+    fn set(&mut self, seed: u64) {
+        info!("Seed: {}", seed);
+        self.seed = seed;
+        self.restart();
+    }
+}
@@ -0,0 +1,94 @@
+use amethyst::prelude::*;
+#[cfg(feature = "sound")]
+use amethyst::{
+    assets::{AssetStorage, Loader, ProgressCounter},
+    audio::{output::Output, Source, SourceHandle, WavFormat},
+    core::timing::Time,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundCue {
+    PointerMove = 0,
+    WallCarve = 1,
+    PhaseComplete = 2,
+    Bump = 3,
+}
+
+#[cfg(feature = "sound")]
+const NUM_CUES: usize = 4;
+
+#[cfg(feature = "sound")]
+const CUE_FILES: [&str; NUM_CUES] = [
+    "sound/pointer_move.wav",
+    "sound/wall_carve.wav",
+    "sound/phase_complete.wav",
+    "sound/bump.wav",
+];
+
+// Fast generators would otherwise play the same cue every few milliseconds.
+#[cfg(feature = "sound")]
+const MIN_CUE_INTERVAL_SECONDS: f64 = 0.08;
+
+#[cfg(feature = "sound")]
+#[derive(Clone, Copy, Debug)]
+pub struct SoundSettings {
+    pub enabled: bool,
+    pub volume: f32,
+}
+
+#[cfg(feature = "sound")]
+pub struct SoundCues {
+    settings: SoundSettings,
+    sources: Vec<SourceHandle>,
+    last_played: [f64; NUM_CUES],
+}
+
+#[cfg(feature = "sound")]
+impl SoundCues {
+    pub fn load(
+        world: &mut World,
+        settings: SoundSettings,
+        progress_counter: &mut ProgressCounter,
+    ) -> Self {
+        let loader = world.read_resource::<Loader>();
+        let storage = world.read_resource::<AssetStorage<Source>>();
+        let sources = CUE_FILES
+            .iter()
+            .map(|file| loader.load(*file, WavFormat, &mut *progress_counter, &storage))
+            .collect();
+        Self {
+            settings,
+            sources,
+            last_played: [std::f64::NEG_INFINITY; NUM_CUES],
+        }
+    }
+}
+
+pub fn play_cue(data: &StateData<'_, GameData<'_, '_>>, cue: SoundCue) {
+    #[cfg(feature = "sound")]
+    {
+        let time = data
+            .world
+            .read_resource::<Time>()
+            .absolute_real_time_seconds();
+        let mut cues = match data.world.try_fetch_mut::<SoundCues>() {
+            Some(cues) => cues,
+            None => return,
+        };
+        if !cues.settings.enabled
+            || time - cues.last_played[cue as usize] < MIN_CUE_INTERVAL_SECONDS
+        {
+            return;
+        }
+        cues.last_played[cue as usize] = time;
+        let storage = data.world.read_resource::<AssetStorage<Source>>();
+        if let (Some(source), Some(output)) = (
+            storage.get(&cues.sources[cue as usize]),
+            data.world.try_fetch::<Output>(),
+        ) {
+            output.play_once(source, cues.settings.volume);
+        }
+    }
+    #[cfg(not(feature = "sound"))]
+    let _ = (data, cue);
+}
@@ -0,0 +1,25 @@
+use amethyst::{ecs::prelude::*, renderer::camera::Camera, window::ScreenDimensions};
+
+#[derive(Default)]
+pub struct CameraAspectSystem {
+    aspect_ratio: Option<f32>,
+}
+
+impl<'a> System<'a> for CameraAspectSystem {
+    type SystemData = (ReadExpect<'a, ScreenDimensions>, WriteStorage<'a, Camera>);
+
+    fn run(&mut self, (dimensions, mut cameras): Self::SystemData) {
+        let aspect_ratio = dimensions.aspect_ratio();
+        if self.aspect_ratio == Some(aspect_ratio) {
+            return;
+        }
+        // Only the horizontal scale of the perspective depends on the aspect ratio, the field of
+        // view and the near plane are kept.
+        for camera in (&mut cameras).join() {
+            let mut matrix = camera.matrix;
+            matrix[(0, 0)] = -matrix[(1, 1)] / aspect_ratio;
+            *camera = Camera::from_matrix(matrix);
+        }
+        self.aspect_ratio = Some(aspect_ratio);
+    }
+}
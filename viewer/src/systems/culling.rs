@@ -0,0 +1,161 @@
+use crate::world::{fractional_axial_translation, RhombusViewerWorld};
+use amethyst::{
+    core::{
+        math::{Matrix4, Vector4},
+        Hidden, Transform,
+    },
+    derive::SystemDesc,
+    ecs::prelude::*,
+    renderer::camera::Camera,
+};
+use rhombus_core::hex::{
+    coordinates::axial::AxialVector,
+    float::layout::HexLayout,
+    storage::rect::{RECT_X_LEN, RECT_Y_LEN},
+};
+use std::{collections::HashMap, sync::Arc};
+
+// The storage rect the hex of an entity belongs to, the entities of a rect out of the view are
+// hidden together.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkTag {
+    x: isize,
+    y: isize,
+}
+
+impl ChunkTag {
+    pub fn new(position: AxialVector) -> Self {
+        Self {
+            x: position.q().div_euclid(RECT_X_LEN as isize),
+            y: position.r().div_euclid(RECT_Y_LEN as isize),
+        }
+    }
+
+    // Ground bounding box of the hexes of the rect, as min and max x and z.
+    fn ground_box(&self, layout: &HexLayout) -> ([f32; 2], [f32; 2]) {
+        let q0 = (self.x * RECT_X_LEN as isize) as f32;
+        let r0 = (self.y * RECT_Y_LEN as isize) as f32;
+        let q1 = q0 + (RECT_X_LEN - 1) as f32;
+        let r1 = r0 + (RECT_Y_LEN - 1) as f32;
+        let mut min = [std::f32::MAX; 2];
+        let mut max = [std::f32::MIN; 2];
+        for (q, r) in &[(q0, r0), (q1, r0), (q0, r1), (q1, r1)] {
+            let center = fractional_axial_translation(layout, *q, *r, 0.0);
+            for (i, coord) in [center[0], center[2]].iter().enumerate() {
+                min[i] = min[i].min(*coord);
+                max[i] = max[i].max(*coord);
+            }
+        }
+        // The centers of the corner hexes, plus the hexes themselves.
+        let margin = layout.size();
+        (
+            [min[0] - margin, min[1] - margin],
+            [max[0] + margin, max[1] + margin],
+        )
+    }
+}
+
+impl Component for ChunkTag {
+    type Storage = DenseVecStorage<ChunkTag>;
+}
+
+// Only the side planes: the perspective has no far plane, and the side planes meet at the camera so
+// nothing behind it is in between.
+struct Frustum {
+    planes: [Vector4<f32>; 4],
+}
+
+impl Frustum {
+    fn new(clip: &Matrix4<f32>) -> Self {
+        let row = |i| clip.row(i).transpose();
+        Self {
+            planes: [
+                row(3) + row(0),
+                row(3) - row(0),
+                row(3) + row(1),
+                row(3) - row(1),
+            ],
+        }
+    }
+
+    // Conservative: a box is only out of the view if it is on the outer side of a plane.
+    fn intersects_box(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        self.planes.iter().all(|plane| {
+            let corner = |i: usize| if plane[i] >= 0.0 { max[i] } else { min[i] };
+            plane[0] * corner(0) + plane[1] * corner(1) + plane[2] * corner(2) + plane[3] >= 0.0
+        })
+    }
+}
+
+// Hides the entities of the chunks out of the camera frustum and shows them again when they come
+// back into view.
+#[derive(SystemDesc)]
+pub struct ChunkCullingSystem;
+
+impl<'s> System<'s> for ChunkCullingSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+        ReadStorage<'s, ChunkTag>,
+        WriteStorage<'s, Hidden>,
+        Option<Read<'s, Arc<RhombusViewerWorld>>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, cameras, transforms, chunk_tags, mut hiddens, world): Self::SystemData,
+    ) {
+        let world = match world {
+            Some(world) => world,
+            None => return,
+        };
+        let frustum = match (&cameras, &transforms)
+            .join()
+            .next()
+            .and_then(|(camera, transform)| {
+                transform
+                    .global_matrix()
+                    .try_inverse()
+                    .map(|view| Frustum::new(&(camera.matrix * view)))
+            }) {
+            Some(frustum) => frustum,
+            None => return,
+        };
+
+        // The altitudes come from the entities themselves, the levels have no upper bound. The
+        // meshes span from -1 to 1 vertically before being scaled.
+        let mut altitudes = HashMap::<ChunkTag, [f32; 2]>::new();
+        for (tag, transform) in (&chunk_tags, &transforms).join() {
+            let altitude = transform.translation()[1];
+            let extent = transform.scale()[1].abs();
+            let range = altitudes
+                .entry(*tag)
+                .or_insert([altitude - extent, altitude + extent]);
+            range[0] = range[0].min(altitude - extent);
+            range[1] = range[1].max(altitude + extent);
+        }
+        let visible = altitudes
+            .into_iter()
+            .map(|(tag, [bottom, top])| {
+                let (min, max) = tag.ground_box(&world.layout);
+                (
+                    tag,
+                    frustum.intersects_box([min[0], bottom, min[1]], [max[0], top, max[1]]),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        for (entity, tag) in (&entities, &chunk_tags).join() {
+            let hidden = hiddens.contains(entity);
+            if visible.get(tag).copied().unwrap_or(true) == hidden {
+                if hidden {
+                    hiddens.remove(entity);
+                } else {
+                    hiddens.insert(entity, Hidden).expect("insert Hidden");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use amethyst::{
+    assets::{AssetStorage, Loader},
+    core::{shrev::EventChannel, timing::Time},
+    derive::SystemDesc,
+    ecs::prelude::*,
+    input::{InputEvent, StringBindings},
+    ui::{get_default_font, Anchor, FontAsset, LineMode, UiText, UiTransform},
+    winit::VirtualKeyCode,
+};
+use log::{info, warn};
+use std::collections::VecDeque;
+
+const CAPACITY: usize = 12;
+const FONT_SIZE: f32 = 16.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventKind {
+    Generation,
+    Pointer,
+    View,
+    // Maps and configuration files written or read again.
+    File,
+    Error,
+}
+
+impl EventKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Generation => "generation",
+            Self::Pointer => "pointer",
+            Self::View => "view",
+            Self::File => "file",
+            Self::Error => "error",
+        }
+    }
+}
+
+pub struct LoggedEvent {
+    pub time: f64,
+    pub kind: EventKind,
+    pub message: String,
+}
+
+// The last events worth showing on the screen, the older ones dropped. Everything pushed is also
+// logged, as a warning for the errors.
+pub struct EventLog {
+    events: VecDeque<LoggedEvent>,
+    visible: bool,
+    // Bumped on every change so that the panel is only written again when needed.
+    revision: usize,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::with_capacity(CAPACITY),
+            visible: true,
+            revision: 0,
+        }
+    }
+}
+
+impl EventLog {
+    pub fn push(&mut self, time: f64, kind: EventKind, message: String) {
+        if kind == EventKind::Error {
+            warn!("{}", message);
+        } else {
+            info!("{}", message);
+        }
+        if self.events.len() == CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(LoggedEvent {
+            time,
+            kind,
+            message,
+        });
+        self.revision += 1;
+    }
+
+    // The oldest first.
+    pub fn events(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.events.iter()
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.revision += 1;
+    }
+
+    fn text(&self) -> String {
+        if !self.visible {
+            return String::new();
+        }
+        self.events()
+            .map(|event| {
+                format!(
+                    "{:>7.1}s {:<10} {}",
+                    event.time,
+                    event.kind.label(),
+                    event.message
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// For the demos, without a log the event is only logged.
+pub fn log_event(world: &World, kind: EventKind, message: String) {
+    let time = world.read_resource::<Time>().absolute_real_time_seconds();
+    match world.try_fetch_mut::<EventLog>() {
+        Some(mut log) => log.push(time, kind, message),
+        None => info!("{}", message),
+    }
+}
+
+// Shows the event log in the bottom left corner of the window, F1 hides and shows it.
+#[derive(SystemDesc)]
+#[system_desc(name(EventLogSystemDesc))]
+pub struct EventLogSystem {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<InputEvent<StringBindings>>,
+    #[system_desc(skip)]
+    panel: Option<Entity>,
+    #[system_desc(skip)]
+    revision: Option<usize>,
+}
+
+impl EventLogSystem {
+    pub fn new(event_reader: ReaderId<InputEvent<StringBindings>>) -> Self {
+        Self {
+            event_reader,
+            panel: None,
+            revision: None,
+        }
+    }
+}
+
+impl<'s> System<'s> for EventLogSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Write<'s, EventLog>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<FontAsset>>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, events, mut log, loader, fonts, mut transforms, mut texts): Self::SystemData,
+    ) {
+        for event in events.read(&mut self.event_reader) {
+            if let InputEvent::KeyPressed {
+                key_code: VirtualKeyCode::F1,
+                ..
+            } = event
+            {
+                log.toggle();
+            }
+        }
+        if self.revision == Some(log.revision) {
+            return;
+        }
+        self.revision = Some(log.revision);
+        let panel = *self.panel.get_or_insert_with(|| {
+            let height = CAPACITY as f32 * FONT_SIZE * 1.25;
+            entities
+                .build_entity()
+                .with(
+                    UiTransform::new(
+                        "event_log".to_string(),
+                        Anchor::BottomLeft,
+                        Anchor::BottomLeft,
+                        10.0,
+                        10.0,
+                        1.0,
+                        900.0,
+                        height,
+                    ),
+                    &mut transforms,
+                )
+                .with(
+                    UiText::new(
+                        get_default_font(&loader, &fonts),
+                        String::new(),
+                        [0.9, 0.9, 0.8, 1.0],
+                        FONT_SIZE,
+                        LineMode::Wrap,
+                        Anchor::BottomLeft,
+                    ),
+                    &mut texts,
+                )
+                .build()
+        });
+        if let Some(text) = texts.get_mut(panel) {
+            text.text = log.text();
+        }
+    }
+}
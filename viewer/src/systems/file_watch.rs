@@ -0,0 +1,92 @@
+use amethyst::ecs::prelude::*;
+use log::warn;
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+    time::Duration,
+};
+
+// Editors tend to write files in several steps, they are reported once they settle.
+const DEBOUNCE_MILLIS: u64 = 200;
+
+// The watched files which changed on disk since the previous frame.
+#[derive(Default)]
+pub struct FileChanges {
+    paths: Vec<PathBuf>,
+}
+
+impl FileChanges {
+    pub fn contains(&self, path: &Path) -> bool {
+        let path = absolute(path);
+        self.paths.iter().any(|changed| *changed == path)
+    }
+}
+
+pub struct FileWatchSystem {
+    paths: HashSet<PathBuf>,
+    // Stops watching when dropped.
+    _watcher: Option<RecommendedWatcher>,
+    receiver: Receiver<DebouncedEvent>,
+}
+
+impl FileWatchSystem {
+    // The parent directories are watched rather than the files, so that files replaced by the
+    // editors (or created later on) are noticed too. Without a watcher nothing is reloaded.
+    pub fn new(paths: &[&Path]) -> Self {
+        let paths = paths
+            .iter()
+            .map(|path| absolute(path))
+            .collect::<HashSet<_>>();
+        let (sender, receiver) = channel();
+        let watcher = watcher(sender, Duration::from_millis(DEBOUNCE_MILLIS))
+            .and_then(|mut watcher| {
+                let directories = paths
+                    .iter()
+                    .filter_map(|path| path.parent())
+                    .collect::<HashSet<_>>();
+                for directory in directories {
+                    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+                }
+                Ok(watcher)
+            })
+            .map_err(|err| warn!("Failed to watch the configuration files ({})", err))
+            .ok();
+        Self {
+            paths,
+            _watcher: watcher,
+            receiver,
+        }
+    }
+}
+
+impl<'s> System<'s> for FileWatchSystem {
+    type SystemData = Write<'s, FileChanges>;
+
+    fn run(&mut self, mut changes: Self::SystemData) {
+        changes.paths.clear();
+        for event in self.receiver.try_iter() {
+            let path = match event {
+                DebouncedEvent::Create(path)
+                | DebouncedEvent::Write(path)
+                | DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+            if self.paths.contains(&path) && !changes.paths.contains(&path) {
+                changes.paths.push(path);
+            }
+        }
+    }
+}
+
+// The watcher reports absolute paths.
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|directory| directory.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
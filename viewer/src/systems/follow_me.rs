@@ -1,9 +1,107 @@
+use crate::input::get_key_and_modifiers;
 use amethyst::{
+    config::Config,
     core::{timing::Time, Transform},
     derive::SystemDesc,
     ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    winit::{Event, VirtualKeyCode},
 };
-use std::collections::{hash_map::Entry, HashMap};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    path::Path,
+};
+
+// Lerp ratios given to the tags when the camera starts following something, see
+// config/follow.ron.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FollowMeSettings {
+    pub translation: f32,
+    pub rotation: f32,
+    pub camera_rotation: f32,
+    // The followers jump to their targets instead of moving smoothly.
+    pub snap: bool,
+}
+
+impl Default for FollowMeSettings {
+    fn default() -> Self {
+        Self {
+            translation: 0.1,
+            rotation: 0.1,
+            camera_rotation: 0.01,
+            snap: false,
+        }
+    }
+}
+
+impl FollowMeSettings {
+    fn validate(&self) -> Result<(), String> {
+        for (name, ratio) in &[
+            ("translation", self.translation),
+            ("rotation", self.rotation),
+            ("camera_rotation", self.camera_rotation),
+        ] {
+            if !(*ratio > 0.0 && *ratio <= 1.0) {
+                return Err(format!("{} must be in ]0, 1]", name));
+            }
+        }
+        Ok(())
+    }
+
+    // T toggles snapping. Returns true if it changed.
+    pub fn handle_event(data: &StateData<'_, GameData<'_, '_>>, event: &Event) -> bool {
+        match get_key_and_modifiers(event) {
+            Some((VirtualKeyCode::T, ElementState::Pressed, _)) => {
+                let mut settings = data.world.write_resource::<FollowMeSettings>();
+                settings.snap = !settings.snap;
+                info!(
+                    "Camera {}",
+                    if settings.snap {
+                        "snapping"
+                    } else {
+                        "moving smoothly"
+                    }
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn ratio(&self, lerp_ratio: f32, delta_millis: u64) -> f32 {
+        if self.snap {
+            1.0
+        } else {
+            (lerp_ratio * delta_millis as f32 * TIME_RATIO).min(1.0)
+        }
+    }
+}
+
+// A missing file means the default settings, a broken one is worth a warning.
+pub fn load_follow_settings(path: &Path) -> FollowMeSettings {
+    if !path.exists() {
+        return FollowMeSettings::default();
+    }
+    match FollowMeSettings::load(path)
+        .map_err(|err| err.to_string())
+        .and_then(|settings| {
+            settings.validate()?;
+            Ok(settings)
+        }) {
+        Ok(settings) => settings,
+        Err(err) => {
+            warn!(
+                "Failed to read follow file: `{}` ({}), using the default settings.",
+                path.display(),
+                err
+            );
+            FollowMeSettings::default()
+        }
+    }
+}
 
 pub struct FollowMeTag {
     pub target: Option<(Entity, f32)>,
@@ -25,9 +123,10 @@ impl<'s> System<'s> for FollowMeSystem {
         WriteStorage<'s, Transform>,
         ReadStorage<'s, FollowMeTag>,
         Read<'s, Time>,
+        Read<'s, FollowMeSettings>,
     );
 
-    fn run(&mut self, (mut transforms, follow_me_tags, time): Self::SystemData) {
+    fn run(&mut self, (mut transforms, follow_me_tags, time, settings): Self::SystemData) {
         let delta_millis = {
             let duration = time.delta_time();
             duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
@@ -61,19 +160,17 @@ impl<'s> System<'s> for FollowMeSystem {
                         || delta[1].abs() >= STAY_HERE_THRESHOLD
                         || delta[2].abs() >= STAY_HERE_THRESHOLD
                     {
-                        transform.prepend_translation(
-                            delta * (*lerp_ratio * delta_millis as f32 * TIME_RATIO).min(1.0),
-                        );
+                        transform
+                            .prepend_translation(delta * settings.ratio(*lerp_ratio, delta_millis));
                     }
                 }
             }
             if let Some((rotation_target, lerp_ratio)) = &follow_me_tag.rotation_target {
                 if let Some(target_transform) = target_transforms.get(rotation_target) {
                     let target_rot = target_transform.rotation();
-                    *transform.rotation_mut() = transform.rotation().slerp(
-                        &target_rot,
-                        (*lerp_ratio * delta_millis as f32 * TIME_RATIO).min(1.0),
-                    );
+                    *transform.rotation_mut() = transform
+                        .rotation()
+                        .slerp(&target_rot, settings.ratio(*lerp_ratio, delta_millis));
                 }
             }
         }
@@ -97,9 +194,10 @@ impl<'s> System<'s> for FollowMyRotationSystem {
         WriteStorage<'s, Transform>,
         ReadStorage<'s, FollowMyRotationTag>,
         Read<'s, Time>,
+        Read<'s, FollowMeSettings>,
     );
 
-    fn run(&mut self, (mut transforms, follow_my_rotation_tags, time): Self::SystemData) {
+    fn run(&mut self, (mut transforms, follow_my_rotation_tags, time, settings): Self::SystemData) {
         let delta_millis = {
             let duration = time.delta_time();
             duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
@@ -130,7 +228,7 @@ impl<'s> System<'s> for FollowMyRotationSystem {
                 let target_rot = target2_transform.rotation() * target1_transform.rotation();
                 *transform.rotation_mut() = transform.rotation().slerp(
                     &target_rot,
-                    (follow_my_rotation_tag.lerp_ratio * delta_millis as f32 * TIME_RATIO).min(1.0),
+                    settings.ratio(follow_my_rotation_tag.lerp_ratio, delta_millis),
                 );
             }
         }
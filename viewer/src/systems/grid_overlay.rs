@@ -0,0 +1,226 @@
+use crate::{
+    hex::inspect::{HexLayer, HexTag},
+    input::get_key_and_modifiers,
+    world::{corner_translation, fractional_axial_translation, RhombusViewerWorld},
+};
+use amethyst::{
+    ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    renderer::{debug_drawing::DebugLinesComponent, palette::Srgba},
+    winit::{Event, VirtualKeyCode},
+};
+use log::info;
+use rhombus_core::hex::{
+    coordinates::{axial::AxialVector, direction::HexagonalDirection},
+    float::layout::HexLayout,
+    storage::{
+        rect::{RECT_X_LEN, RECT_Y_LEN},
+        set::HexSet,
+    },
+};
+use std::{collections::BTreeSet, sync::Arc};
+
+// Just above the floors of the tiles.
+const GRID_ALTITUDE: f32 = 0.25;
+// Above the grid.
+const RANGE_ALTITUDE: f32 = 0.3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GridMode {
+    Off,
+    Hexes,
+    // The boundaries of the storage rects.
+    Chunks,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GridCenter {
+    Origin,
+    Pointer,
+}
+
+pub struct GridSettings {
+    pub mode: GridMode,
+    pub center: GridCenter,
+    pub radius: usize,
+}
+
+impl GridSettings {
+    pub fn new(radius: usize) -> Self {
+        Self {
+            mode: GridMode::Off,
+            center: GridCenter::Origin,
+            radius,
+        }
+    }
+
+    // D cycles through the hex outlines, the chunk boundaries and nothing, Shift+D centers the
+    // grid on the pointer or back on the origin. Returns true if the settings changed.
+    pub fn handle_event(data: &StateData<'_, GameData<'_, '_>>, event: &Event) -> bool {
+        let mut settings = data.world.write_resource::<GridSettings>();
+        match get_key_and_modifiers(event) {
+            Some((VirtualKeyCode::D, ElementState::Pressed, modifiers)) if modifiers.shift => {
+                settings.center = match settings.center {
+                    GridCenter::Origin => GridCenter::Pointer,
+                    GridCenter::Pointer => GridCenter::Origin,
+                };
+            }
+            Some((VirtualKeyCode::D, ElementState::Pressed, _)) => {
+                settings.mode = match settings.mode {
+                    GridMode::Off => GridMode::Hexes,
+                    GridMode::Hexes => GridMode::Chunks,
+                    GridMode::Chunks => GridMode::Off,
+                };
+            }
+            _ => return false,
+        }
+        info!(
+            "Grid: {:?} around the {:?} (radius {})",
+            settings.mode, settings.center, settings.radius
+        );
+        true
+    }
+}
+
+// The hexes a demo highlights, e.g. the movement range of the pointer, outlined whatever the grid
+// mode. An empty range hides the outline.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct RangeOverlay {
+    pub hexes: Vec<AxialVector>,
+}
+
+// Draws the grid on an entity of its own, and draws it again only when the grid, its center or the
+// range changes.
+#[derive(Default)]
+pub struct GridOverlaySystem {
+    entity: Option<Entity>,
+    drawn: Option<(GridMode, usize, AxialVector, RangeOverlay)>,
+}
+
+impl<'s> System<'s> for GridOverlaySystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, GridSettings>,
+        Read<'s, RangeOverlay>,
+        ReadStorage<'s, HexTag>,
+        WriteStorage<'s, DebugLinesComponent>,
+        Option<Read<'s, Arc<RhombusViewerWorld>>>,
+    );
+
+    fn run(&mut self, (entities, settings, range, hex_tags, mut lines, world): Self::SystemData) {
+        let world = match world {
+            Some(world) => world,
+            None => return,
+        };
+        let center = match settings.center {
+            GridCenter::Origin => None,
+            GridCenter::Pointer => hex_tags
+                .join()
+                .find(|tag| tag.layer == HexLayer::Pointer)
+                .map(|tag| tag.position),
+        }
+        .unwrap_or_default();
+        if let Some((mode, radius, drawn_center, drawn_range)) = &self.drawn {
+            if (*mode, *radius, *drawn_center) == (settings.mode, settings.radius, center)
+                && drawn_range == &*range
+            {
+                return;
+            }
+        }
+        self.drawn = Some((settings.mode, settings.radius, center, range.clone()));
+
+        let entity = *self.entity.get_or_insert_with(|| entities.create());
+        if !lines.contains(entity) {
+            lines
+                .insert(entity, DebugLinesComponent::new())
+                .expect("insert DebugLinesComponent");
+        }
+        let lines = lines.get_mut(entity).expect("The grid has lines");
+        lines.clear();
+        match settings.mode {
+            GridMode::Off => {}
+            GridMode::Hexes => draw_hexes(lines, &world.layout, center, settings.radius),
+            GridMode::Chunks => draw_chunks(lines, &world.layout, center, settings.radius),
+        }
+        draw_range(lines, &world.layout, &range.hexes);
+    }
+}
+
+fn draw_hexes(
+    lines: &mut DebugLinesComponent,
+    layout: &HexLayout,
+    center: AxialVector,
+    radius: usize,
+) {
+    let color = Srgba::new(0.5, 0.5, 0.5, 1.0);
+    for position in center.spiral_iter(radius) {
+        // The other edges are drawn by the neighbors, but for the outer ring.
+        let edges = if position.distance(center) as usize == radius {
+            0..6
+        } else {
+            0..3
+        };
+        let pos = (position, GRID_ALTITUDE).into();
+        for corner in edges {
+            lines.add_line(
+                corner_translation(layout, pos, corner).into(),
+                corner_translation(layout, pos, corner + 1).into(),
+                color,
+            );
+        }
+    }
+}
+
+fn draw_chunks(
+    lines: &mut DebugLinesComponent,
+    layout: &HexLayout,
+    center: AxialVector,
+    radius: usize,
+) {
+    let color = Srgba::new(0.8, 0.4, 0.0, 1.0);
+    let chunks = center
+        .spiral_iter(radius)
+        .map(|position| {
+            (
+                position.q().div_euclid(RECT_X_LEN as isize),
+                position.r().div_euclid(RECT_Y_LEN as isize),
+            )
+        })
+        .collect::<BTreeSet<_>>();
+    for (x, y) in chunks {
+        // Halfway between the hexes of the rect and their neighbors outside of it.
+        let q0 = (x * RECT_X_LEN as isize) as f32 - 0.5;
+        let r0 = (y * RECT_Y_LEN as isize) as f32 - 0.5;
+        let q1 = q0 + RECT_X_LEN as f32;
+        let r1 = r0 + RECT_Y_LEN as f32;
+        let corners = [(q0, r0), (q1, r0), (q1, r1), (q0, r1)]
+            .iter()
+            .map(|(q, r)| fractional_axial_translation(layout, *q, *r, GRID_ALTITUDE))
+            .collect::<Vec<_>>();
+        for (start, end) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+            lines.add_line((*start).into(), (*end).into(), color);
+        }
+    }
+}
+
+// The edges between the hexes of the range and the ones outside of it.
+fn draw_range(lines: &mut DebugLinesComponent, layout: &HexLayout, hexes: &[AxialVector]) {
+    let color = Srgba::new(0.2, 0.6, 1.0, 1.0);
+    let range = hexes.iter().copied().collect::<HexSet>();
+    for position in range.iter() {
+        let pos = (position, RANGE_ALTITUDE).into();
+        for direction in 0..6 {
+            if range.contains(position.neighbor(direction)) {
+                continue;
+            }
+            // The corners go counterclockwise and the directions clockwise.
+            let corner = (6 - direction) % 6;
+            lines.add_line(
+                corner_translation(layout, pos, corner).into(),
+                corner_translation(layout, pos, corner + 1).into(),
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use amethyst::{
+    core::{math::Vector3, timing::Time, Transform},
+    derive::SystemDesc,
+    ecs::prelude::*,
+};
+
+// Breathes around the scale the entity had when tagged.
+pub struct PulseTag {
+    pub scale: Vector3<f32>,
+    pub period: f32,
+    pub amplitude: f32,
+}
+
+impl Component for PulseTag {
+    type Storage = HashMapStorage<PulseTag>;
+}
+
+// Bobs around the given height and spins around the vertical axis.
+pub struct HoverTag {
+    pub height: f32,
+    pub period: f32,
+    pub amplitude: f32,
+    pub spin_speed: f32,
+}
+
+impl Component for HoverTag {
+    type Storage = HashMapStorage<HoverTag>;
+}
+
+// Nudges the entity from its translation by the offset and back once, then the tag goes away.
+pub struct BumpTag {
+    pub translation: Vector3<f32>,
+    pub offset: Vector3<f32>,
+    pub start: f32,
+    pub duration: f32,
+}
+
+impl Component for BumpTag {
+    type Storage = HashMapStorage<BumpTag>;
+}
+
+#[derive(SystemDesc)]
+pub struct HighlightSystem;
+
+impl<'s> System<'s> for HighlightSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Transform>,
+        ReadStorage<'s, PulseTag>,
+        ReadStorage<'s, HoverTag>,
+        WriteStorage<'s, BumpTag>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut transforms, pulse_tags, hover_tags, mut bump_tags, time): Self::SystemData,
+    ) {
+        let seconds = time.absolute_time_seconds() as f32;
+        let wave = |period: f32| (seconds * 2.0 * std::f32::consts::PI / period).sin();
+
+        for (transform, pulse_tag) in (&mut transforms, &pulse_tags).join() {
+            let factor = 1.0 + pulse_tag.amplitude * wave(pulse_tag.period);
+            transform.set_scale(pulse_tag.scale * factor);
+        }
+
+        for (transform, hover_tag) in (&mut transforms, &hover_tags).join() {
+            transform.translation_mut()[1] =
+                hover_tag.height + hover_tag.amplitude * wave(hover_tag.period);
+            transform.set_rotation_y_axis(seconds * hover_tag.spin_speed);
+        }
+
+        let mut bumped = Vec::new();
+        for (entity, transform, bump_tag) in (&entities, &mut transforms, &bump_tags).join() {
+            let progress = (seconds - bump_tag.start) / bump_tag.duration;
+            if progress < 1.0 {
+                transform.set_translation(
+                    bump_tag.translation
+                        + bump_tag.offset * (progress * std::f32::consts::PI).sin(),
+                );
+            } else {
+                transform.set_translation(bump_tag.translation);
+                bumped.push(entity);
+            }
+        }
+        for entity in bumped {
+            bump_tags.remove(entity);
+        }
+    }
+}
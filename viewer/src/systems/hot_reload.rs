@@ -0,0 +1,199 @@
+use crate::{
+    assets::ColorData,
+    mesh::HexPrism,
+    pacing::AnimationSettings,
+    systems::{
+        event_log::{EventKind, EventLog},
+        file_watch::FileChanges,
+    },
+    theme::{load_theme, TerrainType, ThemeLights, ThemeSettings},
+    world::RhombusViewerWorld,
+};
+use amethyst::{
+    assets::{AssetLoaderSystemData, Handle},
+    config::Config,
+    core::timing::Time,
+    ecs::prelude::*,
+    renderer::{
+        types::{Mesh, Texture},
+        Material, MaterialDefaults,
+    },
+    window::{DisplayConfig, Window},
+    winit::dpi::LogicalSize,
+};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+// Builds the atlas, the terrain and the wall meshes again, then swaps them on the entities which use the
+// previous ones and in the assets of the world. The new point lights are placed by the next
+// generated maps.
+pub struct ThemeReloadSystem;
+
+impl<'s> System<'s> for ThemeReloadSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Read<'s, FileChanges>,
+        ReadExpect<'s, ThemeSettings>,
+        ReadExpect<'s, MaterialDefaults>,
+        AssetLoaderSystemData<'s, Texture>,
+        AssetLoaderSystemData<'s, Material>,
+        AssetLoaderSystemData<'s, Mesh>,
+        WriteStorage<'s, Handle<Material>>,
+        WriteStorage<'s, Handle<Mesh>>,
+        Write<'s, ThemeLights>,
+        Option<Write<'s, Arc<RhombusViewerWorld>>>,
+        Read<'s, Time>,
+        Write<'s, EventLog>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            changes,
+            settings,
+            mat_defaults,
+            texture_loader,
+            material_loader,
+            mesh_loader,
+            mut materials,
+            mut meshes,
+            mut lights,
+            world,
+            time,
+            mut log,
+        ): Self::SystemData,
+    ) {
+        let mut world = match world {
+            Some(world) if changes.contains(&settings.path) => world,
+            _ => return,
+        };
+        let theme = load_theme(&settings.path);
+        let load_atlas = |brightness: f32| {
+            let texture = texture_loader.load_from_data(theme.atlas_texture(brightness).into(), ());
+            material_loader.load_from_data(
+                Material {
+                    albedo: texture,
+                    ..mat_defaults.0.clone()
+                },
+                (),
+            )
+        };
+        let atlas = ColorData {
+            light: load_atlas(1.0),
+            dark: load_atlas(0.5),
+        };
+        let terrain_handles = TerrainType::ALL
+            .iter()
+            .map(|terrain| {
+                let handle = mesh_loader.load_from_data(
+                    HexPrism::default()
+                        .vertices()
+                        .with_atlas_tile(theme.tile(*terrain), theme.tiles.len())
+                        .into(),
+                    (),
+                );
+                (*terrain, handle)
+            })
+            .collect::<HashMap<_, _>>();
+        let wall_handles = theme
+            .wall_meshes()
+            .into_iter()
+            .map(|vertices| mesh_loader.load_from_data(vertices.into(), ()))
+            .collect::<Vec<_>>();
+
+        let old = &world.assets;
+        for material in (&mut materials).join() {
+            if *material == old.atlas.light {
+                *material = atlas.light.clone();
+            } else if *material == old.atlas.dark {
+                *material = atlas.dark.clone();
+            }
+        }
+        for mesh in (&mut meshes).join() {
+            if let Some(terrain) = TerrainType::ALL
+                .iter()
+                .find(|terrain| *mesh == old.terrain_handles[*terrain])
+            {
+                *mesh = terrain_handles[terrain].clone();
+            } else if let Some(index) = old.wall_handles.iter().position(|wall| mesh == wall) {
+                *mesh = wall_handles[index].clone();
+            }
+        }
+
+        let mut assets = world.assets.clone();
+        assets.atlas = atlas;
+        assets.terrain_handles = terrain_handles;
+        assets.wall_handles = wall_handles;
+        *lights = theme.lights.clone();
+        *world = Arc::new(world.with_assets(assets));
+        log.push(
+            time.absolute_real_time_seconds(),
+            EventKind::File,
+            format!("Theme reloaded from `{}`", settings.path.display()),
+        );
+    }
+}
+
+// Applies the title and the dimensions of the display configuration to the window when there is one,
+// the other settings only apply on restart.
+pub struct DisplayReloadSystem {
+    path: PathBuf,
+    // Set from the command line, which has the last word.
+    keep_dimensions: bool,
+}
+
+impl DisplayReloadSystem {
+    pub fn new(path: PathBuf, keep_dimensions: bool) -> Self {
+        Self {
+            path,
+            keep_dimensions,
+        }
+    }
+}
+
+impl<'s> System<'s> for DisplayReloadSystem {
+    type SystemData = (
+        Read<'s, FileChanges>,
+        Option<Read<'s, Window>>,
+        Option<Write<'s, AnimationSettings>>,
+        Read<'s, Time>,
+        Write<'s, EventLog>,
+    );
+
+    fn run(&mut self, (changes, window, animation_settings, time, mut log): Self::SystemData) {
+        if !changes.contains(&self.path) {
+            return;
+        }
+        let config = match DisplayConfig::load(&self.path) {
+            Ok(config) => config,
+            Err(err) => {
+                log.push(
+                    time.absolute_real_time_seconds(),
+                    EventKind::Error,
+                    format!(
+                        "Failed to read display file: `{}` ({}), keeping the current display.",
+                        self.path.display(),
+                        err
+                    ),
+                );
+                return;
+            }
+        };
+        if let Some(window) = &window {
+            window.set_title(&config.title);
+        }
+        // The demos restore it after showing their own messages.
+        if let Some(mut animation_settings) = animation_settings {
+            animation_settings.window_title = config.title.clone();
+        }
+        if let (Some(window), Some((width, height)), false) =
+            (&window, config.dimensions, self.keep_dimensions)
+        {
+            window.set_inner_size(LogicalSize::new(f64::from(width), f64::from(height)));
+        }
+        log.push(
+            time.absolute_real_time_seconds(),
+            EventKind::File,
+            format!("Display reloaded from `{}`", self.path.display()),
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::input::get_key_and_modifiers;
+use amethyst::{
+    core::{math::Vector3, timing::Time},
+    ecs::prelude::*,
+    input::ElementState,
+    prelude::*,
+    renderer::light::Light,
+    winit::{Event, VirtualKeyCode},
+};
+use log::info;
+use std::f32::consts::PI;
+
+// The sun never goes completely down, the maps stay readable at night.
+const NIGHT_INTENSITY: f32 = 0.1;
+
+pub struct LightingConfig {
+    // Seconds of a whole day and night, the sun stays at noon when zero.
+    pub day_length: f32,
+    // How much the flickering lights vary around their intensity, relatively to it.
+    pub flicker: f32,
+    pub paused: bool,
+}
+
+impl LightingConfig {
+    // Q pauses and resumes the day and night cycle and the flickering. Returns true if it handled
+    // the event.
+    pub fn handle_event(data: &StateData<'_, GameData<'_, '_>>, event: &Event) -> bool {
+        match get_key_and_modifiers(event) {
+            Some((VirtualKeyCode::Q, ElementState::Pressed, _)) => {
+                let mut config = data.world.write_resource::<LightingConfig>();
+                config.paused = !config.paused;
+                info!(
+                    "Lighting {}",
+                    if config.paused { "paused" } else { "resumed" }
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// A directional light going round with the time of the day, at its intensity at noon.
+pub struct SunTag {
+    pub intensity: f32,
+}
+
+impl Component for SunTag {
+    type Storage = HashMapStorage<SunTag>;
+}
+
+// A point light flickering around its intensity, the seed keeps neighbor lights out of step.
+pub struct FlickerTag {
+    pub intensity: f32,
+    pub seed: f32,
+}
+
+impl Component for FlickerTag {
+    type Storage = HashMapStorage<FlickerTag>;
+}
+
+#[derive(Default)]
+pub struct LightingSystem {
+    // Only runs when not paused.
+    seconds: f32,
+}
+
+impl<'s> System<'s> for LightingSystem {
+    type SystemData = (
+        WriteStorage<'s, Light>,
+        ReadStorage<'s, SunTag>,
+        ReadStorage<'s, FlickerTag>,
+        Read<'s, Time>,
+        Option<Read<'s, LightingConfig>>,
+    );
+
+    fn run(&mut self, (mut lights, sun_tags, flicker_tags, time, config): Self::SystemData) {
+        let config = match config {
+            Some(config) => config,
+            None => return,
+        };
+        if !config.paused {
+            self.seconds += time.delta_seconds();
+        }
+
+        // From sunrise at 0 to noon at PI / 2 and sunset at PI, then the night, when the light
+        // still comes from above, dimmed.
+        let angle = if config.day_length > 0.0 {
+            2.0 * PI * (self.seconds / config.day_length).fract()
+        } else {
+            PI / 2.0
+        };
+        for (light, tag) in (&mut lights, &sun_tags).join() {
+            if let Light::Directional(light) = light {
+                light.direction = Vector3::new(angle.cos(), -angle.sin().abs(), 0.0);
+                light.intensity = tag.intensity * angle.sin().max(NIGHT_INTENSITY);
+            }
+        }
+
+        for (light, tag) in (&mut lights, &flicker_tags).join() {
+            if let Light::Point(light) = light {
+                let t = self.seconds + tag.seed;
+                let noise = ((t * 7.0).sin() + 0.5 * (t * 13.0).sin()) / 1.5;
+                light.intensity = tag.intensity * (1.0 + config.flicker * noise);
+            }
+        }
+    }
+}
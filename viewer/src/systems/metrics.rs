@@ -0,0 +1,273 @@
+use crate::systems::spatial::EntitySpatialMap;
+use amethyst::{
+    assets::{AssetStorage, Loader},
+    core::{shrev::EventChannel, timing::Time},
+    derive::SystemDesc,
+    ecs::prelude::*,
+    input::{InputEvent, StringBindings},
+    ui::{get_default_font, Anchor, FontAsset, LineMode, UiImage, UiText, UiTransform},
+    utils::fps_counter::FpsCounter,
+    winit::VirtualKeyCode,
+};
+use std::{collections::VecDeque, time::Duration};
+
+// The frames in the graph, one bar each.
+const FRAME_SAMPLES: usize = 120;
+const FONT_SIZE: f32 = 16.0;
+const TEXT_LINES: usize = 3;
+const MARGIN: f32 = 10.0;
+const BAR_WIDTH: f32 = 2.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+// The frame time at the top of the graph, the longer frames are cut.
+const GRAPH_SECONDS: f32 = 1.0 / 20.0;
+
+// What the performance overlay shows, kept up to date by the MetricsSystem, and by the StepPacer
+// for the generator steps.
+#[derive(Default)]
+pub struct Metrics {
+    frame_seconds: VecDeque<f32>,
+    fps: f32,
+    entities: usize,
+    hexes: usize,
+    last_step: Option<Duration>,
+    // The entities are only counted while the overlay shows them.
+    visible: bool,
+}
+
+impl Metrics {
+    pub fn record_frame(&mut self, seconds: f32) {
+        if self.frame_seconds.len() == FRAME_SAMPLES {
+            self.frame_seconds.pop_front();
+        }
+        self.frame_seconds.push_back(seconds);
+    }
+
+    // The oldest first.
+    pub fn frame_seconds(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_seconds.iter().copied()
+    }
+
+    pub fn record_step(&mut self, duration: Duration) {
+        self.last_step = Some(duration);
+    }
+
+    pub fn last_step(&self) -> Option<Duration> {
+        self.last_step
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    pub fn entities(&self) -> usize {
+        self.entities
+    }
+
+    // The hexes with tagged entities on them.
+    pub fn hexes(&self) -> usize {
+        self.hexes
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    fn text(&self) -> String {
+        let last_frame = self.frame_seconds.back().copied().unwrap_or(0.0);
+        let worst_frame = self.frame_seconds().fold(0.0, f32::max);
+        format!(
+            "{:.0} FPS, frame {:.1}ms (worst {:.1}ms)\n{} entities, {} hexes\nlast step {}",
+            self.fps,
+            last_frame * 1000.0,
+            worst_frame * 1000.0,
+            self.entities,
+            self.hexes,
+            self.last_step.map_or_else(
+                || "-".to_string(),
+                |duration| format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+            ),
+        )
+    }
+}
+
+// Collects the frame times, the frame rate and the counts, with or without a window.
+#[derive(Default)]
+pub struct MetricsSystem;
+
+impl<'s> System<'s> for MetricsSystem {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, Time>,
+        Read<'s, FpsCounter>,
+        Read<'s, EntitySpatialMap>,
+        Write<'s, Metrics>,
+    );
+
+    fn run(&mut self, (entities, time, fps_counter, map, mut metrics): Self::SystemData) {
+        metrics.record_frame(time.delta_real_seconds());
+        metrics.fps = fps_counter.sampled_fps();
+        if metrics.visible {
+            metrics.entities = (&entities).join().count();
+            metrics.hexes = map.hexes();
+        }
+    }
+}
+
+// Shows the metrics in the top right corner of the window, the frame times as a bar graph under
+// them: green within 60 FPS, yellow within 30 FPS, red beyond. F3 shows and hides it.
+#[derive(SystemDesc)]
+#[system_desc(name(MetricsOverlaySystemDesc))]
+pub struct MetricsOverlaySystem {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<InputEvent<StringBindings>>,
+    #[system_desc(skip)]
+    panel: Option<Entity>,
+    #[system_desc(skip)]
+    bars: Vec<Entity>,
+    #[system_desc(skip)]
+    shown: bool,
+}
+
+impl MetricsOverlaySystem {
+    pub fn new(event_reader: ReaderId<InputEvent<StringBindings>>) -> Self {
+        Self {
+            event_reader,
+            panel: None,
+            bars: Vec::new(),
+            shown: false,
+        }
+    }
+}
+
+fn bar_color(seconds: f32) -> [f32; 4] {
+    if seconds <= 1.0 / 60.0 {
+        [0.2, 0.8, 0.2, 0.8]
+    } else if seconds <= 1.0 / 30.0 {
+        [0.9, 0.8, 0.2, 0.8]
+    } else {
+        [0.9, 0.2, 0.2, 0.8]
+    }
+}
+
+impl<'s> System<'s> for MetricsOverlaySystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Write<'s, Metrics>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<FontAsset>>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiImage>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            events,
+            mut metrics,
+            loader,
+            fonts,
+            mut transforms,
+            mut texts,
+            mut images,
+        ): Self::SystemData,
+    ) {
+        for event in events.read(&mut self.event_reader) {
+            if let InputEvent::KeyPressed {
+                key_code: VirtualKeyCode::F3,
+                ..
+            } = event
+            {
+                metrics.toggle();
+            }
+        }
+        if !metrics.is_visible() && !self.shown {
+            return;
+        }
+        let text_height = TEXT_LINES as f32 * FONT_SIZE * 1.25;
+        let panel = *self.panel.get_or_insert_with(|| {
+            entities
+                .build_entity()
+                .with(
+                    UiTransform::new(
+                        "metrics".to_string(),
+                        Anchor::TopRight,
+                        Anchor::TopRight,
+                        -MARGIN,
+                        -MARGIN,
+                        1.0,
+                        FRAME_SAMPLES as f32 * BAR_WIDTH,
+                        text_height,
+                    ),
+                    &mut transforms,
+                )
+                .with(
+                    UiText::new(
+                        get_default_font(&loader, &fonts),
+                        String::new(),
+                        [0.9, 0.9, 0.8, 1.0],
+                        FONT_SIZE,
+                        LineMode::Wrap,
+                        Anchor::TopLeft,
+                    ),
+                    &mut texts,
+                )
+                .build()
+        });
+        if self.bars.is_empty() {
+            // The bars stand on the bottom of the graph, the newest frame on the right.
+            for i in 0..FRAME_SAMPLES {
+                let bar = entities
+                    .build_entity()
+                    .with(
+                        UiTransform::new(
+                            format!("metrics_frame_{}", i),
+                            Anchor::TopRight,
+                            Anchor::BottomLeft,
+                            -MARGIN - (FRAME_SAMPLES - i) as f32 * BAR_WIDTH,
+                            -MARGIN - text_height - GRAPH_HEIGHT,
+                            1.0,
+                            BAR_WIDTH,
+                            0.0,
+                        ),
+                        &mut transforms,
+                    )
+                    .with(UiImage::SolidColor(bar_color(0.0)), &mut images)
+                    .build();
+                self.bars.push(bar);
+            }
+        }
+
+        // Cleared once when hidden.
+        self.shown = metrics.is_visible();
+        if let Some(text) = texts.get_mut(panel) {
+            text.text = if self.shown {
+                metrics.text()
+            } else {
+                String::new()
+            };
+        }
+        let frames = metrics.frame_seconds().collect::<Vec<_>>();
+        let skipped = FRAME_SAMPLES - frames.len();
+        for (i, bar) in self.bars.iter().enumerate() {
+            let seconds = if self.shown && i >= skipped {
+                frames[i - skipped]
+            } else {
+                0.0
+            };
+            if let Some(transform) = transforms.get_mut(*bar) {
+                transform.height = (seconds / GRAPH_SECONDS).min(1.0) * GRAPH_HEIGHT;
+            }
+            if let Some(image) = images.get_mut(*bar) {
+                *image = UiImage::SolidColor(bar_color(seconds));
+            }
+        }
+    }
+}
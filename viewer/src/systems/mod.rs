@@ -1,2 +1,14 @@
+pub mod camera_aspect;
 pub mod camera_distance;
+pub mod culling;
+pub mod event_log;
+pub mod file_watch;
 pub mod follow_me;
+pub mod grid_overlay;
+pub mod highlight;
+pub mod hot_reload;
+pub mod lighting;
+pub mod metrics;
+pub mod path_preview;
+pub mod spatial;
+pub mod tween;
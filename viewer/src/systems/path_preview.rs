@@ -0,0 +1,117 @@
+use crate::world::{axial_translation, RhombusViewerWorld};
+use amethyst::{
+    core::math::{Point3, Vector3},
+    ecs::prelude::*,
+    renderer::{debug_drawing::DebugLinesComponent, palette::Srgba},
+};
+use rhombus_core::hex::coordinates::axial::AxialVector;
+use std::sync::Arc;
+
+// The path drawn above the tiles, from its first hex to its last one. Any demo can set it, an
+// empty path hides the preview.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct PathPreview {
+    pub path: Vec<AxialVector>,
+    pub height: f32,
+}
+
+// Every that many steps, and at the end, the distance from the start is written above the path.
+const LABEL_STEPS: usize = 5;
+const ARROW_LENGTH: f32 = 0.25;
+const DIGIT_WIDTH: f32 = 0.2;
+const DIGIT_HEIGHT: f32 = 0.35;
+const LABEL_HEIGHT: f32 = 0.3;
+
+// Segments a to g of the seven-segment digits.
+const DIGIT_SEGMENTS: [u8; 10] = [
+    0b011_1111, 0b000_0110, 0b101_1011, 0b100_1111, 0b110_0110, 0b110_1101, 0b111_1101, 0b000_0111,
+    0b111_1111, 0b110_1111,
+];
+
+#[derive(Default)]
+pub struct PathPreviewSystem {
+    entity: Option<Entity>,
+    drawn: Option<PathPreview>,
+}
+
+impl<'s> System<'s> for PathPreviewSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, DebugLinesComponent>,
+        Read<'s, PathPreview>,
+        Option<Read<'s, Arc<RhombusViewerWorld>>>,
+    );
+
+    fn run(&mut self, (entities, mut debug_lines_storage, preview, world): Self::SystemData) {
+        let world = if let Some(world) = world {
+            world
+        } else {
+            return;
+        };
+        if self.drawn.as_ref() == Some(&*preview) {
+            return;
+        }
+        let entity = *self.entity.get_or_insert_with(|| entities.create());
+        let mut debug_lines = DebugLinesComponent::with_capacity(preview.path.len() * 20);
+        let point = |position: AxialVector, height: f32| {
+            Point3::from(axial_translation(&world.layout, (position, height).into()))
+        };
+        let color = Srgba::new(0.2, 1.0, 0.8, 1.0);
+        for (from, to) in preview.path.iter().zip(preview.path.iter().skip(1)) {
+            let (from, to) = (point(*from, preview.height), point(*to, preview.height));
+            debug_lines.add_line(from, to, color);
+            // Arrowhead at the end of the step.
+            let direction = (to - from).normalize() * ARROW_LENGTH * world.layout.size();
+            let side = direction.cross(&Vector3::y()) * 0.5;
+            debug_lines.add_line(to, to - direction + side, color);
+            debug_lines.add_line(to, to - direction - side, color);
+        }
+        let label_color = Srgba::new(1.0, 1.0, 1.0, 1.0);
+        for (distance, position) in preview.path.iter().enumerate().skip(1) {
+            if distance % LABEL_STEPS == 0 || distance + 1 == preview.path.len() {
+                let origin = point(*position, preview.height + LABEL_HEIGHT);
+                add_number(&mut debug_lines, origin, distance, label_color);
+            }
+        }
+        debug_lines_storage
+            .insert(entity, debug_lines)
+            .expect("insert debug lines");
+        self.drawn = Some(preview.clone());
+    }
+}
+
+// Centered on the origin, readable from the default camera which looks towards +x (the digits go
+// along +z and their tops towards +x).
+fn add_number(
+    debug_lines: &mut DebugLinesComponent,
+    origin: Point3<f32>,
+    number: usize,
+    color: Srgba,
+) {
+    let digits = number.to_string();
+    let advance = DIGIT_WIDTH * 1.5;
+    let width = advance * digits.len() as f32 - DIGIT_WIDTH * 0.5;
+    for (index, digit) in digits.bytes().enumerate() {
+        let left = -width / 2.0 + advance * index as f32;
+        // (right, up) coordinates of the ends of the segments a to g.
+        let (w, h) = (DIGIT_WIDTH, DIGIT_HEIGHT);
+        let segments = [
+            ((0.0, h), (w, h)),
+            ((w, h), (w, h / 2.0)),
+            ((w, h / 2.0), (w, 0.0)),
+            ((0.0, 0.0), (w, 0.0)),
+            ((0.0, 0.0), (0.0, h / 2.0)),
+            ((0.0, h / 2.0), (0.0, h)),
+            ((0.0, h / 2.0), (w, h / 2.0)),
+        ];
+        let to_point = |(right, up): (f32, f32)| {
+            Point3::new(origin.x + up - h / 2.0, origin.y, origin.z + left + right)
+        };
+        let mask = DIGIT_SEGMENTS[(digit - b'0') as usize];
+        for (segment, (from, to)) in segments.iter().enumerate() {
+            if mask & (1 << segment) != 0 {
+                debug_lines.add_line(to_point(*from), to_point(*to), color);
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::hex::inspect::HexTag;
+use amethyst::{
+    derive::SystemDesc,
+    ecs::{prelude::*, storage::ComponentEvent, world::Index},
+};
+use rhombus_core::hex::{coordinates::axial::AxialVector, storage::hash::RectHashStorage};
+use smallvec::SmallVec;
+use std::collections::HashMap;
+
+// The entities tagged with each hex, to ask what stands on a hex or around it.
+#[derive(Default)]
+pub struct EntitySpatialMap {
+    entities: RectHashStorage<SmallVec<[Entity; 4]>>,
+    // Where each entity was put, to take it out when its tag moves or goes away.
+    positions: HashMap<Index, (Entity, AxialVector)>,
+}
+
+impl EntitySpatialMap {
+    pub fn entities_at(&self, position: AxialVector) -> &[Entity] {
+        self.entities
+            .get(position)
+            .map(|entities| entities.as_slice())
+            .unwrap_or(&[])
+    }
+
+    // The hexes with at least one entity.
+    pub fn hexes(&self) -> usize {
+        self.entities.len()
+    }
+
+    // The entities up to `range` hexes away from the center, with their hex.
+    pub fn entities_in_range<'a>(
+        &'a self,
+        center: AxialVector,
+        range: usize,
+    ) -> impl 'a + Iterator<Item = (AxialVector, Entity)> {
+        center.spiral_iter(range).flat_map(move |position| {
+            self.entities_at(position)
+                .iter()
+                .map(move |entity| (position, *entity))
+        })
+    }
+
+    fn insert(&mut self, entity: Entity, position: AxialVector) {
+        self.remove(entity.id());
+        self.entities
+            .entry(position)
+            .or_insert_with(SmallVec::new)
+            .push(entity);
+        self.positions.insert(entity.id(), (entity, position));
+    }
+
+    fn remove(&mut self, id: Index) {
+        if let Some((entity, position)) = self.positions.remove(&id) {
+            let entities = self.entities.get_mut(position).expect("mapped entity");
+            entities.retain(|mapped| *mapped != entity);
+            if entities.is_empty() {
+                self.entities.remove(position);
+            }
+        }
+    }
+}
+
+// Keeps the EntitySpatialMap in sync with the hex tags as they are inserted, modified and removed.
+#[derive(SystemDesc)]
+#[system_desc(name(EntitySpatialMapSystemDesc))]
+pub struct EntitySpatialMapSystem {
+    #[system_desc(flagged_storage_reader(HexTag))]
+    tag_reader: ReaderId<ComponentEvent>,
+}
+
+impl EntitySpatialMapSystem {
+    pub fn new(tag_reader: ReaderId<ComponentEvent>) -> Self {
+        Self { tag_reader }
+    }
+}
+
+impl<'s> System<'s> for EntitySpatialMapSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, HexTag>,
+        Write<'s, EntitySpatialMap>,
+    );
+
+    fn run(&mut self, (entities, tags, mut map): Self::SystemData) {
+        let mut changed = Vec::new();
+        for event in tags.channel().read(&mut self.tag_reader) {
+            match event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) => changed.push(*id),
+                ComponentEvent::Removed(id) => map.remove(*id),
+            }
+        }
+        // The tags as they are now, whatever happened to them in between.
+        for id in changed {
+            let entity = entities.entity(id);
+            match tags.get(entity) {
+                Some(tag) => map.insert(entity, tag.position),
+                None => map.remove(id),
+            }
+        }
+    }
+}
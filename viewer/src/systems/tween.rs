@@ -0,0 +1,59 @@
+use amethyst::{
+    core::{timing::Time, Transform},
+    derive::SystemDesc,
+    ecs::prelude::*,
+};
+
+// Eases the entity from one height to another, then the tag goes away. The transform always holds
+// the interpolated height, so that the followers of the entity move smoothly too.
+pub struct HeightTweenTag {
+    pub from: f32,
+    pub to: f32,
+    pub start: f32,
+    pub duration: f32,
+}
+
+impl Component for HeightTweenTag {
+    type Storage = HashMapStorage<HeightTweenTag>;
+}
+
+impl HeightTweenTag {
+    fn progress(&self, seconds: f32) -> f32 {
+        ((seconds - self.start) / self.duration).max(0.0).min(1.0)
+    }
+
+    pub fn height(&self, seconds: f32) -> f32 {
+        let progress = self.progress(seconds);
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        self.from + (self.to - self.from) * eased
+    }
+}
+
+// Runs before the follow systems, which would otherwise lag one frame behind.
+#[derive(SystemDesc)]
+pub struct TweenSystem;
+
+impl<'s> System<'s> for TweenSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, HeightTweenTag>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (entities, mut transforms, mut height_tween_tags, time): Self::SystemData) {
+        let seconds = time.absolute_time_seconds() as f32;
+        let mut done = Vec::new();
+        for (entity, transform, height_tween_tag) in
+            (&entities, &mut transforms, &height_tween_tags).join()
+        {
+            transform.translation_mut()[1] = height_tween_tag.height(seconds);
+            if height_tween_tag.progress(seconds) >= 1.0 {
+                done.push(entity);
+            }
+        }
+        for entity in done {
+            height_tween_tags.remove(entity);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::mesh::{wall_vertices, HexPrism, MeshVertices};
+use amethyst::{
+    config::Config,
+    renderer::{
+        palette::Srgba,
+        rendy::{
+            hal::image::{Kind, ViewKind},
+            texture::{pixel::Rgba8Srgb, TextureBuilder},
+        },
+    },
+};
+use log::warn;
+use rhombus_core::hex::analysis::autotile::{autotile_mask, AUTOTILE_COUNT};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+pub enum TerrainType {
+    Ground,
+    Wall,
+}
+
+impl TerrainType {
+    pub const ALL: [TerrainType; 2] = [TerrainType::Ground, TerrainType::Wall];
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum TilePattern {
+    Plain,
+    Checker,
+    Bricks,
+    Noise,
+}
+
+impl TilePattern {
+    fn shade(self, x: u32, y: u32, tile_size: u32) -> f32 {
+        let cell = (tile_size / 4).max(1);
+        match self {
+            TilePattern::Plain => 1.0,
+            TilePattern::Checker => {
+                if (x / cell + y / cell) % 2 == 0 {
+                    1.0
+                } else {
+                    0.8
+                }
+            }
+            TilePattern::Bricks => {
+                // Bricks twice as long as high, every other row shifted by half a brick.
+                let x = x + y / cell % 2 * cell;
+                if y % cell == 0 || x % (2 * cell) == 0 {
+                    0.6
+                } else {
+                    1.0
+                }
+            }
+            TilePattern::Noise => {
+                // Integer hash so that the noise is the same on every run.
+                let hash = x.wrapping_mul(374_761_393) ^ y.wrapping_mul(668_265_263);
+                let hash = (hash ^ (hash >> 13)).wrapping_mul(1_274_126_177);
+                0.8 + 0.2 * (hash >> 24) as f32 / 255.0
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasTile {
+    pub color: (f32, f32, f32),
+    pub pattern: TilePattern,
+}
+
+// The shape of a wall hex, given its wall neighbors.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum WallMesh {
+    Straight,
+    // All the top edges beveled.
+    Bevel(f32),
+    // Only the top edges away from the neighbor walls beveled.
+    Joined(f32),
+}
+
+impl WallMesh {
+    fn vertices(self, walls: u8) -> MeshVertices {
+        match self {
+            WallMesh::Straight => HexPrism::default().vertices(),
+            WallMesh::Bevel(bevel) => HexPrism {
+                bevel,
+                ..Default::default()
+            }
+            .vertices(),
+            WallMesh::Joined(bevel) => wall_vertices(bevel, walls),
+        }
+    }
+}
+
+// A point light hanging above a hex.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HexLight {
+    pub color: (f32, f32, f32),
+    pub intensity: f32,
+    pub radius: f32,
+    pub height: f32,
+    // Like a torch.
+    #[serde(default)]
+    pub flicker: bool,
+}
+
+// The directional lights, as intensities and vertical directions, light the whole world. The point
+// lights are placed from the map data: in the middle of the rooms and on the doors between the
+// regions of the dungeons.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeLights {
+    pub directional: Vec<(f32, f32)>,
+    pub room: HexLight,
+    pub door: HexLight,
+}
+
+impl Default for ThemeLights {
+    fn default() -> Self {
+        Self {
+            directional: vec![(0.3, -1.0), (0.15, 1.0)],
+            room: HexLight {
+                color: (1.0, 0.9, 0.7),
+                intensity: 4.0,
+                radius: 8.0,
+                height: 3.0,
+                flicker: false,
+            },
+            door: HexLight {
+                color: (1.0, 0.6, 0.2),
+                intensity: 1.5,
+                radius: 3.0,
+                height: 2.0,
+                flicker: true,
+            },
+        }
+    }
+}
+
+// The texture atlas of the tiles, the atlas tile of every terrain type, the wall mesh of every
+// autotile variant and the lights.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Theme {
+    pub tile_size: u32,
+    pub tiles: Vec<AtlasTile>,
+    pub terrains: HashMap<TerrainType, usize>,
+    #[serde(default = "default_walls")]
+    pub walls: Vec<WallMesh>,
+    #[serde(default)]
+    pub lights: ThemeLights,
+}
+
+// Pillars fully beveled, surrounded walls flat, the others joined.
+fn default_walls() -> Vec<WallMesh> {
+    (0..AUTOTILE_COUNT)
+        .map(|index| match index {
+            0 => WallMesh::Bevel(0.2),
+            index if index == AUTOTILE_COUNT - 1 => WallMesh::Straight,
+            _ => WallMesh::Joined(0.1),
+        })
+        .collect()
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            tile_size: 32,
+            tiles: vec![
+                AtlasTile {
+                    color: (0.9, 0.9, 0.9),
+                    pattern: TilePattern::Noise,
+                },
+                AtlasTile {
+                    color: (0.8, 0.1, 0.1),
+                    pattern: TilePattern::Bricks,
+                },
+            ],
+            terrains: [(TerrainType::Ground, 0), (TerrainType::Wall, 1)]
+                .iter()
+                .copied()
+                .collect(),
+            walls: default_walls(),
+            lights: ThemeLights::default(),
+        }
+    }
+}
+
+impl Theme {
+    fn validate(&self) -> Result<(), String> {
+        if self.tile_size == 0 {
+            return Err("The tile size must not be zero".to_string());
+        }
+        for terrain in &TerrainType::ALL {
+            match self.terrains.get(terrain) {
+                Some(tile) if *tile < self.tiles.len() => {}
+                Some(tile) => return Err(format!("No tile {} for {:?}", tile, terrain)),
+                None => return Err(format!("No tile for {:?}", terrain)),
+            }
+        }
+        if self.walls.len() != AUTOTILE_COUNT {
+            return Err(format!(
+                "{} wall meshes instead of {}",
+                self.walls.len(),
+                AUTOTILE_COUNT
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn tile(&self, terrain: TerrainType) -> usize {
+        self.terrains[&terrain]
+    }
+
+    // The meshes of the wall hexes, by autotile index, textured with the wall tile. They are
+    // modeled for the tile not turned.
+    pub fn wall_meshes(&self) -> Vec<MeshVertices> {
+        self.walls
+            .iter()
+            .enumerate()
+            .map(|(index, wall)| {
+                wall.vertices(autotile_mask(index))
+                    .with_atlas_tile(self.tile(TerrainType::Wall), self.tiles.len())
+            })
+            .collect()
+    }
+
+    // The tiles side by side, their colors scaled by the brightness.
+    pub fn atlas_texture(&self, brightness: f32) -> TextureBuilder<'static> {
+        let tile_size = self.tile_size;
+        let width = tile_size * self.tiles.len() as u32;
+        let mut pixels = Vec::with_capacity((width * tile_size) as usize);
+        for y in 0..tile_size {
+            for x in 0..width {
+                let tile = &self.tiles[(x / tile_size) as usize];
+                let shade = brightness * tile.pattern.shade(x % tile_size, y, tile_size);
+                let (r, g, b) = tile.color;
+                pixels.push(Rgba8Srgb::from(Srgba::new(
+                    r * shade,
+                    g * shade,
+                    b * shade,
+                    1.0,
+                )));
+            }
+        }
+        TextureBuilder::new()
+            .with_kind(Kind::D2(width, tile_size, 1, 1))
+            .with_view_kind(ViewKind::D2)
+            .with_data_width(width)
+            .with_data_height(tile_size)
+            .with_data(pixels)
+    }
+}
+
+pub struct ThemeSettings {
+    pub path: PathBuf,
+}
+
+// A missing file silently falls back to the default theme, a broken one is worth a warning.
+pub fn load_theme(path: &Path) -> Theme {
+    if !path.exists() {
+        return Theme::default();
+    }
+    match Theme::load(path)
+        .map_err(|err| err.to_string())
+        .and_then(|theme| {
+            theme.validate()?;
+            Ok(theme)
+        }) {
+        Ok(theme) => theme,
+        Err(err) => {
+            warn!(
+                "Failed to read theme file: `{}` ({}), using the default theme.",
+                path.display(),
+                err
+            );
+            Theme::default()
+        }
+    }
+}
@@ -1,7 +1,24 @@
-use crate::{assets::RhombusViewerAssets, systems::follow_me::FollowMeTag};
-use amethyst::{controls::ArcBallControlTag, core::Transform, ecs::prelude::*, prelude::*};
+use crate::{
+    assets::RhombusViewerAssets,
+    systems::follow_me::{FollowMeSettings, FollowMeTag},
+};
+use amethyst::{
+    controls::ArcBallControlTag,
+    core::{math::Vector3, Transform},
+    ecs::prelude::*,
+    prelude::*,
+    renderer::camera::Camera,
+};
 use rhombus_core::{
-    dodec::coordinates::quadric::QuadricVector, hex::coordinates::axial::AxialVector,
+    dodec::coordinates::quadric::QuadricVector,
+    hex::{
+        coordinates::{
+            axial::AxialVector,
+            direction::{HexagonalDirection, NUM_DIRECTIONS},
+            offset::HexOrientation,
+        },
+        float::layout::HexLayout,
+    },
 };
 use std::{
     ops::DerefMut,
@@ -15,6 +32,7 @@ pub struct RhombusViewerWorld {
     pub origin_camera: Entity,
     pub follower: Entity,
     pub follower_camera: Entity,
+    pub layout: HexLayout,
 
     #[new(value = "Arc::new(Mutex::new(None))")]
     follow_mode: Arc<Mutex<Option<(bool, FollowSettings)>>>,
@@ -27,20 +45,29 @@ struct FollowSettings {
 }
 
 impl RhombusViewerWorld {
+    // Same entities and follow mode, other assets.
+    pub fn with_assets(&self, assets: RhombusViewerAssets) -> Self {
+        Self {
+            assets,
+            origin: self.origin,
+            origin_camera: self.origin_camera,
+            follower: self.follower,
+            follower_camera: self.follower_camera,
+            layout: self.layout,
+            follow_mode: self.follow_mode.clone(),
+        }
+    }
+
     pub fn axial_translation(&self, position: AxialPosition) -> [f32; 3] {
-        let col = position.pos().q() + (position.pos().r() - (position.pos().r() & 1)) / 2;
-        let row = position.pos().r();
-        let altitude = position.alt();
-        [
-            f32::sqrt(3.0) * ((col as f32) + (row & 1) as f32 / 2.0),
-            altitude,
-            -row as f32 * 1.5,
-        ]
+        axial_translation(&self.layout, position)
     }
 
     pub fn transform_axial(&self, position: AxialPosition, transform: &mut Transform) {
-        let translation = self.axial_translation(position);
-        transform.set_translation_xyz(translation[0], translation[1], translation[2]);
+        transform_axial(&self.layout, position, transform);
+    }
+
+    pub fn transform_hex(&self, position: AxialPosition, transform: &mut Transform) {
+        transform_hex(&self.layout, position, transform);
     }
 
     pub fn transform_quadric(&self, position: QuadricPosition, transform: &mut Transform) {
@@ -116,12 +143,14 @@ impl RhombusViewerWorld {
         data: &StateData<'_, GameData<'_, '_>>,
         mode: &mut (bool, FollowSettings),
     ) {
+        let settings = data.world.read_resource::<FollowMeSettings>();
         let mut follow_me_storage = data.world.write_storage::<FollowMeTag>();
         if let Some(tag) = follow_me_storage.get_mut(self.follower) {
-            tag.target = Some((mode.1.target, 0.1));
-            tag.rotation_target = mode.1.rotation_target.map(|t| (t, 0.1));
+            tag.target = Some((mode.1.target, settings.translation));
+            tag.rotation_target = mode.1.rotation_target.map(|t| (t, settings.rotation));
         }
-        if mode.1.rotation_target.is_some() {
+        // Without snapping, the camera turns towards its viewing angle instead of jumping to it.
+        if mode.1.rotation_target.is_some() && settings.snap {
             let mut transform_storage = data.world.write_storage::<Transform>();
             let rotation = transform_storage
                 .get(self.origin_camera)
@@ -134,7 +163,10 @@ impl RhombusViewerWorld {
             }
         }
         if let Some(tag) = follow_me_storage.get_mut(self.follower_camera) {
-            tag.rotation_target = mode.1.rotation_target.map(|_| (self.origin_camera, 0.01));
+            tag.rotation_target = mode
+                .1
+                .rotation_target
+                .map(|_| (self.origin_camera, settings.camera_rotation));
         }
     }
 
@@ -144,6 +176,76 @@ impl RhombusViewerWorld {
             tag.distance = distance;
         }
     }
+
+    // The hex direction the camera looks at, none when it looks straight down.
+    pub fn camera_hex_direction(&self, data: &StateData<'_, GameData<'_, '_>>) -> Option<usize> {
+        let camera_storage = data.world.read_storage::<Camera>();
+        let transform_storage = data.world.read_storage::<Transform>();
+        let (_, transform) = (&camera_storage, &transform_storage).join().next()?;
+        let forward = transform.rotation() * -Vector3::z();
+        closest_hex_direction(&self.layout, forward.x, forward.z)
+    }
+}
+
+// The layout plane is mapped to the ground: x to x and y to -z.
+pub fn fractional_axial_translation(layout: &HexLayout, q: f32, r: f32, altitude: f32) -> [f32; 3] {
+    let point = layout.to_fractional_point(q, r);
+    [point[0], altitude, -point[1]]
+}
+
+pub fn axial_translation(layout: &HexLayout, position: AxialPosition) -> [f32; 3] {
+    fractional_axial_translation(
+        layout,
+        position.pos().q() as f32,
+        position.pos().r() as f32,
+        position.alt(),
+    )
+}
+
+// The hex direction closest to a vector of the ground, none if the vector is too short to tell.
+pub fn closest_hex_direction(layout: &HexLayout, x: f32, z: f32) -> Option<usize> {
+    if x.hypot(z) < 1e-3 {
+        return None;
+    }
+    let origin = axial_translation(layout, (AxialVector::default(), 0.0).into());
+    let alignment = |direction: usize| {
+        let translation =
+            axial_translation(layout, (AxialVector::direction(direction), 0.0).into());
+        (translation[0] - origin[0]) * x + (translation[2] - origin[2]) * z
+    };
+    (0..NUM_DIRECTIONS).max_by(|a, b| alignment(*a).partial_cmp(&alignment(*b)).unwrap())
+}
+
+pub fn corner_translation(layout: &HexLayout, position: AxialPosition, corner: usize) -> [f32; 3] {
+    let translation = axial_translation(layout, position);
+    let offset = layout.corner_offset(corner);
+    [
+        translation[0] + offset[0],
+        translation[1],
+        translation[2] - offset[1],
+    ]
+}
+
+pub fn transform_axial(layout: &HexLayout, position: AxialPosition, transform: &mut Transform) {
+    let translation = axial_translation(layout, position);
+    transform.set_translation_xyz(translation[0], translation[1], translation[2]);
+}
+
+// Rotation around the vertical axis to apply to meshes modeled pointy-top.
+pub fn hex_rotation(layout: &HexLayout) -> f32 {
+    match layout.orientation() {
+        HexOrientation::PointyTop => 0.0,
+        HexOrientation::FlatTop => std::f32::consts::FRAC_PI_6,
+    }
+}
+
+// Positions a hex mesh, its horizontal scale must already be set as if the hex size was 1.
+pub fn transform_hex(layout: &HexLayout, position: AxialPosition, transform: &mut Transform) {
+    transform_axial(layout, position, transform);
+    transform.set_rotation_y_axis(hex_rotation(layout));
+    let scale = transform.scale_mut();
+    scale[0] *= layout.size();
+    scale[2] *= layout.size();
 }
 
 #[derive(Clone, Copy, From, Debug)]
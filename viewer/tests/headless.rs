@@ -0,0 +1,58 @@
+#![cfg(feature = "headless")]
+
+use std::process::Command;
+
+// Enough for the generators to take a few steps.
+const FRAMES: &str = "120";
+
+const DEMOS: [&str; 14] = [
+    "hex-directions",
+    "hex-ring",
+    "hex-snake",
+    "dodec-directions",
+    "dodec-sphere",
+    "dodec-snake",
+    "hex-cubic-range-shape",
+    "hex-traversal",
+    "hex-flat-builder",
+    "hex-bumpy-builder",
+    "hex-cellular-builder",
+    "hex-custom-builder",
+    "hex-editor",
+    "hex-ram-builder",
+];
+
+fn run_viewer(args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_rhombus_viewer"))
+        .args(&["--frames", FRAMES, "--log-level", "warn"])
+        .args(args)
+        // The configuration files and the assets are found from there.
+        .env("CARGO_MANIFEST_DIR", env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .expect("run the viewer");
+    assert!(status.success(), "viewer {:?} failed: {}", args, status);
+}
+
+#[test]
+fn test_demos_start_and_stop() {
+    for demo in DEMOS.iter() {
+        run_viewer(&[demo]);
+    }
+}
+
+#[test]
+fn test_carousel_starts_and_stops() {
+    run_viewer(&[]);
+}
+
+#[test]
+fn test_seeded_demos_start_and_stop() {
+    for demo in &[
+        "hex-snake",
+        "dodec-snake",
+        "hex-cellular-builder",
+        "hex-ram-builder",
+    ] {
+        run_viewer(&["--seed", "42", demo]);
+    }
+}